@@ -0,0 +1,478 @@
+//====================================================================
+
+use std::collections::VecDeque;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    characters::{
+        actions::{ActionId, ActionRepo, ActionResolution, TargetType},
+        Character,
+    },
+    combat::{self, BattleRng, CpuProfile, Difficulty, Resolved, Rolls, TurnStartTick},
+    lookahead::{self, Move, SimBattle},
+};
+
+//====================================================================
+
+/// Scripted events fired once, when a boss drops to `health_percent` of its
+/// max health or below.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BossPhase {
+    /// Index of the boss in the battle.
+    pub boss: usize,
+    pub health_percent: u32,
+    /// Replaces the boss's actions.
+    pub actions: Option<Vec<ActionId>>,
+    /// Characters joining the boss's side.
+    pub summon: Vec<Character>,
+    /// Lines shown while the battle pauses.
+    pub cutscene: Vec<String>,
+}
+
+/// Boss phase that was reached, once its actions and summons are in place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FiredPhase {
+    pub boss: usize,
+    pub health_percent: u32,
+    /// Indices of the characters that joined the battle.
+    pub summoned: Vec<usize>,
+    pub cutscene: Vec<String>,
+}
+
+/// How far the battle moved on in a call to [`Battle::advance`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// Everyone had their turn, so a new turn order was rolled.
+    RoundStarted { round: u32 },
+    /// The start of a character's turn was resolved. They act next unless
+    /// `ticks` defeated them.
+    TurnStarted {
+        character: usize,
+        ticks: Vec<TurnStartTick>,
+        phases: Vec<FiredPhase>,
+    },
+    /// One side has nobody left standing.
+    Ended { won: bool },
+}
+
+/// An action used by the character whose turn it was.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Acted {
+    pub caster: usize,
+    pub action: ActionId,
+    pub target: Option<usize>,
+    pub resolved: Resolved,
+    pub phases: Vec<FiredPhase>,
+}
+
+/// Why an action was refused. Sent back to relay clients whose commands are
+/// refused.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+pub enum ActionError {
+    #[error("It isn't character {0}'s turn")]
+    NotTheirTurn(usize),
+    #[error("No action with id {0:?}")]
+    UnknownAction(ActionId),
+    #[error("Character doesn't know that action")]
+    NotKnown,
+    #[error("Action is on cooldown for {0} more turns")]
+    OnCooldown(u32),
+    #[error("Action can't be used on that target")]
+    IllegalTarget,
+}
+
+//====================================================================
+
+/// The rules of a battle, from the first round to one side being defeated,
+/// without a world, scene or renderer. Scenes, simulations and the relay all
+/// play battles through this so they can't disagree.
+///
+/// Characters are identified by their index, friendly first in the order they
+/// were given. Summoned characters are added on the end.
+///
+/// Serializes everything including the state of its rolls, so a restored
+/// battle carries on exactly as the original would have.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Battle {
+    difficulty: Difficulty,
+    /// Every character in battle order, paired with whether they're friendly.
+    characters: Vec<(bool, Character)>,
+    /// Boss phases waiting for their boss's health to drop.
+    phases: Vec<BossPhase>,
+
+    round: u32,
+    /// Characters still to have their turn this round.
+    turn_order: VecDeque<usize>,
+    /// Character whose turn it is, until they act.
+    current: Option<usize>,
+    /// Every roll in the battle.
+    rng: BattleRng,
+}
+
+impl Battle {
+    pub fn new(
+        difficulty: Difficulty,
+        friendly: Vec<Character>,
+        enemy: Vec<Character>,
+        phases: Vec<BossPhase>,
+        seed: u64,
+    ) -> Self {
+        let characters = friendly
+            .into_iter()
+            .map(|character| (true, character))
+            .chain(enemy.into_iter().map(|character| (false, character)))
+            .collect();
+
+        Self {
+            difficulty,
+            characters,
+            phases,
+            round: 0,
+            turn_order: VecDeque::new(),
+            current: None,
+            rng: BattleRng::from_seed(seed),
+        }
+    }
+
+    #[inline]
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    #[inline]
+    pub fn round(&self) -> u32 {
+        self.round
+    }
+
+    /// Character whose turn it is, until they act.
+    #[inline]
+    pub fn current(&self) -> Option<usize> {
+        self.current
+    }
+
+    /// Characters still to have their turn this round, in order.
+    #[inline]
+    pub fn turn_order(&self) -> impl Iterator<Item = usize> + '_ {
+        self.turn_order.iter().copied()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.characters.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.characters.is_empty()
+    }
+
+    #[inline]
+    pub fn is_friendly(&self, index: usize) -> Option<bool> {
+        self.characters.get(index).map(|(friendly, _)| *friendly)
+    }
+
+    #[inline]
+    pub fn character(&self, index: usize) -> Option<&Character> {
+        self.characters.get(index).map(|(_, character)| character)
+    }
+
+    /// For changes made outside the rules, like reloaded data or equipment.
+    #[inline]
+    pub fn character_mut(&mut self, index: usize) -> Option<&mut Character> {
+        self.characters
+            .get_mut(index)
+            .map(|(_, character)| character)
+    }
+
+    /// Every character in battle order, paired with whether they're friendly.
+    #[inline]
+    pub fn characters(&self) -> impl Iterator<Item = (bool, &Character)> {
+        self.characters
+            .iter()
+            .map(|(friendly, character)| (*friendly, character))
+    }
+
+    /// Speed of every character still in the fight, in battle order.
+    pub fn speeds(&self) -> Vec<(u32, usize)> {
+        self.characters
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, character))| !character.stats.is_defeated())
+            .map(|(index, (_, character))| (character.speed(), index))
+            .collect()
+    }
+
+    /// Indices of the characters `caster` could use an action on, following
+    /// [`combat::legal_targets`].
+    pub fn targets(&self, caster: usize, target: TargetType) -> Vec<usize> {
+        combat::legal_targets(&self.characters().collect::<Vec<_>>(), caster, target)
+    }
+
+    /// Whether the friendly side won, once either side has nobody left
+    /// standing. Losing the last character on both sides at once is a win.
+    pub fn winner(&self) -> Option<bool> {
+        let defeated = |side: bool| {
+            self.characters
+                .iter()
+                .filter(|(friendly, _)| *friendly == side)
+                .all(|(_, character)| character.stats.is_defeated())
+        };
+
+        match (defeated(false), defeated(true)) {
+            (true, _) => Some(true),
+            (false, true) => Some(false),
+            (false, false) => None,
+        }
+    }
+
+    /// Move on to the next turn, starting a new round when everyone still
+    /// standing has had theirs. Whoever's turn it was loses it if they
+    /// haven't acted.
+    pub fn advance(&mut self) -> Step {
+        self.current = None;
+
+        if let Some(won) = self.winner() {
+            self.turn_order.clear();
+            return Step::Ended { won };
+        }
+
+        let characters = &self.characters;
+        let next = std::iter::from_fn(|| self.turn_order.pop_front())
+            .find(|index| !characters[*index].1.stats.is_defeated());
+
+        let character = match next {
+            Some(character) => character,
+            None => {
+                self.round += 1;
+                self.turn_order = combat::roll_turn_order(&self.speeds(), &mut self.rng).into();
+
+                return Step::RoundStarted { round: self.round };
+            }
+        };
+
+        let ticks = combat::resolve_turn_start(&mut self.characters[character].1);
+
+        // Characters defeated by their effects lose their turn
+        if !ticks.contains(&TurnStartTick::Defeated) {
+            self.current = Some(character);
+        }
+
+        Step::TurnStarted {
+            character,
+            ticks,
+            phases: self.fire_phases(),
+        }
+    }
+
+    /// Advance until a character can act, for players that don't show what
+    /// happens in between. Returns whether the friendly side won if the
+    /// battle ends first.
+    pub fn advance_to_turn(&mut self) -> Option<bool> {
+        loop {
+            match self.advance() {
+                Step::RoundStarted { .. } => {}
+                Step::TurnStarted { .. } => {
+                    if self.current.is_some() {
+                        return None;
+                    }
+                }
+                Step::Ended { won } => return Some(won),
+            }
+        }
+    }
+
+    /// Check the character whose turn it is could use `action` on `target`.
+    pub fn check_action(
+        &self,
+        actions: &ActionRepo,
+        caster: usize,
+        action: ActionId,
+        target: Option<usize>,
+    ) -> Result<(), ActionError> {
+        let character = match (self.current, self.character(caster)) {
+            (Some(current), Some(character)) if current == caster => character,
+            _ => return Err(ActionError::NotTheirTurn(caster)),
+        };
+
+        let data = actions
+            .get_action(&action)
+            .ok_or(ActionError::UnknownAction(action))?;
+
+        if !character.actions.contains(&action) {
+            return Err(ActionError::NotKnown);
+        }
+
+        match character.cooldown(action) {
+            0 => {}
+            turns => return Err(ActionError::OnCooldown(turns)),
+        }
+
+        let legal = match (data.target, target) {
+            (TargetType::None, target) => target.is_none(),
+            (_, None) => false,
+            (target_type, Some(target)) => self.targets(caster, target_type).contains(&target),
+        };
+
+        match legal {
+            true => Ok(()),
+            false => Err(ActionError::IllegalTarget),
+        }
+    }
+
+    /// Use an action for the character whose turn it is, ending their turn.
+    /// Nothing changes if the action can't be used.
+    pub fn act(
+        &mut self,
+        actions: &ActionRepo,
+        caster: usize,
+        action: ActionId,
+        target: Option<usize>,
+    ) -> Result<Acted, ActionError> {
+        self.check_action(actions, caster, action, target)?;
+        self.current = None;
+
+        // Checked above
+        let data = actions.get_action(&action).unwrap();
+
+        let character = &mut self.characters[caster].1;
+        combat::start_cooldown(character, action, data);
+        let (player_controlled, caster_stats) =
+            (character.player_controlled, character.stats.clone());
+
+        let resolved = match target {
+            Some(target) => combat::resolve_action(
+                data,
+                &caster_stats,
+                player_controlled,
+                &mut self.characters[target].1,
+                self.difficulty,
+                Rolls::Random(&mut self.rng),
+            ),
+            None => Resolved::default(),
+        };
+
+        Ok(Acted {
+            caster,
+            action,
+            target,
+            resolved,
+            phases: self.fire_phases(),
+        })
+    }
+
+    /// Pick an action for the character whose turn it is as the cpu would.
+    /// Looks ahead over the next turns when `profile` does, otherwise picks by
+    /// aggressiveness, healing whoever is most hurt and attacking anyone.
+    pub fn choose_cpu_action(&mut self, actions: &ActionRepo, profile: CpuProfile) -> Option<Move> {
+        let caster = self.current?;
+
+        if profile.lookahead > 0 {
+            // The rest of this round then the likely order of the next
+            let order = self
+                .turn_order
+                .iter()
+                .copied()
+                .chain(combat::predict_turn_order(&self.speeds()))
+                .take(profile.lookahead as usize)
+                .collect::<Vec<_>>();
+
+            let battle = SimBattle::new(self.difficulty, self.characters.clone());
+            let chosen = lookahead::choose_move(
+                &battle,
+                actions,
+                caster,
+                &order,
+                lookahead::LOOKAHEAD_BUDGET,
+            );
+
+            if chosen.is_some() {
+                log::debug!("Looked {} turns ahead to pick {:?}", order.len(), chosen);
+                return chosen;
+            }
+        }
+
+        let character = &self.characters[caster].1;
+
+        let choices = character
+            .actions
+            .iter()
+            .filter(|action| character.cooldown(**action) == 0)
+            .filter_map(|action| {
+                actions
+                    .get_action(action)
+                    .map(|data| (*action, &data.resolution))
+            })
+            .collect::<Vec<_>>();
+
+        let action = combat::choose_cpu_action(&choices, profile.aggressiveness, &mut self.rng)?;
+
+        let data = actions.get_action(&action)?;
+        let targets = self.targets(caster, data.target);
+
+        // Heal whoever is most hurt, otherwise pick anyone
+        let target = match data.resolution {
+            ActionResolution::Heal(_) => targets.iter().copied().min_by_key(|target| {
+                let stats = &self.characters[*target].1.stats;
+                stats.health as u64 * 100 / stats.max_health.max(1) as u64
+            }),
+            _ => match targets.is_empty() {
+                true => None,
+                false => Some(targets[self.rng.gen_range(0..targets.len())]),
+            },
+        };
+
+        match (data.target, target) {
+            (TargetType::None, _) => Some((action, None)),
+            (_, None) => None,
+            (_, target) => Some((action, target)),
+        }
+    }
+
+    // Phases past their threshold, in the order they were given, with their
+    // actions and summons put in place. Defeated bosses don't trigger
+    // anything.
+    fn fire_phases(&mut self) -> Vec<FiredPhase> {
+        let characters = &self.characters;
+        let (fired, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.phases)
+            .into_iter()
+            .partition(|phase| {
+                characters.get(phase.boss).is_some_and(|(_, boss)| {
+                    !boss.stats.is_defeated() && boss.stats.below_percent(phase.health_percent)
+                })
+            });
+        self.phases = pending;
+
+        fired
+            .into_iter()
+            .map(|phase| {
+                let (side, boss) = &mut self.characters[phase.boss];
+                let side = *side;
+
+                if let Some(actions) = phase.actions {
+                    boss.actions = actions;
+                }
+
+                // Summons act from the next round
+                let summoned = phase
+                    .summon
+                    .into_iter()
+                    .map(|character| {
+                        self.characters.push((side, character));
+                        self.characters.len() - 1
+                    })
+                    .collect();
+
+                FiredPhase {
+                    boss: phase.boss,
+                    health_percent: phase.health_percent,
+                    summoned,
+                    cutscene: phase.cutscene,
+                }
+            })
+            .collect()
+    }
+}
+
+//====================================================================
@@ -0,0 +1,142 @@
+//====================================================================
+
+use std::{collections::HashMap, hash::Hasher};
+
+use serde::{Deserialize, Serialize};
+
+use super::StatusEffect;
+
+//====================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ActionId(u32);
+
+pub struct ActionRepo {
+    action_id: ActionId,
+    actions: HashMap<ActionId, Action>,
+}
+
+impl ActionRepo {
+    /// Ids are given in order.
+    pub fn from_actions(actions: Vec<Action>) -> Self {
+        let mut repo = Self {
+            action_id: ActionId(0),
+            actions: HashMap::default(),
+        };
+
+        actions
+            .into_iter()
+            .for_each(|action| repo.add_action(action));
+
+        repo
+    }
+
+    /// Replace actions in place, keeping their ids. Actions past the end of
+    /// the repo are ignored.
+    pub fn update(&mut self, actions: &[Action]) {
+        actions.iter().enumerate().for_each(|(index, action)| {
+            if let Some(existing) = self.actions.get_mut(&ActionId(index as u32)) {
+                *existing = action.clone();
+            }
+        });
+    }
+
+    fn add_action(&mut self, action: Action) {
+        let id = self.action_id;
+        self.action_id.0 += 1;
+
+        self.actions.insert(id, action);
+    }
+
+    pub fn find_action_name(&self, name: &str) -> Option<ActionId> {
+        self.actions
+            .iter()
+            .find(|(_, action)| action.name == name)
+            .map(|(id, _)| *id)
+    }
+
+    #[inline]
+    pub fn get_action(&self, id: &ActionId) -> Option<&Action> {
+        self.actions.get(id)
+    }
+
+    /// Every action in id order.
+    pub fn actions(&self) -> Vec<(ActionId, &Action)> {
+        let mut actions = self
+            .actions
+            .iter()
+            .map(|(id, action)| (*id, action))
+            .collect::<Vec<_>>();
+
+        actions.sort_by_key(|(id, _)| *id);
+        actions
+    }
+
+    /// Feed every action into `state` in id order, so the same actions
+    /// always hash the same.
+    pub fn hash_data(&self, state: &mut impl Hasher) {
+        self.actions().into_iter().for_each(|(id, action)| {
+            std::hash::Hash::hash(&id, state);
+            std::hash::Hash::hash(action, state);
+        });
+    }
+}
+
+//====================================================================
+
+#[derive(Debug, Clone, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Action {
+    pub name: String,
+    pub target: TargetType,
+    pub resolution: ActionResolution,
+    /// Applied to the target along with the resolution.
+    #[serde(default)]
+    pub status: Option<StatusEffect>,
+    /// Turns the caster has to wait before using the action again.
+    #[serde(default)]
+    pub cooldown: u32,
+    /// Camera move played when the action is used, for big attacks.
+    #[serde(default)]
+    pub cinematic: Option<CinematicDef>,
+}
+
+/// Zooms to the caster, cuts to the target on impact, then returns to the
+/// player's camera. Times are in milliseconds.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CinematicDef {
+    /// Time taken to zoom in on the caster, and again to return afterwards.
+    pub zoom_ms: u32,
+    /// Time spent on the caster, and again on the target.
+    pub hold_ms: u32,
+    /// How far from the caster and target the camera sits.
+    pub distance: u32,
+}
+
+impl Default for CinematicDef {
+    fn default() -> Self {
+        Self {
+            zoom_ms: 500,
+            hold_ms: 600,
+            distance: 80,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TargetType {
+    None,
+    Any { can_target_caster: bool },
+    Caster,
+    Friendly { can_target_caster: bool },
+    Enemy,
+}
+
+#[derive(Debug, Clone, PartialEq, Hash, Serialize, Deserialize)]
+pub enum ActionResolution {
+    None,
+    Damage(u32),
+    Heal(u32),
+}
+
+//====================================================================
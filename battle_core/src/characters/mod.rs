@@ -0,0 +1,148 @@
+//====================================================================
+
+use std::collections::BTreeMap;
+
+use actions::ActionId;
+use serde::{Deserialize, Serialize};
+
+pub mod actions;
+
+//====================================================================
+
+/// Health of characters that don't set their own.
+pub const DEFAULT_HEALTH: u32 = 20;
+/// Percent chance to hit of characters that don't set their own.
+pub const DEFAULT_ACCURACY: u32 = 90;
+/// Percent chance for hits to be critical for characters that don't set
+/// their own.
+pub const DEFAULT_CRIT_CHANCE: u32 = 5;
+/// Percent of normal damage dealt by critical hits for characters that don't
+/// set their own.
+pub const DEFAULT_CRIT_MULTIPLIER: u32 = 150;
+
+//====================================================================
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Character {
+    pub name: String,
+    pub player_controlled: bool,
+    pub stats: CharacterStats,
+    pub equipment: Equipment,
+    /// Recolors the character's sprite, in degrees.
+    pub hue_shift: i16,
+    /// Image drawn for the character, by its name in the game data's
+    /// textures. The default texture if None.
+    pub texture: Option<String>,
+    pub actions: Vec<ActionId>,
+    /// Status effects in the order they were applied.
+    pub effects: Vec<StatusEffect>,
+    /// Turns left before an action can be used again. Actions that are ready
+    /// aren't listed.
+    pub cooldowns: BTreeMap<ActionId, u32>,
+
+    pub front_facing: bool,
+}
+
+impl Character {
+    /// Turns left before `action` can be used again.
+    #[inline]
+    pub fn cooldown(&self, action: ActionId) -> u32 {
+        self.cooldowns.get(&action).copied().unwrap_or(0)
+    }
+
+    /// Speed after any haste and slow effects.
+    pub fn speed(&self) -> u32 {
+        self.effects
+            .iter()
+            .fold(self.stats.speed, |speed, effect| match effect.kind {
+                StatusKind::Haste => speed.saturating_add(effect.amount),
+                StatusKind::Slow => speed.saturating_sub(effect.amount),
+                _ => speed,
+            })
+    }
+
+    #[inline]
+    pub fn has_effect(&self, kind: StatusKind) -> bool {
+        self.effects.iter().any(|effect| effect.kind == kind)
+    }
+
+    /// Apply a status effect. Reapplying one of the same kind refreshes it
+    /// rather than stacking.
+    pub fn apply_effect(&mut self, effect: StatusEffect) {
+        match self
+            .effects
+            .iter_mut()
+            .find(|existing| existing.kind == effect.kind)
+        {
+            Some(existing) => *existing = effect,
+            None => self.effects.push(effect),
+        }
+    }
+}
+
+/// Lasting effect resolved at the start of each of the character's turns.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    /// Health lost or gained each turn, or speed gained or lost.
+    #[serde(default)]
+    pub amount: u32,
+    /// Turns left before the effect wears off.
+    pub turns: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StatusKind {
+    /// Damage over time.
+    Poison,
+    /// Healing over time.
+    Regen,
+    /// Opponents can only target characters taunting them.
+    Taunt,
+    /// Left out of opponents' targets, unless there's nobody else to target.
+    Stealth,
+    /// Raises speed.
+    Haste,
+    /// Lowers speed.
+    Slow,
+}
+
+/// Gear worn by a character, in slot order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Equipment {
+    pub armor: Option<String>,
+    pub weapon: Option<String>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CharacterStats {
+    pub speed: u32,
+    pub health: u32,
+    pub max_health: u32,
+    /// Percent chance to hit before the target's evasion.
+    pub accuracy: u32,
+    /// Percent taken off the chance of being hit.
+    pub evasion: u32,
+    /// Percent chance for a hit to be critical.
+    pub crit_chance: u32,
+    /// Percent of normal damage dealt by a critical hit.
+    pub crit_multiplier: u32,
+}
+
+impl CharacterStats {
+    #[inline]
+    pub fn is_defeated(&self) -> bool {
+        self.health == 0
+    }
+
+    /// True once health is at or below `percent` of max health.
+    #[inline]
+    pub fn below_percent(&self, percent: u32) -> bool {
+        self.health as u64 * 100 <= self.max_health as u64 * percent as u64
+    }
+}
+
+//====================================================================
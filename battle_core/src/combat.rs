@@ -0,0 +1,623 @@
+//====================================================================
+
+use std::{
+    ops::{Add, Mul, Sub},
+    time::Duration,
+};
+
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::characters::{
+    actions::{Action, ActionId, ActionResolution, TargetType},
+    Character, CharacterStats, StatusKind,
+};
+
+//====================================================================
+
+/// Scale applied to combat amounts (damage, healing, speed). With the
+/// `fixed_point` feature this is a [`Fixed`] so results don't depend on the
+/// platform's float behaviour.
+#[cfg(feature = "fixed_point")]
+pub type Multiplier = Fixed;
+#[cfg(not(feature = "fixed_point"))]
+pub type Multiplier = f32;
+
+/// Multiplier of `numerator / denominator`. A zero denominator gives zero.
+#[inline]
+pub fn multiplier(numerator: u32, denominator: u32) -> Multiplier {
+    #[cfg(feature = "fixed_point")]
+    {
+        Fixed::from_ratio(numerator, denominator)
+    }
+
+    #[cfg(not(feature = "fixed_point"))]
+    {
+        match denominator {
+            0 => 0.,
+            _ => numerator as f32 / denominator as f32,
+        }
+    }
+}
+
+/// Scale an amount, rounding to the nearest whole number.
+#[inline]
+pub fn scale(amount: u32, multiplier: Multiplier) -> u32 {
+    #[cfg(feature = "fixed_point")]
+    {
+        multiplier.scale(amount)
+    }
+
+    #[cfg(not(feature = "fixed_point"))]
+    {
+        (amount as f32 * multiplier).round().max(0.) as u32
+    }
+}
+
+/// Damage dealt after applying the multiplier, never more than the target's
+/// remaining health.
+#[inline]
+pub fn damage(base: u32, multiplier: Multiplier, health: u32) -> u32 {
+    scale(base, multiplier).min(health)
+}
+
+//--------------------------------------------------
+
+/// Unsigned 16.16 fixed point number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Fixed(u32);
+
+impl Fixed {
+    const FRACTION_BITS: u32 = 16;
+
+    pub const ZERO: Fixed = Fixed(0);
+    pub const ONE: Fixed = Fixed(1 << Self::FRACTION_BITS);
+
+    /// Every `u16` fits, so this can't overflow.
+    #[inline]
+    pub fn from_int(value: u16) -> Self {
+        Self((value as u32) << Self::FRACTION_BITS)
+    }
+
+    /// None for whole numbers above `u16::MAX`, which don't fit.
+    #[inline]
+    pub fn checked_from_int(value: u32) -> Option<Self> {
+        u16::try_from(value).ok().map(Self::from_int)
+    }
+
+    #[inline]
+    pub fn from_ratio(numerator: u32, denominator: u32) -> Self {
+        match denominator {
+            0 => Self::ZERO,
+            _ => Self::from_raw(((numerator as u64) << Self::FRACTION_BITS) / denominator as u64),
+        }
+    }
+
+    #[inline]
+    pub fn to_bits(self) -> u32 {
+        self.0
+    }
+
+    #[inline]
+    pub fn floor(self) -> u32 {
+        self.0 >> Self::FRACTION_BITS
+    }
+
+    /// Round to the nearest whole number, halves round up.
+    #[inline]
+    pub fn round(self) -> u32 {
+        ((self.0 as u64 + (1 << (Self::FRACTION_BITS - 1))) >> Self::FRACTION_BITS) as u32
+    }
+
+    /// Multiply a whole number, rounding to the nearest. Unlike going through
+    /// [`Fixed::from_int`] this takes any `u32`, saturating at `u32::MAX`.
+    #[inline]
+    pub fn scale(self, value: u32) -> u32 {
+        let scaled = (value as u64 * self.0 as u64 + (1 << (Self::FRACTION_BITS - 1)))
+            >> Self::FRACTION_BITS;
+        scaled.min(u32::MAX as u64) as u32
+    }
+
+    #[inline]
+    fn from_raw(raw: u64) -> Self {
+        Self(raw.min(u32::MAX as u64) as u32)
+    }
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Fixed;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::from_raw((self.0 as u64 * rhs.0 as u64) >> Self::FRACTION_BITS)
+    }
+}
+
+//====================================================================
+
+/// Roll the order characters act in for a round. Each pick is weighted by
+/// speed, so faster characters are more likely to act earlier. Only uses
+/// integer math, so the same seed gives the same order on every platform.
+pub fn roll_turn_order<T: Copy>(speeds: &[(u32, T)], rng: &mut impl Rng) -> Vec<T> {
+    let mut remaining = speeds.to_vec();
+    // Summed wide so any number of characters at any speed fits
+    let mut weight = remaining
+        .iter()
+        .map(|(speed, _)| *speed as u64)
+        .sum::<u64>();
+
+    let mut turn_order = Vec::with_capacity(remaining.len());
+
+    while !remaining.is_empty() {
+        // Nobody left with any speed - keep the rest in their given order
+        if remaining.len() == 1 || weight == 0 {
+            turn_order.extend(remaining.iter().map(|(_, id)| *id));
+            break;
+        }
+
+        let roll = rng.gen_range(0..weight);
+        let mut acc = 0;
+
+        let index = remaining
+            .iter()
+            .position(|(speed, _)| match (acc + *speed as u64) > roll {
+                true => true,
+                false => {
+                    acc += *speed as u64;
+                    false
+                }
+            })
+            .unwrap();
+
+        let (speed, id) = remaining.remove(index);
+        turn_order.push(id);
+        weight -= speed as u64;
+    }
+
+    turn_order
+}
+
+/// Most likely order for a round given current speeds, fastest first. Ties
+/// keep the order they were given in. Rolls are weighted by speed so this is
+/// a projection rather than what [`roll_turn_order`] will give.
+pub fn predict_turn_order<T: Copy>(speeds: &[(u32, T)]) -> Vec<T> {
+    let mut predicted = speeds.to_vec();
+    predicted.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+    predicted.into_iter().map(|(_, id)| id).collect()
+}
+
+//====================================================================
+
+/// Something resolved at the start of a character's turn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TurnStartTick {
+    /// Health lost to a damage over time effect.
+    Damaged { kind: StatusKind, amount: u32 },
+    /// Health restored by a healing over time effect.
+    Healed { kind: StatusKind, amount: u32 },
+    /// A status effect wore off.
+    Expired { kind: StatusKind },
+    /// Health reached zero. Nothing else resolves afterwards.
+    Defeated,
+    /// An action can be used again.
+    CooldownReady { action: ActionId },
+}
+
+/// Resolve everything that happens at the start of a character's turn. The
+/// order is always the same so both sides of a networked battle agree:
+///
+/// 1. Damage over time, in the order effects were applied.
+/// 2. Healing over time, in the order effects were applied. Healing can't
+///    save a character that damage defeated.
+/// 3. Effects count down, expiring in the order they were applied.
+/// 4. Cooldowns count down, in action id order.
+///
+/// A defeated character loses all their effects and nothing else resolves.
+pub fn resolve_turn_start(character: &mut Character) -> Vec<TurnStartTick> {
+    let mut ticks = Vec::new();
+
+    if character.stats.is_defeated() {
+        return ticks;
+    }
+
+    let stats = &mut character.stats;
+
+    // Stops at the effect that defeats the character
+    let defeated = character
+        .effects
+        .iter()
+        .filter(|effect| effect.kind == StatusKind::Poison)
+        .any(|effect| {
+            let amount = effect.amount.min(stats.health);
+            stats.health -= amount;
+            ticks.push(TurnStartTick::Damaged {
+                kind: effect.kind,
+                amount,
+            });
+
+            stats.is_defeated()
+        });
+
+    if defeated {
+        ticks.push(TurnStartTick::Defeated);
+        character.effects.clear();
+        return ticks;
+    }
+
+    for effect in character.effects.iter() {
+        if effect.kind != StatusKind::Regen {
+            continue;
+        }
+
+        let amount = effect
+            .amount
+            .min(stats.max_health.saturating_sub(stats.health));
+        stats.health += amount;
+        ticks.push(TurnStartTick::Healed {
+            kind: effect.kind,
+            amount,
+        });
+    }
+
+    character.effects.retain_mut(|effect| {
+        effect.turns = effect.turns.saturating_sub(1);
+
+        match effect.turns {
+            0 => {
+                ticks.push(TurnStartTick::Expired { kind: effect.kind });
+                false
+            }
+            _ => true,
+        }
+    });
+
+    character.cooldowns.retain(|action, turns| {
+        *turns = turns.saturating_sub(1);
+
+        match *turns {
+            0 => {
+                ticks.push(TurnStartTick::CooldownReady { action: *action });
+                false
+            }
+            _ => true,
+        }
+    });
+
+    ticks
+}
+
+//====================================================================
+
+/// Indices into `roster` of the characters still in the fight that the
+/// character at `caster` could use an action on. `roster` pairs every
+/// character with whether they're friendly.
+///
+/// Opponents taunting the caster are the only opponents that can be
+/// targeted. Otherwise stealthed opponents are left out, unless every
+/// opponent is stealthed.
+pub fn legal_targets(
+    roster: &[(bool, &Character)],
+    caster: usize,
+    target: TargetType,
+) -> Vec<usize> {
+    let caster_friendly = match roster.get(caster) {
+        Some((friendly, _)) => *friendly,
+        None => return Vec::new(),
+    };
+
+    let valid = |index: usize, friendly: bool| match target {
+        TargetType::None => false,
+        TargetType::Caster => index == caster,
+        TargetType::Any { can_target_caster } => can_target_caster || index != caster,
+        TargetType::Friendly { can_target_caster } => {
+            friendly == caster_friendly && (can_target_caster || index != caster)
+        }
+        TargetType::Enemy => friendly != caster_friendly,
+    };
+
+    let standing_opponents = roster
+        .iter()
+        .filter(|(friendly, character)| {
+            *friendly != caster_friendly && !character.stats.is_defeated()
+        })
+        .collect::<Vec<_>>();
+
+    let taunting = standing_opponents
+        .iter()
+        .any(|(_, character)| character.has_effect(StatusKind::Taunt));
+    let all_stealthed = standing_opponents
+        .iter()
+        .all(|(_, character)| character.has_effect(StatusKind::Stealth));
+
+    let allowed_opponent = |character: &Character| match (taunting, all_stealthed) {
+        (true, _) => character.has_effect(StatusKind::Taunt),
+        (false, true) => true,
+        (false, false) => !character.has_effect(StatusKind::Stealth),
+    };
+
+    roster
+        .iter()
+        .enumerate()
+        .filter(|(index, (friendly, character))| {
+            valid(*index, *friendly) && !character.stats.is_defeated()
+        })
+        .filter(|(_, (friendly, character))| {
+            *friendly == caster_friendly || allowed_opponent(character)
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+//====================================================================
+
+/// Source of every roll in a battle, so battles started from the same seed
+/// play out the same way. Serializable so a snapshot carries on from the
+/// same point in the sequence.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BattleRng(ChaCha12Rng);
+
+impl BattleRng {
+    #[inline]
+    pub fn from_seed(seed: u64) -> Self {
+        Self(ChaCha12Rng::seed_from_u64(seed))
+    }
+}
+
+impl RngCore for BattleRng {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    #[inline]
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    #[inline]
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
+
+//--------------------------------------------------
+
+/// Lowest chance to hit, so attacks always have some hope of landing.
+pub const MIN_HIT_CHANCE: u32 = 5;
+
+/// Percent chance for an attack to hit. Accuracy is reduced by the
+/// defender's evasion, never going below [`MIN_HIT_CHANCE`] or above 100.
+#[inline]
+pub fn hit_chance(accuracy: u32, evasion: u32) -> u32 {
+    accuracy.saturating_sub(evasion).clamp(MIN_HIT_CHANCE, 100)
+}
+
+/// Roll something with a `chance` percent chance of happening, such as an
+/// attack hitting or a hit being critical. 0 never happens and 100 or more
+/// always does.
+#[inline]
+pub fn roll_chance(chance: u32, rng: &mut impl Rng) -> bool {
+    rng.gen_range(0..100) < chance
+}
+
+/// Damage scale for a critical hit from a percentage, such as 150 for half
+/// as much again. Critical hits never deal less than a normal hit.
+#[inline]
+pub fn crit_multiplier(percent: u32) -> Multiplier {
+    multiplier(percent.max(100), 100)
+}
+
+//====================================================================
+
+/// How the hit and critical hit rolls of an action are made.
+pub enum Rolls<'a> {
+    /// Rolled as in a battle.
+    Random(&'a mut dyn RngCore),
+    /// Replaced by their expected results, so the same action always plays
+    /// out the same way. Damage is scaled by the chance to hit and the
+    /// average boost from critical hits, and nothing misses.
+    Expected,
+}
+
+/// What an action did to its target.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Resolved {
+    pub missed: bool,
+    pub critical: bool,
+    pub damage: u32,
+    pub healed: u32,
+    /// The target has no health left.
+    pub defeated: bool,
+}
+
+/// Put an action on cooldown after `character` uses it. Counted down at the
+/// start of each of their turns, starting with their next one.
+#[inline]
+pub fn start_cooldown(character: &mut Character, id: ActionId, action: &Action) {
+    if action.cooldown > 0 {
+        character.cooldowns.insert(id, action.cooldown + 1);
+    }
+}
+
+/// Resolve an action on its target. The one place battle rules are applied,
+/// whether the battle is played, simulated or looked ahead on.
+///
+/// Only attacks roll to hit, against the target's evasion, then roll for a
+/// critical hit. Damage is scaled by the difficulty for the caster's side.
+/// The action's status effect lands on targets left standing, unless the
+/// attack missed. Takes a copy of the caster's stats as they may be the
+/// target.
+pub fn resolve_action(
+    action: &Action,
+    caster: &CharacterStats,
+    player_controlled: bool,
+    target: &mut Character,
+    difficulty: Difficulty,
+    rolls: Rolls,
+) -> Resolved {
+    let mut resolved = Resolved::default();
+
+    match action.resolution {
+        ActionResolution::None => {}
+
+        ActionResolution::Damage(base) => {
+            let multiplier = difficulty.damage_multiplier(player_controlled);
+            let crit_multiplier = multiplier * crit_multiplier(caster.crit_multiplier);
+            let hit_chance = hit_chance(caster.accuracy, target.stats.evasion);
+
+            let amount = match rolls {
+                Rolls::Random(mut rng) => {
+                    if !roll_chance(hit_chance, &mut rng) {
+                        resolved.missed = true;
+                        return resolved;
+                    }
+
+                    resolved.critical = roll_chance(caster.crit_chance, &mut rng);
+                    match resolved.critical {
+                        true => scale(base, crit_multiplier),
+                        false => scale(base, multiplier),
+                    }
+                }
+                Rolls::Expected => {
+                    let normal = scale(base, multiplier) as u64;
+                    let critical = scale(base, crit_multiplier) as u64;
+                    let crit_chance = caster.crit_chance.min(100) as u64;
+
+                    let expected =
+                        (normal * (100 - crit_chance) + critical * crit_chance) * hit_chance as u64;
+                    ((expected + 5_000) / 10_000) as u32
+                }
+            };
+
+            resolved.damage = amount.min(target.stats.health);
+            target.stats.health -= resolved.damage;
+        }
+
+        ActionResolution::Heal(amount) => {
+            let stats = &mut target.stats;
+            resolved.healed = amount.min(stats.max_health.saturating_sub(stats.health));
+            stats.health += resolved.healed;
+        }
+    }
+
+    resolved.defeated = target.stats.is_defeated();
+
+    if let (false, Some(effect)) = (resolved.defeated, &action.status) {
+        target.apply_effect(effect.clone());
+    }
+
+    resolved
+}
+
+//====================================================================
+
+/// Time a player gets to pick an action when the turn timer is on.
+pub const TURN_TIME: Duration = Duration::from_secs(30);
+
+/// Selectable difficulty, read from the settings at the start of each
+/// battle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    /// Scale applied to damage dealt by characters on either side.
+    pub fn damage_multiplier(&self, player_controlled: bool) -> Multiplier {
+        match (self, player_controlled) {
+            (Difficulty::Easy, true) => multiplier(5, 4),
+            (Difficulty::Easy, false) => multiplier(3, 4),
+            (Difficulty::Normal, _) => multiplier(1, 1),
+            (Difficulty::Hard, true) => multiplier(4, 5),
+            (Difficulty::Hard, false) => multiplier(5, 4),
+        }
+    }
+
+    /// Percent chance a cpu character picks a damaging action over anything
+    /// else it could do.
+    pub fn aggressiveness(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 25,
+            Difficulty::Normal => 50,
+            Difficulty::Hard => 85,
+        }
+    }
+
+    /// Whether turns are timed when the settings don't say.
+    pub fn turn_timer(&self) -> bool {
+        matches!(self, Difficulty::Hard)
+    }
+
+    /// Turns cpu characters play out ahead before picking an action when the
+    /// settings don't say. 0 picks without looking ahead.
+    pub fn lookahead(&self) -> u32 {
+        match self {
+            Difficulty::Easy | Difficulty::Normal => 0,
+            Difficulty::Hard => 2,
+        }
+    }
+}
+
+/// How a cpu controlled character picks its actions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuProfile {
+    /// Percent chance of picking a damaging action over anything else.
+    pub aggressiveness: u32,
+    /// Turns played out ahead before picking, 0 to not look ahead.
+    pub lookahead: u32,
+}
+
+/// Pick an action for a cpu controlled character, preferring damage based on
+/// `aggressiveness` (a percentage).
+pub fn choose_cpu_action<T: Copy>(
+    actions: &[(T, &ActionResolution)],
+    aggressiveness: u32,
+    rng: &mut impl Rng,
+) -> Option<T> {
+    let damaging = actions
+        .iter()
+        .filter(|(_, resolution)| matches!(resolution, ActionResolution::Damage(_)))
+        .collect::<Vec<_>>();
+
+    let aggressive = !damaging.is_empty() && rng.gen_range(0..100) < aggressiveness;
+
+    let choices = match aggressive {
+        true => damaging,
+        false => actions.iter().collect(),
+    };
+
+    match choices.is_empty() {
+        true => None,
+        false => Some(choices[rng.gen_range(0..choices.len())].0),
+    }
+}
+
+//====================================================================
@@ -0,0 +1,9 @@
+//====================================================================
+
+pub mod battle;
+pub mod characters;
+pub mod combat;
+pub mod lookahead;
+pub mod protocol;
+
+//====================================================================
@@ -0,0 +1,273 @@
+//====================================================================
+
+use crate::{
+    characters::{
+        actions::{ActionId, ActionRepo, TargetType},
+        Character,
+    },
+    combat::{self, Difficulty, Rolls},
+};
+
+//====================================================================
+
+/// Moves a cpu character plays out while looking ahead before settling for
+/// the best action found so far. Counted rather than timed so the same
+/// battle always picks the same way, however fast the machine.
+pub const LOOKAHEAD_BUDGET: u32 = 5_000;
+
+/// Score given to winning, outweighing any amount of health.
+const WIN_SCORE: i64 = 1_000_000;
+/// Score for each character still standing, on top of their health.
+const STANDING_SCORE: i64 = 500;
+
+/// An action along with the index of the character it's used on.
+pub type Move = (ActionId, Option<usize>);
+
+//====================================================================
+
+/// Copy of a battle's characters that actions can be played out on without a
+/// world, scene or renderer. Rolls are replaced by their expected results, so
+/// the same moves always play out the same way.
+#[derive(Debug, Clone)]
+pub struct SimBattle {
+    difficulty: Difficulty,
+    /// Every character in battle order, paired with whether they're friendly.
+    characters: Vec<(bool, Character)>,
+}
+
+impl SimBattle {
+    pub fn new(difficulty: Difficulty, characters: Vec<(bool, Character)>) -> Self {
+        Self {
+            difficulty,
+            characters,
+        }
+    }
+
+    fn roster(&self) -> Vec<(bool, &Character)> {
+        self.characters
+            .iter()
+            .map(|(friendly, character)| (*friendly, character))
+            .collect()
+    }
+
+    #[inline]
+    pub fn is_friendly(&self, index: usize) -> Option<bool> {
+        self.characters.get(index).map(|(friendly, _)| *friendly)
+    }
+
+    #[inline]
+    pub fn character(&self, index: usize) -> Option<&Character> {
+        self.characters.get(index).map(|(_, character)| character)
+    }
+
+    /// Speed of every character still in the fight, in battle order.
+    pub fn speeds(&self) -> Vec<(u32, usize)> {
+        self.characters
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, character))| !character.stats.is_defeated())
+            .map(|(index, (_, character))| (character.speed(), index))
+            .collect()
+    }
+
+    /// Side left standing once every character on the other is defeated.
+    pub fn winner(&self) -> Option<bool> {
+        let standing = |side: bool| {
+            self.characters
+                .iter()
+                .any(|(friendly, character)| *friendly == side && !character.stats.is_defeated())
+        };
+
+        match (standing(true), standing(false)) {
+            (true, false) => Some(true),
+            (false, true) => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Every action the character at `caster` could use, paired with each
+    /// target it could be used on.
+    pub fn moves(&self, actions: &ActionRepo, caster: usize) -> Vec<Move> {
+        let roster = self.roster();
+
+        let character = match roster.get(caster) {
+            Some((_, character)) if !character.stats.is_defeated() => character,
+            _ => return Vec::new(),
+        };
+
+        character
+            .actions
+            .iter()
+            .filter(|id| character.cooldown(**id) == 0)
+            .filter_map(|id| actions.get_action(id).map(|action| (*id, action)))
+            .flat_map(|(id, action)| match action.target {
+                TargetType::None => vec![(id, None)],
+                target => combat::legal_targets(&roster, caster, target)
+                    .into_iter()
+                    .map(|target| (id, Some(target)))
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Resolve the start of a character's turn the same way the battle does.
+    pub fn start_turn(&mut self, index: usize) {
+        if let Some((_, character)) = self.characters.get_mut(index) {
+            combat::resolve_turn_start(character);
+        }
+    }
+
+    /// Play out a move with its expected result, as in [`Rolls::Expected`].
+    pub fn apply(&mut self, actions: &ActionRepo, caster: usize, (id, target): Move) {
+        let action = match actions.get_action(&id) {
+            Some(action) => action,
+            None => return,
+        };
+
+        let (player_controlled, caster_stats) = match self.characters.get_mut(caster) {
+            Some((_, character)) => {
+                combat::start_cooldown(character, id, action);
+                (character.player_controlled, character.stats.clone())
+            }
+            None => return,
+        };
+
+        if let Some((_, character)) = target.and_then(|target| self.characters.get_mut(target)) {
+            combat::resolve_action(
+                action,
+                &caster_stats,
+                player_controlled,
+                character,
+                self.difficulty,
+                Rolls::Expected,
+            );
+        }
+    }
+
+    /// How well the battle is going for one side. Each character counts for
+    /// their share of health left plus a bonus for standing, taking away
+    /// whatever the other side has.
+    pub fn score(&self, friendly: bool) -> i64 {
+        match self.winner() {
+            Some(winner) if winner == friendly => return WIN_SCORE,
+            Some(_) => return -WIN_SCORE,
+            None => {}
+        }
+
+        self.characters
+            .iter()
+            .map(|(side, character)| {
+                let stats = &character.stats;
+
+                let value = match stats.is_defeated() {
+                    true => 0,
+                    false => {
+                        stats.health as i64 * 1000 / stats.max_health.max(1) as i64 + STANDING_SCORE
+                    }
+                };
+
+                match *side == friendly {
+                    true => value,
+                    false => -value,
+                }
+            })
+            .sum()
+    }
+}
+
+//====================================================================
+
+/// Pick the move for the character at `caster` that leaves their side best
+/// off once `order` has played out, assuming everyone picks the move best for
+/// their own side. Gives the best move found so far once `budget` moves have
+/// been played out.
+pub fn choose_move(
+    battle: &SimBattle,
+    actions: &ActionRepo,
+    caster: usize,
+    order: &[usize],
+    budget: u32,
+) -> Option<Move> {
+    let mut budget = budget;
+    let side = battle.is_friendly(caster)?;
+
+    let mut best: Option<(i64, Move)> = None;
+
+    for next in battle.moves(actions, caster) {
+        if best.is_some() && budget == 0 {
+            log::debug!("Ran out of budget looking ahead");
+            break;
+        }
+
+        let mut played = battle.clone();
+        played.apply(actions, caster, next);
+        budget = budget.saturating_sub(1);
+
+        let alpha = best.map_or(i64::MIN, |(score, _)| score);
+        let score = search(&played, actions, side, order, alpha, i64::MAX, &mut budget);
+
+        if best.is_none_or(|(best, _)| score > best) {
+            best = Some((score, next));
+        }
+    }
+
+    best.map(|(_, best)| best)
+}
+
+// Minimax with alpha-beta pruning. Characters on `side` look for the highest
+// score and their opponents the lowest.
+fn search(
+    battle: &SimBattle,
+    actions: &ActionRepo,
+    side: bool,
+    order: &[usize],
+    mut alpha: i64,
+    mut beta: i64,
+    budget: &mut u32,
+) -> i64 {
+    let (index, rest) = match order.split_first() {
+        Some((index, rest)) if battle.winner().is_none() && *budget > 0 => (*index, rest),
+        _ => return battle.score(side),
+    };
+
+    let mut battle = battle.clone();
+    battle.start_turn(index);
+
+    let moves = battle.moves(actions, index);
+    if moves.is_empty() {
+        return search(&battle, actions, side, rest, alpha, beta, budget);
+    }
+
+    let maximizing = battle.is_friendly(index) == Some(side);
+    let mut best = match maximizing {
+        true => i64::MIN,
+        false => i64::MAX,
+    };
+
+    for next in moves {
+        let mut played = battle.clone();
+        played.apply(actions, index, next);
+        *budget = budget.saturating_sub(1);
+
+        let score = search(&played, actions, side, rest, alpha, beta, budget);
+
+        match maximizing {
+            true => {
+                best = best.max(score);
+                alpha = alpha.max(best);
+            }
+            false => {
+                best = best.min(score);
+                beta = beta.min(best);
+            }
+        }
+
+        if alpha >= beta {
+            break;
+        }
+    }
+
+    best
+}
+
+//====================================================================
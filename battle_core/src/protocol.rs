@@ -0,0 +1,345 @@
+//====================================================================
+
+use std::hash::Hasher;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    battle::{ActionError, Battle},
+    characters::{
+        actions::{Action, ActionId, ActionRepo},
+        Character,
+    },
+    combat::Difficulty,
+};
+
+//====================================================================
+
+/// Bumped whenever a message changes shape. Peers on different versions
+/// refuse to talk rather than misreading each other.
+pub const PROTOCOL_VERSION: u16 = 8;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProtocolError {
+    #[error("Protocol version mismatch (local {local}, remote {remote}) - both players need the same game version")]
+    VersionMismatch { local: u16, remote: u16 },
+
+    #[error("Message too short to contain a protocol version")]
+    Truncated,
+
+    #[error("Unable to encode or decode message: {0}")]
+    Serialize(#[from] bincode::Error),
+
+    #[error("Connection rejected: {0}")]
+    Rejected(RejectReason),
+
+    #[error("Expected a handshake reply but got {0}")]
+    UnexpectedMessage(String),
+
+    #[error("Missed battle events (expected {expected}, got {got}) - resync required")]
+    MissedEvents { expected: u64, got: u64 },
+}
+
+//====================================================================
+
+/// Sent by both sides when connecting so mismatched builds fail up front
+/// instead of desyncing mid battle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Handshake {
+    pub protocol_version: u16,
+    /// Hash of the actions and characters each side will play with.
+    pub data_hash: u64,
+}
+
+impl Handshake {
+    #[inline]
+    pub fn new(data_hash: u64) -> Self {
+        Self {
+            protocol_version: PROTOCOL_VERSION,
+            data_hash,
+        }
+    }
+
+    /// Check a remote handshake against this one.
+    pub fn check(&self, remote: &Handshake) -> Result<(), RejectReason> {
+        if self.protocol_version != remote.protocol_version {
+            return Err(RejectReason::ProtocolVersion {
+                local: self.protocol_version,
+                remote: remote.protocol_version,
+            });
+        }
+
+        if self.data_hash != remote.data_hash {
+            return Err(RejectReason::GameData {
+                local: self.data_hash,
+                remote: remote.data_hash,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+pub enum RejectReason {
+    #[error("protocol version mismatch (server {local}, client {remote})")]
+    ProtocolVersion { local: u16, remote: u16 },
+
+    #[error("game data mismatch (server {local:016x}, client {remote:016x})")]
+    GameData { local: u64, remote: u64 },
+
+    #[error("battle is full")]
+    Full,
+
+    #[error("no room with code '{0}'")]
+    NoSuchRoom(String),
+}
+
+//--------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ClientMessage {
+    Hello(Handshake),
+    /// Reconnect to a battle in progress. Answered with a welcome followed
+    /// by a snapshot.
+    Rejoin(Handshake),
+    Command(BattleCommand),
+    Leave,
+
+    /// Ask a relay for a new room playing out `RoomSetup`. Answered with a
+    /// welcome then the room code.
+    CreateRoom(RoomSetup),
+    /// Join a relay room, or rejoin after disconnecting. Answered with a
+    /// welcome followed by a snapshot.
+    JoinRoom {
+        code: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ServerMessage {
+    /// Handshake accepted. Everyone rolls from the same seed.
+    Welcome {
+        handshake: Handshake,
+        seed: u64,
+    },
+    Rejected(RejectReason),
+    /// A command accepted by the server, relayed to every client.
+    Event {
+        sequence: u64,
+        command: BattleCommand,
+    },
+    /// Full battle state for clients that are rejoining.
+    Snapshot(Box<BattleSnapshot>),
+
+    RoomJoined {
+        code: String,
+        player: u32,
+    },
+    /// The current player took too long and their turn is skipped.
+    TurnTimedOut {
+        sequence: u64,
+    },
+    /// A command the server wouldn't play. Only sent to the player that sent
+    /// it.
+    CommandRejected(ActionError),
+    /// One side has nobody left standing.
+    BattleEnded {
+        won: bool,
+    },
+}
+
+/// Battle played out in a relay room. The first player to join controls the
+/// friendly side and the second the enemy side.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoomSetup {
+    pub difficulty: Difficulty,
+    /// Every action in id order.
+    pub actions: Vec<Action>,
+    pub friendly: Vec<Character>,
+    pub enemy: Vec<Character>,
+}
+
+impl RoomSetup {
+    /// Hash the room's creator needs to have handshaken with.
+    pub fn data_hash(&self) -> u64 {
+        game_data_hash(
+            &ActionRepo::from_actions(self.actions.clone()),
+            self.friendly.iter().chain(&self.enemy),
+        )
+    }
+}
+
+/// Action chosen by a player for one of their characters. Characters are
+/// referred to by their index in the battle's character list.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BattleCommand {
+    pub character: u32,
+    pub action: ActionId,
+    pub target: Option<u32>,
+}
+
+/// Everything a client needs to carry on a battle in progress, including
+/// where its rolls are up to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BattleSnapshot {
+    /// Sequence of the first event not included in this snapshot.
+    pub next_sequence: u64,
+    pub battle: Battle,
+}
+
+//--------------------------------------------------
+
+/// Tracks which battle events a client has applied, so events already
+/// covered by a snapshot are skipped and gaps are caught.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EventCursor {
+    next: u64,
+}
+
+impl EventCursor {
+    /// Continue from the event after the snapshot.
+    #[inline]
+    pub fn from_snapshot(snapshot: &BattleSnapshot) -> Self {
+        Self {
+            next: snapshot.next_sequence,
+        }
+    }
+
+    /// Returns `Ok(true)` if the event should be applied, `Ok(false)` if it
+    /// has already been applied.
+    pub fn accept(&mut self, sequence: u64) -> Result<bool, ProtocolError> {
+        match sequence.cmp(&self.next) {
+            std::cmp::Ordering::Less => Ok(false),
+            std::cmp::Ordering::Equal => {
+                self.next += 1;
+                Ok(true)
+            }
+            std::cmp::Ordering::Greater => Err(ProtocolError::MissedEvents {
+                expected: self.next,
+                got: sequence,
+            }),
+        }
+    }
+}
+
+//====================================================================
+
+/// Server reply to a client's hello.
+pub fn accept_client(local: &Handshake, client: &Handshake, seed: u64) -> ServerMessage {
+    match local.check(client) {
+        Ok(()) => ServerMessage::Welcome {
+            handshake: *local,
+            seed,
+        },
+        Err(reason) => {
+            log::warn!("Rejecting client: {}", reason);
+            ServerMessage::Rejected(reason)
+        }
+    }
+}
+
+/// Client handling of the server's reply to its hello. Returns the battle
+/// seed on success.
+pub fn handle_welcome(local: &Handshake, reply: ServerMessage) -> Result<u64, ProtocolError> {
+    match reply {
+        ServerMessage::Welcome { handshake, seed } => {
+            // Server should have caught this, but don't trust it
+            handshake.check(local).map_err(ProtocolError::Rejected)?;
+            Ok(seed)
+        }
+        ServerMessage::Rejected(reason) => Err(ProtocolError::Rejected(reason)),
+        other => Err(ProtocolError::UnexpectedMessage(format!("{:?}", other))),
+    }
+}
+
+//====================================================================
+
+/// Serialize a message, prefixed with the protocol version.
+pub fn encode<T: Serialize>(message: &T) -> Result<Vec<u8>, ProtocolError> {
+    let mut bytes = PROTOCOL_VERSION.to_le_bytes().to_vec();
+    bincode::serialize_into(&mut bytes, message)?;
+
+    Ok(bytes)
+}
+
+/// Deserialize a message, failing if it was written by a different version.
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, ProtocolError> {
+    let (version, body) = match bytes {
+        [a, b, body @ ..] => (u16::from_le_bytes([*a, *b]), body),
+        _ => return Err(ProtocolError::Truncated),
+    };
+
+    if version != PROTOCOL_VERSION {
+        return Err(ProtocolError::VersionMismatch {
+            local: PROTOCOL_VERSION,
+            remote: version,
+        });
+    }
+
+    Ok(bincode::deserialize(body)?)
+}
+
+//====================================================================
+
+/// Hash of the game data both sides need to agree on. Stable across
+/// platforms and builds, unlike the std hasher.
+pub fn game_data_hash<'a>(
+    actions: &ActionRepo,
+    characters: impl IntoIterator<Item = &'a Character>,
+) -> u64 {
+    let mut hasher = DataHasher::default();
+
+    actions.hash_data(&mut hasher);
+    characters
+        .into_iter()
+        .for_each(|character| std::hash::Hash::hash(character, &mut hasher));
+
+    hasher.finish()
+}
+
+/// FNV-1a, with integers always written as little endian 64 bit values so
+/// 32 and 64 bit targets agree.
+struct DataHasher(u64);
+
+impl Default for DataHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for DataHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        bytes.iter().for_each(|byte| {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        });
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+}
+
+//====================================================================
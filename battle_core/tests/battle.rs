@@ -0,0 +1,563 @@
+//====================================================================
+
+use std::collections::BTreeMap;
+
+use battle_core::{
+    battle::{ActionError, Battle, BossPhase, Step},
+    characters::{
+        actions::{Action, ActionRepo, ActionResolution, TargetType},
+        Character, CharacterStats, Equipment, StatusEffect, StatusKind,
+    },
+    combat::{CpuProfile, Difficulty, TurnStartTick},
+    protocol::{self, BattleSnapshot},
+};
+
+//====================================================================
+
+/// Picks damage whenever it can, without looking ahead.
+const AGGRESSIVE: CpuProfile = CpuProfile {
+    aggressiveness: 100,
+    lookahead: 0,
+};
+
+fn action(name: &str, target: TargetType, resolution: ActionResolution, cooldown: u32) -> Action {
+    Action {
+        name: name.into(),
+        target,
+        resolution,
+        status: None,
+        cooldown,
+        cinematic: None,
+    }
+}
+
+// Never misses or lands a critical hit, so damage is always the action's.
+// Knows every action in `actions`.
+fn character(name: &str, speed: u32, health: u32, actions: &ActionRepo) -> Character {
+    Character {
+        name: name.into(),
+        player_controlled: false,
+        stats: CharacterStats {
+            speed,
+            health,
+            max_health: health,
+            accuracy: 100,
+            evasion: 0,
+            crit_chance: 0,
+            crit_multiplier: 100,
+        },
+        equipment: Equipment::default(),
+        hue_shift: 0,
+        texture: None,
+        actions: actions.actions().into_iter().map(|(id, _)| id).collect(),
+        effects: Vec::new(),
+        cooldowns: BTreeMap::new(),
+        front_facing: true,
+    }
+}
+
+// Hero (0) against Goblin (1) and Brute (2), with Idle, Punch (5 damage),
+// Heal (5) and Smite (12 damage, 3 turn cooldown)
+fn small_battle(seed: u64) -> (ActionRepo, Battle) {
+    let actions = ActionRepo::from_actions(vec![
+        action("Idle", TargetType::None, ActionResolution::None, 0),
+        action("Punch", TargetType::Enemy, ActionResolution::Damage(5), 0),
+        action(
+            "Heal",
+            TargetType::Friendly {
+                can_target_caster: true,
+            },
+            ActionResolution::Heal(5),
+            0,
+        ),
+        action("Smite", TargetType::Enemy, ActionResolution::Damage(12), 3),
+    ]);
+
+    let battle = Battle::new(
+        Difficulty::Normal,
+        vec![character("Hero", 5, 30, &actions)],
+        vec![
+            character("Goblin", 5, 20, &actions),
+            character("Brute", 4, 28, &actions),
+        ],
+        Vec::new(),
+        seed,
+    );
+
+    (actions, battle)
+}
+
+// Advance until a character can act, returning them. None once the battle
+// is over.
+fn next_turn(battle: &mut Battle) -> Option<usize> {
+    loop {
+        match battle.advance() {
+            Step::RoundStarted { .. } => {}
+            Step::TurnStarted { .. } => {
+                if let Some(current) = battle.current() {
+                    return Some(current);
+                }
+            }
+            Step::Ended { .. } => return None,
+        }
+    }
+}
+
+fn effect(kind: StatusKind, amount: u32, turns: u32) -> StatusEffect {
+    StatusEffect {
+        kind,
+        amount,
+        turns,
+    }
+}
+
+//====================================================================
+// Rounds
+
+#[test]
+fn everyone_standing_gets_one_turn_a_round() {
+    (0..50).for_each(|seed| {
+        let (actions, mut battle) = small_battle(seed);
+        let idle = actions.find_action_name("Idle").unwrap();
+
+        let mut rounds = Vec::new();
+        let mut turns = Vec::<Vec<usize>>::new();
+
+        while rounds.len() <= 5 {
+            match battle.advance() {
+                Step::RoundStarted { round } => {
+                    rounds.push(round);
+                    turns.push(Vec::new());
+                }
+                Step::TurnStarted { character, .. } => {
+                    turns.last_mut().unwrap().push(character);
+                    battle.act(&actions, character, idle, None).unwrap();
+                }
+                Step::Ended { .. } => panic!("Nobody was defeated"),
+            }
+        }
+
+        assert_eq!(rounds, vec![1, 2, 3, 4, 5, 6], "seed {}", seed);
+
+        turns.iter().take(5).for_each(|round| {
+            let mut sorted = round.clone();
+            sorted.sort();
+            assert_eq!(sorted, vec![0, 1, 2], "seed {} gave {:?}", seed, round);
+        });
+    });
+}
+
+#[test]
+fn battles_play_to_an_end() {
+    (0..20).for_each(|seed| {
+        let (actions, mut battle) = small_battle(seed);
+
+        let won = loop {
+            assert!(battle.round() <= 100, "seed {} didn't finish", seed);
+
+            match battle.advance() {
+                Step::TurnStarted { character, .. } => {
+                    if let Some((action, target)) = battle.choose_cpu_action(&actions, AGGRESSIVE) {
+                        battle.act(&actions, character, action, target).unwrap();
+                    }
+                }
+                Step::Ended { won } => break won,
+                Step::RoundStarted { .. } => {}
+            }
+        };
+
+        assert_eq!(battle.winner(), Some(won));
+    });
+}
+
+#[test]
+fn same_seed_plays_out_the_same() {
+    let play = |seed: u64| {
+        let (actions, mut battle) = small_battle(seed);
+        let mut steps = Vec::new();
+
+        loop {
+            let step = battle.advance();
+            steps.push(format!("{:?}", step));
+
+            match step {
+                Step::TurnStarted { character, .. } => {
+                    if let Some((action, target)) = battle.choose_cpu_action(&actions, AGGRESSIVE) {
+                        let acted = battle.act(&actions, character, action, target);
+                        steps.push(format!("{:?}", acted));
+                    }
+                }
+                Step::Ended { .. } => return steps,
+                Step::RoundStarted { .. } => {}
+            }
+        }
+    };
+
+    assert_eq!(play(11), play(11));
+}
+
+#[test]
+fn victory_once_every_enemy_is_defeated() {
+    let (_, mut battle) = small_battle(0);
+    assert_eq!(battle.winner(), None);
+
+    battle.character_mut(1).unwrap().stats.health = 0;
+    assert_eq!(battle.winner(), None);
+
+    battle.character_mut(2).unwrap().stats.health = 0;
+    assert_eq!(battle.winner(), Some(true));
+    assert_eq!(battle.advance(), Step::Ended { won: true });
+}
+
+//====================================================================
+// Actions
+
+#[test]
+fn only_the_current_character_can_act() {
+    let (actions, mut battle) = small_battle(0);
+    let punch = actions.find_action_name("Punch").unwrap();
+
+    let current = next_turn(&mut battle).unwrap();
+    let other = (current + 1) % 3;
+    let target = battle.targets(current, TargetType::Enemy)[0];
+
+    assert_eq!(
+        battle.act(&actions, other, punch, Some(current)),
+        Err(ActionError::NotTheirTurn(other))
+    );
+
+    battle.act(&actions, current, punch, Some(target)).unwrap();
+
+    // Their turn is over once they've acted
+    assert_eq!(
+        battle.act(&actions, current, punch, Some(target)),
+        Err(ActionError::NotTheirTurn(current))
+    );
+}
+
+#[test]
+fn actions_on_cooldown_are_rejected() {
+    let (actions, mut battle) = small_battle(0);
+    let idle = actions.find_action_name("Idle").unwrap();
+    let smite = actions.find_action_name("Smite").unwrap();
+
+    let caster = next_turn(&mut battle).unwrap();
+    let target = battle.targets(caster, TargetType::Enemy)[0];
+    battle.act(&actions, caster, smite, Some(target)).unwrap();
+
+    // Counted down at the start of each of the caster's turns
+    let mut turns = Vec::new();
+    while turns.len() < 4 {
+        let current = next_turn(&mut battle).unwrap();
+
+        if current == caster {
+            turns.push(battle.check_action(&actions, caster, smite, Some(target)));
+        }
+        battle.act(&actions, current, idle, None).unwrap();
+    }
+
+    assert_eq!(
+        turns,
+        vec![
+            Err(ActionError::OnCooldown(3)),
+            Err(ActionError::OnCooldown(2)),
+            Err(ActionError::OnCooldown(1)),
+            Ok(()),
+        ]
+    );
+}
+
+#[test]
+fn actions_need_a_legal_target() {
+    let (actions, mut battle) = small_battle(0);
+    let idle = actions.find_action_name("Idle").unwrap();
+    let punch = actions.find_action_name("Punch").unwrap();
+
+    let caster = next_turn(&mut battle).unwrap();
+    let ally = match caster {
+        0 => 0,
+        _ => 3 - caster,
+    };
+    let opponent = battle.targets(caster, TargetType::Enemy)[0];
+
+    let check = |action, target| battle.check_action(&actions, caster, action, target);
+
+    assert_eq!(check(punch, Some(ally)), Err(ActionError::IllegalTarget));
+    assert_eq!(check(punch, None), Err(ActionError::IllegalTarget));
+    assert_eq!(check(idle, Some(opponent)), Err(ActionError::IllegalTarget));
+    assert_eq!(check(punch, Some(opponent)), Ok(()));
+    assert_eq!(check(idle, None), Ok(()));
+}
+
+#[test]
+fn unknown_actions_are_rejected() {
+    let (actions, mut battle) = small_battle(0);
+    let idle = actions.find_action_name("Idle").unwrap();
+    let punch = actions.find_action_name("Punch").unwrap();
+
+    let caster = next_turn(&mut battle).unwrap();
+    battle.character_mut(caster).unwrap().actions = vec![idle];
+
+    assert_eq!(
+        battle.check_action(&actions, caster, punch, Some(0)),
+        Err(ActionError::NotKnown)
+    );
+
+    let empty = ActionRepo::from_actions(Vec::new());
+    assert_eq!(
+        battle.check_action(&empty, caster, idle, None),
+        Err(ActionError::UnknownAction(idle))
+    );
+}
+
+//====================================================================
+// Targets
+
+#[test]
+fn taunting_opponents_must_be_targeted() {
+    let (_, mut battle) = small_battle(0);
+    battle
+        .character_mut(2)
+        .unwrap()
+        .apply_effect(effect(StatusKind::Taunt, 0, 2));
+
+    assert_eq!(battle.targets(0, TargetType::Enemy), vec![2]);
+}
+
+#[test]
+fn stealthed_opponents_are_hidden_unless_all_are() {
+    let (_, mut battle) = small_battle(0);
+    battle
+        .character_mut(1)
+        .unwrap()
+        .apply_effect(effect(StatusKind::Stealth, 0, 2));
+
+    assert_eq!(battle.targets(0, TargetType::Enemy), vec![2]);
+
+    battle
+        .character_mut(2)
+        .unwrap()
+        .apply_effect(effect(StatusKind::Stealth, 0, 2));
+    assert_eq!(battle.targets(0, TargetType::Enemy), vec![1, 2]);
+}
+
+#[test]
+fn defeated_characters_cant_be_targeted() {
+    let (_, mut battle) = small_battle(0);
+    battle.character_mut(1).unwrap().stats.health = 0;
+
+    assert_eq!(battle.targets(0, TargetType::Enemy), vec![2]);
+    assert_eq!(
+        battle.targets(
+            2,
+            TargetType::Friendly {
+                can_target_caster: false
+            }
+        ),
+        Vec::<usize>::new()
+    );
+}
+
+//====================================================================
+// Turn start
+
+#[test]
+fn poison_defeat_loses_the_turn() {
+    let (actions, mut battle) = small_battle(0);
+    let idle = actions.find_action_name("Idle").unwrap();
+
+    let goblin = battle.character_mut(1).unwrap();
+    goblin.stats.health = 2;
+    goblin.apply_effect(effect(StatusKind::Poison, 5, 3));
+    goblin.apply_effect(effect(StatusKind::Regen, 5, 3));
+
+    let ticks = loop {
+        match battle.advance() {
+            Step::TurnStarted {
+                character: 1,
+                ticks,
+                ..
+            } => break ticks,
+            Step::TurnStarted { character, .. } => {
+                battle.act(&actions, character, idle, None).unwrap();
+            }
+            Step::RoundStarted { .. } => {}
+            Step::Ended { .. } => panic!("Battle ended early"),
+        }
+    };
+
+    assert_eq!(
+        ticks,
+        vec![
+            TurnStartTick::Damaged {
+                kind: StatusKind::Poison,
+                amount: 2
+            },
+            TurnStartTick::Defeated,
+        ]
+    );
+    assert_eq!(battle.current(), None);
+    assert!(battle.character(1).unwrap().effects.is_empty());
+    assert_eq!(
+        battle.act(&actions, 1, idle, None),
+        Err(ActionError::NotTheirTurn(1))
+    );
+}
+
+#[test]
+fn effects_expire_after_their_turns() {
+    let (actions, mut battle) = small_battle(0);
+    let idle = actions.find_action_name("Idle").unwrap();
+
+    battle
+        .character_mut(2)
+        .unwrap()
+        .apply_effect(effect(StatusKind::Taunt, 0, 2));
+
+    let mut brute_turns = Vec::new();
+    while brute_turns.len() < 2 {
+        if let Step::TurnStarted {
+            character, ticks, ..
+        } = battle.advance()
+        {
+            if character == 2 {
+                brute_turns.push(ticks);
+            }
+            battle.act(&actions, character, idle, None).unwrap();
+        }
+    }
+
+    assert_eq!(
+        brute_turns,
+        vec![
+            Vec::new(),
+            vec![TurnStartTick::Expired {
+                kind: StatusKind::Taunt
+            }]
+        ]
+    );
+    assert!(!battle.character(2).unwrap().has_effect(StatusKind::Taunt));
+}
+
+//====================================================================
+// Boss phases
+
+#[test]
+fn boss_phases_fire_once_and_summon_onto_the_boss_side() {
+    let (actions, _) = small_battle(0);
+    let punch = actions.find_action_name("Punch").unwrap();
+
+    let mut battle = Battle::new(
+        Difficulty::Normal,
+        vec![character("Hero", 5, 30, &actions)],
+        vec![Character {
+            stats: CharacterStats {
+                health: 24,
+                ..character("Boss", 5, 40, &actions).stats
+            },
+            ..character("Boss", 5, 40, &actions)
+        }],
+        vec![BossPhase {
+            boss: 1,
+            health_percent: 50,
+            actions: Some(vec![punch]),
+            summon: vec![character("Minion", 3, 10, &actions)],
+            cutscene: vec![String::from("Enough!")],
+        }],
+        0,
+    );
+
+    let mut fired = Vec::new();
+    while battle.round() < 4 {
+        let phases = match battle.advance() {
+            Step::TurnStarted {
+                character, phases, ..
+            } => {
+                let target = battle
+                    .targets(character, TargetType::Enemy)
+                    .first()
+                    .copied();
+                let acted = battle.act(&actions, character, punch, target);
+                [phases, acted.map(|acted| acted.phases).unwrap_or_default()].concat()
+            }
+            Step::Ended { .. } => break,
+            Step::RoundStarted { .. } => Vec::new(),
+        };
+
+        fired.extend(phases);
+    }
+
+    assert_eq!(fired.len(), 1);
+    assert_eq!(fired[0].boss, 1);
+    assert_eq!(fired[0].summoned, vec![2]);
+    assert_eq!(fired[0].cutscene, vec![String::from("Enough!")]);
+
+    assert_eq!(battle.is_friendly(2), Some(false));
+    assert_eq!(battle.character(1).unwrap().actions, vec![punch]);
+}
+
+//====================================================================
+// Cpu
+
+#[test]
+fn lookahead_finishes_off_a_weakened_opponent() {
+    let (actions, mut battle) = small_battle(0);
+    let punch = actions.find_action_name("Punch").unwrap();
+
+    // Hero goes last, and the Brute is left to face them. Only the Hero's
+    // turn is looked at, where they'd heal out of reach of a Punch.
+    let hero = battle.character_mut(0).unwrap();
+    hero.stats.health = 1;
+    hero.stats.speed = 0;
+    battle.character_mut(1).unwrap().stats.health = 0;
+
+    assert_eq!(next_turn(&mut battle), Some(2));
+
+    let profile = CpuProfile {
+        aggressiveness: 0,
+        lookahead: 1,
+    };
+    assert_eq!(
+        battle.choose_cpu_action(&actions, profile),
+        Some((punch, Some(0)))
+    );
+}
+
+//====================================================================
+// Snapshots
+
+#[test]
+fn restored_battles_carry_on_the_same() {
+    let (actions, mut battle) = small_battle(5);
+
+    // Part way through, with rolls already made
+    (0..4).for_each(|_| {
+        if let Some(caster) = next_turn(&mut battle) {
+            let (action, target) = battle.choose_cpu_action(&actions, AGGRESSIVE).unwrap();
+            battle.act(&actions, caster, action, target).unwrap();
+        }
+    });
+
+    let snapshot = BattleSnapshot {
+        next_sequence: 4,
+        battle: battle.clone(),
+    };
+    let bytes = protocol::encode(&snapshot).unwrap();
+    let mut restored = protocol::decode::<BattleSnapshot>(&bytes).unwrap().battle;
+
+    assert_eq!(restored, battle);
+
+    let play = |battle: &mut Battle| {
+        std::iter::from_fn(|| {
+            let caster = next_turn(battle)?;
+            let (action, target) = battle.choose_cpu_action(&actions, AGGRESSIVE)?;
+            Some(battle.act(&actions, caster, action, target).unwrap())
+        })
+        .take(30)
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(play(&mut restored), play(&mut battle));
+}
+
+//====================================================================
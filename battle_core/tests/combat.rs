@@ -0,0 +1,214 @@
+//====================================================================
+
+use std::collections::BTreeMap;
+
+use battle_core::{
+    battle::{Battle, Step},
+    characters::{
+        actions::{Action, ActionRepo, ActionResolution, TargetType},
+        Character, CharacterStats, Equipment,
+    },
+    combat::{self, BattleRng, Difficulty},
+};
+
+//====================================================================
+
+/// Rolls made when counting how often something happens.
+const ROLLS: u32 = 10_000;
+
+// Times out of `ROLLS` seeded rolls that a `chance` percent roll succeeds
+fn successes(chance: u32, seed: u64) -> u32 {
+    let mut rng = BattleRng::from_seed(seed);
+
+    (0..ROLLS)
+        .filter(|_| combat::roll_chance(chance, &mut rng))
+        .count() as u32
+}
+
+// A Punch for 5 that never misses, and one character on each side that
+// knows it, friendly first
+fn duel(friendly_health: u32, enemy_health: u32, seed: u64) -> (ActionRepo, Battle) {
+    let actions = ActionRepo::from_actions(vec![Action {
+        name: "Punch".into(),
+        target: TargetType::Enemy,
+        resolution: ActionResolution::Damage(5),
+        status: None,
+        cooldown: 0,
+        cinematic: None,
+    }]);
+
+    let character = |name: &str, health: u32| Character {
+        name: name.into(),
+        player_controlled: false,
+        stats: CharacterStats {
+            speed: 5,
+            health,
+            max_health: 20,
+            accuracy: 100,
+            evasion: 0,
+            crit_chance: 0,
+            crit_multiplier: 100,
+        },
+        equipment: Equipment::default(),
+        hue_shift: 0,
+        texture: None,
+        actions: actions.actions().into_iter().map(|(id, _)| id).collect(),
+        effects: Vec::new(),
+        cooldowns: BTreeMap::new(),
+        front_facing: true,
+    };
+
+    let battle = Battle::new(
+        Difficulty::Normal,
+        vec![character("Hero", friendly_health)],
+        vec![character("Goblin", enemy_health)],
+        Vec::new(),
+        seed,
+    );
+
+    (actions, battle)
+}
+
+//====================================================================
+
+#[test]
+fn zero_chance_never_happens() {
+    (0..10).for_each(|seed| assert_eq!(successes(0, seed), 0, "seed {}", seed));
+}
+
+#[test]
+fn full_chance_always_happens() {
+    (0..10).for_each(|seed| {
+        assert_eq!(successes(100, seed), ROLLS, "seed {}", seed);
+        assert_eq!(successes(250, seed), ROLLS, "seed {}", seed);
+    });
+}
+
+#[test]
+fn one_percent_chance_happens_rarely() {
+    (0..10).for_each(|seed| {
+        let count = successes(1, seed);
+        assert!((50..=200).contains(&count), "seed {} gave {}", seed, count);
+    });
+
+    let count = successes(99, 0);
+    assert!((ROLLS - 200..ROLLS).contains(&count), "gave {}", count);
+}
+
+#[test]
+fn rolls_repeat_for_the_same_seed() {
+    let mut first = BattleRng::from_seed(42);
+    let mut second = BattleRng::from_seed(42);
+
+    let first = (0..100)
+        .map(|_| combat::roll_chance(50, &mut first))
+        .collect::<Vec<_>>();
+    let second = (0..100)
+        .map(|_| combat::roll_chance(50, &mut second))
+        .collect::<Vec<_>>();
+
+    assert_eq!(first, second);
+}
+
+#[test]
+fn rng_carries_on_after_a_round_trip() {
+    let mut rng = BattleRng::from_seed(9);
+    (0..37).for_each(|_| {
+        combat::roll_chance(50, &mut rng);
+    });
+
+    let bytes = bincode::serialize(&rng).unwrap();
+    let mut restored = bincode::deserialize::<BattleRng>(&bytes).unwrap();
+    assert_eq!(restored, rng);
+
+    (0..100).for_each(|_| {
+        assert_eq!(
+            combat::roll_chance(50, &mut rng),
+            combat::roll_chance(50, &mut restored)
+        )
+    });
+}
+
+//====================================================================
+
+#[test]
+fn hit_chance_is_clamped() {
+    assert_eq!(combat::hit_chance(90, 20), 70);
+    assert_eq!(combat::hit_chance(100, 0), 100);
+
+    // Never certain to miss, or more than certain to hit
+    assert_eq!(combat::hit_chance(0, 0), combat::MIN_HIT_CHANCE);
+    assert_eq!(combat::hit_chance(50, 200), combat::MIN_HIT_CHANCE);
+    assert_eq!(combat::hit_chance(6, 1), combat::MIN_HIT_CHANCE);
+    assert_eq!(combat::hit_chance(250, 10), 100);
+}
+
+#[test]
+fn crit_multiplier_never_weakens_a_hit() {
+    assert_eq!(combat::scale(10, combat::crit_multiplier(0)), 10);
+    assert_eq!(combat::scale(10, combat::crit_multiplier(50)), 10);
+    assert_eq!(combat::scale(10, combat::crit_multiplier(100)), 10);
+    assert_eq!(combat::scale(10, combat::crit_multiplier(150)), 15);
+    assert_eq!(combat::scale(10, combat::crit_multiplier(300)), 30);
+}
+
+#[test]
+fn crits_stack_with_difficulty() {
+    let crit = combat::crit_multiplier(150);
+
+    // 5/4 for the player on easy, then half as much again
+    let player = Difficulty::Easy.damage_multiplier(true) * crit;
+    assert_eq!(combat::scale(8, player), 15);
+
+    // 3/4 for enemies on easy
+    let enemy = Difficulty::Easy.damage_multiplier(false) * crit;
+    assert_eq!(combat::scale(8, enemy), 9);
+
+    assert_eq!(combat::damage(8, player, 6), 6);
+}
+
+//====================================================================
+// Simultaneous defeats
+
+#[test]
+fn both_sides_defeated_together_is_a_victory() {
+    let (_, mut battle) = duel(0, 0, 0);
+
+    assert_eq!(battle.winner(), Some(true));
+    assert_eq!(battle.advance(), Step::Ended { won: true });
+    assert_eq!(battle.current(), None);
+}
+
+// Whoever lands the first blow wins, and the character they defeat never
+// gets the turn they were due later in the round
+#[test]
+fn counter_kill_ends_the_battle_before_the_defeated_act() {
+    let firsts = (0..20)
+        .map(|seed| {
+            let (actions, mut battle) = duel(5, 5, seed);
+            let punch = actions.find_action_name("Punch").unwrap();
+
+            assert_eq!(battle.advance(), Step::RoundStarted { round: 1 });
+
+            let first = match battle.advance() {
+                Step::TurnStarted { character, .. } => character,
+                step => panic!("Expected a turn, got {:?}", step),
+            };
+            let other = 1 - first;
+
+            let acted = battle.act(&actions, first, punch, Some(other)).unwrap();
+            assert_eq!(acted.resolved.damage, 5);
+
+            assert_eq!(battle.advance(), Step::Ended { won: first == 0 });
+            assert!(battle.character(other).unwrap().stats.is_defeated());
+            assert!(battle.act(&actions, other, punch, Some(first)).is_err());
+
+            first
+        })
+        .collect::<Vec<_>>();
+
+    // Both sides got the first blow in for some seed
+    assert!(firsts.contains(&0) && firsts.contains(&1));
+}
+
+//====================================================================
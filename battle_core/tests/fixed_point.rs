@@ -0,0 +1,79 @@
+//====================================================================
+
+use battle_core::combat::{self, Fixed};
+use proptest::prelude::*;
+
+//====================================================================
+
+#[test]
+fn whole_numbers_convert_exactly() {
+    assert_eq!(Fixed::from_int(0), Fixed::ZERO);
+    assert_eq!(Fixed::from_int(1), Fixed::ONE);
+    assert_eq!(Fixed::from_int(u16::MAX).floor(), u16::MAX as u32);
+
+    assert_eq!(
+        Fixed::checked_from_int(u16::MAX as u32),
+        Some(Fixed::from_int(u16::MAX))
+    );
+    assert_eq!(Fixed::checked_from_int(u16::MAX as u32 + 1), None);
+}
+
+#[test]
+fn ratios_round_halves_up() {
+    assert_eq!(Fixed::from_ratio(1, 2).to_bits(), Fixed::ONE.to_bits() / 2);
+    assert_eq!(Fixed::from_ratio(1, 2).round(), 1);
+    assert_eq!(Fixed::from_ratio(1, 3).round(), 0);
+    assert_eq!(Fixed::from_ratio(5, 2).round(), 3);
+    assert_eq!(Fixed::from_ratio(7, 0), Fixed::ZERO);
+}
+
+#[test]
+fn arithmetic_saturates() {
+    let max = Fixed::from_int(u16::MAX);
+
+    assert_eq!(Fixed::ZERO - Fixed::ONE, Fixed::ZERO);
+    assert_eq!((max + max).to_bits(), u32::MAX);
+    assert_eq!((max * max).to_bits(), u32::MAX);
+}
+
+#[test]
+fn scale_takes_amounts_too_large_to_convert() {
+    let half = combat::multiplier(1, 2);
+
+    assert_eq!(combat::scale(100_000, half), 50_000);
+    assert_eq!(combat::scale(u32::MAX, combat::multiplier(2, 1)), u32::MAX);
+    assert_eq!(combat::scale(u32::MAX, Fixed::ONE), u32::MAX);
+}
+
+#[test]
+fn damage_is_capped_at_remaining_health() {
+    let double = combat::multiplier(2, 1);
+
+    assert_eq!(combat::damage(10, double, 100), 20);
+    assert_eq!(combat::damage(10, double, 15), 15);
+    assert_eq!(combat::damage(10, Fixed::ZERO, 15), 0);
+}
+
+//====================================================================
+
+proptest! {
+    #[test]
+    fn scale_matches_exact_rounding(
+        amount in any::<u16>(),
+        numerator in 0..1000_u32,
+        denominator in 1..1000_u32,
+    ) {
+        let multiplier = combat::multiplier(numerator, denominator);
+
+        // Same result as exact integer math on the multiplier's bits
+        let exact = (amount as u64 * multiplier.to_bits() as u64 + (1 << 15)) >> 16;
+        prop_assert_eq!(combat::scale(amount as u32, multiplier) as u64, exact);
+
+        // Going through a Fixed only has room for results up to u16::MAX
+        if multiplier <= Fixed::ONE {
+            prop_assert_eq!((Fixed::from_int(amount) * multiplier).round() as u64, exact);
+        }
+    }
+}
+
+//====================================================================
@@ -0,0 +1,158 @@
+//====================================================================
+
+use battle_core::combat;
+use proptest::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+
+//====================================================================
+
+/// Rolls averaged over when checking positions.
+const ROLLS: u64 = 300;
+
+// Sum of each character's position over `ROLLS` seeded rolls
+fn position_totals(speeds: &[(u32, usize)]) -> Vec<u64> {
+    let mut totals = vec![0; speeds.len()];
+
+    (0..ROLLS).for_each(|seed| {
+        combat::roll_turn_order(speeds, &mut StdRng::seed_from_u64(seed))
+            .into_iter()
+            .enumerate()
+            .for_each(|(position, id)| totals[id] += position as u64);
+    });
+
+    totals
+}
+
+fn assert_everyone_once(speeds: &[(u32, usize)], order: &[usize]) {
+    let mut sorted = order.to_vec();
+    sorted.sort();
+
+    assert_eq!(sorted, (0..speeds.len()).collect::<Vec<_>>());
+}
+
+//====================================================================
+
+proptest! {
+    #[test]
+    fn everyone_appears_exactly_once(
+        speeds in prop::collection::vec(0..50_u32, 0..64),
+        seed in any::<u64>(),
+    ) {
+        let speeds = speeds.into_iter().enumerate().map(|(id, speed)| (speed, id)).collect::<Vec<_>>();
+        let order = combat::roll_turn_order(&speeds, &mut StdRng::seed_from_u64(seed));
+
+        let mut sorted = order.clone();
+        sorted.sort();
+        prop_assert_eq!(sorted, (0..speeds.len()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn zero_speed_goes_last(
+        speeds in prop::collection::vec(1..50_u32, 1..16),
+        slow in 1..8_usize,
+        seed in any::<u64>(),
+    ) {
+        let fast = speeds.len();
+        let speeds = speeds
+            .into_iter()
+            .map(Some)
+            .chain(std::iter::repeat_n(None, slow))
+            .enumerate()
+            .map(|(id, speed)| (speed.unwrap_or(0), id))
+            .collect::<Vec<_>>();
+
+        let order = combat::roll_turn_order(&speeds, &mut StdRng::seed_from_u64(seed));
+
+        // Nobody with speed can be picked after the rest have none
+        prop_assert!(order[..fast].iter().all(|id| *id < fast));
+        prop_assert_eq!(&order[fast..], &(fast..fast + slow).collect::<Vec<_>>()[..]);
+    }
+
+    #[test]
+    fn higher_speed_acts_earlier_on_average(
+        speeds in prop::collection::vec(1..20_u32, 2..8),
+    ) {
+        let fastest = (0..speeds.len()).max_by_key(|id| speeds[*id]).unwrap();
+        let slowest = (0..speeds.len()).min_by_key(|id| speeds[*id]).unwrap();
+        prop_assume!(speeds[fastest] >= speeds[slowest] * 3);
+
+        let speeds = speeds.into_iter().enumerate().map(|(id, speed)| (speed, id)).collect::<Vec<_>>();
+        let totals = position_totals(&speeds);
+
+        prop_assert!(
+            totals[fastest] < totals[slowest],
+            "fastest {:?} slowest {:?}",
+            totals[fastest],
+            totals[slowest]
+        );
+    }
+}
+
+//====================================================================
+
+#[test]
+fn no_characters() {
+    let order = combat::roll_turn_order::<usize>(&[], &mut StdRng::seed_from_u64(0));
+    assert!(order.is_empty());
+}
+
+#[test]
+fn single_character() {
+    [0, 1, u32::MAX].into_iter().for_each(|speed| {
+        let order = combat::roll_turn_order(&[(speed, 7)], &mut StdRng::seed_from_u64(0));
+        assert_eq!(order, vec![7]);
+    });
+}
+
+#[test]
+fn everyone_at_zero_speed_keeps_their_order() {
+    let speeds = (0..10).map(|id| (0, id)).collect::<Vec<_>>();
+    let order = combat::roll_turn_order(&speeds, &mut StdRng::seed_from_u64(0));
+
+    assert_eq!(order, (0..10).collect::<Vec<_>>());
+}
+
+// Speeds summing past u32::MAX used to overflow the total weight
+#[test]
+fn speeds_summing_past_u32() {
+    let speeds = [(u32::MAX, 0), (u32::MAX, 1), (1, 2), (u32::MAX / 2, 3)];
+
+    (0..50).for_each(|seed| {
+        let order = combat::roll_turn_order(&speeds, &mut StdRng::seed_from_u64(seed));
+        assert_everyone_once(&speeds, &order);
+    });
+}
+
+#[test]
+fn thousand_characters() {
+    let speeds = (0..1000).map(|id| (id as u32 % 25, id)).collect::<Vec<_>>();
+
+    (0..10).for_each(|seed| {
+        let order = combat::roll_turn_order(&speeds, &mut StdRng::seed_from_u64(seed));
+        assert_everyone_once(&speeds, &order);
+    });
+}
+
+//====================================================================
+
+#[test]
+fn turn_order_is_seeded() {
+    let speeds = (0..20).map(|id| (1 + id % 7, id)).collect::<Vec<_>>();
+
+    let first = combat::roll_turn_order(&speeds, &mut StdRng::seed_from_u64(7));
+    let second = combat::roll_turn_order(&speeds, &mut StdRng::seed_from_u64(7));
+
+    assert_eq!(first, second);
+}
+
+#[test]
+fn predicted_order_is_fastest_first_and_stable() {
+    let speeds = [(3, 'a'), (5, 'b'), (3, 'c'), (8, 'd')];
+
+    assert_eq!(
+        combat::predict_turn_order(&speeds),
+        vec!['d', 'b', 'a', 'c']
+    );
+}
+
+//====================================================================
@@ -0,0 +1,43 @@
+//====================================================================
+
+use common::Transform;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+//====================================================================
+
+fn transform_math(c: &mut Criterion) {
+    let transform = Transform::from_scale_rotation_translation(
+        (2., 2., 2.),
+        glam::Quat::from_rotation_y(0.5),
+        (10., 4., -3.),
+    );
+
+    c.bench_function("transform_to_matrix", |b| {
+        b.iter(|| black_box(&transform).to_matrix())
+    });
+
+    c.bench_function("transform_look_at", |b| {
+        b.iter(|| {
+            let mut transform = black_box(transform.clone());
+            transform.look_at(glam::vec3(0., 1., 0.), glam::Vec3::Y);
+            transform
+        })
+    });
+
+    let target = Transform::from_translation((5., 5., 5.));
+
+    c.bench_function("transform_lerp", |b| {
+        b.iter(|| {
+            let mut transform = black_box(transform.clone());
+            transform.lerp(&target, 0.5);
+            transform
+        })
+    });
+}
+
+//====================================================================
+
+criterion_group!(benches, transform_math);
+criterion_main!(benches);
+
+//====================================================================
@@ -1,10 +1,16 @@
 //====================================================================
 
-use std::fmt::Display;
+use std::{
+    fmt::Display,
+    ops::{Add, Div, Mul, Sub},
+};
 
 //====================================================================
 
+/// Size without any units attached, such as a texture atlas or a virtual
+/// resolution. Window sizes use [`PhysicalSize`] and [`LogicalSize`].
 #[derive(Clone, Copy, Debug, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Size<T> {
     pub width: T,
     pub height: T,
@@ -42,9 +48,167 @@ impl<T: Display> Display for Size<T> {
     }
 }
 
+//--------------------------------------------------
+
+// Shared by the physical and logical sizes, which only differ in what their
+// units mean
+macro_rules! tagged_size {
+    ($(#[$meta: meta])* $name: ident) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, Default, Hash, PartialEq)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        pub struct $name<T> {
+            pub width: T,
+            pub height: T,
+        }
+
+        impl<T> $name<T> {
+            #[inline]
+            pub fn new(width: T, height: T) -> Self {
+                Self { width, height }
+            }
+        }
+
+        impl<T> From<(T, T)> for $name<T> {
+            #[inline]
+            fn from(value: (T, T)) -> Self {
+                Self::new(value.0, value.1)
+            }
+        }
+
+        impl<T> From<$name<T>> for (T, T) {
+            #[inline]
+            fn from(value: $name<T>) -> Self {
+                (value.width, value.height)
+            }
+        }
+
+        impl<T> From<$name<T>> for Size<T> {
+            #[inline]
+            fn from(value: $name<T>) -> Self {
+                Size::new(value.width, value.height)
+            }
+        }
+
+        impl<T: Display> Display for $name<T> {
+            #[inline]
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "({}, {})", self.width, self.height)
+            }
+        }
+
+        impl<T: Add<Output = T>> Add for $name<T> {
+            type Output = Self;
+
+            #[inline]
+            fn add(self, rhs: Self) -> Self {
+                Self::new(self.width + rhs.width, self.height + rhs.height)
+            }
+        }
+
+        impl<T: Sub<Output = T>> Sub for $name<T> {
+            type Output = Self;
+
+            #[inline]
+            fn sub(self, rhs: Self) -> Self {
+                Self::new(self.width - rhs.width, self.height - rhs.height)
+            }
+        }
+
+        impl<T: Mul<Output = T> + Copy> Mul<T> for $name<T> {
+            type Output = Self;
+
+            #[inline]
+            fn mul(self, rhs: T) -> Self {
+                Self::new(self.width * rhs, self.height * rhs)
+            }
+        }
+
+        impl<T: Div<Output = T> + Copy> Div<T> for $name<T> {
+            type Output = Self;
+
+            #[inline]
+            fn div(self, rhs: T) -> Self {
+                Self::new(self.width / rhs, self.height / rhs)
+            }
+        }
+    };
+}
+
+tagged_size!(
+    /// Size in device pixels, as windows and surfaces are measured.
+    PhysicalSize
+);
+tagged_size!(
+    /// Size in pixels before the display scale factor is applied.
+    LogicalSize
+);
+
+impl PhysicalSize<u32> {
+    /// Size in logical pixels on a display with `scale_factor` device pixels
+    /// to each logical one.
+    #[inline]
+    pub fn to_logical(self, scale_factor: f64) -> LogicalSize<f32> {
+        LogicalSize::new(
+            (self.width as f64 / scale_factor) as f32,
+            (self.height as f64 / scale_factor) as f32,
+        )
+    }
+
+    #[inline]
+    pub fn aspect(&self) -> f32 {
+        self.width as f32 / self.height.max(1) as f32
+    }
+}
+
+impl LogicalSize<f32> {
+    /// Size in device pixels, rounded to the nearest whole pixel.
+    #[inline]
+    pub fn to_physical(self, scale_factor: f64) -> PhysicalSize<u32> {
+        PhysicalSize::new(
+            (self.width as f64 * scale_factor).round() as u32,
+            (self.height as f64 * scale_factor).round() as u32,
+        )
+    }
+}
+
+#[cfg(feature = "winit")]
+mod winit_sizes {
+    use super::{LogicalSize, PhysicalSize};
+
+    impl<T> From<winit::dpi::PhysicalSize<T>> for PhysicalSize<T> {
+        #[inline]
+        fn from(value: winit::dpi::PhysicalSize<T>) -> Self {
+            Self::new(value.width, value.height)
+        }
+    }
+
+    impl<T> From<PhysicalSize<T>> for winit::dpi::PhysicalSize<T> {
+        #[inline]
+        fn from(value: PhysicalSize<T>) -> Self {
+            Self::new(value.width, value.height)
+        }
+    }
+
+    impl<T> From<winit::dpi::LogicalSize<T>> for LogicalSize<T> {
+        #[inline]
+        fn from(value: winit::dpi::LogicalSize<T>) -> Self {
+            Self::new(value.width, value.height)
+        }
+    }
+
+    impl<T> From<LogicalSize<T>> for winit::dpi::LogicalSize<T> {
+        #[inline]
+        fn from(value: LogicalSize<T>) -> Self {
+            Self::new(value.width, value.height)
+        }
+    }
+}
+
 //====================================================================
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Transform {
     pub translation: glam::Vec3,
     pub rotation: glam::Quat,
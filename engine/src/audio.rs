@@ -0,0 +1,266 @@
+//====================================================================
+
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    error::AudioError,
+    music::{AudioClip, LayerMix, LayeredMusic},
+};
+
+//====================================================================
+
+/// Format mixed in when there's no output device to take it from.
+const FALLBACK_CHANNELS: u16 = 2;
+const FALLBACK_SAMPLE_RATE: u32 = 48_000;
+
+//====================================================================
+
+/// A sound effect part way through playing.
+struct Voice {
+    clip: AudioClip,
+    volume: f32,
+    /// Frame of the clip to play next, between frames when the clip's sample
+    /// rate differs from the output's.
+    position: f64,
+    /// Clip frames to move on each output frame.
+    step: f64,
+}
+
+struct Mixer {
+    channels: u16,
+    sample_rate: u32,
+
+    voices: Vec<Voice>,
+
+    music: Option<LayeredMusic>,
+    /// Last frame pulled from the music, at the music's own channel count.
+    music_frame: Vec<f32>,
+    /// Music frames owed to the output. Another is pulled whenever it reaches 1.
+    music_phase: f64,
+}
+
+impl Mixer {
+    fn new(channels: u16, sample_rate: u32) -> Self {
+        Self {
+            channels: channels.max(1),
+            sample_rate,
+            voices: Vec::new(),
+            music: None,
+            music_frame: Vec::new(),
+            music_phase: 1.,
+        }
+    }
+
+    fn set_music(&mut self, music: Option<LayeredMusic>) {
+        self.music_frame = vec![0.; music.as_ref().map_or(0, |music| music.channels() as usize)];
+        self.music_phase = 1.;
+        self.music = music;
+    }
+
+    // Fill `out` with the next frames, interleaved with the output's channel
+    // count. Clips with fewer channels repeat their last one into the rest.
+    fn render(&mut self, out: &mut [f32]) {
+        out.fill(0.);
+
+        let channels = self.channels as usize;
+
+        out.chunks_exact_mut(channels).for_each(|frame| {
+            self.voices.iter_mut().for_each(|voice| {
+                if let Some(samples) = voice.clip.frame(voice.position as usize) {
+                    frame.iter_mut().enumerate().for_each(|(channel, out)| {
+                        *out += samples[channel.min(samples.len() - 1)] * voice.volume;
+                    });
+                }
+                voice.position += voice.step;
+            });
+
+            if let Some(music) = &mut self.music {
+                while self.music_phase >= 1. {
+                    self.music_frame.fill(0.);
+                    music.mix(&mut self.music_frame);
+                    self.music_phase -= 1.;
+                }
+                self.music_phase += music.sample_rate() as f64 / self.sample_rate as f64;
+
+                let samples = &self.music_frame;
+                frame.iter_mut().enumerate().for_each(|(channel, out)| {
+                    *out += samples[channel.min(samples.len() - 1)];
+                });
+            }
+        });
+
+        self.voices
+            .retain(|voice| (voice.position as u64) < voice.clip.frames());
+    }
+}
+
+//====================================================================
+
+/// Plays sound effects and [`LayeredMusic`] on the default output device.
+/// Everything is mixed on the device's own thread as it asks for samples.
+pub struct Audio {
+    mixer: Arc<Mutex<Mixer>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    _stream: Option<cpal::Stream>,
+}
+
+impl Audio {
+    /// Audio that isn't played anywhere, only mixed when [`Audio::render`] is
+    /// called. Used when there's no output device or audio is turned off.
+    pub fn without_device(channels: u16, sample_rate: u32) -> Self {
+        Self {
+            mixer: Arc::new(Mutex::new(Mixer::new(channels, sample_rate))),
+            #[cfg(not(target_arch = "wasm32"))]
+            _stream: None,
+        }
+    }
+
+    /// Start playing on the default output device, in its preferred format.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open() -> Result<Self, AudioError> {
+        use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or(AudioError::NoDevice)?;
+
+        let supported = device
+            .default_output_config()
+            .map_err(|e| AudioError::Output(e.to_string()))?;
+        let config = supported.config();
+
+        let mixer = Arc::new(Mutex::new(Mixer::new(
+            config.channels,
+            config.sample_rate.0,
+        )));
+
+        let stream = match supported.sample_format() {
+            cpal::SampleFormat::F32 => build_stream::<f32>(&device, &config, mixer.clone()),
+            cpal::SampleFormat::I16 => build_stream::<i16>(&device, &config, mixer.clone()),
+            cpal::SampleFormat::U16 => build_stream::<u16>(&device, &config, mixer.clone()),
+            format => {
+                return Err(AudioError::Output(format!(
+                    "unsupported sample format {}",
+                    format
+                )))
+            }
+        }
+        .map_err(|e| AudioError::Output(e.to_string()))?;
+
+        stream
+            .play()
+            .map_err(|e| AudioError::Output(e.to_string()))?;
+
+        log::info!(
+            "Playing audio on '{}' - {} channels at {}Hz",
+            device.name().unwrap_or_default(),
+            config.channels,
+            config.sample_rate.0
+        );
+
+        Ok(Self {
+            mixer,
+            _stream: Some(stream),
+        })
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn open() -> Result<Self, AudioError> {
+        Err(AudioError::Unsupported)
+    }
+
+    /// [`Audio::open`], or carry on without sound if that fails.
+    pub fn open_or_silent() -> Self {
+        Self::open().unwrap_or_else(|e| {
+            log::warn!("Playing without sound: {}", e);
+            Self::silent()
+        })
+    }
+
+    /// [`Audio::without_device`] in a common output format.
+    #[inline]
+    pub fn silent() -> Self {
+        Self::without_device(FALLBACK_CHANNELS, FALLBACK_SAMPLE_RATE)
+    }
+
+    // A poisoned lock only means a panic part way through mixing, so carry on
+    fn mixer(&self) -> std::sync::MutexGuard<'_, Mixer> {
+        self.mixer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Play `clip` once from the start, alongside anything already playing.
+    pub fn play(&self, clip: &AudioClip, volume: f32) {
+        let mut mixer = self.mixer();
+        let step = clip.sample_rate() as f64 / mixer.sample_rate as f64;
+
+        mixer.voices.push(Voice {
+            clip: clip.clone(),
+            volume: volume.max(0.),
+            position: 0.,
+            step,
+        });
+    }
+
+    /// Replace the music, or stop it with None. New music starts silent until
+    /// it's given a mix with [`Audio::set_music_mix`].
+    #[inline]
+    pub fn set_music(&self, music: Option<LayeredMusic>) {
+        self.mixer().set_music(music);
+    }
+
+    /// Crossfade the music's layers towards `mix`.
+    pub fn set_music_mix(&self, mix: LayerMix) {
+        if let Some(music) = &mut self.mixer().music {
+            music.set_mix(mix);
+        }
+    }
+
+    #[inline]
+    pub fn has_music(&self) -> bool {
+        self.mixer().music.is_some()
+    }
+
+    /// Mix the next frames into `out`, interleaved with the output's channel
+    /// count. The output device does this itself, so it's only for audio
+    /// [`Audio::without_device`].
+    #[inline]
+    pub fn render(&self, out: &mut [f32]) {
+        self.mixer().render(out);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mixer: Arc<Mutex<Mixer>>,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: cpal::SizedSample + cpal::FromSample<f32>,
+{
+    use cpal::traits::DeviceTrait;
+
+    let mut buffer = Vec::new();
+
+    device.build_output_stream(
+        config,
+        move |out: &mut [T], _| {
+            buffer.resize(out.len(), 0.);
+
+            mixer
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .render(&mut buffer);
+
+            out.iter_mut()
+                .zip(&buffer)
+                .for_each(|(out, sample)| *out = T::from_sample(*sample));
+        },
+        |e| log::error!("Audio output error: {}", e),
+        None,
+    )
+}
+
+//====================================================================
@@ -0,0 +1,122 @@
+//====================================================================
+
+use std::time::Duration;
+
+use renderer::RendererConfig;
+
+use crate::{error::EngineError, scene::Scene, window::Runner, StateInner};
+
+//====================================================================
+
+/// Frames per second the engine aims for unless told otherwise.
+const DEFAULT_FPS: f32 = 75.;
+
+type Setup = Box<dyn FnOnce(&mut StateInner)>;
+
+/// Optional parts of the engine the game can turn off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EngineFeatures {
+    /// Debug console and hotkeys for dumping the world, diagnostics and render
+    /// views, and the log viewer. Off by default in release builds.
+    pub debug_tools: bool,
+    /// Sound output. Sounds are still mixed while it's off, just never played.
+    pub audio: bool,
+    /// Anything that talks to other processes or machines, such as rich
+    /// presence. Checked by the game before starting any of it.
+    pub networking: bool,
+}
+
+impl Default for EngineFeatures {
+    fn default() -> Self {
+        Self {
+            debug_tools: cfg!(debug_assertions),
+            audio: true,
+            networking: true,
+        }
+    }
+}
+
+//====================================================================
+
+/// Configures the engine and sets up the state before the first scene is
+/// created.
+pub struct EngineBuilder {
+    pub(crate) target_fps: Duration,
+    pub(crate) renderer: RendererConfig,
+    pub(crate) features: EngineFeatures,
+    pub(crate) setup: Vec<Setup>,
+}
+
+impl Default for EngineBuilder {
+    fn default() -> Self {
+        Self {
+            target_fps: Duration::from_secs_f32(1. / DEFAULT_FPS),
+            renderer: RendererConfig::default(),
+            features: EngineFeatures::default(),
+            setup: Vec::new(),
+        }
+    }
+}
+
+impl EngineBuilder {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Frames per second to aim for. Values of zero or less are ignored.
+    pub fn with_target_fps(mut self, fps: f32) -> Self {
+        match fps > 0. {
+            true => self.target_fps = Duration::from_secs_f32(1. / fps),
+            false => log::warn!("Ignoring invalid target fps {}", fps),
+        }
+        self
+    }
+
+    #[inline]
+    pub fn with_renderer_config(mut self, config: RendererConfig) -> Self {
+        self.renderer = config;
+        self
+    }
+
+    #[inline]
+    pub fn with_features(mut self, features: EngineFeatures) -> Self {
+        self.features = features;
+        self
+    }
+
+    #[inline]
+    pub fn with_debug_tools(mut self, enabled: bool) -> Self {
+        self.features.debug_tools = enabled;
+        self
+    }
+
+    #[inline]
+    pub fn with_audio(mut self, enabled: bool) -> Self {
+        self.features.audio = enabled;
+        self
+    }
+
+    #[inline]
+    pub fn with_networking(mut self, enabled: bool) -> Self {
+        self.features.networking = enabled;
+        self
+    }
+
+    /// Run `setup` once the state exists, before the first scene is created.
+    /// Used to register components and prefabs or subscribe to events.
+    /// Setups run in the order they were added.
+    pub fn with_setup(mut self, setup: impl FnOnce(&mut StateInner) + 'static) -> Self {
+        self.setup.push(Box::new(setup));
+        self
+    }
+
+    /// Run the app with `S` as the first scene until it exits. See
+    /// [`Runner::run`].
+    #[inline]
+    pub fn run<S: Scene>(self) -> Result<(), EngineError> {
+        Runner::<S>::run_with(self)
+    }
+}
+
+//====================================================================
@@ -0,0 +1,352 @@
+//====================================================================
+
+use std::collections::BTreeMap;
+
+use common::Transform;
+use hecs::Entity;
+use renderer::pipelines::ui3d_pipeline::Ui3d;
+use winit::{
+    event::KeyEvent,
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::{debug, StateInner};
+
+//====================================================================
+
+/// Key that opens or closes the debug console.
+pub const CONSOLE_KEY: KeyCode = KeyCode::Backquote;
+
+/// Runs a command with the words typed after its name, returning what to
+/// print back or why it failed.
+pub type CommandFn = fn(&mut StateInner, &[&str]) -> Result<String, String>;
+
+struct Command {
+    help: &'static str,
+    run: CommandFn,
+}
+
+/// Typed debug commands, shown in the world like the log viewer. Only takes
+/// input while [`EngineFeatures::debug_tools`](crate::builder::EngineFeatures)
+/// is enabled.
+pub struct Console {
+    /// Number of output lines shown at once.
+    pub lines: usize,
+
+    commands: BTreeMap<&'static str, Command>,
+    open: bool,
+    input: String,
+    output: Vec<String>,
+    /// Lines entered since the last tick, run in order.
+    submitted: Vec<String>,
+
+    entity: Option<Entity>,
+    shown: Vec<String>,
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        let mut console = Self {
+            lines: 16,
+            commands: BTreeMap::new(),
+            open: false,
+            input: String::new(),
+            output: Vec::new(),
+            submitted: Vec::new(),
+            entity: None,
+            shown: Vec::new(),
+        };
+
+        console.register("help", "List every command", help);
+        console.register(
+            "dump_world",
+            "Write every entity and its components to a file [path], as json for .json paths",
+            dump_world,
+        );
+        console.register(
+            "components",
+            "List the components that can be added by name",
+            components,
+        );
+        console.register(
+            "add_component",
+            "Add a component's default to an entity <entity id> <component>",
+            add_component,
+        );
+        console.register(
+            "diagnostics",
+            "Show the renderer diagnostics report",
+            diagnostics,
+        );
+        console.register(
+            "render_view",
+            "Switch debug view [none, wireframe, overdraw, depth]",
+            render_view,
+        );
+        console.register(
+            "capture_frame",
+            "Record the next frame's render commands",
+            capture_frame,
+        );
+        console.register(
+            "logs",
+            "Filter the log viewer [level] [module], 'all' for every module",
+            logs,
+        );
+
+        console
+    }
+}
+
+impl Console {
+    /// Add a command, replacing any already registered with the same name.
+    pub fn register(&mut self, name: &'static str, help: &'static str, run: CommandFn) {
+        if self.commands.insert(name, Command { help, run }).is_some() {
+            log::warn!("Console command '{}' registered twice - replacing", name);
+        }
+    }
+
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Names of every registered command, in alphabetical order.
+    #[inline]
+    pub fn commands(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.commands.keys().copied()
+    }
+
+    fn print(&mut self, text: &str) {
+        self.output.extend(text.lines().map(String::from));
+
+        let excess = self.output.len().saturating_sub(self.lines);
+        self.output.drain(..excess);
+    }
+
+    fn contents(&self) -> Vec<String> {
+        std::iter::once(String::from("Console"))
+            .chain(self.output.iter().cloned())
+            .chain(std::iter::once(format!("> {}_", self.input)))
+            .collect()
+    }
+}
+
+//====================================================================
+
+/// Run a line of console input as if it had been typed.
+pub fn execute(state: &mut StateInner, line: &str) -> Result<String, String> {
+    let words = line.split_whitespace().collect::<Vec<_>>();
+
+    let (name, args) = match words.split_first() {
+        Some(split) => split,
+        None => return Ok(String::new()),
+    };
+
+    // Copied out so the command can borrow the console too
+    let run = match state.console.commands.get(name) {
+        Some(command) => command.run,
+        None => return Err(format!("Unknown command '{}' - try 'help'", name)),
+    };
+
+    run(state, args)
+}
+
+// Typing goes to the console while it's open rather than the game. Returns
+// whether the key was used. Releases are always passed on so nothing is left
+// held down.
+pub(crate) fn process_key(state: &mut StateInner, event: &KeyEvent) -> bool {
+    if !event.state.is_pressed() {
+        return false;
+    }
+
+    let console = &mut state.console;
+
+    match (event.physical_key, console.open) {
+        (PhysicalKey::Code(CONSOLE_KEY), _) => console.open = !console.open,
+        (_, false) => return false,
+
+        (PhysicalKey::Code(KeyCode::Escape), true) => console.open = false,
+        (PhysicalKey::Code(KeyCode::Enter | KeyCode::NumpadEnter), true) => {
+            let line = std::mem::take(&mut console.input);
+            console.submitted.push(line);
+        }
+        (PhysicalKey::Code(KeyCode::Backspace), true) => {
+            console.input.pop();
+        }
+        (_, true) => {
+            if let Some(text) = &event.text {
+                console
+                    .input
+                    .extend(text.chars().filter(|c| !c.is_control()));
+            }
+        }
+    }
+
+    true
+}
+
+pub(crate) fn tick_console(state: &mut StateInner) {
+    std::mem::take(&mut state.console.submitted)
+        .into_iter()
+        .for_each(|line| {
+            state.console.print(&format!("> {}", line));
+
+            match execute(state, &line) {
+                Ok(output) => state.console.print(&output),
+                Err(e) => state.console.print(&format!("Error: {}", e)),
+            }
+        });
+
+    match (state.console.open, state.console.entity) {
+        (true, None) => {
+            let camera = &state.renderer.camera.camera;
+            let position = camera.translation + camera.forward() * 6.;
+
+            state.console.entity = Some(
+                state
+                    .world
+                    .spawn((Transform::from_translation(position), Ui3d::default())),
+            );
+        }
+        (false, Some(entity)) => {
+            state.despawn(entity).ok();
+            state.console.entity = None;
+            state.console.shown.clear();
+            return;
+        }
+        (false, None) => return,
+        (true, Some(_)) => {}
+    }
+
+    let entity = match state.console.entity {
+        Some(entity) => entity,
+        None => return,
+    };
+
+    // Only reshape the text when something changed
+    let contents = state.console.contents();
+    if contents == state.console.shown {
+        return;
+    }
+
+    match state.world.get::<&mut Ui3d>(entity) {
+        Ok(mut ui) => ui.options = contents.clone(),
+        Err(_) => {
+            log::warn!("Console entity removed - closing console");
+            state.console.entity = None;
+            state.console.open = false;
+            return;
+        }
+    }
+
+    state.console.shown = contents;
+}
+
+//====================================================================
+
+fn help(state: &mut StateInner, _: &[&str]) -> Result<String, String> {
+    Ok(state
+        .console
+        .commands
+        .iter()
+        .map(|(name, command)| format!("{:<14} {}", name, command.help))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn dump_world(state: &mut StateInner, args: &[&str]) -> Result<String, String> {
+    let path = args.first().copied().unwrap_or(debug::DUMP_FILE);
+    debug::save_world_dump(&state.world, &state.registry, path)
+}
+
+fn components(state: &mut StateInner, _: &[&str]) -> Result<String, String> {
+    Ok(state.registry.persistent_names().join(", "))
+}
+
+fn add_component(state: &mut StateInner, args: &[&str]) -> Result<String, String> {
+    let (id, name) = match args {
+        [id, name] => (*id, *name),
+        _ => return Err(String::from("Usage: add_component <entity id> <component>")),
+    };
+
+    let id = id
+        .parse::<u32>()
+        .map_err(|_| format!("Invalid entity id '{}'", id))?;
+
+    let entity = state
+        .world
+        .iter()
+        .map(|entity| entity.entity())
+        .find(|entity| entity.id() == id)
+        .ok_or_else(|| format!("No entity with id {}", id))?;
+
+    let added = state
+        .registry
+        .insert_default(&mut state.world, entity, name);
+
+    match added {
+        true => Ok(format!("Added {} to {:?}", name, entity)),
+        false => Err(format!(
+            "Unknown component '{}', expected one of: {}",
+            name,
+            state.registry.persistent_names().join(", ")
+        )),
+    }
+}
+
+fn diagnostics(state: &mut StateInner, _: &[&str]) -> Result<String, String> {
+    Ok(format!(
+        "Renderer diagnostics:\n{}",
+        state.renderer.diagnostics()
+    ))
+}
+
+fn render_view(state: &mut StateInner, args: &[&str]) -> Result<String, String> {
+    let debug_view = match args.first() {
+        Some(name) => {
+            let debug_view = debug::parse_debug_view(name)
+                .ok_or_else(|| format!("Unknown debug view '{}'", name))?;
+
+            if !state.renderer.set_debug_view(debug_view) {
+                return Err(format!(
+                    "Debug view {:?} isn't supported on this device",
+                    debug_view
+                ));
+            }
+            debug_view
+        }
+        None => debug::cycle_debug_view(&mut state.renderer),
+    };
+
+    Ok(format!("Render debug view: {:?}", debug_view))
+}
+
+fn capture_frame(state: &mut StateInner, _: &[&str]) -> Result<String, String> {
+    state.renderer.capture_next_frame();
+    Ok(String::from("Capturing next frame"))
+}
+
+fn logs(state: &mut StateInner, args: &[&str]) -> Result<String, String> {
+    let filter = &mut state.log_viewer.filter;
+
+    if let Some(level) = args.first() {
+        filter.level = level
+            .parse()
+            .map_err(|_| format!("Unknown log level '{}'", level))?;
+    }
+
+    match args.get(1) {
+        Some(&"all") => filter.module = None,
+        Some(module) => filter.module = Some(module.to_string()),
+        None => {}
+    }
+
+    Ok(format!(
+        "Showing {} logs from {}",
+        filter.level,
+        filter.module.as_deref().unwrap_or("every module")
+    ))
+}
+
+//====================================================================
@@ -0,0 +1,114 @@
+//====================================================================
+
+use std::{fmt::Write, panic::PanicHookInfo, sync::Mutex};
+
+use crate::logging;
+
+//====================================================================
+
+/// Directory crash reports are written to on native.
+pub const CRASH_DIR: &str = "crash_reports";
+
+static SNAPSHOT: Mutex<Option<String>> = Mutex::new(None);
+
+/// Record the latest known game state, included in any crash report.
+/// Replaces the previous snapshot.
+pub fn record_snapshot(snapshot: impl Into<String>) {
+    if let Ok(mut current) = SNAPSHOT.lock() {
+        *current = Some(snapshot.into());
+    }
+}
+
+/// Install a panic hook that writes a crash report before running the
+/// previously installed hook. On native the report is written to
+/// [`CRASH_DIR`], on wasm it is posted to the console.
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        let report = crash_report(info);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        match write_report(&report) {
+            Ok(path) => eprintln!("Crash report written to '{}'", path.display()),
+            Err(e) => eprintln!("Unable to write crash report: {}\n{}", e, report),
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        web_sys::console::error_1(&web_sys::wasm_bindgen::JsValue::from(format!(
+            "===== CRASH REPORT =====\n{}===== END CRASH REPORT =====",
+            report
+        )));
+
+        previous(info);
+    }));
+}
+
+//====================================================================
+
+fn crash_report(info: &PanicHookInfo) -> String {
+    let mut report = String::new();
+
+    writeln!(
+        report,
+        "{} {} crashed at {}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        unix_time()
+    )
+    .ok();
+
+    let thread = std::thread::current();
+    writeln!(
+        report,
+        "Thread '{}': {}\n",
+        thread.name().unwrap_or("<unnamed>"),
+        info
+    )
+    .ok();
+
+    writeln!(report, "--- Backtrace ---").ok();
+    writeln!(report, "{}", std::backtrace::Backtrace::force_capture()).ok();
+
+    writeln!(report, "--- Last snapshot ---").ok();
+    match SNAPSHOT
+        .try_lock()
+        .ok()
+        .and_then(|snapshot| snapshot.clone())
+    {
+        Some(snapshot) => writeln!(report, "{}\n", snapshot).ok(),
+        None => writeln!(report, "<none>\n").ok(),
+    };
+
+    writeln!(report, "--- Recent logs ---").ok();
+    match logging::try_recent_logs() {
+        Some(logs) => logs.iter().for_each(|record| {
+            writeln!(report, "{}", record).ok();
+        }),
+        None => {
+            writeln!(report, "<unavailable>").ok();
+        }
+    }
+
+    report
+}
+
+#[inline]
+fn unix_time() -> u64 {
+    web_time::SystemTime::now()
+        .duration_since(web_time::UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_report(report: &str) -> std::io::Result<std::path::PathBuf> {
+    std::fs::create_dir_all(CRASH_DIR)?;
+
+    let path = std::path::Path::new(CRASH_DIR).join(format!("crash_{}.log", unix_time()));
+    std::fs::write(&path, report)?;
+
+    Ok(path)
+}
+
+//====================================================================
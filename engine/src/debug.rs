@@ -0,0 +1,281 @@
+//====================================================================
+
+use std::{fmt::Write, path::Path};
+
+use common::Transform;
+use hecs::{Entity, World};
+use log::LevelFilter;
+use renderer::{capture::FrameCapture, pipelines::ui3d_pipeline::Ui3d, DebugView, Renderer};
+use serde_json::{Map, Value};
+use winit::keyboard::KeyCode;
+
+use crate::{
+    logging::{self, LogFilter},
+    registry::ComponentRegistry,
+    StateInner,
+};
+
+//====================================================================
+
+/// Key that dumps the world to [`DUMP_FILE`] (or the log on wasm).
+pub const DUMP_WORLD_KEY: KeyCode = KeyCode::F9;
+pub const DUMP_FILE: &str = "world_dump.txt";
+/// Key that logs the renderer diagnostics report.
+pub const DIAGNOSTICS_KEY: KeyCode = KeyCode::F10;
+/// Key that shows or hides the in-game log viewer.
+pub const LOG_VIEWER_KEY: KeyCode = KeyCode::F8;
+/// Key that cycles the log viewer's level filter.
+pub const LOG_LEVEL_KEY: KeyCode = KeyCode::F7;
+/// Key that cycles the renderer's debug views.
+pub const RENDER_DEBUG_KEY: KeyCode = KeyCode::F4;
+/// Key that records the next frame's render commands to [`CAPTURE_FILE`] (or
+/// the log on wasm).
+pub const FRAME_CAPTURE_KEY: KeyCode = KeyCode::F2;
+pub const CAPTURE_FILE: &str = "frame_capture.txt";
+
+//====================================================================
+
+/// Readable listing of every entity and its components. Components that
+/// haven't been registered are only counted.
+pub fn dump_world(world: &World, registry: &ComponentRegistry) -> String {
+    let mut output = String::new();
+    writeln!(output, "World dump - {} entities", world.len()).ok();
+
+    let mut entities = world.iter().collect::<Vec<_>>();
+    entities.sort_by_key(|entity| entity.entity().id());
+
+    entities.into_iter().for_each(|entity| {
+        writeln!(output, "{:?}", entity.entity()).ok();
+
+        let (components, unregistered) = registry.inspect(&entity);
+
+        components.into_iter().for_each(|(name, summary)| {
+            match summary {
+                Some(summary) => writeln!(output, "    {:<16} {}", name, summary).ok(),
+                None => writeln!(output, "    {:<16} <unavailable>", name).ok(),
+            };
+        });
+
+        if unregistered > 0 {
+            writeln!(output, "    + {} unregistered components", unregistered).ok();
+        }
+    });
+
+    output
+}
+
+/// Every entity's persistent components as a json object keyed by entity id.
+/// Each entity can be spawned again with [`ComponentRegistry::load_entity`].
+pub fn dump_world_json(world: &World, registry: &ComponentRegistry) -> String {
+    let entities = world
+        .iter()
+        .map(|entity| {
+            (
+                entity.entity().id().to_string(),
+                Value::Object(registry.save_entity(&entity)),
+            )
+        })
+        .collect::<Map<_, _>>();
+
+    serde_json::to_string_pretty(&entities).unwrap_or_default()
+}
+
+// Dump the world to `path`, or the log on wasm where there are no files
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn save_world_dump(
+    world: &World,
+    registry: &ComponentRegistry,
+    path: &str,
+) -> Result<String, String> {
+    dump_world_to_file(world, registry, path)
+        .map(|_| format!("Dumped {} entities to '{}'", world.len(), path))
+        .map_err(|e| format!("Unable to dump world to '{}': {}", path, e))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn save_world_dump(
+    world: &World,
+    registry: &ComponentRegistry,
+    path: &str,
+) -> Result<String, String> {
+    match path.ends_with(".json") {
+        true => log::info!("{}", dump_world_json(world, registry)),
+        false => log::info!("{}", dump_world(world, registry)),
+    }
+    Ok(format!("Dumped {} entities to the log", world.len()))
+}
+
+/// Write a world dump to the given file, as json if it ends in `.json`.
+pub fn dump_world_to_file(
+    world: &World,
+    registry: &ComponentRegistry,
+    path: impl AsRef<Path>,
+) -> std::io::Result<()> {
+    let path = path.as_ref();
+    let json = path
+        .extension()
+        .is_some_and(|extension| extension == "json");
+
+    let dump = match json {
+        true => dump_world_json(world, registry),
+        false => dump_world(world, registry),
+    };
+
+    std::fs::write(path, dump)
+}
+
+// Move on to the next debug view, skipping any the device can't draw. None is
+// always supported so this can't loop forever.
+pub(crate) fn cycle_debug_view(renderer: &mut Renderer) -> DebugView {
+    let mut debug_view = renderer.debug_view().next();
+
+    while !renderer.set_debug_view(debug_view) {
+        debug_view = debug_view.next();
+    }
+
+    debug_view
+}
+
+/// Debug view named as in the console, ignoring case.
+pub fn parse_debug_view(name: &str) -> Option<DebugView> {
+    match name.to_lowercase().as_str() {
+        "none" | "off" => Some(DebugView::None),
+        "wireframe" => Some(DebugView::Wireframe),
+        "overdraw" => Some(DebugView::Overdraw),
+        "depth" => Some(DebugView::Depth),
+        _ => None,
+    }
+}
+
+// Write out a frame capture once the renderer has finished recording it
+pub(crate) fn save_frame_capture(capture: &FrameCapture) {
+    #[cfg(not(target_arch = "wasm32"))]
+    match std::fs::write(CAPTURE_FILE, capture.report()) {
+        Ok(_) => log::info!(
+            "Captured frame {} to '{}' ({} draw calls)",
+            capture.frame,
+            CAPTURE_FILE,
+            capture.draw_calls()
+        ),
+        Err(e) => log::error!("Unable to write frame capture to '{}': {}", CAPTURE_FILE, e),
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    log::info!("{}", capture.report());
+}
+
+//====================================================================
+
+/// In-game view of the recent log buffer, for platforms without a visible
+/// console.
+pub struct LogViewer {
+    pub filter: LogFilter,
+    /// Number of records shown at once.
+    pub lines: usize,
+    /// Longest line shown before truncating.
+    pub line_width: usize,
+
+    entity: Option<Entity>,
+    shown: Vec<String>,
+}
+
+impl Default for LogViewer {
+    fn default() -> Self {
+        Self {
+            filter: LogFilter::default(),
+            lines: 12,
+            line_width: 96,
+            entity: None,
+            shown: Vec::new(),
+        }
+    }
+}
+
+impl LogViewer {
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.entity.is_some()
+    }
+
+    fn contents(&self) -> Vec<String> {
+        let header = match &self.filter.module {
+            Some(module) => format!("Logs - {} ({})", self.filter.level, module),
+            None => format!("Logs - {}", self.filter.level),
+        };
+
+        std::iter::once(header)
+            .chain(
+                logging::filtered_logs(&self.filter, self.lines)
+                    .iter()
+                    .map(|record| {
+                        let line = record.to_string();
+                        match line.chars().count() > self.line_width {
+                            true => line.chars().take(self.line_width).collect(),
+                            false => line,
+                        }
+                    }),
+            )
+            .collect()
+    }
+}
+
+#[inline]
+fn next_level(level: LevelFilter) -> LevelFilter {
+    match level {
+        LevelFilter::Off | LevelFilter::Trace => LevelFilter::Error,
+        LevelFilter::Error => LevelFilter::Warn,
+        LevelFilter::Warn => LevelFilter::Info,
+        LevelFilter::Info => LevelFilter::Debug,
+        LevelFilter::Debug => LevelFilter::Trace,
+    }
+}
+
+pub(crate) fn tick_log_viewer(state: &mut StateInner) {
+    if state.keys.just_pressed(LOG_VIEWER_KEY) {
+        match state.log_viewer.entity.take() {
+            Some(entity) => {
+                state.despawn(entity).ok();
+                state.log_viewer.shown.clear();
+            }
+            None => {
+                let camera = &state.renderer.camera.camera;
+                let position = camera.translation + camera.forward() * 8.;
+
+                state.log_viewer.entity = Some(
+                    state
+                        .world
+                        .spawn((Transform::from_translation(position), Ui3d::default())),
+                );
+            }
+        }
+    }
+
+    let entity = match state.log_viewer.entity {
+        Some(entity) => entity,
+        None => return,
+    };
+
+    if state.keys.just_pressed(LOG_LEVEL_KEY) {
+        state.log_viewer.filter.level = next_level(state.log_viewer.filter.level);
+    }
+
+    // Only touch the ui when something changed, so the text isn't reshaped
+    // every frame
+    let contents = state.log_viewer.contents();
+    if contents == state.log_viewer.shown {
+        return;
+    }
+
+    match state.world.get::<&mut Ui3d>(entity) {
+        Ok(mut ui) => ui.options = contents.clone(),
+        Err(_) => {
+            log::warn!("Log viewer entity removed - closing viewer");
+            state.log_viewer.entity = None;
+            return;
+        }
+    }
+
+    state.log_viewer.shown = contents;
+}
+
+//====================================================================
@@ -0,0 +1,46 @@
+//====================================================================
+
+use renderer::error::RenderError;
+
+//====================================================================
+
+#[derive(Debug, thiserror::Error)]
+pub enum EngineError {
+    #[error("Unable to run event loop: {0}")]
+    EventLoop(#[from] winit::error::EventLoopError),
+
+    #[error("Unable to create window: {0}")]
+    CreateWindow(#[from] winit::error::OsError),
+
+    #[error("Unable to attach canvas to the document")]
+    AttachCanvas,
+
+    #[error("Unable to initialize renderer: {0}")]
+    Renderer(#[from] RenderError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AudioError {
+    #[error("Unable to decode audio: {0}")]
+    Decode(String),
+
+    #[error("No audio output device available")]
+    NoDevice,
+
+    #[error("Audio output isn't supported on this platform")]
+    Unsupported,
+
+    #[error("Unable to open audio output: {0}")]
+    Output(String),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum HapticsError {
+    #[error("Unable to open gamepads: {0}")]
+    Gamepads(String),
+
+    #[error("Rumble isn't supported on this platform")]
+    Unsupported,
+}
+
+//====================================================================
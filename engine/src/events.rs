@@ -0,0 +1,81 @@
+//====================================================================
+
+use std::any::{Any, TypeId};
+
+use rustc_hash::FxHashMap;
+
+//====================================================================
+
+type Handler<T> = Box<dyn FnMut(&T)>;
+
+/// Handle to a subscribed handler, given back to [`EventBus::unsubscribe`] to
+/// remove it.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct Subscription {
+    event: TypeId,
+    id: u64,
+}
+
+/// Synchronous publish/subscribe by event type. Handlers run immediately
+/// when an event is emitted, in the order they subscribed.
+#[derive(Default)]
+pub struct EventBus {
+    handlers: FxHashMap<TypeId, Vec<(u64, Box<dyn Any>)>>,
+    next_id: u64,
+}
+
+impl EventBus {
+    /// Handlers stay subscribed until they're unsubscribed, so anything that
+    /// doesn't last as long as the bus should hold on to the [`Subscription`].
+    pub fn subscribe<T: 'static>(&mut self, handler: impl FnMut(&T) + 'static) -> Subscription {
+        let handler: Handler<T> = Box::new(handler);
+
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.handlers
+            .entry(TypeId::of::<T>())
+            .or_default()
+            .push((id, Box::new(handler)));
+
+        Subscription {
+            event: TypeId::of::<T>(),
+            id,
+        }
+    }
+
+    pub fn unsubscribe(&mut self, subscription: Subscription) {
+        if let Some(handlers) = self.handlers.get_mut(&subscription.event) {
+            handlers.retain(|(id, _)| *id != subscription.id);
+        }
+    }
+
+    pub fn emit<T: 'static>(&mut self, event: T) {
+        let handlers = match self.handlers.get_mut(&TypeId::of::<T>()) {
+            Some(handlers) => handlers,
+            None => return,
+        };
+
+        handlers
+            .iter_mut()
+            .filter_map(|(_, handler)| handler.downcast_mut::<Handler<T>>())
+            .for_each(|handler| handler(&event));
+    }
+
+    #[inline]
+    pub fn has_subscribers<T: 'static>(&self) -> bool {
+        self.handlers
+            .get(&TypeId::of::<T>())
+            .is_some_and(|handlers| !handlers.is_empty())
+    }
+}
+
+//====================================================================
+
+/// Emitted by the engine whenever a new scene starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SceneChanged {
+    pub name: &'static str,
+}
+
+//====================================================================
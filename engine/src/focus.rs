@@ -0,0 +1,111 @@
+//====================================================================
+
+use hecs::{Entity, World};
+use renderer::pipelines::ui3d_pipeline::Ui3d;
+use serde::{Deserialize, Serialize};
+
+use crate::events::EventBus;
+
+//====================================================================
+
+/// Sent through the [`EventBus`] whenever a different widget gets input
+/// focus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FocusChanged {
+    pub from: Option<Entity>,
+    pub to: Option<Entity>,
+}
+
+/// Shows whether a [`Ui3d`] widget has input focus by recoloring its
+/// selection.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Focusable {
+    /// sRGB color with linear alpha.
+    pub focused_color: [f32; 4],
+    /// sRGB color with linear alpha.
+    pub unfocused_color: [f32; 4],
+}
+
+impl Default for Focusable {
+    fn default() -> Self {
+        Self {
+            focused_color: [0.85, 0.8, 0.45, 0.9],
+            unfocused_color: [0.55, 0.55, 0.55, 0.75],
+        }
+    }
+}
+
+//====================================================================
+
+/// Which widget keyboard input goes to. Exactly one widget has focus at a
+/// time, the one focused most recently. When it loses focus, focus goes
+/// back to whichever had it before.
+#[derive(Debug, Default)]
+pub struct InputFocus {
+    stack: Vec<Entity>,
+}
+
+impl InputFocus {
+    #[inline]
+    pub fn focused(&self) -> Option<Entity> {
+        self.stack.last().copied()
+    }
+
+    #[inline]
+    pub fn has_focus(&self, entity: Entity) -> bool {
+        self.focused() == Some(entity)
+    }
+
+    /// Give `entity` focus, taking it from the focused widget until `entity`
+    /// is removed.
+    pub fn push(&mut self, events: &mut EventBus, entity: Entity) {
+        let from = self.focused();
+
+        self.stack.retain(|focused| *focused != entity);
+        self.stack.push(entity);
+
+        Self::emit_changed(events, from, Some(entity));
+    }
+
+    /// Stop `entity` taking input. If it had focus, focus goes back to the
+    /// widget before it.
+    pub fn remove(&mut self, events: &mut EventBus, entity: Entity) {
+        let from = self.focused();
+        self.stack.retain(|focused| *focused != entity);
+
+        Self::emit_changed(events, from, self.focused());
+    }
+
+    /// Scenes start with nothing focused.
+    #[inline]
+    pub(crate) fn clear(&mut self) {
+        self.stack.clear();
+    }
+
+    fn emit_changed(events: &mut EventBus, from: Option<Entity>, to: Option<Entity>) {
+        if from != to {
+            events.emit(FocusChanged { from, to });
+        }
+    }
+}
+
+//====================================================================
+
+/// Recolor the selection of every [`Focusable`] widget to show which one has
+/// focus.
+pub(crate) fn update_indicators(world: &mut World, focus: &InputFocus) {
+    let focused = focus.focused();
+
+    world
+        .query_mut::<(&Focusable, &mut Ui3d)>()
+        .into_iter()
+        .for_each(|(entity, (focusable, ui))| {
+            ui.selection_color = match focused == Some(entity) {
+                true => focusable.focused_color,
+                false => focusable.unfocused_color,
+            };
+        });
+}
+
+//====================================================================
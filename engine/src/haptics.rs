@@ -0,0 +1,164 @@
+//====================================================================
+
+use crate::error::HapticsError;
+
+//====================================================================
+
+/// Rumble is scheduled in steps of this many milliseconds.
+const TICK_MILLIS: u32 = 50;
+
+//====================================================================
+
+/// One step of a rumble pattern, with motor strengths from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rumble {
+    /// Low frequency motor.
+    pub strong: f32,
+    /// High frequency motor.
+    pub weak: f32,
+    pub millis: u32,
+}
+
+/// Rumbles every connected gamepad that supports force feedback.
+pub struct Haptics {
+    #[cfg(not(target_arch = "wasm32"))]
+    gilrs: Option<gilrs::Gilrs>,
+    /// Effects stop as soon as they're dropped, so they're kept until they
+    /// finish.
+    #[cfg(not(target_arch = "wasm32"))]
+    playing: Vec<(gilrs::ff::Effect, web_time::Instant)>,
+}
+
+impl Haptics {
+    /// Haptics that ignore every rumble. Used when there's no gamepad support
+    /// or haptics are turned off.
+    pub fn disabled() -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            gilrs: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            playing: Vec::new(),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open() -> Result<Self, HapticsError> {
+        let gilrs = gilrs::Gilrs::new().map_err(|e| HapticsError::Gamepads(e.to_string()))?;
+
+        Ok(Self {
+            gilrs: Some(gilrs),
+            playing: Vec::new(),
+        })
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn open() -> Result<Self, HapticsError> {
+        Err(HapticsError::Unsupported)
+    }
+
+    /// [`Haptics::open`], or carry on without rumble if that fails.
+    pub fn open_or_disabled() -> Self {
+        Self::open().unwrap_or_else(|e| {
+            log::warn!("Playing without rumble: {}", e);
+            Self::disabled()
+        })
+    }
+
+    /// Play `pattern` once on every gamepad that supports it, one step after
+    /// another.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn rumble(&mut self, pattern: &[Rumble]) {
+        use gilrs::ff::{BaseEffect, BaseEffectType, EffectBuilder, Repeat, Replay, Ticks};
+
+        let gilrs = match &mut self.gilrs {
+            Some(gilrs) => gilrs,
+            None => return,
+        };
+
+        let gamepads = gilrs
+            .gamepads()
+            .filter(|(_, gamepad)| gamepad.is_ff_supported())
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+
+        // Steps round up to whole ticks
+        let millis = pattern
+            .iter()
+            .map(|step| step.millis.div_ceil(TICK_MILLIS) * TICK_MILLIS)
+            .sum::<u32>();
+
+        if gamepads.is_empty() || millis == 0 {
+            return;
+        }
+
+        let length = pattern.iter().fold(Ticks::default(), |length, step| {
+            length + Ticks::from_ms(step.millis)
+        });
+
+        let magnitude = |strength: f32| (strength.clamp(0., 1.) * u16::MAX as f32) as u16;
+
+        let mut builder = EffectBuilder::new();
+        let mut after = Ticks::default();
+
+        pattern.iter().for_each(|step| {
+            let play_for = Ticks::from_ms(step.millis);
+
+            [
+                BaseEffectType::Strong {
+                    magnitude: magnitude(step.strong),
+                },
+                BaseEffectType::Weak {
+                    magnitude: magnitude(step.weak),
+                },
+            ]
+            .into_iter()
+            .for_each(|kind| {
+                builder.add_effect(BaseEffect {
+                    kind,
+                    // Delayed past the end of the pattern so it only plays once
+                    scheduling: Replay {
+                        after,
+                        play_for,
+                        with_delay: length,
+                    },
+                    ..Default::default()
+                });
+            });
+
+            after += play_for;
+        });
+
+        let effect = builder
+            .gamepads(&gamepads)
+            .repeat(Repeat::For(length))
+            .finish(gilrs)
+            .and_then(|effect| effect.play().map(|_| effect));
+
+        match effect {
+            Ok(effect) => self.playing.push((
+                effect,
+                web_time::Instant::now() + web_time::Duration::from_millis(millis as u64),
+            )),
+            Err(e) => log::warn!("Unable to rumble: {}", e),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn rumble(&mut self, _pattern: &[Rumble]) {}
+
+    /// Keep track of connected gamepads and drop finished effects. Called once
+    /// a frame by the engine.
+    pub fn tick(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if let Some(gilrs) = &mut self.gilrs {
+                while gilrs.next_event().is_some() {}
+            }
+
+            let now = web_time::Instant::now();
+            self.playing.retain(|(_, end)| *end > now);
+        }
+    }
+}
+
+//====================================================================
@@ -2,67 +2,259 @@
 
 use std::time::Duration;
 
-use common::Size;
-use hecs::World;
+use audio::Audio;
+use builder::{EngineBuilder, EngineFeatures};
+use common::PhysicalSize;
+use console::Console;
+use debug::LogViewer;
+use error::EngineError;
+use events::{EventBus, SceneChanged};
+use focus::InputFocus;
+use haptics::Haptics;
+use hecs::{Entity, NoSuchEntity, World};
+use loading::{LoadTracker, LoadingScene};
+use modal::Modals;
+use prefab::Prefabs;
+use registry::ComponentRegistry;
 use renderer::Renderer;
+use resources::Resources;
+use runtime::AsyncRuntime;
 use scene::Scene;
-use tools::{Input, Time};
+use tasks::TaskScheduler;
+use tools::{Input, MouseButton, Time};
 use window::Window;
 use winit::{
-    event::{DeviceEvent, DeviceId, WindowEvent},
+    event::{DeviceEvent, DeviceId, MouseScrollDelta, WindowEvent},
     event_loop::ActiveEventLoop,
     keyboard::KeyCode,
     window::WindowId,
 };
 
+pub mod audio;
+pub mod builder;
+pub mod console;
+pub mod crash;
+pub mod debug;
+pub mod error;
+pub mod events;
+pub mod focus;
+pub mod haptics;
+pub mod lifetime;
+pub mod loading;
+pub mod logging;
+pub mod modal;
+pub mod music;
+pub mod picking;
+pub mod prefab;
+pub mod registry;
+pub mod resources;
+pub mod runtime;
 pub mod scene;
+pub mod tasks;
 pub mod tools;
 pub mod window;
 
 //====================================================================
 
-const DEFAULT_FPS: f32 = 1. / 75.;
+/// Frames per second while the window is minimized or hidden, enough to keep
+/// background work and networking going.
+pub const SUSPENDED_FPS: f32 = 10.;
 
 pub struct State {
     inner: StateInner,
     scene: Box<dyn Scene>,
+    /// Whether the scene was last told it's paused by a modal.
+    paused: bool,
+    /// Window has zero size, which is how most platforms report minimizing.
+    minimized: bool,
+    /// Window is fully hidden behind others or on another workspace.
+    occluded: bool,
 }
 
 pub struct StateInner {
     pub target_fps: Duration,
+    /// Optional parts of the engine that were enabled at startup.
+    pub features: EngineFeatures,
     pub window: Window,
     pub renderer: Renderer,
     pub keys: Input<KeyCode>,
+    pub mouse: Input<MouseButton>,
+    /// Cursor position over the window in physical pixels, None while it's
+    /// outside the window.
+    pub cursor: Option<glam::Vec2>,
+    /// Lines scrolled by the mouse wheel this frame, positive away from the
+    /// user.
+    pub scroll: f32,
+    /// Raw mouse movement this frame, unaffected by the cursor being grabbed
+    /// or reaching the edge of the screen. Units depend on the platform,
+    /// roughly pixels.
+    pub mouse_delta: glam::Vec2,
+    /// Whether the window has keyboard focus.
+    pub focused: bool,
+    /// Stop game time while the window doesn't have focus.
+    pub pause_on_focus_loss: bool,
+    pub focus: InputFocus,
     pub time: Time,
+    pub loading: LoadTracker,
+    pub tasks: TaskScheduler,
+    pub runtime: AsyncRuntime,
+    pub audio: Audio,
+    pub haptics: Haptics,
+    pub events: EventBus,
+    pub log_viewer: LogViewer,
+    /// Debug commands, only usable while debug tools are enabled.
+    pub console: Console,
+    pub modals: Modals,
+    pub prefabs: Prefabs,
+    /// Components known by name. Register game components here to have them
+    /// show up in world dumps, saves and data prefabs.
+    pub registry: ComponentRegistry,
+    /// State that outlives scenes, such as what the next scene should load.
+    pub resources: Resources,
 
     pub world: World,
+
+    next_scene: Option<SceneBuilder>,
+    cursor_grabbed: bool,
 }
 
-impl State {
-    pub fn new<S: Scene>(event_loop: &ActiveEventLoop) -> Self {
-        let target_fps = Duration::from_secs_f32(DEFAULT_FPS);
-        let window = Window::new(event_loop);
+type SceneBuilder = fn(&mut StateInner) -> Box<dyn Scene>;
+
+impl StateInner {
+    /// Replace the current scene with `S` at the end of this frame. The old
+    /// scene cleans up after itself in [`Scene::on_exit`].
+    pub fn switch_scene<S: Scene>(&mut self) {
+        let builder: SceneBuilder = |state| Box::new(S::new(state));
+        self.next_scene = Some(builder);
+    }
+
+    /// Switch to `S`, showing the loading screen until all work registered
+    /// with `loading` has finished.
+    #[inline]
+    pub fn switch_scene_with_loading<S: Scene>(&mut self) {
+        self.switch_scene::<LoadingScene<S>>();
+    }
+
+    /// Despawn an entity and release anything the renderer holds for it.
+    /// Widgets lose input focus.
+    pub fn despawn(&mut self, entity: Entity) -> Result<(), NoSuchEntity> {
+        self.focus.remove(&mut self.events, entity);
+        self.renderer.remove_entity(entity);
+        self.world.despawn(entity)
+    }
+
+    /// Lock and hide the cursor so the mouse can be used to look around with
+    /// [`StateInner::mouse_delta`]. Released when the scene changes.
+    pub fn set_cursor_grab(&mut self, grab: bool) {
+        if grab == self.cursor_grabbed {
+            return;
+        }
+
+        self.cursor_grabbed = self.window.set_cursor_grab(grab) && grab;
+    }
 
-        #[cfg(not(target_arch = "wasm32"))]
-        let renderer = Renderer::new(window.0.clone(), window.size().into());
+    #[inline]
+    pub fn cursor_grabbed(&self) -> bool {
+        self.cursor_grabbed
+    }
 
-        #[cfg(target_arch = "wasm32")]
-        let renderer = Renderer::new(window.0.clone(), (500, 450));
+    /// Keyboard input for `entity`, only while it has input focus.
+    #[inline]
+    pub fn focused_keys(&self, entity: Entity) -> Option<&Input<KeyCode>> {
+        match self.focus.has_focus(entity) {
+            true => Some(&self.keys),
+            false => None,
+        }
+    }
+}
 
+impl State {
+    /// Create the window and renderer, blocking until the device is ready.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new<S: Scene>(
+        event_loop: &ActiveEventLoop,
+        builder: EngineBuilder,
+    ) -> Result<Self, EngineError> {
+        let window = Window::new(event_loop)?;
+        let renderer =
+            Renderer::with_config(window.0.clone(), window.size(), builder.renderer.clone())?;
+
+        Ok(Self::from_parts::<S>(window, renderer, builder))
+    }
+
+    /// Create the state around an already initialized window and renderer,
+    /// running the builder's setup before the first scene is created.
+    pub fn from_parts<S: Scene>(
+        window: Window,
+        renderer: Renderer,
+        builder: EngineBuilder,
+    ) -> Self {
         let world = World::new();
 
         let mut inner = StateInner {
-            target_fps,
+            target_fps: builder.target_fps,
+            features: builder.features,
             window,
             renderer,
             keys: Input::default(),
+            mouse: Input::default(),
+            cursor: None,
+            scroll: 0.,
+            mouse_delta: glam::Vec2::ZERO,
+            focused: true,
+            pause_on_focus_loss: false,
+            focus: InputFocus::default(),
             time: Time::default(),
+            loading: LoadTracker::default(),
+            tasks: TaskScheduler::default(),
+            runtime: AsyncRuntime::default(),
+            audio: match builder.features.audio {
+                true => Audio::open_or_silent(),
+                false => Audio::silent(),
+            },
+            haptics: Haptics::open_or_disabled(),
+            events: EventBus::default(),
+            log_viewer: LogViewer::default(),
+            console: Console::default(),
+            modals: Modals::default(),
+            prefabs: Prefabs::default(),
+            registry: ComponentRegistry::with_engine_components(),
+            resources: Resources::default(),
             world,
+            next_scene: None,
+            cursor_grabbed: false,
         };
 
-        let scene = Box::new(S::new(&mut inner));
+        builder
+            .setup
+            .into_iter()
+            .for_each(|setup| setup(&mut inner));
+
+        let scene = Self::create_scene(&mut inner, |state| Box::new(S::new(state)));
 
-        Self { inner, scene }
+        Self {
+            inner,
+            scene,
+            paused: false,
+            minimized: false,
+            occluded: false,
+        }
+    }
+
+    fn create_scene(inner: &mut StateInner, builder: SceneBuilder) -> Box<dyn Scene> {
+        inner.modals.clear();
+        inner.focus.clear();
+        inner.set_cursor_grab(false);
+        let mut scene = builder(inner);
+
+        if let Some(color) = scene.clear_color() {
+            inner.renderer.set_clear_color(color, None);
+        }
+
+        scene.on_enter(inner);
+        inner.events.emit(SceneChanged { name: scene.name() });
+
+        scene
     }
 
     pub fn window_event(
@@ -73,46 +265,93 @@ impl State {
     ) {
         match event {
             WindowEvent::Resized(physical_size) => {
-                if physical_size.width == 0 || physical_size.height == 0 {
-                    log::warn!(
-                        "Window resized to invalid size ({}, {})",
+                let minimized = physical_size.width == 0 || physical_size.height == 0;
+                self.set_suspended(minimized, self.occluded);
+
+                if minimized {
+                    log::debug!(
+                        "Window resized to ({}, {}) - keeping last size",
                         physical_size.width,
                         physical_size.height
                     );
                     return;
                 }
-                let size = Size {
-                    width: physical_size.width,
-                    height: physical_size.height,
-                };
+                let size = PhysicalSize::from(physical_size);
                 self.inner.renderer.resize(size);
-                self.scene.resize(&mut self.inner, size.into());
+                self.scene.resize(&mut self.inner, size);
             }
 
             WindowEvent::CloseRequested => {
                 log::info!("Close requested. Closing App");
+                self.scene.quit(&mut self.inner);
                 event_loop.exit();
             }
 
             WindowEvent::Destroyed => log::error!("Window was destroyed"),
 
+            WindowEvent::Occluded(occluded) => self.set_suspended(self.minimized, occluded),
+
+            WindowEvent::Focused(focused) => {
+                self.inner.focused = focused;
+
+                match focused {
+                    // Release events are lost while unfocused, so don't leave
+                    // anything held down
+                    false => {
+                        self.inner.keys.clear_all();
+                        self.inner.mouse.clear_all();
+                        self.inner.scroll = 0.;
+                        self.inner.mouse_delta = glam::Vec2::ZERO;
+                    }
+                    // Not every platform reports being uncovered, but a
+                    // focused window is always visible. Grabs are dropped
+                    // by the platform along with focus.
+                    true => {
+                        self.set_suspended(false, false);
+
+                        if self.inner.cursor_grabbed {
+                            self.inner.window.set_cursor_grab(true);
+                        }
+                    }
+                }
+            }
+
+            // Synthetic presses are keys already held when focus comes back,
+            // which weren't meant for the game
+            WindowEvent::KeyboardInput {
+                is_synthetic: true, ..
+            } => {}
+
             WindowEvent::KeyboardInput { event, .. } => {
+                if self.inner.features.debug_tools && console::process_key(&mut self.inner, &event)
+                {
+                    return;
+                }
+
                 if let winit::keyboard::PhysicalKey::Code(key) = event.physical_key {
                     tools::process_inputs(&mut self.inner.keys, key, event.state.is_pressed())
                 }
             }
-            //
-            // WindowEvent::CursorMoved { position, .. } => {}
-            // WindowEvent::MouseWheel { delta, .. } => {}
-            // WindowEvent::MouseInput { state, button, .. } => {}
-            //
-            WindowEvent::RedrawRequested => {
-                event_loop.set_control_flow(winit::event_loop::ControlFlow::wait_duration(
-                    self.inner.target_fps,
-                ));
-
-                self.tick();
+
+            WindowEvent::CursorMoved { position, .. } => {
+                self.inner.cursor = Some(glam::vec2(position.x as f32, position.y as f32));
+            }
+
+            WindowEvent::CursorLeft { .. } => self.inner.cursor = None,
+
+            WindowEvent::MouseInput { state, button, .. } => {
+                tools::process_inputs(&mut self.inner.mouse, button, state.is_pressed())
+            }
+
+            WindowEvent::MouseWheel { delta, .. } => {
+                self.inner.scroll += match delta {
+                    MouseScrollDelta::LineDelta(_, lines) => lines,
+                    MouseScrollDelta::PixelDelta(position) => {
+                        position.y as f32 / tools::PIXELS_PER_LINE
+                    }
+                };
             }
+            WindowEvent::RedrawRequested => self.frame(event_loop),
 
             _ => {}
         }
@@ -124,7 +363,14 @@ impl State {
         device_id: DeviceId,
         event: DeviceEvent,
     ) {
-        let _ = (event_loop, device_id, event);
+        let _ = (event_loop, device_id);
+
+        // Device events arrive whether or not the window is focused
+        if let DeviceEvent::MouseMotion { delta: (x, y) } = event {
+            if self.inner.focused {
+                self.inner.mouse_delta += glam::vec2(x as f32, y as f32);
+            }
+        }
     }
 
     #[inline]
@@ -132,13 +378,165 @@ impl State {
         self.inner.window.0.request_redraw();
     }
 
+    /// Whether the window can't be seen, so nothing is rendered and the
+    /// state ticks at [`SUSPENDED_FPS`].
+    #[inline]
+    pub fn suspended(&self) -> bool {
+        self.minimized || self.occluded
+    }
+
+    /// Time for the next frame. Suspended windows aren't sent redraws on
+    /// some platforms, so they're ticked directly.
+    pub fn frame_due(&mut self, event_loop: &ActiveEventLoop) {
+        match self.suspended() {
+            true => self.frame(event_loop),
+            false => self.request_redraw(),
+        }
+    }
+
+    fn frame(&mut self, event_loop: &ActiveEventLoop) {
+        let frame_time = match self.suspended() {
+            true => Duration::from_secs_f32(1. / SUSPENDED_FPS),
+            false => self.inner.target_fps,
+        };
+
+        event_loop.set_control_flow(winit::event_loop::ControlFlow::wait_duration(frame_time));
+
+        self.tick();
+    }
+
+    fn set_suspended(&mut self, minimized: bool, occluded: bool) {
+        let was_suspended = self.suspended();
+        self.minimized = minimized;
+        self.occluded = occluded;
+
+        match (was_suspended, self.suspended()) {
+            (false, true) => log::info!("Window hidden - suspending rendering"),
+            (true, false) => {
+                log::info!("Window visible - resuming rendering");
+                self.request_redraw();
+            }
+            _ => {}
+        }
+    }
+
     pub fn tick(&mut self) {
-        tools::tick_time(&mut self.inner.time);
+        let _span = tracing::info_span!("tick").entered();
+
+        let unfocused = self.inner.pause_on_focus_loss && !self.inner.focused;
+        tools::tick_time(
+            &mut self.inner.time,
+            self.inner.modals.any_open() || unfocused,
+        );
+
+        // Results from async work finished since last frame
+        runtime::apply_results(&mut self.inner);
+        self.inner.haptics.tick();
+
+        let paused = self.inner.modals.any_open();
+        if paused != self.paused {
+            self.paused = paused;
+            match paused {
+                true => self.scene.on_pause(&mut self.inner),
+                false => self.scene.on_resume(&mut self.inner),
+            }
+        }
 
-        self.scene.update(&mut self.inner);
-        self.inner.renderer.tick(&mut self.inner.world);
+        tracing::info_span!("scene_update").in_scope(|| match paused {
+            true => self.scene.update_modal(&mut self.inner),
+            false => self.scene.update(&mut self.inner),
+        });
+
+        if let Some(builder) = self.inner.next_scene.take() {
+            let _span = tracing::info_span!("scene_switch").entered();
+            self.scene.on_exit(&mut self.inner);
+            self.scene = Self::create_scene(&mut self.inner, builder);
+            // Modals were cleared for the new scene
+            self.paused = false;
+        }
+
+        tracing::info_span!("despawn_expired")
+            .in_scope(|| lifetime::despawn_expired(&mut self.inner));
+
+        tracing::info_span!("animations").in_scope(|| {
+            renderer::animation::tick_animations(
+                &mut self.inner.world,
+                self.inner.time.delta_seconds(),
+            );
+            renderer::animation::tick_idle_animations(
+                &mut self.inner.world,
+                self.inner.time.delta_seconds(),
+            );
+        });
+        tracing::info_span!("backgrounds").in_scope(|| {
+            renderer::background::tick_backgrounds(
+                &mut self.inner.world,
+                self.inner.renderer.camera.camera.translation,
+                self.inner.time.delta_seconds(),
+            )
+        });
+        tracing::info_span!("fades").in_scope(|| {
+            renderer::fade::tick_fades(&mut self.inner.world, self.inner.time.delta_seconds())
+        });
+        tracing::info_span!("particles").in_scope(|| {
+            renderer::pipelines::particle_pipeline::tick_particles(
+                &mut self.inner.world,
+                self.inner.time.delta_seconds(),
+            )
+        });
+        focus::update_indicators(&mut self.inner.world, &self.inner.focus);
+
+        if !self.suspended() {
+            self.inner.renderer.tick(&mut self.inner.world);
+        }
+
+        // Use whatever is left of the frame for background work
+        tasks::run_tasks(&mut self.inner);
+
+        if self.inner.features.debug_tools {
+            self.tick_debug_tools();
+        }
 
         tools::reset_input(&mut self.inner.keys);
+        tools::reset_input(&mut self.inner.mouse);
+        self.inner.scroll = 0.;
+        self.inner.mouse_delta = glam::Vec2::ZERO;
+
+        #[cfg(feature = "tracy")]
+        tracy_client::frame_mark();
+    }
+
+    fn tick_debug_tools(&mut self) {
+        if self.inner.keys.just_pressed(debug::DUMP_WORLD_KEY) {
+            self.run_command("dump_world");
+        }
+
+        if self.inner.keys.just_pressed(debug::DIAGNOSTICS_KEY) {
+            self.run_command("diagnostics");
+        }
+
+        if self.inner.keys.just_pressed(debug::RENDER_DEBUG_KEY) {
+            self.run_command("render_view");
+        }
+
+        if self.inner.keys.just_pressed(debug::FRAME_CAPTURE_KEY) {
+            self.run_command("capture_frame");
+        }
+
+        if let Some(capture) = self.inner.renderer.take_frame_capture() {
+            debug::save_frame_capture(&capture);
+        }
+
+        debug::tick_log_viewer(&mut self.inner);
+        console::tick_console(&mut self.inner);
+    }
+
+    // Hotkeys are shortcuts for console commands, logging what they print
+    fn run_command(&mut self, line: &str) {
+        match console::execute(&mut self.inner, line) {
+            Ok(output) => log::info!("{}", output),
+            Err(e) => log::error!("{}", e),
+        }
     }
 }
 
@@ -0,0 +1,73 @@
+//====================================================================
+
+use hecs::Entity;
+use web_time::Duration;
+
+use crate::StateInner;
+
+//====================================================================
+
+/// Most expired entities despawned in a frame. Any over are left for the
+/// next.
+pub const DESPAWN_BUDGET: usize = 256;
+
+//====================================================================
+
+/// Despawn the entity once this much game time has passed. Counts down with
+/// the scaled frame time, so slows down along with the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DespawnAfter(pub Duration);
+
+/// Despawn the entity once [`crate::tools::Time::frame`] reaches this frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DespawnAtFrame(pub u64);
+
+impl DespawnAtFrame {
+    /// Despawn `frames` frames from now.
+    #[inline]
+    pub fn after(state: &StateInner, frames: u64) -> Self {
+        Self(state.time.frame() + frames)
+    }
+}
+
+//====================================================================
+
+/// Count down [`DespawnAfter`] and despawn anything past its lifetime, up to
+/// [`DESPAWN_BUDGET`] entities.
+pub(crate) fn despawn_expired(state: &mut StateInner) {
+    let delta = *state.time.delta();
+    let frame = state.time.frame();
+
+    let mut expired = state
+        .world
+        .query_mut::<&mut DespawnAfter>()
+        .into_iter()
+        .filter_map(|(entity, DespawnAfter(remaining))| {
+            *remaining = remaining.saturating_sub(delta);
+            remaining.is_zero().then_some(entity)
+        })
+        .collect::<Vec<Entity>>();
+
+    expired.extend(
+        state
+            .world
+            .query_mut::<&DespawnAtFrame>()
+            .into_iter()
+            .filter(|(_, DespawnAtFrame(at))| frame >= *at)
+            .map(|(entity, _)| entity),
+    );
+
+    if expired.len() > DESPAWN_BUDGET {
+        log::trace!(
+            "{} expired entities, leaving {} for next frame",
+            expired.len(),
+            expired.len() - DESPAWN_BUDGET
+        );
+    }
+
+    expired.into_iter().take(DESPAWN_BUDGET).for_each(|entity| {
+        state.despawn(entity).ok();
+    });
+}
+
+//====================================================================
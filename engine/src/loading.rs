@@ -0,0 +1,152 @@
+//====================================================================
+
+use std::{
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+use common::{PhysicalSize, Transform};
+use hecs::Entity;
+use renderer::pipelines::ui3d_pipeline::Ui3d;
+
+use crate::{scene::Scene, StateInner};
+
+//====================================================================
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoadProgress {
+    pub loaded: usize,
+    pub total: usize,
+    pub current: Option<String>,
+}
+
+impl LoadProgress {
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        self.loaded >= self.total
+    }
+
+    #[inline]
+    pub fn fraction(&self) -> f32 {
+        match self.total {
+            0 => 1.,
+            total => self.loaded as f32 / total as f32,
+        }
+    }
+}
+
+/// Shared counter that loaders report their progress to. Cheap to clone and
+/// can be handed to background threads.
+#[derive(Debug, Clone, Default)]
+pub struct LoadTracker(Arc<Mutex<LoadProgress>>);
+
+impl LoadTracker {
+    /// Register items that will need loading.
+    pub fn add(&self, count: usize) {
+        self.0.lock().unwrap().total += count;
+    }
+
+    /// Set the item currently being loaded.
+    pub fn start(&self, item: impl Into<String>) {
+        self.0.lock().unwrap().current = Some(item.into());
+    }
+
+    /// Mark an item as loaded.
+    pub fn finish(&self) {
+        let mut progress = self.0.lock().unwrap();
+        progress.loaded = (progress.loaded + 1).min(progress.total);
+
+        if progress.is_done() {
+            progress.current = None;
+        }
+    }
+
+    #[inline]
+    pub fn progress(&self) -> LoadProgress {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// Clear finished work so the next load starts counting from zero.
+    pub fn reset(&self) {
+        let mut progress = self.0.lock().unwrap();
+        if progress.is_done() {
+            *progress = LoadProgress::default();
+        }
+    }
+}
+
+//====================================================================
+
+/// Built-in scene that shows load progress until everything registered with
+/// `StateInner::loading` is done, then switches to `S`.
+pub struct LoadingScene<S: Scene> {
+    label: Entity,
+    frames: u32,
+    last_progress: Option<LoadProgress>,
+    next: PhantomData<S>,
+}
+
+impl<S: Scene> Scene for LoadingScene<S> {
+    fn new(state: &mut StateInner) -> Self {
+        log::debug!("Showing loading screen");
+
+        let camera = &state.renderer.camera.camera;
+        let position = camera.translation + camera.forward() * 10.;
+
+        let label = state.world.spawn((
+            Transform::from_translation(position),
+            Ui3d {
+                options: vec![String::from("Loading...")],
+                ..Default::default()
+            },
+        ));
+
+        Self {
+            label,
+            frames: 0,
+            last_progress: None,
+            next: PhantomData,
+        }
+    }
+
+    fn resize(&mut self, _state: &mut StateInner, _new_size: PhysicalSize<u32>) {}
+
+    fn name(&self) -> &'static str {
+        "Loading"
+    }
+
+    fn on_exit(&mut self, state: &mut StateInner) {
+        state.despawn(self.label).ok();
+    }
+
+    fn update(&mut self, state: &mut StateInner) {
+        self.frames += 1;
+
+        let progress = state.loading.progress();
+
+        if self.last_progress.as_ref() != Some(&progress) {
+            if let Ok(mut ui) = state.world.get::<&mut Ui3d>(self.label) {
+                ui.options = vec![format!(
+                    "Loading {}/{} ({:.0}%)",
+                    progress.loaded,
+                    progress.total,
+                    progress.fraction() * 100.
+                )];
+
+                if let Some(current) = &progress.current {
+                    ui.options.push(current.clone());
+                }
+            }
+
+            self.last_progress = Some(progress.clone());
+        }
+
+        // Always show at least one frame before moving on
+        if self.frames > 1 && progress.is_done() {
+            state.loading.reset();
+            state.switch_scene::<S>();
+        }
+    }
+}
+
+//====================================================================
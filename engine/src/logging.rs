@@ -0,0 +1,196 @@
+//====================================================================
+
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use web_time::Instant;
+
+//====================================================================
+
+/// Number of records kept in the recent log buffer.
+pub const LOG_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    /// Time since the logger was installed.
+    pub elapsed: Duration,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{:>9.3}s {:<5} {}] {}",
+            self.elapsed.as_secs_f32(),
+            self.level,
+            self.target,
+            self.message
+        )
+    }
+}
+
+static RECENT: Mutex<VecDeque<LogRecord>> = Mutex::new(VecDeque::new());
+static START: OnceLock<Instant> = OnceLock::new();
+
+/// Copy of the most recent log records, oldest first.
+pub fn recent_logs() -> Vec<LogRecord> {
+    match RECENT.lock() {
+        Ok(recent) => recent.iter().cloned().collect(),
+        Err(poisoned) => poisoned.into_inner().iter().cloned().collect(),
+    }
+}
+
+/// Which records to show when viewing recent logs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFilter {
+    /// Most verbose level to show.
+    pub level: LevelFilter,
+    /// Only show records whose target starts with this, e.g. `"renderer"`.
+    pub module: Option<String>,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self {
+            level: LevelFilter::Info,
+            module: None,
+        }
+    }
+}
+
+impl LogFilter {
+    #[inline]
+    pub fn matches(&self, record: &LogRecord) -> bool {
+        record.level <= self.level
+            && self
+                .module
+                .as_ref()
+                .is_none_or(|module| record.target.starts_with(module.as_str()))
+    }
+}
+
+/// The most recent records matching `filter`, up to `limit`, oldest first.
+pub fn filtered_logs(filter: &LogFilter, limit: usize) -> Vec<LogRecord> {
+    let recent = match RECENT.lock() {
+        Ok(recent) => recent,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+
+    let mut records = recent
+        .iter()
+        .rev()
+        .filter(|record| filter.matches(record))
+        .take(limit)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    records.reverse();
+    records
+}
+
+// Used from the panic hook - never wait on a lock that may be held by the
+// panicking thread.
+pub(crate) fn try_recent_logs() -> Option<Vec<LogRecord>> {
+    RECENT
+        .try_lock()
+        .ok()
+        .map(|recent| recent.iter().cloned().collect())
+}
+
+//====================================================================
+
+/// Logger that keeps the most recent records in memory before passing them
+/// on to the wrapped logger.
+struct RingLogger {
+    inner: Box<dyn Log>,
+}
+
+impl Log for RingLogger {
+    #[inline]
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let entry = LogRecord {
+            elapsed: START.get_or_init(Instant::now).elapsed(),
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        };
+
+        if let Ok(mut recent) = RECENT.lock() {
+            if recent.len() >= LOG_CAPACITY {
+                recent.pop_front();
+            }
+            recent.push_back(entry);
+        }
+
+        self.inner.log(record);
+    }
+
+    #[inline]
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Install `inner` as the global logger, recording recent output so it can
+/// be included in crash reports.
+pub fn init(inner: Box<dyn Log>, max_level: LevelFilter) -> Result<(), SetLoggerError> {
+    START.get_or_init(Instant::now);
+
+    log::set_boxed_logger(Box::new(RingLogger { inner }))?;
+    log::set_max_level(max_level);
+
+    Ok(())
+}
+
+//====================================================================
+
+/// Logs to the browser console, at or above the given level.
+#[cfg(target_arch = "wasm32")]
+pub struct ConsoleLogger(pub LevelFilter);
+
+#[cfg(target_arch = "wasm32")]
+impl Log for ConsoleLogger {
+    #[inline]
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.0
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let message = web_sys::wasm_bindgen::JsValue::from(format!(
+            "{} {}: {}",
+            record.level(),
+            record.target(),
+            record.args()
+        ));
+
+        match record.level() {
+            Level::Error => web_sys::console::error_1(&message),
+            Level::Warn => web_sys::console::warn_1(&message),
+            Level::Info => web_sys::console::info_1(&message),
+            Level::Debug | Level::Trace => web_sys::console::debug_1(&message),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+//====================================================================
@@ -0,0 +1,54 @@
+//====================================================================
+
+//====================================================================
+
+/// Modal UI holding focus, such as a pause menu or dialogue. While any are
+/// open the scene gets [`Scene::update_modal`](crate::scene::Scene::update_modal)
+/// instead of its usual update and game time stands still. Rendering and
+/// renderer tweens carry on as normal.
+#[derive(Debug, Default)]
+pub struct Modals {
+    /// Most recently opened last.
+    open: Vec<&'static str>,
+}
+
+impl Modals {
+    /// Opening a modal that's already open does nothing.
+    pub fn open(&mut self, name: &'static str) {
+        if !self.is_open(name) {
+            log::debug!("Opened modal '{}'", name);
+            self.open.push(name);
+        }
+    }
+
+    pub fn close(&mut self, name: &'static str) {
+        self.open.retain(|open| *open != name);
+    }
+
+    #[inline]
+    pub fn is_open(&self, name: &'static str) -> bool {
+        self.open.contains(&name)
+    }
+
+    /// True while gameplay should be paused.
+    #[inline]
+    pub fn any_open(&self) -> bool {
+        !self.open.is_empty()
+    }
+
+    /// Most recently opened modal, which takes input.
+    #[inline]
+    pub fn focused(&self) -> Option<&'static str> {
+        self.open.last().copied()
+    }
+
+    // Modals belong to the scene that opened them
+    pub(crate) fn clear(&mut self) {
+        if !self.open.is_empty() {
+            log::warn!("Scene changed with modals still open: {:?}", self.open);
+            self.open.clear();
+        }
+    }
+}
+
+//====================================================================
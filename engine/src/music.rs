@@ -0,0 +1,245 @@
+//====================================================================
+
+use std::sync::Arc;
+
+use crate::error::AudioError;
+
+//====================================================================
+
+/// Decoded audio as interleaved samples.
+#[derive(Debug, Clone)]
+pub struct AudioClip {
+    samples: Arc<[f32]>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl AudioClip {
+    pub fn new(samples: impl Into<Arc<[f32]>>, channels: u16, sample_rate: u32) -> Self {
+        Self {
+            samples: samples.into(),
+            channels: channels.max(1),
+            sample_rate,
+        }
+    }
+
+    /// Decode a wav file. Integer samples are scaled to -1 to 1.
+    pub fn from_wav(bytes: &[u8]) -> Result<Self, AudioError> {
+        let reader = hound::WavReader::new(bytes).map_err(|e| AudioError::Decode(e.to_string()))?;
+        let spec = reader.spec();
+
+        let samples: Vec<f32> = match spec.sample_format {
+            hound::SampleFormat::Float => reader.into_samples::<f32>().collect::<Result<_, _>>(),
+            hound::SampleFormat::Int => {
+                let scale = 1. / (1_i64 << (spec.bits_per_sample - 1)) as f32;
+
+                reader
+                    .into_samples::<i32>()
+                    .map(|sample| sample.map(|sample| sample as f32 * scale))
+                    .collect::<Result<_, _>>()
+            }
+        }
+        .map_err(|e| AudioError::Decode(e.to_string()))?;
+
+        Ok(Self::new(samples, spec.channels, spec.sample_rate))
+    }
+
+    /// Length in frames, one sample per channel.
+    #[inline]
+    pub fn frames(&self) -> u64 {
+        self.samples.len() as u64 / self.channels as u64
+    }
+
+    #[inline]
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    #[inline]
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Samples of one frame, or None past the end.
+    #[inline]
+    pub fn frame(&self, frame: usize) -> Option<&[f32]> {
+        let channels = self.channels as usize;
+        self.samples.get(frame * channels..(frame + 1) * channels)
+    }
+}
+
+/// Part of a track that repeats, in frames. Playback jumps back to `start`
+/// on the exact frame it reaches `end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoopPoints {
+    pub start: u64,
+    pub end: u64,
+}
+
+/// Parts of a layered track. Every layer plays in step with the others and
+/// only their volumes change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MusicLayer {
+    Base,
+    Tension,
+    Victory,
+}
+
+impl MusicLayer {
+    pub const ALL: [MusicLayer; 3] = [MusicLayer::Base, MusicLayer::Tension, MusicLayer::Victory];
+}
+
+/// Volume of each [`MusicLayer`], from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LayerMix {
+    pub base: f32,
+    pub tension: f32,
+    pub victory: f32,
+}
+
+impl LayerMix {
+    #[inline]
+    pub fn get(&self, layer: MusicLayer) -> f32 {
+        match layer {
+            MusicLayer::Base => self.base,
+            MusicLayer::Tension => self.tension,
+            MusicLayer::Victory => self.victory,
+        }
+    }
+}
+
+//====================================================================
+
+/// Vertically layered music that crossfades between [`LayerMix`]es. Samples
+/// are pulled with [`LayeredMusic::mix`] by whatever plays them, which also
+/// moves the fades along so they stay in time with the music.
+pub struct LayeredMusic {
+    channels: u16,
+    sample_rate: u32,
+    loop_points: LoopPoints,
+
+    /// In [`MusicLayer::ALL`] order. Missing layers are silent.
+    layers: [Option<AudioClip>; 3],
+    gains: [f32; 3],
+    targets: [f32; 3],
+    /// Seconds taken to fade a layer fully in or out.
+    crossfade: f32,
+
+    /// Next frame to play.
+    position: u64,
+}
+
+impl LayeredMusic {
+    /// Default seconds taken to fade a layer fully in or out.
+    pub const CROSSFADE: f32 = 2.;
+
+    pub fn new(channels: u16, sample_rate: u32, loop_points: LoopPoints) -> Self {
+        Self {
+            channels: channels.max(1),
+            sample_rate,
+            loop_points,
+            layers: [None, None, None],
+            gains: [0.; 3],
+            targets: [0.; 3],
+            crossfade: Self::CROSSFADE,
+            position: 0,
+        }
+    }
+
+    /// Clips have to match the music's channels and sample rate, anything else
+    /// is left out.
+    pub fn with_layer(mut self, layer: MusicLayer, clip: AudioClip) -> Self {
+        if clip.channels != self.channels || clip.sample_rate != self.sample_rate {
+            log::warn!(
+                "Music layer {:?} is {} channels at {}Hz, expected {} channels at {}Hz - skipping",
+                layer,
+                clip.channels,
+                clip.sample_rate,
+                self.channels,
+                self.sample_rate
+            );
+            return self;
+        }
+
+        self.layers[layer as usize] = Some(clip);
+        self
+    }
+
+    #[inline]
+    pub fn with_crossfade(mut self, seconds: f32) -> Self {
+        self.crossfade = seconds.max(0.);
+        self
+    }
+
+    /// Start fading every layer towards `mix`.
+    pub fn set_mix(&mut self, mix: LayerMix) {
+        MusicLayer::ALL.iter().for_each(|layer| {
+            self.targets[*layer as usize] = mix.get(*layer).clamp(0., 1.);
+        });
+    }
+
+    /// Current volume of a layer, part way through any fade.
+    #[inline]
+    pub fn gain(&self, layer: MusicLayer) -> f32 {
+        self.gains[layer as usize]
+    }
+
+    #[inline]
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    #[inline]
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    #[inline]
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Add the next frames into `out`, interleaved with the music's channel
+    /// count. Trailing samples that don't make up a whole frame are left alone.
+    pub fn mix(&mut self, out: &mut [f32]) {
+        let channels = self.channels as usize;
+
+        // Snap straight to the targets without a crossfade
+        let step = match self.crossfade > 0. {
+            true => 1. / (self.crossfade * self.sample_rate as f32),
+            false => f32::INFINITY,
+        };
+
+        out.chunks_exact_mut(channels).for_each(|frame| {
+            self.layers
+                .iter()
+                .zip(self.gains.iter_mut().zip(self.targets.iter()))
+                .for_each(|(clip, (gain, target))| {
+                    *gain = match *gain < *target {
+                        true => (*gain + step).min(*target),
+                        false => (*gain - step).max(*target),
+                    };
+
+                    let clip = match clip {
+                        Some(clip) if *gain > 0. => clip,
+                        _ => return,
+                    };
+
+                    let start = self.position as usize * channels;
+                    if let Some(samples) = clip.samples.get(start..start + channels) {
+                        frame
+                            .iter_mut()
+                            .zip(samples)
+                            .for_each(|(out, sample)| *out += sample * *gain);
+                    }
+                });
+
+            self.position += 1;
+            if self.position >= self.loop_points.end {
+                self.position = self.loop_points.start;
+            }
+        });
+    }
+}
+
+//====================================================================
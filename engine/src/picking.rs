@@ -0,0 +1,78 @@
+//====================================================================
+
+use common::Transform;
+use hecs::{Entity, World};
+use renderer::{
+    camera::PerspectiveCamera,
+    pipelines::{texture_pipeline::Sprite, Hidden},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::StateInner;
+
+//====================================================================
+
+/// Lets an entity be found under the cursor by its [`Sprite`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pickable;
+
+//====================================================================
+
+/// Closest pickable entity under the cursor. None if there isn't one or the
+/// cursor is outside the view.
+pub fn pick_cursor(state: &StateInner) -> Option<Entity> {
+    let ndc = state.renderer.viewport().window_to_ndc(state.cursor?)?;
+
+    pick(&state.world, &state.renderer.camera.camera, ndc)
+}
+
+/// Closest pickable entity whose sprite covers `ndc`, a point on screen in
+/// normalized device coordinates.
+pub fn pick(world: &World, camera: &PerspectiveCamera, ndc: glam::Vec2) -> Option<Entity> {
+    let (origin, direction) = camera.ray(ndc);
+
+    let mut query = world
+        .query::<(&Transform, &Sprite)>()
+        .with::<&Pickable>()
+        .without::<&Hidden>();
+
+    query
+        .iter()
+        .filter_map(|(entity, (transform, sprite))| {
+            let distance = hit_sprite(transform, sprite.size, origin, direction)?;
+            Some((entity, distance))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity)
+}
+
+// Distance along the ray to where it hits the sprite's quad, if it does
+fn hit_sprite(
+    transform: &Transform,
+    size: glam::Vec2,
+    origin: glam::Vec3,
+    direction: glam::Vec3,
+) -> Option<f32> {
+    // Sprites lie on their local xy plane, centered on the transform
+    let inverse = transform.to_matrix().inverse();
+    let local_origin = inverse.transform_point3(origin);
+    let local_direction = inverse.transform_vector3(direction);
+
+    if local_direction.z.abs() <= f32::EPSILON {
+        return None;
+    }
+
+    let distance = -local_origin.z / local_direction.z;
+    if distance < 0. {
+        return None;
+    }
+
+    let hit = local_origin + local_direction * distance;
+
+    match hit.x.abs() <= size.x / 2. && hit.y.abs() <= size.y / 2. {
+        true => Some(distance),
+        false => None,
+    }
+}
+
+//====================================================================
@@ -0,0 +1,107 @@
+//====================================================================
+
+use hecs::{DynamicBundle, Entity, EntityBuilder};
+use renderer::Renderer;
+use rustc_hash::FxHashMap;
+use serde_json::{Map, Value};
+
+use crate::{registry::ComponentRegistry, StateInner};
+
+//====================================================================
+
+type BuildFn = Box<dyn Fn(&Renderer, &mut EntityBuilder)>;
+
+enum Prefab {
+    /// Built in code, for components that need renderer resources.
+    Code(BuildFn),
+    /// Persistent components by their registered name, see
+    /// [`ComponentRegistry`].
+    Data(Map<String, Value>),
+}
+
+/// Named sets of components that can be spawned in one go with
+/// [`StateInner::spawn_prefab`].
+#[derive(Default)]
+pub struct Prefabs {
+    prefabs: FxHashMap<String, Prefab>,
+}
+
+impl Prefabs {
+    /// Define a prefab in code. Registering a name again replaces it.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        build: impl Fn(&Renderer, &mut EntityBuilder) + 'static,
+    ) {
+        self.prefabs
+            .insert(name.into(), Prefab::Code(Box::new(build)));
+    }
+
+    /// Define a prefab from persistent components, keyed by their registered
+    /// names. Registering a name again replaces it.
+    pub fn register_data(&mut self, name: impl Into<String>, components: Map<String, Value>) {
+        self.prefabs.insert(name.into(), Prefab::Data(components));
+    }
+
+    /// Define every prefab in a json object of prefab names to components.
+    /// Returns the number of prefabs loaded.
+    pub fn load_data(&mut self, json: &str) -> serde_json::Result<usize> {
+        let prefabs = serde_json::from_str::<Map<String, Value>>(json)?;
+        let count = prefabs.len();
+
+        prefabs.into_iter().try_for_each(|(name, components)| {
+            let components = serde_json::from_value(components)?;
+            self.register_data(name, components);
+            Ok::<_, serde_json::Error>(())
+        })?;
+
+        Ok(count)
+    }
+
+    #[inline]
+    pub fn contains(&self, name: &str) -> bool {
+        self.prefabs.contains_key(name)
+    }
+
+    // Add the prefab's components to the builder. False if there's no prefab
+    // with that name.
+    fn build(
+        &self,
+        name: &str,
+        renderer: &Renderer,
+        registry: &ComponentRegistry,
+        builder: &mut EntityBuilder,
+    ) -> bool {
+        match self.prefabs.get(name) {
+            Some(Prefab::Code(build)) => build(renderer, builder),
+            Some(Prefab::Data(components)) => registry.load_components(builder, components),
+            None => return false,
+        }
+
+        true
+    }
+}
+
+//====================================================================
+
+impl StateInner {
+    /// Spawn the prefab called `name`, with `overrides` added afterwards to
+    /// replace any of its components of the same type. None if there's no
+    /// such prefab.
+    pub fn spawn_prefab(&mut self, name: &str, overrides: impl DynamicBundle) -> Option<Entity> {
+        let mut builder = EntityBuilder::new();
+
+        if !self
+            .prefabs
+            .build(name, &self.renderer, &self.registry, &mut builder)
+        {
+            log::warn!("Unable to spawn unknown prefab '{}'", name);
+            return None;
+        }
+        builder.add_bundle(overrides);
+
+        Some(self.world.spawn(builder.build()))
+    }
+}
+
+//====================================================================
@@ -0,0 +1,294 @@
+//====================================================================
+
+use std::{any::TypeId, fmt::Debug};
+
+use common::Transform;
+use hecs::{Component, Entity, EntityBuilder, EntityRef, World};
+use renderer::{
+    animation::{AnimationPlayer, IdleAnimation},
+    background::BackgroundLayer,
+    fade::Fade,
+    pipelines::{
+        grid_pipeline::Grid,
+        particle_pipeline::ParticleEmitter,
+        texture_pipeline::{Sprite, SpriteLayer, SpriteStack},
+        ui3d_pipeline::Ui3d,
+        Hidden, Opacity,
+    },
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    focus::Focusable,
+    lifetime::{DespawnAfter, DespawnAtFrame},
+    picking::Pickable,
+};
+
+//====================================================================
+
+type SummaryFn = Box<dyn Fn(&EntityRef) -> Option<String> + Send + Sync>;
+type SaveFn = Box<dyn Fn(&EntityRef) -> Option<serde_json::Result<Value>> + Send + Sync>;
+type LoadFn = Box<dyn Fn(&mut EntityBuilder, Value) -> serde_json::Result<()> + Send + Sync>;
+type DefaultFn = Box<dyn Fn(&mut World, Entity) -> bool + Send + Sync>;
+
+/// Serialize, deserialize and default for components that can be saved.
+struct Persist {
+    save: SaveFn,
+    load: LoadFn,
+    default: DefaultFn,
+}
+
+struct ComponentEntry {
+    name: &'static str,
+    type_id: TypeId,
+    summary: SummaryFn,
+    persist: Option<Persist>,
+}
+
+/// Marks entities that are written by [`ComponentRegistry::save_world`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Saved;
+
+//====================================================================
+
+/// Components known by name, for world dumps, saves and prefabs.
+#[derive(Default)]
+pub struct ComponentRegistry {
+    entries: Vec<ComponentEntry>,
+}
+
+impl ComponentRegistry {
+    /// Registry holding every component the engine defines.
+    pub fn with_engine_components() -> Self {
+        let mut registry = Self::default();
+
+        registry.register_persistent::<Saved>("Saved");
+        registry.register_persistent::<Transform>("Transform");
+        registry.register_persistent::<Ui3d>("Ui3d");
+        registry.register_persistent::<Hidden>("Hidden");
+        registry.register_persistent::<Opacity>("Opacity");
+        registry.register_persistent::<Focusable>("Focusable");
+        registry.register_persistent::<Grid>("Grid");
+        registry.register_persistent::<Pickable>("Pickable");
+
+        registry.register_component::<SpriteLayer>("SpriteLayer");
+        registry.register_component::<BackgroundLayer>("BackgroundLayer");
+        registry.register_component::<IdleAnimation>("IdleAnimation");
+        registry.register_component::<DespawnAfter>("DespawnAfter");
+        registry.register_component::<DespawnAtFrame>("DespawnAtFrame");
+        registry.register_component::<Fade>("Fade");
+        registry.register_component::<ParticleEmitter>("ParticleEmitter");
+
+        registry.register_component_with::<Sprite>("Sprite", |sprite| {
+            format!(
+                "Sprite {{ texture: {}, sampler: {:?}, blend: {:?}, flags: {:#x}, size: {}, color: {:?}, palette: {:?} }}",
+                sprite.material.texture.id(),
+                sprite.material.sampler,
+                sprite.material.blend,
+                sprite.material.flags.bits(),
+                sprite.size,
+                sprite.color,
+                sprite.palette
+            )
+        });
+
+        registry.register_component_with::<SpriteStack>("SpriteStack", |stack| {
+            format!(
+                "SpriteStack {{ textures: {:?} }}",
+                stack
+                    .layers
+                    .iter()
+                    .map(|layer| layer.texture.id())
+                    .collect::<Vec<_>>()
+            )
+        });
+
+        registry.register_component_with::<AnimationPlayer>("AnimationPlayer", |player| {
+            format!(
+                "AnimationPlayer {{ clip: {:?}, finished: {} }}",
+                player.current_clip(),
+                player.is_finished()
+            )
+        });
+
+        registry
+    }
+
+    /// Register a component so it shows up by name in world dumps.
+    #[inline]
+    pub fn register_component<T: Component + Debug>(&mut self, name: &'static str) {
+        self.register_component_with::<T>(name, |component| format!("{:?}", component));
+    }
+
+    /// Register a component with a custom summary, for components that aren't
+    /// `Debug` or whose debug output is too noisy.
+    pub fn register_component_with<T: Component>(
+        &mut self,
+        name: &'static str,
+        summary: impl Fn(&T) -> String + Send + Sync + 'static,
+    ) {
+        self.register::<T>(name, summary, None);
+    }
+
+    /// Register a component that can also be saved, loaded and added by name
+    /// through its default.
+    pub fn register_persistent<T>(&mut self, name: &'static str)
+    where
+        T: Component + Debug + Default + Serialize + DeserializeOwned,
+    {
+        let persist = Persist {
+            save: Box::new(|entity| {
+                entity
+                    .get::<&T>()
+                    .map(|component| serde_json::to_value(&*component))
+            }),
+            load: Box::new(|builder, value| {
+                builder.add(serde_json::from_value::<T>(value)?);
+                Ok(())
+            }),
+            default: Box::new(|world, entity| world.insert_one(entity, T::default()).is_ok()),
+        };
+
+        self.register::<T>(name, |component| format!("{:?}", component), Some(persist));
+    }
+
+    fn register<T: Component>(
+        &mut self,
+        name: &'static str,
+        summary: impl Fn(&T) -> String + Send + Sync + 'static,
+        persist: Option<Persist>,
+    ) {
+        // Re-registering replaces the old entry
+        self.entries
+            .retain(|entry| entry.type_id != TypeId::of::<T>());
+
+        self.entries.push(ComponentEntry {
+            name,
+            type_id: TypeId::of::<T>(),
+            summary: Box::new(move |entity| {
+                entity.get::<&T>().map(|component| summary(&component))
+            }),
+            persist,
+        });
+    }
+
+    fn persist(&self, name: &str) -> Option<&Persist> {
+        self.entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .and_then(|entry| entry.persist.as_ref())
+    }
+
+    //----------------------------------------------
+
+    /// Name and summary of each registered component on an entity, along with
+    /// the number of components that haven't been registered. Summaries are
+    /// None if the component couldn't be borrowed.
+    pub fn inspect(&self, entity: &EntityRef) -> (Vec<(&'static str, Option<String>)>, usize) {
+        let mut unregistered = 0;
+
+        let components = entity
+            .component_types()
+            .filter_map(|type_id| {
+                match self.entries.iter().find(|entry| entry.type_id == type_id) {
+                    Some(entry) => Some((entry.name, (entry.summary)(entity))),
+                    None => {
+                        unregistered += 1;
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        (components, unregistered)
+    }
+
+    /// Every persistent component on an entity, keyed by its registered name.
+    /// Components that fail to serialize are logged and left out.
+    pub fn save_entity(&self, entity: &EntityRef) -> Map<String, Value> {
+        self.entries
+            .iter()
+            .filter_map(|entry| {
+                let persist = entry.persist.as_ref()?;
+
+                match (persist.save)(entity)? {
+                    Ok(value) => Some((entry.name.to_string(), value)),
+                    Err(e) => {
+                        log::warn!("Unable to save component '{}': {}", entry.name, e);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Spawn an entity from components written by
+    /// [`ComponentRegistry::save_entity`]. Unknown or invalid components are
+    /// logged and skipped.
+    pub fn load_entity(&self, world: &mut World, components: &Map<String, Value>) -> Entity {
+        let mut builder = EntityBuilder::new();
+        self.load_components(&mut builder, components);
+
+        world.spawn(builder.build())
+    }
+
+    /// Add components written by [`ComponentRegistry::save_entity`] to a
+    /// builder. Unknown or invalid components are logged and skipped.
+    pub fn load_components(&self, builder: &mut EntityBuilder, components: &Map<String, Value>) {
+        components.iter().for_each(|(name, value)| {
+            let persist = match self.persist(name) {
+                Some(persist) => persist,
+                None => {
+                    log::warn!("Unable to load unknown component '{}'", name);
+                    return;
+                }
+            };
+
+            if let Err(e) = (persist.load)(builder, value.clone()) {
+                log::warn!("Unable to load component '{}': {}", name, e);
+            }
+        });
+    }
+
+    /// Persistent components of every entity marked [`Saved`], oldest first.
+    pub fn save_world(&self, world: &World) -> Vec<Map<String, Value>> {
+        let mut entities = world
+            .iter()
+            .filter(|entity| entity.has::<Saved>())
+            .collect::<Vec<_>>();
+        entities.sort_by_key(|entity| entity.entity().id());
+
+        entities
+            .iter()
+            .map(|entity| self.save_entity(entity))
+            .collect()
+    }
+
+    /// Spawn every entity written by [`ComponentRegistry::save_world`].
+    pub fn load_world(&self, world: &mut World, entities: &[Map<String, Value>]) -> Vec<Entity> {
+        entities
+            .iter()
+            .map(|components| self.load_entity(world, components))
+            .collect()
+    }
+
+    /// Add the default value of a persistent component by its registered
+    /// name. Returns false if there's no such component or the entity doesn't
+    /// exist.
+    pub fn insert_default(&self, world: &mut World, entity: Entity, name: &str) -> bool {
+        self.persist(name)
+            .is_some_and(|persist| (persist.default)(world, entity))
+    }
+
+    /// Names of every registered persistent component, in registration order.
+    pub fn persistent_names(&self) -> Vec<&'static str> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.persist.is_some())
+            .map(|entry| entry.name)
+            .collect()
+    }
+}
+
+//====================================================================
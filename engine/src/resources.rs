@@ -0,0 +1,60 @@
+//====================================================================
+
+use std::any::{Any, TypeId};
+
+use rustc_hash::FxHashMap;
+
+//====================================================================
+
+/// State owned by the app rather than any one scene, one value per type.
+/// Scenes use it to hand things to the scene that comes after them, and to
+/// keep things that last as long as the app.
+#[derive(Default)]
+pub struct Resources {
+    resources: FxHashMap<TypeId, Box<dyn Any>>,
+}
+
+impl Resources {
+    /// Insert `resource`, returning the one it replaced.
+    pub fn insert<T: 'static>(&mut self, resource: T) -> Option<T> {
+        self.resources
+            .insert(TypeId::of::<T>(), Box::new(resource))
+            .and_then(|old| old.downcast().ok())
+            .map(|old| *old)
+    }
+
+    pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        self.resources
+            .remove(&TypeId::of::<T>())
+            .and_then(|resource| resource.downcast().ok())
+            .map(|resource| *resource)
+    }
+
+    #[inline]
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<T>())
+    }
+
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.resources
+            .get(&TypeId::of::<T>())
+            .and_then(|resource| resource.downcast_ref())
+    }
+
+    pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.resources
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|resource| resource.downcast_mut())
+    }
+
+    /// Resource of type `T`, inserting its default first if there isn't one.
+    pub fn get_or_default<T: Default + 'static>(&mut self) -> &mut T {
+        self.resources
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(T::default()))
+            .downcast_mut()
+            .unwrap()
+    }
+}
+
+//====================================================================
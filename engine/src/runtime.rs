@@ -0,0 +1,56 @@
+//====================================================================
+
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use crate::StateInner;
+
+//====================================================================
+
+type Completion = Box<dyn FnOnce(&mut StateInner) + Send>;
+
+/// Posts work back to the frame loop from other threads. Posted closures run
+/// at the start of the next frame with full access to the state.
+#[derive(Clone)]
+pub struct ResultPoster(Sender<Completion>);
+
+impl ResultPoster {
+    /// Returns false if the engine has shut down.
+    pub fn post(&self, apply: impl FnOnce(&mut StateInner) + Send + 'static) -> bool {
+        self.0.send(Box::new(apply)).is_ok()
+    }
+}
+
+//====================================================================
+
+/// Receives work posted back to the frame loop. Futures run on the frame
+/// loop itself with [`crate::tasks::TaskScheduler::spawn_future`], while
+/// threads doing blocking work hand their results back through a
+/// [`ResultPoster`].
+pub struct AsyncRuntime {
+    sender: Sender<Completion>,
+    receiver: Receiver<Completion>,
+}
+
+impl Default for AsyncRuntime {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self { sender, receiver }
+    }
+}
+
+impl AsyncRuntime {
+    #[inline]
+    pub fn poster(&self) -> ResultPoster {
+        ResultPoster(self.sender.clone())
+    }
+}
+
+pub(crate) fn apply_results(state: &mut StateInner) {
+    let completions = state.runtime.receiver.try_iter().collect::<Vec<_>>();
+
+    completions
+        .into_iter()
+        .for_each(|completion| completion(state));
+}
+
+//====================================================================
@@ -1,6 +1,6 @@
 //====================================================================
 
-use common::Size;
+use common::PhysicalSize;
 
 use crate::StateInner;
 
@@ -11,8 +11,40 @@ pub trait Scene: 'static {
     where
         Self: Sized;
 
-    fn resize(&mut self, state: &mut StateInner, new_size: Size<u32>);
+    fn resize(&mut self, state: &mut StateInner, new_size: PhysicalSize<u32>);
     fn update(&mut self, state: &mut StateInner);
+
+    /// Called once the scene has been created and made current, before its
+    /// first update.
+    fn on_enter(&mut self, _state: &mut StateInner) {}
+
+    /// Called when switching away from the scene, before the next scene is
+    /// created. Despawn the scene's entities and release its resources here.
+    fn on_exit(&mut self, _state: &mut StateInner) {}
+
+    /// Called when a modal opens over the scene and updates stop.
+    fn on_pause(&mut self, _state: &mut StateInner) {}
+
+    /// Called when the last modal closes and updates carry on.
+    fn on_resume(&mut self, _state: &mut StateInner) {}
+
+    /// Called instead of [`Scene::update`] while a modal is open in
+    /// [`StateInner::modals`], to run the modal. Game time is stopped.
+    fn update_modal(&mut self, _state: &mut StateInner) {}
+
+    /// Clear color applied when the scene is created. Scenes can change it
+    /// later through `state.renderer.set_clear_color`.
+    fn clear_color(&self) -> Option<renderer::wgpu::Color> {
+        None
+    }
+
+    /// Called when the app is closing, before the scene is dropped.
+    fn quit(&mut self, _state: &mut StateInner) {}
+
+    /// Name reported in [`SceneChanged`](crate::events::SceneChanged) events.
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
 }
 
 //====================================================================
@@ -0,0 +1,235 @@
+//====================================================================
+
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll, Wake, Waker},
+};
+
+use web_time::{Duration, Instant};
+
+use crate::StateInner;
+
+//====================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum TaskPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskStatus {
+    /// More work left - step again when there's time.
+    Continue,
+    /// Nothing to do until something else happens - step again next frame.
+    Waiting,
+    Done,
+}
+
+type TaskFn = Box<dyn FnMut(&mut StateInner) -> TaskStatus>;
+
+struct Task {
+    name: String,
+    priority: TaskPriority,
+    step: TaskFn,
+    steps: u32,
+    started: Instant,
+    /// Returned [`TaskStatus::Waiting`] this frame.
+    waiting: bool,
+}
+
+// Flags a future's task to be polled again on its next step
+struct FutureWaker(AtomicBool);
+
+impl Wake for FutureWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.store(true, Ordering::Release);
+    }
+}
+
+/// Runs long jobs a small step at a time, within a per-frame time budget.
+/// Each step should do a bounded amount of work and return quickly.
+pub struct TaskScheduler {
+    tasks: Vec<Task>,
+    /// Portion of the time left in the frame that tasks may use.
+    pub budget_fraction: f32,
+    /// Lower bound on the budget so tasks still progress when frames run long.
+    pub min_budget: Duration,
+}
+
+impl Default for TaskScheduler {
+    fn default() -> Self {
+        Self {
+            tasks: Vec::new(),
+            budget_fraction: 0.5,
+            min_budget: Duration::from_micros(500),
+        }
+    }
+}
+
+impl TaskScheduler {
+    pub fn spawn(
+        &mut self,
+        name: impl Into<String>,
+        priority: TaskPriority,
+        step: impl FnMut(&mut StateInner) -> TaskStatus + 'static,
+    ) {
+        let name = name.into();
+        log::trace!("Spawning task '{}' ({:?})", name, priority);
+
+        let task = Task {
+            name,
+            priority,
+            step: Box::new(step),
+            steps: 0,
+            started: Instant::now(),
+            waiting: false,
+        };
+
+        self.insert(task);
+    }
+
+    /// Run a future on the frame loop, polling it as a task whenever it's
+    /// woken and handing its output to `on_complete` once it finishes. Work
+    /// that blocks should happen elsewhere, such as on a thread that wakes
+    /// the future when it's done.
+    pub fn spawn_future<T: 'static>(
+        &mut self,
+        name: impl Into<String>,
+        priority: TaskPriority,
+        future: impl Future<Output = T> + 'static,
+        on_complete: impl FnOnce(&mut StateInner, T) + 'static,
+    ) {
+        let mut future = Box::pin(future);
+        let mut on_complete = Some(on_complete);
+
+        // Woken to start with so it's polled on the first step
+        let wake = Arc::new(FutureWaker(AtomicBool::new(true)));
+        let waker = Waker::from(wake.clone());
+
+        self.spawn(name, priority, move |state| {
+            if !wake.0.swap(false, Ordering::Acquire) {
+                return TaskStatus::Waiting;
+            }
+
+            match future.as_mut().poll(&mut Context::from_waker(&waker)) {
+                Poll::Ready(output) => {
+                    if let Some(on_complete) = on_complete.take() {
+                        on_complete(state, output);
+                    }
+                    TaskStatus::Done
+                }
+                Poll::Pending => TaskStatus::Waiting,
+            }
+        });
+    }
+
+    // Keep sorted by priority, preserving spawn order within a priority
+    fn insert(&mut self, task: Task) {
+        let index = self
+            .tasks
+            .iter()
+            .position(|existing| existing.priority < task.priority)
+            .unwrap_or(self.tasks.len());
+
+        self.tasks.insert(index, task);
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Budget for this frame, given how long is left until the next one.
+    #[inline]
+    pub fn budget(&self, frame_remaining: Duration) -> Duration {
+        frame_remaining
+            .mul_f32(self.budget_fraction)
+            .max(self.min_budget)
+    }
+
+    // Step tasks round robin, highest priority first, until out of budget
+    // or everything left is waiting.
+    fn run(&mut self, state: &mut StateInner, budget: Duration) {
+        let start = Instant::now();
+
+        self.tasks.iter_mut().for_each(|task| task.waiting = false);
+
+        while self.tasks.iter().any(|task| !task.waiting) && start.elapsed() < budget {
+            let mut index = 0;
+
+            while index < self.tasks.len() {
+                if start.elapsed() >= budget {
+                    return;
+                }
+
+                let task = &mut self.tasks[index];
+                if task.waiting {
+                    index += 1;
+                    continue;
+                }
+                task.steps += 1;
+
+                match (task.step)(state) {
+                    TaskStatus::Continue => index += 1,
+                    TaskStatus::Waiting => {
+                        task.waiting = true;
+                        index += 1;
+                    }
+                    TaskStatus::Done => {
+                        let task = self.tasks.remove(index);
+                        log::trace!(
+                            "Task '{}' finished after {} steps in {:?}",
+                            task.name,
+                            task.steps,
+                            task.started.elapsed()
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    // Tasks spawned while running end up in the state's scheduler
+    fn merge(&mut self, other: TaskScheduler) {
+        other.tasks.into_iter().for_each(|task| self.insert(task));
+    }
+}
+
+pub(crate) fn run_tasks(state: &mut StateInner) {
+    if state.tasks.is_empty() {
+        return;
+    }
+
+    let frame_remaining = state
+        .target_fps
+        .saturating_sub(state.time.frame_start().elapsed());
+
+    let _span = tracing::info_span!("tasks", count = state.tasks.len()).entered();
+
+    let mut tasks = std::mem::take(&mut state.tasks);
+    let budget = tasks.budget(frame_remaining);
+
+    tasks.run(state, budget);
+
+    // Put scheduler back, keeping anything spawned during the run
+    let spawned = std::mem::replace(&mut state.tasks, tasks);
+    state.tasks.merge(spawned);
+}
+
+//====================================================================
@@ -21,6 +21,12 @@ pub struct Time {
     last_frame: Instant,
     delta: Duration,
     delta_seconds: f32,
+
+    scale: f32,
+    unscaled_delta_seconds: f32,
+
+    frame: u64,
+    game_time: Duration,
 }
 
 impl Default for Time {
@@ -30,6 +36,10 @@ impl Default for Time {
             last_frame: Instant::now(),
             delta: Duration::ZERO,
             delta_seconds: 0.,
+            scale: 1.,
+            unscaled_delta_seconds: 0.,
+            frame: 0,
+            game_time: Duration::ZERO,
         }
     }
 }
@@ -50,18 +60,68 @@ impl Time {
     pub fn delta_seconds(&self) -> f32 {
         self.delta_seconds
     }
+
+    /// Frame time ignoring the time scale, for things that shouldn't slow
+    /// down along with the game.
+    #[inline]
+    pub fn unscaled_delta_seconds(&self) -> f32 {
+        self.unscaled_delta_seconds
+    }
+
+    #[inline]
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Speed up or slow down game time. Scales [`Time::delta`] and
+    /// [`Time::delta_seconds`] from the next frame.
+    #[inline]
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale.max(0.);
+    }
+
+    /// When the current frame started.
+    #[inline]
+    pub fn frame_start(&self) -> &Instant {
+        &self.last_frame
+    }
+
+    /// Frames ticked since startup.
+    #[inline]
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Total of every frame's [`Time::delta`], so follows the time scale and
+    /// stands still while paused.
+    #[inline]
+    pub fn game_time(&self) -> &Duration {
+        &self.game_time
+    }
 }
 
-pub fn tick_time(time: &mut Time) {
-    time.delta = time.last_frame.elapsed();
+/// Game time doesn't move while `paused`, though unscaled time does.
+pub fn tick_time(time: &mut Time, paused: bool) {
+    let delta = time.last_frame.elapsed();
+    time.unscaled_delta_seconds = delta.as_secs_f32();
+
+    time.delta = match paused {
+        true => Duration::ZERO,
+        false => delta.mul_f32(time.scale),
+    };
     time.delta_seconds = time.delta.as_secs_f32();
+    time.game_time += time.delta;
 
     time.last_frame = Instant::now();
+    time.frame += 1;
 }
 
 //====================================================================
 
-pub use winit::keyboard::KeyCode;
+pub use winit::{event::MouseButton, keyboard::KeyCode};
+
+/// Pixels of touchpad scrolling counted as one line of the mouse wheel.
+pub const PIXELS_PER_LINE: f32 = 20.;
 
 #[derive(Debug)]
 pub struct Input<T> {
@@ -99,6 +159,14 @@ where
     pub fn released(&self, input: T) -> bool {
         self.released.contains(&input)
     }
+
+    /// Forget everything held, pressed or released. Used when the window
+    /// loses focus and won't hear about keys being let go.
+    pub fn clear_all(&mut self) {
+        self.pressed.clear();
+        self.just_pressed.clear();
+        self.released.clear();
+    }
 }
 
 pub fn process_inputs<T>(input: &mut Input<T>, val: T, pressed: bool)
@@ -2,15 +2,18 @@
 
 use std::{marker::PhantomData, sync::Arc};
 
-use common::Size;
+use common::{LogicalSize, PhysicalSize};
+#[cfg(not(target_arch = "wasm32"))]
+use renderer::RendererSurface;
+use renderer::{error::RenderError, Renderer};
 use winit::{
     application::ApplicationHandler,
     event::StartCause,
-    event_loop::{ActiveEventLoop, EventLoop},
-    window::WindowAttributes,
+    event_loop::{ActiveEventLoop, EventLoop, EventLoopProxy},
+    window::{CursorGrabMode, WindowAttributes},
 };
 
-use crate::scene::Scene;
+use crate::{builder::EngineBuilder, error::EngineError, scene::Scene};
 
 use super::State;
 
@@ -19,10 +22,8 @@ use super::State;
 #[derive(Clone)]
 pub struct Window(pub Arc<winit::window::Window>);
 impl Window {
-    pub(super) fn new(event_loop: &ActiveEventLoop) -> Self {
-        let window = event_loop
-            .create_window(WindowAttributes::default())
-            .unwrap();
+    pub(super) fn new(event_loop: &ActiveEventLoop) -> Result<Self, EngineError> {
+        let window = event_loop.create_window(WindowAttributes::default())?;
 
         #[cfg(target_arch = "wasm32")]
         {
@@ -43,50 +44,173 @@ impl Window {
                     dst.append_child(&canvas).ok()?;
                     Some(())
                 })
-                .expect("Couldn't append canvas to document body.");
+                .ok_or(EngineError::AttachCanvas)?;
         }
 
-        Self(Arc::new(window))
+        Ok(Self(Arc::new(window)))
     }
 
     #[inline]
-    pub fn size(&self) -> Size<u32> {
-        let window_size = self.0.inner_size();
+    pub fn size(&self) -> PhysicalSize<u32> {
+        self.0.inner_size().into()
+    }
+
+    /// Lock and hide the cursor, or release it. Falls back to keeping the
+    /// cursor inside the window where it can't be locked. Returns false if
+    /// the platform refused.
+    pub fn set_cursor_grab(&self, grab: bool) -> bool {
+        let result = match grab {
+            true => self
+                .0
+                .set_cursor_grab(CursorGrabMode::Locked)
+                .or_else(|_| self.0.set_cursor_grab(CursorGrabMode::Confined)),
+            false => self.0.set_cursor_grab(CursorGrabMode::None),
+        };
 
-        Size {
-            width: window_size.width,
-            height: window_size.height,
+        match result {
+            Ok(()) => {
+                self.0.set_cursor_visible(!grab);
+                true
+            }
+            Err(e) => {
+                log::warn!("Unable to set cursor grab to {}: {}", grab, e);
+                false
+            }
         }
     }
+
+    /// Size of the window after undoing the display scale factor.
+    #[inline]
+    pub fn logical_size(&self) -> LogicalSize<f32> {
+        self.size().to_logical(self.0.scale_factor())
+    }
 }
 
 //====================================================================
 
+/// Events sent back to the event loop from async tasks.
+pub enum EngineEvent {
+    RendererReady(Window, Result<Renderer, RenderError>),
+}
+
 pub struct Runner<S: Scene> {
     state: Option<State>,
+    error: Option<EngineError>,
+    loading: bool,
+    proxy: EventLoopProxy<EngineEvent>,
+    /// Taken when the state is created.
+    builder: Option<EngineBuilder>,
     default_scene: PhantomData<S>,
 }
 
 impl<S: Scene> Runner<S> {
-    pub fn run() {
-        EventLoop::new()
-            .unwrap()
-            .run_app(&mut Self {
-                state: None,
-                default_scene: PhantomData,
-            })
-            .unwrap();
+    /// Run the app until it exits. Returns an error if the engine couldn't be
+    /// started, after it has been reported to the user.
+    #[inline]
+    pub fn run() -> Result<(), EngineError> {
+        Self::run_with(EngineBuilder::default())
+    }
+
+    /// Same as [`Runner::run`], with the engine configured by `builder`.
+    pub fn run_with(builder: EngineBuilder) -> Result<(), EngineError> {
+        let event_loop = EventLoop::<EngineEvent>::with_user_event().build()?;
+
+        let mut runner = Self {
+            state: None,
+            error: None,
+            loading: false,
+            proxy: event_loop.create_proxy(),
+            builder: Some(builder),
+            default_scene: PhantomData,
+        };
+
+        event_loop.run_app(&mut runner)?;
+
+        match runner.error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
     }
 }
 
-impl<S: Scene> ApplicationHandler for Runner<S> {
+// Show fatal errors somewhere the player will actually see them
+fn report_error(error: &EngineError) {
+    log::error!("Fatal error: {}", error);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    eprintln!("Unable to start the game: {}", error);
+
+    #[cfg(target_arch = "wasm32")]
+    if let Some(window) = web_sys::window() {
+        window
+            .alert_with_message(&format!("Unable to start the game: {}", error))
+            .ok();
+    }
+}
+
+impl<S: Scene> Runner<S> {
+    fn fail(&mut self, event_loop: &ActiveEventLoop, error: EngineError) {
+        report_error(&error);
+        self.error = Some(error);
+        event_loop.exit();
+    }
+}
+
+impl<S: Scene> ApplicationHandler<EngineEvent> for Runner<S> {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         log::trace!("App Resumed - Creating state.");
 
-        match self.state {
-            Some(_) => log::warn!("State already exists."),
-            None => self.state = Some(State::new::<S>(event_loop)),
+        if self.state.is_some() || self.loading {
+            log::warn!("State already exists.");
+            return;
         }
+
+        let window = match Window::new(event_loop) {
+            Ok(window) => window,
+            Err(error) => return self.fail(event_loop, error),
+        };
+
+        // Device creation is awaited off the event loop and the state is
+        // finished once the renderer is sent back
+        self.loading = true;
+        let proxy = self.proxy.clone();
+        let config = self
+            .builder
+            .as_ref()
+            .map(|builder| builder.renderer.clone())
+            .unwrap_or_default();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let surface = match RendererSurface::new(window.0.clone(), &config) {
+                Ok(surface) => surface,
+                Err(error) => return self.fail(event_loop, error.into()),
+            };
+
+            std::thread::spawn(move || {
+                let renderer = pollster::block_on(Renderer::from_surface_async(
+                    surface,
+                    window.size(),
+                    config,
+                ));
+
+                proxy
+                    .send_event(EngineEvent::RendererReady(window, renderer))
+                    .ok();
+            });
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(async move {
+            // Canvas may not have been laid out yet so use the requested size
+            let renderer =
+                Renderer::with_config_async(window.0.clone(), PhysicalSize::new(500, 450), config)
+                    .await;
+
+            proxy
+                .send_event(EngineEvent::RendererReady(window, renderer))
+                .ok();
+        });
     }
 
     #[inline]
@@ -101,16 +225,41 @@ impl<S: Scene> ApplicationHandler for Runner<S> {
         }
     }
 
-    fn new_events(&mut self, _event_loop: &ActiveEventLoop, cause: winit::event::StartCause) {
+    fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: winit::event::StartCause) {
         if let Some(state) = &mut self.state {
             if let StartCause::ResumeTimeReached { .. } = cause {
-                state.request_redraw();
+                state.frame_due(event_loop);
             }
         }
     }
 
-    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: ()) {
-        let _ = (event_loop, event);
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: EngineEvent) {
+        match event {
+            EngineEvent::RendererReady(window, renderer) => {
+                self.loading = false;
+
+                let mut renderer = match renderer {
+                    Ok(renderer) => renderer,
+                    Err(error) => return self.fail(event_loop, error.into()),
+                };
+
+                log::trace!("Renderer ready - Creating scene.");
+
+                // Window may have been resized while the device was being created
+                let size = window.size();
+                if size.width > 0 && size.height > 0 && size != renderer.size() {
+                    renderer.resize(size);
+                }
+
+                // Show something while the scene loads
+                renderer.present_clear_frame();
+
+                let builder = self.builder.take().unwrap_or_default();
+                let state = State::from_parts::<S>(window, renderer, builder);
+                state.request_redraw();
+                self.state = Some(state);
+            }
+        }
     }
 
     fn device_event(
@@ -0,0 +1,149 @@
+//====================================================================
+
+use std::io::Cursor;
+
+use engine::{
+    audio::Audio,
+    music::{AudioClip, LayerMix, LayeredMusic, LoopPoints, MusicLayer},
+};
+
+//====================================================================
+
+const SAMPLE_RATE: u32 = 10;
+
+// Mono clip whose samples count up from 1, one per frame
+fn counting_clip(frames: usize, sample_rate: u32) -> AudioClip {
+    AudioClip::new(
+        (1..=frames).map(|frame| frame as f32).collect::<Vec<_>>(),
+        1,
+        sample_rate,
+    )
+}
+
+fn render(audio: &Audio, frames: usize, channels: usize) -> Vec<f32> {
+    let mut out = vec![0.; frames * channels];
+    audio.render(&mut out);
+    out
+}
+
+fn wav(
+    spec: hound::WavSpec,
+    write: impl FnOnce(&mut hound::WavWriter<&mut Cursor<Vec<u8>>>),
+) -> Vec<u8> {
+    let mut bytes = Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut bytes, spec).unwrap();
+    write(&mut writer);
+    writer.finalize().unwrap();
+
+    bytes.into_inner()
+}
+
+//====================================================================
+
+#[test]
+fn integer_wavs_are_scaled() {
+    let spec = hound::WavSpec {
+        channels: 2,
+        sample_rate: 22_050,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+
+    let bytes = wav(spec, |writer| {
+        [i16::MIN, 0, 16_384, -16_384]
+            .into_iter()
+            .for_each(|sample| writer.write_sample(sample).unwrap());
+    });
+
+    let clip = AudioClip::from_wav(&bytes).unwrap();
+    assert_eq!(clip.channels(), 2);
+    assert_eq!(clip.sample_rate(), 22_050);
+    assert_eq!(clip.frames(), 2);
+    assert_eq!(clip.frame(0), Some(&[-1., 0.][..]));
+    assert_eq!(clip.frame(1), Some(&[0.5, -0.5][..]));
+    assert_eq!(clip.frame(2), None);
+}
+
+#[test]
+fn float_wavs_are_read_as_is() {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: 44_100,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+
+    let bytes = wav(spec, |writer| {
+        [0.25_f32, -0.75]
+            .into_iter()
+            .for_each(|sample| writer.write_sample(sample).unwrap());
+    });
+
+    let clip = AudioClip::from_wav(&bytes).unwrap();
+    assert_eq!(clip.frame(0), Some(&[0.25][..]));
+    assert_eq!(clip.frame(1), Some(&[-0.75][..]));
+}
+
+#[test]
+fn invalid_wavs_are_errors() {
+    assert!(AudioClip::from_wav(b"not a wav file").is_err());
+}
+
+#[test]
+fn sounds_play_once_at_their_volume() {
+    let audio = Audio::without_device(1, SAMPLE_RATE);
+    audio.play(&counting_clip(3, SAMPLE_RATE), 0.5);
+
+    assert_eq!(render(&audio, 5, 1), [0.5, 1., 1.5, 0., 0.]);
+    assert_eq!(render(&audio, 2, 1), [0., 0.]);
+}
+
+#[test]
+fn sounds_play_over_each_other() {
+    let audio = Audio::without_device(1, SAMPLE_RATE);
+    audio.play(&counting_clip(2, SAMPLE_RATE), 1.);
+    audio.play(&counting_clip(3, SAMPLE_RATE), 1.);
+
+    assert_eq!(render(&audio, 3, 1), [2., 4., 3.]);
+}
+
+#[test]
+fn mono_sounds_fill_every_channel() {
+    let audio = Audio::without_device(2, SAMPLE_RATE);
+    audio.play(&counting_clip(2, SAMPLE_RATE), 1.);
+
+    assert_eq!(render(&audio, 2, 2), [1., 1., 2., 2.]);
+}
+
+#[test]
+fn sounds_keep_their_speed_at_other_sample_rates() {
+    let audio = Audio::without_device(1, SAMPLE_RATE * 2);
+    audio.play(&counting_clip(2, SAMPLE_RATE), 1.);
+
+    assert_eq!(render(&audio, 5, 1), [1., 1., 2., 2., 0.]);
+}
+
+#[test]
+fn music_plays_until_replaced() {
+    let audio = Audio::without_device(1, SAMPLE_RATE);
+
+    let music = LayeredMusic::new(1, SAMPLE_RATE, LoopPoints { start: 0, end: 3 })
+        .with_layer(MusicLayer::Base, counting_clip(3, SAMPLE_RATE))
+        .with_crossfade(0.);
+    audio.set_music(Some(music));
+
+    // Silent until given a mix
+    assert_eq!(render(&audio, 2, 1), [0., 0.]);
+
+    audio.set_music_mix(LayerMix {
+        base: 1.,
+        ..Default::default()
+    });
+    assert_eq!(render(&audio, 4, 1), [3., 1., 2., 3.]);
+
+    audio.set_music(None);
+    assert!(!audio.has_music());
+    assert_eq!(render(&audio, 2, 1), [0., 0.]);
+}
+
+//====================================================================
@@ -0,0 +1,78 @@
+//====================================================================
+
+use std::{cell::RefCell, rc::Rc};
+
+use engine::events::EventBus;
+
+//====================================================================
+
+#[derive(Debug)]
+struct Ping(u32);
+
+#[derive(Debug)]
+struct Pong;
+
+// Handler recording every ping it sees under `name`
+fn record(
+    log: &Rc<RefCell<Vec<(&'static str, u32)>>>,
+    name: &'static str,
+) -> impl FnMut(&Ping) + 'static {
+    let log = log.clone();
+    move |ping: &Ping| log.borrow_mut().push((name, ping.0))
+}
+
+#[test]
+fn handlers_run_in_subscription_order() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut events = EventBus::default();
+
+    events.subscribe(record(&log, "first"));
+    events.subscribe(record(&log, "second"));
+    events.emit(Ping(1));
+
+    assert_eq!(*log.borrow(), [("first", 1), ("second", 1)]);
+}
+
+#[test]
+fn unsubscribed_handlers_stop_running() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut events = EventBus::default();
+
+    let first = events.subscribe(record(&log, "first"));
+    events.subscribe(record(&log, "second"));
+
+    events.unsubscribe(first);
+    events.emit(Ping(2));
+
+    assert_eq!(*log.borrow(), [("second", 2)]);
+}
+
+#[test]
+fn unsubscribing_drops_the_handler() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut events = EventBus::default();
+
+    let subscription = events.subscribe(record(&log, "only"));
+    assert_eq!(Rc::strong_count(&log), 2);
+
+    events.unsubscribe(subscription);
+    assert_eq!(Rc::strong_count(&log), 1);
+    assert!(!events.has_subscribers::<Ping>());
+}
+
+#[test]
+fn subscriptions_only_remove_their_own_handler() {
+    let mut events = EventBus::default();
+
+    let ping = events.subscribe(|_: &Ping| {});
+    let pong = events.subscribe(|_: &Pong| {});
+
+    events.unsubscribe(ping);
+    assert!(events.has_subscribers::<Pong>());
+
+    events.unsubscribe(pong);
+    assert!(!events.has_subscribers::<Pong>());
+    events.emit(Pong);
+}
+
+//====================================================================
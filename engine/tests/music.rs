@@ -0,0 +1,70 @@
+//====================================================================
+
+use engine::music::{AudioClip, LayerMix, LayeredMusic, LoopPoints, MusicLayer};
+
+//====================================================================
+
+const SAMPLE_RATE: u32 = 10;
+
+// Mono clip whose samples count up from 0, one per frame
+fn counting_clip(frames: usize) -> AudioClip {
+    AudioClip::new(
+        (0..frames).map(|frame| frame as f32).collect::<Vec<_>>(),
+        1,
+        SAMPLE_RATE,
+    )
+}
+
+fn base_only() -> LayerMix {
+    LayerMix {
+        base: 1.,
+        ..Default::default()
+    }
+}
+
+//====================================================================
+
+#[test]
+fn loops_on_exact_frame() {
+    let mut music = LayeredMusic::new(1, SAMPLE_RATE, LoopPoints { start: 2, end: 6 })
+        .with_layer(MusicLayer::Base, counting_clip(8))
+        .with_crossfade(0.);
+    music.set_mix(base_only());
+
+    let mut out = [0.; 10];
+    music.mix(&mut out);
+
+    assert_eq!(out, [0., 1., 2., 3., 4., 5., 2., 3., 4., 5.]);
+    assert_eq!(music.position(), 2);
+}
+
+#[test]
+fn crossfade_reaches_target_in_time() {
+    let mut music = LayeredMusic::new(1, SAMPLE_RATE, LoopPoints { start: 0, end: 100 })
+        .with_layer(MusicLayer::Base, counting_clip(100))
+        .with_crossfade(1.);
+    music.set_mix(base_only());
+
+    // Half a second in
+    music.mix(&mut [0.; 5]);
+    assert!((music.gain(MusicLayer::Base) - 0.5).abs() < 1e-5);
+
+    music.mix(&mut [0.; 5]);
+    assert_eq!(music.gain(MusicLayer::Base), 1.);
+    assert_eq!(music.gain(MusicLayer::Tension), 0.);
+}
+
+#[test]
+fn mismatched_layers_are_skipped() {
+    let mut music = LayeredMusic::new(2, SAMPLE_RATE, LoopPoints { start: 0, end: 4 })
+        .with_layer(MusicLayer::Base, counting_clip(4))
+        .with_crossfade(0.);
+    music.set_mix(base_only());
+
+    let mut out = [0.; 8];
+    music.mix(&mut out);
+
+    assert_eq!(out, [0.; 8]);
+}
+
+//====================================================================
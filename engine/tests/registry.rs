@@ -0,0 +1,103 @@
+//====================================================================
+
+use common::Transform;
+use engine::registry::{ComponentRegistry, Saved};
+use hecs::World;
+use renderer::pipelines::{Hidden, Opacity};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+//====================================================================
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+struct Health(u32);
+
+// Never registered, so never saved
+#[derive(Debug)]
+struct Scratch;
+
+fn registry() -> ComponentRegistry {
+    let mut registry = ComponentRegistry::with_engine_components();
+    registry.register_persistent::<Health>("Health");
+    registry
+}
+
+//====================================================================
+
+#[test]
+fn saved_entities_load_back() {
+    let registry = registry();
+
+    let mut world = World::new();
+    let transform = Transform::from_translation(glam::vec3(1., 2., 3.));
+    world.spawn((Saved, transform.clone(), Opacity(0.5), Health(7), Scratch));
+    world.spawn((Transform::default(), Health(1)));
+
+    let saved = registry.save_world(&world);
+    assert_eq!(saved.len(), 1);
+
+    // Saves hold the entities as json
+    let json = serde_json::to_string(&saved).unwrap();
+    let saved = serde_json::from_str::<Vec<Map<String, Value>>>(&json).unwrap();
+
+    let mut loaded = World::new();
+    let entities = registry.load_world(&mut loaded, &saved);
+    assert_eq!(entities.len(), 1);
+
+    let entity = loaded.entity(entities[0]).unwrap();
+    assert!(entity.has::<Saved>());
+    assert_eq!(*entity.get::<&Transform>().unwrap(), transform);
+    assert_eq!(*entity.get::<&Opacity>().unwrap(), Opacity(0.5));
+    assert_eq!(*entity.get::<&Health>().unwrap(), Health(7));
+    assert!(!entity.has::<Scratch>());
+
+    // Saving again gives the same components
+    assert_eq!(registry.save_world(&loaded), saved);
+}
+
+#[test]
+fn unknown_components_are_skipped() {
+    let registry = registry();
+
+    let mut components = Map::new();
+    components.insert(String::from("Health"), Value::from(3));
+    components.insert(String::from("Missing"), Value::from(1));
+    components.insert(String::from("Opacity"), Value::from("invalid"));
+
+    let mut world = World::new();
+    let entity = registry.load_entity(&mut world, &components);
+
+    let entity = world.entity(entity).unwrap();
+    assert_eq!(*entity.get::<&Health>().unwrap(), Health(3));
+    assert!(!entity.has::<Opacity>());
+}
+
+#[test]
+fn defaults_are_added_by_name() {
+    let registry = registry();
+
+    let mut world = World::new();
+    let entity = world.spawn((Scratch,));
+
+    assert!(registry.insert_default(&mut world, entity, "Hidden"));
+    assert!(registry.insert_default(&mut world, entity, "Opacity"));
+    assert!(!registry.insert_default(&mut world, entity, "Scratch"));
+    assert!(!registry.insert_default(&mut world, entity, "SpriteLayer"));
+
+    assert!(world.get::<&Hidden>(entity).is_ok());
+    assert_eq!(*world.get::<&Opacity>(entity).unwrap(), Opacity(1.));
+
+    world.despawn(entity).unwrap();
+    assert!(!registry.insert_default(&mut world, entity, "Hidden"));
+}
+
+#[test]
+fn only_persistent_components_are_named() {
+    let names = registry().persistent_names();
+
+    assert!(names.contains(&"Transform"));
+    assert!(names.contains(&"Health"));
+    assert!(!names.contains(&"SpriteLayer"));
+}
+
+//====================================================================
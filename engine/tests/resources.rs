@@ -0,0 +1,45 @@
+//====================================================================
+
+use engine::resources::Resources;
+
+//====================================================================
+
+#[derive(Debug, Default, PartialEq)]
+struct Counter(u32);
+
+#[derive(Debug, PartialEq)]
+struct Name(&'static str);
+
+#[test]
+fn resources_hold_one_of_each_type() {
+    let mut resources = Resources::default();
+
+    assert_eq!(resources.insert(Counter(1)), None);
+    assert_eq!(resources.insert(Name("first")), None);
+    assert_eq!(resources.insert(Counter(2)), Some(Counter(1)));
+
+    assert_eq!(resources.get::<Counter>(), Some(&Counter(2)));
+    assert_eq!(resources.get::<Name>(), Some(&Name("first")));
+}
+
+#[test]
+fn removed_resources_are_gone() {
+    let mut resources = Resources::default();
+    resources.insert(Name("taken"));
+
+    assert_eq!(resources.remove::<Name>(), Some(Name("taken")));
+    assert_eq!(resources.remove::<Name>(), None);
+    assert!(!resources.contains::<Name>());
+}
+
+#[test]
+fn missing_resources_start_from_their_default() {
+    let mut resources = Resources::default();
+
+    resources.get_or_default::<Counter>().0 += 1;
+    resources.get_or_default::<Counter>().0 += 1;
+
+    assert_eq!(resources.get::<Counter>(), Some(&Counter(2)));
+}
+
+//====================================================================
@@ -0,0 +1,23 @@
+//====================================================================
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use game::combat;
+use rand::{rngs::StdRng, SeedableRng};
+
+//====================================================================
+
+fn turn_order(c: &mut Criterion) {
+    let speeds = (0..100).map(|id| (1 + id % 10, id)).collect::<Vec<_>>();
+    let mut rng = StdRng::seed_from_u64(0);
+
+    c.bench_function("turn_order_100_combatants", |b| {
+        b.iter(|| combat::roll_turn_order(black_box(&speeds), &mut rng))
+    });
+}
+
+//====================================================================
+
+criterion_group!(benches, turn_order);
+criterion_main!(benches);
+
+//====================================================================
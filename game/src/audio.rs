@@ -0,0 +1,240 @@
+//====================================================================
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use engine::{
+    audio::Audio,
+    events::{EventBus, Subscription},
+    haptics::{Haptics, Rumble},
+    music::AudioClip,
+};
+
+use crate::{
+    data::{GameData, RumbleDef, RumblePulse, SoundDef, SOUNDS_DIR},
+    scenes::battle_scene::BattleEvent,
+    settings::HapticsSettings,
+};
+
+//====================================================================
+
+/// Names of game events that sounds and gamepad rumble are mapped to in the
+/// audio data files.
+pub mod sound_event {
+    pub const MENU_MOVE: &str = "menu_move";
+    pub const MENU_CONFIRM: &str = "menu_confirm";
+    /// Any attack that lands. All damage is physical for now.
+    pub const HIT_PHYSICAL: &str = "hit_physical";
+    /// Critical hits, on top of [`HIT_PHYSICAL`].
+    pub const CRIT: &str = "crit";
+    pub const HEAL: &str = "heal";
+    pub const VICTORY: &str = "victory";
+
+    pub const ALL: [&str; 6] = [MENU_MOVE, MENU_CONFIRM, HIT_PHYSICAL, CRIT, HEAL, VICTORY];
+}
+
+/// Sent through the engine's event bus whenever a menu is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuEvent {
+    /// The selection moved to another option.
+    Moved,
+    Confirmed,
+}
+
+/// Sound picked for a game event, waiting to be played.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoundCue {
+    pub event: &'static str,
+    pub sound: String,
+    pub volume: f32,
+}
+
+/// Rumble picked for a game event, already scaled by the haptics intensity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RumbleCue {
+    pub event: &'static str,
+    pub pulses: Vec<RumblePulse>,
+}
+
+#[derive(Default)]
+struct Routes {
+    /// Keyed by event name.
+    sounds: HashMap<String, SoundDef>,
+    /// Decoded sound files by file name.
+    clips: HashMap<String, AudioClip>,
+    /// Keyed by event name.
+    rumbles: HashMap<String, RumbleDef>,
+    /// None while rumble is turned off.
+    rumble_scale: Option<f32>,
+
+    queued_sounds: Vec<SoundCue>,
+    queued_rumbles: Vec<RumbleCue>,
+}
+
+impl Routes {
+    fn queue(&mut self, event: &'static str) {
+        if let Some(def) = self.sounds.get(event) {
+            self.queued_sounds.push(SoundCue {
+                event,
+                sound: def.sound.clone(),
+                volume: def.volume,
+            });
+        }
+
+        if let (Some(scale), Some(def)) = (self.rumble_scale, self.rumbles.get(event)) {
+            self.queued_rumbles.push(RumbleCue {
+                event,
+                pulses: def
+                    .pulses
+                    .iter()
+                    .map(|pulse| RumblePulse {
+                        strong: (pulse.strong * scale).clamp(0., 1.),
+                        weak: (pulse.weak * scale).clamp(0., 1.),
+                        millis: pulse.millis,
+                    })
+                    .collect(),
+            });
+        }
+    }
+}
+
+/// Turns game events into [`SoundCue`]s and [`RumbleCue`]s using the
+/// definitions from the data files, so new sounds and rumble only need a data
+/// change. Each scene with menus keeps one for as long as it runs.
+pub struct AudioEventRouter {
+    routes: Rc<RefCell<Routes>>,
+    subscriptions: Vec<Subscription>,
+}
+
+impl AudioEventRouter {
+    pub fn new(events: &mut EventBus, data: &GameData, haptics: HapticsSettings) -> Self {
+        let routes = Rc::new(RefCell::new(Routes {
+            rumble_scale: haptics.scale(),
+            ..Default::default()
+        }));
+
+        let battle_routes = routes.clone();
+        let battle = events.subscribe(move |event: &BattleEvent| {
+            let event = match event {
+                BattleEvent::Hit { .. } => sound_event::HIT_PHYSICAL,
+                BattleEvent::CriticalHit { .. } => sound_event::CRIT,
+                BattleEvent::Healed { .. } => sound_event::HEAL,
+                BattleEvent::Ended { won: true } => sound_event::VICTORY,
+                _ => return,
+            };
+            battle_routes.borrow_mut().queue(event);
+        });
+
+        let menu_routes = routes.clone();
+        let menu = events.subscribe(move |event: &MenuEvent| {
+            let event = match event {
+                MenuEvent::Moved => sound_event::MENU_MOVE,
+                MenuEvent::Confirmed => sound_event::MENU_CONFIRM,
+            };
+            menu_routes.borrow_mut().queue(event);
+        });
+
+        let mut router = Self {
+            routes,
+            subscriptions: vec![battle, menu],
+        };
+        router.set_data(data);
+        router
+    }
+
+    /// Stop routing events, once the scene they come from is over.
+    pub fn unsubscribe(&mut self, events: &mut EventBus) {
+        self.subscriptions
+            .drain(..)
+            .for_each(|subscription| events.unsubscribe(subscription));
+    }
+
+    /// Replace the sound and rumble definitions, such as after the data files
+    /// are reloaded.
+    pub fn set_data(&mut self, data: &GameData) {
+        let mut routes = self.routes.borrow_mut();
+
+        routes.sounds = data
+            .sounds
+            .iter()
+            .map(|def| (def.event.clone(), def.clone()))
+            .collect();
+
+        // Missing sounds are left silent rather than failing the data. No
+        // sounds ship with the game, so this is only worth a debug log
+        routes.clips = data
+            .sounds
+            .iter()
+            .filter_map(|def| {
+                let bytes = match data.audio.get(&def.sound) {
+                    Some(bytes) => bytes,
+                    None => {
+                        log::debug!(
+                            "Sound '{}' for '{}' not found in '{}'",
+                            def.sound,
+                            def.event,
+                            SOUNDS_DIR
+                        );
+                        return None;
+                    }
+                };
+
+                match AudioClip::from_wav(bytes) {
+                    Ok(clip) => Some((def.sound.clone(), clip)),
+                    Err(e) => {
+                        log::warn!("Sound '{}': {}", def.sound, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        routes.rumbles = data
+            .rumbles
+            .iter()
+            .map(|def| (def.event.clone(), def.clone()))
+            .collect();
+    }
+
+    /// Sound cues queued since the last call, in the order their events
+    /// happened.
+    #[inline]
+    pub fn take_sounds(&mut self) -> Vec<SoundCue> {
+        std::mem::take(&mut self.routes.borrow_mut().queued_sounds)
+    }
+
+    /// Play every sound and rumble cue queued since the last call.
+    pub fn play(&mut self, audio: &Audio, haptics: &mut Haptics) {
+        let cues = self.take_sounds();
+        let rumbles = self.take_rumbles();
+        let routes = self.routes.borrow();
+
+        cues.iter().for_each(|cue| {
+            if let Some(clip) = routes.clips.get(&cue.sound) {
+                audio.play(clip, cue.volume);
+            }
+        });
+
+        rumbles.iter().for_each(|cue| {
+            let pattern = cue
+                .pulses
+                .iter()
+                .map(|pulse| Rumble {
+                    strong: pulse.strong,
+                    weak: pulse.weak,
+                    millis: pulse.millis,
+                })
+                .collect::<Vec<_>>();
+
+            haptics.rumble(&pattern);
+        });
+    }
+
+    /// Rumble cues queued since the last call, in the order their events
+    /// happened. Always empty while rumble is turned off.
+    #[inline]
+    pub fn take_rumbles(&mut self) -> Vec<RumbleCue> {
+        std::mem::take(&mut self.routes.borrow_mut().queued_rumbles)
+    }
+}
+
+//====================================================================
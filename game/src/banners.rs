@@ -0,0 +1,160 @@
+//====================================================================
+
+use std::{cell::RefCell, collections::VecDeque, rc::Rc, time::Duration};
+
+use common::Transform;
+use engine::{
+    events::{EventBus, Subscription},
+    lifetime::DespawnAfter,
+    StateInner,
+};
+use hecs::Entity;
+use renderer::pipelines::ui3d_pipeline::Ui3d;
+
+use crate::scenes::battle_scene::BattleEvent;
+
+//====================================================================
+
+/// Seconds a banner is on screen, including sliding in and out.
+const BANNER_TIME: f32 = 1.6;
+/// Seconds spent sliding in and again sliding out.
+const SLIDE_TIME: f32 = 0.3;
+/// How far to the side of the camera banners slide in from.
+const SLIDE_DISTANCE: f32 = 4.;
+
+const BANNER_COLOR: [f32; 4] = [0.15, 0.15, 0.2, 0.8];
+
+//====================================================================
+
+struct Shown {
+    entity: Entity,
+    elapsed: f32,
+}
+
+/// Announces rounds and turns with a banner that slides across the front of
+/// the camera.
+pub struct Banners {
+    queue: Rc<RefCell<VecDeque<String>>>,
+    shown: Option<Shown>,
+    subscription: Option<Subscription>,
+}
+
+impl Banners {
+    /// Queue banners from [`BattleEvent`]s. Nothing is shown when `enabled`
+    /// is false.
+    pub fn new(events: &mut EventBus, enabled: bool) -> Self {
+        let queue = Rc::new(RefCell::new(VecDeque::new()));
+
+        let subscription = enabled.then(|| {
+            let queue = queue.clone();
+            events.subscribe(move |event: &BattleEvent| Self::on_event(&queue, event))
+        });
+
+        Self {
+            queue,
+            shown: None,
+            subscription,
+        }
+    }
+
+    fn on_event(queue: &RefCell<VecDeque<String>>, event: &BattleEvent) {
+        let text = match event {
+            BattleEvent::RoundStarted { round } => format!("Round {}", round),
+            BattleEvent::TurnStarted { character, .. } => format!("{}'s Turn", character),
+            _ => return,
+        };
+
+        queue.borrow_mut().push_back(text);
+    }
+
+    /// Stop queuing banners, once the battle is over.
+    pub fn unsubscribe(&mut self, events: &mut EventBus) {
+        if let Some(subscription) = self.subscription.take() {
+            events.unsubscribe(subscription);
+        }
+    }
+
+    pub fn close(&mut self, state: &mut StateInner) {
+        self.queue.borrow_mut().clear();
+
+        if let Some(shown) = self.shown.take() {
+            state.despawn(shown.entity).ok();
+        }
+    }
+
+    pub fn tick(&mut self, state: &mut StateInner) {
+        if let Some(shown) = &mut self.shown {
+            let waiting = !self.queue.borrow().is_empty();
+
+            shown.elapsed += state.time.delta_seconds();
+
+            // Hurry out of the way once the banner has been seen if more are waiting
+            if waiting && shown.elapsed > SLIDE_TIME && shown.elapsed < BANNER_TIME - SLIDE_TIME {
+                shown.elapsed = BANNER_TIME - SLIDE_TIME;
+            }
+
+            if shown.elapsed < BANNER_TIME {
+                Self::animate(state, shown);
+                return;
+            }
+
+            state.despawn(shown.entity).ok();
+            self.shown = None;
+        }
+
+        let text = match self.queue.borrow_mut().pop_front() {
+            Some(text) => text,
+            None => return,
+        };
+
+        let entity = state.world.spawn((
+            Transform::default(),
+            Ui3d {
+                options: vec![text],
+                font_size: 40.,
+                ..Default::default()
+            },
+            // Banners hurrying out of the way despawn before this
+            DespawnAfter(Duration::from_secs_f32(BANNER_TIME)),
+        ));
+
+        let shown = Shown {
+            entity,
+            elapsed: 0.,
+        };
+        Self::animate(state, &shown);
+
+        self.shown = Some(shown);
+    }
+
+    // Slide in from the right, hold in the centre, then slide out to the left
+    // while fading
+    fn animate(state: &mut StateInner, shown: &Shown) {
+        let slide_in = smoothstep(shown.elapsed / SLIDE_TIME);
+        let slide_out = smoothstep((shown.elapsed - (BANNER_TIME - SLIDE_TIME)) / SLIDE_TIME);
+
+        let offset = (1. - slide_in) * SLIDE_DISTANCE - slide_out * SLIDE_DISTANCE;
+        let alpha = slide_in * (1. - slide_out);
+
+        let camera = &state.renderer.camera.camera;
+        let right = camera.rotation * glam::Vec3::X;
+        let position = camera.translation + camera.forward() * 7. + right * offset;
+
+        if let Ok(mut transform) = state.world.get::<&mut Transform>(shown.entity) {
+            transform.translation = position;
+        }
+
+        if let Ok(mut ui) = state.world.get::<&mut Ui3d>(shown.entity) {
+            let [r, g, b, a] = BANNER_COLOR;
+            ui.menu_color = [r, g, b, a * alpha];
+            ui.selection_color = ui.menu_color;
+        }
+    }
+}
+
+pub(crate) fn smoothstep(t: f32) -> f32 {
+    let t = t.clamp(0., 1.);
+    t * t * (3. - 2. * t)
+}
+
+//====================================================================
@@ -1,20 +1,284 @@
 //====================================================================
 
-use engine::{tools::KeyCode, StateInner};
+use engine::{
+    tools::{KeyCode, MouseButton},
+    StateInner,
+};
 
 //====================================================================
 
+pub const MOVE_LEFT_KEY: KeyCode = KeyCode::KeyA;
+pub const MOVE_RIGHT_KEY: KeyCode = KeyCode::KeyD;
+pub const MOVE_UP_KEY: KeyCode = KeyCode::Space;
+pub const MOVE_DOWN_KEY: KeyCode = KeyCode::ShiftLeft;
+pub const MOVE_FORWARDS_KEY: KeyCode = KeyCode::KeyW;
+pub const MOVE_BACKWARDS_KEY: KeyCode = KeyCode::KeyS;
+
+pub const LOOK_LEFT_KEY: KeyCode = KeyCode::KeyJ;
+pub const LOOK_RIGHT_KEY: KeyCode = KeyCode::KeyL;
+pub const LOOK_UP_KEY: KeyCode = KeyCode::KeyI;
+pub const LOOK_DOWN_KEY: KeyCode = KeyCode::KeyK;
+
+/// Switches between keyboard and orbit camera controls.
+pub const CAMERA_MODE_KEY: KeyCode = KeyCode::KeyO;
+/// Held and dragged to orbit the camera.
+pub const ORBIT_BUTTON: MouseButton = MouseButton::Left;
+/// Held to look around with the mouse in free mode.
+pub const MOUSE_LOOK_BUTTON: MouseButton = MouseButton::Right;
+
 const CAMERA_MOVE_SPEED: f32 = 100.;
 
+/// Radians turned per unit of raw mouse movement, before the sensitivity
+/// setting is applied.
+const MOUSE_LOOK_SPEED: f32 = 0.003;
+
+/// Radians turned per pixel dragged.
+const ORBIT_SENSITIVITY: f32 = 0.005;
+/// How quickly orbiting slows down after letting go, higher stops sooner.
+const ORBIT_DAMPING: f32 = 6.;
+/// Orbit speed in radians per second below which it stops.
+const ORBIT_MIN_SPEED: f32 = 0.01;
+/// Pitch limits, so the camera can't flip over the top or go under the ground.
+const MIN_PITCH: f32 = -0.1;
+const MAX_PITCH: f32 = 1.3;
+
+/// Fraction of the distance zoomed in by each line scrolled.
+const ZOOM_STEP: f32 = 0.1;
+const MIN_ZOOM_DISTANCE: f32 = 80.;
+const MAX_ZOOM_DISTANCE: f32 = 1500.;
+
+/// Pixels from the edge of the window where the cursor starts edge scrolling.
+const EDGE_SCROLL_MARGIN: f32 = 16.;
+
+//====================================================================
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CameraMode {
+    /// Fly around with the movement and look keys.
+    #[default]
+    Free,
+    /// Drag with the mouse to circle a focus point and scroll to zoom.
+    Orbit,
+}
+
+/// Box in world space the camera is kept inside.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraBounds {
+    pub min: glam::Vec3,
+    pub max: glam::Vec3,
+}
+
+impl CameraBounds {
+    #[inline]
+    pub fn clamp(&self, translation: glam::Vec3) -> glam::Vec3 {
+        translation.clamp(self.min, self.max.max(self.min))
+    }
+}
+
+/// Camera controls for a scene, switched between modes with
+/// [`CAMERA_MODE_KEY`].
+pub struct CameraController {
+    mode: CameraMode,
+    orbit: OrbitCamera,
+    bounds: Option<CameraBounds>,
+    edge_scroll: bool,
+    mouse_sensitivity: f32,
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self {
+            mode: CameraMode::default(),
+            orbit: OrbitCamera::default(),
+            bounds: None,
+            edge_scroll: false,
+            mouse_sensitivity: 1.,
+        }
+    }
+}
+
+impl CameraController {
+    /// Keep the camera inside `bounds` while the controller is ticked.
+    #[inline]
+    pub fn with_bounds(mut self, bounds: CameraBounds) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    /// Move the camera across the ground while the cursor is at the edge of
+    /// the window, in free mode.
+    #[inline]
+    pub fn with_edge_scroll(mut self, edge_scroll: bool) -> Self {
+        self.edge_scroll = edge_scroll;
+        self
+    }
+
+    /// Multiplier on how far the camera turns with mouse look.
+    #[inline]
+    pub fn with_mouse_sensitivity(mut self, sensitivity: f32) -> Self {
+        self.mouse_sensitivity = sensitivity;
+        self
+    }
+
+    /// `translation` moved inside the bounds, if there are any.
+    #[inline]
+    pub fn clamp(&self, translation: glam::Vec3) -> glam::Vec3 {
+        match &self.bounds {
+            Some(bounds) => bounds.clamp(translation),
+            None => translation,
+        }
+    }
+
+    /// Move the camera for this frame's input. Orbiting circles `focus`.
+    pub fn tick(&mut self, state: &mut StateInner, focus: glam::Vec3) {
+        if state.keys.just_pressed(CAMERA_MODE_KEY) {
+            self.mode = match self.mode {
+                CameraMode::Free => CameraMode::Orbit,
+                CameraMode::Orbit => CameraMode::Free,
+            };
+            self.orbit = OrbitCamera::default();
+            state.set_cursor_grab(false);
+
+            log::info!("Camera mode set to {:?}", self.mode);
+        }
+
+        match self.mode {
+            CameraMode::Free => {
+                move_camera(state);
+                mouse_look(state, self.mouse_sensitivity);
+
+                if self.edge_scroll {
+                    edge_scroll(state);
+                }
+            }
+            CameraMode::Orbit => self.orbit.tick(state, focus),
+        }
+
+        let camera = &mut state.renderer.camera.camera;
+        camera.translation = self.clamp(camera.translation);
+    }
+}
+
+// Pan along the ground towards whichever edges of the window the cursor is at
+fn edge_scroll(state: &mut StateInner) {
+    let cursor = match state.cursor {
+        Some(cursor) => cursor,
+        None => return,
+    };
+    let size = state.window.size();
+
+    let edge = |position: f32, length: u32| {
+        let near = position < EDGE_SCROLL_MARGIN;
+        let far = position > length as f32 - EDGE_SCROLL_MARGIN;
+        (far as i8 - near as i8) as f32
+    };
+
+    let x_dir = edge(cursor.x, size.width);
+    // Window y goes down, so the top edge scrolls forwards
+    let z_dir = -edge(cursor.y, size.height);
+
+    if x_dir == 0. && z_dir == 0. {
+        return;
+    }
+
+    let camera = &mut state.renderer.camera.camera;
+    let flatten = |direction: glam::Vec3| (direction * glam::vec3(1., 0., 1.)).normalize_or_zero();
+
+    let direction = flatten(camera.right()) * x_dir + flatten(camera.forward()) * z_dir;
+
+    camera.translation +=
+        direction.normalize_or_zero() * CAMERA_MOVE_SPEED * state.time.delta_seconds();
+}
+
+/// Yaw and pitch around a focus point, carrying on turning for a moment after
+/// the mouse is let go.
+#[derive(Default)]
+struct OrbitCamera {
+    yaw: f32,
+    pitch: f32,
+    distance: f32,
+    /// Radians per second in yaw and pitch.
+    velocity: glam::Vec2,
+    last_cursor: Option<glam::Vec2>,
+}
+
+impl OrbitCamera {
+    fn tick(&mut self, state: &mut StateInner, focus: glam::Vec3) {
+        let delta = state.time.unscaled_delta_seconds();
+
+        let dragged = match (state.mouse.pressed(ORBIT_BUTTON), state.cursor) {
+            (true, Some(cursor)) => {
+                let dragged = self.last_cursor.map(|last| cursor - last);
+                self.last_cursor = Some(cursor);
+                dragged
+            }
+            _ => {
+                self.last_cursor = None;
+                None
+            }
+        };
+
+        let moving = self.velocity.length() > ORBIT_MIN_SPEED;
+        let zoomed = state.scroll != 0.;
+
+        // Leave the camera alone while idle, so tweens and cinematics can
+        // move it
+        if dragged.is_none() && !moving && !zoomed {
+            self.velocity = glam::Vec2::ZERO;
+            return;
+        }
+
+        // Pick up from wherever the camera has been moved to since
+        if !moving {
+            self.sync(state, focus);
+        }
+
+        match dragged {
+            Some(dragged) => {
+                self.velocity = match delta > 0. {
+                    true => dragged * ORBIT_SENSITIVITY / delta,
+                    false => glam::Vec2::ZERO,
+                }
+            }
+            None => self.velocity *= (-ORBIT_DAMPING * delta).exp(),
+        }
+
+        self.yaw += self.velocity.x * delta;
+        self.pitch = (self.pitch + self.velocity.y * delta).clamp(MIN_PITCH, MAX_PITCH);
+
+        self.distance = (self.distance * (1. - ZOOM_STEP).powf(state.scroll))
+            .clamp(MIN_ZOOM_DISTANCE, MAX_ZOOM_DISTANCE);
+
+        let rotation =
+            glam::Quat::from_rotation_y(self.yaw) * glam::Quat::from_rotation_x(self.pitch);
+
+        let camera = &mut state.renderer.camera.camera;
+        camera.rotation = rotation;
+        camera.translation = focus - rotation * glam::Vec3::Z * self.distance;
+    }
+
+    // Take the yaw, pitch and distance from the camera's current placement
+    fn sync(&mut self, state: &StateInner, focus: glam::Vec3) {
+        let translation = state.renderer.camera.camera.translation;
+        let look = (focus - translation).normalize_or(glam::Vec3::Z);
+
+        self.yaw = look.x.atan2(look.z);
+        self.pitch = (-look.y.asin()).clamp(MIN_PITCH, MAX_PITCH);
+        self.distance = translation.distance(focus);
+    }
+}
+
+//====================================================================
+
 pub fn move_camera(state: &mut StateInner) {
-    let left = state.keys.pressed(KeyCode::KeyA);
-    let right = state.keys.pressed(KeyCode::KeyD);
+    let left = state.keys.pressed(MOVE_LEFT_KEY);
+    let right = state.keys.pressed(MOVE_RIGHT_KEY);
 
-    let up = state.keys.pressed(KeyCode::Space);
-    let down = state.keys.pressed(KeyCode::ShiftLeft);
+    let up = state.keys.pressed(MOVE_UP_KEY);
+    let down = state.keys.pressed(MOVE_DOWN_KEY);
 
-    let forwards = state.keys.pressed(KeyCode::KeyW);
-    let backwards = state.keys.pressed(KeyCode::KeyS);
+    let forwards = state.keys.pressed(MOVE_FORWARDS_KEY);
+    let backwards = state.keys.pressed(MOVE_BACKWARDS_KEY);
 
     let x_dir = (right as i8 - left as i8) as f32;
     let y_dir = (up as i8 - down as i8) as f32;
@@ -35,11 +299,11 @@ pub fn move_camera(state: &mut StateInner) {
 
     //--------------------------------------------------
 
-    let look_left = state.keys.pressed(KeyCode::KeyJ);
-    let look_right = state.keys.pressed(KeyCode::KeyL);
+    let look_left = state.keys.pressed(LOOK_LEFT_KEY);
+    let look_right = state.keys.pressed(LOOK_RIGHT_KEY);
 
-    let look_up = state.keys.pressed(KeyCode::KeyI);
-    let look_down = state.keys.pressed(KeyCode::KeyK);
+    let look_up = state.keys.pressed(LOOK_UP_KEY);
+    let look_down = state.keys.pressed(LOOK_DOWN_KEY);
 
     let yaw = (look_right as i8 - look_left as i8) as f32;
     let pitch = (look_down as i8 - look_up as i8) as f32;
@@ -52,4 +316,38 @@ pub fn move_camera(state: &mut StateInner) {
     );
 }
 
+/// Turn the camera with raw mouse movement while [`MOUSE_LOOK_BUTTON`] is
+/// held, grabbing the cursor so it stays put.
+pub fn mouse_look(state: &mut StateInner, sensitivity: f32) {
+    let looking = state.mouse.pressed(MOUSE_LOOK_BUTTON);
+
+    // Also catches the button being lost along with window focus
+    if state.mouse.just_pressed(MOUSE_LOOK_BUTTON) {
+        state.set_cursor_grab(true);
+    } else if !looking && state.cursor_grabbed() {
+        state.set_cursor_grab(false);
+    }
+
+    if !looking {
+        return;
+    }
+
+    let turn = state.mouse_delta * MOUSE_LOOK_SPEED * sensitivity;
+
+    if turn != glam::Vec2::ZERO {
+        state.renderer.camera.camera.rotate_camera(turn.x, turn.y);
+    }
+}
+
+//====================================================================
+
+/// Camera rotation at `from` looking towards `to`, without any roll.
+pub fn look_rotation(from: glam::Vec3, to: glam::Vec3) -> glam::Quat {
+    let look = (to - from).normalize_or(glam::Vec3::Z);
+    let yaw = look.x.atan2(look.z);
+    let pitch = -look.y.asin();
+
+    glam::Quat::from_rotation_y(yaw) * glam::Quat::from_rotation_x(pitch)
+}
+
 //====================================================================
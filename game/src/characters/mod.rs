@@ -1,18 +1,40 @@
 //====================================================================
 
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, HashSet},
     f32::consts::{FRAC_PI_2, PI, TAU},
+    sync::Arc,
 };
 
-use actions::ActionId;
+use battle_core::characters::actions::ActionId;
 use common::Transform;
-use engine::StateInner;
+use engine::{picking::Pickable, prefab::Prefabs, StateInner};
 use glam::Vec3Swizzles;
 use hecs::{Entity, World};
-use renderer::{pipelines::texture_pipeline::Sprite, texture_storage::DefaultTexture};
+use renderer::{
+    animation::IdleAnimation,
+    pipelines::texture_pipeline::{PaletteSwap, Sprite, SpriteStack, StackedSprite},
+    texture_storage::{DefaultTexture, LoadedTexture},
+};
+
+pub use battle_core::characters::*;
+
+//====================================================================
 
-pub mod actions;
+/// Every character's components before its own sprite and stats are added.
+pub const CHARACTER_PREFAB: &str = "character";
+/// Size of a character's sprite.
+const CHARACTER_SIZE: glam::Vec2 = glam::vec2(50., 50.);
+
+pub fn register_prefabs(prefabs: &mut Prefabs) {
+    prefabs.register(CHARACTER_PREFAB, |renderer, builder| {
+        builder.add_bundle((
+            Transform::default(),
+            Sprite::new(renderer.default_texture.get(), CHARACTER_SIZE),
+            Pickable,
+        ));
+    });
+}
 
 //====================================================================
 
@@ -25,6 +47,8 @@ pub struct CharacterManager {
     characters: HashSet<Entity>,
 
     default_texture: DefaultTexture,
+    /// Textures characters can ask for by name.
+    textures: BTreeMap<String, Arc<LoadedTexture>>,
 }
 
 impl CharacterManager {
@@ -34,50 +58,208 @@ impl CharacterManager {
             characters: HashSet::default(),
 
             default_texture: DefaultTexture::new(state.renderer.default_texture.get()),
+            textures: BTreeMap::new(),
         }
     }
 
-    pub fn spawn(&mut self, world: &mut World, name: &str, actions: Vec<ActionId>) -> Entity {
+    /// Load images characters can use as their texture, by name. Images
+    /// that can't be loaded are left out so those characters use the default
+    /// texture.
+    pub fn load_textures(&mut self, state: &mut StateInner, textures: &BTreeMap<String, Vec<u8>>) {
+        self.textures = textures
+            .iter()
+            .filter_map(|(name, bytes)| {
+                let label = format!("Character Texture {}", name);
+
+                match state.renderer.load_texture(&label, bytes) {
+                    Ok(texture) => Some((name.clone(), texture)),
+                    Err(e) => {
+                        log::error!("Unable to load character texture '{}': {}", name, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+    }
+
+    pub fn spawn(&mut self, state: &mut StateInner, name: &str, actions: Vec<ActionId>) -> Entity {
         assert!(actions.len() > 0);
 
-        let character = world.spawn((
+        self.spawn_character(
+            state,
             Character {
                 name: name.into(),
                 player_controlled: true,
-                stats: CharacterStats { speed: 5 },
+                stats: CharacterStats {
+                    speed: 5,
+                    health: DEFAULT_HEALTH,
+                    max_health: DEFAULT_HEALTH,
+                    accuracy: DEFAULT_ACCURACY,
+                    evasion: 0,
+                    crit_chance: DEFAULT_CRIT_CHANCE,
+                    crit_multiplier: DEFAULT_CRIT_MULTIPLIER,
+                },
+                equipment: Equipment::default(),
+                hue_shift: 0,
+                texture: None,
                 actions,
+                effects: Vec::new(),
+                cooldowns: BTreeMap::new(),
                 front_facing: true,
             },
-            Transform::default(),
-            Sprite {
-                texture: self.default_texture.get(),
-                size: glam::vec2(50., 50.),
-                color: [1.; 4],
-            },
-        ));
+        )
+    }
+
+    /// Spawn an existing character, such as one received from the server.
+    pub fn spawn_character(&mut self, state: &mut StateInner, character: Character) -> Entity {
+        let stack = self.equipment_stack(&character.equipment);
+
+        let palette = match character.hue_shift {
+            0 => PaletteSwap::None,
+            degrees => PaletteSwap::HueShift(degrees as f32),
+        };
+
+        let texture = character
+            .texture
+            .as_ref()
+            .and_then(|texture| self.textures.get(texture))
+            .map_or_else(|| self.default_texture.get(), Arc::clone);
+
+        let sprite = Sprite {
+            palette,
+            ..Sprite::new(texture, CHARACTER_SIZE)
+        };
+
+        let character = state
+            .spawn_prefab(CHARACTER_PREFAB, (character, sprite, stack))
+            .expect("Character prefab isn't registered");
+
+        // Offset per character so the party doesn't bob in unison
+        state
+            .world
+            .insert_one(
+                character,
+                IdleAnimation::new(IdleAnimation::phase_for(character)),
+            )
+            .ok();
 
         self.characters.insert(character);
         character
     }
+
+    /// Stop tracking a character without despawning it.
+    #[inline]
+    pub fn remove(&mut self, character: Entity) {
+        self.characters.remove(&character);
+    }
+
+    /// Despawn a character spawned by this manager. False if it isn't one of
+    /// ours.
+    pub fn despawn(&mut self, state: &mut StateInner, character: Entity) -> bool {
+        match self.characters.remove(&character) {
+            true => {
+                state.despawn(character).ok();
+                true
+            }
+            false => false,
+        }
+    }
+
+    /// Despawn every character spawned by this manager.
+    pub fn despawn_all(&mut self, state: &mut StateInner) {
+        self.characters.drain().for_each(|character| {
+            state.despawn(character).ok();
+        });
+    }
+
+    /// Every character spawned by this manager, in no particular order.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.characters.iter().copied()
+    }
+
+    #[inline]
+    pub fn contains(&self, character: Entity) -> bool {
+        self.characters.contains(&character)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.characters.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.characters.is_empty()
+    }
+
+    /// Forget characters that were despawned from the world behind the
+    /// manager's back, returning how many there were.
+    pub fn retain_alive(&mut self, world: &World) -> usize {
+        let before = self.characters.len();
+        self.characters
+            .retain(|character| world.satisfies::<&Character>(*character).unwrap_or(false));
+
+        let stale = before - self.characters.len();
+        if stale > 0 {
+            log::warn!("Dropped {} characters no longer in the world", stale);
+        }
+
+        stale
+    }
+
+    /// Change what a character has equipped, updating the sprites drawn over
+    /// it.
+    pub fn equip(&self, world: &mut World, character: Entity, equipment: Equipment) {
+        let stack = self.equipment_stack(&equipment);
+
+        if let Ok(mut existing) = world.get::<&mut Character>(character) {
+            existing.equipment = equipment;
+        }
+
+        world.insert_one(character, stack).ok();
+    }
+
+    // Armor sits over the body and the weapon over both
+    fn equipment_stack(&self, equipment: &Equipment) -> SpriteStack {
+        let armor = equipment.armor.as_ref().map(|armor| StackedSprite {
+            texture: self.default_texture.get(),
+            size: glam::vec2(40., 34.),
+            color: item_color(armor),
+            offset: glam::vec3(0., -4., 0.),
+        });
+
+        let weapon = equipment.weapon.as_ref().map(|weapon| StackedSprite {
+            texture: self.default_texture.get(),
+            size: glam::vec2(8., 46.),
+            color: item_color(weapon),
+            offset: glam::vec3(24., 4., 0.),
+        });
+
+        SpriteStack {
+            layers: armor.into_iter().chain(weapon).collect(),
+        }
+    }
 }
 
 //====================================================================
 
-#[allow(dead_code)]
-#[derive(Debug)]
-pub struct Character {
-    pub name: String,
-    pub player_controlled: bool,
-    pub stats: CharacterStats,
-    pub actions: Vec<ActionId>,
+// Placeholder art - items are told apart by tint until they have textures
+fn item_color(name: &str) -> [f32; 4] {
+    const PALETTE: [[f32; 4]; 6] = [
+        [0.55, 0.35, 0.2, 1.],
+        [0.7, 0.7, 0.75, 1.],
+        [0.85, 0.7, 0.2, 1.],
+        [0.3, 0.45, 0.8, 1.],
+        [0.6, 0.2, 0.25, 1.],
+        [0.3, 0.6, 0.35, 1.],
+    ];
 
-    pub front_facing: bool,
-}
+    let hash = name.bytes().fold(0_u32, |hash, byte| {
+        hash.wrapping_mul(31).wrapping_add(byte as u32)
+    });
 
-#[allow(dead_code)]
-#[derive(Debug)]
-pub struct CharacterStats {
-    pub speed: u32,
+    PALETTE[hash as usize % PALETTE.len()]
 }
 
 pub fn update_characters(state: &mut StateInner) {
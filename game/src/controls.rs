@@ -0,0 +1,249 @@
+//====================================================================
+
+use common::Transform;
+use engine::{debug, tools::KeyCode, StateInner};
+use hecs::Entity;
+use renderer::pipelines::ui3d_pipeline::Ui3d;
+
+use crate::{
+    camera, hints, scenery,
+    scenes::{
+        battle_scene::{self, auto_battle, fast_forward, ui},
+        load_scene, overworld_scene,
+    },
+    stats,
+};
+
+//====================================================================
+
+/// Toggles the controls overlay.
+pub const HELP_KEY: KeyCode = KeyCode::F1;
+
+const OVERLAY_COLOR: [f32; 4] = [0.1, 0.1, 0.15, 0.85];
+
+/// Where a control does something.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    Camera,
+    Overworld,
+    Battle,
+    /// A battle menu has input focus.
+    BattleMenu,
+    LoadGame,
+    Debug,
+}
+
+impl Context {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Context::Camera => "Camera",
+            Context::Overworld => "Overworld",
+            Context::Battle => "Battle",
+            Context::BattleMenu => "Menu",
+            Context::LoadGame => "Load Game",
+            Context::Debug => "Debug",
+        }
+    }
+}
+
+pub struct Control {
+    pub context: Context,
+    pub keys: &'static [KeyCode],
+    pub description: &'static str,
+}
+
+const fn control(context: Context, keys: &'static [KeyCode], description: &'static str) -> Control {
+    Control {
+        context,
+        keys,
+        description,
+    }
+}
+
+/// Every control in the game, grouped by context in the order shown.
+pub const CONTROLS: &[Control] = &[
+    control(
+        Context::Camera,
+        &[
+            camera::MOVE_FORWARDS_KEY,
+            camera::MOVE_LEFT_KEY,
+            camera::MOVE_BACKWARDS_KEY,
+            camera::MOVE_RIGHT_KEY,
+        ],
+        "Move",
+    ),
+    control(
+        Context::Camera,
+        &[camera::MOVE_UP_KEY, camera::MOVE_DOWN_KEY],
+        "Up / down",
+    ),
+    control(
+        Context::Camera,
+        &[
+            camera::LOOK_UP_KEY,
+            camera::LOOK_LEFT_KEY,
+            camera::LOOK_DOWN_KEY,
+            camera::LOOK_RIGHT_KEY,
+        ],
+        "Look around (or hold right mouse)",
+    ),
+    control(
+        Context::Camera,
+        &[camera::CAMERA_MODE_KEY],
+        "Battle orbit camera (drag to turn, scroll to zoom)",
+    ),
+    control(
+        Context::Overworld,
+        &[overworld_scene::QUICK_BATTLE_KEY],
+        "Quick battle",
+    ),
+    control(Context::Overworld, &[overworld_scene::SAVE_KEY], "Save"),
+    control(Context::Overworld, &[overworld_scene::LOAD_KEY], "Load"),
+    control(
+        Context::BattleMenu,
+        &[ui::MENU_UP_KEY, ui::MENU_DOWN_KEY],
+        "Change selection",
+    ),
+    control(
+        Context::BattleMenu,
+        &[ui::MENU_SELECT_KEY, ui::MENU_FORWARD_KEY],
+        "Confirm",
+    ),
+    control(Context::BattleMenu, &[ui::MENU_BACK_KEY], "Back"),
+    control(
+        Context::Battle,
+        &[fast_forward::FAST_FORWARD_KEY],
+        "Hold to fast forward enemy turns",
+    ),
+    control(
+        Context::Battle,
+        &[auto_battle::AUTO_BATTLE_KEY],
+        "Toggle auto battle",
+    ),
+    control(Context::Battle, &[stats::ACHIEVEMENTS_KEY], "Achievements"),
+    control(Context::Battle, &[hints::DISMISS_HINT_KEY], "Dismiss hint"),
+    control(Context::Battle, &[battle_scene::RETREAT_KEY], "Retreat"),
+    control(
+        Context::LoadGame,
+        &[load_scene::PREVIOUS_SLOT_KEY, load_scene::NEXT_SLOT_KEY],
+        "Change slot",
+    ),
+    control(Context::LoadGame, &[load_scene::LOAD_SLOT_KEY], "Load"),
+    control(Context::LoadGame, &[load_scene::COPY_SLOT_KEY], "Copy"),
+    control(Context::LoadGame, &[load_scene::DELETE_SLOT_KEY], "Delete"),
+    control(Context::LoadGame, &[load_scene::EXPORT_SLOT_KEY], "Export"),
+    control(Context::LoadGame, &[load_scene::IMPORT_SLOT_KEY], "Import"),
+    control(Context::LoadGame, &[load_scene::BACK_KEY], "Back"),
+    control(Context::Debug, &[HELP_KEY], "Toggle this help"),
+    control(Context::Debug, &[debug::LOG_VIEWER_KEY], "Log viewer"),
+    control(Context::Debug, &[debug::LOG_LEVEL_KEY], "Log level"),
+    control(Context::Debug, &[debug::DUMP_WORLD_KEY], "Dump world"),
+    control(Context::Debug, &[debug::DIAGNOSTICS_KEY], "Diagnostics"),
+    control(
+        Context::Debug,
+        &[debug::RENDER_DEBUG_KEY],
+        "Render debug view",
+    ),
+    control(Context::Debug, &[debug::FRAME_CAPTURE_KEY], "Capture frame"),
+    control(Context::Debug, &[scenery::GROUND_KEY], "Toggle ground grid"),
+];
+
+/// Readable name for a key, such as "W" or "Up".
+pub fn key_name(key: KeyCode) -> String {
+    let name = format!("{:?}", key);
+
+    match name
+        .strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Arrow"))
+    {
+        Some(stripped) => stripped.to_string(),
+        None => name,
+    }
+}
+
+/// Keys shown the way the game lists them, such as "[Up/Down]".
+pub fn key_label(keys: &[KeyCode]) -> String {
+    let names = keys.iter().map(|key| key_name(*key)).collect::<Vec<_>>();
+    format!("[{}]", names.join("/"))
+}
+
+/// Lines listing the controls for each of `contexts`, under their titles.
+pub fn help_lines(contexts: &[Context]) -> Vec<String> {
+    std::iter::once(format!("Controls - {} to close", key_label(&[HELP_KEY])))
+        .chain(contexts.iter().flat_map(|context| {
+            std::iter::once(format!("{}:", context.title())).chain(
+                CONTROLS
+                    .iter()
+                    .filter(move |control| control.context == *context)
+                    .map(|control| {
+                        format!("  {} {}", key_label(control.keys), control.description)
+                    }),
+            )
+        }))
+        .collect()
+}
+
+//====================================================================
+
+/// Overlay listing the controls that apply right now, toggled with
+/// [`HELP_KEY`].
+#[derive(Default)]
+pub struct HelpOverlay {
+    entity: Option<Entity>,
+    shown: Vec<Context>,
+}
+
+impl HelpOverlay {
+    /// `contexts` are the controls that currently do something. The overlay
+    /// follows along as they change.
+    pub fn tick(&mut self, state: &mut StateInner, contexts: &[Context]) {
+        if state.keys.just_pressed(HELP_KEY) {
+            match self.entity.take() {
+                Some(entity) => {
+                    state.despawn(entity).ok();
+                    self.shown.clear();
+                }
+                None => {
+                    self.entity = Some(state.world.spawn((
+                        Transform::from_scale((0.5, 0.5, 0.5)),
+                        Ui3d {
+                            menu_color: OVERLAY_COLOR,
+                            selection_color: OVERLAY_COLOR,
+                            font_size: 24.,
+                            ..Default::default()
+                        },
+                    )));
+                }
+            }
+        }
+
+        let entity = match self.entity {
+            Some(entity) => entity,
+            None => return,
+        };
+
+        // Only reshape the text when the controls change
+        if self.shown != contexts {
+            if let Ok(mut ui) = state.world.get::<&mut Ui3d>(entity) {
+                ui.options = help_lines(contexts);
+            }
+            self.shown = contexts.to_vec();
+        }
+
+        let camera = &state.renderer.camera.camera;
+        let position = camera.translation + camera.forward() * 8. + camera.right() * -3.;
+
+        if let Ok(mut transform) = state.world.get::<&mut Transform>(entity) {
+            transform.translation = position;
+        }
+    }
+
+    pub fn close(&mut self, state: &mut StateInner) {
+        if let Some(entity) = self.entity.take() {
+            state.despawn(entity).ok();
+        }
+        self.shown.clear();
+    }
+}
+
+//====================================================================
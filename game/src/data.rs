@@ -0,0 +1,1158 @@
+//====================================================================
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    audio,
+    characters::{
+        self, actions::Action, actions::ActionRepo, Character, CharacterStats, Equipment,
+    },
+    locale,
+    settings::Settings,
+    stats,
+};
+
+//====================================================================
+
+/// Directory data files are read from, next to the executable.
+pub const DATA_DIR: &str = "data";
+
+/// Data files in the source tree, read by debug builds that don't have any
+/// next to the executable.
+#[cfg(debug_assertions)]
+const SOURCE_DATA_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/data");
+
+/// Where data files are read from: [`DATA_DIR`] next to the executable, or
+/// the source tree's data in debug builds. Builds without either fall back
+/// to the copies embedded at compile time.
+pub fn data_dir() -> PathBuf {
+    let beside_exe = std::env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.parent()?.join(DATA_DIR)));
+
+    match beside_exe {
+        Some(dir) if dir.is_dir() => dir,
+        #[cfg(debug_assertions)]
+        _ => PathBuf::from(SOURCE_DATA_DIR),
+        #[cfg(not(debug_assertions))]
+        _ => PathBuf::from(DATA_DIR),
+    }
+}
+
+const DATA_KINDS: [&str; 6] = [
+    "achievements",
+    "actions",
+    "audio",
+    "characters",
+    "encounters",
+    "locale",
+];
+
+/// Directory in the data and each pack that character textures are read
+/// from. Files are referred to by name.
+pub const TEXTURES_DIR: &str = "textures";
+/// Directory in the data and each pack that sound effects and music are read
+/// from, as wav files. Files are referred to by name.
+pub const SOUNDS_DIR: &str = "sounds";
+
+const BUILTIN: [(&str, &str); 5] = [
+    (
+        "achievements/base.toml",
+        include_str!("../data/achievements/base.toml"),
+    ),
+    (
+        "actions/base.toml",
+        include_str!("../data/actions/base.toml"),
+    ),
+    ("audio/base.toml", include_str!("../data/audio/base.toml")),
+    (
+        "characters/base.toml",
+        include_str!("../data/characters/base.toml"),
+    ),
+    (
+        "encounters/base.toml",
+        include_str!("../data/encounters/base.toml"),
+    ),
+];
+
+#[derive(Debug, thiserror::Error)]
+pub enum DataError {
+    #[error("Unable to read '{path}': {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Unable to read archive '{path}': {message}")]
+    Archive { path: PathBuf, message: String },
+
+    #[error("Invalid data in '{path}': {message}")]
+    Parse { path: PathBuf, message: String },
+
+    #[error("Action '{0}' is defined more than once")]
+    DuplicateAction(String),
+
+    #[error("Character '{character}' uses unknown action '{action}'")]
+    UnknownAction { character: String, action: String },
+
+    #[error("Character '{0}' has no actions")]
+    NoActions(String),
+
+    #[error("No {0:?} characters defined")]
+    NoCharacters(Side),
+
+    #[error("Encounter '{encounter}' uses unknown character '{character}'")]
+    UnknownCharacter {
+        encounter: String,
+        character: String,
+    },
+
+    #[error("Encounter '{0}' has no bosses or enemies that cost anything")]
+    EmptyEncounter(String),
+
+    #[error("Phase of boss '{boss}' uses unknown action '{action}'")]
+    UnknownPhaseAction { boss: String, action: String },
+
+    #[error("Phase of boss '{boss}' triggers at {percent}% health - must be below 100")]
+    InvalidPhase { boss: String, percent: u32 },
+
+    #[error("Achievement '{0}' is defined more than once")]
+    DuplicateAchievement(String),
+
+    #[error("Achievement '{achievement}' tracks unknown stat '{stat}'")]
+    UnknownStat { achievement: String, stat: String },
+
+    #[error("Sound event '{0}' is mapped more than once")]
+    DuplicateSound(String),
+
+    #[error("Sound '{sound}' is mapped to unknown event '{event}'")]
+    UnknownSoundEvent { event: String, sound: String },
+
+    #[error("Rumble for event '{0}' is defined more than once")]
+    DuplicateRumble(String),
+
+    #[error("Rumble is mapped to unknown event '{0}'")]
+    UnknownRumbleEvent(String),
+
+    #[error("Music '{0}' is defined more than once")]
+    DuplicateMusic(String),
+
+    #[error("Encounter '{encounter}' uses unknown music '{music}'")]
+    UnknownMusic { encounter: String, music: String },
+
+    #[error("Character '{character}' uses missing texture '{texture}'")]
+    MissingTexture { character: String, texture: String },
+
+    #[error("Locale '{language}' is missing '{key}'")]
+    MissingLocaleKey { language: String, key: String },
+
+    #[error("Locale '{language}' has '{key}' that doesn't match anything")]
+    UnknownLocaleKey { language: String, key: String },
+}
+
+//====================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Side {
+    Friendly,
+    Enemy,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct CharacterDef {
+    pub name: String,
+    pub side: Side,
+    pub speed: u32,
+    #[serde(default = "default_health")]
+    pub health: u32,
+    #[serde(default = "default_accuracy")]
+    pub accuracy: u32,
+    #[serde(default)]
+    pub evasion: u32,
+    #[serde(default = "default_crit_chance")]
+    pub crit_chance: u32,
+    #[serde(default = "default_crit_multiplier")]
+    pub crit_multiplier: u32,
+    pub actions: Vec<String>,
+    #[serde(default)]
+    pub equipment: Equipment,
+    /// Recolors the character so variants can share art, in degrees.
+    #[serde(default)]
+    pub hue_shift: i16,
+    /// File name of an image in a [`TEXTURES_DIR`] to draw the character
+    /// with, instead of the default texture.
+    #[serde(default)]
+    pub texture: Option<String>,
+    #[serde(default = "default_player_controlled")]
+    pub player_controlled: bool,
+}
+
+#[inline]
+fn default_health() -> u32 {
+    characters::DEFAULT_HEALTH
+}
+
+#[inline]
+fn default_accuracy() -> u32 {
+    characters::DEFAULT_ACCURACY
+}
+
+#[inline]
+fn default_crit_chance() -> u32 {
+    characters::DEFAULT_CRIT_CHANCE
+}
+
+#[inline]
+fn default_crit_multiplier() -> u32 {
+    characters::DEFAULT_CRIT_MULTIPLIER
+}
+
+#[inline]
+fn default_player_controlled() -> bool {
+    true
+}
+
+impl CharacterDef {
+    /// Build the character, skipping any actions the repo doesn't have.
+    pub fn to_character(&self, actions: &ActionRepo) -> Character {
+        Character {
+            name: self.name.clone(),
+            player_controlled: self.player_controlled,
+            stats: CharacterStats {
+                speed: self.speed,
+                health: self.health,
+                max_health: self.health,
+                accuracy: self.accuracy,
+                evasion: self.evasion,
+                crit_chance: self.crit_chance,
+                crit_multiplier: self.crit_multiplier,
+            },
+            equipment: self.equipment.clone(),
+            hue_shift: self.hue_shift,
+            texture: self.texture.clone(),
+            actions: self
+                .actions
+                .iter()
+                .filter_map(|name| actions.find_action_name(name))
+                .collect(),
+            effects: Vec::new(),
+            cooldowns: Default::default(),
+            front_facing: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AchievementDef {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// One of [`stats::stat::ALL`].
+    pub stat: String,
+    pub threshold: u64,
+}
+
+/// Sound played when a game event happens.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SoundDef {
+    /// One of [`audio::sound_event::ALL`].
+    pub event: String,
+    /// Wav file in [`SOUNDS_DIR`].
+    pub sound: String,
+    #[serde(default = "default_volume")]
+    pub volume: f32,
+}
+
+#[inline]
+fn default_volume() -> f32 {
+    1.
+}
+
+/// Gamepad rumble played when a game event happens, using the same events as
+/// [`SoundDef`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RumbleDef {
+    /// One of [`audio::sound_event::ALL`].
+    pub event: String,
+    /// Played one after another.
+    pub pulses: Vec<RumblePulse>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct RumblePulse {
+    /// Strength of the low frequency motor, from 0 to 1.
+    #[serde(default)]
+    pub strong: f32,
+    /// Strength of the high frequency motor, from 0 to 1.
+    #[serde(default)]
+    pub weak: f32,
+    pub millis: u32,
+}
+
+/// Pool of enemies a random encounter is assembled from.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct EncounterDef {
+    pub name: String,
+    /// Budget at level 1.
+    pub budget: u32,
+    #[serde(default)]
+    pub budget_per_level: u32,
+    pub max_enemies: u32,
+    pub enemies: Vec<EnemyTemplate>,
+    /// Always in the encounter, on top of the budget.
+    #[serde(default)]
+    pub bosses: Vec<BossDef>,
+    #[serde(default)]
+    pub arena: ArenaDef,
+}
+
+/// Space a battle is fought in.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ArenaDef {
+    /// Corners of the box the camera is kept inside, in world space.
+    pub camera_min: [f32; 3],
+    pub camera_max: [f32; 3],
+    /// Name of a music definition, or [`crate::music::DEFAULT_MUSIC`] if None.
+    pub music: Option<String>,
+}
+
+impl Default for ArenaDef {
+    fn default() -> Self {
+        Self {
+            camera_min: [-400., -10., -700.],
+            camera_max: [700., 500., 600.],
+            music: None,
+        }
+    }
+}
+
+/// Layered battle music. Every layer is a wav file in [`SOUNDS_DIR`] with the
+/// same length, channels and sample rate as the base, and they play in step
+/// while the battle crossfades between them.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MusicDef {
+    pub name: String,
+    /// Always playing, quieter when things are going badly.
+    pub base: String,
+    /// Fades in while losing or fighting a boss.
+    #[serde(default)]
+    pub tension: Option<String>,
+    /// Fades in once the battle is won.
+    #[serde(default)]
+    pub victory: Option<String>,
+    /// Frame the music jumps back to when it reaches `loop_end`. Defaults to
+    /// the start.
+    #[serde(default)]
+    pub loop_start: Option<u64>,
+    /// Defaults to the end of the base layer.
+    #[serde(default)]
+    pub loop_end: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct EnemyTemplate {
+    /// Name of a character definition.
+    pub character: String,
+    pub cost: u32,
+    #[serde(default = "default_min_level")]
+    pub min_level: u32,
+}
+
+#[inline]
+fn default_min_level() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct BossDef {
+    /// Name of a character definition.
+    pub character: String,
+    /// Ordered by threshold, highest first.
+    #[serde(default)]
+    pub phases: Vec<PhaseDef>,
+}
+
+/// Scripted events fired once, when a boss drops to `health_percent` of its
+/// max health or below.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PhaseDef {
+    pub health_percent: u32,
+    /// Replaces the boss's actions.
+    #[serde(default)]
+    pub actions: Option<Vec<String>>,
+    /// Characters joining the boss's side.
+    #[serde(default)]
+    pub summon: Vec<String>,
+    /// Lines shown while the battle pauses.
+    #[serde(default)]
+    pub cutscene: Vec<String>,
+}
+
+// Layout of a single data file. Any file may hold any kind of definition.
+#[derive(Deserialize, Default)]
+struct DataFile {
+    #[serde(default)]
+    action: Vec<Action>,
+    #[serde(default)]
+    character: Vec<CharacterDef>,
+    #[serde(default)]
+    achievement: Vec<AchievementDef>,
+    #[serde(default)]
+    encounter: Vec<EncounterDef>,
+    #[serde(default)]
+    sound: Vec<SoundDef>,
+    #[serde(default)]
+    rumble: Vec<RumbleDef>,
+    #[serde(default)]
+    music: Vec<MusicDef>,
+    #[serde(default)]
+    locale: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+//====================================================================
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GameData {
+    pub actions: Vec<Action>,
+    pub characters: Vec<CharacterDef>,
+    pub achievements: Vec<AchievementDef>,
+    pub encounters: Vec<EncounterDef>,
+    pub sounds: Vec<SoundDef>,
+    pub rumbles: Vec<RumbleDef>,
+    pub music: Vec<MusicDef>,
+    /// Translated text by language, then by key. See [`crate::locale`].
+    pub locales: BTreeMap<String, BTreeMap<String, String>>,
+    /// Images from each [`TEXTURES_DIR`] by file name.
+    pub textures: BTreeMap<String, Vec<u8>>,
+    /// Wav files from each [`SOUNDS_DIR`] by file name.
+    pub audio: BTreeMap<String, Vec<u8>>,
+}
+
+impl GameData {
+    /// Data embedded in the binary.
+    pub fn builtin() -> Self {
+        let mut data = Self::default();
+
+        BUILTIN.iter().for_each(|(path, contents)| {
+            data.add_file(Path::new(path), contents)
+                .expect("Built in game data is invalid")
+        });
+
+        data
+    }
+
+    fn read(source: &DataSource, errors: &mut Vec<DataError>) -> Self {
+        let mut data = Self::default();
+
+        let mut add = |path: PathBuf, contents: Result<String, DataError>| match contents {
+            Ok(contents) => {
+                if let Err(e) = data.add_file(&path, &contents) {
+                    errors.push(e);
+                }
+            }
+            Err(e) => errors.push(e),
+        };
+
+        match source {
+            DataSource::Directory(root) => data_files(root).into_iter().for_each(|path| {
+                let contents = std::fs::read_to_string(&path).map_err(|source| DataError::Io {
+                    path: path.clone(),
+                    source,
+                });
+                add(path, contents);
+            }),
+
+            DataSource::Archive(archive) => match archive_files(archive) {
+                Ok(files) => files
+                    .into_iter()
+                    .for_each(|(name, contents)| add(archive.join(name), Ok(contents))),
+                Err(message) => errors.push(DataError::Archive {
+                    path: archive.clone(),
+                    message,
+                }),
+            },
+        }
+
+        data.textures = read_assets(source, TEXTURES_DIR, errors);
+        data.audio = read_assets(source, SOUNDS_DIR, errors);
+
+        data
+    }
+
+    /// Load every data file under `root`, returning all problems found rather
+    /// than stopping at the first.
+    pub fn load(root: impl AsRef<Path>) -> Result<Self, Vec<DataError>> {
+        Self::load_with_packs(Some(root.as_ref()), &[])
+    }
+
+    /// Load the base data from `root` (or the built in data if `None`), then
+    /// each pack in order. Definitions in a pack replace earlier ones with the
+    /// same name and anything new is added.
+    pub fn load_with_packs(root: Option<&Path>, packs: &[Pack]) -> Result<Self, Vec<DataError>> {
+        let mut errors = Vec::new();
+
+        let mut data = match root {
+            Some(root) => Self::read(&DataSource::Directory(root.to_path_buf()), &mut errors),
+            None => Self::builtin(),
+        };
+
+        packs.iter().for_each(|pack| {
+            log::debug!("Loading content pack '{}'", pack.name);
+            data.merge(Self::read(&pack.source, &mut errors));
+        });
+
+        errors.extend(data.validate());
+
+        match errors.is_empty() {
+            true => Ok(data),
+            false => Err(errors),
+        }
+    }
+
+    /// Load from [`data_dir`] when it exists (otherwise the built in data),
+    /// plus any content packs enabled in the settings.
+    pub fn load_configured() -> Result<Self, Vec<DataError>> {
+        let root = data_dir();
+        let packs = enabled_packs(&Settings::load());
+
+        Self::load_with_packs(root.is_dir().then_some(root.as_path()), &packs)
+    }
+
+    /// Like [`GameData::load_configured`], but problems are logged and the
+    /// built in data used instead.
+    pub fn load_or_builtin() -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        match Self::load_configured() {
+            Ok(data) => return data,
+            Err(errors) => {
+                log::error!("Unable to load game data - using built in data");
+                errors.iter().for_each(|e| log::error!("    {}", e));
+            }
+        }
+
+        Self::builtin()
+    }
+
+    /// Add definitions from `other`, replacing any with the same name.
+    pub fn merge(&mut self, other: GameData) {
+        other.actions.into_iter().for_each(|action| {
+            match self
+                .actions
+                .iter_mut()
+                .find(|existing| existing.name == action.name)
+            {
+                Some(existing) => *existing = action,
+                None => self.actions.push(action),
+            }
+        });
+
+        other.characters.into_iter().for_each(|character| {
+            match self
+                .characters
+                .iter_mut()
+                .find(|existing| existing.name == character.name)
+            {
+                Some(existing) => *existing = character,
+                None => self.characters.push(character),
+            }
+        });
+
+        other.achievements.into_iter().for_each(|achievement| {
+            match self
+                .achievements
+                .iter_mut()
+                .find(|existing| existing.id == achievement.id)
+            {
+                Some(existing) => *existing = achievement,
+                None => self.achievements.push(achievement),
+            }
+        });
+
+        other.encounters.into_iter().for_each(|encounter| {
+            match self
+                .encounters
+                .iter_mut()
+                .find(|existing| existing.name == encounter.name)
+            {
+                Some(existing) => *existing = encounter,
+                None => self.encounters.push(encounter),
+            }
+        });
+
+        other.sounds.into_iter().for_each(|sound| {
+            match self
+                .sounds
+                .iter_mut()
+                .find(|existing| existing.event == sound.event)
+            {
+                Some(existing) => *existing = sound,
+                None => self.sounds.push(sound),
+            }
+        });
+
+        other.rumbles.into_iter().for_each(|rumble| {
+            match self
+                .rumbles
+                .iter_mut()
+                .find(|existing| existing.event == rumble.event)
+            {
+                Some(existing) => *existing = rumble,
+                None => self.rumbles.push(rumble),
+            }
+        });
+
+        other.music.into_iter().for_each(|music| {
+            match self
+                .music
+                .iter_mut()
+                .find(|existing| existing.name == music.name)
+            {
+                Some(existing) => *existing = music,
+                None => self.music.push(music),
+            }
+        });
+
+        other.locales.into_iter().for_each(|(language, strings)| {
+            self.locales.entry(language).or_default().extend(strings);
+        });
+
+        self.textures.extend(other.textures);
+        self.audio.extend(other.audio);
+    }
+
+    fn add_file(&mut self, path: &Path, contents: &str) -> Result<(), DataError> {
+        let file = toml::from_str::<DataFile>(contents).map_err(|e| DataError::Parse {
+            path: path.to_path_buf(),
+            message: e.to_string().trim().to_string(),
+        })?;
+
+        self.actions.extend(file.action);
+        self.characters.extend(file.character);
+        self.achievements.extend(file.achievement);
+        self.encounters.extend(file.encounter);
+        self.sounds.extend(file.sound);
+        self.rumbles.extend(file.rumble);
+        self.music.extend(file.music);
+        file.locale.into_iter().for_each(|(language, strings)| {
+            self.locales.entry(language).or_default().extend(strings);
+        });
+
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Vec<DataError> {
+        let mut errors = Vec::new();
+        let mut names = HashSet::new();
+
+        self.actions.iter().for_each(|action| {
+            if !names.insert(action.name.as_str()) {
+                errors.push(DataError::DuplicateAction(action.name.clone()));
+            }
+        });
+
+        self.characters.iter().for_each(|character| {
+            if character.actions.is_empty() {
+                errors.push(DataError::NoActions(character.name.clone()));
+            }
+
+            character
+                .actions
+                .iter()
+                .filter(|action| !names.contains(action.as_str()))
+                .for_each(|action| {
+                    errors.push(DataError::UnknownAction {
+                        character: character.name.clone(),
+                        action: action.clone(),
+                    })
+                });
+        });
+
+        [Side::Friendly, Side::Enemy]
+            .into_iter()
+            .filter(|side| {
+                !self
+                    .characters
+                    .iter()
+                    .any(|character| character.side == *side)
+            })
+            .for_each(|side| errors.push(DataError::NoCharacters(side)));
+
+        self.encounters.iter().for_each(|encounter| {
+            if encounter.bosses.is_empty() && !encounter.enemies.iter().any(|enemy| enemy.cost > 0)
+            {
+                errors.push(DataError::EmptyEncounter(encounter.name.clone()));
+            }
+
+            let phases = encounter
+                .bosses
+                .iter()
+                .flat_map(|boss| boss.phases.iter().map(move |phase| (boss, phase)));
+
+            encounter
+                .enemies
+                .iter()
+                .map(|enemy| &enemy.character)
+                .chain(encounter.bosses.iter().map(|boss| &boss.character))
+                .chain(phases.clone().flat_map(|(_, phase)| phase.summon.iter()))
+                .filter(|character| self.character(character).is_none())
+                .for_each(|character| {
+                    errors.push(DataError::UnknownCharacter {
+                        encounter: encounter.name.clone(),
+                        character: character.clone(),
+                    })
+                });
+
+            phases.for_each(|(boss, phase)| {
+                if phase.health_percent >= 100 {
+                    errors.push(DataError::InvalidPhase {
+                        boss: boss.character.clone(),
+                        percent: phase.health_percent,
+                    });
+                }
+
+                phase
+                    .actions
+                    .iter()
+                    .flatten()
+                    .filter(|action| !names.contains(action.as_str()))
+                    .for_each(|action| {
+                        errors.push(DataError::UnknownPhaseAction {
+                            boss: boss.character.clone(),
+                            action: action.clone(),
+                        })
+                    });
+            });
+        });
+
+        let mut ids = HashSet::new();
+
+        self.achievements.iter().for_each(|achievement| {
+            if !ids.insert(achievement.id.as_str()) {
+                errors.push(DataError::DuplicateAchievement(achievement.id.clone()));
+            }
+
+            if !stats::stat::ALL.contains(&achievement.stat.as_str()) {
+                errors.push(DataError::UnknownStat {
+                    achievement: achievement.id.clone(),
+                    stat: achievement.stat.clone(),
+                });
+            }
+        });
+
+        let mut events = HashSet::new();
+
+        self.sounds.iter().for_each(|sound| {
+            if !events.insert(sound.event.as_str()) {
+                errors.push(DataError::DuplicateSound(sound.event.clone()));
+            }
+
+            if !audio::sound_event::ALL.contains(&sound.event.as_str()) {
+                errors.push(DataError::UnknownSoundEvent {
+                    event: sound.event.clone(),
+                    sound: sound.sound.clone(),
+                });
+            }
+        });
+
+        let mut events = HashSet::new();
+
+        self.rumbles.iter().for_each(|rumble| {
+            if !events.insert(rumble.event.as_str()) {
+                errors.push(DataError::DuplicateRumble(rumble.event.clone()));
+            }
+
+            if !audio::sound_event::ALL.contains(&rumble.event.as_str()) {
+                errors.push(DataError::UnknownRumbleEvent(rumble.event.clone()));
+            }
+        });
+
+        let mut music = HashSet::new();
+
+        self.music.iter().for_each(|def| {
+            if !music.insert(def.name.as_str()) {
+                errors.push(DataError::DuplicateMusic(def.name.clone()));
+            }
+        });
+
+        self.encounters.iter().for_each(|encounter| {
+            if let Some(name) = &encounter.arena.music {
+                if !music.contains(name.as_str()) {
+                    errors.push(DataError::UnknownMusic {
+                        encounter: encounter.name.clone(),
+                        music: name.clone(),
+                    });
+                }
+            }
+        });
+
+        self.characters.iter().for_each(|character| {
+            if let Some(texture) = &character.texture {
+                if !self.textures.contains_key(texture) {
+                    errors.push(DataError::MissingTexture {
+                        character: character.name.clone(),
+                        texture: texture.clone(),
+                    });
+                }
+            }
+        });
+
+        // Every language translates everything, so none of it shows up
+        // untranslated
+        let keys = self
+            .actions
+            .iter()
+            .map(|action| locale::action_key(&action.name))
+            .collect::<BTreeSet<_>>();
+
+        self.locales.iter().for_each(|(language, strings)| {
+            keys.iter()
+                .filter(|key| !strings.contains_key(*key))
+                .for_each(|key| {
+                    errors.push(DataError::MissingLocaleKey {
+                        language: language.clone(),
+                        key: key.clone(),
+                    })
+                });
+
+            strings
+                .keys()
+                .filter(|key| !keys.contains(*key))
+                .for_each(|key| {
+                    errors.push(DataError::UnknownLocaleKey {
+                        language: language.clone(),
+                        key: key.clone(),
+                    })
+                });
+        });
+
+        errors
+    }
+
+    #[inline]
+    pub fn character(&self, name: &str) -> Option<&CharacterDef> {
+        self.characters
+            .iter()
+            .find(|character| character.name == name)
+    }
+
+    /// Characters in the order they join a battle, friendly first.
+    pub fn battle_order(&self) -> impl Iterator<Item = &CharacterDef> {
+        let side = |side: Side| {
+            self.characters
+                .iter()
+                .filter(move |character| character.side == side)
+        };
+
+        side(Side::Friendly).chain(side(Side::Enemy))
+    }
+
+    /// True if the differences from `other` are only in values (numbers,
+    /// names) that can be swapped into a running battle.
+    pub fn same_structure(&self, other: &GameData) -> bool {
+        self.actions.len() == other.actions.len()
+            && self.characters.len() == other.characters.len()
+            && self
+                .battle_order()
+                .zip(other.battle_order())
+                .all(|(a, b)| a.side == b.side && a.actions == b.actions)
+    }
+}
+
+/// Check every data file under `root` and print a report of all problems
+/// found. Returns true if the data is valid.
+pub fn validate_data(root: impl AsRef<Path>) -> bool {
+    let root = root.as_ref();
+
+    println!(
+        "Validating {} data files in '{}'",
+        data_files(root).len(),
+        root.display()
+    );
+
+    match GameData::load(root) {
+        Ok(data) => {
+            println!(
+                "No problems found - {} actions, {} characters",
+                data.actions.len(),
+                data.characters.len()
+            );
+            true
+        }
+        Err(errors) => {
+            println!("{} problems found:", errors.len());
+            errors.iter().for_each(|e| println!("    {}", e));
+            false
+        }
+    }
+}
+
+//====================================================================
+
+/// Directory content packs are discovered in, relative to the working
+/// directory.
+pub const PACKS_DIR: &str = "packs";
+
+/// Where a set of data files comes from. Both use the same layout as
+/// [`DATA_DIR`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataSource {
+    Directory(PathBuf),
+    /// A zip archive.
+    Archive(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pack {
+    pub name: String,
+    pub source: DataSource,
+}
+
+/// Every pack in `dir`, either a directory or a `.zip` named after the pack.
+pub fn discover_packs(dir: impl AsRef<Path>) -> Vec<Pack> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut packs = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            let name = path.file_stem()?.to_string_lossy().to_string();
+
+            let source = match (path.is_dir(), path.extension()) {
+                (true, _) => DataSource::Directory(path),
+                (false, Some(extension)) if extension == "zip" => DataSource::Archive(path),
+                _ => return None,
+            };
+
+            Some(Pack { name, source })
+        })
+        .collect::<Vec<_>>();
+
+    packs.sort_by(|a, b| a.name.cmp(&b.name));
+    packs
+}
+
+/// Packs enabled in the settings, in the order they should be loaded.
+pub fn enabled_packs(settings: &Settings) -> Vec<Pack> {
+    let available = discover_packs(PACKS_DIR);
+
+    settings
+        .packs
+        .enabled
+        .iter()
+        .filter_map(|name| {
+            let pack = available.iter().find(|pack| &pack.name == name);
+            if pack.is_none() {
+                log::warn!(
+                    "Enabled content pack '{}' not found in '{}'",
+                    name,
+                    PACKS_DIR
+                );
+            }
+            pack.cloned()
+        })
+        .collect()
+}
+
+// Data files in a stable order so ids don't change between runs
+fn data_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = DATA_KINDS
+        .iter()
+        .filter_map(|kind| std::fs::read_dir(root.join(kind)).ok())
+        .flat_map(|entries| entries.filter_map(|entry| entry.ok()))
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "toml")
+        })
+        .collect::<Vec<_>>();
+
+    files.sort();
+    files
+}
+
+// Every file directly in `dir` by name, read whole rather than parsed. No
+// directory means no files.
+fn directory_assets(dir: &Path, errors: &mut Vec<DataError>) -> BTreeMap<String, Vec<u8>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return BTreeMap::new(),
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter_map(|path| {
+            let name = path.file_name()?.to_string_lossy().to_string();
+
+            match std::fs::read(&path) {
+                Ok(bytes) => Some((name, bytes)),
+                Err(source) => {
+                    errors.push(DataError::Io { path, source });
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+// Files in `dir` of a data directory or archive by name
+fn read_assets(
+    source: &DataSource,
+    dir: &str,
+    errors: &mut Vec<DataError>,
+) -> BTreeMap<String, Vec<u8>> {
+    match source {
+        DataSource::Directory(root) => directory_assets(&root.join(dir), errors),
+
+        DataSource::Archive(archive) => archive_assets(archive, dir).unwrap_or_else(|message| {
+            errors.push(DataError::Archive {
+                path: archive.clone(),
+                message,
+            });
+            BTreeMap::new()
+        }),
+    }
+}
+
+// Same as `directory_assets`, but from inside a zip archive
+fn archive_assets(archive: &Path, dir: &str) -> Result<BTreeMap<String, Vec<u8>>, String> {
+    let file = std::fs::File::open(archive).map_err(|e| e.to_string())?;
+    let mut zip = zip::ZipArchive::new(file).map_err(|e| e.to_string())?;
+
+    (0..zip.len())
+        .map(|index| {
+            let mut entry = zip.by_index(index).map_err(|e| e.to_string())?;
+
+            let name = match entry.name().strip_prefix(&format!("{}/", dir)) {
+                Some(name) if entry.is_file() && !name.contains('/') => name.to_string(),
+                _ => return Ok(None),
+            };
+
+            let mut bytes = Vec::new();
+            std::io::Read::read_to_end(&mut entry, &mut bytes)
+                .map_err(|e| format!("{}: {}", entry.name(), e))?;
+
+            Ok(Some((name, bytes)))
+        })
+        .filter_map(Result::transpose)
+        .collect()
+}
+
+// Same as `data_files`, but from inside a zip archive
+fn archive_files(archive: &Path) -> Result<Vec<(String, String)>, String> {
+    let file = std::fs::File::open(archive).map_err(|e| e.to_string())?;
+    let mut zip = zip::ZipArchive::new(file).map_err(|e| e.to_string())?;
+
+    let mut files = (0..zip.len())
+        .map(|index| {
+            let mut entry = zip.by_index(index).map_err(|e| e.to_string())?;
+            let name = entry.name().to_string();
+
+            let is_data = entry.is_file()
+                && name.ends_with(".toml")
+                && DATA_KINDS
+                    .iter()
+                    .any(|kind| name.starts_with(&format!("{}/", kind)));
+
+            if !is_data {
+                return Ok(None);
+            }
+
+            let mut contents = String::new();
+            std::io::Read::read_to_string(&mut entry, &mut contents)
+                .map_err(|e| format!("{}: {}", name, e))?;
+
+            Ok(Some((name, contents)))
+        })
+        .filter_map(Result::transpose)
+        .collect::<Result<Vec<_>, String>>()?;
+
+    files.sort();
+    Ok(files)
+}
+
+//====================================================================
+
+/// Polls data directories and pack archives for changes. Only checks at most
+/// once per `interval` so it can be called every frame.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct DataWatcher {
+    sources: Vec<DataSource>,
+    interval: std::time::Duration,
+    last_check: std::time::Instant,
+    stamps: Vec<(PathBuf, Option<std::time::SystemTime>)>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl DataWatcher {
+    /// Watch [`data_dir`] and the content packs enabled in the settings.
+    pub fn configured() -> Self {
+        let sources = std::iter::once(DataSource::Directory(data_dir()))
+            .chain(
+                enabled_packs(&Settings::load())
+                    .into_iter()
+                    .map(|pack| pack.source),
+            )
+            .collect();
+
+        Self::new(sources)
+    }
+
+    pub fn new(sources: Vec<DataSource>) -> Self {
+        let stamps = Self::stamps(&sources);
+
+        Self {
+            sources,
+            interval: std::time::Duration::from_secs(1),
+            last_check: std::time::Instant::now(),
+            stamps,
+        }
+    }
+
+    // Archives are watched as a whole since their files can't be stamped
+    fn stamps(sources: &[DataSource]) -> Vec<(PathBuf, Option<std::time::SystemTime>)> {
+        sources
+            .iter()
+            .flat_map(|source| match source {
+                DataSource::Directory(root) => data_files(root),
+                DataSource::Archive(path) => vec![path.clone()],
+            })
+            .map(|path| {
+                let modified = std::fs::metadata(&path)
+                    .and_then(|metadata| metadata.modified())
+                    .ok();
+                (path, modified)
+            })
+            .collect()
+    }
+
+    /// True if any data file was added, removed or modified since the last
+    /// time this returned true.
+    pub fn changed(&mut self) -> bool {
+        if self.last_check.elapsed() < self.interval {
+            return false;
+        }
+        self.last_check = std::time::Instant::now();
+
+        let stamps = Self::stamps(&self.sources);
+        match stamps == self.stamps {
+            true => false,
+            false => {
+                self.stamps = stamps;
+                true
+            }
+        }
+    }
+}
+
+//====================================================================
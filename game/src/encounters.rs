@@ -0,0 +1,266 @@
+//====================================================================
+
+use engine::resources::Resources;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    battle::{Battle, BossPhase},
+    characters::actions::ActionRepo,
+    combat::Difficulty,
+    data::{ArenaDef, CharacterDef, EncounterDef, GameData, PhaseDef, Side},
+    party::Party,
+};
+
+//====================================================================
+
+/// Enemy party for a battle, along with what it was generated from so it can
+/// be reproduced.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Encounter {
+    pub pool: String,
+    pub level: u32,
+    pub seed: u64,
+    pub enemies: Vec<CharacterDef>,
+    pub triggers: Vec<PhaseTrigger>,
+    pub arena: ArenaDef,
+}
+
+/// Boss phase, waiting for its enemy's health to drop.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhaseTrigger {
+    /// Index into [`Encounter::enemies`].
+    pub enemy: usize,
+    pub phase: PhaseDef,
+}
+
+// Handed from the scene that started a battle to the battle scene
+struct QueuedEncounter(Encounter);
+
+/// Fight `encounter` in the next battle scene created.
+pub fn queue_encounter(resources: &mut Resources, encounter: Encounter) {
+    resources.insert(QueuedEncounter(encounter));
+}
+
+pub(crate) fn take_queued(resources: &mut Resources) -> Option<Encounter> {
+    resources
+        .remove::<QueuedEncounter>()
+        .map(|QueuedEncounter(encounter)| encounter)
+}
+
+//====================================================================
+
+/// Assemble an enemy party from `pool`. The same data, level and seed always
+/// give the same party.
+pub fn generate(data: &GameData, pool: &EncounterDef, level: u32, seed: u64) -> Encounter {
+    let level = level.max(1);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut budget = pool
+        .budget
+        .saturating_add(pool.budget_per_level.saturating_mul(level - 1));
+
+    let templates = pool
+        .enemies
+        .iter()
+        .filter(|enemy| enemy.cost > 0 && enemy.min_level <= level)
+        .filter_map(|enemy| {
+            data.character(&enemy.character)
+                .map(|character| (enemy.cost, character))
+        })
+        .collect::<Vec<_>>();
+
+    let mut enemies = Vec::new();
+    let mut triggers = Vec::new();
+
+    pool.bosses.iter().for_each(|boss| {
+        if let Some(character) = data.character(&boss.character) {
+            triggers.extend(boss.phases.iter().map(|phase| PhaseTrigger {
+                enemy: enemies.len(),
+                phase: phase.clone(),
+            }));
+            enemies.push(scale_character(character, level));
+        }
+    });
+
+    let max_enemies = enemies.len() + pool.max_enemies as usize;
+
+    while enemies.len() < max_enemies {
+        let affordable = templates
+            .iter()
+            .filter(|(cost, _)| *cost <= budget)
+            .collect::<Vec<_>>();
+
+        if affordable.is_empty() {
+            break;
+        }
+
+        let (cost, character) = affordable[rng.gen_range(0..affordable.len())];
+        budget -= cost;
+        enemies.push(scale_character(character, level));
+    }
+
+    // A battle needs someone to fight, even if the budget is too small
+    if enemies.is_empty() {
+        if let Some((_, character)) = templates.iter().min_by_key(|(cost, _)| *cost) {
+            enemies.push(scale_character(character, level));
+        }
+    }
+
+    name_duplicates(&mut enemies);
+
+    log::debug!(
+        "Generated '{}' encounter (level {}, seed {}) with {} enemies",
+        pool.name,
+        level,
+        seed,
+        enemies.len()
+    );
+
+    Encounter {
+        pool: pool.name.clone(),
+        level,
+        seed,
+        enemies,
+        triggers,
+        arena: pool.arena.clone(),
+    }
+}
+
+/// Generate from a random pool, choosing the pool with the same seed.
+pub fn generate_any(data: &GameData, level: u32, seed: u64) -> Option<Encounter> {
+    if data.encounters.is_empty() {
+        return None;
+    }
+
+    let index = StdRng::seed_from_u64(seed).gen_range(0..data.encounters.len());
+    Some(generate(data, &data.encounters[index], level, seed))
+}
+
+/// Set up a battle against `encounter`, or the default enemies from the data
+/// files without one, with the friendly characters at their level in `party`.
+/// Boss phases are looked up in `data` up front so the battle doesn't need
+/// it.
+pub fn start_battle(
+    data: &GameData,
+    actions: &ActionRepo,
+    party: &Party,
+    encounter: Option<&Encounter>,
+    difficulty: Difficulty,
+    seed: u64,
+) -> Battle {
+    let side = |side: Side| {
+        data.characters
+            .iter()
+            .filter(move |character| character.side == side)
+    };
+
+    let friendly = side(Side::Friendly)
+        .map(|def| party.levelled(def).to_character(actions))
+        .collect::<Vec<_>>();
+
+    let enemies = match encounter {
+        Some(encounter) => encounter.enemies.iter().collect::<Vec<_>>(),
+        None => side(Side::Enemy).collect(),
+    };
+
+    let level = encounter.map_or(1, |encounter| encounter.level);
+
+    let phases = encounter
+        .iter()
+        .flat_map(|encounter| encounter.triggers.iter())
+        .map(|trigger| {
+            boss_phase(
+                data,
+                actions,
+                level,
+                friendly.len() + trigger.enemy,
+                &trigger.phase,
+            )
+        })
+        .collect();
+
+    Battle::new(
+        difficulty,
+        friendly,
+        enemies
+            .into_iter()
+            .map(|def| def.to_character(actions))
+            .collect(),
+        phases,
+        seed,
+    )
+}
+
+// Summons are scaled to the encounter's level, like the rest of its enemies
+fn boss_phase(
+    data: &GameData,
+    actions: &ActionRepo,
+    level: u32,
+    boss: usize,
+    phase: &PhaseDef,
+) -> BossPhase {
+    BossPhase {
+        boss,
+        health_percent: phase.health_percent,
+        actions: phase.actions.as_ref().map(|names| {
+            names
+                .iter()
+                .filter_map(|name| actions.find_action_name(name))
+                .collect()
+        }),
+        summon: phase
+            .summon
+            .iter()
+            .filter_map(|name| match data.character(name) {
+                Some(def) => Some(scale_character(def, level).to_character(actions)),
+                None => {
+                    log::warn!("Unable to summon unknown character '{}'", name);
+                    None
+                }
+            })
+            .collect(),
+        cutscene: phase.cutscene.clone(),
+    }
+}
+
+// Enemy version of `character` at `level`
+pub(crate) fn scale_character(character: &CharacterDef, level: u32) -> CharacterDef {
+    let mut character = character.clone();
+
+    character.side = Side::Enemy;
+    character.player_controlled = false;
+    apply_level(&mut character, level);
+
+    character
+}
+
+/// Grow `character` to `level`, gaining a tenth of its speed and health for
+/// each level past the first.
+pub fn apply_level(character: &mut CharacterDef, level: u32) {
+    let levels = level.max(1) - 1;
+
+    character.speed += character.speed * levels / 10;
+    character.health += character.health * levels / 10;
+}
+
+// "Goblin", "Goblin" -> "Goblin 1", "Goblin 2"
+fn name_duplicates(enemies: &mut [CharacterDef]) {
+    let names = enemies
+        .iter()
+        .map(|enemy| enemy.name.clone())
+        .collect::<Vec<_>>();
+
+    enemies.iter_mut().enumerate().for_each(|(index, enemy)| {
+        let count = names.iter().filter(|name| **name == enemy.name).count();
+        if count > 1 {
+            let number = names[..index]
+                .iter()
+                .filter(|name| **name == enemy.name)
+                .count()
+                + 1;
+            enemy.name = format!("{} {}", enemy.name, number);
+        }
+    });
+}
+
+//====================================================================
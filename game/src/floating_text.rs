@@ -0,0 +1,133 @@
+//====================================================================
+
+use std::time::Duration;
+
+use common::Transform;
+use engine::{lifetime::DespawnAfter, prefab::Prefabs, StateInner};
+use hecs::Entity;
+use renderer::pipelines::ui3d_pipeline::Ui3d;
+
+//====================================================================
+
+/// Seconds floating text stays up, fading over the second half.
+const FLOAT_TIME: f32 = 1.;
+/// How far floating text rises while it's shown.
+const RISE_DISTANCE: f32 = 30.;
+/// Height above the character it was spawned for.
+const START_HEIGHT: f32 = 40.;
+
+/// Text panel that despawns once it's faded out, given its text, color and
+/// position by its overrides.
+pub const FLOATING_TEXT_PREFAB: &str = "effect_floating_text";
+
+pub fn register_prefabs(prefabs: &mut Prefabs) {
+    prefabs.register(FLOATING_TEXT_PREFAB, |_, builder| {
+        builder.add_bundle((
+            Transform::default(),
+            Ui3d::default(),
+            DespawnAfter(Duration::from_secs_f32(FLOAT_TIME)),
+        ));
+    });
+}
+
+//====================================================================
+
+/// Look of a piece of floating text.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FloatingStyle {
+    /// Panel behind the text, sRGB with linear alpha.
+    pub color: [f32; 4],
+    pub scale: f32,
+}
+
+struct Floating {
+    entity: Entity,
+    origin: glam::Vec3,
+    color: [f32; 4],
+    elapsed: f32,
+}
+
+/// Short pieces of text over characters, such as misses, that rise and fade
+/// away. Each despawns itself once it's faded out.
+#[derive(Default)]
+pub struct FloatingTexts {
+    shown: Vec<Floating>,
+}
+
+impl FloatingTexts {
+    /// Show `text` above `character`.
+    pub fn spawn(
+        &mut self,
+        state: &mut StateInner,
+        character: Entity,
+        text: impl Into<String>,
+        style: FloatingStyle,
+    ) {
+        let FloatingStyle { color, scale } = style;
+
+        let origin = match state.world.get::<&Transform>(character) {
+            Ok(transform) => transform.translation + glam::Vec3::Y * START_HEIGHT,
+            Err(_) => return,
+        };
+
+        let entity = state.spawn_prefab(
+            FLOATING_TEXT_PREFAB,
+            (
+                Transform::from_scale_translation((scale, scale, scale), origin),
+                Ui3d {
+                    menu_color: color,
+                    selection_color: color,
+                    options: vec![text.into()],
+                    ..Default::default()
+                },
+            ),
+        );
+
+        let entity = match entity {
+            Some(entity) => entity,
+            None => return,
+        };
+
+        self.shown.push(Floating {
+            entity,
+            origin,
+            color,
+            elapsed: 0.,
+        });
+    }
+
+    pub fn tick(&mut self, state: &mut StateInner) {
+        let delta = state.time.delta_seconds();
+
+        self.shown.retain_mut(|floating| {
+            if !state.world.contains(floating.entity) {
+                return false;
+            }
+
+            floating.elapsed += delta;
+
+            let t = (floating.elapsed / FLOAT_TIME).min(1.);
+            let alpha = (2. - t * 2.).min(1.);
+
+            if let Ok(mut transform) = state.world.get::<&mut Transform>(floating.entity) {
+                transform.translation = floating.origin + glam::Vec3::Y * RISE_DISTANCE * t;
+            }
+
+            if let Ok(mut ui) = state.world.get::<&mut Ui3d>(floating.entity) {
+                let [r, g, b, a] = floating.color;
+                ui.menu_color = [r, g, b, a * alpha];
+                ui.selection_color = ui.menu_color;
+            }
+
+            true
+        });
+    }
+
+    pub fn close(&mut self, state: &mut StateInner) {
+        self.shown.drain(..).for_each(|floating| {
+            state.despawn(floating.entity).ok();
+        });
+    }
+}
+
+//====================================================================
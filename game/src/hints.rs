@@ -0,0 +1,148 @@
+//====================================================================
+
+use std::collections::VecDeque;
+
+use common::Transform;
+use engine::{tools::KeyCode, StateInner};
+use hecs::Entity;
+use renderer::pipelines::ui3d_pipeline::Ui3d;
+
+use crate::{
+    controls,
+    scenes::battle_scene::ui,
+    settings::{HintSettings, Settings},
+};
+
+//====================================================================
+
+/// Dismisses the hint currently on screen.
+pub const DISMISS_HINT_KEY: KeyCode = KeyCode::Escape;
+
+/// Tips shown the first time the player reaches a situation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hint {
+    TurnOrder,
+    ActionMenu,
+    TargetMenu,
+}
+
+impl Hint {
+    /// Stable name used for the seen-flag in the settings.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Hint::TurnOrder => "turn_order",
+            Hint::ActionMenu => "action_menu",
+            Hint::TargetMenu => "target_menu",
+        }
+    }
+
+    pub fn text(&self) -> Vec<String> {
+        match self {
+            Hint::TurnOrder => vec![
+                String::from("Each round, turn order is rolled from every"),
+                String::from("character's speed - faster characters tend"),
+                String::from("to act first."),
+            ],
+            Hint::ActionMenu => vec![
+                format!(
+                    "Choose an action with {}",
+                    controls::key_label(&[ui::MENU_UP_KEY, ui::MENU_DOWN_KEY])
+                ),
+                format!(
+                    "and confirm with {}.",
+                    controls::key_label(&[ui::MENU_SELECT_KEY, ui::MENU_FORWARD_KEY])
+                ),
+            ],
+            Hint::TargetMenu => vec![
+                String::from("Pick who the action targets. Press"),
+                format!(
+                    "{} to go back to the action menu.",
+                    controls::key_label(&[ui::MENU_BACK_KEY])
+                ),
+            ],
+        }
+    }
+}
+
+//====================================================================
+
+/// Shows hints one at a time in a panel in front of the camera until they
+/// are dismissed.
+pub struct Hints {
+    seen: HintSettings,
+    queue: VecDeque<Hint>,
+    shown: Option<(Hint, Entity)>,
+}
+
+impl Hints {
+    pub fn new(seen: HintSettings) -> Self {
+        Self {
+            seen,
+            queue: VecDeque::new(),
+            shown: None,
+        }
+    }
+
+    /// Queue a hint if the player hasn't dismissed it before.
+    pub fn trigger(&mut self, hint: Hint) {
+        let pending =
+            self.queue.contains(&hint) || self.shown.is_some_and(|(shown, _)| shown == hint);
+
+        if pending || self.seen.has_seen(hint) {
+            return;
+        }
+
+        log::debug!("Showing hint '{}'", hint.id());
+        self.queue.push_back(hint);
+    }
+
+    /// Remove the panel without marking the hint as seen.
+    pub fn close(&mut self, state: &mut StateInner) {
+        if let Some((_, entity)) = self.shown.take() {
+            state.despawn(entity).ok();
+        }
+    }
+
+    pub fn tick(&mut self, state: &mut StateInner) {
+        if let Some((hint, entity)) = self.shown {
+            if !state.keys.just_pressed(DISMISS_HINT_KEY) {
+                return;
+            }
+
+            state.despawn(entity).ok();
+            self.shown = None;
+
+            self.seen.seen.insert(hint.id().to_string());
+            Settings::update(|settings| {
+                settings.hints.seen.insert(hint.id().to_string());
+            });
+        }
+
+        let hint = match self.queue.pop_front() {
+            Some(hint) => hint,
+            None => return,
+        };
+
+        let camera = &state.renderer.camera.camera;
+        let position = camera.translation + camera.forward() * 6.;
+
+        let mut options = hint.text();
+        options.push(format!(
+            "{} Dismiss",
+            controls::key_label(&[DISMISS_HINT_KEY])
+        ));
+
+        let entity = state.world.spawn((
+            Transform::from_scale_translation((0.8, 0.8, 0.8), position),
+            Ui3d {
+                selected: (options.len() - 1) as u8,
+                options,
+                ..Default::default()
+            },
+        ));
+
+        self.shown = Some((hint, entity));
+    }
+}
+
+//====================================================================
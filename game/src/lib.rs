@@ -1,14 +1,35 @@
 //====================================================================
 
-use engine::window::Runner;
-use scenes::battle_scene::BattleScene;
+use characters::Character;
+use engine::{builder::EngineBuilder, loading::LoadingScene, StateInner};
+use scenes::overworld_scene::OverworldScene;
+use settings::Settings;
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
+pub mod audio;
+pub(crate) mod banners;
 pub(crate) mod camera;
-pub(crate) mod characters;
+pub mod characters;
+pub(crate) mod controls;
+pub mod data;
+pub mod encounters;
+pub(crate) mod floating_text;
+pub(crate) mod hints;
+pub mod locale;
+pub mod music;
+pub mod party;
+pub mod placement;
+#[cfg(feature = "discord")]
+pub(crate) mod presence;
+pub mod saves;
 pub(crate) mod scenery;
 pub(crate) mod scenes;
+pub mod settings;
+pub mod stats;
+pub mod telemetry;
+
+pub use battle_core::{battle, combat, lookahead, protocol};
 
 //====================================================================
 
@@ -17,18 +38,61 @@ pub fn run() {
     #[cfg(target_arch = "wasm32")]
     {
         std::panic::set_hook(Box::new(console_error_panic_hook::hook));
-        console_log::init_with_level(log::Level::Debug).expect("Couldn't initialize logger");
+        engine::logging::init(
+            Box::new(engine::logging::ConsoleLogger(log::LevelFilter::Debug)),
+            log::LevelFilter::Debug,
+        )
+        .expect("Couldn't initialize logger");
     }
     #[cfg(not(target_arch = "wasm32"))]
-    env_logger::Builder::new()
-        // .filter_module(env!("CARGO_PKG_NAME"), log::LevelFilter::Trace)
-        .filter_module("game", log::LevelFilter::Trace)
-        .filter_module("engine", log::LevelFilter::Trace)
-        .filter_module("renderer", log::LevelFilter::Trace)
-        .filter_module("wgpu", log::LevelFilter::Warn)
-        .init();
-
-    Runner::<BattleScene>::run();
+    {
+        let logger = env_logger::Builder::new()
+            // .filter_module(env!("CARGO_PKG_NAME"), log::LevelFilter::Trace)
+            .filter_module("game", log::LevelFilter::Trace)
+            .filter_module("engine", log::LevelFilter::Trace)
+            .filter_module("renderer", log::LevelFilter::Trace)
+            .filter_module("wgpu", log::LevelFilter::Warn)
+            .build();
+
+        let max_level = logger.filter();
+        engine::logging::init(Box::new(logger), max_level).expect("Couldn't initialize logger");
+    }
+
+    engine::crash::install_panic_hook();
+
+    #[cfg(feature = "tracy")]
+    {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let subscriber = tracing_subscriber::registry().with(tracing_tracy::TracyLayer::default());
+        tracing::subscriber::set_global_default(subscriber).expect("Couldn't initialize tracy");
+        log::info!("Streaming profiling spans to tracy");
+    }
+
+    // Errors have already been reported to the user by the runner
+    let result = EngineBuilder::new()
+        .with_setup(setup)
+        .run::<LoadingScene<OverworldScene>>();
+
+    if let Err(e) = result {
+        log::error!("Exiting: {}", e);
+    }
+}
+
+// Everything that only needs doing once, before any scene exists
+fn setup(state: &mut StateInner) {
+    state.pause_on_focus_loss = Settings::load().interface.pause_on_focus_loss;
+
+    state.registry.register_component::<Character>("Character");
+    scenery::register_prefabs(&mut state.prefabs);
+    characters::register_prefabs(&mut state.prefabs);
+    floating_text::register_prefabs(&mut state.prefabs);
+    scenes::battle_scene::register_prefabs(&mut state.prefabs);
+
+    #[cfg(feature = "discord")]
+    if state.features.networking {
+        presence::install(state);
+    }
 }
 
 //====================================================================
@@ -0,0 +1,43 @@
+//====================================================================
+
+use std::collections::BTreeMap;
+
+use crate::data::GameData;
+
+//====================================================================
+
+/// Key an action's name is translated under.
+#[inline]
+pub fn action_key(name: &str) -> String {
+    format!("action.{}", name)
+}
+
+//====================================================================
+
+/// Text in the player's language, from the `[locale.<language>]` tables in
+/// the data. Anything without a translation shows as it's named in the data.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Locale {
+    strings: BTreeMap<String, String>,
+}
+
+impl Locale {
+    /// Translations for `language`, or none if the data doesn't have it.
+    pub fn new(data: &GameData, language: &str) -> Self {
+        let strings = data.locales.get(language).cloned().unwrap_or_default();
+
+        if strings.is_empty() && !language.is_empty() {
+            log::warn!("No text for language '{}'", language);
+        }
+
+        Self { strings }
+    }
+
+    pub fn action<'a>(&'a self, name: &'a str) -> &'a str {
+        self.strings
+            .get(&action_key(name))
+            .map_or(name, String::as_str)
+    }
+}
+
+//====================================================================
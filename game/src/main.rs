@@ -3,9 +3,94 @@
 //====================================================================
 
 fn main() {
+    if std::env::args().any(|arg| arg == "--validate-data") {
+        let valid = game::data::validate_data(game::data::data_dir());
+        std::process::exit(match valid {
+            true => 0,
+            false => 1,
+        });
+    }
+
+    // --headless-battle <encounter> [--seed <seed>] [--level <level>]
+    if let Some(encounter) = required_value("--headless-battle") {
+        let data = game::data::GameData::load_or_builtin();
+        let settings = game::settings::Settings::load();
+
+        let found = game::telemetry::run_headless_battle(
+            &data,
+            &encounter,
+            arg_value("--seed")
+                .and_then(|seed| seed.parse().ok())
+                .unwrap_or(0),
+            arg_value("--level")
+                .and_then(|level| level.parse().ok())
+                .unwrap_or(1),
+            settings.difficulty,
+        );
+        std::process::exit(match found {
+            true => 0,
+            false => 1,
+        });
+    }
+
+    // --simulate <runs> [--level <level>] [--write]
+    if let Some(runs) = required_value("--simulate") {
+        let runs = match runs.parse() {
+            Ok(runs) => runs,
+            Err(_) => arg_error(&format!(
+                "--simulate takes a number of runs, not '{}'",
+                runs
+            )),
+        };
+
+        let data = game::data::GameData::load_or_builtin();
+        let settings = game::settings::Settings::load();
+
+        game::telemetry::run_simulations(
+            &data,
+            runs,
+            arg_value("--level")
+                .and_then(|level| level.parse().ok())
+                .unwrap_or(1),
+            settings.difficulty,
+            std::env::args().any(|arg| arg == "--write"),
+        );
+        return;
+    }
+
+    if let Some(dir) = required_value("--summarize") {
+        let records = game::telemetry::read_records(&dir);
+        println!("Summarizing {} battle records in '{}'", records.len(), dir);
+
+        game::telemetry::print_summary(&game::telemetry::summarize(&records));
+        return;
+    }
+
     println!("Hello, world!");
 
     game::run();
 }
 
+// Value following `name` on the command line
+fn arg_value(name: &str) -> Option<String> {
+    std::env::args().skip_while(|arg| arg != name).nth(1)
+}
+
+// Value following `name` if it was passed, which it can't be without
+fn required_value(name: &str) -> Option<String> {
+    if !std::env::args().any(|arg| arg == name) {
+        return None;
+    }
+
+    match arg_value(name).filter(|value| !value.starts_with("--")) {
+        Some(value) => Some(value),
+        None => arg_error(&format!("{} needs a value", name)),
+    }
+}
+
+fn arg_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(2);
+}
+
 //====================================================================
@@ -0,0 +1,197 @@
+//====================================================================
+
+use std::{cell::RefCell, rc::Rc};
+
+use engine::{
+    events::{EventBus, Subscription},
+    music::{AudioClip, LayerMix, LayeredMusic, LoopPoints, MusicLayer},
+};
+
+use crate::{data::GameData, scenes::battle_scene::BattleEvent};
+
+//====================================================================
+
+/// Music played in arenas that don't pick their own.
+pub const DEFAULT_MUSIC: &str = "battle";
+
+/// Difference in the sides' remaining health, as a fraction of their max
+/// health, before one counts as winning.
+const LEAD_THRESHOLD: f32 = 0.25;
+
+//====================================================================
+
+/// How the battle is going, as far as the music is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MusicMood {
+    Even,
+    Winning,
+    Losing,
+    /// A boss has moved onto a later phase. Lasts the rest of the battle.
+    BossPhase,
+    Victory,
+    Defeat,
+}
+
+impl MusicMood {
+    /// Mood for a battle that ended with `ended` (whether the player won) or
+    /// is still going, given the health left on each side as fractions of
+    /// their max health.
+    pub fn select(
+        ended: Option<bool>,
+        boss_phase: bool,
+        friendly_health: f32,
+        enemy_health: f32,
+    ) -> Self {
+        match (ended, boss_phase) {
+            (Some(true), _) => MusicMood::Victory,
+            (Some(false), _) => MusicMood::Defeat,
+            (None, true) => MusicMood::BossPhase,
+            (None, false) => match friendly_health - enemy_health {
+                lead if lead > LEAD_THRESHOLD => MusicMood::Winning,
+                lead if lead < -LEAD_THRESHOLD => MusicMood::Losing,
+                _ => MusicMood::Even,
+            },
+        }
+    }
+
+    pub fn layer_mix(&self) -> LayerMix {
+        let (base, tension, victory) = match self {
+            MusicMood::Even => (1., 0.4, 0.),
+            MusicMood::Winning => (1., 0., 0.),
+            MusicMood::Losing => (0.8, 1., 0.),
+            MusicMood::BossPhase => (1., 1., 0.),
+            MusicMood::Victory => (0.5, 0., 1.),
+            MusicMood::Defeat => (0.3, 0., 0.),
+        };
+
+        LayerMix {
+            base,
+            tension,
+            victory,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Cues {
+    boss_phase: bool,
+    /// Whether the player won, once the battle is over.
+    ended: Option<bool>,
+}
+
+/// Picks the battle music's [`LayerMix`] from [`BattleEvent`]s and how much
+/// health each side has left, for [`engine::audio::Audio::set_music_mix`] to
+/// crossfade the layers.
+pub struct BattleMusic {
+    cues: Rc<RefCell<Cues>>,
+    mood: MusicMood,
+    subscription: Option<Subscription>,
+}
+
+impl BattleMusic {
+    pub fn new(events: &mut EventBus) -> Self {
+        let cues = Rc::new(RefCell::new(Cues::default()));
+
+        let subscription = {
+            let cues = cues.clone();
+            events.subscribe(move |event: &BattleEvent| Self::on_event(&cues, event))
+        };
+
+        Self {
+            cues,
+            mood: MusicMood::Even,
+            subscription: Some(subscription),
+        }
+    }
+
+    fn on_event(cues: &RefCell<Cues>, event: &BattleEvent) {
+        match event {
+            BattleEvent::BossPhase { .. } => cues.borrow_mut().boss_phase = true,
+            BattleEvent::Ended { won } => cues.borrow_mut().ended = Some(*won),
+            _ => {}
+        }
+    }
+
+    /// Stop following the battle, once it's over.
+    pub fn unsubscribe(&mut self, events: &mut EventBus) {
+        if let Some(subscription) = self.subscription.take() {
+            events.unsubscribe(subscription);
+        }
+    }
+
+    #[inline]
+    pub fn mood(&self) -> MusicMood {
+        self.mood
+    }
+
+    #[inline]
+    pub fn layer_mix(&self) -> LayerMix {
+        self.mood.layer_mix()
+    }
+
+    /// Update the mood from the health left on each side, as fractions of
+    /// their max health.
+    pub fn tick(&mut self, friendly_health: f32, enemy_health: f32) {
+        let cues = self.cues.borrow();
+        let mood = MusicMood::select(cues.ended, cues.boss_phase, friendly_health, enemy_health);
+
+        if mood != self.mood {
+            log::debug!("Battle music mood {:?} -> {:?}", self.mood, mood);
+            self.mood = mood;
+        }
+    }
+}
+
+//====================================================================
+
+/// Decode the layers of the music named `name`. None if there's no such music
+/// or its base layer can't be read. Layers that can't be read are left out.
+pub fn load_music(data: &GameData, name: &str) -> Option<LayeredMusic> {
+    let def = match data.music.iter().find(|def| def.name == name) {
+        Some(def) => def,
+        None => {
+            log::debug!("No music named '{}'", name);
+            return None;
+        }
+    };
+
+    let decode = |file: &str| {
+        let bytes = match data.audio.get(file) {
+            Some(bytes) => bytes,
+            None => {
+                log::warn!("Music '{}' uses missing sound file '{}'", name, file);
+                return None;
+            }
+        };
+
+        AudioClip::from_wav(bytes)
+            .inspect_err(|e| log::warn!("Music '{}' layer '{}': {}", name, file, e))
+            .ok()
+    };
+
+    let base = decode(&def.base)?;
+
+    // Loops have to fit inside the base layer to stay in step
+    let end = def.loop_end.unwrap_or(base.frames()).min(base.frames());
+    let loop_points = LoopPoints {
+        start: def.loop_start.unwrap_or(0).min(end.saturating_sub(1)),
+        end,
+    };
+
+    let layers = [
+        (MusicLayer::Tension, def.tension.as_deref()),
+        (MusicLayer::Victory, def.victory.as_deref()),
+    ];
+
+    let music = LayeredMusic::new(base.channels(), base.sample_rate(), loop_points)
+        .with_layer(MusicLayer::Base, base);
+
+    Some(layers.into_iter().fold(music, |music, (layer, file)| {
+        match file.and_then(|file| decode(file)) {
+            Some(clip) => music.with_layer(layer, clip),
+            None => music,
+        }
+    }))
+}
+
+//====================================================================
@@ -0,0 +1,115 @@
+//====================================================================
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{data::CharacterDef, encounters};
+
+//====================================================================
+
+/// Experience a character needs for each level.
+pub const EXPERIENCE_PER_LEVEL: u64 = 100;
+
+/// Level reached with `experience`, starting from 1.
+#[inline]
+pub fn level(experience: u64) -> u32 {
+    u32::try_from(1 + experience / EXPERIENCE_PER_LEVEL).unwrap_or(u32::MAX)
+}
+
+/// Experience one character gained from a battle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExperienceGain {
+    pub name: String,
+    pub before: u64,
+    pub gained: u64,
+}
+
+impl ExperienceGain {
+    #[inline]
+    pub fn after(&self) -> u64 {
+        self.before.saturating_add(self.gained)
+    }
+
+    /// Level reached, if the gain was enough for a new one.
+    pub fn level_up(&self) -> Option<u32> {
+        let reached = level(self.after());
+
+        match reached > level(self.before) {
+            true => Some(reached),
+            false => None,
+        }
+    }
+}
+
+//====================================================================
+
+/// Progress the player's characters carry from battle to battle. Kept in
+/// [`engine::resources::Resources`] while playing and written into saves.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Party {
+    /// Experience of each friendly character by name. Characters missing
+    /// from it have none.
+    pub experience: BTreeMap<String, u64>,
+    /// Loot picked up after battles, oldest first.
+    pub inventory: Vec<String>,
+}
+
+impl Party {
+    #[inline]
+    pub fn experience(&self, name: &str) -> u64 {
+        self.experience.get(name).copied().unwrap_or(0)
+    }
+
+    #[inline]
+    pub fn level(&self, name: &str) -> u32 {
+        level(self.experience(name))
+    }
+
+    /// Highest level among `names`, shown as the party's level.
+    pub fn highest_level<'a>(&self, names: impl IntoIterator<Item = &'a str>) -> u32 {
+        names
+            .into_iter()
+            .map(|name| self.level(name))
+            .max()
+            .unwrap_or(1)
+    }
+
+    /// Give each of `names` `amount` experience.
+    pub fn award_experience<'a>(
+        &mut self,
+        names: impl IntoIterator<Item = &'a str>,
+        amount: u64,
+    ) -> Vec<ExperienceGain> {
+        names
+            .into_iter()
+            .map(|name| {
+                let experience = self.experience.entry(name.to_string()).or_default();
+                let before = *experience;
+                *experience = experience.saturating_add(amount);
+
+                ExperienceGain {
+                    name: name.to_string(),
+                    before,
+                    gained: amount,
+                }
+            })
+            .collect()
+    }
+
+    #[inline]
+    pub fn grant_loot(&mut self, loot: impl IntoIterator<Item = String>) {
+        self.inventory.extend(loot);
+    }
+
+    /// `character` grown to the level its experience has reached. Characters
+    /// grow the same way enemies do with their encounter's level.
+    pub fn levelled(&self, character: &CharacterDef) -> CharacterDef {
+        let mut character = character.clone();
+        encounters::apply_level(&mut character, self.level(&character.name));
+        character
+    }
+}
+
+//====================================================================
@@ -0,0 +1,170 @@
+//====================================================================
+
+use common::Transform;
+use hecs::{Entity, World};
+use renderer::{
+    camera::PerspectiveCamera,
+    pipelines::{texture_pipeline::Sprite, ui3d_pipeline::Ui3d},
+};
+
+//====================================================================
+
+/// Camera movement in world units past which menus are placed again.
+pub const REPLACE_DISTANCE: f32 = 15.;
+/// Camera rotation in radians past which menus are placed again.
+pub const REPLACE_ANGLE: f32 = 0.15;
+
+/// Any amount off screen costs more than covering something up.
+const OFFSCREEN_WEIGHT: f32 = 100.;
+/// Small nudge towards earlier candidates so ties keep the preferred spot.
+const CANDIDATE_BIAS: f32 = 0.001;
+
+//====================================================================
+
+/// Rectangle on screen in normalized device coordinates, -1 to 1 across the
+/// view with y up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScreenRect {
+    pub min: glam::Vec2,
+    pub max: glam::Vec2,
+}
+
+const VIEWPORT: ScreenRect = ScreenRect {
+    min: glam::Vec2::NEG_ONE,
+    max: glam::Vec2::ONE,
+};
+
+impl ScreenRect {
+    fn from_points(points: impl IntoIterator<Item = glam::Vec2>) -> Self {
+        points.into_iter().fold(
+            Self {
+                min: glam::Vec2::INFINITY,
+                max: glam::Vec2::NEG_INFINITY,
+            },
+            |rect, point| Self {
+                min: rect.min.min(point),
+                max: rect.max.max(point),
+            },
+        )
+    }
+
+    #[inline]
+    pub fn area(&self) -> f32 {
+        let size = (self.max - self.min).max(glam::Vec2::ZERO);
+        size.x * size.y
+    }
+
+    /// Area covered by both rectangles.
+    #[inline]
+    pub fn overlap(&self, other: &ScreenRect) -> f32 {
+        Self {
+            min: self.min.max(other.min),
+            max: self.max.min(other.max),
+        }
+        .area()
+    }
+
+    /// Area hanging outside the view.
+    #[inline]
+    pub fn offscreen(&self) -> f32 {
+        self.area() - self.overlap(&VIEWPORT)
+    }
+}
+
+//====================================================================
+
+// Project a camera facing rectangle spanning `from` to `to`, as offsets along
+// the camera's right and up
+fn project_rect(
+    camera: &PerspectiveCamera,
+    position: glam::Vec3,
+    from: glam::Vec2,
+    to: glam::Vec2,
+) -> Option<ScreenRect> {
+    let right = camera.rotation * glam::Vec3::X;
+    let up = camera.rotation * glam::Vec3::Y;
+
+    let corners = [
+        glam::vec2(from.x, from.y),
+        glam::vec2(to.x, from.y),
+        glam::vec2(from.x, to.y),
+        glam::vec2(to.x, to.y),
+    ];
+
+    corners
+        .iter()
+        .map(|corner| camera.project(position + right * corner.x + up * corner.y))
+        .collect::<Option<Vec<_>>>()
+        .map(ScreenRect::from_points)
+}
+
+/// Screen area a menu covers when placed at `position`. None if any of it is
+/// behind the camera.
+pub fn ui_rect(
+    camera: &PerspectiveCamera,
+    ui: &Ui3d,
+    scale: f32,
+    position: glam::Vec3,
+) -> Option<ScreenRect> {
+    // Matches how the menu background is laid out around its position
+    let size = ui.size() * scale;
+    project_rect(
+        camera,
+        position,
+        glam::vec2(0., size.y * 0.1),
+        glam::vec2(size.x, -size.y * 0.9),
+    )
+}
+
+/// Screen area covered by an entity's sprite.
+pub fn sprite_rect(
+    camera: &PerspectiveCamera,
+    world: &World,
+    entity: Entity,
+) -> Option<ScreenRect> {
+    let mut query = world.query_one::<(&Transform, &Sprite)>(entity).ok()?;
+    let (transform, sprite) = query.get()?;
+
+    let half = sprite.size * transform.scale.truncate() / 2.;
+    project_rect(camera, transform.translation, -half, half)
+}
+
+/// Best of `candidates` for the menu on `entity`, keeping it on screen and off
+/// everything in `avoid`. Earlier candidates win ties. None if the entity
+/// isn't a menu or every candidate is behind the camera.
+pub fn place(
+    camera: &PerspectiveCamera,
+    world: &World,
+    entity: Entity,
+    candidates: &[glam::Vec3],
+    avoid: &[ScreenRect],
+) -> Option<(glam::Vec3, ScreenRect)> {
+    let mut query = world.query_one::<(&Transform, &Ui3d)>(entity).ok()?;
+    let (transform, ui) = query.get()?;
+
+    candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(index, candidate)| {
+            let rect = ui_rect(camera, ui, transform.scale.x, *candidate)?;
+
+            let covered = avoid.iter().map(|other| rect.overlap(other)).sum::<f32>();
+            let cost =
+                rect.offscreen() * OFFSCREEN_WEIGHT + covered + index as f32 * CANDIDATE_BIAS;
+
+            Some((cost, *candidate, rect))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, candidate, rect)| (candidate, rect))
+}
+
+/// True once the camera has moved or turned enough since `from` that
+/// placements should be worked out again.
+pub fn camera_moved(camera: &PerspectiveCamera, from: (glam::Vec3, glam::Quat)) -> bool {
+    let (translation, rotation) = from;
+
+    camera.translation.distance(translation) > REPLACE_DISTANCE
+        || camera.rotation.angle_between(rotation) > REPLACE_ANGLE
+}
+
+//====================================================================
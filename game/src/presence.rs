@@ -0,0 +1,137 @@
+//====================================================================
+
+use std::sync::mpsc::{self, Sender};
+
+use discord_rich_presence::{activity::Activity, DiscordIpc, DiscordIpcClient};
+use engine::{events::SceneChanged, StateInner};
+
+use crate::scenes::battle_scene::BattleEvent;
+
+//====================================================================
+
+/// Discord application id to report presence under.
+pub const APP_ID_VAR: &str = "TURNBASE_DISCORD_APP_ID";
+
+// Details and state to show, sent to the presence thread
+type PresenceUpdate = (String, Option<String>);
+
+// Kept in the state's resources once installed
+struct PresenceInstalled;
+
+struct Presence {
+    client: DiscordIpcClient,
+    details: String,
+    state: Option<String>,
+}
+
+impl Presence {
+    fn connect(app_id: &str) -> Option<Self> {
+        let mut client = match DiscordIpcClient::new(app_id) {
+            Ok(client) => client,
+            Err(e) => {
+                log::warn!("Unable to create Discord client: {}", e);
+                return None;
+            }
+        };
+
+        if let Err(e) = client.connect() {
+            log::warn!("Unable to connect to Discord: {}", e);
+            return None;
+        }
+
+        log::info!("Connected to Discord for rich presence");
+
+        Some(Self {
+            client,
+            details: String::new(),
+            state: None,
+        })
+    }
+
+    fn update(&mut self, details: String, state: Option<String>) {
+        if self.details == details && self.state == state {
+            return;
+        }
+
+        self.details = details;
+        self.state = state;
+
+        let mut activity = Activity::new().details(&self.details);
+        if let Some(state) = &self.state {
+            activity = activity.state(state);
+        }
+
+        if let Err(e) = self.client.set_activity(activity) {
+            log::warn!("Unable to update Discord presence: {}", e);
+        }
+    }
+}
+
+//====================================================================
+
+/// Report the current scene and battle progress to Discord. Talking to
+/// Discord blocks, so it's done on a thread of its own. Only the first call
+/// does anything.
+pub fn install(state: &mut StateInner) {
+    if state.resources.insert(PresenceInstalled).is_some() {
+        return;
+    }
+
+    let app_id = match std::env::var(APP_ID_VAR) {
+        Ok(app_id) => app_id,
+        Err(_) => {
+            log::info!("{} not set - Discord presence disabled", APP_ID_VAR);
+            return;
+        }
+    };
+
+    let (sender, receiver) = mpsc::channel::<PresenceUpdate>();
+
+    let result = std::thread::Builder::new()
+        .name(String::from("discord presence"))
+        .spawn(move || {
+            let mut presence = match Presence::connect(&app_id) {
+                Some(presence) => presence,
+                None => return,
+            };
+
+            // Ends once the game drops its senders
+            receiver
+                .into_iter()
+                .for_each(|(details, state)| presence.update(details, state));
+        });
+
+    if let Err(e) = result {
+        log::warn!("Unable to start Discord presence: {}", e);
+        return;
+    }
+
+    subscribe(state, sender);
+}
+
+fn subscribe(state: &mut StateInner, sender: Sender<PresenceUpdate>) {
+    let scene_sender = sender.clone();
+    state.events.subscribe(move |event: &SceneChanged| {
+        let details = match event.name {
+            "Battle" => "In battle",
+            "Overworld" => "Exploring",
+            "Loading" => "Loading",
+            _ => "In menus",
+        };
+
+        scene_sender.send((details.to_string(), None)).ok();
+    });
+
+    state.events.subscribe(move |event: &BattleEvent| {
+        let round = match event {
+            BattleEvent::RoundStarted { round } | BattleEvent::TurnStarted { round, .. } => round,
+            _ => return,
+        };
+
+        sender
+            .send((String::from("In battle"), Some(format!("Round {}", round))))
+            .ok();
+    });
+}
+
+//====================================================================
@@ -0,0 +1,715 @@
+//====================================================================
+
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use common::PhysicalSize;
+use engine::{registry::Saved, StateInner};
+use renderer::image::{self, RgbaImage};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::party::Party;
+
+//====================================================================
+
+/// Folder in the working directory holding save slots on native, and the
+/// prefix for local storage keys on web.
+pub const SAVES_DIR: &str = "saves";
+/// Number of slots the player saves to.
+pub const SLOT_COUNT: usize = 4;
+/// Extra slot after the player's, written automatically.
+pub const AUTOSAVE_SLOT: usize = SLOT_COUNT;
+/// Previous versions of each slot kept in case the latest is unreadable.
+const BACKUP_COUNT: usize = 3;
+
+/// Thumbnails are shrunk to fit within this size.
+const THUMBNAIL_SIZE: PhysicalSize<u32> = PhysicalSize {
+    width: 160,
+    height: 90,
+};
+/// Frames to wait for a thumbnail before saving without one.
+const THUMBNAIL_FRAMES: u32 = 10;
+
+//====================================================================
+
+/// Shown in the slot list without loading the rest of the save.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SaveMeta {
+    /// Seconds since the unix epoch.
+    pub timestamp: u64,
+    /// Seconds played, across every session that led to this save.
+    pub playtime: u64,
+    pub party: String,
+}
+
+impl SaveMeta {
+    /// Readable UTC date and time the save was made.
+    pub fn date(&self) -> String {
+        let days = (self.timestamp / 86400) as i64;
+        let seconds = self.timestamp % 86400;
+
+        // Days to civil date, from Howard Hinnant's date algorithms
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z.rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + (month <= 2) as i64;
+
+        format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}",
+            year,
+            month,
+            day,
+            seconds / 3600,
+            seconds % 3600 / 60
+        )
+    }
+
+    pub fn playtime(&self) -> String {
+        format!(
+            "{}h {:02}m",
+            self.playtime / 3600,
+            self.playtime % 3600 / 60
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SaveData {
+    /// Format the save was written in, see [`SAVE_VERSION`].
+    pub version: u32,
+    pub meta: SaveMeta,
+    pub overworld: OverworldSave,
+    pub party: Party,
+    /// Entities marked [`Saved`], as the json array written by
+    /// [`ComponentRegistry::save_world`](engine::registry::ComponentRegistry::save_world).
+    /// Kept as json since components can hold nulls, which toml doesn't have.
+    pub entities: String,
+}
+
+impl Default for SaveData {
+    fn default() -> Self {
+        Self {
+            version: SAVE_VERSION,
+            meta: SaveMeta::default(),
+            overworld: OverworldSave::default(),
+            party: Party::default(),
+            entities: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OverworldSave {
+    pub position: glam::Vec3,
+    pub travelled: f32,
+}
+
+//====================================================================
+
+#[derive(Debug, thiserror::Error)]
+pub enum SaveError {
+    #[error("Invalid save: {0}")]
+    Parse(String),
+
+    #[error(
+        "Save is from a newer version ({0}, expected {} or older)",
+        SAVE_VERSION
+    )]
+    TooNew(u32),
+
+    #[error("Unable to upgrade save from version {version}: {message}")]
+    Migration { version: u32, message: String },
+
+    #[error("Slot {0} is empty")]
+    EmptySlot(usize),
+
+    #[error("Unable to export save: {0}")]
+    Export(String),
+
+    #[error("Unable to import save: {0}")]
+    Import(String),
+}
+
+/// Version written to new saves. Bump it and add a migration to
+/// [`MIGRATIONS`] whenever the save format changes.
+pub const SAVE_VERSION: u32 = 2;
+
+type Migration = fn(&mut toml::Table) -> Result<(), String>;
+
+/// Each upgrades a save from the version matching its position (starting at
+/// 1) to the next version.
+const MIGRATIONS: [Migration; SAVE_VERSION as usize - 1] = [migrate_v1];
+
+// Version 2 moved overworld state into its own table
+fn migrate_v1(save: &mut toml::Table) -> Result<(), String> {
+    let mut overworld = toml::Table::new();
+
+    ["position", "travelled"].into_iter().for_each(|key| {
+        if let Some(value) = save.remove(key) {
+            overworld.insert(key.to_string(), value);
+        }
+    });
+
+    save.insert(String::from("overworld"), toml::Value::Table(overworld));
+    Ok(())
+}
+
+/// Parse a save of any supported version, upgrading it to [`SAVE_VERSION`].
+pub fn parse_save(contents: &str) -> Result<SaveData, SaveError> {
+    let mut save =
+        toml::from_str::<toml::Table>(contents).map_err(|e| SaveError::Parse(e.to_string()))?;
+
+    // Saves from before versioning have no version
+    let version = match save.get("version") {
+        Some(version) => version
+            .as_integer()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| SaveError::Parse(String::from("version isn't a number")))?,
+        None => 1,
+    };
+
+    if version > SAVE_VERSION {
+        return Err(SaveError::TooNew(version));
+    }
+
+    (version.max(1)..SAVE_VERSION).try_for_each(|from| {
+        log::info!("Upgrading save from version {} to {}", from, from + 1);
+
+        MIGRATIONS[from as usize - 1](&mut save).map_err(|message| SaveError::Migration {
+            version: from,
+            message,
+        })
+    })?;
+
+    save.insert(
+        String::from("version"),
+        toml::Value::Integer(SAVE_VERSION as i64),
+    );
+
+    toml::Value::Table(save)
+        .try_into()
+        .map_err(|e| SaveError::Parse(e.to_string()))
+}
+
+//====================================================================
+
+/// Slot being played and where its playtime is counted from. Kept in
+/// [`engine::resources::Resources`] for as long as the app runs.
+#[derive(Debug, Default)]
+pub struct SaveSession {
+    slot: Option<usize>,
+    playtime: Duration,
+    started: Duration,
+    /// Handed from the load scene to the overworld.
+    loaded: Option<SaveData>,
+}
+
+// Set when leaving a battle so the overworld autosaves
+struct AutosaveRequested;
+
+fn since_startup(state: &StateInner) -> Duration {
+    state
+        .time
+        .frame_start()
+        .duration_since(*state.time.elapsed())
+}
+
+/// Total time played, including time from the save that was loaded.
+pub fn playtime(state: &StateInner) -> Duration {
+    match state.resources.get::<SaveSession>() {
+        Some(session) => session.playtime + since_startup(state).saturating_sub(session.started),
+        None => since_startup(state),
+    }
+}
+
+/// Slot last loaded or saved to this session.
+pub fn current_slot(state: &StateInner) -> Option<usize> {
+    state
+        .resources
+        .get::<SaveSession>()
+        .and_then(|session| session.slot)
+}
+
+/// Continue from `data` in the next overworld scene created.
+pub fn queue_load(state: &mut StateInner, slot: usize, data: SaveData) {
+    let started = since_startup(state);
+
+    state.resources.insert(SaveSession {
+        slot: Some(slot),
+        playtime: Duration::from_secs(data.meta.playtime),
+        started,
+        loaded: Some(data),
+    });
+}
+
+pub(crate) fn take_loaded(state: &mut StateInner) -> Option<SaveData> {
+    state
+        .resources
+        .get_mut::<SaveSession>()
+        .and_then(|session| session.loaded.take())
+}
+
+/// Write every entity marked [`Saved`] into `data`.
+pub fn save_entities(state: &StateInner, data: &mut SaveData) {
+    let entities = state.registry.save_world(&state.world);
+
+    data.entities = serde_json::to_string(&entities).unwrap_or_else(|e| {
+        log::error!("Unable to save entities: {}", e);
+        String::new()
+    });
+}
+
+/// Replace every entity marked [`Saved`] with those in `data`.
+pub fn load_entities(state: &mut StateInner, data: &SaveData) {
+    let old = state
+        .world
+        .query::<&Saved>()
+        .iter()
+        .map(|(entity, _)| entity)
+        .collect::<Vec<_>>();
+
+    old.into_iter().for_each(|entity| {
+        state.despawn(entity).ok();
+    });
+
+    // Saves from before entities were saved have none
+    if data.entities.is_empty() {
+        return;
+    }
+
+    match serde_json::from_str::<Vec<Map<String, Value>>>(&data.entities) {
+        Ok(entities) => {
+            let entities = state.registry.load_world(&mut state.world, &entities);
+            log::info!("Loaded {} saved entities", entities.len());
+        }
+        Err(e) => log::error!("Unable to load saved entities: {}", e),
+    }
+}
+
+/// Autosave once the overworld is back.
+pub fn request_autosave(state: &mut StateInner) {
+    state.resources.insert(AutosaveRequested);
+}
+
+pub(crate) fn take_autosave_request(state: &mut StateInner) -> bool {
+    state.resources.remove::<AutosaveRequested>().is_some()
+}
+
+//====================================================================
+
+// Save files are kept in SAVES_DIR on native and in local storage on web,
+// where they're base64 encoded
+#[cfg(not(target_arch = "wasm32"))]
+mod storage {
+    use std::{io, path::PathBuf};
+
+    fn path(name: &str) -> PathBuf {
+        std::path::Path::new(super::SAVES_DIR).join(name)
+    }
+
+    pub fn read(name: &str) -> io::Result<Vec<u8>> {
+        std::fs::read(path(name))
+    }
+
+    pub fn write(name: &str, bytes: &[u8]) -> io::Result<()> {
+        std::fs::create_dir_all(super::SAVES_DIR)?;
+        std::fs::write(path(name), bytes)
+    }
+
+    pub fn rename(from: &str, to: &str) -> io::Result<()> {
+        std::fs::rename(path(from), path(to))
+    }
+
+    pub fn remove(name: &str) -> io::Result<()> {
+        std::fs::remove_file(path(name))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod storage {
+    use std::io;
+
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    fn local_storage() -> io::Result<web_sys::Storage> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "No local storage"))
+    }
+
+    fn key(name: &str) -> String {
+        format!("{}/{}", super::SAVES_DIR, name)
+    }
+
+    fn js_error(e: wasm_bindgen::JsValue) -> io::Error {
+        io::Error::other(format!("{:?}", e))
+    }
+
+    pub fn read(name: &str) -> io::Result<Vec<u8>> {
+        let value = local_storage()?
+            .get_item(&key(name))
+            .map_err(js_error)?
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+
+        STANDARD
+            .decode(value)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn write(name: &str, bytes: &[u8]) -> io::Result<()> {
+        local_storage()?
+            .set_item(&key(name), &STANDARD.encode(bytes))
+            .map_err(js_error)
+    }
+
+    pub fn rename(from: &str, to: &str) -> io::Result<()> {
+        write(to, &read(from)?)?;
+        remove(from)
+    }
+
+    pub fn remove(name: &str) -> io::Result<()> {
+        let storage = local_storage()?;
+
+        match storage.get_item(&key(name)).map_err(js_error)? {
+            Some(_) => storage.remove_item(&key(name)).map_err(js_error),
+            None => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+}
+
+fn slot_file(slot: usize, extension: &str) -> String {
+    match slot {
+        AUTOSAVE_SLOT => format!("autosave.{}", extension),
+        slot => format!("slot_{}.{}", slot, extension),
+    }
+}
+
+// Backup 0 is the save itself
+fn backup_file(slot: usize, backup: usize) -> String {
+    match backup {
+        0 => slot_file(slot, "toml"),
+        backup => slot_file(slot, &format!("bak{}.toml", backup)),
+    }
+}
+
+// Missing files are already removed
+fn remove_file(name: &str) -> std::io::Result<()> {
+    match storage::remove(name) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// Save in `slot`, if there is one. Falls back to the newest readable backup
+/// when the save itself can't be read.
+pub fn load(slot: usize) -> Option<SaveData> {
+    for backup in 0..=BACKUP_COUNT {
+        let name = backup_file(slot, backup);
+
+        let result = match storage::read(&name) {
+            Ok(bytes) => String::from_utf8(bytes)
+                .map_err(|e| e.to_string())
+                .and_then(|contents| parse_save(&contents).map_err(|e| e.to_string())),
+            // A backup can outlive a save lost partway through being replaced
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => Err(e.to_string()),
+        };
+
+        match result {
+            Ok(data) => {
+                if backup > 0 {
+                    log::warn!("Loaded slot {} from backup '{}'", slot, name);
+                }
+                return Some(data);
+            }
+            Err(e) => log::error!("Unable to load save '{}': {}", name, e),
+        }
+    }
+
+    None
+}
+
+/// Metadata for every slot, or `None` where it's empty. The autosave is
+/// last.
+pub fn list() -> Vec<Option<SaveMeta>> {
+    (0..=AUTOSAVE_SLOT)
+        .map(|slot| load(slot).map(|data| data.meta))
+        .collect()
+}
+
+/// PNG thumbnail captured when `slot` was saved.
+#[inline]
+pub fn thumbnail(slot: usize) -> Option<Vec<u8>> {
+    storage::read(&slot_file(slot, "png")).ok()
+}
+
+/// First empty slot the player can save to.
+#[inline]
+pub fn first_free() -> Option<usize> {
+    list()[..SLOT_COUNT].iter().position(|meta| meta.is_none())
+}
+
+fn encode_thumbnail(thumbnail: &RgbaImage) -> Result<Vec<u8>, image::ImageError> {
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    thumbnail.write_to(&mut bytes, image::ImageFormat::Png)?;
+    Ok(bytes.into_inner())
+}
+
+pub fn write(slot: usize, data: &SaveData, thumbnail: Option<&RgbaImage>) {
+    let thumbnail = thumbnail.and_then(|thumbnail| {
+        encode_thumbnail(thumbnail)
+            .map_err(|e| log::warn!("Unable to encode thumbnail: {}", e))
+            .ok()
+    });
+
+    write_encoded(slot, data, thumbnail.as_deref());
+}
+
+// Thumbnail is already a PNG. The save is written in full before it
+// replaces anything, so a failed write leaves the slot and its backups as
+// they were.
+fn write_encoded(slot: usize, data: &SaveData, thumbnail: Option<&[u8]>) {
+    let temp_file = slot_file(slot, "tmp");
+
+    let result = toml::to_string(data)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| {
+            storage::write(&temp_file, contents.as_bytes()).map_err(|e| e.to_string())
+        });
+
+    if let Err(e) = result {
+        log::error!("Unable to save to slot {}: {}", slot, e);
+        remove_file(&temp_file).ok();
+        return;
+    }
+
+    // Oldest backup drops off the end
+    (0..BACKUP_COUNT).rev().for_each(|backup| {
+        let from = backup_file(slot, backup);
+        match storage::rename(&from, &backup_file(slot, backup + 1)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                log::warn!("Unable to back up '{}': {}", from, e)
+            }
+            _ => {}
+        }
+    });
+
+    if let Err(e) = storage::rename(&temp_file, &backup_file(slot, 0)) {
+        log::error!("Unable to save to slot {}: {}", slot, e);
+        return;
+    }
+
+    // A stale thumbnail is worse than none
+    let thumbnail_file = slot_file(slot, "png");
+    let result = match thumbnail {
+        Some(thumbnail) => storage::write(&thumbnail_file, thumbnail),
+        None => remove_file(&thumbnail_file),
+    };
+
+    if let Err(e) = result {
+        log::warn!("Unable to save thumbnail for slot {}: {}", slot, e);
+    }
+
+    log::info!("Saved to slot {}", slot);
+}
+
+/// Remove a slot along with its thumbnail and backups.
+pub fn delete(state: &mut StateInner, slot: usize) {
+    (0..=BACKUP_COUNT)
+        .map(|backup| backup_file(slot, backup))
+        .chain(std::iter::once(slot_file(slot, "png")))
+        .for_each(|name| {
+            if let Err(e) = remove_file(&name) {
+                log::error!("Unable to delete save '{}': {}", name, e);
+            }
+        });
+
+    if let Some(session) = state.resources.get_mut::<SaveSession>() {
+        if session.slot == Some(slot) {
+            session.slot = None;
+        }
+    }
+
+    log::info!("Deleted save slot {}", slot);
+}
+
+pub fn copy(from: usize, to: usize) {
+    let data = match load(from) {
+        Some(data) => data,
+        None => return,
+    };
+
+    write_encoded(to, &data, thumbnail(from).as_deref());
+    log::info!("Copied save slot {} to {}", from, to);
+}
+
+//====================================================================
+
+// Names of the files inside an exported save
+const EXPORT_SAVE: &str = "save.toml";
+const EXPORT_THUMBNAIL: &str = "thumbnail.png";
+
+/// Pack a slot into a single compressed blob for moving it between installs.
+/// Every file in it is checksummed.
+pub fn export(slot: usize) -> Result<Vec<u8>, SaveError> {
+    use std::io::Write;
+
+    let data = load(slot).ok_or(SaveError::EmptySlot(slot))?;
+    let contents = toml::to_string(&data).map_err(|e| SaveError::Export(e.to_string()))?;
+
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+
+    let result = zip
+        .start_file(EXPORT_SAVE, options)
+        .map_err(|e| e.to_string())
+        .and_then(|_| {
+            zip.write_all(contents.as_bytes())
+                .map_err(|e| e.to_string())
+        });
+
+    // Thumbnails are already compressed
+    let result = result.and_then(|_| match thumbnail(slot) {
+        Some(thumbnail) => zip
+            .start_file(
+                EXPORT_THUMBNAIL,
+                options.compression_method(zip::CompressionMethod::Stored),
+            )
+            .map_err(|e| e.to_string())
+            .and_then(|_| zip.write_all(&thumbnail).map_err(|e| e.to_string())),
+        None => Ok(()),
+    });
+
+    result
+        .and_then(|_| zip.finish().map_err(|e| e.to_string()))
+        .map(|cursor| cursor.into_inner())
+        .map_err(SaveError::Export)
+}
+
+/// [`export`] as base64, for copying and pasting as text.
+#[inline]
+pub fn export_string(slot: usize) -> Result<String, SaveError> {
+    export(slot).map(|blob| STANDARD.encode(blob))
+}
+
+/// Unpack an exported save into `slot`, upgrading it if it's from an older
+/// version. Accepts the blob or its base64 text.
+pub fn import(slot: usize, blob: &[u8]) -> Result<SaveData, SaveError> {
+    use std::io::Read;
+
+    // Zip archives start with "PK"
+    let blob = match blob.starts_with(b"PK") {
+        true => blob.to_vec(),
+        false => {
+            let text = std::str::from_utf8(blob).map_err(|e| SaveError::Import(e.to_string()))?;
+            STANDARD
+                .decode(text.trim())
+                .map_err(|e| SaveError::Import(e.to_string()))?
+        }
+    };
+
+    let mut zip = zip::ZipArchive::new(std::io::Cursor::new(blob))
+        .map_err(|e| SaveError::Import(e.to_string()))?;
+
+    // Reading to the end verifies the checksum
+    let mut read = |name: &str| -> Result<Option<Vec<u8>>, SaveError> {
+        let mut file = match zip.by_name(name) {
+            Ok(file) => file,
+            Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+            Err(e) => return Err(SaveError::Import(e.to_string())),
+        };
+
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)
+            .map_err(|e| SaveError::Import(format!("'{}': {}", name, e)))?;
+
+        Ok(Some(bytes))
+    };
+
+    let contents = read(EXPORT_SAVE)?
+        .ok_or_else(|| SaveError::Import(format!("Missing '{}'", EXPORT_SAVE)))?;
+    let thumbnail = read(EXPORT_THUMBNAIL)?;
+
+    let contents = String::from_utf8(contents).map_err(|e| SaveError::Import(e.to_string()))?;
+    let data = parse_save(&contents)?;
+
+    write_encoded(slot, &data, thumbnail.as_deref());
+    log::info!("Imported save into slot {}", slot);
+
+    Ok(data)
+}
+
+//====================================================================
+
+/// Save waiting on a screenshot for its thumbnail.
+pub struct PendingSave {
+    slot: usize,
+    data: SaveData,
+    frames: u32,
+}
+
+impl PendingSave {
+    /// Start saving to `slot`, taking a screenshot of the next frame.
+    pub fn new(state: &mut StateInner, slot: usize, mut data: SaveData) -> Self {
+        data.meta.playtime = playtime(state).as_secs();
+
+        data.meta.timestamp = web_time::SystemTime::now()
+            .duration_since(web_time::SystemTime::UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+
+        state.renderer.request_screenshot();
+
+        Self {
+            slot,
+            data,
+            frames: 0,
+        }
+    }
+
+    /// Returns true once the save has been written.
+    pub fn tick(&mut self, state: &mut StateInner) -> bool {
+        self.frames += 1;
+
+        let thumbnail = state.renderer.take_screenshot().map(|screenshot| {
+            let PhysicalSize { width, height } = THUMBNAIL_SIZE;
+            let scale = (width as f32 / screenshot.width() as f32)
+                .min(height as f32 / screenshot.height() as f32);
+
+            image::imageops::thumbnail(
+                &screenshot,
+                ((screenshot.width() as f32 * scale) as u32).max(1),
+                ((screenshot.height() as f32 * scale) as u32).max(1),
+            )
+        });
+
+        if thumbnail.is_none() && self.frames < THUMBNAIL_FRAMES {
+            return false;
+        }
+
+        self.finish(state, thumbnail.as_ref());
+        true
+    }
+
+    /// Write the save straight away, such as when quitting.
+    pub fn finish(&self, state: &mut StateInner, thumbnail: Option<&RgbaImage>) {
+        write(self.slot, &self.data, thumbnail);
+
+        // Manual saves keep going to the same slot, autosaves don't take it over
+        if self.slot != AUTOSAVE_SLOT {
+            state.resources.get_or_default::<SaveSession>().slot = Some(self.slot);
+        }
+    }
+}
+
+//====================================================================
@@ -1,26 +1,160 @@
 //====================================================================
 
 use common::Transform;
-use engine::StateInner;
-use renderer::pipelines::texture_pipeline::Sprite;
+use engine::{prefab::Prefabs, tools::KeyCode, StateInner};
+use renderer::{
+    background::{BackgroundLayer, BACKGROUND_LAYER},
+    pipelines::{grid_pipeline::Grid, texture_pipeline::Sprite},
+};
 
 //====================================================================
 
 pub struct Scenery;
 
-pub fn spawn_scenery(state: &mut StateInner) {
-    state.world.spawn((
-        Scenery,
-        Transform::from_rotation_translation(
-            glam::Quat::from_rotation_x(90_f32.to_radians()),
-            glam::vec3(0., -20., 0.),
-        ),
-        Sprite {
-            texture: state.renderer.default_texture.get(),
-            size: glam::vec2(500., 500.),
-            color: [0.3, 0.3, 0.3, 1.],
-        },
-    ));
+/// Switches the ground between the plain floor and the grid.
+pub const GROUND_KEY: KeyCode = KeyCode::F3;
+
+/// Height of the ground the characters stand on.
+const GROUND_HEIGHT: f32 = -20.;
+
+/// What the characters stand on. Also added to the ground entity so it can be
+/// found and swapped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Ground {
+    #[default]
+    Plain,
+    /// Endless grid, handy for lining characters up.
+    Grid,
+}
+
+impl Ground {
+    #[inline]
+    fn prefab(&self) -> &'static str {
+        match self {
+            Ground::Plain => GROUND_PREFAB,
+            Ground::Grid => GRID_PREFAB,
+        }
+    }
+}
+
+/// Flat ground the characters stand on.
+pub const GROUND_PREFAB: &str = "scenery_ground";
+/// Endless grid used in place of the flat ground.
+pub const GRID_PREFAB: &str = "scenery_grid";
+/// Parallax layer behind everything, sized and colored by its overrides.
+pub const BACKGROUND_PREFAB: &str = "scenery_background";
+
+pub fn register_prefabs(prefabs: &mut Prefabs) {
+    prefabs.register(GROUND_PREFAB, |renderer, builder| {
+        builder.add_bundle((
+            Scenery,
+            Ground::Plain,
+            Transform::from_rotation_translation(
+                glam::Quat::from_rotation_x(90_f32.to_radians()),
+                glam::vec3(0., GROUND_HEIGHT, 0.),
+            ),
+            Sprite {
+                color: [0.3, 0.3, 0.3, 1.],
+                ..Sprite::new(renderer.default_texture.get(), glam::vec2(500., 500.))
+            },
+        ));
+    });
+
+    prefabs.register(GRID_PREFAB, |_, builder| {
+        builder.add_bundle((
+            Scenery,
+            Ground::Grid,
+            Transform::from_translation(glam::vec3(0., GROUND_HEIGHT, 0.)),
+            Grid::default(),
+        ));
+    });
+
+    prefabs.register(BACKGROUND_PREFAB, |renderer, builder| {
+        builder.add_bundle((
+            Scenery,
+            Transform::default(),
+            Sprite::new(renderer.default_texture.get(), glam::Vec2::ONE),
+            BACKGROUND_LAYER,
+        ));
+    });
+}
+
+pub fn spawn_scenery(state: &mut StateInner, ground: Ground) {
+    state.spawn_prefab(ground.prefab(), ());
+
+    // Far sky drifts slowly and barely moves with the camera
+    spawn_background(
+        state,
+        BackgroundLayer::new(glam::vec3(0., 100., 900.), 0.9).with_scroll(glam::vec2(0.01, 0.)),
+        glam::vec2(4000., 1500.),
+        [0.35, 0.5, 0.75, 1.],
+    );
+
+    // Hills in the middle distance
+    spawn_background(
+        state,
+        BackgroundLayer::new(glam::vec3(0., 0., 600.), 0.5),
+        glam::vec2(2500., 200.),
+        [0.25, 0.4, 0.25, 1.],
+    );
+}
+
+fn spawn_background(
+    state: &mut StateInner,
+    layer: BackgroundLayer,
+    size: glam::Vec2,
+    color: [f32; 4],
+) {
+    let sprite = Sprite {
+        color,
+        ..Sprite::new(state.renderer.default_texture.get(), size)
+    };
+
+    state.spawn_prefab(
+        BACKGROUND_PREFAB,
+        (Transform::from_translation(layer.anchor), sprite, layer),
+    );
+}
+
+/// Swap the ground when [`GROUND_KEY`] is pressed.
+pub fn tick_ground(state: &mut StateInner) {
+    if !state.keys.just_pressed(GROUND_KEY) {
+        return;
+    }
+
+    let current = state
+        .world
+        .query_mut::<&Ground>()
+        .into_iter()
+        .map(|(id, ground)| (id, *ground))
+        .next();
+
+    let ground = match current {
+        Some((id, ground)) => {
+            state.despawn(id).ok();
+            match ground {
+                Ground::Plain => Ground::Grid,
+                Ground::Grid => Ground::Plain,
+            }
+        }
+        None => Ground::default(),
+    };
+
+    state.spawn_prefab(ground.prefab(), ());
+    log::info!("Ground set to {:?}", ground);
+}
+
+pub fn despawn_scenery(state: &mut StateInner) {
+    let scenery = state
+        .world
+        .query_mut::<&Scenery>()
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+
+    scenery.into_iter().for_each(|id| {
+        state.despawn(id).ok();
+    });
 }
 
 //====================================================================
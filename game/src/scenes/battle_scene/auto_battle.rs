@@ -0,0 +1,81 @@
+//====================================================================
+
+use common::Transform;
+use engine::{tools::KeyCode, StateInner};
+use hecs::Entity;
+use renderer::pipelines::ui3d_pipeline::Ui3d;
+
+use crate::combat::CpuProfile;
+
+//====================================================================
+
+pub const AUTO_BATTLE_KEY: KeyCode = KeyCode::KeyT;
+
+/// How the player's characters pick actions while auto battling.
+pub const AUTO_BATTLE_PROFILE: CpuProfile = CpuProfile {
+    aggressiveness: 70,
+    lookahead: 2,
+};
+
+const INDICATOR_COLOR: [f32; 4] = [0.45, 0.2, 0.1, 0.8];
+
+//====================================================================
+
+/// Hands the player's characters over to the cpu until toggled off with
+/// [`AUTO_BATTLE_KEY`], showing "AUTO" in the corner of the view while on.
+#[derive(Default)]
+pub struct AutoBattle {
+    indicator: Option<Entity>,
+}
+
+impl AutoBattle {
+    #[inline]
+    pub fn is_on(&self) -> bool {
+        self.indicator.is_some()
+    }
+
+    pub fn toggle(&mut self, state: &mut StateInner) {
+        match self.is_on() {
+            true => self.close(state),
+            false => {
+                log::info!("Auto battle on");
+
+                self.indicator = Some(state.world.spawn((
+                    Transform::from_scale((0.4, 0.4, 0.4)),
+                    Ui3d {
+                        menu_color: INDICATOR_COLOR,
+                        selection_color: INDICATOR_COLOR,
+                        options: vec!["AUTO".into()],
+                        font_size: 24.,
+                        ..Default::default()
+                    },
+                )));
+            }
+        }
+    }
+
+    pub fn tick(&self, state: &mut StateInner) {
+        let entity = match self.indicator {
+            Some(entity) => entity,
+            None => return,
+        };
+
+        let camera = &state.renderer.camera.camera;
+        let position =
+            camera.translation + camera.forward() * 8. + camera.right() * -5. + glam::Vec3::Y * 2.6;
+
+        if let Ok(mut transform) = state.world.get::<&mut Transform>(entity) {
+            transform.translation = position;
+        }
+    }
+
+    /// Give control back to the player.
+    pub fn close(&mut self, state: &mut StateInner) {
+        if let Some(entity) = self.indicator.take() {
+            log::info!("Auto battle off");
+            state.despawn(entity).ok();
+        }
+    }
+}
+
+//====================================================================
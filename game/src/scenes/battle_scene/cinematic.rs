@@ -0,0 +1,189 @@
+//====================================================================
+
+use common::Transform;
+use engine::StateInner;
+use hecs::Entity;
+use renderer::viewport::{AspectPolicy, VirtualResolution};
+
+use crate::characters::actions::CinematicDef;
+
+//====================================================================
+
+/// Width over height of the letterboxed view while a cinematic plays.
+const CINEMATIC_ASPECT: f32 = 2.35;
+
+/// How far above the subject the camera sits, relative to its distance.
+const CAMERA_RISE: f32 = 0.25;
+
+//====================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shot {
+    Caster,
+    Target,
+    Returning,
+}
+
+/// Where a cinematic is up to after a tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CinematicStep {
+    Playing,
+    /// The camera just cut to the target. Happens once, when the action's
+    /// result should be shown.
+    Impact,
+    Finished,
+}
+
+type Pose = (glam::Vec3, glam::Quat);
+
+/// Scripted camera move for an action. The battle waits while it plays, then
+/// the player's camera and view are put back with [`Cinematic::close`].
+///
+/// Timed on game time alone, with the camera placed every tick rather than
+/// tweened, so the impact always lines up with the shot.
+#[derive(Debug)]
+pub struct Cinematic {
+    def: CinematicDef,
+    target: Option<Entity>,
+
+    shot: Shot,
+    elapsed: f32,
+    /// Camera the current shot eases from.
+    from: Pose,
+    /// Camera the current shot ends on.
+    to: Pose,
+
+    player_camera: Pose,
+    virtual_resolution: Option<VirtualResolution>,
+}
+
+impl Cinematic {
+    pub fn new(
+        state: &mut StateInner,
+        def: CinematicDef,
+        caster: Entity,
+        target: Option<Entity>,
+    ) -> Self {
+        let camera = &state.renderer.camera.camera;
+        let player_camera = (camera.translation, camera.rotation);
+
+        // Letterbox bars for the duration
+        let virtual_resolution = state.renderer.virtual_resolution();
+        let width = state.renderer.viewport().virtual_size.width;
+        state
+            .renderer
+            .set_virtual_resolution(Some(VirtualResolution::new(
+                width,
+                width / CINEMATIC_ASPECT,
+                AspectPolicy::Letterbox,
+            )));
+
+        let mut cinematic = Self {
+            def,
+            target,
+            shot: Shot::Caster,
+            elapsed: 0.,
+            from: player_camera,
+            to: player_camera,
+            player_camera,
+            virtual_resolution,
+        };
+
+        if let Some(framed) = cinematic.framing(state, caster) {
+            cinematic.to = framed;
+        }
+
+        cinematic
+    }
+
+    #[inline]
+    fn zoom_time(&self) -> f32 {
+        self.def.zoom_ms as f32 / 1000.
+    }
+
+    #[inline]
+    fn hold_time(&self) -> f32 {
+        self.def.hold_ms as f32 / 1000.
+    }
+
+    pub fn tick(&mut self, state: &mut StateInner) -> CinematicStep {
+        self.elapsed += state.time.delta_seconds();
+
+        let zoom = self.zoom_time();
+        let hold = self.hold_time();
+
+        let step = match self.shot {
+            // Cut to the target on impact. Actions without one stay on the caster.
+            Shot::Caster if self.elapsed >= zoom + hold => {
+                let framed = self
+                    .target
+                    .and_then(|target| self.framing(state, target))
+                    .unwrap_or(self.to);
+
+                self.from = framed;
+                self.to = framed;
+                self.shot = Shot::Target;
+                self.elapsed = 0.;
+                CinematicStep::Impact
+            }
+
+            Shot::Target if self.elapsed >= hold => {
+                self.from = self.to;
+                self.to = self.player_camera;
+                self.shot = Shot::Returning;
+                self.elapsed = 0.;
+                CinematicStep::Playing
+            }
+
+            Shot::Returning if self.elapsed >= zoom => return CinematicStep::Finished,
+
+            _ => CinematicStep::Playing,
+        };
+
+        self.place_camera(state);
+        step
+    }
+
+    /// Put back the player's camera and view, even if cut short.
+    pub fn close(&self, state: &mut StateInner) {
+        let (translation, rotation) = self.player_camera;
+        state.renderer.tween_camera(translation, rotation, None);
+
+        state
+            .renderer
+            .set_virtual_resolution(self.virtual_resolution);
+    }
+
+    // Ease from the start of the shot to its end, holding once there
+    fn place_camera(&self, state: &mut StateInner) {
+        let zoom = self.zoom_time();
+        let t = match zoom > 0. {
+            true => (self.elapsed / zoom).min(1.),
+            false => 1.,
+        };
+        let eased = t * t * (3. - 2. * t);
+
+        let translation = self.from.0.lerp(self.to.0, eased);
+        let rotation = self.from.1.slerp(self.to.1, eased);
+
+        state.renderer.tween_camera(translation, rotation, None);
+    }
+
+    // Camera looking at `subject` from in front along the player's view
+    fn framing(&self, state: &StateInner, subject: Entity) -> Option<Pose> {
+        let position = state.world.get::<&Transform>(subject).ok()?.translation;
+
+        let (_, player_rotation) = self.player_camera;
+        let forward = player_rotation * glam::Vec3::Z;
+
+        let distance = self.def.distance as f32;
+        let translation = position - forward * distance + glam::Vec3::Y * distance * CAMERA_RISE;
+
+        Some((
+            translation,
+            crate::camera::look_rotation(translation, position),
+        ))
+    }
+}
+
+//====================================================================
@@ -0,0 +1,89 @@
+//====================================================================
+
+use common::Transform;
+use engine::{tools::KeyCode, StateInner};
+use hecs::Entity;
+use renderer::pipelines::ui3d_pipeline::Ui3d;
+
+//====================================================================
+
+pub const FAST_FORWARD_KEY: KeyCode = KeyCode::KeyF;
+
+/// Time scale while fast forwarding, capped so actions can still be followed.
+/// The battle only waits on game time and takes one step a frame at most, so
+/// it plays out the same at any speed.
+const FAST_FORWARD_SCALE: f32 = 3.;
+
+const INDICATOR_COLOR: [f32; 4] = [0.15, 0.15, 0.2, 0.75];
+
+//====================================================================
+
+/// Speeds up enemy turns while [`FAST_FORWARD_KEY`] is held, showing an
+/// indicator in the corner of the view.
+#[derive(Default)]
+pub struct FastForward {
+    indicator: Option<Entity>,
+}
+
+impl FastForward {
+    /// Only fast forwards while `allowed`. Leaves the time scale alone while
+    /// `time_taken` as something else, such as the kill cam, is using it.
+    pub fn tick(&mut self, state: &mut StateInner, allowed: bool, time_taken: bool) {
+        if time_taken {
+            self.hide(state);
+            return;
+        }
+
+        match allowed && state.keys.pressed(FAST_FORWARD_KEY) {
+            true => {
+                state.time.set_scale(FAST_FORWARD_SCALE);
+                self.show(state);
+            }
+            false => {
+                if self.indicator.is_some() {
+                    state.time.set_scale(1.);
+                }
+                self.hide(state);
+            }
+        }
+    }
+
+    fn show(&mut self, state: &mut StateInner) {
+        let entity = *self.indicator.get_or_insert_with(|| {
+            state.world.spawn((
+                Transform::from_scale((0.4, 0.4, 0.4)),
+                Ui3d {
+                    menu_color: INDICATOR_COLOR,
+                    selection_color: INDICATOR_COLOR,
+                    options: vec![format!(">> x{}", FAST_FORWARD_SCALE)],
+                    font_size: 24.,
+                    ..Default::default()
+                },
+            ))
+        });
+
+        let camera = &state.renderer.camera.camera;
+        let position =
+            camera.translation + camera.forward() * 8. + camera.right() * 5. + glam::Vec3::Y * 2.6;
+
+        if let Ok(mut transform) = state.world.get::<&mut Transform>(entity) {
+            transform.translation = position;
+        }
+    }
+
+    fn hide(&mut self, state: &mut StateInner) {
+        if let Some(entity) = self.indicator.take() {
+            state.despawn(entity).ok();
+        }
+    }
+
+    /// Put back normal time if still fast forwarding.
+    pub fn close(&mut self, state: &mut StateInner) {
+        if self.indicator.is_some() {
+            state.time.set_scale(1.);
+        }
+        self.hide(state);
+    }
+}
+
+//====================================================================
@@ -0,0 +1,131 @@
+//====================================================================
+
+use std::{cell::Cell, rc::Rc, time::Duration};
+
+use common::Transform;
+use engine::{
+    events::{EventBus, Subscription},
+    StateInner,
+};
+use hecs::Entity;
+
+use super::BattleEvent;
+
+//====================================================================
+
+/// Time scale while the kill cam plays.
+const SLOW_MOTION: f32 = 0.25;
+/// Real seconds the kill cam lasts, not affected by the slow down.
+const KILL_CAM_TIME: f32 = 1.5;
+/// Time taken to push in on the target.
+const PUSH_TIME: Duration = Duration::from_millis(400);
+/// How much of the way to the target the camera moves.
+const PUSH_AMOUNT: f32 = 0.5;
+
+//====================================================================
+
+struct Playing {
+    elapsed: f32,
+    player_camera: (glam::Vec3, glam::Quat),
+}
+
+/// Slows time and pushes the camera in on whoever takes the finishing blow,
+/// from [`BattleEvent::FinishingBlow`].
+pub struct KillCam {
+    pending: Rc<Cell<Option<Entity>>>,
+    playing: Option<Playing>,
+    subscription: Option<Subscription>,
+}
+
+impl KillCam {
+    /// Nothing is shown when `enabled` is false.
+    pub fn new(events: &mut EventBus, enabled: bool) -> Self {
+        let pending = Rc::new(Cell::new(None));
+
+        let subscription = enabled.then(|| {
+            let pending = pending.clone();
+            events.subscribe(move |event: &BattleEvent| Self::on_event(&pending, event))
+        });
+
+        Self {
+            pending,
+            playing: None,
+            subscription,
+        }
+    }
+
+    fn on_event(pending: &Cell<Option<Entity>>, event: &BattleEvent) {
+        if let BattleEvent::FinishingBlow { character } = event {
+            pending.set(Some(*character));
+        }
+    }
+
+    /// Stop watching for finishing blows, once the battle is over.
+    pub fn unsubscribe(&mut self, events: &mut EventBus) {
+        if let Some(subscription) = self.subscription.take() {
+            events.unsubscribe(subscription);
+        }
+    }
+
+    /// True if the kill cam is playing or about to.
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.playing.is_some() || self.pending.get().is_some()
+    }
+
+    /// Returns true while the kill cam is still playing.
+    pub fn tick(&mut self, state: &mut StateInner) -> bool {
+        if let Some(target) = self.pending.take() {
+            self.start(state, target);
+        }
+
+        let playing = match &mut self.playing {
+            Some(playing) => playing,
+            None => return false,
+        };
+
+        playing.elapsed += state.time.unscaled_delta_seconds();
+        if playing.elapsed < KILL_CAM_TIME {
+            return true;
+        }
+
+        self.close(state);
+        false
+    }
+
+    fn start(&mut self, state: &mut StateInner, target: Entity) {
+        let position = match state.world.get::<&Transform>(target) {
+            Ok(transform) => transform.translation,
+            Err(_) => return,
+        };
+
+        let camera = &state.renderer.camera.camera;
+        let player_camera = (camera.translation, camera.rotation);
+
+        let translation = camera.translation.lerp(position, PUSH_AMOUNT);
+        let rotation = crate::camera::look_rotation(camera.translation, position);
+
+        state
+            .renderer
+            .tween_camera(translation, rotation, Some(PUSH_TIME));
+        state.time.set_scale(SLOW_MOTION);
+
+        self.playing = Some(Playing {
+            elapsed: 0.,
+            player_camera,
+        });
+    }
+
+    /// Put back normal time and the player's camera, even if cut short.
+    pub fn close(&mut self, state: &mut StateInner) {
+        self.pending.set(None);
+
+        if let Some(playing) = self.playing.take() {
+            let (translation, rotation) = playing.player_camera;
+            state.renderer.tween_camera(translation, rotation, None);
+            state.time.set_scale(1.);
+        }
+    }
+}
+
+//====================================================================
@@ -1,25 +1,111 @@
 //====================================================================
 
-use std::collections::{HashSet, VecDeque};
+use std::{collections::HashSet, time::Duration};
 
-use common::{Size, Transform};
-use engine::{scene::Scene, StateInner};
+use auto_battle::{AutoBattle, AUTO_BATTLE_KEY, AUTO_BATTLE_PROFILE};
+use cinematic::{Cinematic, CinematicStep};
+use common::{PhysicalSize, Transform};
+use engine::{lifetime::DespawnAfter, prefab::Prefabs, scene::Scene, tools::KeyCode, StateInner};
+use fast_forward::FastForward;
 use hecs::{Entity, World};
-use rand::Rng;
-use ui::{UiMenuOutput, UiMenus};
+use kill_cam::KillCam;
+use renderer::{
+    animation, fade,
+    pipelines::{
+        particle_pipeline::ParticleEmitter,
+        texture_pipeline::{Sprite, SpriteStack},
+    },
+};
+use results::BattleResults;
+use tooltip::HoverTooltip;
+use turn_strip::TurnStrip;
+use ui::{UiFocus, UiMenuOutput, UiMenus};
 
-use crate::characters::{self, Character, CharacterManager};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::data::DataWatcher;
+use crate::{
+    audio::AudioEventRouter,
+    banners::Banners,
+    battle::{Acted, Battle, Step},
+    camera::{CameraBounds, CameraController},
+    characters::{
+        self,
+        actions::{Action, ActionId, ActionResolution, TargetType},
+        Character, CharacterManager,
+    },
+    combat::{self, CpuProfile, TurnStartTick},
+    controls::{self, HelpOverlay},
+    data::{ArenaDef, GameData},
+    encounters::{self, Encounter},
+    floating_text::{FloatingStyle, FloatingTexts},
+    hints::{Hint, Hints},
+    locale::Locale,
+    music::{self, BattleMusic},
+    party::Party,
+    settings::{DifficultySettings, Settings},
+    stats::{self, AchievementsScreen, Stats},
+    telemetry::{self, ActionRecord, BattleRecord, Outcome},
+};
 
 use self::characters::actions::ActionRepo;
 
+use super::overworld_scene::OverworldScene;
+
+pub(crate) mod auto_battle;
+mod cinematic;
+pub(crate) mod fast_forward;
+mod kill_cam;
+mod results;
 mod server;
-mod ui;
+mod tooltip;
+mod turn_strip;
+pub(crate) mod ui;
 
 //====================================================================
 
+/// Leave the battle and return to the overworld.
+pub const RETREAT_KEY: KeyCode = KeyCode::Backspace;
+
+/// Pause after a cpu character acts so the battle can be followed.
+const CPU_TURN_TIME: Duration = Duration::from_secs(1);
+
+/// Seconds taken for defeated characters to fade back.
+const DEFEAT_FADE_TIME: f32 = 0.8;
+/// Defeated characters stay on the field, faded back to this opacity.
+const DEFEATED_OPACITY: f32 = 0.5;
+
+/// World space kept around characters when the camera frames them.
+const FRAME_PADDING: f32 = 60.;
+/// Time taken for the camera to move when framing characters.
+const FRAME_TIME: Duration = Duration::from_millis(600);
+
+const MISS_STYLE: FloatingStyle = FloatingStyle {
+    color: [0.35, 0.35, 0.4, 0.9],
+    scale: 0.5,
+};
+const DAMAGE_STYLE: FloatingStyle = FloatingStyle {
+    color: [0.6, 0.15, 0.15, 0.9],
+    scale: 0.5,
+};
+const CRIT_STYLE: FloatingStyle = FloatingStyle {
+    color: [0.9, 0.6, 0.1, 0.95],
+    scale: 0.8,
+};
+
+/// Screen flash for critical hits.
+const CRIT_FLASH: [f32; 4] = [1., 0.95, 0.8, 0.4];
+const CRIT_FLASH_TIME: Duration = Duration::from_millis(200);
+/// Sparks thrown off a critically hit character.
+const CRIT_SPARKS: u32 = 600;
+/// sRGB with linear alpha.
+const CRIT_SPARK_COLOR: [f32; 4] = [1., 0.7, 0.25, 0.9];
+
 pub struct Characters {
     friendly: HashSet<Entity>,
     enemy: HashSet<Entity>,
+    /// Every character in battle order, friendly first. Indices into this
+    /// identify characters over the network.
+    roster: Vec<Entity>,
 }
 
 impl Characters {
@@ -32,80 +118,467 @@ impl Characters {
     pub fn enemy(&self) -> &HashSet<Entity> {
         &self.enemy
     }
+
+    #[inline]
+    pub fn index_of(&self, character: Entity) -> Option<u32> {
+        self.roster
+            .iter()
+            .position(|id| *id == character)
+            .map(|index| index as u32)
+    }
+
+    #[inline]
+    pub fn from_index(&self, index: u32) -> Option<Entity> {
+        self.roster.get(index as usize).copied()
+    }
+
+    /// Middle of every character in the battle, for the camera to orbit.
+    pub fn center(&self, world: &World) -> glam::Vec3 {
+        let positions = self
+            .roster
+            .iter()
+            .filter_map(|id| world.get::<&Transform>(*id).ok())
+            .map(|transform| transform.translation)
+            .collect::<Vec<_>>();
+
+        match positions.is_empty() {
+            true => glam::Vec3::ZERO,
+            false => positions.iter().sum::<glam::Vec3>() / positions.len() as f32,
+        }
+    }
+
+    /// Characters still in the fight that `caster` could use an action on,
+    /// in battle order, following [`combat::legal_targets`] as the battle
+    /// does when an action is used.
+    pub fn targets(&self, world: &World, caster: Entity, target: TargetType) -> Vec<Entity> {
+        let characters = match self
+            .roster
+            .iter()
+            .map(|id| world.get::<&Character>(*id).ok())
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(characters) => characters,
+            None => return Vec::new(),
+        };
+
+        let roster = self
+            .roster
+            .iter()
+            .zip(&characters)
+            .map(|(id, character)| (self.friendly.contains(id), &**character))
+            .collect::<Vec<_>>();
+
+        let caster = match self.index_of(caster) {
+            Some(caster) => caster as usize,
+            None => return Vec::new(),
+        };
+
+        combat::legal_targets(&roster, caster, target)
+            .into_iter()
+            .map(|index| self.roster[index])
+            .collect()
+    }
+
+    /// Percent chance for `caster`'s attacks to hit `target`.
+    pub fn hit_chance(world: &World, caster: Entity, target: Entity) -> u32 {
+        let accuracy = world
+            .get::<&Character>(caster)
+            .map_or(characters::DEFAULT_ACCURACY, |character| {
+                character.stats.accuracy
+            });
+        let evasion = world
+            .get::<&Character>(target)
+            .map_or(0, |character| character.stats.evasion);
+
+        combat::hit_chance(accuracy, evasion)
+    }
+
+    /// Speed of every character still in the fight, in battle order.
+    pub fn speeds(&self, world: &World) -> Vec<(u32, Entity)> {
+        self.roster
+            .iter()
+            .filter_map(|id| {
+                world
+                    .get::<&Character>(*id)
+                    .ok()
+                    .filter(|character| !character.stats.is_defeated())
+                    .map(|character| (character.speed(), *id))
+            })
+            .collect()
+    }
+
+    // Health left across a side as a fraction of its max health
+    fn health_fraction(world: &World, ids: &HashSet<Entity>) -> f32 {
+        let (health, max_health) = ids
+            .iter()
+            .filter_map(|id| world.get::<&Character>(*id).ok())
+            .fold((0, 0), |(health, max_health), character| {
+                (
+                    health + character.stats.health as u64,
+                    max_health + character.stats.max_health as u64,
+                )
+            });
+
+        match max_health {
+            0 => 0.,
+            _ => health as f32 / max_health as f32,
+        }
+    }
+
+    fn all_defeated(world: &World, ids: &HashSet<Entity>) -> bool {
+        ids.iter().all(|id| {
+            world
+                .get::<&Character>(*id)
+                .map_or(true, |character| character.stats.is_defeated())
+        })
+    }
 }
 
 pub struct BattleScene {
-    _character_manager: CharacterManager,
+    character_manager: CharacterManager,
     action_repo: ActionRepo,
+    /// Translates action names in the menus.
+    locale: Locale,
 
     battle_state: BattleState,
     characters: Characters,
 
+    /// The rules of the battle. Characters in the world are kept in step
+    /// with it after everything it resolves.
+    battle: Battle,
     current_character: Entity,
-    turn_order: VecDeque<Entity>,
+
+    difficulty: DifficultySettings,
+    /// Game time when the current turn runs out.
+    turn_deadline: Option<Duration>,
+
+    /// Lines from boss phases, shown once the action that reached them has
+    /// been.
+    cutscene_lines: Vec<String>,
+    /// Panel shown while the battle is paused, and when it resumes.
+    cutscene: Option<(Entity, Duration)>,
+    /// Camera move for the last action. The battle waits for it to finish.
+    cinematic: Option<Cinematic>,
+    /// Action the cinematic is playing, shown on its impact.
+    impact: Option<ShownAction>,
+    kill_cam: KillCam,
+    fast_forward: FastForward,
+    auto_battle: AutoBattle,
+    camera: CameraController,
+    help: HelpOverlay,
+
+    stats: Stats,
+    achievements_screen: AchievementsScreen,
+    hints: Hints,
+    banners: Banners,
+    music: BattleMusic,
+    audio: AudioEventRouter,
+    floating_text: FloatingTexts,
+    turn_strip: TurnStrip,
+    tooltip: HoverTooltip,
+
+    data: GameData,
+    /// Enemies come from the data files when there isn't one.
+    encounter: Option<Encounter>,
+    /// Written out when the battle ends, if enabled in the settings.
+    telemetry: Option<BattleRecord>,
+    #[cfg(not(target_arch = "wasm32"))]
+    data_watcher: DataWatcher,
 }
 
 impl Scene for BattleScene {
     fn new(state: &mut StateInner) -> Self {
-        crate::scenery::spawn_scenery(state);
+        crate::scenery::spawn_scenery(state, crate::scenery::Ground::Plain);
+
+        let data = GameData::load_or_builtin();
+
+        // Read each battle so setting changes apply from the next one
+        let settings = Settings::load();
+        let difficulty = settings.difficulty;
+        log::info!(
+            "Difficulty: {:?}, turn timer {}",
+            difficulty.level,
+            match difficulty.turn_timer() {
+                true => "on",
+                false => "off",
+            }
+        );
 
         let mut character_manager = CharacterManager::new(state);
-        let action_repo = ActionRepo::new();
+        character_manager.load_textures(state, &data.textures);
+        let action_repo = ActionRepo::from_actions(data.actions.clone());
         // let mut battle_manager = BattleManager::default();
 
-        let idle_action = action_repo.find_action_name("Idle").unwrap();
+        let encounter = encounters::take_queued(&mut state.resources);
+
+        // Battles against the default enemies have no encounter to seed from
+        let seed = encounter
+            .as_ref()
+            .map_or_else(rand::random, |encounter| encounter.seed);
+        log::info!("Battle seed: {}", seed);
+
+        let party = state.resources.get_or_default::<Party>().clone();
+
+        let music = encounter
+            .as_ref()
+            .and_then(|encounter| encounter.arena.music.as_deref())
+            .unwrap_or(music::DEFAULT_MUSIC);
+        state.audio.set_music(music::load_music(&data, music));
+
+        let battle = encounters::start_battle(
+            &data,
+            &action_repo,
+            &party,
+            encounter.as_ref(),
+            difficulty.level,
+            seed,
+        );
 
-        let friendly_characters = vec![character_manager.spawn(
-            &mut state.world,
-            "Friendly Character",
-            vec![idle_action],
-        )];
+        let spawned = battle
+            .characters()
+            .map(|(friendly, character)| {
+                let id = character_manager.spawn_character(state, character.clone());
+                (friendly, id)
+            })
+            .collect::<Vec<_>>();
 
-        let enemy_characters =
-            vec![character_manager.spawn(&mut state.world, "Enemy Character", vec![idle_action])];
+        let side = |side: bool| {
+            spawned
+                .iter()
+                .filter(move |(friendly, _)| *friendly == side)
+                .map(|(_, id)| *id)
+        };
 
         Self {
-            _character_manager: character_manager,
+            character_manager,
             action_repo,
+            locale: Locale::new(&data, &settings.language),
             battle_state: BattleState::Initializing,
             characters: Characters {
-                friendly: HashSet::from_iter(friendly_characters),
-                enemy: HashSet::from_iter(enemy_characters),
+                roster: spawned.iter().map(|(_, id)| *id).collect(),
+                friendly: side(true).collect(),
+                enemy: side(false).collect(),
             },
+            battle,
             current_character: Entity::DANGLING,
-            turn_order: VecDeque::default(),
+
+            difficulty,
+            turn_deadline: None,
+
+            cutscene_lines: Vec::new(),
+            cutscene: None,
+            cinematic: None,
+            impact: None,
+            kill_cam: KillCam::new(&mut state.events, settings.interface.kill_cam),
+            fast_forward: FastForward::default(),
+            auto_battle: AutoBattle::default(),
+            camera: CameraController::default()
+                .with_bounds(camera_bounds(encounter.as_ref()))
+                .with_edge_scroll(settings.interface.edge_scroll)
+                .with_mouse_sensitivity(settings.interface.mouse_sensitivity),
+            help: HelpOverlay::default(),
+
+            stats: Stats::new(data.achievements.clone()),
+            achievements_screen: AchievementsScreen::default(),
+            hints: Hints::new(settings.hints.clone()),
+            banners: Banners::new(&mut state.events, settings.interface.banners),
+            music: BattleMusic::new(&mut state.events),
+            audio: AudioEventRouter::new(&mut state.events, &data, settings.haptics),
+            floating_text: FloatingTexts::default(),
+            turn_strip: TurnStrip::new(state),
+            tooltip: HoverTooltip::default(),
+
+            data,
+            telemetry: settings
+                .telemetry
+                .enabled
+                .then(|| BattleRecord::new(encounter.as_ref(), difficulty.level, false)),
+            encounter,
+            #[cfg(not(target_arch = "wasm32"))]
+            data_watcher: DataWatcher::configured(),
         }
     }
 
-    fn resize(&mut self, state: &mut StateInner, new_size: Size<u32>) {
-        state
-            .renderer
-            .camera
-            .set_aspect(new_size.width as f32, new_size.height as f32);
+    // Camera aspect is kept in sync with the viewport by the renderer
+    fn resize(&mut self, _state: &mut StateInner, _new_size: PhysicalSize<u32>) {}
+
+    fn name(&self) -> &'static str {
+        "Battle"
+    }
+
+    fn on_exit(&mut self, state: &mut StateInner) {
+        self.stats.save();
+        self.character_manager.despawn_all(state);
+
+        self.banners.unsubscribe(&mut state.events);
+        self.kill_cam.unsubscribe(&mut state.events);
+        self.music.unsubscribe(&mut state.events);
+        state.audio.set_music(None);
+        self.audio.play(&state.audio, &mut state.haptics);
+        self.audio.unsubscribe(&mut state.events);
+
+        self.hints.close(state);
+        self.banners.close(state);
+        self.floating_text.close(state);
+        self.tooltip.close(state);
+        self.turn_strip.close(state);
+        self.achievements_screen.close(state);
+        if let Some((entity, _)) = self.cutscene.take() {
+            state.despawn(entity).ok();
+        }
+        if let Some(cinematic) = self.cinematic.take() {
+            cinematic.close(state);
+        }
+        self.kill_cam.close(state);
+        self.fast_forward.close(state);
+        self.auto_battle.close(state);
+        self.help.close(state);
+        crate::scenery::despawn_scenery(state);
+    }
+
+    fn quit(&mut self, _state: &mut StateInner) {
+        self.stats.save();
+    }
+
+    // Tooltips would otherwise sit over the modal until it closes
+    fn on_pause(&mut self, state: &mut StateInner) {
+        self.tooltip.close(state);
     }
 
     fn update(&mut self, state: &mut StateInner) {
-        crate::camera::move_camera(state);
+        if !self.showing_action() {
+            let focus = self.characters.center(&state.world);
+            self.camera.tick(state, focus);
+        }
+        crate::scenery::tick_ground(state);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        self.reload_data(&mut state.world);
+
+        self.character_manager.retain_alive(&state.world);
+
+        if state.keys.just_pressed(RETREAT_KEY) {
+            self.leave(state);
+            return;
+        }
+
+        if state.keys.just_pressed(AUTO_BATTLE_KEY) {
+            self.toggle_auto_battle(state);
+        }
 
         self.tick_battle(state);
 
+        self.music.tick(
+            Characters::health_fraction(&state.world, &self.characters.friendly),
+            Characters::health_fraction(&state.world, &self.characters.enemy),
+        );
+        state.audio.set_music_mix(self.music.layer_mix());
+
+        self.audio.play(&state.audio, &mut state.haptics);
+
+        if let BattleState::Finished = self.battle_state {
+            return;
+        }
+
+        // Only enemy turns can be sped through
+        let enemy_turn = matches!(self.battle_state, BattleState::ProcessingCpu);
+        self.fast_forward
+            .tick(state, enemy_turn, self.kill_cam.is_active());
+
+        self.auto_battle.tick(state);
+        self.tick_help(state);
+        self.achievements_screen.tick(state, &self.stats);
+        self.hints.tick(state);
+        self.banners.tick(state);
+        self.floating_text.tick(state);
+        self.tooltip.tick(state);
+        self.update_turn_strip(state);
+
         characters::update_characters(state);
     }
+
+    fn update_modal(&mut self, state: &mut StateInner) {
+        self.achievements_screen.tick(state, &self.stats);
+    }
 }
 
 //====================================================================
 
+/// Sent through the engine's event bus as the battle progresses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BattleEvent {
+    RoundStarted {
+        round: u32,
+    },
+    TurnStarted {
+        round: u32,
+        character: String,
+    },
+    /// The last character standing on a side was defeated.
+    FinishingBlow {
+        character: Entity,
+    },
+    /// Something was resolved at the start of a character's turn, in
+    /// resolution order.
+    TurnStartTick {
+        character: Entity,
+        tick: TurnStartTick,
+    },
+    /// An attack failed its hit roll.
+    Missed {
+        caster: Entity,
+        target: Entity,
+    },
+    /// An attack landed, critical or not.
+    Hit {
+        caster: Entity,
+        target: Entity,
+        amount: u32,
+    },
+    Healed {
+        caster: Entity,
+        target: Entity,
+        amount: u32,
+    },
+    /// An attack landed a critical hit.
+    CriticalHit {
+        caster: Entity,
+        target: Entity,
+        amount: u32,
+    },
+    /// A boss reached the health threshold of one of its phases.
+    BossPhase {
+        boss: Entity,
+    },
+    /// One side has nobody left standing.
+    Ended {
+        won: bool,
+    },
+}
+
+/// An action the battle resolved, waiting to be shown.
+struct ShownAction {
+    caster: Entity,
+    action: Action,
+    target: Option<Entity>,
+    /// Chance the action had to hit, for the log.
+    chance: u32,
+    acted: Acted,
+}
+
 #[derive(Debug, Default)]
 enum BattleState {
     #[default]
     Initializing,
-    StartingRound,
     StartingTurn,
     WaitingForInput(UiMenus),
     ProcessingCpu,
+    Results(BattleResults),
+    Finished,
 }
 
 impl BattleScene {
+    #[tracing::instrument(skip_all, name = "battle_initialize")]
     fn position_characters(&self, world: &mut World) {
         self.characters
             .friendly
@@ -127,115 +600,948 @@ impl BattleScene {
 
                 transform.translation = glam::vec3(index as f32 * 100., 0., 100.);
                 transform.rotation = glam::Quat::from_rotation_y(0.);
+
+                // Enemies face the other way to the party
+                if let Ok(mut sprite) = world.get::<&mut Sprite>(*id) {
+                    sprite.flip_x = true;
+                }
+            });
+    }
+
+    // The world's characters follow the battle, which owns the rules
+    fn sync_characters(&self, world: &mut World) {
+        self.characters
+            .roster
+            .iter()
+            .zip(self.battle.characters())
+            .for_each(|(id, (_, battle))| {
+                if let Ok(mut character) = world.get::<&mut Character>(*id) {
+                    let front_facing = character.front_facing;
+                    *character = battle.clone();
+                    character.front_facing = front_facing;
+                }
             });
     }
 
     fn tick_battle(&mut self, state: &mut StateInner) {
+        if self.tick_cutscene(state) || self.tick_cinematic(state) || self.tick_kill_cam(state) {
+            return;
+        }
+
         match &mut self.battle_state {
             BattleState::Initializing => {
                 self.position_characters(&mut state.world);
+                frame_characters(state, &self.characters.roster, &self.camera);
 
-                self.battle_state = BattleState::StartingRound;
-            }
-
-            BattleState::StartingRound => {
-                self.start_round(&state.world);
                 self.battle_state = BattleState::StartingTurn;
             }
 
             BattleState::StartingTurn => self.start_turn(state),
 
             BattleState::WaitingForInput(ui_menus) => {
-                match ui_menus.tick(state, &self.action_repo, &self.characters) {
-                    UiMenuOutput::None => {}
-                    UiMenuOutput::SkipTurn => {
-                        // next_turn = true;
-                        ui_menus.drop_menus(&mut state.world);
+                if self
+                    .turn_deadline
+                    .is_some_and(|deadline| battle_time(state) >= deadline)
+                {
+                    log::info!("Turn timed out");
+                    ui_menus.drop_menus(state);
+
+                    self.start_turn(state);
+                    return;
+                }
+
+                match ui_menus.tick(
+                    state,
+                    &self.action_repo,
+                    &self.locale,
+                    &self.characters,
+                    &self.camera,
+                ) {
+                    UiMenuOutput::None => {
+                        if ui_menus.focus(state) == Some(UiFocus::Targets) {
+                            self.hints.trigger(Hint::TargetMenu);
+                        }
+                    }
+                    UiMenuOutput::Act { action, target } => {
+                        ui_menus.drop_menus(state);
 
-                        self.start_turn(state);
+                        self.resolve_action(state, self.current_character, action, target);
+                        if !self.showing_action() {
+                            self.finish_action(state);
+                        }
                     }
                 }
             }
 
-            BattleState::ProcessingCpu => {}
+            BattleState::ProcessingCpu => {
+                if self
+                    .turn_deadline
+                    .is_none_or(|deadline| battle_time(state) >= deadline)
+                {
+                    self.start_turn(state);
+                }
+            }
+
+            BattleState::Results(results) => {
+                if results.tick(state) {
+                    self.leave(state);
+                }
+            }
+
+            BattleState::Finished => {}
         }
     }
 
-    fn start_round(&mut self, world: &World) {
-        log::info!("------Starting new round------");
-        self.turn_order.clear();
+    #[tracing::instrument(skip_all, name = "battle_start_round")]
+    fn start_round(&mut self, state: &mut StateInner, round: u32) {
+        log::info!("------Starting round {}------", round);
 
-        let mut weight = 0;
-        let mut character_weights = Vec::new();
+        log::debug!("Character weightings = {:?}", self.battle.speeds());
+        log::debug!(
+            "Turn order = {:?}",
+            self.battle
+                .turn_order()
+                .filter_map(|index| self.battle.character(index))
+                .map(|character| character.name.as_str())
+                .collect::<Vec<_>>()
+        );
 
-        self.characters
-            .friendly
-            .iter()
-            .chain(self.characters.enemy.iter())
-            .for_each(|id| {
-                let character = world.get::<&Character>(*id).unwrap();
+        state.events.emit(BattleEvent::RoundStarted { round });
+        self.hints.trigger(Hint::TurnOrder);
+
+        self.stats
+            .add(&mut state.events, stats::stat::ROUNDS_PLAYED, 1);
+    }
+
+    #[tracing::instrument(skip_all, name = "battle_start_turn")]
+    fn start_turn(&mut self, state: &mut StateInner) {
+        let (index, ticks, phases) = loop {
+            match self.battle.advance() {
+                Step::RoundStarted { round } => self.start_round(state, round),
+                Step::TurnStarted {
+                    character,
+                    ticks,
+                    phases,
+                } => break (character, ticks, phases),
+                Step::Ended { .. } => {
+                    self.finish_if_over(state);
+                    return;
+                }
+            }
+        };
+
+        self.sync_characters(&mut state.world);
+
+        let next_character = self.characters.roster[index];
+        self.current_character = next_character;
+
+        if let Some(record) = &mut self.telemetry {
+            record.turns += 1;
+        }
 
-                weight += character.stats.speed;
-                character_weights.push((character.stats.speed, *id));
+        if let Ok(character) = state.world.get::<&Character>(next_character) {
+            state.events.emit(BattleEvent::TurnStarted {
+                round: self.battle.round(),
+                character: character.name.clone(),
             });
+        }
 
-        log::debug!(
-            "Total weight = {}, Character Weightings = {:?}",
-            weight,
-            character_weights
-        );
+        self.stats
+            .add(&mut state.events, stats::stat::TURNS_TAKEN, 1);
+
+        self.turn_deadline = None;
+        self.show_phases(state, phases);
+
+        // Characters defeated by their effects lose their turn
+        if self.show_turn_start(state, next_character, ticks) {
+            self.battle_state = BattleState::ProcessingCpu;
+            if !self.showing_action() {
+                self.finish_action(state);
+            }
+            return;
+        }
+        self.play_phase_cutscene(state);
+
+        let player_controlled = state
+            .world
+            .get::<&Character>(next_character)
+            .is_ok_and(|character| character.player_controlled);
+
+        match (player_controlled, self.auto_battle.is_on()) {
+            (true, false) => {
+                let menu =
+                    UiMenus::new(state, &self.action_repo, &self.locale, next_character).unwrap();
+                self.battle_state = BattleState::WaitingForInput(menu);
+                self.hints.trigger(Hint::ActionMenu);
 
-        let mut rng = rand::thread_rng();
+                if self.difficulty.turn_timer() {
+                    self.turn_deadline = Some(battle_time(state) + combat::TURN_TIME);
+                }
+            }
+            (true, true) => {
+                self.process_cpu_turn(state, next_character, AUTO_BATTLE_PROFILE);
+            }
+            (false, _) => {
+                let profile = self.difficulty.cpu_profile();
+                self.process_cpu_turn(state, next_character, profile);
+            }
+        }
 
-        while !character_weights.is_empty() {
-            if character_weights.len() == 1 {
-                self.turn_order.push_back(character_weights[0].1);
-                break;
+        self.record_snapshot(&state.world);
+    }
+
+    // Cpu characters, and the player's while auto battling, act straight away
+    // then leave a pause before the next turn
+    fn process_cpu_turn(&mut self, state: &mut StateInner, id: Entity, profile: CpuProfile) {
+        self.take_cpu_turn(state, id, profile);
+        self.battle_state = BattleState::ProcessingCpu;
+
+        // Otherwise picked up once the action has been shown
+        if !self.showing_action() {
+            if self.finish_if_over(state) {
+                return;
             }
+            self.play_phase_cutscene(state);
+
+            self.turn_deadline = Some(battle_time(state) + CPU_TURN_TIME);
+        }
+    }
 
-            let roll = rng.gen_range(0..weight);
-            let mut acc = 0;
+    // Switching on mid turn hands over the turn the player is on
+    fn toggle_auto_battle(&mut self, state: &mut StateInner) {
+        self.auto_battle.toggle(state);
 
-            let character = character_weights
+        if !self.auto_battle.is_on() {
+            return;
+        }
+
+        if let BattleState::WaitingForInput(ui_menus) = &self.battle_state {
+            ui_menus.drop_menus(state);
+            self.turn_deadline = None;
+            self.process_cpu_turn(state, self.current_character, AUTO_BATTLE_PROFILE);
+        }
+    }
+
+    // Menu controls only show while a menu has focus, and camera controls
+    // while the camera is free
+    fn tick_help(&mut self, state: &mut StateInner) {
+        let menu_focused = match &self.battle_state {
+            BattleState::WaitingForInput(ui_menus) => ui_menus.focus(state).is_some(),
+            _ => false,
+        };
+
+        let contexts = [
+            (menu_focused, controls::Context::BattleMenu),
+            (true, controls::Context::Battle),
+            (!self.showing_action(), controls::Context::Camera),
+            (true, controls::Context::Debug),
+        ]
+        .into_iter()
+        .filter_map(|(shown, context)| shown.then_some(context))
+        .collect::<Vec<_>>();
+
+        self.help.tick(state, &contexts);
+    }
+
+    // Returns true while the battle should wait on a cinematic. Finishes off
+    // the action that started it once done.
+    fn tick_cinematic(&mut self, state: &mut StateInner) -> bool {
+        let cinematic = match &mut self.cinematic {
+            Some(cinematic) => cinematic,
+            None => return false,
+        };
+
+        match cinematic.tick(state) {
+            CinematicStep::Playing => return true,
+            CinematicStep::Impact => {
+                if let Some(shown) = self.impact.take() {
+                    self.show_action(state, shown);
+                }
+                return true;
+            }
+            CinematicStep::Finished => {}
+        }
+
+        cinematic.close(state);
+        self.cinematic = None;
+
+        // The kill cam follows on if the action ended the battle
+        if !self.kill_cam.is_active() {
+            self.finish_action(state);
+        }
+        true
+    }
+
+    // Returns true while the battle should wait on the kill cam
+    fn tick_kill_cam(&mut self, state: &mut StateInner) -> bool {
+        if !self.kill_cam.is_active() {
+            return false;
+        }
+
+        if !self.kill_cam.tick(state) {
+            self.finish_action(state);
+        }
+        true
+    }
+
+    /// Whether the last action is still being shown, holding up the battle.
+    #[inline]
+    fn showing_action(&self) -> bool {
+        self.cinematic.is_some() || self.kill_cam.is_active()
+    }
+
+    // Carry on once an action has been resolved and shown
+    fn finish_action(&mut self, state: &mut StateInner) {
+        if self.finish_if_over(state) {
+            return;
+        }
+        self.play_phase_cutscene(state);
+
+        self.start_turn(state);
+    }
+
+    // The rest of this round followed by a projection of the next, so speed
+    // changes show up straight away
+    fn update_turn_strip(&mut self, state: &mut StateInner) {
+        let text = {
+            let name = |id: &Entity| {
+                state
+                    .world
+                    .get::<&Character>(*id)
+                    .ok()
+                    .filter(|character| !character.stats.is_defeated())
+                    .map(|character| character.name.clone())
+            };
+
+            let current = std::iter::once(self.current_character)
+                .chain(
+                    self.battle
+                        .turn_order()
+                        .map(|index| self.characters.roster[index]),
+                )
+                .filter_map(|id| name(&id))
+                .collect::<Vec<_>>();
+
+            let next = combat::predict_turn_order(&self.characters.speeds(&state.world))
                 .iter()
-                .enumerate()
-                .find(|(_, (weight, _))| match (acc + weight) > roll {
-                    true => true,
-                    false => {
-                        acc += weight;
-                        false
+                .filter_map(name)
+                .collect::<Vec<_>>();
+
+            format!(
+                "Turn order: {}  |  Next round: {}",
+                current.join(" > "),
+                next.join(" > ")
+            )
+        };
+
+        self.turn_strip.show(state, text);
+        self.turn_strip.tick(state);
+
+        // Out of the way of the camera while an action plays out
+        self.turn_strip.set_hidden(state, self.showing_action());
+    }
+
+    fn leave(&mut self, state: &mut StateInner) {
+        log::info!("Leaving battle");
+
+        match &self.battle_state {
+            BattleState::WaitingForInput(ui_menus) => ui_menus.drop_menus(state),
+            BattleState::Results(results) => results.close(state),
+            _ => {}
+        }
+
+        // Records can't be written on the web
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(mut record) = self.telemetry.take() {
+            record.rounds = self.battle.round();
+
+            match telemetry::write_record(&record) {
+                Ok(path) => log::info!("Wrote battle record to '{}'", path.display()),
+                Err(e) => log::error!("Unable to write battle record: {}", e),
+            }
+        }
+
+        self.battle_state = BattleState::Finished;
+        crate::saves::request_autosave(state);
+        state.switch_scene_with_loading::<OverworldScene>();
+    }
+
+    // Ends the battle once either side has nobody left standing
+    fn finish_if_over(&mut self, state: &mut StateInner) -> bool {
+        let won = Characters::all_defeated(&state.world, &self.characters.enemy);
+        let lost = Characters::all_defeated(&state.world, &self.characters.friendly);
+
+        if won || lost {
+            state.events.emit(BattleEvent::Ended { won });
+        }
+
+        match (won, lost) {
+            (true, _) => {
+                log::info!("------Victory------");
+                if let Some(record) = &mut self.telemetry {
+                    record.outcome = Outcome::Victory;
+                }
+
+                self.stats
+                    .add(&mut state.events, stats::stat::BATTLES_WON, 1);
+
+                let flawless = self.characters.friendly.iter().all(|id| {
+                    state
+                        .world
+                        .get::<&Character>(*id)
+                        .is_ok_and(|character| character.stats.health == character.stats.max_health)
+                });
+
+                if flawless {
+                    self.stats
+                        .add(&mut state.events, stats::stat::FLAWLESS_VICTORIES, 1);
+                }
+
+                self.show_results(state);
+                return true;
+            }
+            (false, true) => {
+                log::info!("------Defeat------");
+                if let Some(record) = &mut self.telemetry {
+                    record.outcome = Outcome::Defeat;
+                }
+            }
+            (false, false) => return false,
+        }
+
+        self.leave(state);
+        true
+    }
+
+    fn show_results(&mut self, state: &mut StateInner) {
+        let level = self
+            .encounter
+            .as_ref()
+            .map_or(1, |encounter| encounter.level as u64);
+
+        let experience_gained =
+            self.characters.enemy.len() as u64 * results::EXPERIENCE_PER_ENEMY * level;
+
+        let names = self
+            .characters
+            .roster
+            .iter()
+            .filter(|id| self.characters.friendly.contains(*id))
+            .filter_map(|id| {
+                state
+                    .world
+                    .get::<&Character>(*id)
+                    .ok()
+                    .map(|character| character.name.clone())
+            })
+            .collect::<Vec<_>>();
+
+        // Enemies drop whatever they had equipped
+        let loot = self
+            .characters
+            .roster
+            .iter()
+            .filter(|id| self.characters.enemy.contains(*id))
+            .filter_map(|id| state.world.get::<&Character>(*id).ok())
+            .flat_map(|character| {
+                [
+                    character.equipment.armor.clone(),
+                    character.equipment.weapon.clone(),
+                ]
+            })
+            .flatten()
+            .collect::<Vec<_>>();
+
+        log::info!("Gained {} experience, loot: {:?}", experience_gained, loot);
+
+        let party = state.resources.get_or_default::<Party>();
+        let gains = party.award_experience(names.iter().map(String::as_str), experience_gained);
+        party.grant_loot(loot.iter().cloned());
+
+        gains.iter().for_each(|gain| {
+            if let Some(level) = gain.level_up() {
+                log::info!("{} reached level {}", gain.name, level);
+            }
+        });
+
+        self.auto_battle.close(state);
+        self.battle_state = BattleState::Results(BattleResults::new(state, gains, loot));
+    }
+
+    fn resolve_action(
+        &mut self,
+        state: &mut StateInner,
+        caster: Entity,
+        action: ActionId,
+        target: Option<Entity>,
+    ) {
+        let id = action;
+        // Owned so the scene can be borrowed while it's shown
+        let action = match self.action_repo.get_action(&id) {
+            Some(action) => action.clone(),
+            None => return,
+        };
+
+        let caster_index = match self.characters.index_of(caster) {
+            Some(index) => index as usize,
+            None => return,
+        };
+        let target_index = match target.map(|target| self.characters.index_of(target)) {
+            Some(None) => return,
+            index => index.flatten().map(|index| index as usize),
+        };
+
+        // Taken before the action changes anything, for the log
+        let chance = target.map_or(100, |target| {
+            Characters::hit_chance(&state.world, caster, target)
+        });
+
+        let acted = match self
+            .battle
+            .act(&self.action_repo, caster_index, id, target_index)
+        {
+            Ok(acted) => acted,
+            Err(e) => {
+                log::warn!("Ignoring {}: {}", action.name, e);
+                return;
+            }
+        };
+
+        let shown = ShownAction {
+            caster,
+            action,
+            target,
+            chance,
+            acted,
+        };
+
+        // Actions with a cinematic are shown on its impact
+        match shown.action.cinematic.clone() {
+            Some(def) => {
+                self.cinematic = Some(Cinematic::new(state, def, caster, target));
+                self.impact = Some(shown);
+            }
+            None => self.show_action(state, shown),
+        }
+    }
+
+    // Bring the world in step with an action the battle resolved and show
+    // what it did
+    fn show_action(&mut self, state: &mut StateInner, shown: ShownAction) {
+        let ShownAction {
+            caster,
+            action,
+            target,
+            chance,
+            acted,
+        } = shown;
+
+        self.sync_characters(&mut state.world);
+        self.show_phases(state, acted.phases);
+        let resolved = acted.resolved;
+
+        let (caster_name, player_controlled) = match state.world.get::<&Character>(caster) {
+            Ok(character) => (character.name.clone(), character.player_controlled),
+            Err(_) => return,
+        };
+
+        let target = match target {
+            Some(target) => target,
+            None => {
+                log::info!("{} uses {}", caster_name, action.name);
+
+                if let Some(record) = &mut self.telemetry {
+                    record.actions.push(action_record(
+                        &state.world,
+                        &self.characters,
+                        self.battle.round(),
+                        caster,
+                        &action,
+                        None,
+                    ));
+                }
+                return;
+            }
+        };
+
+        let (target_name, target_health) = match state.world.get::<&Character>(target) {
+            Ok(character) => (character.name.clone(), character.stats.health),
+            Err(_) => return,
+        };
+
+        if resolved.missed {
+            log::info!(
+                "{} uses {} on {} but misses ({}% to hit)",
+                caster_name,
+                action.name,
+                target_name,
+                chance
+            );
+
+            if let Some(record) = &mut self.telemetry {
+                record.actions.push(ActionRecord {
+                    missed: true,
+                    ..action_record(
+                        &state.world,
+                        &self.characters,
+                        self.battle.round(),
+                        caster,
+                        &action,
+                        Some(target),
+                    )
+                });
+            }
+
+            self.floating_text.spawn(state, target, "MISS", MISS_STYLE);
+            state.events.emit(BattleEvent::Missed { caster, target });
+            return;
+        }
+
+        // Damage dealt and whether it was critical, shown once the target is
+        // free to borrow
+        let mut hit = None;
+        let mut healed = None;
+
+        match action.resolution {
+            ActionResolution::None => {
+                log::info!("{} uses {} on {}", caster_name, action.name, target_name)
+            }
+
+            ActionResolution::Damage(_) => {
+                log::info!(
+                    "{} uses {} on {} for {} {}damage ({} health left)",
+                    caster_name,
+                    action.name,
+                    target_name,
+                    resolved.damage,
+                    match resolved.critical {
+                        true => "critical ",
+                        false => "",
+                    },
+                    target_health
+                );
+
+                if player_controlled {
+                    self.stats.add(
+                        &mut state.events,
+                        stats::stat::DAMAGE_DEALT,
+                        resolved.damage as u64,
+                    );
+
+                    if resolved.critical {
+                        self.stats.add(&mut state.events, stats::stat::CRITS, 1);
                     }
-                })
-                .unwrap();
+                }
+
+                hit = Some((resolved.damage, resolved.critical));
+            }
 
-            self.turn_order.push_back(character.1 .1);
-            weight -= character.1 .0;
-            character_weights.remove(character.0);
+            ActionResolution::Heal(_) => {
+                log::info!(
+                    "{} uses {} on {}, healing {}",
+                    caster_name,
+                    action.name,
+                    target_name,
+                    resolved.healed
+                );
+
+                healed = Some(resolved.healed);
+            }
         }
 
-        log::debug!(
-            "Turn order = {:?}",
-            self.turn_order
-                .iter()
-                .fold(String::new(), |acc, id| format!(
-                    "{}, {}",
-                    acc,
-                    world.get::<&Character>(*id).unwrap().name
-                ))
-        );
+        let defeated = resolved.defeated;
+
+        if let (false, Some(effect)) = (defeated, &action.status) {
+            log::info!(
+                "{} gains {:?} for {} turns",
+                target_name,
+                effect.kind,
+                effect.turns
+            );
+        }
+
+        if let Some(record) = &mut self.telemetry {
+            let (damage, critical) = hit.unwrap_or_default();
+            record.actions.push(ActionRecord {
+                damage,
+                critical,
+                ..action_record(
+                    &state.world,
+                    &self.characters,
+                    self.battle.round(),
+                    caster,
+                    &action,
+                    Some(target),
+                )
+            });
+        }
+
+        if let Some((amount, _)) = hit {
+            state.events.emit(BattleEvent::Hit {
+                caster,
+                target,
+                amount,
+            });
+        }
+        if let Some(amount) = healed {
+            state.events.emit(BattleEvent::Healed {
+                caster,
+                target,
+                amount,
+            });
+        }
+
+        match hit {
+            Some((amount, true)) => {
+                self.floating_text
+                    .spawn(state, target, format!("{}!", amount), CRIT_STYLE);
+                state.renderer.flash(CRIT_FLASH, CRIT_FLASH_TIME);
+                spawn_crit_sparks(state, target);
+                state.events.emit(BattleEvent::CriticalHit {
+                    caster,
+                    target,
+                    amount,
+                });
+            }
+            Some((amount, false)) => {
+                self.floating_text
+                    .spawn(state, target, amount.to_string(), DAMAGE_STYLE)
+            }
+            None => {}
+        }
+
+        if defeated {
+            self.defeat(state, target);
+        }
     }
 
-    fn start_turn(&mut self, state: &mut StateInner) {
-        match self.turn_order.pop_front() {
-            Some(next_character) => {
-                self.current_character = next_character;
+    fn defeat(&mut self, state: &mut StateInner, target: Entity) {
+        if let Ok(character) = state.world.get::<&Character>(target) {
+            log::info!("{} is defeated", character.name);
+        }
 
-                let menu = UiMenus::new(state, &self.action_repo, next_character).unwrap();
-                self.battle_state = BattleState::WaitingForInput(menu);
+        let side = match self.characters.friendly.contains(&target) {
+            true => &self.characters.friendly,
+            false => &self.characters.enemy,
+        };
+        if Characters::all_defeated(&state.world, side) {
+            state
+                .events
+                .emit(BattleEvent::FinishingBlow { character: target });
+        }
+
+        animation::stop_idle_animation(&mut state.world, target);
+        fade::fade_to(&mut state.world, target, DEFEATED_OPACITY, DEFEAT_FADE_TIME);
+        if let Ok(mut sprite) = state.world.get::<&mut Sprite>(target) {
+            sprite.color = [0.3, 0.3, 0.3, 1.];
+        }
+        if let Ok(mut stack) = state.world.get::<&mut SpriteStack>(target) {
+            stack
+                .layers
+                .iter_mut()
+                .for_each(|layer| layer.color = [0.3, 0.3, 0.3, 1.]);
+        }
+    }
+
+    fn take_cpu_turn(&mut self, state: &mut StateInner, id: Entity, profile: CpuProfile) {
+        match self.battle.choose_cpu_action(&self.action_repo, profile) {
+            Some((action, target)) => {
+                let target = target.and_then(|index| self.characters.from_index(index as u32));
+                self.resolve_action(state, id, action, target);
+            }
+            None => {
+                if let Ok(character) = state.world.get::<&Character>(id) {
+                    log::info!("{} has nothing to do", character.name);
+                }
             }
-            None => self.battle_state = BattleState::StartingRound,
         }
     }
+
+    // Swap changed values into the running battle. Anything that would
+    // change the battle's structure needs a restart to apply.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reload_data(&mut self, world: &mut World) {
+        if !self.data_watcher.changed() {
+            return;
+        }
+
+        let data = match GameData::load_configured() {
+            Ok(data) => data,
+            Err(errors) => {
+                log::error!("Unable to reload game data - keeping current data");
+                errors.iter().for_each(|e| log::error!("    {}", e));
+                return;
+            }
+        };
+
+        if !self.data.same_structure(&data) {
+            log::warn!("Game data changed structure - restart the battle to apply");
+            return;
+        }
+
+        self.action_repo.update(&data.actions);
+        self.locale = Locale::new(&data, &Settings::load().language);
+
+        // Encounter enemies are generated rather than read from the data
+        let roster = match self.encounter.is_some() {
+            true => self.characters.friendly.len(),
+            false => self.characters.roster.len(),
+        };
+
+        self.characters
+            .roster
+            .iter()
+            .take(roster)
+            .zip(data.battle_order())
+            .enumerate()
+            .for_each(|(index, (id, def))| {
+                let character = match self.battle.character_mut(index) {
+                    Some(character) => character,
+                    None => return,
+                };
+
+                character.name = def.name.clone();
+                character.player_controlled = def.player_controlled;
+                character.stats.speed = def.speed;
+                character.stats.max_health = def.health;
+                character.stats.health = character.stats.health.min(def.health);
+                character.stats.accuracy = def.accuracy;
+                character.stats.evasion = def.evasion;
+                character.stats.crit_chance = def.crit_chance;
+                character.stats.crit_multiplier = def.crit_multiplier;
+
+                if character.equipment != def.equipment {
+                    character.equipment = def.equipment.clone();
+                    self.character_manager
+                        .equip(world, *id, def.equipment.clone());
+                }
+            });
+        self.sync_characters(world);
+
+        self.audio.set_data(&data);
+
+        log::info!("Reloaded game data");
+        self.data = data;
+    }
+
+    // Kept up to date so crash reports show where the battle was
+    fn record_snapshot(&self, world: &World) {
+        let character = |id: &Entity| match world.get::<&Character>(*id) {
+            Ok(character) => format!("{:?} {:?}", id, *character),
+            Err(_) => format!("{:?} <missing>", id),
+        };
+
+        let mut snapshot = format!(
+            "Battle state: {:?}\nCurrent character: {:?}\nTurn order: {:?}\n",
+            self.battle_state,
+            self.current_character,
+            self.battle.turn_order().collect::<Vec<_>>()
+        );
+
+        self.characters
+            .friendly
+            .iter()
+            .for_each(|id| snapshot.push_str(&format!("Friendly {}\n", character(id))));
+        self.characters
+            .enemy
+            .iter()
+            .for_each(|id| snapshot.push_str(&format!("Enemy {}\n", character(id))));
+
+        engine::crash::record_snapshot(snapshot);
+    }
+}
+
+#[inline]
+// Names are taken straight away as characters are gone by the time the record
+// is written
+fn action_record(
+    world: &World,
+    characters: &Characters,
+    round: u32,
+    caster: Entity,
+    action: &Action,
+    target: Option<Entity>,
+) -> ActionRecord {
+    let name = |id: Entity| {
+        world
+            .get::<&Character>(id)
+            .map_or(String::new(), |character| character.name.clone())
+    };
+
+    ActionRecord {
+        round,
+        caster: name(caster),
+        friendly: characters.friendly.contains(&caster),
+        action: action.name.clone(),
+        target: target.map(name),
+        damage: 0,
+        missed: false,
+        critical: false,
+    }
+}
+
+// Move the camera back until every character is in view, keeping the way it
+// faces and staying in bounds
+fn frame_characters(state: &mut StateInner, characters: &[Entity], controller: &CameraController) {
+    let points = characters
+        .iter()
+        .filter_map(|id| state.world.get::<&Transform>(*id).ok())
+        .map(|transform| transform.translation)
+        .collect::<Vec<_>>();
+
+    let camera = &state.renderer.camera.camera;
+    if let Some(translation) = camera.frame_points(&points, FRAME_PADDING) {
+        let translation = controller.clamp(translation);
+        let rotation = camera.rotation;
+        state
+            .renderer
+            .tween_camera(translation, rotation, Some(FRAME_TIME));
+    }
+}
+
+// From the encounter's arena, or the default arena without an encounter
+fn camera_bounds(encounter: Option<&Encounter>) -> CameraBounds {
+    let arena = encounter.map_or_else(ArenaDef::default, |encounter| encounter.arena.clone());
+
+    CameraBounds {
+        min: arena.camera_min.into(),
+        max: arena.camera_max.into(),
+    }
+}
+
+// Game time, so timers hold while a modal is open
+fn battle_time(state: &StateInner) -> Duration {
+    *state.time.game_time()
+}
+
+/// One off burst of sparks that despawns once the last spark has faded.
+pub const CRIT_SPARKS_PREFAB: &str = "effect_crit_sparks";
+
+pub(crate) fn register_prefabs(prefabs: &mut Prefabs) {
+    prefabs.register(CRIT_SPARKS_PREFAB, |_, builder| {
+        let mut emitter = ParticleEmitter::burst(CRIT_SPARKS);
+        emitter.velocity = glam::vec3(0., 80., 0.);
+        emitter.spread = 120.;
+        emitter.color = CRIT_SPARK_COLOR;
+
+        builder.add_bundle((
+            Transform::default(),
+            DespawnAfter(Duration::from_secs_f32(emitter.lifetime)),
+            emitter,
+        ));
+    });
+}
+
+fn spawn_crit_sparks(state: &mut StateInner, target: Entity) {
+    let translation = match state.world.get::<&Transform>(target) {
+        Ok(transform) => transform.translation,
+        Err(_) => return,
+    };
+
+    state.spawn_prefab(
+        CRIT_SPARKS_PREFAB,
+        (Transform::from_translation(translation),),
+    );
 }
 
 //====================================================================
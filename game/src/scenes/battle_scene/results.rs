@@ -0,0 +1,198 @@
+//====================================================================
+
+use common::Transform;
+use engine::{tools::KeyCode, StateInner};
+use hecs::Entity;
+use renderer::pipelines::ui3d_pipeline::Ui3d;
+
+use crate::{
+    audio::MenuEvent,
+    controls,
+    party::{self, ExperienceGain, EXPERIENCE_PER_LEVEL},
+};
+
+//====================================================================
+
+/// Skips the results tally, or leaves once it's done.
+pub const CONTINUE_KEY: KeyCode = KeyCode::Enter;
+
+/// Experience for each enemy defeated, multiplied by the encounter level.
+pub const EXPERIENCE_PER_ENEMY: u64 = 10;
+
+/// Seconds taken to fill the experience bars.
+const FILL_TIME: f32 = 1.2;
+/// Seconds each level up stays highlighted.
+const LEVEL_UP_TIME: f32 = 0.8;
+/// Seconds between each piece of loot being revealed.
+const LOOT_INTERVAL: f32 = 0.4;
+
+const BAR_WIDTH: usize = 20;
+
+//====================================================================
+
+/// Part of the tally, played one after another.
+#[derive(Debug, Clone, PartialEq)]
+enum ResultStep {
+    /// Every character's experience bar fills together.
+    Experience,
+    LevelUp {
+        name: String,
+        level: u32,
+    },
+    Loot(String),
+}
+
+impl ResultStep {
+    #[inline]
+    fn duration(&self) -> f32 {
+        match self {
+            ResultStep::Experience => FILL_TIME,
+            ResultStep::LevelUp { .. } => LEVEL_UP_TIME,
+            ResultStep::Loot(_) => LOOT_INTERVAL,
+        }
+    }
+}
+
+/// Tally shown after a victory, already applied to the party. Experience
+/// bars fill first, then each level up pops up, then loot is revealed one
+/// item at a time.
+#[derive(Debug)]
+pub struct BattleResults {
+    entity: Entity,
+    gains: Vec<ExperienceGain>,
+
+    steps: Vec<ResultStep>,
+    /// Step playing, or the number of steps once they've all played.
+    step: usize,
+    /// Seconds into the current step.
+    elapsed: f32,
+}
+
+impl BattleResults {
+    pub fn new(state: &mut StateInner, gains: Vec<ExperienceGain>, loot: Vec<String>) -> Self {
+        let camera = &state.renderer.camera.camera;
+        let position = camera.translation + camera.forward() * 7.;
+
+        let entity = state
+            .world
+            .spawn((Transform::from_translation(position), Ui3d::default()));
+
+        let steps = std::iter::once(ResultStep::Experience)
+            .chain(gains.iter().filter_map(|gain| {
+                gain.level_up().map(|level| ResultStep::LevelUp {
+                    name: gain.name.clone(),
+                    level,
+                })
+            }))
+            .chain(loot.into_iter().map(ResultStep::Loot))
+            .collect();
+
+        let results = Self {
+            entity,
+            gains,
+            steps,
+            step: 0,
+            elapsed: 0.,
+        };
+        results.update_panel(state);
+
+        results
+    }
+
+    #[inline]
+    fn is_done(&self) -> bool {
+        self.step >= self.steps.len()
+    }
+
+    pub fn close(&self, state: &mut StateInner) {
+        state.despawn(self.entity).ok();
+    }
+
+    /// Returns true once the player is done with the results.
+    pub fn tick(&mut self, state: &mut StateInner) -> bool {
+        let confirmed = state.keys.just_pressed(CONTINUE_KEY);
+        if confirmed {
+            state.events.emit(MenuEvent::Confirmed);
+        }
+
+        if self.is_done() {
+            return confirmed;
+        }
+
+        match confirmed {
+            true => self.step = self.steps.len(),
+            false => self.elapsed += state.time.delta_seconds(),
+        }
+
+        // Long frames can finish more than one step
+        while let Some(step) = self.steps.get(self.step) {
+            if self.elapsed < step.duration() {
+                break;
+            }
+
+            self.elapsed -= step.duration();
+            self.step += 1;
+        }
+
+        self.update_panel(state);
+        false
+    }
+
+    fn update_panel(&self, state: &mut StateInner) {
+        // Bars are full once the experience step is over
+        let fill = match self.step {
+            0 => ease_out_cubic(self.elapsed / FILL_TIME),
+            _ => 1.,
+        };
+
+        let mut options = vec![String::from("Victory!")];
+
+        options.extend(self.gains.iter().map(|gain| {
+            let experience = gain.before + (gain.gained as f32 * fill).round() as u64;
+            let into_level = experience % EXPERIENCE_PER_LEVEL;
+            let filled = (into_level * BAR_WIDTH as u64 / EXPERIENCE_PER_LEVEL) as usize;
+
+            format!(
+                "{} Lv {} [{}{}] {}/{}",
+                gain.name,
+                party::level(experience),
+                "#".repeat(filled),
+                "-".repeat(BAR_WIDTH - filled),
+                into_level,
+                EXPERIENCE_PER_LEVEL
+            )
+        }));
+
+        // Steps show up as they start playing
+        let shown = match self.is_done() {
+            true => self.steps.len(),
+            false => self.step + 1,
+        };
+
+        options.extend(self.steps[..shown].iter().filter_map(|step| match step {
+            ResultStep::Experience => None,
+            ResultStep::LevelUp { name, level } => {
+                Some(format!("Level up! {} reached level {}", name, level))
+            }
+            ResultStep::Loot(item) => Some(format!("Found {}", item)),
+        }));
+
+        if self.is_done() {
+            options.push(format!("{} Continue", controls::key_label(&[CONTINUE_KEY])));
+        }
+
+        // Highlight whatever is news
+        let selected = options.len() - 1;
+
+        if let Ok(mut ui) = state.world.get::<&mut Ui3d>(self.entity) {
+            ui.options = options;
+            ui.selected = selected as u8;
+        }
+    }
+}
+
+fn ease_out_cubic(t: f32) -> f32 {
+    1. - (1. - t.clamp(0., 1.)).powi(3)
+}
+
+//====================================================================
@@ -1,23 +1,162 @@
 //====================================================================
 
-use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
 
-use super::characters::Character;
+use common::Transform;
+use engine::StateInner;
+use hecs::Entity;
+use renderer::{fade::Fade, pipelines::ui3d_pipeline::Ui3d};
 
-//====================================================================
+use crate::{battle::FiredPhase, combat::TurnStartTick};
 
-pub struct BattleServer {
-    current_character: CharacterId,
-    turn_order: VecDeque<CharacterId>,
-}
+use super::{battle_time, characters::Character, BattleEvent, BattleScene};
 
 //====================================================================
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct CharacterId(u32);
+/// How long the battle pauses for a cutscene.
+const CUTSCENE_TIME: Duration = Duration::from_secs(3);
+/// Seconds taken for summoned characters to fade in.
+const SUMMON_FADE_TIME: f32 = 0.6;
+
+impl BattleScene {
+    /// Show the status effects and cooldowns resolved at the start of `id`'s
+    /// turn, emitting a [`BattleEvent::TurnStartTick`] for each. Returns true
+    /// if the character was defeated.
+    pub(super) fn show_turn_start(
+        &mut self,
+        state: &mut StateInner,
+        id: Entity,
+        ticks: Vec<TurnStartTick>,
+    ) -> bool {
+        let name = match state.world.get::<&Character>(id) {
+            Ok(character) => character.name.clone(),
+            Err(_) => return false,
+        };
+
+        let mut defeated = false;
+
+        ticks.into_iter().for_each(|tick| {
+            match &tick {
+                TurnStartTick::Damaged { kind, amount } => {
+                    log::info!("{} takes {} damage from {:?}", name, amount, kind)
+                }
+                TurnStartTick::Healed { kind, amount } => {
+                    log::info!("{} heals {} from {:?}", name, amount, kind)
+                }
+                TurnStartTick::Expired { kind } => log::info!("{}'s {:?} wore off", name, kind),
+                TurnStartTick::Defeated => defeated = true,
+                TurnStartTick::CooldownReady { action } => {
+                    if let Some(action) = self.action_repo.get_action(action) {
+                        log::debug!("{} can use {} again", name, action.name);
+                    }
+                }
+            }
+
+            state.events.emit(BattleEvent::TurnStartTick {
+                character: id,
+                tick,
+            });
+        });
+
+        if defeated {
+            self.defeat(state, id);
+        }
+
+        defeated
+    }
+
+    /// Show the boss phases the battle reached, spawning anyone summoned.
+    /// Their cutscenes wait for [`BattleScene::play_phase_cutscene`].
+    pub(super) fn show_phases(&mut self, state: &mut StateInner, phases: Vec<FiredPhase>) {
+        let mut summoned = false;
+
+        phases.into_iter().for_each(|phase| {
+            log::info!("Boss phase triggered at {}% health", phase.health_percent);
+
+            if let Some(boss) = self.characters.from_index(phase.boss as u32) {
+                state.events.emit(BattleEvent::BossPhase { boss });
+            }
+
+            phase.summoned.into_iter().for_each(|index| {
+                summoned |= self.summon(state, index);
+            });
+
+            self.cutscene_lines.extend(phase.cutscene);
+        });
+
+        if summoned {
+            self.position_characters(&mut state.world);
+        }
+    }
+
+    /// Pause for the cutscenes of boss phases reached since the last one.
+    pub(super) fn play_phase_cutscene(&mut self, state: &mut StateInner) {
+        if !self.cutscene_lines.is_empty() {
+            let lines = std::mem::take(&mut self.cutscene_lines);
+            self.play_cutscene(state, lines);
+        }
+    }
+
+    // Summons join the boss's side and act from the next round
+    fn summon(&mut self, state: &mut StateInner, index: usize) -> bool {
+        let (friendly, character) =
+            match (self.battle.is_friendly(index), self.battle.character(index)) {
+                (Some(friendly), Some(character)) => (friendly, character.clone()),
+                _ => return false,
+            };
+
+        log::info!("{} joins the battle", character.name);
+
+        let id = self.character_manager.spawn_character(state, character);
+        state
+            .world
+            .insert_one(id, Fade::fade_in(SUMMON_FADE_TIME))
+            .ok();
+
+        match friendly {
+            true => self.characters.friendly.insert(id),
+            false => self.characters.enemy.insert(id),
+        };
+        self.characters.roster.push(id);
+
+        true
+    }
+
+    fn play_cutscene(&mut self, state: &mut StateInner, lines: Vec<String>) {
+        if let Some((entity, _)) = self.cutscene.take() {
+            state.despawn(entity).ok();
+        }
+
+        let camera = &state.renderer.camera.camera;
+        let position = camera.translation + camera.forward() * 6.;
+
+        let entity = state.world.spawn((
+            Transform::from_translation(position),
+            Ui3d {
+                options: lines,
+                ..Default::default()
+            },
+        ));
+
+        self.cutscene = Some((entity, battle_time(state) + CUTSCENE_TIME));
+    }
+
+    /// Returns true while a cutscene is holding up the battle.
+    pub(super) fn tick_cutscene(&mut self, state: &mut StateInner) -> bool {
+        let (entity, end) = match self.cutscene {
+            Some(cutscene) => cutscene,
+            None => return false,
+        };
+
+        if battle_time(state) < end {
+            return true;
+        }
+
+        state.despawn(entity).ok();
+        self.cutscene = None;
 
-pub struct CharacterStorage {
-    characters: HashMap<CharacterId, Character>,
+        false
+    }
 }
 
 //====================================================================
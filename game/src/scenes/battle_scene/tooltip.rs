@@ -0,0 +1,162 @@
+//====================================================================
+
+use common::Transform;
+use engine::{picking, StateInner};
+use hecs::Entity;
+use renderer::pipelines::ui3d_pipeline::Ui3d;
+
+use crate::characters::Character;
+
+//====================================================================
+
+/// Seconds the cursor has to rest on a character before its tooltip shows.
+const SHOW_DELAY: f32 = 0.4;
+/// Seconds a tooltip lingers after the cursor leaves, so it doesn't flicker
+/// when the cursor brushes past the edge of a sprite.
+const HIDE_DELAY: f32 = 0.2;
+/// Height above the character.
+const TOOLTIP_HEIGHT: f32 = 45.;
+const TOOLTIP_SCALE: f32 = 0.6;
+const TOOLTIP_COLOR: [f32; 4] = [0.1, 0.1, 0.15, 0.85];
+
+//====================================================================
+
+struct Shown {
+    character: Entity,
+    panel: Entity,
+    lines: Vec<String>,
+}
+
+/// Panel with a character's name, health and status effects, shown above
+/// whichever character the cursor rests on.
+#[derive(Default)]
+pub struct HoverTooltip {
+    hovered: Option<Entity>,
+    /// Seconds the cursor has been on the hovered character, or off any
+    /// character if there isn't one.
+    hover_time: f32,
+    shown: Option<Shown>,
+}
+
+impl HoverTooltip {
+    pub fn tick(&mut self, state: &mut StateInner) {
+        // Hovering keeps working while the game is slowed or sped up
+        let delta = state.time.unscaled_delta_seconds();
+
+        let hovered = picking::pick_cursor(state).filter(|entity| {
+            state
+                .world
+                .satisfies::<&Character>(*entity)
+                .unwrap_or(false)
+        });
+
+        match hovered == self.hovered {
+            true => self.hover_time += delta,
+            false => {
+                self.hovered = hovered;
+                self.hover_time = 0.;
+            }
+        }
+
+        let showing = self.shown.as_ref().map(|shown| shown.character);
+
+        match (self.hovered, showing) {
+            (Some(hovered), Some(showing)) if hovered == showing => {}
+
+            // Replaces any other character's tooltip
+            (Some(hovered), _) if self.hover_time >= SHOW_DELAY => {
+                self.close(state);
+                self.open(state, hovered);
+            }
+
+            (None, Some(_)) if self.hover_time >= HIDE_DELAY => self.close(state),
+
+            _ => {}
+        }
+
+        self.update(state);
+    }
+
+    pub fn close(&mut self, state: &mut StateInner) {
+        if let Some(shown) = self.shown.take() {
+            state.despawn(shown.panel).ok();
+        }
+    }
+
+    fn open(&mut self, state: &mut StateInner, character: Entity) {
+        let panel = state.world.spawn((
+            Transform::from_scale((TOOLTIP_SCALE, TOOLTIP_SCALE, TOOLTIP_SCALE)),
+            Ui3d {
+                menu_color: TOOLTIP_COLOR,
+                selection_color: TOOLTIP_COLOR,
+                ..Default::default()
+            },
+        ));
+
+        self.shown = Some(Shown {
+            character,
+            panel,
+            lines: Vec::new(),
+        });
+    }
+
+    // Follow the character and keep the text up to date, closing the tooltip
+    // if the character is gone
+    fn update(&mut self, state: &mut StateInner) {
+        let shown = match &mut self.shown {
+            Some(shown) => shown,
+            None => return,
+        };
+
+        let target = state
+            .world
+            .query_one::<(&Transform, &Character)>(shown.character)
+            .ok()
+            .and_then(|mut query| {
+                query.get().map(|(transform, character)| {
+                    (
+                        transform.translation + glam::Vec3::Y * TOOLTIP_HEIGHT,
+                        tooltip_lines(character),
+                    )
+                })
+            });
+
+        let (position, lines) = match target {
+            Some(target) => target,
+            None => {
+                self.close(state);
+                return;
+            }
+        };
+
+        if let Ok(mut transform) = state.world.get::<&mut Transform>(shown.panel) {
+            transform.translation = position;
+        }
+
+        if lines != shown.lines {
+            if let Ok(mut ui) = state.world.get::<&mut Ui3d>(shown.panel) {
+                ui.options = lines.clone();
+            }
+            shown.lines = lines;
+        }
+    }
+}
+
+fn tooltip_lines(character: &Character) -> Vec<String> {
+    let stats = &character.stats;
+
+    [
+        character.name.clone(),
+        format!("HP {}/{}", stats.health, stats.max_health),
+    ]
+    .into_iter()
+    .chain(
+        character
+            .effects
+            .iter()
+            .map(|effect| format!("{:?} ({} turns)", effect.kind, effect.turns)),
+    )
+    .collect()
+}
+
+//====================================================================
@@ -0,0 +1,109 @@
+//====================================================================
+
+use common::Transform;
+use engine::StateInner;
+use hecs::Entity;
+use renderer::pipelines::{ui3d_pipeline::Ui3d, Hidden};
+
+use crate::banners::smoothstep;
+
+//====================================================================
+
+/// Seconds taken to slide a changed order into place.
+const SLIDE_TIME: f32 = 0.35;
+/// How far above its place a changed order starts.
+const SLIDE_DISTANCE: f32 = 0.6;
+
+const STRIP_COLOR: [f32; 4] = [0.1, 0.1, 0.15, 0.7];
+/// Color a changed order starts at before settling.
+const CHANGED_COLOR: [f32; 4] = [0.3, 0.3, 0.55, 0.9];
+
+//====================================================================
+
+/// Strip along the top of the view showing who acts next, sliding in again
+/// whenever the order changes.
+pub struct TurnStrip {
+    entity: Entity,
+    text: String,
+    elapsed: f32,
+}
+
+impl TurnStrip {
+    pub fn new(state: &mut StateInner) -> Self {
+        let entity = state.world.spawn((
+            Transform::default(),
+            Ui3d {
+                menu_color: STRIP_COLOR,
+                selection_color: STRIP_COLOR,
+                font_size: 24.,
+                ..Default::default()
+            },
+        ));
+
+        Self {
+            entity,
+            text: String::new(),
+            elapsed: SLIDE_TIME,
+        }
+    }
+
+    pub fn close(&self, state: &mut StateInner) {
+        state.despawn(self.entity).ok();
+    }
+
+    /// Show `text`, animating it in if it's changed.
+    pub fn show(&mut self, state: &mut StateInner, text: String) {
+        if text == self.text {
+            return;
+        }
+
+        if let Ok(mut ui) = state.world.get::<&mut Ui3d>(self.entity) {
+            ui.options = vec![text.clone()];
+        }
+
+        self.text = text;
+        self.elapsed = 0.;
+    }
+
+    /// Keep the strip out of the way without losing its text.
+    pub fn set_hidden(&self, state: &mut StateInner, hidden: bool) {
+        let is_hidden = state
+            .world
+            .satisfies::<&Hidden>(self.entity)
+            .unwrap_or(false);
+
+        match (hidden, is_hidden) {
+            (true, false) => {
+                state.world.insert_one(self.entity, Hidden).ok();
+            }
+            (false, true) => {
+                state.world.remove_one::<Hidden>(self.entity).ok();
+            }
+            _ => {}
+        }
+    }
+
+    pub fn tick(&mut self, state: &mut StateInner) {
+        self.elapsed += state.time.delta_seconds();
+        let settled = smoothstep(self.elapsed / SLIDE_TIME);
+
+        let camera = &state.renderer.camera.camera;
+        let position = camera.translation
+            + camera.forward() * 8.
+            + glam::Vec3::Y * (2.6 + (1. - settled) * SLIDE_DISTANCE);
+
+        if let Ok(mut transform) = state.world.get::<&mut Transform>(self.entity) {
+            transform.translation = position;
+        }
+
+        if let Ok(mut ui) = state.world.get::<&mut Ui3d>(self.entity) {
+            let lerp = |from: f32, to: f32| from + (to - from) * settled;
+            let color = std::array::from_fn(|i| lerp(CHANGED_COLOR[i], STRIP_COLOR[i]));
+
+            ui.menu_color = color;
+            ui.selection_color = color;
+        }
+    }
+}
+
+//====================================================================
@@ -1,15 +1,20 @@
 //====================================================================
 
-use std::collections::HashSet;
-
 use common::Transform;
-use engine::{tools::KeyCode, StateInner};
-use hecs::{Entity, World};
-use renderer::pipelines::ui3d_pipeline::Ui3d;
+use engine::{focus::Focusable, tools::KeyCode, StateInner};
+use hecs::Entity;
+use renderer::{camera::PerspectiveCamera, fade::Fade, pipelines::ui3d_pipeline::Ui3d};
+
+use crate::{
+    audio::MenuEvent,
+    camera::CameraController,
+    locale::Locale,
+    placement::{self, ScreenRect},
+};
 
 use super::{
     characters::{
-        actions::{Action, ActionRepo, TargetType},
+        actions::{Action, ActionId, ActionRepo, ActionResolution, TargetType},
         Character,
     },
     Characters,
@@ -17,10 +22,39 @@ use super::{
 
 //====================================================================
 
+/// Moves the selection in the focused menu.
+pub const MENU_UP_KEY: KeyCode = KeyCode::ArrowUp;
+pub const MENU_DOWN_KEY: KeyCode = KeyCode::ArrowDown;
+/// Picks the selected option.
+pub const MENU_SELECT_KEY: KeyCode = KeyCode::Enter;
+/// Picks the selected option, like [`MENU_SELECT_KEY`].
+pub const MENU_FORWARD_KEY: KeyCode = KeyCode::ArrowRight;
+/// Closes the focused menu, going back to the one before it.
+pub const MENU_BACK_KEY: KeyCode = KeyCode::ArrowLeft;
+
+/// Options in the confirm menu, after its prompt.
+const CONFIRM_YES: u8 = 1;
+const CONFIRM_NO: u8 = 2;
+
+/// Seconds taken for the action menu to fade in at the start of a turn.
+const MENU_FADE_TIME: f32 = 0.2;
+
+/// World distance between a character and their action menu.
+const MENU_GAP: f32 = 50.;
+
+//====================================================================
+
 #[derive(Debug)]
 pub struct UiMenus {
     action_menu: Entity,
     target_menu: Option<Entity>,
+    /// Action the target menu was opened for, and the characters it lists.
+    targeting: Option<(ActionId, Vec<Entity>)>,
+    confirm_menu: Option<Entity>,
+    /// Action and target waiting on the confirm menu.
+    confirming: Option<(ActionId, Option<Entity>)>,
+    /// Camera the menus were last placed for. None to place them again.
+    placed_for: Option<(glam::Vec3, glam::Quat)>,
 
     current_character: Entity,
 }
@@ -31,15 +65,27 @@ enum UiMenuAction {
     Select,
 }
 
+/// Which menu has input focus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiFocus {
+    Actions,
+    Targets,
+    Confirm,
+}
+
 pub enum UiMenuOutput {
     None,
-    SkipTurn,
+    Act {
+        action: ActionId,
+        target: Option<Entity>,
+    },
 }
 
 impl UiMenus {
     pub fn new(
         state: &mut StateInner,
         actions: &ActionRepo,
+        locale: &Locale,
         current_character: Entity,
     ) -> Result<Self, ()> {
         let menu_pos = {
@@ -47,15 +93,24 @@ impl UiMenus {
             character_transform.translation + character_transform.right() * 50.
         };
 
-        let character_actions = state
-            .world
-            .get::<&Character>(current_character)
-            .unwrap()
+        let character = state.world.get::<&Character>(current_character).unwrap();
+
+        // Actions cooling down are listed with the turns left
+        let character_actions = character
             .actions
             .iter()
-            .map(|action| actions.get_action(action).unwrap().name.clone())
+            .map(|action| {
+                let name = locale.action(&actions.get_action(action).unwrap().name);
+
+                match character.cooldown(*action) {
+                    0 => name.to_string(),
+                    turns => format!("{} ({})", name, turns),
+                }
+            })
             .collect::<Vec<_>>();
 
+        drop(character);
+
         if character_actions.is_empty() {
             return Err(());
         }
@@ -66,138 +121,297 @@ impl UiMenus {
                 ..Default::default()
             },
             Transform::from_scale_translation((0.8, 0.8, 0.8), menu_pos),
+            Focusable::default(),
+            Fade::fade_in(MENU_FADE_TIME),
         ));
+        state.focus.push(&mut state.events, action_menu);
 
         Ok(Self {
             action_menu,
             target_menu: None,
+            targeting: None,
+            confirm_menu: None,
+            confirming: None,
+            placed_for: None,
             current_character,
         })
     }
 
     fn spawn_target_menu(
         &mut self,
-        world: &mut World,
+        state: &mut StateInner,
         characters: &Characters,
+        camera: &CameraController,
+        id: ActionId,
         action: &Action,
     ) -> Result<(), ()> {
-        let friendly = characters.friendly.contains(&self.current_character);
-
-        let options = match (action.target, friendly) {
-            (TargetType::Any { can_target_caster }, _) => {
-                let mut characters = characters
-                    .friendly()
-                    .iter()
-                    .chain(characters.enemy())
-                    .map(|id| *id)
-                    .collect::<HashSet<_>>();
-
-                if !can_target_caster {
-                    characters.remove(&self.current_character);
-                }
+        let targets = characters.targets(&state.world, self.current_character, action.target);
 
-                characters
-            }
+        if targets.is_empty() {
+            return Err(());
+        }
 
-            (TargetType::Friendly { can_target_caster }, true) => {
-                let mut characters = characters.friendly().clone();
-                if !can_target_caster {
-                    characters.remove(&self.current_character);
-                }
-                characters
-            }
-            (TargetType::Friendly { can_target_caster }, false) => {
-                let mut characters = characters.enemy().clone();
-                if !can_target_caster {
-                    characters.remove(&self.current_character);
+        // Bring every choice into view if some are off screen
+        let perspective = &state.renderer.camera.camera;
+        let hidden = targets.iter().any(|id| {
+            state
+                .world
+                .get::<&Transform>(*id)
+                .ok()
+                .and_then(|transform| perspective.project(transform.translation))
+                .is_none_or(|point| point.abs().max_element() > 1.)
+        });
+        if hidden {
+            super::frame_characters(state, &targets, camera);
+        }
+
+        let world = &mut state.world;
+
+        // Attacks show their chance to hit each target
+        let options = targets
+            .iter()
+            .map(|id| {
+                let name = world.get::<&Character>(*id).unwrap().name.clone();
+
+                match action.resolution {
+                    ActionResolution::Damage(_) => format!(
+                        "{} ({}%)",
+                        name,
+                        Characters::hit_chance(world, self.current_character, *id)
+                    ),
+                    _ => name,
                 }
-                characters
-            }
+            })
+            .collect::<Vec<_>>();
 
-            (TargetType::Enemy, true) => characters.friendly().clone(),
-            (TargetType::Enemy, false) => characters.enemy().clone(),
+        self.targeting = Some((id, targets));
 
-            _ => todo!(),
+        let target_menu = world.spawn((
+            Transform::from_scale((0.3, 0.3, 0.3)),
+            Ui3d {
+                options,
+                ..Default::default()
+            },
+            Focusable::default(),
+        ));
+        state.focus.push(&mut state.events, target_menu);
+        self.target_menu = Some(target_menu);
+        self.placed_for = None;
+
+        Ok(())
+    }
+
+    // Ask before committing the turn. Backing out leaves the other menus as
+    // they were.
+    fn spawn_confirm_menu(
+        &mut self,
+        state: &mut StateInner,
+        action_repo: &ActionRepo,
+        locale: &Locale,
+        action: ActionId,
+        target: Option<Entity>,
+    ) {
+        let action_name = locale.action(&action_repo.get_action(&action).unwrap().name);
+
+        let target_name = target
+            .filter(|target| *target != self.current_character)
+            .and_then(|target| state.world.get::<&Character>(target).ok())
+            .map(|character| character.name.clone());
+
+        let prompt = match target_name {
+            Some(target_name) => format!("Use {} on {}?", action_name, target_name),
+            None => format!("Use {}?", action_name),
         };
 
-        if options.is_empty() {
-            return Err(());
-        }
+        self.confirming = Some((action, target));
 
-        let options = options
-            .into_iter()
-            .map(|id| world.get::<&Character>(id).unwrap().name.clone())
-            .collect::<Vec<_>>();
+        let confirm_menu = state.world.spawn((
+            Transform::from_scale((0.3, 0.3, 0.3)),
+            Ui3d {
+                options: vec![prompt, "Yes".into(), "No".into()],
+                selected: CONFIRM_YES,
+                ..Default::default()
+            },
+            Focusable::default(),
+        ));
+        state.focus.push(&mut state.events, confirm_menu);
+        self.confirm_menu = Some(confirm_menu);
+        self.placed_for = None;
+    }
 
-        self.target_menu = world
-            .spawn((
-                Transform::from_scale((0.3, 0.3, 0.3)),
-                Ui3d {
-                    options,
-                    ..Default::default()
-                },
-            ))
-            .into();
+    fn close_confirm_menu(&mut self, state: &mut StateInner) {
+        if let Some(confirm_menu) = self.confirm_menu.take() {
+            state.despawn(confirm_menu).ok();
+        }
+        self.confirming = None;
+    }
 
-        Ok(())
+    /// Menu with input focus. None while something outside the battle menus,
+    /// such as a console, has it.
+    pub fn focus(&self, state: &StateInner) -> Option<UiFocus> {
+        let focused = state.focus.focused()?;
+
+        if Some(focused) == self.confirm_menu {
+            Some(UiFocus::Confirm)
+        } else if Some(focused) == self.target_menu {
+            Some(UiFocus::Targets)
+        } else if focused == self.action_menu {
+            Some(UiFocus::Actions)
+        } else {
+            None
+        }
     }
 
-    pub fn drop_menus(&self, world: &mut World) {
-        world.despawn(self.action_menu).ok();
+    pub fn drop_menus(&self, state: &mut StateInner) {
+        state.despawn(self.action_menu).ok();
         if let Some(target_menu) = self.target_menu {
-            world.despawn(target_menu).ok();
+            state.despawn(target_menu).ok();
+        }
+        if let Some(confirm_menu) = self.confirm_menu {
+            state.despawn(confirm_menu).ok();
         }
     }
 
+    /// Input only goes to the menu with focus.
     pub fn tick(
         &mut self,
         state: &mut StateInner,
         action_repo: &ActionRepo,
+        locale: &Locale,
         characters: &Characters,
+        camera: &CameraController,
     ) -> UiMenuOutput {
-        self.position_children(state);
+        self.place_menus(state, characters);
 
-        // Process target menu if available
-        if let Some(target_menu) = self.target_menu {
-            match Self::process_input(state, target_menu) {
-                Some(UiMenuAction::Forward | UiMenuAction::Select) => {
-                    return UiMenuOutput::SkipTurn;
-                }
-                Some(UiMenuAction::Back) => {
-                    state.world.despawn(target_menu).ok();
-                    self.target_menu = None;
+        match self.focus(state) {
+            Some(UiFocus::Confirm) => self.tick_confirm(state),
+            Some(UiFocus::Targets) => self.tick_targets(state, action_repo, locale, characters),
+            Some(UiFocus::Actions) => {
+                self.tick_actions(state, action_repo, locale, characters, camera)
+            }
+            None => UiMenuOutput::None,
+        }
+    }
+
+    fn tick_confirm(&mut self, state: &mut StateInner) -> UiMenuOutput {
+        let confirm_menu = match self.confirm_menu {
+            Some(confirm_menu) => confirm_menu,
+            None => return UiMenuOutput::None,
+        };
+        let input = Self::process_input(state, confirm_menu);
+
+        // The prompt can't be selected
+        let selected = match state.world.get::<&mut Ui3d>(confirm_menu) {
+            Ok(mut ui) => {
+                ui.selected = ui.selected.clamp(CONFIRM_YES, CONFIRM_NO);
+                ui.selected
+            }
+            // Despawned by something else, so forget it
+            Err(_) => {
+                self.close_confirm_menu(state);
+                return UiMenuOutput::None;
+            }
+        };
+
+        match (input, selected) {
+            (Some(UiMenuAction::Forward | UiMenuAction::Select), CONFIRM_YES) => {
+                if let Some((action, target)) = self.confirming {
+                    return UiMenuOutput::Act { action, target };
                 }
-                None => {}
             }
+            (Some(_), _) => self.close_confirm_menu(state),
+            (None, _) => {}
+        }
 
+        UiMenuOutput::None
+    }
+
+    fn tick_targets(
+        &mut self,
+        state: &mut StateInner,
+        action_repo: &ActionRepo,
+        locale: &Locale,
+        characters: &Characters,
+    ) -> UiMenuOutput {
+        let target_menu = match self.target_menu {
+            Some(target_menu) => target_menu,
+            None => return UiMenuOutput::None,
+        };
+
+        // Despawned by something else, so forget it
+        if !state.world.contains(target_menu) {
+            self.target_menu = None;
+            self.targeting = None;
             return UiMenuOutput::None;
         }
 
-        // Process Actions menu
+        match Self::process_input(state, target_menu) {
+            Some(UiMenuAction::Forward | UiMenuAction::Select) => {
+                let selected = state
+                    .world
+                    .get::<&Ui3d>(target_menu)
+                    .map_or(0, |ui| ui.selected);
+
+                if let Some((action, targets)) = &self.targeting {
+                    let (action, target) = (*action, targets.get(selected as usize).copied());
+                    self.spawn_confirm_menu(state, action_repo, locale, action, target);
+                    self.place_menus(state, characters);
+                }
+            }
+            Some(UiMenuAction::Back) => {
+                state.despawn(target_menu).ok();
+                self.target_menu = None;
+                self.targeting = None;
+            }
+            None => {}
+        }
+
+        UiMenuOutput::None
+    }
+
+    fn tick_actions(
+        &mut self,
+        state: &mut StateInner,
+        action_repo: &ActionRepo,
+        locale: &Locale,
+        characters: &Characters,
+        camera: &CameraController,
+    ) -> UiMenuOutput {
         match Self::process_input(state, self.action_menu) {
             // Forward or select entered
             Some(UiMenuAction::Forward | UiMenuAction::Select) => {
-                println!("Seledted to dosthings");
-                let action = {
+                let (id, cooldown) = {
                     let ui = state.world.get::<&Ui3d>(self.action_menu).unwrap();
                     let character = state
                         .world
                         .get::<&Character>(self.current_character)
                         .unwrap();
 
-                    *character.actions.get(ui.selected as usize).unwrap()
+                    let id = *character.actions.get(ui.selected as usize).unwrap();
+                    (id, character.cooldown(id))
                 };
 
-                let action = action_repo.get_action(&action).unwrap();
+                // Can't be used until it's cooled down
+                if cooldown > 0 {
+                    return UiMenuOutput::None;
+                }
+
+                let action = action_repo.get_action(&id).unwrap();
 
                 match action.target {
-                    TargetType::None | TargetType::Caster => return UiMenuOutput::SkipTurn,
+                    TargetType::None => {
+                        self.spawn_confirm_menu(state, action_repo, locale, id, None);
+                    }
+                    TargetType::Caster => {
+                        let target = Some(self.current_character);
+                        self.spawn_confirm_menu(state, action_repo, locale, id, target);
+                    }
                     _ => {
-                        self.spawn_target_menu(&mut state.world, characters, &action)
+                        self.spawn_target_menu(state, characters, camera, id, &action)
                             .ok();
-                        self.position_children(state);
                     }
                 }
+                self.place_menus(state, characters);
             }
             // Don't care about anything else
             _ => {}
@@ -206,44 +420,141 @@ impl UiMenus {
         UiMenuOutput::None
     }
 
-    fn position_children(&mut self, state: &mut StateInner) {
-        if let Some(target_menu) = self.target_menu {
-            let new_pos = {
-                let parent_transform = state.world.get::<&Transform>(self.action_menu).unwrap();
+    // Find spots for the menus that keep them on screen and clear of the
+    // characters and each other. Done again when a menu opens or the camera
+    // moves far enough that the old spots might not work.
+    fn place_menus(&mut self, state: &mut StateInner, characters: &Characters) {
+        let camera = state.renderer.camera.camera.clone();
+
+        if self
+            .placed_for
+            .is_some_and(|from| !placement::camera_moved(&camera, from))
+        {
+            return;
+        }
+        self.placed_for = Some((camera.translation, camera.rotation));
+
+        let mut avoid = characters
+            .roster
+            .iter()
+            .filter_map(|id| placement::sprite_rect(&camera, &state.world, *id))
+            .collect::<Vec<_>>();
 
-                parent_transform.translation
-                    + parent_transform.right() * (parent_transform.scale.x * 100.)
-                    + parent_transform.forward() * 2.
+        let right = camera.rotation * glam::Vec3::X;
+        let up = camera.rotation * glam::Vec3::Y;
+
+        // Beside the character, preferring the side it has always opened on
+        let candidates = {
+            let character = state
+                .world
+                .get::<&Transform>(self.current_character)
+                .unwrap();
+            let size = Self::menu_size(state, self.action_menu);
+
+            [
+                character.translation + character.right() * MENU_GAP,
+                character.translation - right * (MENU_GAP + size.x),
+                character.translation + up * (MENU_GAP + size.y),
+                character.translation - up * MENU_GAP,
+            ]
+        };
+        Self::place_menu(state, &camera, self.action_menu, &candidates, &mut avoid);
+
+        // Children sit next to whichever menu they were opened from
+        let children = [
+            (self.action_menu, self.target_menu),
+            (
+                self.target_menu.unwrap_or(self.action_menu),
+                self.confirm_menu,
+            ),
+        ];
+
+        children.into_iter().for_each(|(parent, child)| {
+            let child = match child {
+                Some(child) => child,
+                None => return,
             };
 
-            let mut transform = state.world.get::<&mut Transform>(target_menu).unwrap();
+            let candidates = {
+                let parent_transform = state.world.get::<&Transform>(parent).unwrap();
+                let (parent_size, child_size) = (
+                    Self::menu_size(state, parent),
+                    Self::menu_size(state, child),
+                );
+                let position = parent_transform.translation + parent_transform.forward() * 2.;
+
+                [
+                    position + parent_transform.right() * (parent_transform.scale.x * 100.),
+                    position - right * child_size.x,
+                    position - up * parent_size.y,
+                    position + up * child_size.y,
+                ]
+            };
+            Self::place_menu(state, &camera, child, &candidates, &mut avoid);
+        });
+    }
 
-            transform.translation = new_pos;
+    fn place_menu(
+        state: &mut StateInner,
+        camera: &PerspectiveCamera,
+        menu: Entity,
+        candidates: &[glam::Vec3],
+        avoid: &mut Vec<ScreenRect>,
+    ) {
+        let (position, rect) = match placement::place(camera, &state.world, menu, candidates, avoid)
+        {
+            Some(placed) => placed,
+            None => return,
+        };
+
+        if let Ok(mut transform) = state.world.get::<&mut Transform>(menu) {
+            transform.translation = position;
         }
+        avoid.push(rect);
+    }
+
+    // World size of a menu
+    fn menu_size(state: &StateInner, menu: Entity) -> glam::Vec2 {
+        let mut query = state.world.query_one::<(&Transform, &Ui3d)>(menu).unwrap();
+
+        query.get().map_or(glam::Vec2::ZERO, |(transform, ui)| {
+            ui.size() * transform.scale.x
+        })
     }
 
     fn process_input(state: &mut StateInner, target: Entity) -> Option<UiMenuAction> {
-        let keys = &mut state.keys;
+        let keys = state.focused_keys(target)?;
 
-        let up_pressed = keys.just_pressed(KeyCode::ArrowUp);
-        let down_pressed = keys.just_pressed(KeyCode::ArrowDown);
+        let up_pressed = keys.just_pressed(MENU_UP_KEY);
+        let down_pressed = keys.just_pressed(MENU_DOWN_KEY);
         let dir = down_pressed as i8 - up_pressed as i8;
 
-        let action = if keys.just_pressed(KeyCode::Enter) {
+        let action = if keys.just_pressed(MENU_SELECT_KEY) {
             Some(UiMenuAction::Select)
-        } else if keys.just_pressed(KeyCode::ArrowRight) {
+        } else if keys.just_pressed(MENU_FORWARD_KEY) {
             Some(UiMenuAction::Forward)
-        } else if keys.just_pressed(KeyCode::ArrowLeft) {
+        } else if keys.just_pressed(MENU_BACK_KEY) {
             Some(UiMenuAction::Back)
         } else {
             None
         };
 
-        let mut ui = state.world.get::<&mut Ui3d>(target).unwrap();
+        let mut ui = state.world.get::<&mut Ui3d>(target).ok()?;
 
+        let previous = ui.selected;
         let selected = ui.selected as i8 + dir;
         ui.selected = selected.clamp(0, ui.options.len() as i8 - 1) as u8;
 
+        let moved = ui.selected != previous;
+        drop(ui);
+
+        if moved {
+            state.events.emit(MenuEvent::Moved);
+        }
+        if let Some(UiMenuAction::Forward | UiMenuAction::Select) = action {
+            state.events.emit(MenuEvent::Confirmed);
+        }
+
         return action;
     }
 }
@@ -0,0 +1,318 @@
+//====================================================================
+
+use common::{PhysicalSize, Transform};
+use engine::{scene::Scene, tools::KeyCode, StateInner};
+use hecs::Entity;
+use renderer::pipelines::{texture_pipeline::Sprite, ui3d_pipeline::Ui3d};
+
+use crate::{
+    audio::{AudioEventRouter, MenuEvent},
+    controls::{self, Context, HelpOverlay},
+    data::GameData,
+    saves::{self, SaveMeta},
+    settings::Settings,
+};
+
+use super::overworld_scene::OverworldScene;
+
+//====================================================================
+
+/// Move the selection between slots, wrapping around at either end.
+pub const PREVIOUS_SLOT_KEY: KeyCode = KeyCode::ArrowUp;
+pub const NEXT_SLOT_KEY: KeyCode = KeyCode::ArrowDown;
+/// Loads the selected slot.
+pub const LOAD_SLOT_KEY: KeyCode = KeyCode::Enter;
+/// Deletes the selected slot.
+pub const DELETE_SLOT_KEY: KeyCode = KeyCode::Delete;
+/// Copies the selected slot into the first free one.
+pub const COPY_SLOT_KEY: KeyCode = KeyCode::KeyC;
+/// Exports the selected slot.
+pub const EXPORT_SLOT_KEY: KeyCode = KeyCode::KeyX;
+/// Imports a save into the selected slot.
+pub const IMPORT_SLOT_KEY: KeyCode = KeyCode::KeyI;
+/// Returns to the overworld without loading.
+pub const BACK_KEY: KeyCode = KeyCode::Escape;
+
+/// Written to the working directory when exporting on native, along with a
+/// base64 copy for pasting into the web build.
+pub const EXPORT_FILE: &str = "save_export.tbsave";
+pub const EXPORT_TEXT_FILE: &str = "save_export.txt";
+/// Read from the working directory when importing on native. Either the
+/// exported blob or its base64 text.
+pub const IMPORT_FILE: &str = "save_import.tbsave";
+
+/// World size of the thumbnail next to the slot list.
+const THUMBNAIL_SIZE: glam::Vec2 = glam::vec2(3.2, 1.8);
+
+/// Lists save slots with their details and a thumbnail of the selected one.
+pub struct LoadScene {
+    slots: Vec<Option<SaveMeta>>,
+    selected: usize,
+
+    menu: Entity,
+    thumbnail: Option<Entity>,
+    help: HelpOverlay,
+    audio: AudioEventRouter,
+}
+
+impl Scene for LoadScene {
+    fn new(state: &mut StateInner) -> Self {
+        let menu = state.world.spawn((Transform::default(), Ui3d::default()));
+
+        let mut scene = Self {
+            slots: saves::list(),
+            selected: saves::current_slot(state).unwrap_or(0),
+            menu,
+            thumbnail: None,
+            help: HelpOverlay::default(),
+            audio: AudioEventRouter::new(
+                &mut state.events,
+                &GameData::load_or_builtin(),
+                Settings::load().haptics,
+            ),
+        };
+        scene.refresh(state);
+
+        scene
+    }
+
+    fn resize(&mut self, _state: &mut StateInner, _new_size: PhysicalSize<u32>) {}
+
+    fn name(&self) -> &'static str {
+        "Load Game"
+    }
+
+    fn on_exit(&mut self, state: &mut StateInner) {
+        state.despawn(self.menu).ok();
+        if let Some(thumbnail) = self.thumbnail.take() {
+            state.despawn(thumbnail).ok();
+        }
+        self.help.close(state);
+
+        self.audio.play(&state.audio, &mut state.haptics);
+        self.audio.unsubscribe(&mut state.events);
+    }
+
+    fn update(&mut self, state: &mut StateInner) {
+        if state.keys.just_pressed(BACK_KEY) {
+            self.leave(state);
+            return;
+        }
+
+        if state.keys.just_pressed(PREVIOUS_SLOT_KEY) {
+            self.selected = self.selected.checked_sub(1).unwrap_or(self.slots.len() - 1);
+            state.events.emit(MenuEvent::Moved);
+            self.refresh(state);
+        }
+
+        if state.keys.just_pressed(NEXT_SLOT_KEY) {
+            self.selected = (self.selected + 1) % self.slots.len();
+            state.events.emit(MenuEvent::Moved);
+            self.refresh(state);
+        }
+
+        if state.keys.just_pressed(DELETE_SLOT_KEY) && self.slots[self.selected].is_some() {
+            saves::delete(state, self.selected);
+            self.reload(state);
+        }
+
+        if state.keys.just_pressed(COPY_SLOT_KEY) && self.slots[self.selected].is_some() {
+            match saves::first_free() {
+                Some(free) => {
+                    saves::copy(self.selected, free);
+                    self.reload(state);
+                }
+                None => log::warn!("No free slot to copy to"),
+            }
+        }
+
+        if state.keys.just_pressed(EXPORT_SLOT_KEY) && self.slots[self.selected].is_some() {
+            export_slot(self.selected);
+        }
+
+        if state.keys.just_pressed(IMPORT_SLOT_KEY) && import_slot(self.selected) {
+            self.reload(state);
+        }
+
+        if state.keys.just_pressed(LOAD_SLOT_KEY) {
+            if let Some(data) = saves::load(self.selected) {
+                log::info!("Loading slot {}", self.selected);
+                state.events.emit(MenuEvent::Confirmed);
+
+                saves::queue_load(state, self.selected, data);
+                self.leave(state);
+                return;
+            }
+        }
+
+        self.position_panels(state);
+        self.help.tick(state, &[Context::LoadGame, Context::Debug]);
+        self.audio.play(&state.audio, &mut state.haptics);
+    }
+}
+
+impl LoadScene {
+    fn reload(&mut self, state: &mut StateInner) {
+        self.slots = saves::list();
+        self.refresh(state);
+    }
+
+    // Rebuild the slot list and swap in the selected slot's thumbnail
+    fn refresh(&mut self, state: &mut StateInner) {
+        let mut options = vec![String::from("Load Game")];
+        options.extend(self.slots.iter().enumerate().map(|(slot, meta)| {
+            let name = match slot {
+                saves::AUTOSAVE_SLOT => String::from("Auto"),
+                slot => (slot + 1).to_string(),
+            };
+
+            match meta {
+                Some(meta) => format!(
+                    "{}: {} | {} | {}",
+                    name,
+                    meta.date(),
+                    meta.party,
+                    meta.playtime()
+                ),
+                None => format!("{}: Empty", name),
+            }
+        }));
+        options.push(
+            [
+                (LOAD_SLOT_KEY, "Load"),
+                (DELETE_SLOT_KEY, "Delete"),
+                (COPY_SLOT_KEY, "Copy"),
+                (EXPORT_SLOT_KEY, "Export"),
+                (IMPORT_SLOT_KEY, "Import"),
+                (BACK_KEY, "Back"),
+            ]
+            .iter()
+            .map(|(key, label)| format!("{} {}", controls::key_label(&[*key]), label))
+            .collect::<Vec<_>>()
+            .join("  "),
+        );
+
+        if let Ok(mut ui) = state.world.get::<&mut Ui3d>(self.menu) {
+            ui.options = options;
+            ui.selected = (self.selected + 1) as u8;
+        }
+
+        if let Some(thumbnail) = self.thumbnail.take() {
+            state.despawn(thumbnail).ok();
+        }
+
+        let texture = self.slots[self.selected].as_ref().and_then(|meta| {
+            let bytes = saves::thumbnail(self.selected)?;
+
+            // Timestamp in the label so an overwritten slot isn't cached
+            let label = format!("Save Thumbnail {} {}", self.selected, meta.timestamp);
+            state
+                .renderer
+                .load_texture(&label, &bytes)
+                .map_err(|e| log::warn!("Invalid thumbnail for slot {}: {}", self.selected, e))
+                .ok()
+        });
+
+        self.thumbnail = texture.map(|texture| {
+            state
+                .world
+                .spawn((Transform::default(), Sprite::new(texture, THUMBNAIL_SIZE)))
+        });
+
+        self.position_panels(state);
+    }
+
+    fn position_panels(&self, state: &mut StateInner) {
+        let camera = &state.renderer.camera.camera;
+        let center = camera.translation + camera.forward() * 8.;
+        let right = camera.rotation * glam::Vec3::X;
+        let rotation = camera.rotation;
+
+        if let Ok(mut transform) = state.world.get::<&mut Transform>(self.menu) {
+            transform.translation = center - right * 1.5;
+        }
+
+        if let Some(thumbnail) = self.thumbnail {
+            if let Ok(mut transform) = state.world.get::<&mut Transform>(thumbnail) {
+                transform.translation = center + right * 4.;
+                transform.rotation = rotation;
+            }
+        }
+    }
+
+    fn leave(&mut self, state: &mut StateInner) {
+        state.switch_scene::<OverworldScene>();
+    }
+}
+
+//====================================================================
+
+#[cfg(not(target_arch = "wasm32"))]
+fn export_slot(slot: usize) {
+    let result = saves::export(slot)
+        .map_err(|e| e.to_string())
+        .and_then(|blob| {
+            use base64::Engine;
+
+            let text = base64::engine::general_purpose::STANDARD.encode(&blob);
+
+            std::fs::write(EXPORT_FILE, blob)
+                .and_then(|_| std::fs::write(EXPORT_TEXT_FILE, text))
+                .map_err(|e| e.to_string())
+        });
+
+    match result {
+        Ok(()) => log::info!("Exported slot {} to '{}'", slot, EXPORT_FILE),
+        Err(e) => log::error!("Unable to export slot {}: {}", slot, e),
+    }
+}
+
+// Shown in a prompt so it can be copied
+#[cfg(target_arch = "wasm32")]
+fn export_slot(slot: usize) {
+    let text = match saves::export_string(slot) {
+        Ok(text) => text,
+        Err(e) => {
+            log::error!("Unable to export slot {}: {}", slot, e);
+            return;
+        }
+    };
+
+    if let Some(window) = web_sys::window() {
+        window
+            .prompt_with_message_and_default("Copy your save:", &text)
+            .ok();
+    }
+}
+
+// Returns true if the slot was replaced
+#[cfg(not(target_arch = "wasm32"))]
+fn import_slot(slot: usize) -> bool {
+    let blob = match std::fs::read(IMPORT_FILE) {
+        Ok(blob) => blob,
+        Err(e) => {
+            log::error!("Unable to read '{}': {}", IMPORT_FILE, e);
+            return false;
+        }
+    };
+
+    saves::import(slot, &blob)
+        .map_err(|e| log::error!("{}", e))
+        .is_ok()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn import_slot(slot: usize) -> bool {
+    let text = web_sys::window()
+        .and_then(|window| window.prompt_with_message("Paste a save:").ok())
+        .flatten();
+
+    match text {
+        Some(text) if !text.trim().is_empty() => saves::import(slot, text.as_bytes())
+            .map_err(|e| log::error!("{}", e))
+            .is_ok(),
+        _ => false,
+    }
+}
+
+//====================================================================
@@ -1,6 +1,8 @@
 //====================================================================
 
 pub mod battle_scene;
+pub mod load_scene;
+pub mod overworld_scene;
 
 //====================================================================
 
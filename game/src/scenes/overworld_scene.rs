@@ -0,0 +1,246 @@
+//====================================================================
+
+use common::{PhysicalSize, Transform};
+use engine::{scene::Scene, tools::KeyCode, StateInner};
+use glam::Vec3Swizzles;
+use hecs::Entity;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use renderer::pipelines::ui3d_pipeline::Ui3d;
+
+use crate::{
+    audio::{AudioEventRouter, MenuEvent},
+    controls::{self, Context, HelpOverlay},
+    data::{GameData, Side},
+    encounters,
+    party::Party,
+    saves::{self, OverworldSave, PendingSave, SaveData, SaveMeta},
+    settings::Settings,
+};
+
+use super::{battle_scene::BattleScene, load_scene::LoadScene};
+
+//====================================================================
+
+/// Starts a battle against a random level 1 encounter.
+pub const QUICK_BATTLE_KEY: KeyCode = KeyCode::Enter;
+/// Saves to the slot last used, or the first free one.
+pub const SAVE_KEY: KeyCode = KeyCode::F5;
+/// Opens the list of save slots.
+pub const LOAD_KEY: KeyCode = KeyCode::F6;
+
+/// Distance travelled between encounter rolls.
+const ENCOUNTER_STEP: f32 = 100.;
+/// Percent chance of an encounter each step.
+const ENCOUNTER_CHANCE: u32 = 20;
+/// Encounters gain a level for each of these the camera is from the origin.
+const LEVEL_DISTANCE: f32 = 250.;
+
+pub struct OverworldScene {
+    menu: Entity,
+    data: GameData,
+    rng: StdRng,
+
+    last_position: glam::Vec3,
+    travelled: f32,
+
+    pending_save: Option<PendingSave>,
+    help: HelpOverlay,
+    mouse_sensitivity: f32,
+    audio: AudioEventRouter,
+}
+
+impl Scene for OverworldScene {
+    fn new(state: &mut StateInner) -> Self {
+        crate::scenery::spawn_scenery(state, crate::scenery::Ground::Grid);
+
+        let menu = state.world.spawn((
+            Transform::default(),
+            Ui3d {
+                options: vec![
+                    format!("{} Quick Battle", controls::key_label(&[QUICK_BATTLE_KEY])),
+                    format!("{} Save", controls::key_label(&[SAVE_KEY])),
+                    format!("{} Load", controls::key_label(&[LOAD_KEY])),
+                ],
+                ..Default::default()
+            },
+        ));
+
+        let data = GameData::load_or_builtin();
+        let settings = Settings::load();
+
+        let mut travelled = 0.;
+        if let Some(save) = saves::take_loaded(state) {
+            state.renderer.camera.camera.translation = save.overworld.position;
+            travelled = save.overworld.travelled;
+            saves::load_entities(state, &save);
+            state.resources.insert(save.party);
+        }
+
+        let mut scene = Self {
+            menu,
+            audio: AudioEventRouter::new(&mut state.events, &data, settings.haptics),
+            data,
+            rng: StdRng::from_entropy(),
+            last_position: state.renderer.camera.camera.translation,
+            travelled,
+            pending_save: None,
+            help: HelpOverlay::default(),
+            mouse_sensitivity: settings.interface.mouse_sensitivity,
+        };
+
+        if saves::take_autosave_request(state) {
+            let data = scene.save_data(state);
+            scene.pending_save = Some(PendingSave::new(state, saves::AUTOSAVE_SLOT, data));
+        }
+
+        scene
+    }
+
+    fn resize(&mut self, _state: &mut StateInner, _new_size: PhysicalSize<u32>) {}
+
+    fn name(&self) -> &'static str {
+        "Overworld"
+    }
+
+    fn on_exit(&mut self, state: &mut StateInner) {
+        state.despawn(self.menu).ok();
+        self.help.close(state);
+        crate::scenery::despawn_scenery(state);
+
+        // Menu sounds from the last frame still play
+        self.audio.play(&state.audio, &mut state.haptics);
+        self.audio.unsubscribe(&mut state.events);
+    }
+
+    fn quit(&mut self, state: &mut StateInner) {
+        // No time to wait for a thumbnail
+        let data = self.save_data(state);
+        PendingSave::new(state, saves::AUTOSAVE_SLOT, data).finish(state, None);
+    }
+
+    fn update(&mut self, state: &mut StateInner) {
+        crate::camera::move_camera(state);
+        crate::camera::mouse_look(state, self.mouse_sensitivity);
+        crate::scenery::tick_ground(state);
+
+        let camera = &state.renderer.camera.camera;
+        let position = camera.translation;
+        let menu_position = position + camera.forward() * 8.;
+
+        if let Ok(mut transform) = state.world.get::<&mut Transform>(self.menu) {
+            transform.translation = menu_position;
+        }
+
+        self.help.tick(
+            state,
+            &[Context::Overworld, Context::Camera, Context::Debug],
+        );
+
+        if let Some(pending_save) = &mut self.pending_save {
+            if pending_save.tick(state) {
+                self.pending_save = None;
+            }
+        }
+
+        if state.keys.just_pressed(SAVE_KEY) && self.pending_save.is_none() {
+            state.events.emit(MenuEvent::Confirmed);
+            self.save(state);
+        }
+
+        if state.keys.just_pressed(LOAD_KEY) && self.pending_save.is_none() {
+            state.events.emit(MenuEvent::Confirmed);
+            state.switch_scene::<LoadScene>();
+            return;
+        }
+
+        if state.keys.just_pressed(QUICK_BATTLE_KEY) {
+            state.events.emit(MenuEvent::Confirmed);
+            let seed = self.rng.gen();
+            self.start_battle(state, 1, seed);
+            return;
+        }
+
+        self.audio.play(&state.audio, &mut state.haptics);
+
+        self.travelled += (position - self.last_position).xz().length();
+        self.last_position = position;
+
+        while self.travelled >= ENCOUNTER_STEP {
+            self.travelled -= ENCOUNTER_STEP;
+
+            if self.rng.gen_range(0..100) < ENCOUNTER_CHANCE {
+                let level = 1 + (position.xz().length() / LEVEL_DISTANCE) as u32;
+                let seed = self.rng.gen();
+
+                self.start_battle(state, level, seed);
+                return;
+            }
+        }
+    }
+}
+
+impl OverworldScene {
+    fn start_battle(&mut self, state: &mut StateInner, level: u32, seed: u64) {
+        let encounter = match encounters::generate_any(&self.data, level, seed) {
+            Some(encounter) => encounter,
+            None => {
+                log::warn!("No encounters defined - unable to start a battle");
+                return;
+            }
+        };
+
+        log::info!(
+            "Encounter: '{}' level {} (seed {})",
+            encounter.pool,
+            encounter.level,
+            encounter.seed
+        );
+
+        encounters::queue_encounter(&mut state.resources, encounter);
+
+        state.switch_scene_with_loading::<BattleScene>();
+    }
+
+    fn save(&mut self, state: &mut StateInner) {
+        let slot = saves::current_slot(state)
+            .or_else(saves::first_free)
+            .unwrap_or(0);
+
+        let data = self.save_data(state);
+        self.pending_save = Some(PendingSave::new(state, slot, data));
+    }
+
+    fn save_data(&self, state: &StateInner) -> SaveData {
+        let party = state.resources.get::<Party>().cloned().unwrap_or_default();
+
+        let names = self
+            .data
+            .characters
+            .iter()
+            .filter(|character| character.side == Side::Friendly)
+            .map(|character| character.name.as_str())
+            .collect::<Vec<_>>();
+
+        let mut data = SaveData {
+            meta: SaveMeta {
+                party: format!(
+                    "Lv {} {}",
+                    party.highest_level(names.iter().copied()),
+                    names.join(", ")
+                ),
+                ..Default::default()
+            },
+            overworld: OverworldSave {
+                position: state.renderer.camera.camera.translation,
+                travelled: self.travelled,
+            },
+            party,
+            ..Default::default()
+        };
+
+        saves::save_entities(state, &mut data);
+        data
+    }
+}
+
+//====================================================================
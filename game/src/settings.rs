@@ -0,0 +1,207 @@
+//====================================================================
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    combat::{CpuProfile, Difficulty},
+    hints::Hint,
+};
+
+//====================================================================
+
+/// Read from and written to the working directory on native.
+pub const SETTINGS_FILE: &str = "settings.toml";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Language text is shown in, as named in the data's `[locale]` tables.
+    /// Empty to show text as it's named in the data.
+    pub language: String,
+    pub packs: PackSettings,
+    pub difficulty: DifficultySettings,
+    pub interface: InterfaceSettings,
+    pub haptics: HapticsSettings,
+    pub telemetry: TelemetrySettings,
+    pub hints: HintSettings,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PackSettings {
+    /// Content packs to load, in order. Later packs override earlier ones.
+    /// Packs not listed here are ignored.
+    pub enabled: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DifficultySettings {
+    pub level: Difficulty,
+    /// Overrides whether the difficulty times turns.
+    pub turn_timer: Option<bool>,
+    /// Overrides how many turns ahead cpu characters look, 0 to not look
+    /// ahead at all.
+    pub lookahead: Option<u32>,
+}
+
+impl DifficultySettings {
+    #[inline]
+    pub fn turn_timer(&self) -> bool {
+        self.turn_timer.unwrap_or(self.level.turn_timer())
+    }
+
+    #[inline]
+    pub fn lookahead(&self) -> u32 {
+        self.lookahead.unwrap_or(self.level.lookahead())
+    }
+
+    /// How cpu characters pick actions at this difficulty.
+    #[inline]
+    pub fn cpu_profile(&self) -> CpuProfile {
+        CpuProfile {
+            aggressiveness: self.level.aggressiveness(),
+            lookahead: self.lookahead(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InterfaceSettings {
+    /// Announce each round and turn with a banner.
+    pub banners: bool,
+    /// Slow down and push in on the finishing blow of a battle.
+    pub kill_cam: bool,
+    /// Pan the battle camera while the cursor is at the edge of the window.
+    pub edge_scroll: bool,
+    /// Stop the game while its window is in the background.
+    pub pause_on_focus_loss: bool,
+    /// Multiplier on how far the camera turns when looking with the mouse.
+    pub mouse_sensitivity: f32,
+}
+
+impl Default for InterfaceSettings {
+    fn default() -> Self {
+        Self {
+            banners: true,
+            kill_cam: true,
+            edge_scroll: false,
+            pause_on_focus_loss: false,
+            mouse_sensitivity: 1.,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HapticsSettings {
+    /// Rumble supported gamepads on hits, crits and menu confirms.
+    pub enabled: bool,
+    /// Multiplier on the strength of every rumble, from 0 to 1.
+    pub intensity: f32,
+}
+
+impl Default for HapticsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            intensity: 1.,
+        }
+    }
+}
+
+impl HapticsSettings {
+    /// Multiplier to apply to rumble patterns, or None while rumble is off.
+    #[inline]
+    pub fn scale(&self) -> Option<f32> {
+        let intensity = self.intensity.clamp(0., 1.);
+
+        match self.enabled && intensity > 0. {
+            true => Some(intensity),
+            false => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetrySettings {
+    /// Write a record of each battle to [`crate::telemetry::TELEMETRY_DIR`]
+    /// for balancing.
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HintSettings {
+    /// Hints the player has dismissed, by [`Hint::id`].
+    pub seen: BTreeSet<String>,
+}
+
+impl HintSettings {
+    #[inline]
+    pub(crate) fn has_seen(&self, hint: Hint) -> bool {
+        self.seen.contains(hint.id())
+    }
+}
+
+impl Settings {
+    /// Load [`SETTINGS_FILE`], using defaults if it's missing or invalid.
+    pub fn load() -> Self {
+        Self::read().unwrap_or_default()
+    }
+
+    /// Change the settings in [`SETTINGS_FILE`] and write them back. The file
+    /// is read again first so changes made to it while playing are kept, and
+    /// left alone if it's invalid so it can be fixed by hand.
+    pub fn update(change: impl FnOnce(&mut Self)) {
+        let mut settings = match Self::read() {
+            Some(settings) => settings,
+            None => return,
+        };
+
+        change(&mut settings);
+        settings.save();
+    }
+
+    // Settings in the file, default if there isn't one or None if it's invalid
+    fn read() -> Option<Self> {
+        #[cfg(not(target_arch = "wasm32"))]
+        match std::fs::read_to_string(SETTINGS_FILE) {
+            Ok(contents) => match toml::from_str(&contents) {
+                Ok(settings) => return Some(settings),
+                Err(e) => {
+                    log::error!("Invalid settings in '{}': {}", SETTINGS_FILE, e);
+                    return None;
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                log::error!("Unable to read '{}': {}", SETTINGS_FILE, e);
+                return None;
+            }
+        }
+
+        Some(Self::default())
+    }
+
+    fn save(&self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let result = toml::to_string(self)
+                .map_err(|e| e.to_string())
+                .and_then(|contents| {
+                    std::fs::write(SETTINGS_FILE, contents).map_err(|e| e.to_string())
+                });
+
+            if let Err(e) = result {
+                log::error!("Unable to save settings to '{}': {}", SETTINGS_FILE, e);
+            }
+        }
+    }
+}
+
+//====================================================================
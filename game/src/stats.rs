@@ -0,0 +1,229 @@
+//====================================================================
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use common::Transform;
+use engine::{events::EventBus, tools::KeyCode, StateInner};
+use hecs::Entity;
+use renderer::pipelines::ui3d_pipeline::Ui3d;
+use serde::{Deserialize, Serialize};
+
+use crate::data::AchievementDef;
+
+//====================================================================
+
+/// Read from and written to the working directory on native.
+pub const STATS_FILE: &str = "stats.toml";
+
+/// Names of tracked statistics, as used by achievement definitions.
+pub mod stat {
+    pub const BATTLES_WON: &str = "battles_won";
+    pub const DAMAGE_DEALT: &str = "damage_dealt";
+    pub const CRITS: &str = "crits";
+    pub const FLAWLESS_VICTORIES: &str = "flawless_victories";
+    pub const ROUNDS_PLAYED: &str = "rounds_played";
+    pub const TURNS_TAKEN: &str = "turns_taken";
+
+    pub const ALL: [&str; 6] = [
+        BATTLES_WON,
+        DAMAGE_DEALT,
+        CRITS,
+        FLAWLESS_VICTORIES,
+        ROUNDS_PLAYED,
+        TURNS_TAKEN,
+    ];
+}
+
+/// Emitted through the event bus when an achievement is unlocked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AchievementUnlocked {
+    pub id: String,
+    pub name: String,
+}
+
+//====================================================================
+
+/// Persisted statistics and unlocked achievements.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatsStore {
+    pub values: BTreeMap<String, u64>,
+    pub unlocked: BTreeSet<String>,
+}
+
+impl StatsStore {
+    /// Load [`STATS_FILE`], starting fresh if it's missing or invalid.
+    pub fn load() -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        match std::fs::read_to_string(STATS_FILE) {
+            Ok(contents) => match toml::from_str(&contents) {
+                Ok(store) => return store,
+                Err(e) => log::error!("Invalid stats in '{}': {}", STATS_FILE, e),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => log::error!("Unable to read '{}': {}", STATS_FILE, e),
+        }
+
+        Self::default()
+    }
+
+    pub fn save(&self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let result = toml::to_string(self)
+                .map_err(|e| e.to_string())
+                .and_then(|contents| {
+                    std::fs::write(STATS_FILE, contents).map_err(|e| e.to_string())
+                });
+
+            if let Err(e) = result {
+                log::error!("Unable to save stats to '{}': {}", STATS_FILE, e);
+            }
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, stat: &str) -> u64 {
+        self.values.get(stat).copied().unwrap_or(0)
+    }
+}
+
+//====================================================================
+
+/// Tracks statistics and unlocks achievements as they pass their threshold.
+/// Changes are kept in memory until [`Stats::save`].
+pub struct Stats {
+    store: StatsStore,
+    achievements: Vec<AchievementDef>,
+    /// Changed since it was last saved.
+    dirty: bool,
+}
+
+impl Stats {
+    pub fn new(achievements: Vec<AchievementDef>) -> Self {
+        Self {
+            store: StatsStore::load(),
+            achievements,
+            dirty: false,
+        }
+    }
+
+    #[inline]
+    pub fn store(&self) -> &StatsStore {
+        &self.store
+    }
+
+    /// Write any changes to [`STATS_FILE`].
+    pub fn save(&mut self) {
+        if self.dirty {
+            self.store.save();
+            self.dirty = false;
+        }
+    }
+
+    /// Increase a stat, unlocking any achievements it completes.
+    pub fn add(&mut self, events: &mut EventBus, stat: &str, amount: u64) {
+        let value = self.store.values.entry(stat.to_string()).or_default();
+        *value = value.saturating_add(amount);
+        let value = *value;
+        self.dirty = true;
+
+        self.achievements
+            .iter()
+            .filter(|achievement| {
+                achievement.stat == stat
+                    && value >= achievement.threshold
+                    && !self.store.unlocked.contains(&achievement.id)
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .for_each(|achievement| {
+                log::info!("Achievement unlocked: {}", achievement.name);
+                self.store.unlocked.insert(achievement.id.clone());
+
+                events.emit(AchievementUnlocked {
+                    id: achievement.id.clone(),
+                    name: achievement.name.clone(),
+                });
+            });
+    }
+
+    /// One line per achievement for the achievements screen.
+    pub fn listing(&self) -> Vec<String> {
+        self.achievements
+            .iter()
+            .map(
+                |achievement| match self.store.unlocked.contains(&achievement.id) {
+                    true => format!("[x] {} - {}", achievement.name, achievement.description),
+                    false => format!(
+                        "[ ] {} - {} ({}/{})",
+                        achievement.name,
+                        achievement.description,
+                        self.store.get(&achievement.stat).min(achievement.threshold),
+                        achievement.threshold
+                    ),
+                },
+            )
+            .collect()
+    }
+}
+
+//====================================================================
+
+/// Toggles the achievements screen.
+pub const ACHIEVEMENTS_KEY: KeyCode = KeyCode::Tab;
+/// Name the achievements screen is opened under in
+/// [`engine::modal::Modals`].
+pub const ACHIEVEMENTS_MODAL: &str = "achievements";
+
+/// In-game list of achievements and progress towards them. Pauses the game
+/// while it's open.
+#[derive(Default)]
+pub struct AchievementsScreen {
+    entity: Option<Entity>,
+}
+
+impl AchievementsScreen {
+    pub fn close(&mut self, state: &mut StateInner) {
+        if let Some(entity) = self.entity.take() {
+            state.despawn(entity).ok();
+            state.modals.close(ACHIEVEMENTS_MODAL);
+        }
+    }
+
+    pub fn tick(&mut self, state: &mut StateInner, stats: &Stats) {
+        if !state.keys.just_pressed(ACHIEVEMENTS_KEY) {
+            return;
+        }
+
+        match self.entity.take() {
+            Some(entity) => {
+                state.despawn(entity).ok();
+                state.modals.close(ACHIEVEMENTS_MODAL);
+            }
+            None => {
+                state.modals.open(ACHIEVEMENTS_MODAL);
+
+                let camera = &state.renderer.camera.camera;
+                let position = camera.translation + camera.forward() * 8.;
+
+                let mut options = vec![format!(
+                    "Achievements ({}/{})",
+                    stats.store.unlocked.len(),
+                    stats.achievements.len()
+                )];
+                options.extend(stats.listing());
+
+                self.entity = Some(state.world.spawn((
+                    Transform::from_translation(position),
+                    Ui3d {
+                        options,
+                        ..Default::default()
+                    },
+                )));
+            }
+        }
+    }
+}
+
+//====================================================================
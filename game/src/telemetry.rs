@@ -0,0 +1,455 @@
+//====================================================================
+
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    battle::{Battle, Step},
+    characters::actions::ActionRepo,
+    combat::{CpuProfile, Difficulty},
+    data::GameData,
+    encounters::{self, Encounter},
+    party::Party,
+    scenes::battle_scene::auto_battle::AUTO_BATTLE_PROFILE,
+    settings::DifficultySettings,
+};
+
+//====================================================================
+
+/// Directory battle records are written to, relative to the working
+/// directory.
+pub const TELEMETRY_DIR: &str = "telemetry";
+
+/// Recorded for battles against the default enemies rather than an
+/// encounter.
+pub const DEFAULT_ENCOUNTER: &str = "default";
+
+/// Rounds a simulated battle can go on for before it's called a draw.
+pub const MAX_SIMULATED_ROUNDS: u32 = 100;
+
+//====================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    Victory,
+    Defeat,
+    /// Left before either side was defeated.
+    Retreat,
+    /// Simulated battle that ran out of rounds.
+    Draw,
+}
+
+/// One action taken in a battle.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionRecord {
+    pub round: u32,
+    pub caster: String,
+    /// Whether the caster is on the player's side.
+    pub friendly: bool,
+    pub action: String,
+    pub target: Option<String>,
+    pub damage: u32,
+    pub missed: bool,
+    pub critical: bool,
+}
+
+/// What happened in a battle, for balancing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BattleRecord {
+    /// Pool the encounter was generated from, or [`DEFAULT_ENCOUNTER`].
+    pub encounter: String,
+    pub level: u32,
+    /// Seed the encounter was generated from.
+    pub seed: Option<u64>,
+    pub difficulty: Difficulty,
+    pub simulated: bool,
+    pub rounds: u32,
+    pub turns: u32,
+    pub actions: Vec<ActionRecord>,
+    pub outcome: Outcome,
+}
+
+impl BattleRecord {
+    /// Empty record, counted as a retreat until the battle says otherwise.
+    pub fn new(encounter: Option<&Encounter>, difficulty: Difficulty, simulated: bool) -> Self {
+        Self {
+            encounter: encounter
+                .map_or(DEFAULT_ENCOUNTER.into(), |encounter| encounter.pool.clone()),
+            level: encounter.map_or(1, |encounter| encounter.level),
+            seed: encounter.map(|encounter| encounter.seed),
+            difficulty,
+            simulated,
+            rounds: 0,
+            turns: 0,
+            actions: Vec::new(),
+            outcome: Outcome::Retreat,
+        }
+    }
+
+    /// Total damage dealt by one side.
+    pub fn damage_dealt(&self, friendly: bool) -> u64 {
+        self.actions
+            .iter()
+            .filter(|action| action.friendly == friendly)
+            .map(|action| action.damage as u64)
+            .sum()
+    }
+}
+
+//====================================================================
+
+/// Write `record` to its own file under [`TELEMETRY_DIR`].
+pub fn write_record(record: &BattleRecord) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(TELEMETRY_DIR)?;
+
+    let timestamp = web_time::SystemTime::now()
+        .duration_since(web_time::SystemTime::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis());
+    let json = serde_json::to_string_pretty(record).map_err(std::io::Error::other)?;
+
+    // Simulated runs can finish many records within the same millisecond, so
+    // each takes the next number free
+    for count in 0.. {
+        let path = Path::new(TELEMETRY_DIR).join(format!("battle-{}-{}.json", timestamp, count));
+
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(mut file) => {
+                file.write_all(json.as_bytes())?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    unreachable!()
+}
+
+/// Every record in `dir`. Files that aren't records are skipped.
+pub fn read_records(dir: impl AsRef<Path>) -> Vec<BattleRecord> {
+    let entries = match std::fs::read_dir(dir.as_ref()) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::warn!("Unable to read '{}': {}", dir.as_ref().display(), e);
+            return Vec::new();
+        }
+    };
+
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .filter_map(|path| {
+            let contents = std::fs::read_to_string(&path).ok()?;
+
+            match serde_json::from_str(&contents) {
+                Ok(record) => Some(record),
+                Err(e) => {
+                    log::warn!("Skipping '{}': {}", path.display(), e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+//====================================================================
+
+/// Totals for every battle fought against one encounter.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EncounterSummary {
+    pub battles: u32,
+    pub victories: u32,
+    pub defeats: u32,
+    pub turns: u64,
+    pub damage_dealt: u64,
+    pub damage_taken: u64,
+}
+
+impl EncounterSummary {
+    /// Percent of battles won.
+    pub fn win_rate(&self) -> f32 {
+        match self.battles {
+            0 => 0.,
+            battles => self.victories as f32 * 100. / battles as f32,
+        }
+    }
+
+    #[inline]
+    pub fn average_turns(&self) -> f32 {
+        self.turns as f32 / self.battles.max(1) as f32
+    }
+}
+
+/// Summarize records by encounter, in name order.
+pub fn summarize(records: &[BattleRecord]) -> BTreeMap<String, EncounterSummary> {
+    let mut summaries = BTreeMap::<String, EncounterSummary>::new();
+
+    records.iter().for_each(|record| {
+        let summary = summaries.entry(record.encounter.clone()).or_default();
+
+        summary.battles += 1;
+        match record.outcome {
+            Outcome::Victory => summary.victories += 1,
+            Outcome::Defeat => summary.defeats += 1,
+            Outcome::Retreat | Outcome::Draw => {}
+        }
+        summary.turns += record.turns as u64;
+        summary.damage_dealt += record.damage_dealt(true);
+        summary.damage_taken += record.damage_dealt(false);
+    });
+
+    summaries
+}
+
+/// Print a table of win rates and averages per encounter.
+pub fn print_summary(summaries: &BTreeMap<String, EncounterSummary>) {
+    println!(
+        "{:<20} {:>8} {:>8} {:>10} {:>10} {:>10}",
+        "Encounter", "Battles", "Win %", "Avg turns", "Avg dealt", "Avg taken"
+    );
+
+    summaries.iter().for_each(|(encounter, summary)| {
+        let battles = summary.battles.max(1) as f32;
+
+        println!(
+            "{:<20} {:>8} {:>8.1} {:>10.1} {:>10.1} {:>10.1}",
+            encounter,
+            summary.battles,
+            summary.win_rate(),
+            summary.average_turns(),
+            summary.damage_dealt as f32 / battles,
+            summary.damage_taken as f32 / battles,
+        );
+    });
+}
+
+//====================================================================
+
+/// Fight `encounter` (or the default enemies) without a scene, with every
+/// character controlled by the cpu and the party at level 1. Plays the same
+/// battle as the battle scene, boss phases included, so the same seed always
+/// plays out the same way.
+pub fn simulate(
+    data: &GameData,
+    encounter: Option<&Encounter>,
+    difficulty: DifficultySettings,
+    seed: u64,
+) -> BattleRecord {
+    let actions = ActionRepo::from_actions(data.actions.clone());
+    let mut battle = encounters::start_battle(
+        data,
+        &actions,
+        &Party::default(),
+        encounter,
+        difficulty.level,
+        seed,
+    );
+    let mut record = BattleRecord::new(encounter, difficulty.level, true);
+
+    play_battle(&mut battle, &actions, difficulty.cpu_profile(), &mut record);
+    record
+}
+
+/// Play `battle` to the end as the battle scene would with auto battle on.
+/// Enemies pick their actions with `profile`, and the player's characters as
+/// they do while auto battling. Called a draw after [`MAX_SIMULATED_ROUNDS`].
+pub fn play_battle(
+    battle: &mut Battle,
+    actions: &ActionRepo,
+    profile: CpuProfile,
+    record: &mut BattleRecord,
+) {
+    loop {
+        match battle.advance() {
+            Step::RoundStarted { round } if round > MAX_SIMULATED_ROUNDS => {
+                record.outcome = Outcome::Draw;
+                return;
+            }
+            Step::RoundStarted { round } => record.rounds = round,
+            Step::TurnStarted { character, .. } => {
+                record.turns += 1;
+                take_turn(battle, actions, character, profile, record);
+            }
+            Step::Ended { won } => {
+                record.outcome = match won {
+                    true => Outcome::Victory,
+                    false => Outcome::Defeat,
+                };
+                return;
+            }
+        }
+    }
+}
+
+// Characters defeated by their effects at the start of their turn don't get
+// to act
+fn take_turn(
+    battle: &mut Battle,
+    actions: &ActionRepo,
+    caster: usize,
+    profile: CpuProfile,
+    record: &mut BattleRecord,
+) {
+    if battle.current() != Some(caster) {
+        return;
+    }
+
+    let profile = match battle
+        .character(caster)
+        .is_some_and(|character| character.player_controlled)
+    {
+        true => AUTO_BATTLE_PROFILE,
+        false => profile,
+    };
+
+    let (action, target) = match battle.choose_cpu_action(actions, profile) {
+        Some(chosen) => chosen,
+        None => return,
+    };
+
+    let acted = match battle.act(actions, caster, action, target) {
+        Ok(acted) => acted,
+        Err(e) => {
+            log::warn!("Cpu picked an action it can't use: {}", e);
+            return;
+        }
+    };
+
+    let name = |index: usize| {
+        battle
+            .character(index)
+            .map_or(String::new(), |character| character.name.clone())
+    };
+
+    record.actions.push(ActionRecord {
+        round: battle.round(),
+        caster: name(caster),
+        friendly: battle.is_friendly(caster).unwrap_or(false),
+        action: actions
+            .get_action(&action)
+            .map_or(String::new(), |action| action.name.clone()),
+        target: target.map(name),
+        damage: acted.resolved.damage,
+        missed: acted.resolved.missed,
+        critical: acted.resolved.critical,
+    });
+}
+
+/// Fight a single battle against the encounter pool named `encounter` (or
+/// [`DEFAULT_ENCOUNTER`]) generated from `seed`, printing every action and
+/// the outcome. Runs the same battle as the battle scene, boss phases
+/// included, without a window or renderer. Returns false if there's no such
+/// encounter.
+pub fn run_headless_battle(
+    data: &GameData,
+    encounter: &str,
+    seed: u64,
+    level: u32,
+    difficulty: DifficultySettings,
+) -> bool {
+    let generated = match encounter {
+        DEFAULT_ENCOUNTER => None,
+        name => match data.encounters.iter().find(|pool| pool.name == name) {
+            Some(pool) => Some(encounters::generate(data, pool, level, seed)),
+            None => {
+                println!("No encounter named '{}'", name);
+                return false;
+            }
+        },
+    };
+
+    println!(
+        "Battle against '{}' at level {} on {:?} with seed {}",
+        encounter, level, difficulty.level, seed
+    );
+
+    let record = simulate(data, generated.as_ref(), difficulty, seed);
+    print_record(&record);
+
+    true
+}
+
+/// Print each action in `record` followed by the outcome.
+pub fn print_record(record: &BattleRecord) {
+    record.actions.iter().for_each(|action| {
+        let target = action
+            .target
+            .as_ref()
+            .map_or(String::new(), |target| format!(" on {}", target));
+
+        let result = match (action.missed, action.critical, action.damage) {
+            (true, _, _) => " but misses".into(),
+            (false, true, damage) => format!(" for {} critical damage", damage),
+            (false, false, 0) => String::new(),
+            (false, false, damage) => format!(" for {} damage", damage),
+        };
+
+        println!(
+            "Round {}: {} uses {}{}{}",
+            action.round, action.caster, action.action, target, result
+        );
+    });
+
+    println!(
+        "{:?} after {} rounds and {} turns",
+        record.outcome, record.rounds, record.turns
+    );
+}
+
+/// Simulate `runs` battles against every encounter in `data` at `level`,
+/// plus the default enemies, then print a summary. Records are written to
+/// [`TELEMETRY_DIR`] when `write` is set.
+pub fn run_simulations(
+    data: &GameData,
+    runs: u32,
+    level: u32,
+    difficulty: DifficultySettings,
+    write: bool,
+) {
+    println!(
+        "Simulating {} battles per encounter at level {} on {:?}",
+        runs, level, difficulty.level
+    );
+
+    let records = (0..runs as u64)
+        .flat_map(|seed| {
+            let generated = data
+                .encounters
+                .iter()
+                .map(|pool| encounters::generate(data, pool, level, seed))
+                .collect::<Vec<_>>();
+
+            std::iter::once(simulate(data, None, difficulty, seed))
+                .chain(
+                    generated
+                        .into_iter()
+                        .map(move |encounter| simulate(data, Some(&encounter), difficulty, seed)),
+                )
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    if write {
+        records.iter().for_each(|record| {
+            if let Err(e) = write_record(record) {
+                log::error!("Unable to write battle record: {}", e);
+            }
+        });
+    }
+
+    print_summary(&summarize(&records));
+}
+
+//====================================================================
@@ -0,0 +1,141 @@
+//====================================================================
+
+use engine::{audio::Audio, events::EventBus, haptics::Haptics};
+use game::{
+    audio::{sound_event, AudioEventRouter, MenuEvent},
+    data::GameData,
+    settings::HapticsSettings,
+};
+
+//====================================================================
+
+// Mono 16 bit wav holding `samples`
+fn wav(samples: &[i16], sample_rate: u32) -> Vec<u8> {
+    let data = (samples.len() * 2) as u32;
+
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16_u32.to_le_bytes());
+    bytes.extend_from_slice(&1_u16.to_le_bytes());
+    bytes.extend_from_slice(&1_u16.to_le_bytes());
+    bytes.extend_from_slice(&sample_rate.to_le_bytes());
+    bytes.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    bytes.extend_from_slice(&2_u16.to_le_bytes());
+    bytes.extend_from_slice(&16_u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data.to_le_bytes());
+    samples
+        .iter()
+        .for_each(|sample| bytes.extend_from_slice(&sample.to_le_bytes()));
+
+    bytes
+}
+
+fn data_with_sounds() -> GameData {
+    let mut data = GameData::builtin();
+    data.sounds.iter().for_each(|def| {
+        data.audio
+            .insert(def.sound.clone(), wav(&[16_384, 16_384], 8_000));
+    });
+    data
+}
+
+//====================================================================
+
+#[test]
+fn menu_events_queue_their_sound() {
+    let data = data_with_sounds();
+    let mut events = EventBus::default();
+    let mut router = AudioEventRouter::new(&mut events, &data, HapticsSettings::default());
+
+    events.emit(MenuEvent::Moved);
+    events.emit(MenuEvent::Confirmed);
+
+    let cues = router.take_sounds();
+    let names = cues.iter().map(|cue| cue.event).collect::<Vec<_>>();
+    assert_eq!(names, [sound_event::MENU_MOVE, sound_event::MENU_CONFIRM]);
+    assert_eq!(cues[0].volume, 0.6);
+
+    assert!(router.take_sounds().is_empty());
+}
+
+#[test]
+fn rumble_is_scaled_and_can_be_turned_off() {
+    let data = GameData::builtin();
+    let mut events = EventBus::default();
+
+    let haptics = HapticsSettings {
+        enabled: true,
+        intensity: 0.5,
+    };
+    let mut router = AudioEventRouter::new(&mut events, &data, haptics);
+
+    events.emit(MenuEvent::Confirmed);
+    let rumbles = router.take_rumbles();
+    assert_eq!(rumbles.len(), 1);
+    assert_eq!(rumbles[0].pulses[0].weak, 0.15);
+
+    router.unsubscribe(&mut events);
+
+    let haptics = HapticsSettings {
+        enabled: false,
+        ..haptics
+    };
+    let mut router = AudioEventRouter::new(&mut events, &data, haptics);
+
+    events.emit(MenuEvent::Confirmed);
+    assert!(router.take_rumbles().is_empty());
+    assert_eq!(router.take_sounds().len(), 1);
+}
+
+#[test]
+fn cues_play_through_the_mixer() {
+    let data = data_with_sounds();
+    let mut events = EventBus::default();
+    let mut router = AudioEventRouter::new(&mut events, &data, HapticsSettings::default());
+    let audio = Audio::without_device(1, 8_000);
+
+    events.emit(MenuEvent::Confirmed);
+    router.play(&audio, &mut Haptics::disabled());
+
+    let mut out = [0.; 3];
+    audio.render(&mut out);
+    assert_eq!(out, [0.5, 0.5, 0.]);
+
+    // Played cues aren't played again
+    router.play(&audio, &mut Haptics::disabled());
+    audio.render(&mut out);
+    assert_eq!(out, [0.; 3]);
+}
+
+#[test]
+fn missing_sounds_are_silent() {
+    let data = GameData::builtin();
+    let mut events = EventBus::default();
+    let mut router = AudioEventRouter::new(&mut events, &data, HapticsSettings::default());
+    let audio = Audio::without_device(1, 8_000);
+
+    events.emit(MenuEvent::Confirmed);
+    router.play(&audio, &mut Haptics::disabled());
+
+    let mut out = [1.; 2];
+    audio.render(&mut out);
+    assert_eq!(out, [0.; 2]);
+}
+
+#[test]
+fn unsubscribed_routers_stop_routing() {
+    let data = data_with_sounds();
+    let mut events = EventBus::default();
+    let mut router = AudioEventRouter::new(&mut events, &data, HapticsSettings::default());
+
+    router.unsubscribe(&mut events);
+    events.emit(MenuEvent::Confirmed);
+
+    assert!(router.take_sounds().is_empty());
+    assert!(!events.has_subscribers::<MenuEvent>());
+}
+
+//====================================================================
@@ -0,0 +1,55 @@
+//====================================================================
+
+use game::{
+    data::GameData,
+    settings::DifficultySettings,
+    telemetry::{self, Outcome},
+};
+
+//====================================================================
+// Built in battles, played through the same battle as the scene
+
+#[test]
+fn rounds_only_move_forward() {
+    let data = GameData::builtin();
+    let record = telemetry::simulate(&data, None, DifficultySettings::default(), 3);
+
+    let rounds = record
+        .actions
+        .iter()
+        .map(|action| action.round)
+        .collect::<Vec<_>>();
+
+    assert!(rounds.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert!(rounds.first().is_some_and(|round| *round == 1));
+    assert!(rounds.iter().all(|round| *round <= record.rounds));
+}
+
+#[test]
+fn simulated_battles_finish() {
+    let data = GameData::builtin();
+
+    (0..20).for_each(|seed| {
+        let record = telemetry::simulate(&data, None, DifficultySettings::default(), seed);
+
+        match record.outcome {
+            Outcome::Victory | Outcome::Defeat => {}
+            Outcome::Draw => assert_eq!(record.rounds, telemetry::MAX_SIMULATED_ROUNDS),
+            Outcome::Retreat => panic!("Simulated battles can't retreat"),
+        }
+
+        assert!(record.turns as usize >= record.actions.len());
+    });
+}
+
+#[test]
+fn simulation_is_deterministic() {
+    let data = GameData::builtin();
+
+    let first = telemetry::simulate(&data, None, DifficultySettings::default(), 11);
+    let second = telemetry::simulate(&data, None, DifficultySettings::default(), 11);
+
+    assert_eq!(first, second);
+}
+
+//====================================================================
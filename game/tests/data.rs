@@ -0,0 +1,125 @@
+//====================================================================
+
+use std::collections::BTreeMap;
+
+use game::{
+    data::{DataError, GameData, MusicDef},
+    locale::{self, Locale},
+};
+
+//====================================================================
+
+#[test]
+fn builtin_data_is_valid() {
+    let errors = GameData::builtin().validate();
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn missing_textures_are_reported() {
+    let mut data = GameData::builtin();
+    data.characters[0].texture = Some(String::from("knight.png"));
+
+    assert!(matches!(
+        data.validate().as_slice(),
+        [DataError::MissingTexture { texture, .. }] if texture == "knight.png"
+    ));
+
+    data.textures.insert(String::from("knight.png"), Vec::new());
+    assert!(data.validate().is_empty());
+}
+
+#[test]
+fn arenas_need_defined_music() {
+    let mut data = GameData::builtin();
+    data.encounters[0].arena.music = Some(String::from("boss"));
+
+    assert!(matches!(
+        data.validate().as_slice(),
+        [DataError::UnknownMusic { music, .. }] if music == "boss"
+    ));
+
+    let boss = MusicDef {
+        name: String::from("boss"),
+        base: String::from("boss.wav"),
+        tension: None,
+        victory: None,
+        loop_start: None,
+        loop_end: None,
+    };
+    data.music.push(boss.clone());
+    assert!(data.validate().is_empty());
+
+    data.music.push(boss);
+    assert!(matches!(
+        data.validate().as_slice(),
+        [DataError::DuplicateMusic(name)] if name == "boss"
+    ));
+}
+
+#[test]
+fn sounds_need_a_known_event_each() {
+    let mut data = GameData::builtin();
+
+    let mut sound = data.sounds[0].clone();
+    data.sounds.push(sound.clone());
+    assert!(matches!(
+        data.validate().as_slice(),
+        [DataError::DuplicateSound(event)] if *event == sound.event
+    ));
+
+    sound.event = String::from("explosion");
+    *data.sounds.last_mut().unwrap() = sound;
+    assert!(matches!(
+        data.validate().as_slice(),
+        [DataError::UnknownSoundEvent { event, sound }]
+            if event == "explosion" && sound == &data.sounds[0].sound
+    ));
+
+    data.sounds.pop();
+    assert!(data.validate().is_empty());
+}
+
+#[test]
+fn locales_need_every_key_and_nothing_else() {
+    let mut data = GameData::builtin();
+    let (first, rest) = data.actions.split_first().unwrap();
+
+    let strings = BTreeMap::from([
+        (locale::action_key(&first.name), String::from("Translated")),
+        (locale::action_key("Not An Action"), String::from("Nothing")),
+    ]);
+    let missing = rest.len();
+    data.locales.insert(String::from("test"), strings);
+
+    let errors = data.validate();
+
+    assert_eq!(
+        errors
+            .iter()
+            .filter(|e| matches!(e, DataError::MissingLocaleKey { .. }))
+            .count(),
+        missing
+    );
+    assert!(errors.iter().any(|e| matches!(
+        e,
+        DataError::UnknownLocaleKey { key, .. } if key == "action.Not An Action"
+    )));
+}
+
+#[test]
+fn untranslated_text_keeps_its_name() {
+    let mut data = GameData::builtin();
+    let name = data.actions[0].name.clone();
+
+    data.locales.insert(
+        String::from("test"),
+        BTreeMap::from([(locale::action_key(&name), String::from("Translated"))]),
+    );
+
+    assert_eq!(Locale::new(&data, "test").action(&name), "Translated");
+    assert_eq!(Locale::new(&data, "test").action("Other"), "Other");
+    assert_eq!(Locale::new(&data, "").action(&name), name);
+}
+
+//====================================================================
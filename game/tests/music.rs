@@ -0,0 +1,130 @@
+//====================================================================
+
+use engine::music::{LayerMix, MusicLayer};
+use game::{
+    data::{GameData, MusicDef},
+    music::{self, MusicMood},
+};
+
+//====================================================================
+
+// Mono 16 bit wav holding `samples`
+fn wav(samples: &[i16], sample_rate: u32) -> Vec<u8> {
+    let data = (samples.len() * 2) as u32;
+
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16_u32.to_le_bytes());
+    bytes.extend_from_slice(&1_u16.to_le_bytes());
+    bytes.extend_from_slice(&1_u16.to_le_bytes());
+    bytes.extend_from_slice(&sample_rate.to_le_bytes());
+    bytes.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    bytes.extend_from_slice(&2_u16.to_le_bytes());
+    bytes.extend_from_slice(&16_u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data.to_le_bytes());
+    samples
+        .iter()
+        .for_each(|sample| bytes.extend_from_slice(&sample.to_le_bytes()));
+
+    bytes
+}
+
+fn music_def(name: &str) -> MusicDef {
+    MusicDef {
+        name: name.into(),
+        base: "base.wav".into(),
+        tension: Some("tension.wav".into()),
+        victory: None,
+        loop_start: None,
+        loop_end: None,
+    }
+}
+
+//====================================================================
+
+#[test]
+fn mood_follows_the_lead() {
+    assert_eq!(MusicMood::select(None, false, 0.5, 0.5), MusicMood::Even);
+    assert_eq!(MusicMood::select(None, false, 0.9, 0.4), MusicMood::Winning);
+    assert_eq!(MusicMood::select(None, false, 0.2, 0.8), MusicMood::Losing);
+
+    // Small leads don't count
+    assert_eq!(MusicMood::select(None, false, 0.6, 0.5), MusicMood::Even);
+}
+
+#[test]
+fn boss_phases_override_the_lead() {
+    assert_eq!(MusicMood::select(None, true, 1., 0.1), MusicMood::BossPhase);
+}
+
+#[test]
+fn endings_override_everything() {
+    assert_eq!(
+        MusicMood::select(Some(true), true, 0.1, 1.),
+        MusicMood::Victory
+    );
+    assert_eq!(
+        MusicMood::select(Some(false), false, 1., 0.),
+        MusicMood::Defeat
+    );
+}
+
+#[test]
+fn only_victory_plays_the_victory_layer() {
+    [
+        MusicMood::Even,
+        MusicMood::Winning,
+        MusicMood::Losing,
+        MusicMood::BossPhase,
+        MusicMood::Defeat,
+    ]
+    .into_iter()
+    .for_each(|mood| assert_eq!(mood.layer_mix().victory, 0., "{:?}", mood));
+
+    assert_eq!(MusicMood::Victory.layer_mix().victory, 1.);
+    assert!(MusicMood::Losing.layer_mix().tension > MusicMood::Winning.layer_mix().tension);
+}
+
+#[test]
+fn music_loads_from_its_layers() {
+    let mut data = GameData::default();
+    data.music.push(music_def("battle"));
+    data.audio
+        .insert("base.wav".into(), wav(&[100, 200, 300], 8_000));
+    data.audio
+        .insert("tension.wav".into(), wav(&[1, 2, 3], 8_000));
+
+    let mut music = music::load_music(&data, "battle").unwrap();
+    assert_eq!(music.sample_rate(), 8_000);
+
+    music.set_mix(LayerMix {
+        base: 1.,
+        tension: 1.,
+        ..Default::default()
+    });
+    music = music.with_crossfade(0.);
+
+    let mut out = [0.; 4];
+    music.mix(&mut out);
+
+    // Loops over the whole base layer
+    assert_eq!(music.position(), 1);
+    assert!(out[0] > 0. && out[3] == out[0]);
+    assert_eq!(music.gain(MusicLayer::Tension), 1.);
+}
+
+#[test]
+fn music_needs_its_base_layer() {
+    let mut data = GameData::default();
+    data.music.push(music_def("battle"));
+    data.audio
+        .insert("tension.wav".into(), wav(&[1, 2, 3], 8_000));
+
+    assert!(music::load_music(&data, "battle").is_none());
+    assert!(music::load_music(&data, "unknown").is_none());
+}
+
+//====================================================================
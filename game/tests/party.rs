@@ -0,0 +1,64 @@
+//====================================================================
+
+use game::{
+    data::{GameData, Side},
+    party::{self, Party, EXPERIENCE_PER_LEVEL},
+};
+
+//====================================================================
+
+#[test]
+fn levels_start_at_one() {
+    assert_eq!(party::level(0), 1);
+    assert_eq!(party::level(EXPERIENCE_PER_LEVEL - 1), 1);
+    assert_eq!(party::level(EXPERIENCE_PER_LEVEL), 2);
+    assert_eq!(party::level(u64::MAX), u32::MAX);
+}
+
+#[test]
+fn experience_is_awarded_to_each_character() {
+    let mut party = Party::default();
+    party.award_experience(["Knight"], EXPERIENCE_PER_LEVEL - 10);
+
+    let gains = party.award_experience(["Knight", "Mage"], 20);
+
+    assert_eq!(gains.len(), 2);
+    assert_eq!(gains[0].before, EXPERIENCE_PER_LEVEL - 10);
+    assert_eq!(gains[0].level_up(), Some(2));
+    assert_eq!(gains[1].before, 0);
+    assert_eq!(gains[1].level_up(), None);
+
+    assert_eq!(party.experience("Knight"), EXPERIENCE_PER_LEVEL + 10);
+    assert_eq!(party.experience("Mage"), 20);
+    assert_eq!(party.highest_level(["Knight", "Mage"]), 2);
+}
+
+#[test]
+fn levelled_characters_grow() {
+    let data = GameData::builtin();
+    let character = data
+        .characters
+        .iter()
+        .find(|character| character.side == Side::Friendly)
+        .unwrap();
+
+    let mut party = Party::default();
+    assert_eq!(&party.levelled(character), character);
+
+    party.award_experience([character.name.as_str()], EXPERIENCE_PER_LEVEL * 10);
+    let levelled = party.levelled(character);
+
+    assert!(levelled.health > character.health);
+    assert!(levelled.speed >= character.speed);
+}
+
+#[test]
+fn loot_is_kept_in_order() {
+    let mut party = Party::default();
+    party.grant_loot([String::from("Sword")]);
+    party.grant_loot([String::from("Shield"), String::from("Sword")]);
+
+    assert_eq!(party.inventory, ["Sword", "Shield", "Sword"]);
+}
+
+//====================================================================
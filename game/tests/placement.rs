@@ -0,0 +1,147 @@
+//====================================================================
+
+use common::Transform;
+use game::placement::{self, ScreenRect, REPLACE_ANGLE, REPLACE_DISTANCE};
+use hecs::World;
+use renderer::{camera::PerspectiveCamera, pipelines::ui3d_pipeline::Ui3d};
+
+//====================================================================
+
+const EPSILON: f32 = 1e-5;
+
+fn rect(min: (f32, f32), max: (f32, f32)) -> ScreenRect {
+    ScreenRect {
+        min: glam::vec2(min.0, min.1),
+        max: glam::vec2(max.0, max.1),
+    }
+}
+
+// Looking down +z from the origin
+fn camera() -> PerspectiveCamera {
+    PerspectiveCamera {
+        aspect: 1.,
+        fovy: std::f32::consts::FRAC_PI_2,
+        ..Default::default()
+    }
+}
+
+fn menu(world: &mut World, options: &[&str]) -> hecs::Entity {
+    world.spawn((
+        Transform::from_scale(glam::Vec3::splat(0.01)),
+        Ui3d {
+            options: options.iter().map(|option| option.to_string()).collect(),
+            ..Default::default()
+        },
+    ))
+}
+
+//====================================================================
+
+#[test]
+fn inverted_rects_have_no_area() {
+    assert_eq!(rect((0., 0.), (2., 3.)).area(), 6.);
+    assert_eq!(rect((1., 1.), (0., 2.)).area(), 0.);
+}
+
+#[test]
+fn overlap_is_shared_area() {
+    let a = rect((0., 0.), (2., 2.));
+    let b = rect((1., 1.), (3., 3.));
+
+    assert_eq!(a.overlap(&b), 1.);
+    assert_eq!(b.overlap(&a), 1.);
+    assert_eq!(a.overlap(&a), a.area());
+
+    // Touching edges don't overlap
+    assert_eq!(a.overlap(&rect((2., 0.), (4., 2.))), 0.);
+    assert_eq!(a.overlap(&rect((5., 5.), (6., 6.))), 0.);
+}
+
+#[test]
+fn offscreen_is_area_outside_the_view() {
+    assert_eq!(rect((-0.5, -0.5), (0.5, 0.5)).offscreen(), 0.);
+    assert_eq!(rect((0.5, 0.), (1.5, 1.)).offscreen(), 0.5);
+    assert_eq!(rect((2., 2.), (3., 3.)).offscreen(), 1.);
+}
+
+#[test]
+fn menus_behind_the_camera_have_no_rect() {
+    let ui = Ui3d {
+        options: vec!["Attack".into()],
+        ..Default::default()
+    };
+
+    assert!(placement::ui_rect(&camera(), &ui, 0.01, glam::vec3(0., 0., 5.)).is_some());
+    assert!(placement::ui_rect(&camera(), &ui, 0.01, glam::vec3(0., 0., -5.)).is_none());
+}
+
+#[test]
+fn placement_keeps_menus_on_screen() {
+    let mut world = World::new();
+    let entity = menu(&mut world, &["Attack", "Defend"]);
+
+    let offscreen = glam::vec3(20., 0., 5.);
+    let onscreen = glam::vec3(0., 0., 5.);
+
+    let (position, rect) =
+        placement::place(&camera(), &world, entity, &[offscreen, onscreen], &[]).unwrap();
+
+    assert_eq!(position, onscreen);
+    assert!(rect.offscreen() < EPSILON);
+}
+
+#[test]
+fn placement_avoids_covered_rects() {
+    let mut world = World::new();
+    let entity = menu(&mut world, &["Attack", "Defend"]);
+
+    let first = glam::vec3(0., 0., 5.);
+    let second = glam::vec3(-2., 0., 5.);
+
+    let covered = placement::place(&camera(), &world, entity, &[first], &[])
+        .unwrap()
+        .1;
+
+    let (position, _) =
+        placement::place(&camera(), &world, entity, &[first, second], &[covered]).unwrap();
+    assert_eq!(position, second);
+}
+
+#[test]
+fn earlier_candidates_win_ties() {
+    let mut world = World::new();
+    let entity = menu(&mut world, &["Attack"]);
+
+    let first = glam::vec3(0., 0., 5.);
+    let second = glam::vec3(-0.5, 0., 5.);
+
+    let (position, _) = placement::place(&camera(), &world, entity, &[first, second], &[]).unwrap();
+    assert_eq!(position, first);
+}
+
+#[test]
+fn only_menus_are_placed() {
+    let mut world = World::new();
+    let entity = world.spawn((Transform::default(),));
+
+    assert!(placement::place(&camera(), &world, entity, &[glam::Vec3::Z], &[]).is_none());
+}
+
+#[test]
+fn small_camera_movements_keep_placements() {
+    let mut camera = camera();
+    let from = (camera.translation, camera.rotation);
+
+    camera.translation.x = REPLACE_DISTANCE * 0.5;
+    camera.rotation = glam::Quat::from_rotation_y(REPLACE_ANGLE * 0.5);
+    assert!(!placement::camera_moved(&camera, from));
+
+    camera.translation.x = REPLACE_DISTANCE * 1.5;
+    assert!(placement::camera_moved(&camera, from));
+
+    camera.translation.x = 0.;
+    camera.rotation = glam::Quat::from_rotation_y(REPLACE_ANGLE * 1.5);
+    assert!(placement::camera_moved(&camera, from));
+}
+
+//====================================================================
@@ -0,0 +1,72 @@
+//====================================================================
+
+use game::saves::{self, SaveData, SaveError, SAVE_VERSION};
+
+//====================================================================
+// Versions
+
+#[test]
+fn current_saves_read_back_unchanged() {
+    let mut data = SaveData::default();
+    data.meta.party = String::from("Lv 2 Hero");
+    data.overworld.position = glam::Vec3::new(1., 2., 3.);
+    data.overworld.travelled = 40.;
+    data.entities = String::from(r#"[{"Saved":null,"Opacity":0.5}]"#);
+
+    let contents = toml::to_string(&data).unwrap();
+
+    assert_eq!(saves::parse_save(&contents).unwrap(), data);
+}
+
+#[test]
+fn unversioned_saves_are_upgraded() {
+    // Version 1 kept the overworld state at the top level
+    let contents = r#"
+        position = [1.0, 2.0, 3.0]
+        travelled = 40.0
+
+        [meta]
+        party = "Hero"
+    "#;
+
+    let data = saves::parse_save(contents).unwrap();
+
+    assert_eq!(data.version, SAVE_VERSION);
+    assert_eq!(data.meta.party, "Hero");
+    assert_eq!(data.overworld.position, glam::Vec3::new(1., 2., 3.));
+    assert_eq!(data.overworld.travelled, 40.);
+}
+
+#[test]
+fn every_version_upgrades_to_the_current_one() {
+    (1..=SAVE_VERSION).for_each(|version| {
+        let contents = format!("version = {}", version);
+
+        let data = saves::parse_save(&contents).unwrap();
+        assert_eq!(data.version, SAVE_VERSION, "from version {}", version);
+    });
+}
+
+#[test]
+fn newer_saves_are_refused() {
+    let contents = format!("version = {}", SAVE_VERSION + 1);
+
+    assert!(matches!(
+        saves::parse_save(&contents),
+        Err(SaveError::TooNew(version)) if version == SAVE_VERSION + 1
+    ));
+}
+
+#[test]
+fn broken_saves_are_refused() {
+    assert!(matches!(
+        saves::parse_save("version = \"two\""),
+        Err(SaveError::Parse(_))
+    ));
+    assert!(matches!(
+        saves::parse_save("not toml ["),
+        Err(SaveError::Parse(_))
+    ));
+}
+
+//====================================================================
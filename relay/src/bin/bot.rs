@@ -0,0 +1,384 @@
+//====================================================================
+
+use std::collections::BTreeMap;
+
+use battle_core::{
+    battle::Battle,
+    characters::{
+        actions::{Action, ActionRepo, ActionResolution, TargetType},
+        Character, CharacterStats, Equipment,
+    },
+    combat::{CpuProfile, Difficulty},
+    protocol::{
+        self, BattleCommand, ClientMessage, EventCursor, Handshake, ProtocolError, RoomSetup,
+        ServerMessage,
+    },
+};
+use relay::frame::{read_frame, write_frame};
+use tokio::net::{
+    tcp::{OwnedReadHalf, OwnedWriteHalf},
+    TcpStream,
+};
+
+//====================================================================
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:7878";
+const DEFAULT_TURNS: u64 = 100;
+
+/// Picks whatever is available until told otherwise.
+const DEFAULT_PROFILE: CpuProfile = CpuProfile {
+    aggressiveness: 50,
+    lookahead: 0,
+};
+
+struct Config {
+    address: String,
+    join: Option<String>,
+    turns: u64,
+    profile: CpuProfile,
+}
+
+impl Config {
+    // bot [address] [--join CODE] [--turns N] [--aggressiveness PERCENT] [--lookahead TURNS]
+    fn from_args() -> Result<Self, String> {
+        let mut config = Self {
+            address: DEFAULT_ADDRESS.to_string(),
+            join: None,
+            turns: DEFAULT_TURNS,
+            profile: DEFAULT_PROFILE,
+        };
+
+        let mut args = std::env::args().skip(1);
+
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+
+            match arg.as_str() {
+                "--join" => config.join = Some(value()?),
+                "--turns" => {
+                    config.turns = value()?
+                        .parse()
+                        .map_err(|_| String::from("Invalid turn count"))?
+                }
+                "--aggressiveness" => {
+                    config.profile.aggressiveness = value()?
+                        .parse()
+                        .map_err(|_| String::from("Invalid aggressiveness"))?
+                }
+                "--lookahead" => {
+                    config.profile.lookahead = value()?
+                        .parse()
+                        .map_err(|_| String::from("Invalid lookahead"))?
+                }
+                _ => config.address = arg,
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+//====================================================================
+
+#[derive(Debug, thiserror::Error)]
+enum BotError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("{0}")]
+    Protocol(#[from] ProtocolError),
+
+    #[error("Protocol invariant broken: {0}")]
+    Invariant(String),
+}
+
+struct Bot {
+    reader: OwnedReadHalf,
+    writer: OwnedWriteHalf,
+
+    profile: CpuProfile,
+    actions: ActionRepo,
+    /// The room's battle, played alongside the server to know whose turn it
+    /// is.
+    battle: Battle,
+
+    player: Option<u32>,
+    cursor: EventCursor,
+    /// Commands sent and not yet echoed back.
+    pending: Option<BattleCommand>,
+}
+
+impl Bot {
+    async fn send(&mut self, message: &ClientMessage) -> Result<(), BotError> {
+        write_frame(&mut self.writer, &protocol::encode(message)?).await?;
+        Ok(())
+    }
+
+    async fn receive(&mut self) -> Result<ServerMessage, BotError> {
+        let frame = read_frame(&mut self.reader).await?;
+        Ok(protocol::decode(&frame)?)
+    }
+
+    // Pick for the character whose turn it is as the cpu would. Picked on a
+    // copy of the battle so its rolls stay in step with the server's.
+    fn choose_command(&self) -> Option<BattleCommand> {
+        let caster = self.battle.current()?;
+        let (action, target) = self
+            .battle
+            .clone()
+            .choose_cpu_action(&self.actions, self.profile)?;
+
+        Some(BattleCommand {
+            character: caster as u32,
+            action,
+            target: target.map(|target| target as u32),
+        })
+    }
+
+    // Whether the character whose turn it is belongs to this bot. The first
+    // player controls the friendly side.
+    fn our_turn(&self) -> Option<usize> {
+        let current = self.battle.current()?;
+
+        match self.battle.is_friendly(current) == Some(self.player? == 0) {
+            true => Some(current),
+            false => None,
+        }
+    }
+
+    // Play until `turns` events have gone by or the battle ends, returning
+    // how many went by
+    async fn play(&mut self, turns: u64) -> Result<u64, BotError> {
+        let mut next_sequence = 0;
+
+        while next_sequence < turns {
+            if let Some(caster) = self.our_turn().filter(|_| self.pending.is_none()) {
+                let command = self.choose_command().ok_or_else(|| {
+                    BotError::Invariant(format!("Character {} has nothing to do", caster))
+                })?;
+                log::debug!("Turn {}: sending {:?}", next_sequence, command);
+
+                self.send(&ClientMessage::Command(command.clone())).await?;
+                self.pending = Some(command);
+            }
+
+            let sequence = match self.receive().await? {
+                ServerMessage::Event { sequence, command } => {
+                    if self.our_turn().is_some() && self.pending.take() != Some(command.clone()) {
+                        return Err(BotError::Invariant(format!(
+                            "Event {} doesn't match the command we sent",
+                            sequence
+                        )));
+                    }
+
+                    self.battle
+                        .act(
+                            &self.actions,
+                            command.character as usize,
+                            command.action,
+                            command.target.map(|target| target as usize),
+                        )
+                        .map_err(|e| {
+                            BotError::Invariant(format!(
+                                "Event {} can't be played: {}",
+                                sequence, e
+                            ))
+                        })?;
+                    sequence
+                }
+                ServerMessage::TurnTimedOut { sequence } => {
+                    log::info!("Turn {} timed out", sequence);
+                    self.pending = None;
+                    sequence
+                }
+                ServerMessage::RoomJoined { code, player } => {
+                    log::info!("Playing as player {} in room {}", player, code);
+                    self.player = Some(player);
+                    continue;
+                }
+                // Joining a room carries on from wherever its battle is up to
+                ServerMessage::Snapshot(snapshot) => {
+                    log::info!("Caught up to event {}", snapshot.next_sequence);
+                    self.cursor = EventCursor::from_snapshot(&snapshot);
+                    self.battle = snapshot.battle;
+                    next_sequence = snapshot.next_sequence;
+                    continue;
+                }
+                ServerMessage::BattleEnded { won } => {
+                    if self.battle.winner() != Some(won) {
+                        return Err(BotError::Invariant(String::from(
+                            "Server ended the battle before we did",
+                        )));
+                    }
+
+                    let winner = match won {
+                        true => "Friendly",
+                        false => "Enemy",
+                    };
+                    log::info!("Battle ended. {} side won", winner);
+                    return Ok(next_sequence);
+                }
+                ServerMessage::CommandRejected(e) => {
+                    return Err(BotError::Invariant(format!(
+                        "Server refused {:?}: {}",
+                        self.pending, e
+                    )))
+                }
+                other => {
+                    return Err(BotError::Invariant(format!(
+                        "Unexpected message during battle: {:?}",
+                        other
+                    )))
+                }
+            };
+
+            // Events must arrive in order with no gaps
+            if !self.cursor.accept(sequence)? {
+                return Err(BotError::Invariant(format!(
+                    "Event {} was sent twice",
+                    sequence
+                )));
+            }
+
+            self.battle.advance_to_turn();
+            next_sequence = sequence + 1;
+        }
+
+        Ok(next_sequence)
+    }
+}
+
+//====================================================================
+
+// The relay doesn't depend on the game's data, so bots play with their own
+// actions and characters. Every bot builds the same ones so their handshakes
+// match.
+fn bot_setup() -> RoomSetup {
+    let action = |name: &str, target: TargetType, resolution: ActionResolution| Action {
+        name: name.into(),
+        target,
+        resolution,
+        status: None,
+        cooldown: 0,
+        cinematic: None,
+    };
+
+    let actions = vec![
+        action("Idle", TargetType::None, ActionResolution::None),
+        action("Punch", TargetType::Enemy, ActionResolution::Damage(5)),
+        action(
+            "Heal",
+            TargetType::Any {
+                can_target_caster: true,
+            },
+            ActionResolution::Heal(5),
+        ),
+    ];
+
+    let repo = ActionRepo::from_actions(actions.clone());
+    let character = |name: &str| Character {
+        name: name.into(),
+        player_controlled: false,
+        stats: CharacterStats {
+            speed: 5,
+            health: 30,
+            max_health: 30,
+            accuracy: 90,
+            evasion: 0,
+            crit_chance: 5,
+            crit_multiplier: 150,
+        },
+        equipment: Equipment::default(),
+        hue_shift: 0,
+        texture: None,
+        actions: repo.actions().into_iter().map(|(id, _)| id).collect(),
+        effects: Vec::new(),
+        cooldowns: BTreeMap::new(),
+        front_facing: true,
+    };
+
+    RoomSetup {
+        difficulty: Difficulty::Normal,
+        actions,
+        friendly: vec![character("Friendly Bot")],
+        enemy: vec![character("Enemy Bot")],
+    }
+}
+
+async fn run(config: Config) -> Result<(), BotError> {
+    let setup = bot_setup();
+    let handshake = Handshake::new(setup.data_hash());
+
+    let (reader, writer) = TcpStream::connect(&config.address).await?.into_split();
+    log::info!("Connected to {}", config.address);
+
+    let mut bot = Bot {
+        reader,
+        writer,
+        profile: config.profile,
+        actions: ActionRepo::from_actions(setup.actions.clone()),
+        battle: Battle::new(
+            setup.difficulty,
+            setup.friendly.clone(),
+            setup.enemy.clone(),
+            Vec::new(),
+            0,
+        ),
+        player: None,
+        cursor: EventCursor::default(),
+        pending: None,
+    };
+
+    bot.send(&ClientMessage::Hello(handshake)).await?;
+
+    match &config.join {
+        Some(code) => {
+            bot.send(&ClientMessage::JoinRoom { code: code.clone() })
+                .await?
+        }
+        None => bot.send(&ClientMessage::CreateRoom(setup.clone())).await?,
+    }
+
+    // The welcome always comes first. Rooms we create start from its seed,
+    // and rooms we join send a snapshot straight after.
+    let seed = protocol::handle_welcome(&handshake, bot.receive().await?)?;
+    bot.battle = Battle::new(
+        setup.difficulty,
+        setup.friendly,
+        setup.enemy,
+        Vec::new(),
+        seed,
+    );
+    bot.battle.advance_to_turn();
+    log::info!("Joined battle with seed {} as {:?}", seed, bot.profile);
+
+    let played = bot.play(config.turns).await?;
+
+    bot.send(&ClientMessage::Leave).await?;
+    log::info!("Played {} turns without errors", played);
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    env_logger::Builder::new()
+        .filter_level(log::LevelFilter::Info)
+        .parse_default_env()
+        .init();
+
+    let config = match Config::from_args() {
+        Ok(config) => config,
+        Err(e) => {
+            log::error!("{}", e);
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(config).await {
+        log::error!("Bot failed: {}", e);
+        std::process::exit(1);
+    }
+}
+
+//====================================================================
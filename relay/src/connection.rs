@@ -0,0 +1,149 @@
+//====================================================================
+
+use std::{net::SocketAddr, sync::Arc};
+
+use battle_core::protocol::{
+    self, ClientMessage, Handshake, ProtocolError, RejectReason, ServerMessage,
+};
+use tokio::{
+    net::TcpStream,
+    sync::{mpsc, Mutex},
+};
+
+use crate::{
+    frame::{read_frame, write_frame},
+    rooms::{Rooms, OUTBOX_SIZE},
+};
+
+//====================================================================
+
+struct Seat {
+    code: String,
+    player: u32,
+}
+
+pub async fn handle(stream: TcpStream, address: SocketAddr, rooms: Arc<Mutex<Rooms>>) {
+    let (mut reader, mut writer) = stream.into_split();
+    let (sender, mut receiver) = mpsc::channel::<ServerMessage>(OUTBOX_SIZE);
+
+    let write_task = tokio::spawn(async move {
+        while let Some(message) = receiver.recv().await {
+            let frame = match protocol::encode(&message) {
+                Ok(frame) => frame,
+                Err(e) => {
+                    log::error!("Unable to encode {:?}: {}", message, e);
+                    continue;
+                }
+            };
+
+            if write_frame(&mut writer, &frame).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut handshake: Option<Handshake> = None;
+    let mut seat: Option<Seat> = None;
+
+    loop {
+        let frame = match read_frame(&mut reader).await {
+            Ok(frame) => frame,
+            Err(e) => {
+                log::debug!("Connection from {} closed: {}", address, e);
+                break;
+            }
+        };
+
+        let message = match protocol::decode::<ClientMessage>(&frame) {
+            Ok(message) => message,
+
+            // Tell outdated clients why before dropping them
+            Err(ProtocolError::VersionMismatch { local, remote }) => {
+                log::info!("Client {} is on protocol {}", address, remote);
+                sender
+                    .try_send(ServerMessage::Rejected(RejectReason::ProtocolVersion {
+                        local,
+                        remote,
+                    }))
+                    .ok();
+                break;
+            }
+            Err(e) => {
+                log::warn!("Bad message from {}: {}", address, e);
+                break;
+            }
+        };
+
+        match (message, &handshake, &seat) {
+            (ClientMessage::Hello(hello) | ClientMessage::Rejoin(hello), None, _) => {
+                handshake = Some(hello)
+            }
+
+            (ClientMessage::CreateRoom(setup), Some(hello), None) => {
+                let created = rooms.lock().await.create(*hello, setup, sender.clone());
+
+                match created {
+                    Ok((code, player)) => {
+                        sender
+                            .try_send(ServerMessage::RoomJoined {
+                                code: code.clone(),
+                                player,
+                            })
+                            .ok();
+                        seat = Some(Seat { code, player });
+                    }
+                    Err(reason) => {
+                        log::info!("Rejected room from {}: {}", address, reason);
+                        sender.try_send(ServerMessage::Rejected(reason)).ok();
+                    }
+                }
+            }
+
+            (ClientMessage::JoinRoom { code }, Some(hello), None) => {
+                let joined = rooms.lock().await.join(&code, *hello, sender.clone());
+
+                match joined {
+                    Ok(player) => {
+                        sender
+                            .try_send(ServerMessage::RoomJoined {
+                                code: code.clone(),
+                                player,
+                            })
+                            .ok();
+                        seat = Some(Seat { code, player });
+                    }
+                    Err(reason) => {
+                        log::info!("Rejected {} from room {}: {}", address, code, reason);
+                        sender.try_send(ServerMessage::Rejected(reason)).ok();
+                    }
+                }
+            }
+
+            (ClientMessage::Command(command), _, Some(seat)) => {
+                let played = rooms.lock().await.command(&seat.code, seat.player, command);
+
+                if let Err(e) = played {
+                    log::debug!("Refused command from {}: {}", address, e);
+                    sender.try_send(ServerMessage::CommandRejected(e)).ok();
+                }
+            }
+
+            (ClientMessage::Leave, _, _) => break,
+
+            (message, _, _) => {
+                log::warn!("Unexpected message from {}: {:?}", address, message);
+                break;
+            }
+        }
+    }
+
+    if let Some(seat) = seat {
+        rooms.lock().await.leave(&seat.code, seat.player);
+    }
+
+    // Let any queued replies go out before closing
+    drop(sender);
+    write_task.await.ok();
+}
+
+//====================================================================
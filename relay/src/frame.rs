@@ -0,0 +1,36 @@
+//====================================================================
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+//====================================================================
+
+/// Largest frame accepted from a peer.
+pub const MAX_FRAME: usize = 1 << 20;
+
+// Frames are a little endian u32 length followed by an encoded message
+pub async fn read_frame(reader: &mut (impl AsyncRead + Unpin)) -> std::io::Result<Vec<u8>> {
+    let length = reader.read_u32_le().await? as usize;
+
+    if length > MAX_FRAME {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Frame of {} bytes is too large", length),
+        ));
+    }
+
+    let mut frame = vec![0; length];
+    reader.read_exact(&mut frame).await?;
+
+    Ok(frame)
+}
+
+pub async fn write_frame(
+    writer: &mut (impl AsyncWrite + Unpin),
+    frame: &[u8],
+) -> std::io::Result<()> {
+    writer.write_u32_le(frame.len() as u32).await?;
+    writer.write_all(frame).await?;
+    writer.flush().await
+}
+
+//====================================================================
@@ -0,0 +1,7 @@
+//====================================================================
+
+pub mod connection;
+pub mod frame;
+pub mod rooms;
+
+//====================================================================
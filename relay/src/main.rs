@@ -0,0 +1,68 @@
+//====================================================================
+
+use std::{sync::Arc, time::Duration};
+
+use relay::{connection, rooms::Rooms};
+use tokio::{net::TcpListener, sync::Mutex};
+
+//====================================================================
+
+const DEFAULT_ADDRESS: &str = "0.0.0.0:7878";
+const DEFAULT_TURN_TIMEOUT: Duration = Duration::from_secs(60);
+
+struct Config {
+    address: String,
+    turn_timeout: Duration,
+}
+
+impl Config {
+    // relay [address] [turn timeout seconds]
+    fn from_args() -> Self {
+        let mut args = std::env::args().skip(1);
+
+        let address = args.next().unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+        let turn_timeout = args
+            .next()
+            .and_then(|secs| secs.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TURN_TIMEOUT);
+
+        Self {
+            address,
+            turn_timeout,
+        }
+    }
+}
+
+//====================================================================
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    env_logger::Builder::new()
+        .filter_level(log::LevelFilter::Info)
+        .parse_default_env()
+        .init();
+
+    let config = Config::from_args();
+
+    let listener = TcpListener::bind(&config.address).await?;
+    log::info!(
+        "Relay listening on {} (protocol {}, turn timeout {:?})",
+        config.address,
+        battle_core::protocol::PROTOCOL_VERSION,
+        config.turn_timeout
+    );
+
+    let rooms = Arc::new(Mutex::new(Rooms::new(config.turn_timeout)));
+
+    tokio::spawn(relay::rooms::enforce_timeouts(rooms.clone()));
+
+    loop {
+        let (stream, address) = listener.accept().await?;
+        log::debug!("Accepted connection from {}", address);
+
+        tokio::spawn(connection::handle(stream, address, rooms.clone()));
+    }
+}
+
+//====================================================================
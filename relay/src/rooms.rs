@@ -0,0 +1,305 @@
+//====================================================================
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use battle_core::{
+    battle::{ActionError, Battle},
+    characters::actions::ActionRepo,
+    protocol::{BattleCommand, BattleSnapshot, Handshake, RejectReason, RoomSetup, ServerMessage},
+};
+use rand::Rng;
+use tokio::{
+    sync::{
+        mpsc::{error::TrySendError, Sender},
+        Mutex,
+    },
+    time::Instant,
+};
+
+//====================================================================
+
+const CODE_LENGTH: usize = 4;
+const CODE_CHARS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
+const MAX_PLAYERS: u32 = 2;
+
+/// Messages queued for a player before they're dropped for falling behind.
+pub const OUTBOX_SIZE: usize = 256;
+
+pub type PlayerSender = Sender<ServerMessage>;
+
+enum RoomEvent {
+    Command(BattleCommand),
+    TurnTimedOut,
+}
+
+impl RoomEvent {
+    fn message(&self, sequence: u64) -> ServerMessage {
+        match self {
+            RoomEvent::Command(command) => ServerMessage::Event {
+                sequence,
+                command: command.clone(),
+            },
+            RoomEvent::TurnTimedOut => ServerMessage::TurnTimedOut { sequence },
+        }
+    }
+}
+
+// Player 0 controls the friendly side and player 1 the enemy side
+#[inline]
+fn controls_friendly(player: u32) -> bool {
+    player == 0
+}
+
+struct Room {
+    handshake: Handshake,
+    seed: u64,
+    actions: ActionRepo,
+    /// The battle as the server plays it. Only commands it accepts are
+    /// relayed.
+    battle: Battle,
+    players: HashMap<u32, PlayerSender>,
+    /// Sequence the next event is sent with.
+    next_sequence: u64,
+    turn_deadline: Option<Instant>,
+}
+
+impl Room {
+    fn push_event(&mut self, event: RoomEvent) {
+        let message = event.message(self.next_sequence);
+        self.next_sequence += 1;
+
+        self.broadcast(message);
+    }
+
+    // Move the battle on to the next character able to act, starting their
+    // turn clock if everyone is here
+    fn next_turn(&mut self, turn_timeout: Duration) {
+        self.turn_deadline = None;
+
+        if let Some(won) = self.battle.advance_to_turn() {
+            self.broadcast(ServerMessage::BattleEnded { won });
+            return;
+        }
+
+        if self.players.len() == MAX_PLAYERS as usize {
+            self.turn_deadline = Some(Instant::now() + turn_timeout);
+        }
+    }
+
+    // Players too far behind to take another message are dropped. They can
+    // rejoin to catch up.
+    fn broadcast(&mut self, message: ServerMessage) {
+        self.players
+            .retain(|player, sender| match sender.try_send(message.clone()) {
+                Ok(()) | Err(TrySendError::Closed(_)) => true,
+                Err(TrySendError::Full(_)) => {
+                    log::warn!("Dropping player {} for falling behind", player);
+                    false
+                }
+            });
+    }
+}
+
+//====================================================================
+
+pub struct Rooms {
+    rooms: HashMap<String, Room>,
+    turn_timeout: Duration,
+}
+
+impl Rooms {
+    pub fn new(turn_timeout: Duration) -> Self {
+        Self {
+            rooms: HashMap::new(),
+            turn_timeout,
+        }
+    }
+
+    fn new_code(&self) -> String {
+        let mut rng = rand::thread_rng();
+
+        loop {
+            let code = (0..CODE_LENGTH)
+                .map(|_| CODE_CHARS[rng.gen_range(0..CODE_CHARS.len())] as char)
+                .collect::<String>();
+
+            if !self.rooms.contains_key(&code) {
+                return code;
+            }
+        }
+    }
+
+    /// Open a room playing out `setup`, which has to match the creator's
+    /// handshake. Returns the room code and player id.
+    pub fn create(
+        &mut self,
+        handshake: Handshake,
+        setup: RoomSetup,
+        player: PlayerSender,
+    ) -> Result<(String, u32), RejectReason> {
+        let data_hash = setup.data_hash();
+        if data_hash != handshake.data_hash {
+            return Err(RejectReason::GameData {
+                local: data_hash,
+                remote: handshake.data_hash,
+            });
+        }
+
+        let code = self.new_code();
+        let seed = rand::random();
+
+        player
+            .try_send(ServerMessage::Welcome { handshake, seed })
+            .ok();
+
+        let mut room = Room {
+            handshake,
+            seed,
+            actions: ActionRepo::from_actions(setup.actions),
+            battle: Battle::new(
+                setup.difficulty,
+                setup.friendly,
+                setup.enemy,
+                Vec::new(),
+                seed,
+            ),
+            players: HashMap::from([(0, player)]),
+            next_sequence: 0,
+            turn_deadline: None,
+        };
+        room.next_turn(self.turn_timeout);
+
+        self.rooms.insert(code.clone(), room);
+
+        log::info!("Opened room {}", code);
+        Ok((code, 0))
+    }
+
+    /// Join an existing room, taking the first free seat, and send the new
+    /// player a snapshot of the battle so far. Returns the player id.
+    pub fn join(
+        &mut self,
+        code: &str,
+        handshake: Handshake,
+        player: PlayerSender,
+    ) -> Result<u32, RejectReason> {
+        let turn_timeout = self.turn_timeout;
+
+        let room = self
+            .rooms
+            .get_mut(code)
+            .ok_or_else(|| RejectReason::NoSuchRoom(code.to_string()))?;
+
+        room.handshake.check(&handshake)?;
+
+        let id = (0..MAX_PLAYERS)
+            .find(|id| !room.players.contains_key(id))
+            .ok_or(RejectReason::Full)?;
+
+        player
+            .try_send(ServerMessage::Welcome {
+                handshake: room.handshake,
+                seed: room.seed,
+            })
+            .ok();
+
+        player
+            .try_send(ServerMessage::Snapshot(Box::new(BattleSnapshot {
+                next_sequence: room.next_sequence,
+                battle: room.battle.clone(),
+            })))
+            .ok();
+
+        room.players.insert(id, player);
+
+        // Turn clock starts once everyone is here
+        if room.players.len() == MAX_PLAYERS as usize
+            && room.turn_deadline.is_none()
+            && room.battle.current().is_some()
+        {
+            room.turn_deadline = Some(Instant::now() + turn_timeout);
+        }
+
+        log::info!("Player {} joined room {}", id, code);
+        Ok(id)
+    }
+
+    /// Play a command for `player` and relay it to everyone in the room,
+    /// including the sender. Nothing is relayed if the battle refuses it or
+    /// the character isn't on the player's side.
+    pub fn command(
+        &mut self,
+        code: &str,
+        player: u32,
+        command: BattleCommand,
+    ) -> Result<(), ActionError> {
+        let turn_timeout = self.turn_timeout;
+        let caster = command.character as usize;
+
+        let room = match self.rooms.get_mut(code) {
+            Some(room) if room.players.contains_key(&player) => room,
+            _ => return Err(ActionError::NotTheirTurn(caster)),
+        };
+
+        if room.battle.is_friendly(caster) != Some(controls_friendly(player)) {
+            return Err(ActionError::NotTheirTurn(caster));
+        }
+
+        room.battle.act(
+            &room.actions,
+            caster,
+            command.action,
+            command.target.map(|target| target as usize),
+        )?;
+
+        room.push_event(RoomEvent::Command(command));
+        room.next_turn(turn_timeout);
+
+        Ok(())
+    }
+
+    /// Remove a player, closing the room once it's empty.
+    pub fn leave(&mut self, code: &str, player: u32) {
+        let room = match self.rooms.get_mut(code) {
+            Some(room) => room,
+            None => return,
+        };
+
+        room.players.remove(&player);
+        log::info!("Player {} left room {}", player, code);
+
+        // Hold the turn clock until they rejoin
+        room.turn_deadline = None;
+
+        if room.players.is_empty() {
+            self.rooms.remove(code);
+            log::info!("Closed room {}", code);
+        }
+    }
+
+    /// Skip the turns of rooms whose turn clock ran out before `now`.
+    pub fn expire_turns(&mut self, now: Instant) {
+        let turn_timeout = self.turn_timeout;
+
+        self.rooms
+            .iter_mut()
+            .filter(|(_, room)| room.turn_deadline.is_some_and(|deadline| deadline <= now))
+            .for_each(|(code, room)| {
+                log::debug!("Turn timed out in room {}", code);
+                room.push_event(RoomEvent::TurnTimedOut);
+                room.next_turn(turn_timeout);
+            });
+    }
+}
+
+/// Skip the turns of players that take longer than the turn timeout.
+pub async fn enforce_timeouts(rooms: Arc<Mutex<Rooms>>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+
+    loop {
+        interval.tick().await;
+        rooms.lock().await.expire_turns(Instant::now());
+    }
+}
+
+//====================================================================
@@ -0,0 +1,393 @@
+//====================================================================
+
+use std::{collections::BTreeMap, time::Duration};
+
+use battle_core::{
+    battle::{ActionError, Battle},
+    characters::{
+        actions::{Action, ActionRepo, ActionResolution, TargetType},
+        Character, CharacterStats, Equipment,
+    },
+    combat::Difficulty,
+    protocol::{BattleCommand, EventCursor, Handshake, RejectReason, RoomSetup, ServerMessage},
+};
+use relay::rooms::Rooms;
+use tokio::{
+    sync::mpsc::{self, Receiver},
+    time::Instant,
+};
+
+//====================================================================
+
+const TURN_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Idle and a Punch that never misses, with one character on each side
+// that goes down to `punches` punches
+fn setup(punches: u32) -> RoomSetup {
+    let action = |name: &str, target: TargetType, resolution: ActionResolution| Action {
+        name: name.into(),
+        target,
+        resolution,
+        status: None,
+        cooldown: 0,
+        cinematic: None,
+    };
+
+    let actions = vec![
+        action("Idle", TargetType::None, ActionResolution::None),
+        action("Punch", TargetType::Enemy, ActionResolution::Damage(5)),
+    ];
+
+    let repo = ActionRepo::from_actions(actions.clone());
+    let character = |name: &str| Character {
+        name: name.into(),
+        player_controlled: false,
+        stats: CharacterStats {
+            speed: 5,
+            health: punches * 5,
+            max_health: punches * 5,
+            accuracy: 100,
+            evasion: 0,
+            crit_chance: 0,
+            crit_multiplier: 100,
+        },
+        equipment: Equipment::default(),
+        hue_shift: 0,
+        texture: None,
+        actions: repo.actions().into_iter().map(|(id, _)| id).collect(),
+        effects: Vec::new(),
+        cooldowns: BTreeMap::new(),
+        front_facing: true,
+    };
+
+    RoomSetup {
+        difficulty: Difficulty::Normal,
+        actions,
+        friendly: vec![character("Hero")],
+        enemy: vec![character("Goblin")],
+    }
+}
+
+// Everything waiting for a player
+fn received(receiver: &mut Receiver<ServerMessage>) -> Vec<ServerMessage> {
+    std::iter::from_fn(|| receiver.try_recv().ok()).collect()
+}
+
+struct Table {
+    rooms: Rooms,
+    code: String,
+    setup: RoomSetup,
+    /// The room's battle, played alongside the server.
+    battle: Battle,
+    players: [Receiver<ServerMessage>; 2],
+}
+
+// A room with both players in it, and the battle waiting on the first turn
+fn table(punches: u32) -> Table {
+    let setup = setup(punches);
+    let handshake = Handshake::new(setup.data_hash());
+    let mut rooms = Rooms::new(TURN_TIMEOUT);
+
+    let (first, mut first_receiver) = mpsc::channel(64);
+    let (code, player) = rooms.create(handshake, setup.clone(), first).unwrap();
+    assert_eq!(player, 0);
+
+    let (second, mut second_receiver) = mpsc::channel(64);
+    assert_eq!(rooms.join(&code, handshake, second), Ok(1));
+
+    let seed = match received(&mut first_receiver).as_slice() {
+        [ServerMessage::Welcome { seed, .. }] => *seed,
+        other => panic!("Expected a welcome, got {:?}", other),
+    };
+
+    let mut battle = Battle::new(
+        setup.difficulty,
+        setup.friendly.clone(),
+        setup.enemy.clone(),
+        Vec::new(),
+        seed,
+    );
+    battle.advance_to_turn();
+
+    assert!(matches!(
+        received(&mut second_receiver).as_slice(),
+        [
+            ServerMessage::Welcome { seed: second_seed, .. },
+            ServerMessage::Snapshot(snapshot),
+        ] if *second_seed == seed && snapshot.next_sequence == 0 && snapshot.battle == battle
+    ));
+
+    Table {
+        rooms,
+        code,
+        setup,
+        battle,
+        players: [first_receiver, second_receiver],
+    }
+}
+
+fn punch(table: &Table, caster: usize) -> BattleCommand {
+    let actions = ActionRepo::from_actions(table.setup.actions.clone());
+
+    BattleCommand {
+        character: caster as u32,
+        action: actions.find_action_name("Punch").unwrap(),
+        target: Some(1 - caster as u32),
+    }
+}
+
+//====================================================================
+// Joining
+
+#[test]
+fn rooms_need_a_setup_matching_the_handshake() {
+    let mut rooms = Rooms::new(TURN_TIMEOUT);
+    let (sender, _receiver) = mpsc::channel(8);
+
+    let handshake = Handshake::new(setup(2).data_hash());
+
+    assert!(matches!(
+        rooms.create(handshake, setup(3), sender),
+        Err(RejectReason::GameData { .. })
+    ));
+}
+
+#[test]
+fn rooms_take_two_players() {
+    let mut table = table(2);
+    let handshake = Handshake::new(table.setup.data_hash());
+    let (sender, _receiver) = mpsc::channel(8);
+
+    assert_eq!(
+        table.rooms.join(&table.code, handshake, sender.clone()),
+        Err(RejectReason::Full)
+    );
+    assert_eq!(
+        table.rooms.join("ZZZZ", handshake, sender),
+        Err(RejectReason::NoSuchRoom(String::from("ZZZZ")))
+    );
+}
+
+#[test]
+fn players_leaving_free_their_seat() {
+    let mut table = table(2);
+    let handshake = Handshake::new(table.setup.data_hash());
+
+    table.rooms.leave(&table.code, 0);
+
+    let (sender, _receiver) = mpsc::channel(8);
+    assert_eq!(table.rooms.join(&table.code, handshake, sender), Ok(0));
+}
+
+#[test]
+fn rejoining_carries_on_from_a_snapshot() {
+    let mut table = table(3);
+    let handshake = Handshake::new(table.setup.data_hash());
+    let actions = ActionRepo::from_actions(table.setup.actions.clone());
+
+    // Play a turn, keeping the mirrored battle in step
+    let caster = table.battle.current().unwrap();
+    let command = punch(&table, caster);
+    table
+        .rooms
+        .command(&table.code, caster as u32, command.clone())
+        .unwrap();
+    table
+        .battle
+        .act(
+            &actions,
+            caster,
+            command.action,
+            command.target.map(|target| target as usize),
+        )
+        .unwrap();
+    table.battle.advance_to_turn();
+
+    table.rooms.leave(&table.code, 1);
+
+    let (sender, mut receiver) = mpsc::channel(8);
+    assert_eq!(table.rooms.join(&table.code, handshake, sender), Ok(1));
+
+    let snapshot = match received(&mut receiver).as_slice() {
+        [ServerMessage::Welcome { .. }, ServerMessage::Snapshot(snapshot)] => snapshot.clone(),
+        other => panic!("Expected a welcome and snapshot, got {:?}", other),
+    };
+
+    assert_eq!(snapshot.next_sequence, 1);
+    assert_eq!(snapshot.battle, table.battle);
+
+    // The next event carries on where the snapshot left off
+    let mut cursor = EventCursor::from_snapshot(&snapshot);
+    let next = table.battle.current().unwrap();
+    table
+        .rooms
+        .command(&table.code, next as u32, punch(&table, next))
+        .unwrap();
+
+    match received(&mut receiver).as_slice() {
+        [ServerMessage::Event { sequence, .. }] => {
+            assert!(cursor.accept(*sequence).unwrap())
+        }
+        other => panic!("Expected an event, got {:?}", other),
+    }
+}
+
+//====================================================================
+// Commands
+
+#[test]
+fn commands_are_played_and_relayed_to_everyone() {
+    let mut table = table(2);
+
+    let caster = table.battle.current().unwrap();
+    let command = punch(&table, caster);
+
+    assert_eq!(
+        table
+            .rooms
+            .command(&table.code, caster as u32, command.clone()),
+        Ok(())
+    );
+
+    table.players.iter_mut().for_each(|player| {
+        assert!(matches!(
+            received(player).as_slice(),
+            [ServerMessage::Event { sequence: 0, command: relayed }] if *relayed == command
+        ));
+    });
+}
+
+#[test]
+fn commands_for_the_other_side_are_refused() {
+    let mut table = table(2);
+
+    let caster = table.battle.current().unwrap();
+    let other_player = 1 - caster as u32;
+
+    assert_eq!(
+        table
+            .rooms
+            .command(&table.code, other_player, punch(&table, caster)),
+        Err(ActionError::NotTheirTurn(caster))
+    );
+    assert!(table
+        .players
+        .iter_mut()
+        .all(|player| received(player).is_empty()));
+}
+
+#[test]
+fn commands_the_battle_refuses_arent_relayed() {
+    let mut table = table(2);
+
+    let caster = table.battle.current().unwrap();
+    let waiting = 1 - caster;
+
+    // Out of turn
+    assert_eq!(
+        table
+            .rooms
+            .command(&table.code, waiting as u32, punch(&table, waiting)),
+        Err(ActionError::NotTheirTurn(waiting))
+    );
+
+    // On themselves
+    let mut command = punch(&table, caster);
+    command.target = Some(caster as u32);
+    assert_eq!(
+        table.rooms.command(&table.code, caster as u32, command),
+        Err(ActionError::IllegalTarget)
+    );
+
+    assert!(table
+        .players
+        .iter_mut()
+        .all(|player| received(player).is_empty()));
+}
+
+#[test]
+fn battles_end_when_a_side_falls() {
+    let mut table = table(1);
+
+    let caster = table.battle.current().unwrap();
+    table
+        .rooms
+        .command(&table.code, caster as u32, punch(&table, caster))
+        .unwrap();
+
+    table.players.iter_mut().for_each(|player| {
+        assert!(matches!(
+            received(player).as_slice(),
+            [
+                ServerMessage::Event { sequence: 0, .. },
+                ServerMessage::BattleEnded { won }
+            ] if *won == (caster == 0)
+        ));
+    });
+
+    // Nothing more to play
+    assert_eq!(
+        table
+            .rooms
+            .command(&table.code, caster as u32, punch(&table, caster)),
+        Err(ActionError::NotTheirTurn(caster))
+    );
+}
+
+//====================================================================
+// Timeouts
+
+#[test]
+fn slow_turns_are_skipped() {
+    let mut table = table(2);
+
+    let caster = table.battle.current().unwrap();
+
+    // Still inside the timeout
+    table.rooms.expire_turns(Instant::now());
+    assert!(received(&mut table.players[0]).is_empty());
+
+    table
+        .rooms
+        .expire_turns(Instant::now() + TURN_TIMEOUT + Duration::from_secs(1));
+
+    table.players.iter_mut().for_each(|player| {
+        assert!(matches!(
+            received(player).as_slice(),
+            [ServerMessage::TurnTimedOut { sequence: 0 }]
+        ));
+    });
+
+    // Their turn was lost
+    table.battle.advance_to_turn();
+    let next = table.battle.current().unwrap();
+    assert_ne!(next, caster);
+    assert_eq!(
+        table
+            .rooms
+            .command(&table.code, next as u32, punch(&table, next)),
+        Ok(())
+    );
+}
+
+#[test]
+fn players_falling_behind_are_dropped() {
+    let setup = setup(100);
+    let handshake = Handshake::new(setup.data_hash());
+    let mut rooms = Rooms::new(TURN_TIMEOUT);
+
+    let (first, _first_receiver) = mpsc::channel(64);
+    let (code, _) = rooms.create(handshake, setup, first).unwrap();
+
+    // Room for the welcome and snapshot and nothing else
+    let (slow, _slow_receiver) = mpsc::channel(2);
+    assert_eq!(rooms.join(&code, handshake, slow), Ok(1));
+
+    rooms.expire_turns(Instant::now() + TURN_TIMEOUT * 2);
+
+    // Their seat is free again
+    let (sender, _receiver) = mpsc::channel(8);
+    assert_eq!(rooms.join(&code, handshake, sender), Ok(1));
+}
+
+//====================================================================
@@ -0,0 +1,109 @@
+//====================================================================
+
+use common::{PhysicalSize, Transform};
+use cosmic_text::Metrics;
+use criterion::{criterion_group, criterion_main, Criterion};
+use hecs::World;
+use renderer::{
+    pipelines::texture_pipeline::{Sprite, SpriteLayer},
+    testing::HeadlessHarness,
+    text_shared::{self, TextBuffer, TextBufferDescriptor},
+};
+
+//====================================================================
+
+const SPRITE_COUNT: u32 = 10_000;
+
+const PARAGRAPH: &str = "The quick brown fox jumps over the lazy dog while the party \
+    waits for its turn. Speed decides who acts first, but luck still has a say. ";
+
+fn harness() -> Option<HeadlessHarness> {
+    let harness = HeadlessHarness::new(PhysicalSize::new(256, 256));
+
+    if harness.is_none() {
+        eprintln!("No wgpu adapter available - skipping renderer benchmarks");
+    }
+
+    harness
+}
+
+//====================================================================
+
+fn texture_prep(c: &mut Criterion) {
+    let harness = match harness() {
+        Some(harness) => harness,
+        None => return,
+    };
+
+    let mut world = World::new();
+
+    (0..SPRITE_COUNT).for_each(|index| {
+        world.spawn((
+            Transform::from_translation(((index % 100) as f32, (index / 100) as f32, 0.)),
+            Sprite::new(harness.default_texture.get(), glam::Vec2::ONE),
+            SpriteLayer((index % 4) as i16),
+        ));
+    });
+
+    let mut renderer = harness.texture_renderer();
+
+    c.bench_function("texture_prep_10k_sprites", |b| {
+        b.iter(|| harness.prep_texture_renderer(&mut renderer, &mut world))
+    });
+}
+
+fn text_prep(c: &mut Criterion) {
+    let mut harness = match harness() {
+        Some(harness) => harness,
+        None => return,
+    };
+
+    let document = PARAGRAPH.repeat(50);
+    let edited = format!("{}!", document);
+
+    let mut buffer = TextBuffer::new(
+        &harness.device,
+        &mut harness.text_res.font_system,
+        &TextBufferDescriptor {
+            metrics: Metrics::new(14., 16.),
+            text: &document,
+            ..Default::default()
+        },
+    );
+
+    let mut toggle = false;
+
+    c.bench_function("text_prep_long_document", |b| {
+        b.iter(|| {
+            // Alternate text so every iteration goes through the full prep
+            toggle = !toggle;
+            let text = match toggle {
+                true => &edited,
+                false => &document,
+            };
+
+            let text_res = &mut harness.text_res;
+            buffer.set_text(&mut text_res.font_system, text);
+
+            let vertices = text_shared::prep(
+                &harness.device,
+                &harness.queue,
+                harness.shared.recorder(),
+                &mut text_res.font_system,
+                &mut text_res.swash_cache,
+                &mut text_res.text_atlas,
+                &mut buffer,
+            );
+
+            text_res.text_atlas.post_render_trim();
+            vertices
+        })
+    });
+}
+
+//====================================================================
+
+criterion_group!(benches, texture_prep, text_prep);
+criterion_main!(benches);
+
+//====================================================================
@@ -0,0 +1,458 @@
+//====================================================================
+
+use std::sync::Arc;
+
+use common::Transform;
+use hecs::{Entity, World};
+
+use crate::error::SkeletonError;
+
+//====================================================================
+
+/// Most joints a skeleton can have. Matches skinned.wgsl.
+pub const MAX_JOINTS: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct Joint {
+    pub name: String,
+    pub parent: Option<usize>,
+    pub inverse_bind: glam::Mat4,
+    pub rest: Transform,
+}
+
+/// Joint hierarchy for a skinned mesh. Parents must come before their children.
+#[derive(Debug, Clone)]
+pub struct Skeleton {
+    joints: Vec<Joint>,
+}
+
+impl Skeleton {
+    pub fn new(joints: Vec<Joint>) -> Result<Self, SkeletonError> {
+        if joints.len() > MAX_JOINTS {
+            return Err(SkeletonError::TooManyJoints(joints.len()));
+        }
+
+        // Joints are posed in order, so each parent has to be posed first
+        let misordered = joints.iter().enumerate().find_map(|(index, joint)| {
+            joint
+                .parent
+                .filter(|parent| *parent >= index)
+                .map(|parent| SkeletonError::ParentOrder {
+                    joint: index,
+                    parent,
+                })
+        });
+
+        if let Some(err) = misordered {
+            return Err(err);
+        }
+
+        Ok(Self { joints })
+    }
+
+    #[inline]
+    pub fn joints(&self) -> &[Joint] {
+        &self.joints
+    }
+
+    #[inline]
+    pub fn joint_count(&self) -> usize {
+        self.joints.len()
+    }
+
+    pub fn find_joint(&self, name: &str) -> Option<usize> {
+        self.joints.iter().position(|joint| joint.name == name)
+    }
+}
+
+//====================================================================
+
+#[derive(Debug, Clone)]
+pub struct Keyframe {
+    pub time: f32,
+    pub transform: Transform,
+}
+
+#[derive(Debug, Clone)]
+pub struct JointChannel {
+    pub joint: usize,
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl JointChannel {
+    // Sample channel at time. Keyframes are expected to be sorted by time.
+    fn sample(&self, time: f32) -> Option<Transform> {
+        let first = self.keyframes.first()?;
+        let last = self.keyframes.last()?;
+
+        if time <= first.time {
+            return Some(first.transform.clone());
+        }
+
+        if time >= last.time {
+            return Some(last.transform.clone());
+        }
+
+        let next = self.keyframes.iter().position(|key| key.time > time)?;
+        let (a, b) = (&self.keyframes[next - 1], &self.keyframes[next]);
+
+        let s = (time - a.time) / (b.time - a.time);
+
+        Some(lerp_transform(&a.transform, &b.transform, s))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AnimationClip {
+    pub name: String,
+    pub duration: f32,
+    pub channels: Vec<JointChannel>,
+}
+
+impl AnimationClip {
+    // Sample local joint transforms, using the rest pose for joints without a channel
+    fn sample(&self, skeleton: &Skeleton, time: f32, pose: &mut [Transform]) {
+        skeleton
+            .joints
+            .iter()
+            .zip(pose.iter_mut())
+            .for_each(|(joint, local)| *local = joint.rest.clone());
+
+        self.channels.iter().for_each(|channel| {
+            if let (Some(local), Some(sampled)) =
+                (pose.get_mut(channel.joint), channel.sample(time))
+            {
+                *local = sampled;
+            }
+        });
+    }
+}
+
+//====================================================================
+
+#[derive(Debug, Clone)]
+struct PlayingClip {
+    clip: Arc<AnimationClip>,
+    time: f32,
+}
+
+impl PlayingClip {
+    fn advance(&mut self, delta: f32, looping: bool) {
+        self.time += delta;
+
+        if self.clip.duration <= 0. {
+            self.time = 0.;
+            return;
+        }
+
+        self.time = match looping {
+            true => self.time.rem_euclid(self.clip.duration),
+            false => self.time.min(self.clip.duration),
+        };
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Crossfade {
+    from: PlayingClip,
+    elapsed: f32,
+    duration: f32,
+}
+
+/// Component driving a skeleton through animation clips with optional crossfading.
+#[derive(Debug, Clone)]
+pub struct AnimationPlayer {
+    pub skeleton: Arc<Skeleton>,
+    pub speed: f32,
+    pub looping: bool,
+
+    current: Option<PlayingClip>,
+    crossfade: Option<Crossfade>,
+}
+
+impl AnimationPlayer {
+    pub fn new(skeleton: Arc<Skeleton>) -> Self {
+        Self {
+            skeleton,
+            speed: 1.,
+            looping: true,
+            current: None,
+            crossfade: None,
+        }
+    }
+
+    #[inline]
+    pub fn play(&mut self, clip: Arc<AnimationClip>) {
+        self.current = Some(PlayingClip { clip, time: 0. });
+        self.crossfade = None;
+    }
+
+    /// Blend from the current clip into a new one over `duration` seconds.
+    pub fn crossfade_to(&mut self, clip: Arc<AnimationClip>, duration: f32) {
+        let next = PlayingClip { clip, time: 0. };
+
+        match self.current.replace(next) {
+            Some(from) if duration > 0. => {
+                self.crossfade = Some(Crossfade {
+                    from,
+                    elapsed: 0.,
+                    duration,
+                })
+            }
+            _ => self.crossfade = None,
+        }
+    }
+
+    #[inline]
+    pub fn current_clip(&self) -> Option<&str> {
+        self.current
+            .as_ref()
+            .map(|playing| playing.clip.name.as_str())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        match &self.current {
+            Some(playing) => !self.looping && playing.time >= playing.clip.duration,
+            None => true,
+        }
+    }
+
+    fn advance(&mut self, delta: f32) {
+        let delta = delta * self.speed;
+
+        if let Some(current) = &mut self.current {
+            current.advance(delta, self.looping);
+        }
+
+        if let Some(crossfade) = &mut self.crossfade {
+            crossfade.from.advance(delta, self.looping);
+            crossfade.elapsed += delta;
+
+            if crossfade.elapsed >= crossfade.duration {
+                self.crossfade = None;
+            }
+        }
+    }
+
+    // Sample blended local pose for all joints
+    fn sample_pose(&self) -> Vec<Transform> {
+        let joint_count = self.skeleton.joint_count();
+        let mut pose = vec![Transform::default(); joint_count];
+
+        let current = match &self.current {
+            Some(current) => current,
+            None => {
+                self.skeleton
+                    .joints
+                    .iter()
+                    .zip(pose.iter_mut())
+                    .for_each(|(joint, local)| *local = joint.rest.clone());
+                return pose;
+            }
+        };
+
+        current.clip.sample(&self.skeleton, current.time, &mut pose);
+
+        if let Some(crossfade) = &self.crossfade {
+            let mut from_pose = vec![Transform::default(); joint_count];
+            crossfade
+                .from
+                .clip
+                .sample(&self.skeleton, crossfade.from.time, &mut from_pose);
+
+            let weight = (crossfade.elapsed / crossfade.duration).clamp(0., 1.);
+
+            pose.iter_mut()
+                .zip(from_pose.iter())
+                .for_each(|(to, from)| *to = lerp_transform(from, to, weight));
+        }
+
+        pose
+    }
+}
+
+//====================================================================
+
+/// Final skinning matrices for each joint, ready to be uploaded per instance.
+#[derive(Debug, Clone, Default)]
+pub struct JointPalette(pub Vec<glam::Mat4>);
+
+pub fn compute_joint_palette(
+    skeleton: &Skeleton,
+    pose: &[Transform],
+    palette: &mut Vec<glam::Mat4>,
+) {
+    let mut globals = Vec::with_capacity(skeleton.joint_count());
+
+    skeleton
+        .joints
+        .iter()
+        .zip(pose.iter())
+        .for_each(|(joint, local)| {
+            let local = local.to_matrix();
+            let global = match joint.parent {
+                Some(parent) => globals[parent] * local,
+                None => local,
+            };
+            globals.push(global);
+        });
+
+    palette.clear();
+    palette.extend(
+        skeleton
+            .joints
+            .iter()
+            .zip(globals.iter())
+            .map(|(joint, global)| *global * joint.inverse_bind),
+    );
+}
+
+pub fn tick_animations(world: &mut World, delta_seconds: f32) {
+    let mut to_insert = Vec::new();
+
+    world
+        .query_mut::<(&mut AnimationPlayer, Option<&mut JointPalette>)>()
+        .into_iter()
+        .for_each(|(entity, (player, palette))| {
+            player.advance(delta_seconds);
+
+            let pose = player.sample_pose();
+
+            match palette {
+                Some(palette) => compute_joint_palette(&player.skeleton, &pose, &mut palette.0),
+                None => {
+                    let mut palette = JointPalette::default();
+                    compute_joint_palette(&player.skeleton, &pose, &mut palette.0);
+                    to_insert.push((entity, palette));
+                }
+            }
+        });
+
+    to_insert.into_iter().for_each(|(entity, palette)| {
+        world.insert_one(entity, palette).ok();
+    });
+}
+
+//====================================================================
+
+#[derive(Debug, Clone, Copy)]
+struct IdleApplied {
+    base_translation: glam::Vec3,
+    base_scale: glam::Vec3,
+    translation: glam::Vec3,
+    scale: glam::Vec3,
+}
+
+/// Procedural idle motion for sprites that would otherwise stand still. Bobs
+/// the entity up and down and squashes it slightly as it breathes.
+///
+/// The motion is applied on top of wherever the entity is placed, so moving it
+/// by setting its [`Transform`] still works.
+#[derive(Debug, Clone)]
+pub struct IdleAnimation {
+    /// Height of the bob in world units.
+    pub bob_height: f32,
+    /// Fraction the sprite stretches by at the top of a breath.
+    pub breathing: f32,
+    /// Breaths per second.
+    pub speed: f32,
+    /// Offset into the cycle in radians so groups don't move in lockstep.
+    pub phase: f32,
+
+    time: f32,
+    applied: Option<IdleApplied>,
+}
+
+impl IdleAnimation {
+    #[inline]
+    pub fn new(phase: f32) -> Self {
+        Self {
+            bob_height: 2.,
+            breathing: 0.03,
+            speed: 0.5,
+            phase,
+            time: 0.,
+            applied: None,
+        }
+    }
+
+    /// Phase derived from an entity so each gets a stable, different offset.
+    #[inline]
+    pub fn phase_for(entity: Entity) -> f32 {
+        // Golden ratio spreads consecutive ids evenly around the cycle
+        (entity.id() as f32 * 0.618034).fract() * std::f32::consts::TAU
+    }
+
+    fn apply(&mut self, transform: &mut Transform) {
+        // Anything other than what was last written means the entity was moved
+        let (base_translation, base_scale) = match self.applied {
+            Some(applied)
+                if applied.translation == transform.translation
+                    && applied.scale == transform.scale =>
+            {
+                (applied.base_translation, applied.base_scale)
+            }
+            _ => (transform.translation, transform.scale),
+        };
+
+        let cycle = self.time * self.speed * std::f32::consts::TAU + self.phase;
+
+        // Stay above the base position so characters don't sink into the floor
+        let bob = (cycle.sin() + 1.) * 0.5 * self.bob_height;
+
+        // Breathe in slightly ahead of the bob
+        let breath = (cycle + std::f32::consts::FRAC_PI_4).sin() * self.breathing;
+
+        transform.translation = base_translation + glam::Vec3::Y * bob;
+        transform.scale = base_scale * glam::vec3(1. - breath * 0.5, 1. + breath, 1.);
+
+        self.applied = Some(IdleApplied {
+            base_translation,
+            base_scale,
+            translation: transform.translation,
+            scale: transform.scale,
+        });
+    }
+
+    // Transform without any idle motion applied
+    fn restore(&self, transform: &mut Transform) {
+        if let Some(applied) = self.applied {
+            if applied.translation == transform.translation && applied.scale == transform.scale {
+                transform.translation = applied.base_translation;
+                transform.scale = applied.base_scale;
+            }
+        }
+    }
+}
+
+pub fn tick_idle_animations(world: &mut World, delta_seconds: f32) {
+    world
+        .query_mut::<(&mut IdleAnimation, &mut Transform)>()
+        .into_iter()
+        .for_each(|(_, (idle, transform))| {
+            idle.time += delta_seconds;
+            idle.apply(transform);
+        });
+}
+
+/// Remove an entity's [`IdleAnimation`], leaving it back at its resting
+/// transform.
+pub fn stop_idle_animation(world: &mut World, entity: Entity) {
+    if let Ok(idle) = world.remove_one::<IdleAnimation>(entity) {
+        if let Ok(mut transform) = world.get::<&mut Transform>(entity) {
+            idle.restore(&mut transform);
+        }
+    }
+}
+
+//====================================================================
+
+fn lerp_transform(a: &Transform, b: &Transform, s: f32) -> Transform {
+    Transform {
+        translation: a.translation.lerp(b.translation, s),
+        rotation: a.rotation.slerp(b.rotation, s),
+        scale: a.scale.lerp(b.scale, s),
+    }
+}
+
+//====================================================================
@@ -0,0 +1,56 @@
+//====================================================================
+
+use common::Transform;
+use hecs::World;
+
+use crate::pipelines::texture_pipeline::{Sprite, SpriteLayer};
+
+//====================================================================
+
+/// Draw layer for backgrounds, below everything else.
+pub const BACKGROUND_LAYER: SpriteLayer = SpriteLayer(i16::MIN);
+
+/// Moves a sprite with the camera for parallax and scrolls its texture.
+/// Give the entity [`BACKGROUND_LAYER`] and place it behind the scene.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BackgroundLayer {
+    /// Position when the camera is at the origin.
+    pub anchor: glam::Vec3,
+    /// How much of the camera's movement the layer follows on each axis. 0
+    /// stays put in the world, 1 moves with the camera so it looks infinitely
+    /// far away.
+    pub parallax: glam::Vec3,
+    /// Texture scroll in whole textures per second.
+    pub scroll_speed: glam::Vec2,
+}
+
+impl BackgroundLayer {
+    #[inline]
+    pub fn new(anchor: glam::Vec3, parallax: f32) -> Self {
+        Self {
+            anchor,
+            parallax: glam::Vec3::splat(parallax),
+            scroll_speed: glam::Vec2::ZERO,
+        }
+    }
+
+    #[inline]
+    pub fn with_scroll(mut self, scroll_speed: glam::Vec2) -> Self {
+        self.scroll_speed = scroll_speed;
+        self
+    }
+}
+
+pub fn tick_backgrounds(world: &mut World, camera_position: glam::Vec3, delta_seconds: f32) {
+    world
+        .query_mut::<(&BackgroundLayer, &mut Transform, &mut Sprite)>()
+        .into_iter()
+        .for_each(|(_, (layer, transform, sprite))| {
+            transform.translation = layer.anchor + camera_position * layer.parallax;
+
+            // Kept within a single texture so precision doesn't drift over time
+            sprite.uv_offset = (sprite.uv_offset + layer.scroll_speed * delta_seconds).fract();
+        });
+}
+
+//====================================================================
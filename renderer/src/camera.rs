@@ -1,36 +1,31 @@
 //====================================================================
 
-use wgpu::util::DeviceExt;
+use crate::shared::SharedRenderResources;
 
 //====================================================================
 
+/// Camera used to draw the scene. Uploaded to the frame bind group owned by
+/// [`SharedRenderResources`].
 pub struct Camera {
     pub camera: PerspectiveCamera,
-    pub data: CameraData,
+    pub orthographic: Option<OrthographicProjection>,
 }
 
 impl Camera {
     #[inline]
-    pub fn new(device: &wgpu::Device, camera: PerspectiveCamera) -> Self {
+    pub fn new(camera: PerspectiveCamera) -> Self {
         Self {
-            data: CameraData::new(device, &camera),
+            orthographic: None,
             camera,
         }
     }
 
     #[inline]
-    pub fn update_camera(&self, queue: &wgpu::Queue) {
-        self.data.update_camera(queue, &self.camera);
-    }
-
-    #[inline]
-    pub fn bind_group_layout(&self) -> &wgpu::BindGroupLayout {
-        self.data.bind_group_layout()
-    }
-
-    #[inline]
-    pub fn bind_group(&self) -> &wgpu::BindGroup {
-        self.data.bind_group()
+    pub fn update_camera(&self, queue: &wgpu::Queue, shared: &SharedRenderResources) {
+        match self.orthographic {
+            Some(projection) => shared.update_camera(queue, &(projection, &self.camera)),
+            None => shared.update_camera(queue, &self.camera),
+        }
     }
 
     #[inline]
@@ -41,82 +36,6 @@ impl Camera {
 
 //====================================================================
 
-pub struct CameraData {
-    camera_buffer: wgpu::Buffer,
-    camera_bind_group_layout: wgpu::BindGroupLayout,
-    camera_bind_group: wgpu::BindGroup,
-}
-
-impl CameraData {
-    pub fn new<C: CameraUniform>(device: &wgpu::Device, camera: &C) -> Self {
-        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Camera buffer"),
-            contents: bytemuck::cast_slice(&[camera.into_uniform()]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
-
-        let camera_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Camera Bind Group Layout"),
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                }],
-            });
-
-        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Camera Bind Group"),
-            layout: &camera_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::Buffer(camera_buffer.as_entire_buffer_binding()),
-            }],
-        });
-
-        Self {
-            camera_buffer,
-            camera_bind_group_layout,
-            camera_bind_group,
-        }
-    }
-
-    #[inline]
-    pub fn update_camera<C: CameraUniform>(&self, queue: &wgpu::Queue, camera: &C) {
-        // queue
-        //     .write_buffer_with(
-        //         &self.camera_buffer,
-        //         0,
-        //         wgpu::BufferSize::new(std::mem::size_of::<CameraUniformRaw>() as u64).unwrap(),
-        //     )
-        //     .unwrap()
-        //     .copy_from_slice(bytemuck::cast_slice(&[camera.into_uniform()]));
-
-        queue.write_buffer(
-            &self.camera_buffer,
-            0,
-            bytemuck::cast_slice(&[camera.into_uniform()]),
-        );
-    }
-
-    #[inline]
-    pub fn bind_group_layout(&self) -> &wgpu::BindGroupLayout {
-        &self.camera_bind_group_layout
-    }
-
-    #[inline]
-    pub fn bind_group(&self) -> &wgpu::BindGroup {
-        &self.camera_bind_group
-    }
-}
-
-//====================================================================
-
 pub trait CameraUniform {
     fn into_uniform(&self) -> CameraUniformRaw;
 }
@@ -141,6 +60,7 @@ impl CameraUniformRaw {
 //--------------------------------------------------
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OrthographicCamera {
     pub left: f32,
     pub right: f32,
@@ -232,7 +152,70 @@ impl OrthographicCamera {
 
 //--------------------------------------------------
 
+/// Orthographic projection that reuses the position and rotation of a
+/// [`PerspectiveCamera`]. `height` is the visible height in world units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrthographicProjection {
+    pub height: f32,
+}
+
+impl CameraUniform for (OrthographicProjection, &PerspectiveCamera) {
+    fn into_uniform(&self) -> CameraUniformRaw {
+        let (projection, camera) = self;
+
+        let half_height = projection.height / 2.;
+        let half_width = half_height * camera.aspect;
+
+        let projection_matrix = glam::Mat4::orthographic_lh(
+            -half_width,
+            half_width,
+            -half_height,
+            half_height,
+            camera.z_near,
+            camera.z_far,
+        );
+
+        CameraUniformRaw::new(projection_matrix * camera.view_matrix(), camera.translation)
+    }
+}
+
+/// Snaps world positions to the virtual pixel grid of an orthographic camera.
+#[derive(Debug, Clone, Copy)]
+pub struct PixelSnap {
+    pub unit: f32,
+    pub origin: glam::Vec3,
+    pub rotation: glam::Quat,
+}
+
+impl PixelSnap {
+    pub fn new(camera: &PerspectiveCamera, unit: f32) -> Self {
+        // View matrix holds the inverse of the camera rotation
+        let rotation = glam::Quat::from_mat4(&camera.view_matrix()).inverse();
+
+        Self {
+            unit,
+            origin: camera.translation,
+            rotation,
+        }
+    }
+
+    pub fn snap(&self, position: glam::Vec3) -> glam::Vec3 {
+        let local = self.rotation.inverse() * (position - self.origin);
+        let snapped = glam::vec3(
+            (local.x / self.unit).round() * self.unit,
+            (local.y / self.unit).round() * self.unit,
+            local.z,
+        );
+
+        self.origin + self.rotation * snapped
+    }
+}
+
+//--------------------------------------------------
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PerspectiveCamera {
     pub up: glam::Vec3,
     pub aspect: f32,
@@ -267,15 +250,69 @@ impl CameraUniform for PerspectiveCamera {
 
 impl PerspectiveCamera {
     fn get_projection(&self) -> glam::Mat4 {
-        let forward = (self.rotation * glam::Vec3::Z).normalize();
-
         let projection_matrix =
             glam::Mat4::perspective_lh(self.fovy, self.aspect, self.z_near, self.z_far);
 
-        let view_matrix =
-            glam::Mat4::look_at_lh(self.translation, self.translation + forward, self.up);
+        projection_matrix * self.view_matrix()
+    }
+
+    fn view_matrix(&self) -> glam::Mat4 {
+        let forward = (self.rotation * glam::Vec3::Z).normalize();
+
+        glam::Mat4::look_at_lh(self.translation, self.translation + forward, self.up)
+    }
+
+    /// Where `point` lands on screen in normalized device coordinates, -1 to 1
+    /// across the view with y up. None if it's behind the camera.
+    pub fn project(&self, point: glam::Vec3) -> Option<glam::Vec2> {
+        let clip = self.get_projection() * point.extend(1.);
+
+        match clip.w > 0. {
+            true => Some(clip.truncate().truncate() / clip.w),
+            false => None,
+        }
+    }
+
+    /// Origin and direction of the ray from the camera through `ndc`, a point
+    /// on screen in normalized device coordinates as given by [`Self::project`].
+    pub fn ray(&self, ndc: glam::Vec2) -> (glam::Vec3, glam::Vec3) {
+        let inverse = self.get_projection().inverse();
+
+        let near = inverse.project_point3(ndc.extend(0.));
+        let far = inverse.project_point3(ndc.extend(1.));
+
+        (near, (far - near).normalize())
+    }
+
+    /// Translation that keeps the current rotation and field of view while
+    /// fitting every point in view, with `padding` world units spare around
+    /// them. None if there are no points.
+    pub fn frame_points(&self, points: &[glam::Vec3], padding: f32) -> Option<glam::Vec3> {
+        let (min, max) = points.iter().fold(None, |bounds, point| match bounds {
+            Some((min, max)) => Some((point.min(min), point.max(max))),
+            None => Some((*point, *point)),
+        })?;
+        let center = (min + max) / 2.;
+
+        // Same axes as the view matrix
+        let forward = (self.rotation * glam::Vec3::Z).normalize();
+        let right = self.up.cross(forward).normalize();
+        let up = forward.cross(right);
+
+        let tan_y = (self.fovy / 2.).tan();
+        let tan_x = tan_y * self.aspect;
+
+        let distance = points
+            .iter()
+            .map(|point| {
+                let local = *point - center;
+                let (x, y, z) = (local.dot(right), local.dot(up), local.dot(forward));
+
+                ((x.abs() + padding) / tan_x).max((y.abs() + padding) / tan_y) - z
+            })
+            .fold(self.z_near, f32::max);
 
-        projection_matrix * view_matrix
+        Some(center - forward * distance)
     }
 
     pub fn forward(&self) -> glam::Vec3 {
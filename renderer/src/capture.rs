@@ -0,0 +1,194 @@
+//====================================================================
+
+use std::{cell::RefCell, fmt::Write};
+
+//====================================================================
+
+/// Something the renderer did while a frame was being captured.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaptureCommand {
+    /// Data written into an existing buffer.
+    WriteBuffer {
+        label: String,
+        bytes: u64,
+    },
+    /// Buffer created, or recreated to fit more data.
+    CreateBuffer {
+        label: String,
+        bytes: u64,
+    },
+    /// Pixels uploaded to a texture.
+    WriteTexture {
+        label: String,
+        bytes: u64,
+    },
+    BeginPass {
+        label: &'static str,
+    },
+    SetPipeline {
+        label: &'static str,
+    },
+    SetBindGroup {
+        index: u32,
+        label: String,
+    },
+    Draw {
+        vertices: u32,
+        instances: u32,
+        indexed: bool,
+    },
+    /// Draw with its instance count decided on the GPU.
+    DrawIndirect {
+        max_instances: u32,
+    },
+    /// Several indexed draws submitted with a single call.
+    MultiDrawIndirect {
+        draws: u32,
+        instances: u32,
+    },
+    Dispatch {
+        workgroups: u32,
+    },
+}
+
+/// Every command recorded over a single frame, in the order they happened.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrameCapture {
+    pub frame: u32,
+    pub commands: Vec<CaptureCommand>,
+}
+
+impl FrameCapture {
+    #[inline]
+    pub fn draw_calls(&self) -> usize {
+        self.commands
+            .iter()
+            .filter(|command| {
+                matches!(
+                    command,
+                    CaptureCommand::Draw { .. }
+                        | CaptureCommand::DrawIndirect { .. }
+                        | CaptureCommand::MultiDrawIndirect { .. }
+                )
+            })
+            .count()
+    }
+
+    /// Bytes written to buffers and textures, including newly created buffers.
+    pub fn bytes_uploaded(&self) -> u64 {
+        self.commands
+            .iter()
+            .map(|command| match command {
+                CaptureCommand::WriteBuffer { bytes, .. }
+                | CaptureCommand::CreateBuffer { bytes, .. }
+                | CaptureCommand::WriteTexture { bytes, .. } => *bytes,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Readable breakdown of the frame, one command per line with draws
+    /// indented under their pipeline.
+    pub fn report(&self) -> String {
+        let mut output = String::new();
+
+        writeln!(
+            output,
+            "Frame {} capture - {} commands, {} draw calls, {} bytes uploaded",
+            self.frame,
+            self.commands.len(),
+            self.draw_calls(),
+            self.bytes_uploaded()
+        )
+        .ok();
+
+        self.commands.iter().for_each(|command| {
+            match command {
+                CaptureCommand::WriteBuffer { label, bytes } => {
+                    writeln!(output, "  Write buffer   '{}' ({} bytes)", label, bytes)
+                }
+                CaptureCommand::CreateBuffer { label, bytes } => {
+                    writeln!(output, "  Create buffer  '{}' ({} bytes)", label, bytes)
+                }
+                CaptureCommand::WriteTexture { label, bytes } => {
+                    writeln!(output, "  Write texture  '{}' ({} bytes)", label, bytes)
+                }
+                CaptureCommand::BeginPass { label } => writeln!(output, "Pass '{}'", label),
+                CaptureCommand::SetPipeline { label } => writeln!(output, "  Pipeline '{}'", label),
+                CaptureCommand::SetBindGroup { index, label } => {
+                    writeln!(output, "    Bind group {} = {}", index, label)
+                }
+                CaptureCommand::Draw {
+                    vertices,
+                    instances,
+                    indexed,
+                } => {
+                    let kind = match indexed {
+                        true => "Draw indexed",
+                        false => "Draw",
+                    };
+                    writeln!(
+                        output,
+                        "    {} - {} vertices x {} instances",
+                        kind, vertices, instances
+                    )
+                }
+                CaptureCommand::DrawIndirect { max_instances } => {
+                    writeln!(
+                        output,
+                        "    Draw indirect - up to {} instances",
+                        max_instances
+                    )
+                }
+                CaptureCommand::MultiDrawIndirect { draws, instances } => {
+                    writeln!(
+                        output,
+                        "    Multi draw indirect - {} draws, {} instances",
+                        draws, instances
+                    )
+                }
+                CaptureCommand::Dispatch { workgroups } => {
+                    writeln!(output, "    Dispatch - {} workgroups", workgroups)
+                }
+            }
+            .ok();
+        });
+
+        output
+    }
+}
+
+//====================================================================
+
+/// Collects commands while a frame is being captured. Owned by
+/// [`SharedRenderResources`](crate::shared::SharedRenderResources) and handed
+/// to everything that records into it.
+#[derive(Debug, Default)]
+pub struct FrameRecorder {
+    // Only set while a frame is being captured
+    capture: RefCell<Option<FrameCapture>>,
+}
+
+impl FrameRecorder {
+    pub(crate) fn start(&self, frame: u32) {
+        *self.capture.borrow_mut() = Some(FrameCapture {
+            frame,
+            commands: Vec::new(),
+        });
+    }
+
+    pub(crate) fn finish(&self) -> Option<FrameCapture> {
+        self.capture.borrow_mut().take()
+    }
+
+    /// Record a command if a frame is being captured. The command is only
+    /// built when it's needed.
+    #[inline]
+    pub(crate) fn record(&self, command: impl FnOnce() -> CaptureCommand) {
+        if let Some(capture) = self.capture.borrow_mut().as_mut() {
+            capture.commands.push(command());
+        }
+    }
+}
+
+//====================================================================
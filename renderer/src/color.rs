@@ -0,0 +1,61 @@
+//====================================================================
+
+// Colors given to the renderer through components (`Sprite::color`,
+// `Ui3d::menu_color`, text colors) are sRGB encoded, matching how they would be
+// picked in an image editor. Shaders blend in linear space when drawing into an
+// sRGB or HDR target, so instance colors are converted with these helpers
+// during prep. Other targets store whatever the shader writes, so colors are
+// passed through as is. `Renderer::clear_color` is passed straight to wgpu and
+// is already linear.
+
+//====================================================================
+
+#[inline]
+pub fn srgb_to_linear(value: f32) -> f32 {
+    match value <= 0.04045 {
+        true => value / 12.92,
+        false => ((value + 0.055) / 1.055).powf(2.4),
+    }
+}
+
+#[inline]
+pub fn linear_to_srgb(value: f32) -> f32 {
+    match value <= 0.0031308 {
+        true => value * 12.92,
+        false => 1.055 * value.powf(1. / 2.4) - 0.055,
+    }
+}
+
+/// Convert an sRGB color with linear alpha into linear space.
+#[inline]
+pub fn srgba_to_linear(color: [f32; 4]) -> [f32; 4] {
+    [
+        srgb_to_linear(color[0]),
+        srgb_to_linear(color[1]),
+        srgb_to_linear(color[2]),
+        color[3],
+    ]
+}
+
+/// True if shaders drawing into `format` work in linear space. sRGB targets
+/// encode what's written and float targets hold linear HDR values.
+#[inline]
+pub fn is_linear_target(format: wgpu::TextureFormat) -> bool {
+    format.is_srgb()
+        || matches!(
+            format,
+            wgpu::TextureFormat::Rgba16Float | wgpu::TextureFormat::Rgba32Float
+        )
+}
+
+/// Convert an sRGB color with linear alpha for a target, leaving it as is
+/// unless the target is linear. See [`is_linear_target`].
+#[inline]
+pub fn srgba_for_target(color: [f32; 4], linear_target: bool) -> [f32; 4] {
+    match linear_target {
+        true => srgba_to_linear(color),
+        false => color,
+    }
+}
+
+//====================================================================
@@ -0,0 +1,31 @@
+//====================================================================
+
+//====================================================================
+
+#[derive(Debug, thiserror::Error)]
+pub enum RenderError {
+    #[error("Unable to create surface: {0}")]
+    CreateSurface(#[from] wgpu::CreateSurfaceError),
+
+    #[error("No compatible graphics adapter found")]
+    NoAdapter,
+
+    #[error("Unable to request device: {0}")]
+    RequestDevice(#[from] wgpu::RequestDeviceError),
+
+    #[error("Surface does not support any texture formats")]
+    NoSurfaceFormat,
+}
+
+//====================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SkeletonError {
+    #[error("Joint {joint} has parent {parent}, which doesn't come before it")]
+    ParentOrder { joint: usize, parent: usize },
+
+    #[error("Skeleton has {0} joints, more than the {max} that can be skinned", max = crate::animation::MAX_JOINTS)]
+    TooManyJoints(usize),
+}
+
+//====================================================================
@@ -0,0 +1,89 @@
+//====================================================================
+
+use hecs::{Entity, World};
+
+use crate::pipelines::Opacity;
+
+//====================================================================
+
+/// Tweens an entity's [`Opacity`], removing itself once finished and leaving
+/// the opacity at `to`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fade {
+    pub from: f32,
+    pub to: f32,
+    /// Seconds taken to go from `from` to `to`.
+    pub duration: f32,
+    elapsed: f32,
+}
+
+impl Fade {
+    #[inline]
+    pub fn new(from: f32, to: f32, duration: f32) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            elapsed: 0.,
+        }
+    }
+
+    #[inline]
+    pub fn fade_in(duration: f32) -> Self {
+        Self::new(0., 1., duration)
+    }
+
+    #[inline]
+    pub fn fade_out(duration: f32) -> Self {
+        Self::new(1., 0., duration)
+    }
+
+    #[inline]
+    fn progress(&self) -> f32 {
+        match self.duration > 0. {
+            true => (self.elapsed / self.duration).clamp(0., 1.),
+            false => 1.,
+        }
+    }
+}
+
+/// Start fading `entity` from its current opacity to `to`.
+pub fn fade_to(world: &mut World, entity: Entity, to: f32, duration: f32) {
+    let from = world
+        .get::<&Opacity>(entity)
+        .map_or(1., |opacity| opacity.0);
+
+    world.insert_one(entity, Fade::new(from, to, duration)).ok();
+}
+
+pub fn tick_fades(world: &mut World, delta_seconds: f32) {
+    // Fades added to entities without an opacity start from their own `from`
+    let missing = world
+        .query_mut::<&Fade>()
+        .without::<&Opacity>()
+        .into_iter()
+        .map(|(entity, fade)| (entity, fade.from))
+        .collect::<Vec<_>>();
+
+    missing.into_iter().for_each(|(entity, from)| {
+        world.insert_one(entity, Opacity(from)).ok();
+    });
+
+    let finished = world
+        .query_mut::<(&mut Fade, &mut Opacity)>()
+        .into_iter()
+        .filter_map(|(entity, (fade, opacity))| {
+            fade.elapsed += delta_seconds;
+            let progress = fade.progress();
+
+            opacity.0 = fade.from + (fade.to - fade.from) * progress;
+            (progress >= 1.).then_some(entity)
+        })
+        .collect::<Vec<_>>();
+
+    finished.into_iter().for_each(|entity| {
+        world.remove_one::<Fade>(entity).ok();
+    });
+}
+
+//====================================================================
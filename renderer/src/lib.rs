@@ -2,61 +2,388 @@
 
 use std::sync::Arc;
 
-use camera::Camera;
-use common::Size;
+use web_time::{Duration, Instant};
+
+use camera::{Camera, OrthographicProjection, PixelSnap};
+use capture::{CaptureCommand, FrameCapture, FrameRecorder};
+use common::PhysicalSize;
+use error::RenderError;
 use hecs::World;
-use pipelines::{texture_pipeline::TextureRenderer, ui3d_pipeline::Ui3dRenderer};
-use shared::SharedRenderResources;
+use pipelines::{
+    blit_pipeline::BlitRenderer, grid_pipeline::GridRenderer, particle_pipeline::ParticleRenderer,
+    skinned_pipeline::SkinnedRenderer, texture_pipeline::TextureRenderer,
+    ui3d_pipeline::Ui3dRenderer,
+};
+use screenshot::Screenshots;
+use shared::{FrameUniform, SharedRenderResources};
 use text_shared::TextResources;
 use texture::Texture;
-use texture_storage::{DefaultTexture, LoadedTexture};
+use texture_storage::{DefaultTexture, LoadedTexture, TextureRegistry};
+use viewport::{Viewport, VirtualResolution};
 use wgpu::SurfaceTarget;
 
+pub use image;
+pub use wgpu;
+
+pub mod animation;
+pub mod background;
 pub mod camera;
+pub mod capture;
+pub mod color;
+pub mod error;
+pub mod fade;
 pub mod pipelines;
+mod screenshot;
 pub mod shared;
 pub mod text_shared;
 pub mod texture;
 pub mod texture_storage;
 pub mod tools;
+pub mod viewport;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
+//====================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RenderMode {
+    #[default]
+    Standard,
+
+    /// Render at a low internal resolution and upscale by an integer factor with
+    /// nearest filtering. The camera becomes orthographic showing `view_height`
+    /// world units and sprites are snapped to the resulting pixel grid.
+    PixelPerfect { pixel_scale: u32, view_height: f32 },
+}
+
+/// Replaces how sprites are drawn to help debug geometry and blending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DebugView {
+    #[default]
+    None,
+    /// Sprite outlines only. Needs [`wgpu::Features::POLYGON_MODE_LINE`].
+    Wireframe,
+    /// Brighter where more sprites are drawn over each other.
+    Overdraw,
+    /// Grey scale distance from the camera, white being closest.
+    Depth,
+}
+
+impl DebugView {
+    /// Following view, wrapping back round to none.
+    pub fn next(self) -> Self {
+        match self {
+            DebugView::None => DebugView::Wireframe,
+            DebugView::Wireframe => DebugView::Overdraw,
+            DebugView::Overdraw => DebugView::Depth,
+            DebugView::Depth => DebugView::None,
+        }
+    }
+}
+
+/// Counters from the last frame rendered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    /// Occlusion queries made around depth tested [`Ui3d`](pipelines::ui3d_pipeline::Ui3d)
+    /// menus.
+    pub ui_occlusion_queries: u32,
+    /// Menus whose text was skipped as they were completely hidden the frame
+    /// before.
+    pub ui_text_occluded: u32,
+}
+
+/// Offscreen color and depth target the scene is rendered into before being
+/// blitted to the surface. Used for the pixel perfect mode and letterboxing.
+struct OffscreenTarget {
+    color: Texture,
+    depth: Texture,
+    bind_group: wgpu::BindGroup,
+    size: PhysicalSize<u32>,
+    blit_viewport: [f32; 4],
+}
+
+impl OffscreenTarget {
+    fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        blit_pipeline: &BlitRenderer,
+        viewport: &Viewport,
+        pixel_scale: u32,
+    ) -> Self {
+        let pixel_scale = pixel_scale.max(1);
+
+        let size = PhysicalSize::new(
+            (viewport.width as u32 / pixel_scale).max(1),
+            (viewport.height as u32 / pixel_scale).max(1),
+        );
+
+        let color = Texture::create_render_target(
+            device,
+            size,
+            format,
+            wgpu::FilterMode::Nearest,
+            "Offscreen Target",
+        );
+        let depth = Texture::create_depth_texture(device, size, "Offscreen Target");
+        let bind_group = blit_pipeline.create_bind_group(device, &color);
+
+        // Integer scale and center inside the viewport
+        let width = ((size.width * pixel_scale) as f32).min(viewport.width);
+        let height = ((size.height * pixel_scale) as f32).min(viewport.height);
+
+        let blit_viewport = [
+            viewport.x + ((viewport.width - width) / 2.).floor(),
+            viewport.y + ((viewport.height - height) / 2.).floor(),
+            width,
+            height,
+        ];
+
+        Self {
+            color,
+            depth,
+            bind_group,
+            size,
+            blit_viewport,
+        }
+    }
+}
 
 //====================================================================
 
+struct ColorTween {
+    from: wgpu::Color,
+    to: wgpu::Color,
+    start: Instant,
+    duration: Duration,
+}
+
+impl ColorTween {
+    // Get the current color and whether the tween has finished
+    fn sample(&self) -> (wgpu::Color, bool) {
+        let t = (self.start.elapsed().as_secs_f64() / self.duration.as_secs_f64()).min(1.);
+        let lerp = |a: f64, b: f64| a + (b - a) * t;
+
+        let color = wgpu::Color {
+            r: lerp(self.from.r, self.to.r),
+            g: lerp(self.from.g, self.to.g),
+            b: lerp(self.from.b, self.to.b),
+            a: lerp(self.from.a, self.to.a),
+        };
+
+        (color, t >= 1.)
+    }
+}
+
+struct CameraTween {
+    from: (glam::Vec3, glam::Quat),
+    to: (glam::Vec3, glam::Quat),
+    start: Instant,
+    duration: Duration,
+}
+
+impl CameraTween {
+    // Get the current translation and rotation and whether the tween has finished
+    fn sample(&self) -> (glam::Vec3, glam::Quat, bool) {
+        let t = (self.start.elapsed().as_secs_f32() / self.duration.as_secs_f32()).min(1.);
+
+        // Ease in and out so moves don't start or stop abruptly
+        let eased = t * t * (3. - 2. * t);
+
+        let translation = self.from.0.lerp(self.to.0, eased);
+        let rotation = self.from.1.slerp(self.to.1, eased);
+
+        (translation, rotation, t >= 1.)
+    }
+}
+
+struct ScreenFlash {
+    texture: Texture,
+    bind_group: wgpu::BindGroup,
+    color: [f32; 4],
+    start: Instant,
+    duration: Duration,
+}
+
+impl ScreenFlash {
+    // Fade the flash out, returning true once it's gone
+    fn update(&mut self, queue: &wgpu::Queue, recorder: &FrameRecorder) -> bool {
+        let t = (self.start.elapsed().as_secs_f32() / self.duration.as_secs_f32()).min(1.);
+        let [r, g, b, a] = self.color.map(|channel| channel.clamp(0., 1.));
+
+        let pixel = [r, g, b, a * (1. - t)].map(|channel| (channel * 255.).round() as u8);
+        self.texture
+            .update_area(queue, recorder, &pixel, 0, 0, 1, 1);
+
+        t >= 1.
+    }
+}
+
+struct Background {
+    _texture: Arc<LoadedTexture>,
+    bind_group: wgpu::BindGroup,
+}
+
+//====================================================================
+
+/// Options that have to be decided when the renderer is created.
+#[derive(Debug, Clone, Default)]
+pub struct RendererConfig {
+    /// Render the scene into a linear HDR target and tonemap it when presenting.
+    /// An HDR surface format is used instead when the display supports one.
+    pub hdr: bool,
+    /// Backends to pick an adapter from. Falls back to the `WGPU_BACKEND`
+    /// environment variable, then the platform default.
+    pub backends: Option<wgpu::Backends>,
+    /// Use the first adapter whose name contains this (case insensitive).
+    /// Falls back to the `WGPU_ADAPTER_NAME` environment variable.
+    pub adapter_name: Option<String>,
+}
+
+impl RendererConfig {
+    pub const ADAPTER_NAME_VAR: &'static str = "WGPU_ADAPTER_NAME";
+
+    #[inline]
+    fn backends(&self) -> wgpu::Backends {
+        self.backends
+            .or_else(wgpu::util::backend_bits_from_env)
+            .unwrap_or(RendererCore::DEFAULT_BACKENDS)
+    }
+
+    #[inline]
+    fn adapter_name(&self) -> Option<String> {
+        self.adapter_name
+            .clone()
+            .or_else(|| std::env::var(Self::ADAPTER_NAME_VAR).ok())
+    }
+}
+
+/// Info for every adapter available on the given backends.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn available_adapters(backends: wgpu::Backends) -> Vec<wgpu::AdapterInfo> {
+    wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends,
+        ..Default::default()
+    })
+    .enumerate_adapters(backends)
+    .iter()
+    .map(|adapter| adapter.get_info())
+    .collect()
+}
+
 pub struct Renderer {
     core: RendererCore,
-    _shared: SharedRenderResources,
+    shared: SharedRenderResources,
     depth_texture: Texture,
     pub default_texture: DefaultTexture,
+    pub textures: TextureRegistry,
 
     pub camera: Camera,
     pub clear_color: wgpu::Color,
+    pub letterbox_color: wgpu::Color,
+    clear_color_tween: Option<ColorTween>,
+    camera_tween: Option<CameraTween>,
+    background: Option<Background>,
+    flash: Option<ScreenFlash>,
+    screenshots: Screenshots,
+    capture_requested: bool,
+    frame_capture: Option<FrameCapture>,
+    /// When the renderer was created, for [`FrameUniform::time`].
+    started: Instant,
+    frame: u32,
+
+    hdr: bool,
+    scene_format: wgpu::TextureFormat,
+
+    render_mode: RenderMode,
+    debug_view: DebugView,
+    virtual_resolution: Option<VirtualResolution>,
+    viewport: Viewport,
+    offscreen_target: Option<OffscreenTarget>,
 
     text_res: TextResources,
     texture_pipeline: TextureRenderer,
+    skinned_pipeline: SkinnedRenderer,
+    grid_pipeline: GridRenderer,
+    particle_pipeline: ParticleRenderer,
     ui3d_pipeline: Ui3dRenderer,
+    blit_pipeline: BlitRenderer,
+    background_pipeline: BlitRenderer,
+    overlay_pipeline: BlitRenderer,
 }
 
 impl Renderer {
-    pub fn new(window: impl Into<SurfaceTarget<'static>>, window_size: Size<u32>) -> Self {
-        let core = pollster::block_on(RendererCore::new(window, window_size));
+    pub const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[inline]
+    pub fn new(
+        window: impl Into<SurfaceTarget<'static>> + Clone,
+        window_size: PhysicalSize<u32>,
+    ) -> Result<Self, RenderError> {
+        Self::with_config(window, window_size, RendererConfig::default())
+    }
+
+    /// Blocks until the device is ready. Not available on web, where the
+    /// device has to be awaited with [`Renderer::with_config_async`].
+    #[cfg(not(target_arch = "wasm32"))]
+    #[inline]
+    pub fn with_config(
+        window: impl Into<SurfaceTarget<'static>> + Clone,
+        window_size: PhysicalSize<u32>,
+        config: RendererConfig,
+    ) -> Result<Self, RenderError> {
+        pollster::block_on(Self::with_config_async(window, window_size, config))
+    }
+
+    pub async fn with_config_async(
+        window: impl Into<SurfaceTarget<'static>> + Clone,
+        window_size: PhysicalSize<u32>,
+        config: RendererConfig,
+    ) -> Result<Self, RenderError> {
+        let core = RendererCore::new(window, window_size, &config).await?;
+        Ok(Self::from_core(core, window_size, config))
+    }
+
+    /// Finish creating the renderer from a surface made beforehand, see [`RendererSurface`].
+    pub async fn from_surface_async(
+        surface: RendererSurface,
+        window_size: PhysicalSize<u32>,
+        config: RendererConfig,
+    ) -> Result<Self, RenderError> {
+        let core = RendererCore::from_surface(surface, window_size, &config).await?;
+        Ok(Self::from_core(core, window_size, config))
+    }
+
+    fn from_core(
+        core: RendererCore,
+        window_size: PhysicalSize<u32>,
+        config: RendererConfig,
+    ) -> Self {
         let shared = SharedRenderResources::new(&core.device);
 
         let depth_texture =
             Texture::create_depth_texture(&core.device, window_size, "Depth Texture");
 
-        let default_texture = DefaultTexture::new(Arc::new(LoadedTexture::load_texture(
-            &core.device,
-            &shared,
-            Texture::from_color(
+        let mut textures = TextureRegistry::default();
+
+        let default_texture = DefaultTexture::new(textures.insert(
+            "Default Texture",
+            LoadedTexture::load_texture(
                 &core.device,
-                &core.queue,
-                [255; 3],
-                Some("Default Texture"),
-                None,
+                &shared,
+                Texture::from_color(
+                    &core.device,
+                    &core.queue,
+                    [255; 3],
+                    Some("Default Texture"),
+                    None,
+                ),
             ),
-        )));
+            false,
+        ));
 
-        let camera = Camera::new(&core.device, camera::PerspectiveCamera::default());
+        let camera = Camera::new(camera::PerspectiveCamera::default());
 
         let clear_color = wgpu::Color {
             r: 0.2,
@@ -67,34 +394,87 @@ impl Renderer {
 
         let text_res = TextResources::new(&core.device);
 
+        // Scene pipelines draw into the HDR target when enabled
+        let scene_format = match config.hdr {
+            true => Self::HDR_FORMAT,
+            false => core.config.format,
+        };
+
+        let scene_config = wgpu::SurfaceConfiguration {
+            format: scene_format,
+            ..core.config.clone()
+        };
+
         let texture_pipeline = TextureRenderer::new(
             &core.device,
-            &core.config,
+            &scene_config,
             &shared,
-            camera.bind_group_layout(),
+            default_texture.texture(),
         );
 
-        let ui3d_pipeline = Ui3dRenderer::new(
+        let skinned_pipeline = SkinnedRenderer::new(&core.device, &scene_config, &shared);
+
+        let grid_pipeline = GridRenderer::new(&core.device, &scene_config, &shared);
+
+        let particle_pipeline = ParticleRenderer::new(
             &core.device,
-            &core.config,
-            &text_res.text_atlas,
-            camera.bind_group_layout(),
+            core.adapter.get_downlevel_capabilities().flags,
+            &scene_config,
+            &shared,
         );
 
-        Self {
+        let ui3d_pipeline =
+            Ui3dRenderer::new(&core.device, &scene_config, &text_res.text_atlas, &shared);
+
+        // HDR surfaces can display the scene as is, otherwise it needs tonemapping
+        let tonemap = config.hdr && core.config.format != Self::HDR_FORMAT;
+        let blit_pipeline = BlitRenderer::new(&core.device, &core.config, tonemap);
+        let background_pipeline = BlitRenderer::new_background(&core.device, &scene_config);
+        let overlay_pipeline = BlitRenderer::new_overlay(&core.device, &scene_config);
+
+        let viewport = Viewport::new(window_size, None);
+
+        let mut renderer = Self {
             core,
-            _shared: shared,
+            shared,
             depth_texture,
             default_texture,
+            textures,
             camera,
             clear_color,
+            letterbox_color: wgpu::Color::BLACK,
+            clear_color_tween: None,
+            camera_tween: None,
+            background: None,
+            flash: None,
+            screenshots: Screenshots::default(),
+            capture_requested: false,
+            frame_capture: None,
+            started: Instant::now(),
+            frame: 0,
+            hdr: config.hdr,
+            scene_format,
+            render_mode: RenderMode::default(),
+            debug_view: DebugView::default(),
+            virtual_resolution: None,
+            viewport,
+            offscreen_target: None,
             text_res,
             texture_pipeline,
+            skinned_pipeline,
+            grid_pipeline,
+            particle_pipeline,
             ui3d_pipeline,
-        }
+            blit_pipeline,
+            background_pipeline,
+            overlay_pipeline,
+        };
+
+        renderer.rebuild_offscreen_target();
+        renderer
     }
 
-    pub fn resize(&mut self, new_size: Size<u32>) {
+    pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
         self.core.config.width = new_size.width;
         self.core.config.height = new_size.height;
         self.core
@@ -103,24 +483,420 @@ impl Renderer {
 
         self.depth_texture =
             Texture::create_depth_texture(&self.core.device, new_size, "Depth Texture");
+
+        self.rebuild_offscreen_target();
+    }
+
+    /// Set the clear color, optionally fading from the current color over `duration`.
+    pub fn set_clear_color(&mut self, color: wgpu::Color, duration: Option<Duration>) {
+        self.clear_color_tween = match duration {
+            Some(duration) if !duration.is_zero() => Some(ColorTween {
+                from: self.clear_color,
+                to: color,
+                start: Instant::now(),
+                duration,
+            }),
+            _ => {
+                self.clear_color = color;
+                None
+            }
+        };
+    }
+
+    /// Move the camera to `translation` and `rotation`, optionally easing from
+    /// where it is over `duration`. Replaces any tween already running.
+    pub fn tween_camera(
+        &mut self,
+        translation: glam::Vec3,
+        rotation: glam::Quat,
+        duration: Option<Duration>,
+    ) {
+        let camera = &mut self.camera.camera;
+
+        self.camera_tween = match duration {
+            Some(duration) if !duration.is_zero() => Some(CameraTween {
+                from: (camera.translation, camera.rotation),
+                to: (translation, rotation),
+                start: Instant::now(),
+                duration,
+            }),
+            _ => {
+                camera.translation = translation;
+                camera.rotation = rotation;
+                None
+            }
+        };
+    }
+
+    /// True while a tween started with [`Renderer::tween_camera`] is moving
+    /// the camera.
+    #[inline]
+    pub fn is_camera_tweening(&self) -> bool {
+        self.camera_tween.is_some()
+    }
+
+    /// Release any GPU resources held for an entity. Should be called when
+    /// despawning entities or removing their render components.
+    #[inline]
+    pub fn remove_entity(&mut self, entity: hecs::Entity) {
+        self.ui3d_pipeline.remove_entity(entity);
+        self.particle_pipeline.remove_entity(entity);
+        self.skinned_pipeline.remove_entity(entity);
+    }
+
+    /// Load an image into a texture, reusing the cached one if a texture with
+    /// the same label is still alive.
+    pub fn load_texture(
+        &mut self,
+        label: &str,
+        bytes: &[u8],
+    ) -> Result<Arc<LoadedTexture>, image::ImageError> {
+        if let Some(texture) = self.textures.get(label) {
+            return Ok(texture);
+        }
+
+        let texture = Texture::from_bytes(
+            &self.core.device,
+            &self.core.queue,
+            bytes,
+            Some(label),
+            None,
+        )?;
+
+        Ok(self.textures.insert(
+            label,
+            LoadedTexture::load_texture(&self.core.device, &self.shared, texture),
+            true,
+        ))
+    }
+
+    /// Draw a texture stretched over the viewport behind the scene, instead of
+    /// only the clear color.
+    pub fn set_background(&mut self, texture: Option<Arc<LoadedTexture>>) {
+        self.background = texture.map(|texture| Background {
+            bind_group: self
+                .background_pipeline
+                .create_bind_group(&self.core.device, texture._texture()),
+            _texture: texture,
+        });
+    }
+
+    /// Flash the whole view with `color` (sRGB with linear alpha), fading out
+    /// over `duration`. Replaces any flash already showing.
+    pub fn flash(&mut self, color: [f32; 4], duration: Duration) {
+        let [r, g, b, _] = color.map(|channel| (channel.clamp(0., 1.) * 255.).round() as u8);
+
+        let texture = Texture::from_color(
+            &self.core.device,
+            &self.core.queue,
+            [r, g, b],
+            Some("Flash Texture"),
+            None,
+        );
+
+        let mut flash = ScreenFlash {
+            bind_group: self
+                .overlay_pipeline
+                .create_bind_group(&self.core.device, &texture),
+            texture,
+            color,
+            start: Instant::now(),
+            duration,
+        };
+        flash.update(&self.core.queue, self.shared.recorder());
+
+        self.flash = Some(flash);
+    }
+
+    /// Capture the next frame presented. Collect it with
+    /// [`Renderer::take_screenshot`], usually from the following frame.
+    #[inline]
+    pub fn request_screenshot(&mut self) {
+        self.screenshots.request();
+    }
+
+    /// The last screenshot requested, once it's been read back.
+    #[inline]
+    pub fn take_screenshot(&mut self) -> Option<image::RgbaImage> {
+        self.screenshots.take()
+    }
+
+    /// Record the buffer writes, pipelines, binds and draws of the next frame.
+    /// Collect it with [`Renderer::take_frame_capture`] once it's rendered.
+    #[inline]
+    pub fn capture_next_frame(&mut self) {
+        self.capture_requested = true;
+    }
+
+    #[inline]
+    pub fn take_frame_capture(&mut self) -> Option<FrameCapture> {
+        self.frame_capture.take()
+    }
+
+    /// Readable report of the renderer setup, for triaging rendering issues.
+    pub fn diagnostics(&self) -> String {
+        use std::fmt::Write;
+
+        let mut output = self.core.diagnostics();
+
+        writeln!(
+            output,
+            "HDR: {} (scene format {:?})",
+            self.hdr, self.scene_format
+        )
+        .ok();
+        writeln!(output, "Render mode: {:?}", self.render_mode).ok();
+        writeln!(output, "Viewport: {:?}", self.viewport).ok();
+        writeln!(output, "Last frame: {:?}", self.stats()).ok();
+        writeln!(
+            output,
+            "Sprite batches: {:?}",
+            self.texture_pipeline.batch_submission()
+        )
+        .ok();
+        writeln!(
+            output,
+            "Texture memory: {} bytes",
+            self.textures.total_bytes()
+        )
+        .ok();
+        writeln!(
+            output,
+            "Text atlas pages: {:?} ({} glyphs cached)",
+            self.text_res.text_atlas.page_sizes(),
+            self.text_res.text_atlas.cached_glyph_count()
+        )
+        .ok();
+
+        output
+    }
+
+    pub fn stats(&self) -> RenderStats {
+        let (ui_occlusion_queries, ui_text_occluded) = self.ui3d_pipeline.occlusion_stats();
+
+        RenderStats {
+            ui_occlusion_queries,
+            ui_text_occluded,
+        }
+    }
+
+    #[inline]
+    pub fn hdr(&self) -> bool {
+        self.hdr
+    }
+
+    #[inline]
+    pub fn viewport(&self) -> &Viewport {
+        &self.viewport
+    }
+
+    #[inline]
+    pub fn virtual_resolution(&self) -> Option<VirtualResolution> {
+        self.virtual_resolution
+    }
+
+    /// Set the virtual resolution and how it adapts to the window. The camera
+    /// aspect ratio is kept in sync with the resulting viewport.
+    pub fn set_virtual_resolution(&mut self, virtual_resolution: Option<VirtualResolution>) {
+        self.virtual_resolution = virtual_resolution;
+        self.rebuild_offscreen_target();
+    }
+
+    #[inline]
+    pub fn render_mode(&self) -> RenderMode {
+        self.render_mode
+    }
+
+    pub fn set_render_mode(&mut self, render_mode: RenderMode) {
+        self.render_mode = render_mode;
+
+        self.camera.orthographic = match render_mode {
+            RenderMode::Standard => None,
+            RenderMode::PixelPerfect { view_height, .. } => Some(OrthographicProjection {
+                height: view_height,
+            }),
+        };
+
+        self.rebuild_offscreen_target();
     }
 
     #[inline]
+    pub fn debug_view(&self) -> DebugView {
+        self.debug_view
+    }
+
+    /// Switch how sprites are drawn. Returns false and keeps the current view
+    /// if the device can't support the new one.
+    pub fn set_debug_view(&mut self, debug_view: DebugView) -> bool {
+        let supported =
+            self.texture_pipeline
+                .set_debug_view(&self.core.device, &self.shared, debug_view);
+
+        match supported {
+            true => self.debug_view = debug_view,
+            false => log::warn!("Debug view {:?} isn't supported on this device", debug_view),
+        }
+
+        supported
+    }
+
+    fn rebuild_offscreen_target(&mut self) {
+        let window_size = PhysicalSize::new(self.core.config.width, self.core.config.height);
+
+        self.viewport = Viewport::new(window_size, self.virtual_resolution);
+        self.camera.set_aspect(
+            self.viewport.virtual_size.width,
+            self.viewport.virtual_size.height,
+        );
+
+        // Letterboxing needs an offscreen target so bars don't get the clear color
+        let pixel_scale = match self.render_mode {
+            RenderMode::PixelPerfect { pixel_scale, .. } => Some(pixel_scale),
+            RenderMode::Standard if self.hdr || !self.viewport.fills(window_size) => Some(1),
+            RenderMode::Standard => None,
+        };
+
+        self.offscreen_target = pixel_scale.map(|pixel_scale| {
+            OffscreenTarget::new(
+                &self.core.device,
+                self.scene_format,
+                &self.blit_pipeline,
+                &self.viewport,
+                pixel_scale,
+            )
+        });
+    }
+
+    #[inline]
+    pub fn size(&self) -> PhysicalSize<u32> {
+        PhysicalSize::new(self.core.config.width, self.core.config.height)
+    }
+
+    /// Present a frame of just the clear color. Used to show something while
+    /// the first scene is still loading.
+    pub fn present_clear_frame(&mut self) {
+        let surface_texture = match self.core.surface.get_current_texture() {
+            Ok(texture) => texture,
+            Err(_) => {
+                log::warn!("Unable to get surface texture - skipping loading frame");
+                return;
+            }
+        };
+
+        let view = surface_texture
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut encoder = self
+            .core
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Loading Frame Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(self.clear_color),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        self.core.queue.submit(Some(encoder.finish()));
+        surface_texture.present();
+    }
+
+    #[inline]
+    #[tracing::instrument(skip_all, name = "renderer_tick")]
     pub fn tick(&mut self, world: &mut World) {
+        let capturing = std::mem::take(&mut self.capture_requested);
+        if capturing {
+            self.shared.recorder().start(self.frame);
+        }
+
         self.update(world);
         self.render(world);
 
+        if capturing {
+            self.frame_capture = self.shared.recorder().finish();
+        }
+
         self.core.device.poll(wgpu::Maintain::Wait);
+        self.screenshots.collect();
 
         self.text_res.text_atlas.post_render_trim();
+        self.textures.trim();
     }
 
+    #[tracing::instrument(skip_all, name = "renderer_update")]
     fn update(&mut self, world: &mut World) {
-        self.camera.update_camera(&self.core.queue);
+        if let Some(tween) = &self.clear_color_tween {
+            let (color, finished) = tween.sample();
+            self.clear_color = color;
+
+            if finished {
+                self.clear_color_tween = None;
+            }
+        }
+
+        if let Some(flash) = &mut self.flash {
+            if flash.update(&self.core.queue, self.shared.recorder()) {
+                self.flash = None;
+            }
+        }
+
+        if let Some(tween) = &self.camera_tween {
+            let (translation, rotation, finished) = tween.sample();
+            self.camera.camera.translation = translation;
+            self.camera.camera.rotation = rotation;
 
-        self.texture_pipeline
+            if finished {
+                self.camera_tween = None;
+            }
+        }
+
+        self.camera.update_camera(&self.core.queue, &self.shared);
+        self.shared.update_frame(
+            &self.core.queue,
+            &FrameUniform {
+                viewport_size: [self.viewport.width, self.viewport.height],
+                time: self.started.elapsed().as_secs_f32(),
+                frame: self.frame,
+            },
+        );
+        self.frame = self.frame.wrapping_add(1);
+
+        let pixel_snap = match (self.render_mode, &self.offscreen_target) {
+            (RenderMode::PixelPerfect { view_height, .. }, Some(target)) => Some(PixelSnap::new(
+                &self.camera.camera,
+                view_height / target.size.height as f32,
+            )),
+            _ => None,
+        };
+
+        self.texture_pipeline.prep(
+            world,
+            &self.core.device,
+            &self.core.queue,
+            &self.shared,
+            self.camera.camera.translation,
+            pixel_snap,
+        );
+
+        self.skinned_pipeline
+            .prep(world, &self.core.device, &self.core.queue, &self.shared);
+
+        self.grid_pipeline
             .prep(world, &self.core.device, &self.core.queue);
 
+        self.particle_pipeline
+            .prep(world, &self.core.device, &self.core.queue, &self.shared);
+
         self.ui3d_pipeline
             .prep_rotations(world, self.camera.camera.translation);
 
@@ -129,9 +905,11 @@ impl Renderer {
             &self.core.device,
             &self.core.queue,
             &mut self.text_res,
+            &self.shared,
         );
     }
 
+    #[tracing::instrument(skip_all, name = "renderer_render")]
     fn render(&mut self, _world: &mut World) {
         let (surface_texture, surface_view) = match self.core.surface.get_current_texture() {
             Ok(texture) => {
@@ -151,21 +929,53 @@ impl Renderer {
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
 
-        self.render_inner(&mut encoder, &surface_view);
+        self.particle_pipeline
+            .simulate(&mut encoder, self.shared.recorder());
+
+        match &self.offscreen_target {
+            Some(target) => {
+                self.render_scene(
+                    &mut encoder,
+                    &target.color.view,
+                    &target.depth.view,
+                    [target.size.width, target.size.height],
+                );
+                self.render_offscreen_target(&mut encoder, &surface_view, target);
+            }
+            None => self.render_scene(
+                &mut encoder,
+                &surface_view,
+                &self.depth_texture.view,
+                [self.core.config.width, self.core.config.height],
+            ),
+        }
+
+        self.ui3d_pipeline.resolve_occlusion(&mut encoder);
+
+        self.screenshots
+            .copy_texture(&self.core.device, &mut encoder, &surface_texture.texture);
 
         self.core.queue.submit(Some(encoder.finish()));
+        self.screenshots.map();
+        self.ui3d_pipeline.map_occlusion();
         surface_texture.present();
     }
 
-    fn render_inner(
-        &mut self,
+    fn render_scene(
+        &self,
         encoder: &mut wgpu::CommandEncoder,
-        surface_view: &wgpu::TextureView,
+        color_view: &wgpu::TextureView,
+        depth_view: &wgpu::TextureView,
+        target_size: [u32; 2],
     ) {
+        self.shared.recorder().record(|| CaptureCommand::BeginPass {
+            label: "Main Render Pass",
+        });
+
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Main Render Pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &surface_view,
+                view: color_view,
                 resolve_target: None,
                 ops: wgpu::Operations {
                     load: wgpu::LoadOp::Clear(self.clear_color),
@@ -174,7 +984,7 @@ impl Renderer {
             })],
 
             depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                view: &self.depth_texture.view,
+                view: depth_view,
                 depth_ops: Some(wgpu::Operations {
                     load: wgpu::LoadOp::Clear(1.),
                     store: wgpu::StoreOp::Store,
@@ -183,24 +993,122 @@ impl Renderer {
             }),
 
             timestamp_writes: None,
-            occlusion_query_set: None,
+            occlusion_query_set: self.ui3d_pipeline.occlusion_query_set(),
         });
 
+        if let Some(background) = &self.background {
+            self.background_pipeline.render(
+                &mut render_pass,
+                self.shared.recorder(),
+                &background.bind_group,
+            );
+        }
+
         // Render stuff here
-        self.texture_pipeline
-            .render(&mut render_pass, self.camera.bind_group());
+        self.texture_pipeline.render(&mut render_pass, &self.shared);
+        self.skinned_pipeline.render(&mut render_pass, &self.shared);
+
+        // After sprites so they hide the grid lines behind them
+        self.grid_pipeline.render(&mut render_pass, &self.shared);
+
+        // After everything that writes depth, as particles don't
+        self.particle_pipeline
+            .render(&mut render_pass, &self.shared);
 
         self.ui3d_pipeline.render(
             &mut render_pass,
             &self.text_res.text_atlas,
-            self.camera.bind_group(),
+            &self.shared,
+            target_size,
         );
+
+        if let Some(flash) = &self.flash {
+            self.overlay_pipeline.render(
+                &mut render_pass,
+                self.shared.recorder(),
+                &flash.bind_group,
+            );
+        }
+    }
+
+    fn render_offscreen_target(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        surface_view: &wgpu::TextureView,
+        target: &OffscreenTarget,
+    ) {
+        self.shared.recorder().record(|| CaptureCommand::BeginPass {
+            label: "Offscreen Blit Render Pass",
+        });
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Offscreen Blit Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: surface_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(self.letterbox_color),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        let [x, y, width, height] = target.blit_viewport;
+        render_pass.set_viewport(x, y, width, height, 0., 1.);
+
+        self.blit_pipeline
+            .render(&mut render_pass, self.shared.recorder(), &target.bind_group);
     }
 }
 
 //====================================================================
 
+/// Instance and surface created ahead of the device. Surfaces have to be
+/// created on the main thread on some platforms, while the device can be
+/// awaited anywhere.
+pub struct RendererSurface {
+    instance: wgpu::Instance,
+    surface: wgpu::Surface<'static>,
+    backends: wgpu::Backends,
+}
+
+impl RendererSurface {
+    #[inline]
+    pub fn new(
+        window: impl Into<SurfaceTarget<'static>>,
+        renderer_config: &RendererConfig,
+    ) -> Result<Self, RenderError> {
+        Self::with_backends(window, renderer_config.backends())
+    }
+
+    fn with_backends(
+        window: impl Into<SurfaceTarget<'static>>,
+        backends: wgpu::Backends,
+    ) -> Result<Self, RenderError> {
+        log::debug!("Using backends {:?}", backends);
+
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends,
+            ..Default::default()
+        });
+
+        let surface = instance.create_surface(window)?;
+
+        Ok(Self {
+            instance,
+            surface,
+            backends,
+        })
+    }
+}
+
+//--------------------------------------------------
+
 pub struct RendererCore {
+    pub adapter: wgpu::Adapter,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
     pub surface: wgpu::Surface<'static>,
@@ -208,56 +1116,180 @@ pub struct RendererCore {
 }
 
 impl RendererCore {
-    pub async fn new(window: impl Into<SurfaceTarget<'static>>, window_size: Size<u32>) -> Self {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub const DEFAULT_BACKENDS: wgpu::Backends = wgpu::Backends::PRIMARY;
+    #[cfg(target_arch = "wasm32")]
+    pub const DEFAULT_BACKENDS: wgpu::Backends =
+        wgpu::Backends::BROWSER_WEBGPU.union(wgpu::Backends::GL);
+
+    pub async fn new(
+        window: impl Into<SurfaceTarget<'static>> + Clone,
+        window_size: PhysicalSize<u32>,
+        renderer_config: &RendererConfig,
+    ) -> Result<Self, RenderError> {
         log::debug!("Creating core wgpu renderer components.");
 
         log::debug!("Window inner size = {:?}", window_size);
 
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            #[cfg(not(target_arch = "wasm32"))]
-            backends: wgpu::Backends::PRIMARY,
-            #[cfg(target_arch = "wasm32")]
-            backends: wgpu::Backends::GL,
-            ..Default::default()
-        });
+        let backends = renderer_config.backends();
 
-        // let surface = instance.create_surface(window.0.clone()).unwrap();
-        let surface = instance.create_surface(window).unwrap();
+        // Prefer WebGPU when the browser has it, otherwise fall back to WebGL2
+        #[cfg(target_arch = "wasm32")]
+        if backends.contains(wgpu::Backends::BROWSER_WEBGPU | wgpu::Backends::GL) {
+            match webgpu_available() {
+                true => {
+                    match Self::create(
+                        window.clone(),
+                        window_size,
+                        renderer_config,
+                        wgpu::Backends::BROWSER_WEBGPU,
+                    )
+                    .await
+                    {
+                        Ok(core) => return Ok(core),
+                        Err(e) => log::warn!("Unable to use WebGPU, falling back to WebGL2: {}", e),
+                    }
+                }
+                false => log::info!("WebGPU not available, using WebGL2"),
+            }
 
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                force_fallback_adapter: false,
-                compatible_surface: Some(&surface),
-            })
-            .await
-            .unwrap();
+            return Self::create(window, window_size, renderer_config, wgpu::Backends::GL).await;
+        }
+
+        Self::create(window, window_size, renderer_config, backends).await
+    }
+
+    #[inline]
+    async fn create(
+        window: impl Into<SurfaceTarget<'static>>,
+        window_size: PhysicalSize<u32>,
+        renderer_config: &RendererConfig,
+        backends: wgpu::Backends,
+    ) -> Result<Self, RenderError> {
+        let surface = RendererSurface::with_backends(window, backends)?;
+        Self::from_surface(surface, window_size, renderer_config).await
+    }
+
+    pub async fn from_surface(
+        surface: RendererSurface,
+        window_size: PhysicalSize<u32>,
+        renderer_config: &RendererConfig,
+    ) -> Result<Self, RenderError> {
+        let RendererSurface {
+            instance,
+            surface,
+            backends,
+        } = surface;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        log::debug!(
+            "Available adapters: {:?}",
+            instance
+                .enumerate_adapters(backends)
+                .iter()
+                .map(|adapter| adapter.get_info().name)
+                .collect::<Vec<_>>()
+        );
+
+        let requested_adapter = match renderer_config.adapter_name() {
+            Some(name) => Self::find_adapter(&instance, backends, &surface, &name),
+            None => None,
+        };
+
+        let adapter = match requested_adapter {
+            Some(adapter) => adapter,
+            None => instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::default(),
+                    force_fallback_adapter: false,
+                    compatible_surface: Some(&surface),
+                })
+                .await
+                .ok_or(RenderError::NoAdapter)?,
+        };
 
         log::debug!("Chosen device adapter: {:#?}", adapter.get_info());
 
+        // Binding arrays let the texture pipeline draw every sprite from one bind
+        // group, and multi draw indirect then submits its batches together. Line
+        // polygon mode is only used by the wireframe debug view.
+        #[cfg(not(target_arch = "wasm32"))]
+        let (required_features, required_limits) = {
+            let default_limits = wgpu::Limits::default();
+
+            let max_sampled_textures = adapter
+                .limits()
+                .max_sampled_textures_per_shader_stage
+                .min(pipelines::texture_pipeline::MAX_ARRAY_TEXTURES)
+                .max(default_limits.max_sampled_textures_per_shader_stage);
+
+            (
+                adapter.features()
+                    & (pipelines::texture_pipeline::BINDING_ARRAY_FEATURES
+                        | pipelines::texture_pipeline::MULTI_DRAW_FEATURES
+                        | pipelines::texture_pipeline::WIREFRAME_FEATURES),
+                wgpu::Limits {
+                    max_sampled_textures_per_shader_stage: max_sampled_textures,
+                    ..default_limits
+                },
+            )
+        };
+
+        #[cfg(target_arch = "wasm32")]
+        let (required_features, required_limits) = match adapter.get_info().backend {
+            wgpu::Backend::Gl => (
+                wgpu::Features::empty(),
+                wgpu::Limits::downlevel_webgl2_defaults(),
+            ),
+            _ => (
+                wgpu::Features::empty(),
+                wgpu::Limits::downlevel_defaults().using_resolution(adapter.limits()),
+            ),
+        };
+
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
-                    #[cfg(target_arch = "wasm32")]
-                    required_limits: wgpu::Limits::downlevel_webgl2_defaults(),
+                    required_features,
+                    required_limits,
                     ..Default::default()
                 },
                 None,
             )
-            .await
-            .unwrap();
+            .await?;
 
         let surface_capabilities = surface.get_capabilities(&adapter);
 
-        let surface_format = surface_capabilities
+        let hdr_format = surface_capabilities
             .formats
             .iter()
-            .find(|format| format.is_srgb())
-            .copied()
-            .unwrap_or(surface_capabilities.formats[0]);
+            .find(|format| **format == Renderer::HDR_FORMAT)
+            .copied();
+
+        log::debug!("HDR surface format available: {:?}", hdr_format);
+
+        let surface_format = match (renderer_config.hdr, hdr_format) {
+            (true, Some(format)) => format,
+            _ => surface_capabilities
+                .formats
+                .iter()
+                .find(|format| format.is_srgb())
+                .or(surface_capabilities.formats.first())
+                .copied()
+                .ok_or(RenderError::NoSurfaceFormat)?,
+        };
+
+        // Copying from the surface is only needed for screenshots
+        let usage = match surface_capabilities
+            .usages
+            .contains(wgpu::TextureUsages::COPY_SRC)
+        {
+            true => wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            false => wgpu::TextureUsages::RENDER_ATTACHMENT,
+        };
 
         let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage,
             format: surface_format,
             width: window_size.width,
             height: window_size.height,
@@ -271,13 +1303,103 @@ impl RendererCore {
 
         log::debug!("Successfully created core wgpu components.");
 
-        Self {
+        Ok(Self {
+            adapter,
             device,
             queue,
             surface,
             config,
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn find_adapter(
+        instance: &wgpu::Instance,
+        backends: wgpu::Backends,
+        surface: &wgpu::Surface,
+        name: &str,
+    ) -> Option<wgpu::Adapter> {
+        let name = name.to_lowercase();
+
+        let adapter = instance
+            .enumerate_adapters(backends)
+            .into_iter()
+            .filter(|adapter| adapter.is_surface_supported(surface))
+            .find(|adapter| adapter.get_info().name.to_lowercase().contains(&name));
+
+        if adapter.is_none() {
+            log::warn!(
+                "No adapter matching '{}' found - using default adapter",
+                name
+            );
         }
+
+        adapter
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn find_adapter(
+        _instance: &wgpu::Instance,
+        _backends: wgpu::Backends,
+        _surface: &wgpu::Surface,
+        name: &str,
+    ) -> Option<wgpu::Adapter> {
+        log::warn!(
+            "Adapter selection isn't supported on web - ignoring '{}'",
+            name
+        );
+        None
+    }
+
+    /// Readable report of the adapter, surface and device capabilities.
+    pub fn diagnostics(&self) -> String {
+        use std::fmt::Write;
+
+        let info = self.adapter.get_info();
+        let capabilities = self.surface.get_capabilities(&self.adapter);
+
+        let mut output = String::new();
+
+        writeln!(output, "Adapter: {} ({:?})", info.name, info.device_type).ok();
+        writeln!(output, "Backend: {:?}", info.backend).ok();
+        writeln!(output, "Driver: {} {}", info.driver, info.driver_info).ok();
+        writeln!(
+            output,
+            "Vendor/Device: {:#x}/{:#x}",
+            info.vendor, info.device
+        )
+        .ok();
+
+        writeln!(
+            output,
+            "Surface: {:?}, {}x{}, {:?}, {:?}",
+            self.config.format,
+            self.config.width,
+            self.config.height,
+            self.config.present_mode,
+            self.config.alpha_mode
+        )
+        .ok();
+        writeln!(output, "Surface formats: {:?}", capabilities.formats).ok();
+        writeln!(output, "Present modes: {:?}", capabilities.present_modes).ok();
+
+        writeln!(output, "Features: {:?}", self.device.features()).ok();
+        writeln!(output, "Limits: {:#?}", self.device.limits()).ok();
+
+        output
     }
 }
 
 //====================================================================
+
+#[cfg(target_arch = "wasm32")]
+fn webgpu_available() -> bool {
+    use wasm_bindgen::JsValue;
+
+    js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("navigator"))
+        .and_then(|navigator| js_sys::Reflect::get(&navigator, &JsValue::from_str("gpu")))
+        .map(|gpu| !gpu.is_undefined() && !gpu.is_null())
+        .unwrap_or(false)
+}
+
+//====================================================================
@@ -0,0 +1,163 @@
+//====================================================================
+
+use crate::{
+    capture::{CaptureCommand, FrameRecorder},
+    texture::Texture,
+    tools,
+};
+
+//====================================================================
+
+/// Draws a texture over the whole viewport. Used to present offscreen targets,
+/// optionally tonemapping HDR content down to the surface range.
+pub struct BlitRenderer {
+    label: &'static str,
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+}
+
+impl BlitRenderer {
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        tonemap: bool,
+    ) -> Self {
+        let shader = match tonemap {
+            true => include_str!("shaders/tonemap.wgsl"),
+            false => include_str!("shaders/blit.wgsl"),
+        };
+
+        Self::create(
+            device,
+            config,
+            "Blit Pipeline",
+            shader,
+            tools::RenderPipelineDescriptor::default(),
+        )
+    }
+
+    /// Variant drawn at the start of the main pass, behind everything else.
+    pub(crate) fn new_background(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+    ) -> Self {
+        Self::create(
+            device,
+            config,
+            "Background Pipeline",
+            include_str!("shaders/blit.wgsl"),
+            tools::RenderPipelineDescriptor {
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: Texture::DEPTH_FORMAT,
+                    depth_write_enabled: false,
+                    depth_compare: wgpu::CompareFunction::Always,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Variant drawn at the end of the main pass, blended over everything.
+    pub(crate) fn new_overlay(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> Self {
+        let fragment_targets = [Some(wgpu::ColorTargetState {
+            format: config.format,
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+            write_mask: wgpu::ColorWrites::all(),
+        })];
+
+        Self::create(
+            device,
+            config,
+            "Overlay Pipeline",
+            include_str!("shaders/blit.wgsl"),
+            tools::RenderPipelineDescriptor {
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: Texture::DEPTH_FORMAT,
+                    depth_write_enabled: false,
+                    depth_compare: wgpu::CompareFunction::Always,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                fragment_targets: Some(&fragment_targets),
+                ..Default::default()
+            },
+        )
+    }
+
+    fn create(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        label: &'static str,
+        shader: &str,
+        desc: tools::RenderPipelineDescriptor,
+    ) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(&format!("{} Bind Group Layout", label)),
+            entries: &[tools::bgl_texture_entry(0), tools::bgl_sampler_entry(1)],
+        });
+
+        let pipeline = tools::create_pipeline(
+            device,
+            config,
+            label,
+            &[&bind_group_layout],
+            &[],
+            shader,
+            desc,
+        );
+
+        Self {
+            label,
+            pipeline,
+            bind_group_layout,
+        }
+    }
+
+    pub(crate) fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        texture: &Texture,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Blit Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                },
+            ],
+        })
+    }
+
+    #[tracing::instrument(skip_all, name = "blit_render")]
+    pub(crate) fn render(
+        &self,
+        pass: &mut wgpu::RenderPass,
+        recorder: &FrameRecorder,
+        bind_group: &wgpu::BindGroup,
+    ) {
+        recorder.record(|| CaptureCommand::SetPipeline { label: self.label });
+        recorder.record(|| CaptureCommand::SetBindGroup {
+            index: 0,
+            label: String::from("Blit texture"),
+        });
+        recorder.record(|| CaptureCommand::Draw {
+            vertices: 3,
+            instances: 1,
+            indexed: false,
+        });
+
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
+
+//====================================================================
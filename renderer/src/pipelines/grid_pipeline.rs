@@ -0,0 +1,166 @@
+//====================================================================
+
+use common::Transform;
+use hecs::World;
+
+use crate::{
+    capture::CaptureCommand,
+    color,
+    shared::{SharedRenderResources, Vertex},
+    texture::Texture,
+    tools,
+};
+
+use super::{Hidden, Opacity};
+
+//====================================================================
+
+/// Flat grid on the xz plane at the entity's height. It follows the camera so
+/// it looks endless, fading out with distance like an editor viewport grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Grid {
+    /// World units between grid lines.
+    pub cell_size: f32,
+    /// Every nth line is drawn thicker. 0 or 1 for no major lines.
+    pub major_every: u32,
+    /// sRGB line color with linear alpha.
+    pub color: [f32; 4],
+    /// Distance from the camera at which the grid has fully faded out.
+    pub fade_distance: f32,
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Self {
+            cell_size: 20.,
+            major_every: 5,
+            color: [0.8, 0.8, 0.8, 0.6],
+            fade_distance: 1200.,
+        }
+    }
+}
+
+//====================================================================
+
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Debug)]
+struct InstanceGrid {
+    color: glam::Vec4,
+    height: f32,
+    cell_size: f32,
+    major_every: f32,
+    fade_distance: f32,
+}
+
+impl Vertex for InstanceGrid {
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 2] = wgpu::vertex_attr_array![
+            0 => Float32x4, // Color
+            1 => Float32x4, // Height, cell size, major every, fade distance
+        ];
+
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &VERTEX_ATTRIBUTES,
+        }
+    }
+}
+
+/// Draws every [`Grid`] as an instanced quad centered under the camera.
+pub struct GridRenderer {
+    pipeline: wgpu::RenderPipeline,
+    linear_target: bool,
+    instances: tools::InstanceBuffer<InstanceGrid>,
+}
+
+impl GridRenderer {
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+    ) -> Self {
+        let fragment_targets = [Some(wgpu::ColorTargetState {
+            format: config.format,
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+            write_mask: wgpu::ColorWrites::all(),
+        })];
+
+        let pipeline = tools::create_pipeline(
+            device,
+            config,
+            "Grid Pipeline",
+            &[shared.frame_bind_group_layout()],
+            &[InstanceGrid::desc()],
+            include_str!("shaders/grid.wgsl"),
+            tools::RenderPipelineDescriptor {
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleStrip,
+                    ..Default::default()
+                },
+                // Tested against sprites but never hides them
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: Texture::DEPTH_FORMAT,
+                    depth_write_enabled: false,
+                    depth_compare: wgpu::CompareFunction::Less,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                fragment_targets: Some(&fragment_targets),
+                ..Default::default()
+            },
+        );
+
+        Self {
+            pipeline,
+            linear_target: color::is_linear_target(config.format),
+            instances: tools::InstanceBuffer::new(device, &[]),
+        }
+    }
+
+    #[tracing::instrument(skip_all, name = "grid_prep")]
+    pub(crate) fn prep(&mut self, world: &mut World, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let instances = world
+            .query_mut::<(&Transform, &Grid, Option<&Opacity>)>()
+            .without::<&Hidden>()
+            .into_iter()
+            .map(|(_, (transform, grid, opacity))| InstanceGrid {
+                color: color::srgba_for_target(
+                    Opacity::apply(grid.color, Opacity::of(opacity)),
+                    self.linear_target,
+                )
+                .into(),
+                height: transform.translation.y,
+                cell_size: grid.cell_size.max(f32::EPSILON),
+                major_every: grid.major_every as f32,
+                fade_distance: grid.fade_distance,
+            })
+            .collect::<Vec<_>>();
+
+        self.instances.update(device, queue, &instances);
+    }
+
+    pub(crate) fn render(&self, pass: &mut wgpu::RenderPass, shared: &SharedRenderResources) {
+        if self.instances.count() == 0 {
+            return;
+        }
+
+        shared.recorder().record(|| CaptureCommand::SetPipeline {
+            label: "Grid Pipeline",
+        });
+        shared.recorder().record(|| CaptureCommand::Draw {
+            vertices: 4,
+            instances: self.instances.count(),
+            indexed: false,
+        });
+
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, shared.frame_bind_group(), &[]);
+        pass.set_vertex_buffer(0, self.instances.buffer().slice(..));
+        pass.draw(0..4, 0..self.instances.count());
+    }
+}
+
+//====================================================================
@@ -1,8 +1,43 @@
 //====================================================================
 
+pub mod blit_pipeline;
+pub mod grid_pipeline;
+pub mod particle_pipeline;
+pub mod skinned_pipeline;
 pub mod texture_pipeline;
 pub mod ui3d_pipeline;
 
 //====================================================================
 
+/// Skips drawing an entity in every pipeline while keeping whatever the
+/// renderer holds for it, so it can be shown again by removing this.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hidden;
+
+/// Multiplies the alpha of everything drawn for an entity, from 0 for fully
+/// transparent to 1. Entities without one are drawn as normal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Opacity(pub f32);
+
+impl Default for Opacity {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+impl Opacity {
+    #[inline]
+    pub(crate) fn of(opacity: Option<&Opacity>) -> f32 {
+        opacity.map_or(1., |opacity| opacity.0.clamp(0., 1.))
+    }
+
+    /// `color` with its alpha multiplied by `opacity`.
+    #[inline]
+    pub(crate) fn apply(color: [f32; 4], opacity: f32) -> [f32; 4] {
+        [color[0], color[1], color[2], color[3] * opacity]
+    }
+}
+
 //====================================================================
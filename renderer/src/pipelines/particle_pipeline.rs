@@ -0,0 +1,837 @@
+//====================================================================
+
+use std::collections::HashMap;
+
+use common::Transform;
+use hecs::{Entity, World};
+
+use crate::{
+    capture::{CaptureCommand, FrameRecorder},
+    color,
+    shared::{SharedRenderResources, Vertex},
+    texture::Texture,
+    tools,
+};
+
+use super::Hidden;
+
+//====================================================================
+
+/// Particles simulated by each compute workgroup. Matches particle_sim.wgsl.
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Shortest a particle's lifetime is randomly cut down to, as a fraction of
+/// [`ParticleEmitter::lifetime`].
+const MIN_LIFETIME: f32 = 0.75;
+
+/// Needed to simulate in a compute shader and draw straight from its output.
+const GPU_SIMULATION_FLAGS: wgpu::DownlevelFlags = wgpu::DownlevelFlags::COMPUTE_SHADERS
+    .union(wgpu::DownlevelFlags::VERTEX_STORAGE)
+    .union(wgpu::DownlevelFlags::INDIRECT_EXECUTION);
+
+/// Spawns short lived particles from the entity's position. Particles are left
+/// where they were emitted rather than following the entity, and fade out as
+/// they age.
+#[derive(Debug, Clone)]
+pub struct ParticleEmitter {
+    /// Particles emitted per second.
+    pub rate: f32,
+    /// Particles emitted all at once on the next frame.
+    pub burst: u32,
+    /// Most particles alive at once. New particles replace the oldest.
+    pub max_particles: u32,
+    /// Seconds each particle lives for, give or take a little.
+    pub lifetime: f32,
+    /// Starting velocity of every particle.
+    pub velocity: glam::Vec3,
+    /// Random speed added to each particle's velocity, in any direction.
+    pub spread: f32,
+    /// Added to each particle's velocity per second.
+    pub gravity: glam::Vec3,
+    /// Width and height of each particle.
+    pub size: f32,
+    /// sRGB color with linear alpha. Particles are drawn additively so
+    /// overlapping particles glow.
+    pub color: [f32; 4],
+
+    /// Seconds passed since the renderer last simulated this emitter.
+    pending_seconds: f32,
+    /// Particles waiting to be emitted, including any fraction left over.
+    pending_particles: f32,
+}
+
+impl Default for ParticleEmitter {
+    fn default() -> Self {
+        Self {
+            rate: 0.,
+            burst: 0,
+            max_particles: 256,
+            lifetime: 1.,
+            velocity: glam::vec3(0., 60., 0.),
+            spread: 40.,
+            gravity: glam::vec3(0., -90., 0.),
+            size: 6.,
+            color: [1.; 4],
+            pending_seconds: 0.,
+            pending_particles: 0.,
+        }
+    }
+}
+
+impl ParticleEmitter {
+    /// Emit `count` particles once.
+    #[inline]
+    pub fn burst(count: u32) -> Self {
+        Self {
+            burst: count,
+            max_particles: count.max(1),
+            ..Default::default()
+        }
+    }
+
+    /// Emit `rate` particles per second until removed.
+    #[inline]
+    pub fn continuous(rate: f32) -> Self {
+        Self {
+            rate,
+            ..Default::default()
+        }
+    }
+}
+
+/// Advance emitters by the game's frame time, so particles stop with the game
+/// when it's paused.
+pub fn tick_particles(world: &mut World, delta_seconds: f32) {
+    world
+        .query_mut::<&mut ParticleEmitter>()
+        .into_iter()
+        .for_each(|(_, emitter)| {
+            emitter.pending_seconds += delta_seconds;
+            emitter.pending_particles +=
+                emitter.rate.max(0.) * delta_seconds + std::mem::take(&mut emitter.burst) as f32;
+        });
+}
+
+//====================================================================
+
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Debug, Default)]
+pub struct ParticleRaw {
+    pub position: glam::Vec3,
+    /// Seconds since the particle was emitted.
+    pub age: f32,
+    pub velocity: glam::Vec3,
+    /// The particle is dead once its age reaches this.
+    pub lifetime: f32,
+    /// Linear color with alpha.
+    pub color: glam::Vec4,
+}
+
+impl Vertex for ParticleRaw {
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 3] = wgpu::vertex_attr_array![
+            0 => Float32x4, // Position and age
+            1 => Float32x4, // Velocity and lifetime
+            2 => Float32x4, // Color
+        ];
+
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &VERTEX_ATTRIBUTES,
+        }
+    }
+}
+
+impl ParticleRaw {
+    #[inline]
+    pub fn alive(&self) -> bool {
+        self.age < self.lifetime
+    }
+}
+
+/// Step alive particles forward by `delta` seconds the same way
+/// particle_sim.wgsl does, returning the ones still alive afterwards. Used
+/// when simulating on the CPU.
+pub fn simulate_particles(
+    particles: &mut [ParticleRaw],
+    gravity: glam::Vec3,
+    delta: f32,
+) -> Vec<ParticleRaw> {
+    particles
+        .iter_mut()
+        .filter(|particle| particle.alive())
+        .filter_map(|particle| {
+            particle.velocity += gravity * delta;
+            particle.position += particle.velocity * delta;
+            particle.age += delta;
+
+            particle.alive().then_some(*particle)
+        })
+        .collect()
+}
+
+/// Hands out slots in an emitter's particle buffer, wrapping around so new
+/// particles replace the oldest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParticleRing {
+    capacity: u32,
+    next: u32,
+}
+
+impl ParticleRing {
+    #[inline]
+    pub fn new(capacity: u32) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            next: 0,
+        }
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// Claim slots for up to `count` new particles, no more than the capacity.
+    /// Returns the first slot and length of the runs they fill, in the order
+    /// the particles were emitted. The second run is empty unless the first
+    /// reaches the end of the buffer.
+    pub fn claim(&mut self, count: u32) -> [(u32, u32); 2] {
+        let count = count.min(self.capacity);
+
+        let start = self.next;
+        let first_run = (self.capacity - start).min(count);
+        self.next = (start + count) % self.capacity;
+
+        [(start, first_run), (0, count - first_run)]
+    }
+}
+
+/// True if the device can simulate particles in a compute shader and draw
+/// them from storage buffers. Otherwise they're simulated on the CPU.
+pub fn supports_gpu_simulation(limits: &wgpu::Limits, downlevel: wgpu::DownlevelFlags) -> bool {
+    limits.max_compute_workgroups_per_dimension > 0
+        && limits.max_storage_buffers_per_shader_stage >= 3
+        && downlevel.contains(GPU_SIMULATION_FLAGS)
+}
+
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Debug)]
+struct EmitterUniformRaw {
+    gravity: glam::Vec3,
+    /// Seconds to simulate this frame.
+    delta: f32,
+    size: f32,
+    /// Particles in the emitter's buffer, alive or not.
+    capacity: u32,
+    pad: [u32; 2],
+}
+
+//====================================================================
+
+/// Simulates and draws every [`ParticleEmitter`]. Particles are simulated in a
+/// compute shader and drawn indirectly where compute is available, and on the
+/// CPU otherwise (WebGL).
+pub struct ParticleRenderer {
+    pipeline: wgpu::RenderPipeline,
+    emitter_bind_group_layout: wgpu::BindGroupLayout,
+    /// Set when simulating on the GPU.
+    compute: Option<ParticleCompute>,
+    linear_target: bool,
+
+    emitters: HashMap<Entity, EmitterData>,
+}
+
+struct ParticleCompute {
+    pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+}
+
+impl ParticleRenderer {
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        downlevel: wgpu::DownlevelFlags,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+    ) -> Self {
+        let gpu_simulation = supports_gpu_simulation(&device.limits(), downlevel);
+
+        log::debug!(
+            "Particle pipeline simulating on the {}",
+            match gpu_simulation {
+                true => "gpu",
+                false => "cpu",
+            }
+        );
+
+        let emitter_bind_group_layout = match gpu_simulation {
+            true => device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Particle Emitter Bind Group Layout"),
+                entries: &[
+                    tools::bgl_uniform_entry(0, wgpu::ShaderStages::VERTEX),
+                    tools::bgl_storage_entry(1, wgpu::ShaderStages::VERTEX, true),
+                    tools::bgl_storage_entry(2, wgpu::ShaderStages::VERTEX, true),
+                ],
+            }),
+            false => device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Particle Emitter Bind Group Layout"),
+                entries: &[tools::bgl_uniform_entry(0, wgpu::ShaderStages::VERTEX)],
+            }),
+        };
+
+        // Additive so particles don't need sorting, and no depth writes so
+        // they don't hide each other
+        let fragment_targets = [Some(wgpu::ColorTargetState {
+            format: config.format,
+            blend: Some(wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Zero,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+            }),
+            write_mask: wgpu::ColorWrites::all(),
+        })];
+
+        let descriptor = tools::RenderPipelineDescriptor {
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            fragment_targets: Some(&fragment_targets),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            ..Default::default()
+        };
+
+        let pipeline = match gpu_simulation {
+            true => tools::create_pipeline(
+                device,
+                config,
+                "Particle Pipeline",
+                &[shared.frame_bind_group_layout(), &emitter_bind_group_layout],
+                &[],
+                concat!(
+                    include_str!("shaders/particle_billboard.wgsl"),
+                    include_str!("shaders/particle_storage.wgsl")
+                ),
+                descriptor,
+            ),
+            false => tools::create_pipeline(
+                device,
+                config,
+                "Particle Pipeline",
+                &[shared.frame_bind_group_layout(), &emitter_bind_group_layout],
+                &[ParticleRaw::desc()],
+                concat!(
+                    include_str!("shaders/particle_billboard.wgsl"),
+                    include_str!("shaders/particle.wgsl")
+                ),
+                descriptor,
+            ),
+        };
+
+        let compute = match gpu_simulation {
+            true => Some(ParticleCompute::new(device)),
+            false => None,
+        };
+
+        Self {
+            pipeline,
+            emitter_bind_group_layout,
+            compute,
+            linear_target: color::is_linear_target(config.format),
+            emitters: HashMap::default(),
+        }
+    }
+
+    /// True if particles are simulated in a compute shader.
+    #[inline]
+    pub fn gpu_simulation(&self) -> bool {
+        self.compute.is_some()
+    }
+
+    #[tracing::instrument(skip_all, name = "particle_prep")]
+    pub(crate) fn prep(
+        &mut self,
+        world: &mut World,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        shared: &SharedRenderResources,
+    ) {
+        world
+            .query_mut::<(&Transform, &mut ParticleEmitter, Option<&Hidden>)>()
+            .into_iter()
+            .for_each(|(entity, (transform, emitter, hidden))| {
+                let capacity = emitter.max_particles.max(1);
+
+                // Resized emitters start over with an empty buffer
+                let stale = !matches!(
+                    self.emitters.get(&entity),
+                    Some(data) if data.ring.capacity() == capacity
+                );
+
+                if stale {
+                    log::trace!("Creating particle buffers for entity {:?}", entity);
+                    self.emitters.insert(
+                        entity,
+                        EmitterData::new(
+                            device,
+                            &self.emitter_bind_group_layout,
+                            self.compute.as_ref(),
+                            entity,
+                            capacity,
+                        ),
+                    );
+                }
+
+                let data = self.emitters.get_mut(&entity).unwrap();
+
+                data.seen = true;
+                data.visible = hidden.is_none();
+
+                let delta = std::mem::take(&mut emitter.pending_seconds);
+                let count = emitter.pending_particles.floor();
+                emitter.pending_particles -= count;
+
+                data.emit(
+                    queue,
+                    shared.recorder(),
+                    transform.translation,
+                    emitter,
+                    count as u32,
+                    self.linear_target,
+                );
+                data.simulate(device, queue, shared.recorder(), emitter, delta);
+            });
+
+        // Anything not found above has been despawned or lost its emitter
+        self.emitters
+            .retain(|_, data| std::mem::take(&mut data.seen));
+    }
+
+    /// Free the buffers held for an entity's emitter.
+    #[inline]
+    pub(crate) fn remove_entity(&mut self, entity: Entity) {
+        self.emitters.remove(&entity);
+    }
+
+    /// Run the compute simulation for every emitter. Does nothing when
+    /// simulating on the CPU.
+    pub(crate) fn simulate(&self, encoder: &mut wgpu::CommandEncoder, recorder: &FrameRecorder) {
+        let compute = match &self.compute {
+            Some(compute) if !self.emitters.is_empty() => compute,
+            _ => return,
+        };
+
+        recorder.record(|| CaptureCommand::BeginPass {
+            label: "Particle Compute Pass",
+        });
+
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Particle Compute Pass"),
+            timestamp_writes: None,
+        });
+
+        recorder.record(|| CaptureCommand::SetPipeline {
+            label: "Particle Compute Pipeline",
+        });
+        pass.set_pipeline(&compute.pipeline);
+
+        self.emitters.values().for_each(|data| {
+            if let Simulation::Gpu {
+                compute_bind_group, ..
+            } = &data.simulation
+            {
+                let workgroups = data.ring.capacity().div_ceil(WORKGROUP_SIZE);
+
+                recorder.record(|| CaptureCommand::Dispatch { workgroups });
+                pass.set_bind_group(0, compute_bind_group, &[]);
+                pass.dispatch_workgroups(workgroups, 1, 1);
+            }
+        });
+    }
+
+    #[tracing::instrument(skip_all, name = "particle_render")]
+    pub(crate) fn render(&self, pass: &mut wgpu::RenderPass, shared: &SharedRenderResources) {
+        if self.emitters.is_empty() {
+            return;
+        }
+
+        shared.recorder().record(|| CaptureCommand::SetPipeline {
+            label: "Particle Pipeline",
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, shared.frame_bind_group(), &[]);
+
+        self.emitters
+            .values()
+            .filter(|data| data.visible)
+            .for_each(|data| {
+                pass.set_bind_group(1, &data.bind_group, &[]);
+
+                match &data.simulation {
+                    Simulation::Gpu { draw_buffer, .. } => {
+                        shared.recorder().record(|| CaptureCommand::DrawIndirect {
+                            max_instances: data.ring.capacity(),
+                        });
+                        pass.draw_indirect(draw_buffer, 0);
+                    }
+                    Simulation::Cpu { instances, .. } => {
+                        if instances.count() == 0 {
+                            return;
+                        }
+
+                        shared.recorder().record(|| CaptureCommand::Draw {
+                            vertices: 4,
+                            instances: instances.count(),
+                            indexed: false,
+                        });
+                        pass.set_vertex_buffer(0, instances.buffer().slice(..));
+                        pass.draw(0..4, 0..instances.count());
+                    }
+                }
+            });
+    }
+}
+
+impl ParticleCompute {
+    fn new(device: &wgpu::Device) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Particle Compute Bind Group Layout"),
+            entries: &[
+                tools::bgl_uniform_entry(0, wgpu::ShaderStages::COMPUTE),
+                tools::bgl_storage_entry(1, wgpu::ShaderStages::COMPUTE, false),
+                tools::bgl_storage_entry(2, wgpu::ShaderStages::COMPUTE, false),
+                tools::bgl_storage_entry(3, wgpu::ShaderStages::COMPUTE, false),
+            ],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Particle Compute Pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Particle Compute shader module"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/particle_sim.wgsl").into()),
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Particle Compute Pipeline"),
+            layout: Some(&layout),
+            module: &module,
+            entry_point: Some("cs_main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+        }
+    }
+}
+
+//====================================================================
+
+enum Simulation {
+    /// Particles stay on the GPU. Only newly emitted particles are uploaded.
+    Gpu {
+        particle_buffer: wgpu::Buffer,
+        /// Indirect draw args, with the instance count filled in by the
+        /// compute pass.
+        draw_buffer: wgpu::Buffer,
+        compute_bind_group: wgpu::BindGroup,
+    },
+    Cpu {
+        particles: Vec<ParticleRaw>,
+        /// Alive particles, rebuilt each frame.
+        instances: tools::InstanceBuffer<ParticleRaw>,
+    },
+}
+
+struct EmitterData {
+    ring: ParticleRing,
+    seed: u32,
+    visible: bool,
+    /// Set when the emitter is found during prep.
+    seen: bool,
+
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    simulation: Simulation,
+}
+
+impl EmitterData {
+    fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        compute: Option<&ParticleCompute>,
+        entity: Entity,
+        capacity: u32,
+    ) -> Self {
+        let uniform_buffer = tools::buffer(
+            device,
+            tools::BufferType::Uniform,
+            "Particle Emitter",
+            &[EmitterUniformRaw {
+                gravity: glam::Vec3::ZERO,
+                delta: 0.,
+                size: 1.,
+                capacity,
+                pad: [0; 2],
+            }],
+        );
+
+        let (bind_group, simulation) = match compute {
+            Some(compute) => {
+                let particle_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Particle Storage Buffer"),
+                    size: capacity as u64 * std::mem::size_of::<ParticleRaw>() as u64,
+                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+
+                let alive_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Particle Alive Storage Buffer"),
+                    size: capacity as u64 * std::mem::size_of::<u32>() as u64,
+                    usage: wgpu::BufferUsages::STORAGE,
+                    mapped_at_creation: false,
+                });
+
+                let draw_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Particle Indirect Buffer"),
+                    size: std::mem::size_of::<wgpu::util::DrawIndirectArgs>() as u64,
+                    usage: wgpu::BufferUsages::INDIRECT
+                        | wgpu::BufferUsages::STORAGE
+                        | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+
+                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Particle Emitter Bind Group"),
+                    layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: uniform_buffer.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: particle_buffer.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: alive_buffer.as_entire_binding(),
+                        },
+                    ],
+                });
+
+                let compute_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Particle Compute Bind Group"),
+                    layout: &compute.bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: uniform_buffer.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: particle_buffer.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: alive_buffer.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 3,
+                            resource: draw_buffer.as_entire_binding(),
+                        },
+                    ],
+                });
+
+                (
+                    bind_group,
+                    Simulation::Gpu {
+                        particle_buffer,
+                        draw_buffer,
+                        compute_bind_group,
+                    },
+                )
+            }
+
+            None => {
+                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Particle Emitter Bind Group"),
+                    layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniform_buffer.as_entire_binding(),
+                    }],
+                });
+
+                (
+                    bind_group,
+                    Simulation::Cpu {
+                        particles: vec![ParticleRaw::default(); capacity as usize],
+                        instances: tools::InstanceBuffer::new(device, &[]),
+                    },
+                )
+            }
+        };
+
+        Self {
+            ring: ParticleRing::new(capacity),
+            // Any nonzero seed works, this just keeps emitters from matching
+            seed: entity.id().wrapping_mul(0x9E37_79B9) | 1,
+            visible: true,
+            seen: false,
+            uniform_buffer,
+            bind_group,
+            simulation,
+        }
+    }
+
+    // Xorshift, good enough to scatter particles
+    fn random(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        (self.seed >> 8) as f32 / (1 << 24) as f32
+    }
+
+    fn random_in_sphere(&mut self) -> glam::Vec3 {
+        loop {
+            let point =
+                glam::vec3(self.random(), self.random(), self.random()) * 2. - glam::Vec3::ONE;
+            if point.length_squared() <= 1. {
+                return point;
+            }
+        }
+    }
+
+    fn emit(
+        &mut self,
+        queue: &wgpu::Queue,
+        recorder: &FrameRecorder,
+        origin: glam::Vec3,
+        emitter: &ParticleEmitter,
+        count: u32,
+        linear_target: bool,
+    ) {
+        let count = count.min(self.ring.capacity());
+        if count == 0 {
+            return;
+        }
+
+        let color = glam::Vec4::from(color::srgba_for_target(emitter.color, linear_target));
+
+        let emitted = (0..count)
+            .map(|_| ParticleRaw {
+                position: origin,
+                age: 0.,
+                velocity: emitter.velocity + self.random_in_sphere() * emitter.spread,
+                lifetime: emitter.lifetime * (MIN_LIFETIME + (1. - MIN_LIFETIME) * self.random()),
+                color,
+            })
+            .collect::<Vec<_>>();
+
+        // Ring buffer so only the new particles are written, in at most two runs
+        let [(start, first_run), (_, second_run)] = self.ring.claim(count);
+        let runs = [
+            (start as usize, &emitted[..first_run as usize]),
+            (0, &emitted[first_run as usize..][..second_run as usize]),
+        ];
+
+        match &mut self.simulation {
+            Simulation::Gpu {
+                particle_buffer, ..
+            } => {
+                let size = std::mem::size_of::<ParticleRaw>() as u64;
+
+                runs.into_iter()
+                    .filter(|(_, run)| !run.is_empty())
+                    .for_each(|(slot, run)| {
+                        recorder.record(|| CaptureCommand::WriteBuffer {
+                            label: String::from("Particle Storage Buffer"),
+                            bytes: std::mem::size_of_val(run) as u64,
+                        });
+                        queue.write_buffer(
+                            particle_buffer,
+                            slot as u64 * size,
+                            bytemuck::cast_slice(run),
+                        );
+                    });
+            }
+            Simulation::Cpu { particles, .. } => {
+                runs.into_iter().for_each(|(slot, run)| {
+                    particles[slot..slot + run.len()].copy_from_slice(run);
+                });
+            }
+        }
+    }
+
+    fn simulate(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        recorder: &FrameRecorder,
+        emitter: &ParticleEmitter,
+        delta: f32,
+    ) {
+        let uniform = EmitterUniformRaw {
+            gravity: emitter.gravity,
+            delta,
+            size: emitter.size,
+            capacity: self.ring.capacity(),
+            pad: [0; 2],
+        };
+
+        recorder.record(|| CaptureCommand::WriteBuffer {
+            label: String::from("Particle Emitter Uniform"),
+            bytes: std::mem::size_of::<EmitterUniformRaw>() as u64,
+        });
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
+
+        match &mut self.simulation {
+            Simulation::Gpu { draw_buffer, .. } => {
+                // Instance count is counted back up by the compute pass
+                let args = wgpu::util::DrawIndirectArgs {
+                    vertex_count: 4,
+                    instance_count: 0,
+                    first_vertex: 0,
+                    first_instance: 0,
+                };
+
+                recorder.record(|| CaptureCommand::WriteBuffer {
+                    label: String::from("Particle Indirect Buffer"),
+                    bytes: std::mem::size_of::<wgpu::util::DrawIndirectArgs>() as u64,
+                });
+                queue.write_buffer(draw_buffer, 0, args.as_bytes());
+            }
+
+            Simulation::Cpu {
+                particles,
+                instances,
+            } => {
+                let alive = simulate_particles(particles, emitter.gravity, delta);
+                instances.update(device, queue, &alive);
+            }
+        }
+    }
+}
+
+//====================================================================
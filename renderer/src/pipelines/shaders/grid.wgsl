@@ -0,0 +1,96 @@
+//====================================================================
+// Uniforms
+
+struct Camera {
+    projection: mat4x4<f32>,
+    position: vec3<f32>,
+}
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+
+
+// Width of grid lines in pixels
+const LINE_WIDTH: f32 = 1.;
+// Major lines are drawn this many times wider
+const MAJOR_LINE_WIDTH: f32 = 2.;
+
+//====================================================================
+
+struct VertexIn {
+    @builtin(vertex_index) index: u32,
+
+    // Instance
+    @location(0) color: vec4<f32>,
+    @location(1) grid: vec4<f32>,
+}
+
+struct VertexOut {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) cell_size: f32,
+    @location(3) major_every: f32,
+    @location(4) fade_distance: f32,
+}
+
+//====================================================================
+
+@vertex
+fn vs_main(in: VertexIn) -> VertexOut {
+    var out: VertexOut;
+
+    // Quad corners from -1 to 1, scaled out to where the grid fades away
+    let corner = vec2<f32>(
+        f32(in.index & 1u) * 2. - 1.,
+        f32((in.index >> 1u) & 1u) * 2. - 1.,
+    );
+
+    let fade_distance = in.grid.w;
+    let world_position = vec3<f32>(
+        camera.position.x + corner.x * fade_distance,
+        in.grid.x,
+        camera.position.z + corner.y * fade_distance,
+    );
+
+    out.clip_position = camera.projection * vec4<f32>(world_position, 1.);
+    out.world_position = world_position;
+    out.color = in.color;
+    out.cell_size = in.grid.y;
+    out.major_every = in.grid.z;
+    out.fade_distance = fade_distance;
+
+    return out;
+}
+
+// 1 on a grid line, fading to 0 over `width` pixels either side
+fn grid_line(position: vec2<f32>, cell_size: f32, width: f32) -> f32 {
+    let coord = position / cell_size;
+    let derivative = fwidth(coord);
+    let distance = abs(fract(coord - 0.5) - 0.5) / (derivative * width);
+
+    return 1. - min(min(distance.x, distance.y), 1.);
+}
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    // Both worked out up front as derivatives need uniform control flow
+    let minor = grid_line(in.world_position.xz, in.cell_size, LINE_WIDTH);
+    let major = grid_line(
+        in.world_position.xz,
+        in.cell_size * max(in.major_every, 1.),
+        MAJOR_LINE_WIDTH,
+    );
+    let line = select(minor, max(minor, major), in.major_every > 1.);
+
+    let distance = length(in.world_position.xz - camera.position.xz);
+    let fade = 1. - smoothstep(in.fade_distance * 0.5, in.fade_distance, distance);
+
+    let alpha = in.color.a * line * fade;
+    if alpha <= 0. {
+        discard;
+    }
+
+    return vec4<f32>(in.color.rgb, alpha);
+}
+
+//====================================================================
@@ -0,0 +1,25 @@
+
+//====================================================================
+// Instances read from a vertex buffer, simulated on the CPU
+
+struct VertexIn {
+    // Vertex
+    @builtin(vertex_index) index: u32,
+
+    // Instance
+    @location(0) position_age: vec4<f32>,
+    @location(1) velocity_lifetime: vec4<f32>,
+    @location(2) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(in: VertexIn) -> VertexOut {
+    return billboard(
+        in.index,
+        in.position_age.xyz,
+        in.color,
+        in.position_age.w / in.velocity_lifetime.w
+    );
+}
+
+//====================================================================
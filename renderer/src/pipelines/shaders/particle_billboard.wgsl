@@ -0,0 +1,65 @@
+//====================================================================
+// Shared by particle.wgsl and particle_storage.wgsl, which each add their own
+// vs_main reading particles from where they're kept.
+//====================================================================
+// Uniforms
+
+struct Camera {
+    projection: mat4x4<f32>,
+    position: vec3<f32>,
+}
+
+struct Emitter {
+    gravity: vec3<f32>,
+    delta: f32,
+    size: f32,
+    capacity: u32,
+}
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+
+@group(1) @binding(0) var<uniform> emitter: Emitter;
+
+//====================================================================
+
+struct VertexOut {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+//====================================================================
+
+// Quad facing the camera, fading out as the particle ages
+fn billboard(index: u32, position: vec3<f32>, color: vec4<f32>, progress: f32) -> VertexOut {
+    var out: VertexOut;
+
+    // Triangle strip corners - 0 = Bottom Left, 1 = Bottom Right, 2 = Top Left, 3 = Top Right
+    let corner = vec2<f32>(f32(index & 1u), f32(index >> 1u)) - 0.5;
+
+    let to_camera = normalize(camera.position - position);
+    let side = cross(vec3<f32>(0., 1., 0.), to_camera);
+
+    // Looking straight down has no side vector to build from
+    let right = select(vec3<f32>(1., 0., 0.), normalize(side), length(side) > 0.0001);
+    let up = cross(to_camera, right);
+
+    let vertex_pos = position + (right * corner.x + up * corner.y) * emitter.size;
+
+    out.clip_position = camera.projection * vec4<f32>(vertex_pos, 1.);
+    out.uv = corner + 0.5;
+    out.color = vec4<f32>(color.rgb, color.a * (1. - progress));
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    // Soft round particles
+    let distance = length(in.uv - 0.5) * 2.;
+    let falloff = 1. - smoothstep(0.5, 1., distance);
+
+    return vec4<f32>(in.color.rgb, in.color.a * falloff);
+}
+
+//====================================================================
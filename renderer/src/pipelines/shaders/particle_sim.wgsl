@@ -0,0 +1,57 @@
+//====================================================================
+// Uniforms
+
+struct Emitter {
+    gravity: vec3<f32>,
+    delta: f32,
+    size: f32,
+    capacity: u32,
+}
+
+struct Particle {
+    position: vec3<f32>,
+    age: f32,
+    velocity: vec3<f32>,
+    lifetime: f32,
+    color: vec4<f32>,
+}
+
+struct DrawArgs {
+    vertex_count: u32,
+    instance_count: atomic<u32>,
+    first_vertex: u32,
+    first_instance: u32,
+}
+
+@group(0) @binding(0) var<uniform> emitter: Emitter;
+@group(0) @binding(1) var<storage, read_write> particles: array<Particle>;
+@group(0) @binding(2) var<storage, read_write> alive: array<u32>;
+@group(0) @binding(3) var<storage, read_write> draw: DrawArgs;
+
+//====================================================================
+
+// Step every particle forward and list the ones still alive for drawing
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= emitter.capacity {
+        return;
+    }
+
+    var particle = particles[index];
+    if particle.age >= particle.lifetime {
+        return;
+    }
+
+    particle.velocity += emitter.gravity * emitter.delta;
+    particle.position += particle.velocity * emitter.delta;
+    particle.age += emitter.delta;
+    particles[index] = particle;
+
+    if particle.age < particle.lifetime {
+        let slot = atomicAdd(&draw.instance_count, 1u);
+        alive[slot] = index;
+    }
+}
+
+//====================================================================
@@ -0,0 +1,26 @@
+//====================================================================
+// Particles read from the storage buffers the compute pass simulates
+
+struct Particle {
+    position: vec3<f32>,
+    age: f32,
+    velocity: vec3<f32>,
+    lifetime: f32,
+    color: vec4<f32>,
+}
+
+@group(1) @binding(1) var<storage, read> particles: array<Particle>;
+@group(1) @binding(2) var<storage, read> alive: array<u32>;
+
+//====================================================================
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) index: u32,
+    @builtin(instance_index) instance: u32,
+) -> VertexOut {
+    let particle = particles[alive[instance]];
+    return billboard(index, particle.position, particle.color, particle.age / particle.lifetime);
+}
+
+//====================================================================
@@ -0,0 +1,74 @@
+//====================================================================
+// Uniforms
+
+struct Camera {
+    projection: mat4x4<f32>,
+    position: vec3<f32>,
+}
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+
+
+// Matches animation::MAX_JOINTS
+const MAX_JOINTS: u32 = 64u;
+
+struct Instance {
+    transform: mat4x4<f32>,
+    color: vec4<f32>,
+    joints: array<mat4x4<f32>, MAX_JOINTS>,
+}
+
+@group(1) @binding(0) var<uniform> instance: Instance;
+
+@group(2) @binding(0) var texture: texture_2d<f32>;
+@group(2) @binding(1) var texture_sampler: sampler;
+
+//====================================================================
+
+struct VertexIn {
+    @location(0) position: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) joints: vec4<u32>,
+    @location(3) weights: vec4<f32>,
+}
+
+struct VertexOut {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+//====================================================================
+
+@vertex
+fn vs_main(in: VertexIn) -> VertexOut {
+    var out: VertexOut;
+
+    // Out of range joints are clamped rather than read past the palette
+    let joints = min(in.joints, vec4<u32>(MAX_JOINTS - 1u));
+
+    let skin =
+        instance.joints[joints.x] * in.weights.x +
+        instance.joints[joints.y] * in.weights.y +
+        instance.joints[joints.z] * in.weights.z +
+        instance.joints[joints.w] * in.weights.w;
+
+    let world_position = instance.transform * skin * vec4<f32>(in.position, 1.);
+
+    out.clip_position = camera.projection * world_position;
+    out.uv = in.uv;
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    let color = textureSample(texture, texture_sampler, in.uv) * instance.color;
+
+    if color.a <= 0. {
+        discard;
+    }
+
+    return color;
+}
+
+//====================================================================
@@ -8,12 +8,18 @@ struct Camera {
 
 struct Position {
     transform: mat4x4<f32>,
+    opacity: f32,
+    // Nonzero if drawing into an sRGB or HDR target
+    linear_target: u32,
+    // Local clip as min xy then max xy
+    clip: vec4<f32>,
 }
 
 @group(0) @binding(0) var<uniform> camera: Camera;
 
 @group(1) @binding(0) var atlas_texture: texture_2d<f32>;
 @group(1) @binding(1) var atlas_texture_sampler: sampler;
+@group(1) @binding(2) var atlas_color_texture: texture_2d<f32>;
 
 @group(2) @binding(0) var<uniform> position: Position;
 
@@ -30,16 +36,31 @@ struct VertexIn {
     @location(2) uv_start: vec2<f32>,
     @location(3) uv_end: vec2<f32>,
     @location(4) color: u32,
+    @location(5) content: u32,
 }
 
 struct VertexOut {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) uv: vec2<f32>,
     @location(1) color: vec4<f32>,
+    @location(2) @interpolate(flat) content: u32,
+    @location(3) local_pos: vec2<f32>,
 }
 
 //====================================================================
 
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let cutoff = color <= vec3<f32>(0.04045);
+    let lower = color / 12.92;
+    let higher = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+
+    return select(higher, lower, cutoff);
+}
+
+fn clipped(local_pos: vec2<f32>) -> bool {
+    return any(local_pos < position.clip.xy) || any(local_pos > position.clip.zw);
+}
+
 @vertex
 fn vs_main(in: VertexIn) -> VertexOut {
     var out: VertexOut;
@@ -81,21 +102,46 @@ fn vs_main(in: VertexIn) -> VertexOut {
         * position.transform
         * vec4<f32>(vertex_pos, 1., 1.);
 
-    out.color = vec4<f32>(
+    out.local_pos = vertex_pos;
+
+    // Glyph colors are sRGB encoded - convert to linear for blending unless
+    // the target stores them as is
+    let srgb = vec3<f32>(
         f32((in.color & 0x00ff0000u) >> 16u) / 255.,
         f32((in.color & 0x0000ff00u) >> 8u) / 255.,
-        f32(in.color & 0x00ff0000u) / 255.,
-        f32((in.color & 0xff000000u) >> 24u) / 255.,
+        f32(in.color & 0x000000ffu) / 255.,
+    );
+
+    out.color = vec4<f32>(
+        select(srgb, srgb_to_linear(srgb), position.linear_target != 0u),
+        f32((in.color & 0xff000000u) >> 24u) / 255. * position.opacity,
     );
 
+    out.content = in.content;
+
     return out;
 }
 
 @fragment
 fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
-    let tex_color = textureSample(atlas_texture, atlas_texture_sampler, in.uv);
-    
-    return vec4<f32>(in.color.xyz, in.color.w * tex_color.x);
+    // UVs are in texels so glyphs keep their place as pages grow. Sample both
+    // pages to keep sampling in uniform control flow
+    let mask_uv = in.uv / vec2<f32>(textureDimensions(atlas_texture));
+    let color_uv = in.uv / vec2<f32>(textureDimensions(atlas_color_texture));
+
+    let mask = textureSample(atlas_texture, atlas_texture_sampler, mask_uv);
+    let color = textureSample(atlas_color_texture, atlas_texture_sampler, color_uv);
+
+    if clipped(in.local_pos) {
+        discard;
+    }
+
+    // 0 = Mask, 1 = Color
+    if (in.content == 1u) {
+        return vec4<f32>(color.xyz, color.w * in.color.w);
+    }
+
+    return vec4<f32>(in.color.xyz, in.color.w * mask.x);
 }
 
 //====================================================================
@@ -0,0 +1,164 @@
+
+//====================================================================
+// Uniforms
+
+struct Camera {
+    projection: mat4x4<f32>,
+    position: vec3<f32>,
+}
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+
+@group(1) @binding(0) var textures: binding_array<texture_2d<f32>>;
+@group(1) @binding(1) var texture_sampler: sampler;
+
+
+// Material flags, matching MaterialFlags
+const FLAG_GRAYSCALE: u32 = 1u;
+const FLAG_SILHOUETTE: u32 = 2u;
+const FLAG_ALPHA_CUTOUT: u32 = 4u;
+
+// Alpha below which cutout materials are discarded
+const ALPHA_CUTOUT: f32 = 0.5;
+
+// Depth offset applied per layer of a sprite stack
+const STACK_DEPTH_BIAS: f32 = 0.00001;
+
+// Color added per sprite by the overdraw debug view
+const OVERDRAW_COLOR: vec3<f32> = vec3<f32>(0.1, 0.04, 0.02);
+// Distance from the camera drawn as black by the depth debug view
+const DEPTH_VIEW_RANGE: f32 = 2000.;
+
+//====================================================================
+
+struct VertexIn {
+    // Vertex
+    @location(0) vertex_position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+
+    // Instance
+    @location(2) size_index: vec4<f32>,
+    @location(3) transform_1: vec4<f32>,
+    @location(4) transform_2: vec4<f32>,
+    @location(5) transform_3: vec4<f32>,
+    @location(6) transform_4: vec4<f32>,
+    @location(7) color: vec4<f32>,
+    @location(8) palette: vec4<f32>,
+    @location(9) uv_transform: vec4<f32>,
+}
+
+struct VertexOut {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) @interpolate(flat) texture_index: u32,
+    @location(3) hue_shift: f32,
+    @location(4) depth: f32,
+    @location(5) @interpolate(flat) flags: u32,
+}
+
+//====================================================================
+
+@vertex
+fn vs_main(in: VertexIn) -> VertexOut {
+    var out: VertexOut;
+    
+    let transform = mat4x4<f32>(
+        in.transform_1,
+        in.transform_2,
+        in.transform_3,
+        in.transform_4,
+    );
+
+    let vertex_pos = in.vertex_position * in.size_index.xy;
+
+    out.clip_position =
+        camera.projection
+        * transform
+        * vec4<f32>(vertex_pos, 1., 1.);
+
+    // Stacked sprites are nudged towards the camera so they draw over the
+    // sprites beneath them instead of z-fighting
+    out.clip_position.z -= in.size_index.w * STACK_DEPTH_BIAS * out.clip_position.w;
+
+    out.uv = in.uv * in.uv_transform.zw + in.uv_transform.xy;
+    out.color = in.color;
+    out.hue_shift = in.palette.x;
+    out.flags = u32(in.palette.y);
+    out.depth = out.clip_position.w;
+    out.texture_index = u32(in.size_index.z);
+
+    return out;
+}
+
+// Rotate around the grey axis, keeping brightness
+fn hue_shift(color: vec3<f32>, angle: f32) -> vec3<f32> {
+    let axis = vec3<f32>(0.57735);
+    let cos_angle = cos(angle);
+
+    return color * cos_angle
+        + cross(axis, color) * sin(angle)
+        + axis * dot(axis, color) * (1. - cos_angle);
+}
+
+// Tinted and recolored texture color, shared by the blend mode entry points
+fn sprite_color(in: VertexOut) -> vec4<f32> {
+    var tex_color = textureSample(textures[in.texture_index], texture_sampler, in.uv);
+
+    if (in.flags & FLAG_GRAYSCALE) != 0u {
+        let luminance = dot(tex_color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+        tex_color = vec4<f32>(vec3<f32>(luminance), tex_color.a);
+    }
+
+    if (in.flags & FLAG_SILHOUETTE) != 0u {
+        tex_color = vec4<f32>(vec3<f32>(1.), tex_color.a);
+    }
+
+    var color = tex_color * in.color;
+
+    // Shifted after tinting so a variant's tint is recolored along with it
+    if in.hue_shift != 0. {
+        color = vec4<f32>(hue_shift(color.rgb, in.hue_shift), color.a);
+    }
+
+    if (in.flags & FLAG_ALPHA_CUTOUT) != 0u && color.a < ALPHA_CUTOUT {
+        discard;
+    }
+    
+    return color;
+}
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    return sprite_color(in);
+}
+
+// Multiply blending scales what's behind by the output color, so fade
+// transparent texels towards white rather than black
+@fragment
+fn fs_multiply(in: VertexOut) -> @location(0) vec4<f32> {
+    let color = sprite_color(in);
+    return vec4<f32>(mix(vec3<f32>(1.), color.rgb, color.a), color.a);
+}
+
+//====================================================================
+
+// Debug views
+
+@fragment
+fn fs_overdraw(in: VertexOut) -> @location(0) vec4<f32> {
+    return vec4<f32>(OVERDRAW_COLOR, 1.);
+}
+
+@fragment
+fn fs_depth(in: VertexOut) -> @location(0) vec4<f32> {
+    // Keep sprite outlines rather than drawing whole quads
+    if textureSample(textures[in.texture_index], texture_sampler, in.uv).a * in.color.a < 0.01 {
+        discard;
+    }
+
+    let grey = 1. - clamp(in.depth / DEPTH_VIEW_RANGE, 0., 1.);
+    return vec4<f32>(vec3<f32>(grey), 1.);
+}
+
+//====================================================================
@@ -0,0 +1,53 @@
+
+//====================================================================
+// Uniforms
+
+@group(0) @binding(0) var source_texture: texture_2d<f32>;
+@group(0) @binding(1) var source_sampler: sampler;
+
+
+//====================================================================
+
+struct VertexOut {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+//====================================================================
+
+// Single triangle covering the whole viewport
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOut {
+    var out: VertexOut;
+
+    let uv = vec2<f32>(
+        f32((index << 1u) & 2u),
+        f32(index & 2u),
+    );
+
+    out.clip_position = vec4<f32>(uv.x * 2. - 1., 1. - uv.y * 2., 0., 1.);
+    out.uv = uv;
+
+    return out;
+}
+
+// Narkowicz ACES filmic curve approximation
+fn aces(color: vec3<f32>) -> vec3<f32> {
+    let a = 2.51;
+    let b = 0.03;
+    let c = 2.43;
+    let d = 0.59;
+    let e = 0.14;
+
+    return clamp((color * (a * color + b)) / (color * (c * color + d) + e), vec3<f32>(0.), vec3<f32>(1.));
+}
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    let hdr = textureSample(source_texture, source_sampler, in.uv);
+
+    // Output is linear - the sRGB surface format handles encoding
+    return vec4<f32>(aces(hdr.rgb), hdr.a);
+}
+
+//====================================================================
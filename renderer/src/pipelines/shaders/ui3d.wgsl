@@ -6,6 +6,12 @@ struct Camera {
     position: vec3<f32>,
 }
 
+struct Frame {
+    viewport_size: vec2<f32>,
+    time: f32,
+    index: u32,
+}
+
 struct Ui {
     size: vec4<f32>,
     menu_color: vec4<f32>,
@@ -15,9 +21,13 @@ struct Ui {
 
 struct Position {
     transform: mat4x4<f32>,
+    opacity: f32,
+    // Local clip as min xy then max xy
+    clip: vec4<f32>,
 }
 
 @group(0) @binding(0) var<uniform> camera: Camera;
+@group(0) @binding(1) var<uniform> frame: Frame;
 
 @group(1) @binding(0) var<uniform> ui: Ui;
 
@@ -32,10 +42,15 @@ struct VertexOut {
     @location(1) menu_color: vec4<f32>,
     @location(2) selection_color: vec4<f32>,
     @location(3) selection_range: vec2<f32>,
+    @location(4) local_pos: vec2<f32>,
 }
 
 //====================================================================
 
+fn clipped(local_pos: vec2<f32>) -> bool {
+    return any(local_pos < position.clip.xy) || any(local_pos > position.clip.zw);
+}
+
 @vertex
 fn vs_main(@builtin(vertex_index) index: u32) -> VertexOut {
     var out: VertexOut;
@@ -85,6 +100,8 @@ fn vs_main(@builtin(vertex_index) index: u32) -> VertexOut {
         * position.transform
         * vec4<f32>(vertex_pos, 1., 1.);
 
+    out.local_pos = vertex_pos;
+
     out.menu_color = ui.menu_color;
     out.selection_color = ui.selection_color;
     out.selection_range = ui.selection_range_y.xy;
@@ -92,10 +109,20 @@ fn vs_main(@builtin(vertex_index) index: u32) -> VertexOut {
     return out;
 }
 
+// Selection brightness swings this far either way
+const PULSE_AMOUNT: f32 = 0.08;
+// Radians per second the pulse moves through
+const PULSE_SPEED: f32 = 4.;
+
 @fragment
 fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    if clipped(in.local_pos) {
+        discard;
+    }
+
     if in.uv.y > in.selection_range.x && in.uv.y < in.selection_range.y {
-        return in.selection_color;
+        let pulse = 1. + sin(frame.time * PULSE_SPEED) * PULSE_AMOUNT;
+        return vec4<f32>(in.selection_color.rgb * pulse, in.selection_color.a);
     }
 
     return in.menu_color;
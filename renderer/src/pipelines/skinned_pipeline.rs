@@ -0,0 +1,342 @@
+//====================================================================
+
+use std::{collections::HashMap, sync::Arc};
+
+use common::Transform;
+use hecs::{Entity, World};
+
+use crate::{
+    animation::{JointPalette, MAX_JOINTS},
+    capture::CaptureCommand,
+    color,
+    shared::{SharedRenderResources, Vertex},
+    texture::Texture,
+    texture_storage::LoadedTexture,
+    tools,
+};
+
+use super::{Hidden, Opacity};
+
+//====================================================================
+
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Debug, Default, PartialEq)]
+pub struct SkinnedVertex {
+    pub position: glam::Vec3,
+    pub uv: glam::Vec2,
+    /// Up to four joints moving this vertex, indexing the skeleton.
+    pub joints: [u32; 4],
+    /// How much each of `joints` moves this vertex. Should add up to 1.
+    pub weights: [f32; 4],
+}
+
+impl Vertex for SkinnedVertex {
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+            0 => Float32x3, // Position
+            1 => Float32x2, // Uv
+            2 => Uint32x4, // Joints
+            3 => Float32x4, // Weights
+        ];
+
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &VERTEX_ATTRIBUTES,
+        }
+    }
+}
+
+/// Vertices and indices of a mesh bound to a skeleton. Share one between
+/// entities with an [`Arc`] so they share its GPU buffers too.
+#[derive(Debug, Clone, Default)]
+pub struct SkinnedMeshData {
+    pub vertices: Vec<SkinnedVertex>,
+    pub indices: Vec<u16>,
+}
+
+/// Mesh deformed in the vertex shader by the entity's [`JointPalette`], as
+/// kept up to date by an [`AnimationPlayer`](crate::animation::AnimationPlayer).
+/// Drawn in its bind pose until the entity has a palette.
+#[derive(Debug, Clone)]
+pub struct SkinnedMesh {
+    pub mesh: Arc<SkinnedMeshData>,
+    pub texture: Arc<LoadedTexture>,
+    /// sRGB tint with linear alpha, multiplied with the texture.
+    pub color: [f32; 4],
+}
+
+impl SkinnedMesh {
+    #[inline]
+    pub fn new(mesh: Arc<SkinnedMeshData>, texture: Arc<LoadedTexture>) -> Self {
+        Self {
+            mesh,
+            texture,
+            color: [1.; 4],
+        }
+    }
+}
+
+//====================================================================
+
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Debug)]
+struct SkinnedUniformRaw {
+    transform: glam::Mat4,
+    /// Linear color with alpha.
+    color: glam::Vec4,
+    joints: [glam::Mat4; MAX_JOINTS],
+}
+
+struct MeshBuffers {
+    mesh: Arc<SkinnedMeshData>,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    index_count: u32,
+}
+
+struct SkinnedInstance {
+    /// Rebuilt whenever the entity's mesh is swapped for another.
+    buffers: MeshBuffers,
+    texture: Arc<LoadedTexture>,
+    visible: bool,
+    /// Set when the entity is found during prep.
+    seen: bool,
+
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+/// Draws every [`SkinnedMesh`], uploading each entity's joint palette to its
+/// own uniform buffer.
+pub struct SkinnedRenderer {
+    pipeline: wgpu::RenderPipeline,
+    instance_bind_group_layout: wgpu::BindGroupLayout,
+    linear_target: bool,
+
+    instances: HashMap<Entity, SkinnedInstance>,
+}
+
+impl SkinnedRenderer {
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+    ) -> Self {
+        // A uniform rather than storage buffer so skinning also works on WebGL
+        let instance_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Skinned Instance Bind Group Layout"),
+                entries: &[tools::bgl_uniform_entry(
+                    0,
+                    wgpu::ShaderStages::VERTEX_FRAGMENT,
+                )],
+            });
+
+        let fragment_targets = [Some(wgpu::ColorTargetState {
+            format: config.format,
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+            write_mask: wgpu::ColorWrites::all(),
+        })];
+
+        let pipeline = tools::create_pipeline(
+            device,
+            config,
+            "Skinned Pipeline",
+            &[
+                shared.frame_bind_group_layout(),
+                &instance_bind_group_layout,
+                shared.texture_bind_group_layout(),
+            ],
+            &[SkinnedVertex::desc()],
+            include_str!("shaders/skinned.wgsl"),
+            tools::RenderPipelineDescriptor {
+                fragment_targets: Some(&fragment_targets),
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: Texture::DEPTH_FORMAT,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::Less,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                ..Default::default()
+            },
+        );
+
+        Self {
+            pipeline,
+            instance_bind_group_layout,
+            linear_target: color::is_linear_target(config.format),
+            instances: HashMap::default(),
+        }
+    }
+
+    #[tracing::instrument(skip_all, name = "skinned_prep")]
+    pub(crate) fn prep(
+        &mut self,
+        world: &mut World,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        shared: &SharedRenderResources,
+    ) {
+        world
+            .query_mut::<(
+                &Transform,
+                &SkinnedMesh,
+                Option<&JointPalette>,
+                Option<&Opacity>,
+                Option<&Hidden>,
+            )>()
+            .into_iter()
+            .for_each(|(entity, (transform, skinned, palette, opacity, hidden))| {
+                let instance = self.instances.entry(entity).or_insert_with(|| {
+                    log::trace!("Creating skinned mesh buffers for entity {:?}", entity);
+                    SkinnedInstance::new(device, &self.instance_bind_group_layout, skinned)
+                });
+
+                if !Arc::ptr_eq(&instance.buffers.mesh, &skinned.mesh) {
+                    instance.buffers = MeshBuffers::new(device, &skinned.mesh);
+                }
+
+                instance.texture = skinned.texture.clone();
+                instance.visible = hidden.is_none();
+                instance.seen = true;
+
+                let uniform = SkinnedUniformRaw {
+                    transform: transform.to_matrix(),
+                    color: color::srgba_for_target(
+                        Opacity::apply(skinned.color, Opacity::of(opacity)),
+                        self.linear_target,
+                    )
+                    .into(),
+                    joints: palette_matrices(palette),
+                };
+
+                shared.recorder().record(|| CaptureCommand::WriteBuffer {
+                    label: String::from("Skinned Instance Uniform"),
+                    bytes: std::mem::size_of::<SkinnedUniformRaw>() as u64,
+                });
+                queue.write_buffer(
+                    &instance.uniform_buffer,
+                    0,
+                    bytemuck::cast_slice(&[uniform]),
+                );
+            });
+
+        // Anything not found above has been despawned or lost its mesh
+        self.instances
+            .retain(|_, instance| std::mem::take(&mut instance.seen));
+    }
+
+    /// Free the buffers held for an entity's mesh.
+    #[inline]
+    pub(crate) fn remove_entity(&mut self, entity: Entity) {
+        self.instances.remove(&entity);
+    }
+
+    #[tracing::instrument(skip_all, name = "skinned_render")]
+    pub(crate) fn render(&self, pass: &mut wgpu::RenderPass, shared: &SharedRenderResources) {
+        if self.instances.is_empty() {
+            return;
+        }
+
+        shared.recorder().record(|| CaptureCommand::SetPipeline {
+            label: "Skinned Pipeline",
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, shared.frame_bind_group(), &[]);
+
+        self.instances
+            .values()
+            .filter(|instance| instance.visible && instance.buffers.index_count > 0)
+            .for_each(|instance| {
+                shared.recorder().record(|| CaptureCommand::Draw {
+                    vertices: instance.buffers.index_count,
+                    instances: 1,
+                    indexed: true,
+                });
+
+                pass.set_bind_group(1, &instance.bind_group, &[]);
+                pass.set_bind_group(2, instance.texture.bind_group(), &[]);
+                pass.set_vertex_buffer(0, instance.buffers.vertex_buffer.slice(..));
+                pass.set_index_buffer(
+                    instance.buffers.index_buffer.slice(..),
+                    wgpu::IndexFormat::Uint16,
+                );
+                pass.draw_indexed(0..instance.buffers.index_count, 0, 0..1);
+            });
+    }
+}
+
+// Palette padded out with identity matrices, so meshes without one stay in
+// their bind pose
+fn palette_matrices(palette: Option<&JointPalette>) -> [glam::Mat4; MAX_JOINTS] {
+    let mut joints = [glam::Mat4::IDENTITY; MAX_JOINTS];
+
+    if let Some(palette) = palette {
+        joints
+            .iter_mut()
+            .zip(palette.0.iter())
+            .for_each(|(joint, matrix)| *joint = *matrix);
+    }
+
+    joints
+}
+
+//====================================================================
+
+impl MeshBuffers {
+    fn new(device: &wgpu::Device, mesh: &Arc<SkinnedMeshData>) -> Self {
+        Self {
+            mesh: mesh.clone(),
+            vertex_buffer: tools::buffer(
+                device,
+                tools::BufferType::Vertex,
+                "Skinned Mesh",
+                &mesh.vertices,
+            ),
+            index_buffer: tools::buffer(
+                device,
+                tools::BufferType::Index,
+                "Skinned Mesh",
+                &mesh.indices,
+            ),
+            index_count: mesh.indices.len() as u32,
+        }
+    }
+}
+
+impl SkinnedInstance {
+    fn new(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, skinned: &SkinnedMesh) -> Self {
+        let uniform_buffer = tools::buffer(
+            device,
+            tools::BufferType::Uniform,
+            "Skinned Instance",
+            &[SkinnedUniformRaw {
+                transform: glam::Mat4::IDENTITY,
+                color: glam::Vec4::ONE,
+                joints: [glam::Mat4::IDENTITY; MAX_JOINTS],
+            }],
+        );
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Skinned Instance Bind Group"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        Self {
+            buffers: MeshBuffers::new(device, &skinned.mesh),
+            texture: skinned.texture.clone(),
+            visible: true,
+            seen: false,
+            uniform_buffer,
+            bind_group,
+        }
+    }
+}
+
+//====================================================================
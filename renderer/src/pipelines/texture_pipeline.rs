@@ -1,7 +1,8 @@
 //====================================================================
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
+    num::NonZeroU32,
     sync::Arc,
 };
 
@@ -9,32 +10,414 @@ use common::Transform;
 use hecs::World;
 
 use crate::{
+    camera::PixelSnap,
+    capture::{CaptureCommand, FrameRecorder},
+    color,
     shared::{
         SharedRenderResources, TextureRectVertex, Vertex, TEXTURE_RECT_INDEX_COUNT,
         TEXTURE_RECT_INDICES, TEXTURE_RECT_VERTICES,
     },
     texture_storage::LoadedTexture,
-    tools,
+    tools, DebugView,
 };
 
+use super::{Hidden, Opacity};
+
 //====================================================================
 
 pub struct Sprite {
+    pub material: Material,
+    pub size: glam::Vec2,
+    /// sRGB tint with linear alpha, multiplied with the texture.
+    pub color: [f32; 4],
+    pub palette: PaletteSwap,
+
+    pub flip_x: bool,
+    pub flip_y: bool,
+    /// Added to texture coordinates, in whole textures. Animate it to scroll
+    /// the texture across the sprite.
+    pub uv_offset: glam::Vec2,
+    /// Values above 1 repeat the texture across the sprite.
+    pub uv_scale: glam::Vec2,
+}
+
+impl Sprite {
+    /// Untinted sprite showing the whole texture. Takes a [`Material`] or a
+    /// texture to use with the default material.
+    pub fn new(material: impl Into<Material>, size: glam::Vec2) -> Self {
+        Self {
+            material: material.into(),
+            size,
+            color: [1.; 4],
+            palette: PaletteSwap::None,
+            flip_x: false,
+            flip_y: false,
+            uv_offset: glam::Vec2::ZERO,
+            uv_scale: glam::Vec2::ONE,
+        }
+    }
+}
+
+// Offset in xy and scale in zw, with flips folded in
+fn uv_transform(flip_x: bool, flip_y: bool, offset: glam::Vec2, scale: glam::Vec2) -> glam::Vec4 {
+    let flip = |flipped: bool, offset: f32, scale: f32| match flipped {
+        true => (offset + scale, -scale),
+        false => (offset, scale),
+    };
+
+    let (offset_x, scale_x) = flip(flip_x, offset.x, scale.x);
+    let (offset_y, scale_y) = flip(flip_y, offset.y, scale.y);
+
+    glam::vec4(offset_x, offset_y, scale_x, scale_y)
+}
+
+/// Split translucent batches into runs that draw every translucent sprite far
+/// to near, as they have to be drawn over whatever is behind them. Sprites
+/// with different textures or samplers end up in separate runs, which are
+/// ordered by [`BatchKey::depth_order`].
+pub fn order_translucent(
+    batches: &mut HashMap<BatchKey, Vec<InstanceTexture>>,
+    camera_position: glam::Vec3,
+) {
+    let keys = batches
+        .keys()
+        .filter(|key| key.translucent)
+        .copied()
+        .collect::<Vec<_>>();
+
+    let mut translucent = keys
+        .into_iter()
+        .flat_map(|key| {
+            batches
+                .remove(&key)
+                .unwrap_or_default()
+                .into_iter()
+                .map(move |instance| (key, instance))
+        })
+        .collect::<Vec<_>>();
+
+    let distance = |instance: &InstanceTexture| {
+        instance
+            .transform
+            .w_axis
+            .truncate()
+            .distance_squared(camera_position)
+    };
+
+    // Layers still come first, and parts of a stack at the same depth keep
+    // their order
+    translucent.sort_by(|(a_key, a), (b_key, b)| {
+        a_key
+            .layer
+            .cmp(&b_key.layer)
+            .then_with(|| distance(b).total_cmp(&distance(a)))
+            .then_with(|| a_key.stack.cmp(&b_key.stack))
+    });
+
+    let mut depth_order = 0;
+    let mut previous = None;
+
+    translucent.into_iter().for_each(|(key, instance)| {
+        if previous.is_some_and(|previous| previous != key) {
+            depth_order += 1;
+        }
+        previous = Some(key);
+
+        batches
+            .entry(BatchKey { depth_order, ..key })
+            .or_default()
+            .push(instance);
+    });
+}
+
+/// Recolors a sprite in the shader so variants can share the same texture.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum PaletteSwap {
+    #[default]
+    None,
+    /// Rotate the hue of the tinted texture by this many degrees, keeping its
+    /// brightness.
+    HueShift(f32),
+}
+
+impl PaletteSwap {
+    #[inline]
+    fn hue_shift_radians(&self) -> f32 {
+        match self {
+            PaletteSwap::None => 0.,
+            PaletteSwap::HueShift(degrees) => degrees.to_radians(),
+        }
+    }
+}
+
+/// How a sprite is drawn. Sprites sharing a texture, sampler and blend mode
+/// are batched together, while flags are applied per sprite so they never
+/// split a batch.
+#[derive(Debug, Clone)]
+pub struct Material {
+    pub texture: Arc<LoadedTexture>,
+    pub sampler: SamplerPreset,
+    /// How the sprite is combined with what's behind it. Layers of a
+    /// [`SpriteStack`] are always alpha blended.
+    pub blend: BlendMode,
+    pub flags: MaterialFlags,
+}
+
+impl Material {
+    /// Alpha blended with nearest sampling and no flags.
+    #[inline]
+    pub fn new(texture: Arc<LoadedTexture>) -> Self {
+        Self {
+            texture,
+            sampler: SamplerPreset::default(),
+            blend: BlendMode::default(),
+            flags: MaterialFlags::NONE,
+        }
+    }
+
+    #[inline]
+    pub fn with_sampler(mut self, sampler: SamplerPreset) -> Self {
+        self.sampler = sampler;
+        self
+    }
+
+    #[inline]
+    pub fn with_blend(mut self, blend: BlendMode) -> Self {
+        self.blend = blend;
+        self
+    }
+
+    #[inline]
+    pub fn with_flags(mut self, flags: MaterialFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+}
+
+impl From<Arc<LoadedTexture>> for Material {
+    #[inline]
+    fn from(texture: Arc<LoadedTexture>) -> Self {
+        Self::new(texture)
+    }
+}
+
+/// Filtering used when sampling a material's texture.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SamplerPreset {
+    /// Crisp pixels, for pixel art.
+    #[default]
+    Nearest,
+    /// Smoothly filtered, for painted art and sprites scaled well off their
+    /// texture size.
+    Linear,
+}
+
+impl SamplerPreset {
+    pub const ALL: [SamplerPreset; 2] = [SamplerPreset::Nearest, SamplerPreset::Linear];
+
+    fn create_sampler(&self, device: &wgpu::Device) -> wgpu::Sampler {
+        let (label, filter) = match self {
+            SamplerPreset::Nearest => ("Nearest Sprite Sampler", wgpu::FilterMode::Nearest),
+            SamplerPreset::Linear => ("Linear Sprite Sampler", wgpu::FilterMode::Linear),
+        };
+
+        // Repeats so uv offsets and scales can scroll and tile any texture
+        device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some(label),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            mag_filter: filter,
+            min_filter: filter,
+            ..Default::default()
+        })
+    }
+}
+
+/// Shader variations applied per sprite. Combine with `|`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct MaterialFlags(u32);
+
+impl MaterialFlags {
+    pub const NONE: MaterialFlags = MaterialFlags(0);
+    /// Drop the texture's color, keeping its brightness.
+    pub const GRAYSCALE: MaterialFlags = MaterialFlags(1);
+    /// Fill the texture's shape with the sprite's tint, for shadows and hit
+    /// flashes.
+    pub const SILHOUETTE: MaterialFlags = MaterialFlags(1 << 1);
+    /// Discard mostly transparent texels instead of blending them, so they
+    /// don't write depth and hide sprites drawn behind them later.
+    pub const ALPHA_CUTOUT: MaterialFlags = MaterialFlags(1 << 2);
+
+    #[inline]
+    pub fn contains(&self, other: MaterialFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    #[inline]
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+}
+
+impl std::ops::BitOr for MaterialFlags {
+    type Output = MaterialFlags;
+
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self::Output {
+        MaterialFlags(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for MaterialFlags {
+    #[inline]
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// How a sprite's color is combined with what's already been drawn.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BlendMode {
+    /// Covers what's behind by the sprite's alpha.
+    #[default]
+    Alpha,
+    /// Adds to what's behind, for glows and energy effects.
+    Additive,
+    /// Darkens what's behind, for shadows and tinted glass.
+    Multiply,
+}
+
+impl BlendMode {
+    pub const ALL: [BlendMode; 3] = [BlendMode::Alpha, BlendMode::Additive, BlendMode::Multiply];
+
+    fn blend_state(&self) -> wgpu::BlendState {
+        // Destination alpha is left alone by the effect blends
+        let keep_alpha = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::Zero,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+
+        match self {
+            BlendMode::Alpha => wgpu::BlendState::ALPHA_BLENDING,
+            BlendMode::Additive => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: keep_alpha,
+            },
+            BlendMode::Multiply => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Dst,
+                    dst_factor: wgpu::BlendFactor::Zero,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: keep_alpha,
+            },
+        }
+    }
+
+    #[inline]
+    fn label(&self) -> &'static str {
+        match self {
+            BlendMode::Alpha => "Texture Pipeline",
+            BlendMode::Additive => "Texture Additive Pipeline",
+            BlendMode::Multiply => "Texture Multiply Pipeline",
+        }
+    }
+}
+
+/// Extra sprites drawn over an entity's [`Sprite`], such as equipment. Layers
+/// share the entity's transform and flips and are drawn in order, so later
+/// layers always cover earlier ones.
+#[derive(Default)]
+pub struct SpriteStack {
+    pub layers: Vec<StackedSprite>,
+}
+
+pub struct StackedSprite {
     pub texture: Arc<LoadedTexture>,
     pub size: glam::Vec2,
+    /// sRGB tint with linear alpha, multiplied with the texture.
     pub color: [f32; 4],
+    /// Offset from the entity in its local space.
+    pub offset: glam::Vec3,
+}
+
+/// Optional draw layer for sprites. Lower layers are drawn first and sprites
+/// without a layer are drawn on layer 0.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SpriteLayer(pub i16);
+
+/// Key used to batch sprite instances together. Batches are drawn in key order
+/// so translucent sprites come after everything else, far to near, then the
+/// layer takes priority over the stack position, then the parts of the
+/// [`Material`] that need a pipeline or bind group change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BatchKey {
+    /// Alpha blended sprites that are partly see through, such as while
+    /// fading. Drawn far to near without writing depth, so they don't hide
+    /// what's behind them.
+    pub translucent: bool,
+    /// Position of a translucent run in far to near order, see
+    /// [`order_translucent`]. 0 for everything else.
+    pub depth_order: u32,
+    pub layer: SpriteLayer,
+    /// 0 for sprites, then 1 onwards for each layer of a [`SpriteStack`].
+    pub stack: u8,
+    pub blend: BlendMode,
+    pub sampler: SamplerPreset,
+    pub texture: u32,
 }
 
 //====================================================================
 
+/// Features required to draw sprites from a single texture binding array.
+pub(crate) const BINDING_ARRAY_FEATURES: wgpu::Features = wgpu::Features::TEXTURE_BINDING_ARRAY
+    .union(wgpu::Features::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING);
+
+/// Upper bound on the number of textures bound at once in binding array mode.
+pub(crate) const MAX_ARRAY_TEXTURES: u32 = 256;
+
+/// Features required for the wireframe debug view.
+pub(crate) const WIREFRAME_FEATURES: wgpu::Features = wgpu::Features::POLYGON_MODE_LINE;
+
+/// Features required to submit batches with multi draw indirect, where every
+/// batch draws from its own range of one shared instance buffer.
+pub(crate) const MULTI_DRAW_FEATURES: wgpu::Features =
+    wgpu::Features::MULTI_DRAW_INDIRECT.union(wgpu::Features::INDIRECT_FIRST_INSTANCE);
+
+/// How sprite batches are submitted each frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchSubmission {
+    /// One draw call per batch.
+    PerBatch,
+    /// Consecutive batches sharing a pipeline and bind group go out in a
+    /// single multi draw indirect call. Needs binding array mode.
+    MultiDrawIndirect,
+}
+
 pub struct TextureRenderer {
-    pipeline: wgpu::RenderPipeline,
+    /// One per blend mode, in [`BlendMode::ALL`] order.
+    pipelines: Vec<wgpu::RenderPipeline>,
+    /// Alpha blending without depth writes, for translucent batches.
+    translucent_pipeline: wgpu::RenderPipeline,
+    /// Used instead of the main pipeline while a debug view is on.
+    debug_pipeline: Option<wgpu::RenderPipeline>,
+    texture_array: Option<TextureArray>,
+    /// One per sampler preset, in [`SamplerPreset::ALL`] order.
+    samplers: Vec<wgpu::Sampler>,
+    config: wgpu::SurfaceConfiguration,
 
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
     index_count: u32,
 
-    instances: HashMap<u32, TextureInstanceBuffer>,
+    instances: BTreeMap<BatchKey, TextureInstanceBuffer>,
+    /// Replaces the per batch instance buffers when the device can multi draw.
+    multi_draw: Option<MultiDraw>,
 }
 
 impl TextureRenderer {
@@ -42,23 +425,66 @@ impl TextureRenderer {
         device: &wgpu::Device,
         config: &wgpu::SurfaceConfiguration,
         shared: &SharedRenderResources,
-        camera_bind_group_layout: &wgpu::BindGroupLayout,
+        default_texture: &Arc<LoadedTexture>,
     ) -> Self {
-        let pipeline = tools::create_pipeline(
+        let samplers = SamplerPreset::ALL
+            .iter()
+            .map(|preset| preset.create_sampler(device))
+            .collect::<Vec<_>>();
+
+        // WebGL and older native backends fall back to one bind group per texture
+        let texture_array = match device.features().contains(BINDING_ARRAY_FEATURES) {
+            true => Some(TextureArray::new(
+                device,
+                default_texture.clone(),
+                &samplers,
+            )),
+            false => None,
+        };
+
+        // Batches only share a bind group, and so can be drawn together, in
+        // array mode
+        let multi_draw =
+            match texture_array.is_some() && device.features().contains(MULTI_DRAW_FEATURES) {
+                true => Some(MultiDraw::new(device)),
+                false => None,
+            };
+
+        log::debug!(
+            "Texture pipeline using {} texture binding, {}",
+            match texture_array.is_some() {
+                true => "array",
+                false => "per texture",
+            },
+            match multi_draw.is_some() {
+                true => "multi draw indirect",
+                false => "draw per batch",
+            }
+        );
+
+        let pipelines = BlendMode::ALL
+            .iter()
+            .map(|blend| {
+                Self::create_pipeline(
+                    device,
+                    config,
+                    shared,
+                    texture_array.as_ref(),
+                    DebugView::None,
+                    *blend,
+                    false,
+                )
+            })
+            .collect();
+
+        let translucent_pipeline = Self::create_pipeline(
             device,
             config,
-            "Texture Pipeline",
-            &[camera_bind_group_layout, shared.texture_bind_group_layout()],
-            &[TextureRectVertex::desc(), InstanceTexture::desc()],
-            include_str!("shaders/texture.wgsl"),
-            tools::RenderPipelineDescriptor {
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleStrip,
-                    ..Default::default()
-                },
-                ..Default::default()
-            }
-            .with_depth_stencil(),
+            shared,
+            texture_array.as_ref(),
+            DebugView::None,
+            BlendMode::Alpha,
+            true,
         );
 
         let vertex_buffer = tools::buffer(
@@ -76,82 +502,481 @@ impl TextureRenderer {
         );
         let index_count = TEXTURE_RECT_INDEX_COUNT;
 
-        let instances = HashMap::default();
+        let instances = BTreeMap::default();
 
         Self {
-            pipeline,
+            pipelines,
+            translucent_pipeline,
+            debug_pipeline: None,
+            texture_array,
+            samplers,
+            config: config.clone(),
             vertex_buffer,
             index_buffer,
             index_count,
             instances,
+            multi_draw,
+        }
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        shared: &SharedRenderResources,
+        texture_array: Option<&TextureArray>,
+        debug_view: DebugView,
+        blend_mode: BlendMode,
+        translucent: bool,
+    ) -> wgpu::RenderPipeline {
+        let (texture_bind_group_layout, shader) = match texture_array {
+            Some(array) => (
+                &array.bind_group_layout,
+                include_str!("shaders/texture_array.wgsl"),
+            ),
+            None => (
+                shared.texture_bind_group_layout(),
+                include_str!("shaders/texture.wgsl"),
+            ),
+        };
+
+        let blend = match debug_view {
+            // Every sprite adds a little light, so stacked sprites glow
+            DebugView::Overdraw => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent::OVER,
+            },
+            DebugView::Depth => wgpu::BlendState::REPLACE,
+            // Blended so tints and opacity can fade sprites out
+            DebugView::None | DebugView::Wireframe => blend_mode.blend_state(),
+        };
+
+        let fragment_targets = [Some(wgpu::ColorTargetState {
+            format: config.format,
+            blend: Some(blend),
+            write_mask: wgpu::ColorWrites::all(),
+        })];
+
+        let mut descriptor = tools::RenderPipelineDescriptor {
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                polygon_mode: match debug_view {
+                    DebugView::Wireframe => wgpu::PolygonMode::Line,
+                    _ => wgpu::PolygonMode::Fill,
+                },
+                ..Default::default()
+            },
+            fragment_targets: Some(&fragment_targets),
+            fragment_entry: match (debug_view, blend_mode) {
+                (DebugView::Overdraw, _) => Some("fs_overdraw"),
+                (DebugView::Depth, _) => Some("fs_depth"),
+                (_, BlendMode::Multiply) => Some("fs_multiply"),
+                (_, BlendMode::Alpha | BlendMode::Additive) => None,
+            },
+            ..Default::default()
         }
+        .with_depth_stencil();
+
+        // Overdraw has to count sprites hidden behind others too
+        if let (DebugView::Overdraw, Some(depth)) = (debug_view, &mut descriptor.depth_stencil) {
+            depth.depth_write_enabled = false;
+            depth.depth_compare = wgpu::CompareFunction::Always;
+        }
+
+        // Effects and see through sprites shouldn't hide whatever is drawn
+        // behind them afterwards
+        let see_through =
+            translucent || matches!(blend_mode, BlendMode::Additive | BlendMode::Multiply);
+        if let (true, Some(depth)) = (see_through, &mut descriptor.depth_stencil) {
+            depth.depth_write_enabled = false;
+        }
+
+        tools::create_pipeline(
+            device,
+            config,
+            match translucent {
+                true => "Texture Translucent Pipeline",
+                false => blend_mode.label(),
+            },
+            &[shared.frame_bind_group_layout(), texture_bind_group_layout],
+            &[TextureRectVertex::desc(), InstanceTexture::desc()],
+            shader,
+            descriptor,
+        )
     }
 
-    pub(crate) fn prep(&mut self, world: &mut World, device: &wgpu::Device, queue: &wgpu::Queue) {
-        let mut previous = self.instances.keys().map(|id| *id).collect::<HashSet<_>>();
+    /// Build the pipeline for a debug view, or drop it for [`DebugView::None`].
+    /// Returns false if the device can't draw the view.
+    pub(crate) fn set_debug_view(
+        &mut self,
+        device: &wgpu::Device,
+        shared: &SharedRenderResources,
+        debug_view: DebugView,
+    ) -> bool {
+        if debug_view == DebugView::Wireframe && !device.features().contains(WIREFRAME_FEATURES) {
+            return false;
+        }
+
+        self.debug_pipeline = match debug_view {
+            DebugView::None => None,
+            _ => Some(Self::create_pipeline(
+                device,
+                &self.config,
+                shared,
+                self.texture_array.as_ref(),
+                debug_view,
+                BlendMode::Alpha,
+                false,
+            )),
+        };
+
+        true
+    }
+
+    #[tracing::instrument(skip_all, name = "texture_prep")]
+    pub(crate) fn prep(
+        &mut self,
+        world: &mut World,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        shared: &SharedRenderResources,
+        camera_position: glam::Vec3,
+        pixel_snap: Option<PixelSnap>,
+    ) {
+        let mut previous = self.instances.keys().copied().collect::<HashSet<_>>();
         let mut textures_to_add = HashMap::new();
+        let linear_target = color::is_linear_target(self.config.format);
+
+        let mut instances = HashMap::new();
+
+        let mut add =
+            |material: &Material, mut instance: InstanceTexture, layer: SpriteLayer, stack: u8| {
+                // Array mode shares one bind group per sampler so the texture
+                // doesn't matter
+                let (texture_key, array_index) = match &mut self.texture_array {
+                    Some(array) => (0, array.index(&material.texture)),
+                    None => (material.texture.id(), 0),
+                };
+
+                instance.pad = [array_index as f32, stack as f32];
+                instance.palette.y = material.flags.bits() as f32;
+
+                let key = BatchKey {
+                    translucent: material.blend == BlendMode::Alpha && instance.color.w < 1.,
+                    depth_order: 0,
+                    layer,
+                    stack,
+                    blend: material.blend,
+                    sampler: material.sampler,
+                    texture: texture_key,
+                };
+
+                textures_to_add
+                    .entry(texture_key)
+                    .or_insert_with(|| material.texture.clone());
+
+                instances.entry(key).or_default().push(instance);
+            };
+
+        world
+            .query_mut::<(
+                &Transform,
+                &Sprite,
+                Option<&SpriteLayer>,
+                Option<&SpriteStack>,
+                Option<&Opacity>,
+            )>()
+            .without::<&Hidden>()
+            .into_iter()
+            .for_each(|(_, (transform, sprite, layer, stack, opacity))| {
+                let opacity = Opacity::of(opacity);
+
+                let transform = match pixel_snap {
+                    Some(snap) => glam::Mat4::from_scale_rotation_translation(
+                        transform.scale,
+                        transform.rotation,
+                        snap.snap(transform.translation),
+                    ),
+                    None => transform.to_matrix(),
+                };
+                let layer = layer.copied().unwrap_or_default();
 
-        let instances = world.query_mut::<(&Transform, &Sprite)>().into_iter().fold(
-            HashMap::new(),
-            |mut acc, (_, (transform, sprite))| {
                 let instance = InstanceTexture {
                     size: sprite.size,
                     pad: [0.; 2],
-                    transform: transform.to_matrix(),
-                    color: sprite.color.into(),
+                    transform,
+                    color: color::srgba_for_target(
+                        Opacity::apply(sprite.color, opacity),
+                        linear_target,
+                    )
+                    .into(),
+                    palette: glam::vec4(sprite.palette.hue_shift_radians(), 0., 0., 0.),
+                    uv: uv_transform(
+                        sprite.flip_x,
+                        sprite.flip_y,
+                        sprite.uv_offset,
+                        sprite.uv_scale,
+                    ),
                 };
 
-                acc.entry(sprite.texture.id())
-                    .or_insert_with(|| {
-                        textures_to_add.insert(sprite.texture.id(), sprite.texture.clone());
-                        Vec::new()
-                    })
-                    .push(instance);
+                add(&sprite.material, instance, layer, 0);
 
-                acc
-            },
-        );
+                // Layers mirror with the sprite so equipment stays on the same side
+                let flip = glam::vec3(
+                    match sprite.flip_x {
+                        true => -1.,
+                        false => 1.,
+                    },
+                    match sprite.flip_y {
+                        true => -1.,
+                        false => 1.,
+                    },
+                    1.,
+                );
+
+                stack
+                    .iter()
+                    .flat_map(|stack| stack.layers.iter())
+                    .enumerate()
+                    .for_each(|(index, stacked)| {
+                        let instance = InstanceTexture {
+                            size: stacked.size,
+                            pad: [0.; 2],
+                            transform: transform
+                                * glam::Mat4::from_translation(stacked.offset * flip),
+                            color: color::srgba_for_target(
+                                Opacity::apply(stacked.color, opacity),
+                                linear_target,
+                            )
+                            .into(),
+                            palette: glam::Vec4::ZERO,
+                            uv: uv_transform(
+                                sprite.flip_x,
+                                sprite.flip_y,
+                                glam::Vec2::ZERO,
+                                glam::Vec2::ONE,
+                            ),
+                        };
+
+                        add(
+                            &Material::new(stacked.texture.clone()),
+                            instance,
+                            layer,
+                            (index + 1).min(u8::MAX as usize) as u8,
+                        )
+                    });
+            });
+
+        order_translucent(&mut instances, camera_position);
 
-        instances.into_iter().for_each(|(id, raw)| {
-            previous.remove(&id);
+        if let Some(multi_draw) = &mut self.multi_draw {
+            multi_draw.update(
+                device,
+                queue,
+                shared.recorder(),
+                instances.into_iter().collect(),
+                self.index_count,
+            );
+
+            if let Some(array) = &mut self.texture_array {
+                array.finish(device, &self.samplers);
+            }
+
+            return;
+        }
+
+        instances.into_iter().for_each(|(key, raw)| {
+            previous.remove(&key);
 
             self.instances
-                .entry(id)
+                .entry(key)
                 .and_modify(|instance| {
                     instance.update(device, queue, raw.as_slice());
                 })
                 .or_insert_with(|| {
-                    TextureInstanceBuffer::new(
-                        device,
-                        textures_to_add.remove(&id).unwrap(),
-                        raw.as_slice(),
-                    )
+                    let texture = Arc::clone(&textures_to_add[&key.texture]);
+
+                    // A texture's own sampler clamps to the edge, so every
+                    // preset gets a bind group with its repeating sampler for
+                    // uv offsets and scales
+                    let bind_group = match &self.texture_array {
+                        None => Some(shared.create_sampler_bind_group(
+                            device,
+                            texture._texture(),
+                            &self.samplers[key.sampler as usize],
+                            Some("Texture Sampler Bind Group"),
+                        )),
+                        Some(_) => None,
+                    };
+
+                    TextureInstanceBuffer::new(device, texture, bind_group, raw.as_slice())
                 });
         });
 
         previous.into_iter().for_each(|to_remove| {
-            log::trace!("Removing texture instance {}", to_remove);
+            log::trace!("Removing texture instance {:?}", to_remove);
             self.instances.remove(&to_remove);
         });
+
+        if let Some(array) = &mut self.texture_array {
+            array.finish(device, &self.samplers);
+        }
     }
 
-    pub(crate) fn render(
-        &mut self,
-        pass: &mut wgpu::RenderPass,
-        camera_bind_group: &wgpu::BindGroup,
-    ) {
-        pass.set_pipeline(&self.pipeline);
-        pass.set_bind_group(0, camera_bind_group, &[]);
+    /// Batches prepared for the next render, along with their instance counts.
+    pub fn batches(&self) -> impl Iterator<Item = (BatchKey, u32)> + '_ {
+        // Only one of these is filled, depending on the submission path
+        self.multi_draw
+            .iter()
+            .flat_map(|multi_draw| multi_draw.batches.iter().copied())
+            .chain(
+                self.instances
+                    .iter()
+                    .map(|(key, instance)| (*key, instance.buffer.count())),
+            )
+    }
+
+    #[inline]
+    pub fn batch_submission(&self) -> BatchSubmission {
+        match self.multi_draw.is_some() {
+            true => BatchSubmission::MultiDrawIndirect,
+            false => BatchSubmission::PerBatch,
+        }
+    }
+
+    #[tracing::instrument(skip_all, name = "texture_render")]
+    pub(crate) fn render(&self, pass: &mut wgpu::RenderPass, shared: &SharedRenderResources) {
+        // Debug views draw everything with the one pipeline
+        if let Some(debug_pipeline) = &self.debug_pipeline {
+            shared.recorder().record(|| CaptureCommand::SetPipeline {
+                label: "Texture Debug Pipeline",
+            });
+            pass.set_pipeline(debug_pipeline);
+        }
+
+        pass.set_bind_group(0, shared.frame_bind_group(), &[]);
 
         pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
         pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
 
-        self.instances.iter().for_each(|(_, instance)| {
-            pass.set_bind_group(1, instance.texture.bind_group(), &[]);
+        if let (Some(multi_draw), Some(array)) = (&self.multi_draw, &self.texture_array) {
+            self.render_multi_draw(pass, shared, multi_draw, array);
+            return;
+        }
+
+        // Batches are sorted by key so consecutive batches often share a
+        // pipeline and texture
+        let mut bound_blend = None;
+        let mut bound_texture = None;
+
+        self.instances.iter().for_each(|(key, instance)| {
+            let blend = (key.blend, key.translucent);
+            if self.debug_pipeline.is_none() && bound_blend != Some(blend) {
+                self.set_pipeline(pass, shared, blend);
+                bound_blend = Some(blend);
+            }
+
+            // Every batch uses texture key 0 in array mode so only the
+            // sampler changes the bind group
+            if bound_texture != Some((key.texture, key.sampler)) {
+                match &self.texture_array {
+                    Some(array) => {
+                        shared.recorder().record(|| CaptureCommand::SetBindGroup {
+                            index: 1,
+                            label: format!("Texture array ({:?})", key.sampler),
+                        });
+                        pass.set_bind_group(1, &array.bind_groups[key.sampler as usize], &[]);
+                    }
+                    None => {
+                        shared.recorder().record(|| CaptureCommand::SetBindGroup {
+                            index: 1,
+                            label: format!("Texture {} ({:?})", instance.texture.id(), key.sampler),
+                        });
+                        pass.set_bind_group(1, instance.bind_group(), &[]);
+                    }
+                }
+                bound_texture = Some((key.texture, key.sampler));
+            }
+
+            shared.recorder().record(|| CaptureCommand::Draw {
+                vertices: self.index_count,
+                instances: instance.buffer.count(),
+                indexed: true,
+            });
+
             pass.set_vertex_buffer(1, instance.buffer.buffer().slice(..));
             pass.draw_indexed(0..self.index_count, 0, 0..instance.buffer.count());
         });
     }
+
+    fn set_pipeline(
+        &self,
+        pass: &mut wgpu::RenderPass,
+        shared: &SharedRenderResources,
+        (blend, translucent): (BlendMode, bool),
+    ) {
+        let (label, pipeline) = match translucent {
+            true => ("Texture Translucent Pipeline", &self.translucent_pipeline),
+            false => (blend.label(), &self.pipelines[blend as usize]),
+        };
+
+        shared
+            .recorder()
+            .record(|| CaptureCommand::SetPipeline { label });
+        pass.set_pipeline(pipeline);
+    }
+
+    // Each run differs from the last in pipeline or sampler, so at most a
+    // pipeline and a bind group change between multi draws
+    fn render_multi_draw(
+        &self,
+        pass: &mut wgpu::RenderPass,
+        shared: &SharedRenderResources,
+        multi_draw: &MultiDraw,
+        array: &TextureArray,
+    ) {
+        if multi_draw.runs.is_empty() {
+            return;
+        }
+
+        pass.set_vertex_buffer(1, multi_draw.instances.buffer().slice(..));
+
+        let mut bound_blend = None;
+        let mut bound_sampler = None;
+
+        multi_draw.runs.iter().for_each(|run| {
+            let blend = (run.blend, run.translucent);
+            if self.debug_pipeline.is_none() && bound_blend != Some(blend) {
+                self.set_pipeline(pass, shared, blend);
+                bound_blend = Some(blend);
+            }
+
+            if bound_sampler != Some(run.sampler) {
+                shared.recorder().record(|| CaptureCommand::SetBindGroup {
+                    index: 1,
+                    label: format!("Texture array ({:?})", run.sampler),
+                });
+                pass.set_bind_group(1, &array.bind_groups[run.sampler as usize], &[]);
+                bound_sampler = Some(run.sampler);
+            }
+
+            shared
+                .recorder()
+                .record(|| CaptureCommand::MultiDrawIndirect {
+                    draws: run.draws,
+                    instances: run.instances,
+                });
+
+            pass.multi_draw_indexed_indirect(
+                &multi_draw.indirect_buffer,
+                run.first_draw as u64 * INDIRECT_ARGS_SIZE,
+                run.draws,
+            );
+        });
+    }
 }
 
 //====================================================================
@@ -160,20 +985,29 @@ impl TextureRenderer {
 #[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Debug)]
 pub struct InstanceTexture {
     pub size: glam::Vec2,
+    /// `pad[0]` holds the texture index when drawing from a binding array and
+    /// `pad[1]` the sprite's position in its entity's [`SpriteStack`].
     pub pad: [f32; 2],
     pub transform: glam::Mat4,
     pub color: glam::Vec4,
+    /// `palette.x` holds the hue shift in radians and `palette.y` the
+    /// [`MaterialFlags`].
+    pub palette: glam::Vec4,
+    /// Texture coordinate offset in xy and scale in zw.
+    pub uv: glam::Vec4,
 }
 
 impl Vertex for InstanceTexture {
     fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
-        const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 6] = wgpu::vertex_attr_array![
-            2 => Float32x4, // Transform
-            3 => Float32x4,
+        const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 8] = wgpu::vertex_attr_array![
+            2 => Float32x4, // Size
+            3 => Float32x4, // Transform
             4 => Float32x4,
             5 => Float32x4,
-            6 => Float32x4, // Color
-            7 => Float32x4, // Size
+            6 => Float32x4,
+            7 => Float32x4, // Color
+            8 => Float32x4, // Palette
+            9 => Float32x4, // Uv
         ];
 
         wgpu::VertexBufferLayout {
@@ -186,6 +1020,9 @@ impl Vertex for InstanceTexture {
 
 struct TextureInstanceBuffer {
     texture: Arc<LoadedTexture>,
+    /// Used over the texture's own bind group, pairing the texture with the
+    /// batch's sampler preset.
+    bind_group: Option<wgpu::BindGroup>,
     buffer: tools::InstanceBuffer<InstanceTexture>,
 }
 
@@ -194,14 +1031,23 @@ impl TextureInstanceBuffer {
     pub fn new(
         device: &wgpu::Device,
         texture: Arc<LoadedTexture>,
+        bind_group: Option<wgpu::BindGroup>,
         data: &[InstanceTexture],
     ) -> Self {
         Self {
             texture,
+            bind_group,
             buffer: tools::InstanceBuffer::new(device, data),
         }
     }
 
+    #[inline]
+    fn bind_group(&self) -> &wgpu::BindGroup {
+        self.bind_group
+            .as_ref()
+            .unwrap_or(self.texture.bind_group())
+    }
+
     #[inline]
     pub fn update(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[InstanceTexture]) {
         self.buffer.update(device, queue, data);
@@ -209,3 +1055,285 @@ impl TextureInstanceBuffer {
 }
 
 //====================================================================
+
+const INDIRECT_ARGS_SIZE: u64 = std::mem::size_of::<wgpu::util::DrawIndexedIndirectArgs>() as u64;
+
+/// Draws the indirect buffer holds before it first needs to grow.
+const INITIAL_INDIRECT_CAPACITY: u32 = 64;
+
+/// Every batch's instances packed into one buffer, in batch order, with an
+/// indirect draw per batch pointing at its range.
+struct MultiDraw {
+    instances: tools::InstanceBuffer<InstanceTexture>,
+    indirect_buffer: wgpu::Buffer,
+    /// Number of draws the indirect buffer can hold.
+    indirect_capacity: u32,
+
+    batches: Vec<(BatchKey, u32)>,
+    runs: Vec<DrawRun>,
+}
+
+/// Consecutive batches drawn with the same pipeline and bind group.
+struct DrawRun {
+    blend: BlendMode,
+    translucent: bool,
+    sampler: SamplerPreset,
+    first_draw: u32,
+    draws: u32,
+    instances: u32,
+}
+
+impl MultiDraw {
+    fn new(device: &wgpu::Device) -> Self {
+        Self {
+            instances: tools::InstanceBuffer::new(device, &[]),
+            indirect_buffer: Self::create_indirect_buffer(device, INITIAL_INDIRECT_CAPACITY),
+            indirect_capacity: INITIAL_INDIRECT_CAPACITY,
+            batches: Vec::new(),
+            runs: Vec::new(),
+        }
+    }
+
+    fn create_indirect_buffer(device: &wgpu::Device, capacity: u32) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Texture Indirect Buffer"),
+            size: capacity as u64 * INDIRECT_ARGS_SIZE,
+            usage: wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn update(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        recorder: &FrameRecorder,
+        batches: BTreeMap<BatchKey, Vec<InstanceTexture>>,
+        index_count: u32,
+    ) {
+        self.batches.clear();
+        self.runs.clear();
+
+        let mut data = Vec::new();
+        let mut args = Vec::with_capacity(batches.len() * INDIRECT_ARGS_SIZE as usize);
+
+        batches.into_iter().for_each(|(key, raw)| {
+            let count = raw.len() as u32;
+
+            args.extend_from_slice(
+                wgpu::util::DrawIndexedIndirectArgs {
+                    index_count,
+                    instance_count: count,
+                    first_index: 0,
+                    base_vertex: 0,
+                    first_instance: data.len() as u32,
+                }
+                .as_bytes(),
+            );
+            data.extend(raw);
+
+            match self.runs.last_mut() {
+                Some(run)
+                    if run.blend == key.blend
+                        && run.translucent == key.translucent
+                        && run.sampler == key.sampler =>
+                {
+                    run.draws += 1;
+                    run.instances += count;
+                }
+                _ => self.runs.push(DrawRun {
+                    blend: key.blend,
+                    translucent: key.translucent,
+                    sampler: key.sampler,
+                    first_draw: self.batches.len() as u32,
+                    draws: 1,
+                    instances: count,
+                }),
+            }
+
+            self.batches.push((key, count));
+        });
+
+        self.instances.update(device, queue, &data);
+
+        if args.is_empty() {
+            return;
+        }
+
+        let draws = self.batches.len() as u32;
+
+        if draws > self.indirect_capacity {
+            // Grow ahead so a few new batches don't recreate it every frame
+            self.indirect_capacity = draws.next_power_of_two();
+
+            recorder.record(|| CaptureCommand::CreateBuffer {
+                label: String::from("Texture Indirect Buffer"),
+                bytes: self.indirect_capacity as u64 * INDIRECT_ARGS_SIZE,
+            });
+            self.indirect_buffer = Self::create_indirect_buffer(device, self.indirect_capacity);
+        }
+
+        recorder.record(|| CaptureCommand::WriteBuffer {
+            label: String::from("Texture Indirect Buffer"),
+            bytes: args.len() as u64,
+        });
+        queue.write_buffer(&self.indirect_buffer, 0, &args);
+    }
+}
+
+//====================================================================
+
+/// Sprite textures bound together as a single binding array so every sprite
+/// can be drawn without swapping bind groups. Index 0 is the default texture.
+struct TextureArray {
+    capacity: u32,
+    bind_group_layout: wgpu::BindGroupLayout,
+    /// One per sampler preset, in [`SamplerPreset::ALL`] order.
+    bind_groups: Vec<wgpu::BindGroup>,
+
+    default_texture: Arc<LoadedTexture>,
+    textures: Vec<Arc<LoadedTexture>>,
+
+    frame_textures: Vec<Arc<LoadedTexture>>,
+    frame_indices: HashMap<u32, u32>,
+    overflow_warned: bool,
+}
+
+impl TextureArray {
+    fn new(
+        device: &wgpu::Device,
+        default_texture: Arc<LoadedTexture>,
+        samplers: &[wgpu::Sampler],
+    ) -> Self {
+        let capacity = device
+            .limits()
+            .max_sampled_textures_per_shader_stage
+            .min(MAX_ARRAY_TEXTURES);
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Texture Array Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    count: NonZeroU32::new(capacity),
+                    ..tools::bgl_texture_entry(0)
+                },
+                tools::bgl_sampler_entry(1),
+            ],
+        });
+
+        let bind_groups = Self::create_bind_groups(
+            device,
+            &bind_group_layout,
+            samplers,
+            capacity,
+            &default_texture,
+            &[],
+        );
+
+        Self {
+            capacity,
+            bind_group_layout,
+            bind_groups,
+            default_texture,
+            textures: Vec::new(),
+            frame_textures: Vec::new(),
+            frame_indices: HashMap::default(),
+            overflow_warned: false,
+        }
+    }
+
+    // Get the array index of a texture for this frame, adding it if needed
+    fn index(&mut self, texture: &Arc<LoadedTexture>) -> u32 {
+        if let Some(index) = self.frame_indices.get(&texture.id()) {
+            return *index;
+        }
+
+        let index = self.frame_textures.len() as u32 + 1;
+
+        if index >= self.capacity {
+            if !self.overflow_warned {
+                log::warn!(
+                    "Texture array full ({} textures) - using default texture for overflow",
+                    self.capacity
+                );
+                self.overflow_warned = true;
+            }
+            return 0;
+        }
+
+        self.frame_textures.push(texture.clone());
+        self.frame_indices.insert(texture.id(), index);
+
+        index
+    }
+
+    // Rebuild the bind groups if the set of textures used changed this frame
+    fn finish(&mut self, device: &wgpu::Device, samplers: &[wgpu::Sampler]) {
+        let changed = self.frame_textures.len() != self.textures.len()
+            || self
+                .frame_textures
+                .iter()
+                .zip(self.textures.iter())
+                .any(|(a, b)| a.id() != b.id());
+
+        if changed {
+            log::trace!(
+                "Rebuilding texture array bind group with {} textures",
+                self.frame_textures.len()
+            );
+
+            std::mem::swap(&mut self.textures, &mut self.frame_textures);
+
+            self.bind_groups = Self::create_bind_groups(
+                device,
+                &self.bind_group_layout,
+                samplers,
+                self.capacity,
+                &self.default_texture,
+                &self.textures,
+            );
+        }
+
+        self.frame_textures.clear();
+        self.frame_indices.clear();
+    }
+
+    fn create_bind_groups(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        samplers: &[wgpu::Sampler],
+        capacity: u32,
+        default_texture: &Arc<LoadedTexture>,
+        textures: &[Arc<LoadedTexture>],
+    ) -> Vec<wgpu::BindGroup> {
+        // Unused slots are filled with the default texture as partial binding isn't required
+        let views = std::iter::once(default_texture)
+            .chain(textures.iter())
+            .map(|texture| &texture._texture().view)
+            .chain(std::iter::repeat(&default_texture._texture().view))
+            .take(capacity as usize)
+            .collect::<Vec<_>>();
+
+        samplers
+            .iter()
+            .map(|sampler| {
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Texture Array Bind Group"),
+                    layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureViewArray(&views),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::Sampler(sampler),
+                        },
+                    ],
+                })
+            })
+            .collect()
+    }
+}
+
+//====================================================================
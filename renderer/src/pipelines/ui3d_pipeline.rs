@@ -1,6 +1,9 @@
 //====================================================================
 
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::HashMap,
+    sync::mpsc::{self, Receiver},
+};
 
 use common::Transform;
 use cosmic_text::{Metrics, Wrap};
@@ -8,22 +11,78 @@ use hecs::{Entity, World};
 use wgpu::util::DeviceExt;
 
 use crate::{
-    shared::Vertex,
+    capture::{CaptureCommand, FrameRecorder},
+    color,
+    shared::{SharedRenderResources, Vertex},
     text_shared::{TextAtlas, TextBuffer, TextBufferDescriptor, TextResources, TextVertex},
     texture::Texture,
     tools,
 };
 
+use super::{Hidden, Opacity};
+
 //====================================================================
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Ui3d {
+    /// sRGB color with linear alpha.
     pub menu_color: [f32; 4],
+    /// sRGB color with linear alpha.
     pub selection_color: [f32; 4],
 
     pub options: Vec<String>,
     pub selected: u8,
     pub font_size: f32,
+
+    /// Hidden behind anything in front of it in the world, instead of drawn
+    /// over everything.
+    pub depth_tested: bool,
+    /// Only draw the parts of the menu and its text inside this area.
+    pub clip: Option<UiClip>,
+    /// Skip the text while the menu was completely hidden behind the world the
+    /// frame before, for large panels. Only used when depth tested, and the
+    /// text shows up a frame late when the menu comes back into view.
+    pub occlusion_cull: bool,
+}
+
+impl Ui3d {
+    /// Size of the menu background before scaling. It spans this far right of
+    /// the entity's position, with most of its height below it.
+    pub fn size(&self) -> glam::Vec2 {
+        let longest_line = self.options.iter().map(|option| option.len()).max();
+
+        glam::vec2(
+            self.font_size * longest_line.unwrap_or(0) as f32,
+            self.font_size * self.options.len() as f32,
+        )
+    }
+
+    /// Start and end of the selected option as fractions of the menu's height
+    /// from the top. Selections past the end highlight the last option.
+    pub fn selection_range(&self) -> glam::Vec2 {
+        if self.options.is_empty() {
+            return glam::Vec2::ZERO;
+        }
+
+        let selected = (self.selected as usize).min(self.options.len() - 1) as f32;
+        let option_range = 1. / self.options.len() as f32;
+
+        glam::vec2(option_range * selected, option_range * (selected + 1.))
+    }
+
+    /// Corners of the menu background in the entity's local space, matching
+    /// [`UiClip::Local`]. Returned as the bottom left then the top right.
+    pub fn bounds(&self) -> (glam::Vec2, glam::Vec2) {
+        let size = self.size();
+
+        // Matches the offset applied in ui3d.wgsl
+        (
+            glam::vec2(0., -size.y * 0.9),
+            glam::vec2(size.x, size.y * 0.1),
+        )
+    }
 }
 
 impl Default for Ui3d {
@@ -34,6 +93,55 @@ impl Default for Ui3d {
             options: Vec::new(),
             selected: 0,
             font_size: 30.,
+            depth_tested: false,
+            clip: None,
+            occlusion_cull: false,
+        }
+    }
+}
+
+/// Area a [`Ui3d`] is clipped to, for scrolling lists and panels with more
+/// content than fits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UiClip {
+    /// Fractions of the rendered scene from its top left corner, applied with a
+    /// scissor rect. For ui that stays in one place on screen.
+    Screen { min: glam::Vec2, max: glam::Vec2 },
+    /// Corners in the entity's local space, the same space as
+    /// [`Ui3d::bounds`]. Clipped in the shader so it follows the menu around
+    /// the world.
+    Local { min: glam::Vec2, max: glam::Vec2 },
+}
+
+impl UiClip {
+    /// Scissor rect covering the clip area in a target of `target_size`
+    /// pixels. Local clips and empty areas give `None`.
+    pub fn scissor_rect(&self, target_size: [u32; 2]) -> Option<[u32; 4]> {
+        let (min, max) = match self {
+            UiClip::Screen { min, max } => (*min, *max),
+            UiClip::Local { .. } => return None,
+        };
+
+        let size = glam::UVec2::from(target_size).as_vec2();
+        let start = (min * size)
+            .floor()
+            .clamp(glam::Vec2::ZERO, size)
+            .as_uvec2();
+        let end = (max * size).ceil().clamp(glam::Vec2::ZERO, size).as_uvec2();
+
+        match end.x > start.x && end.y > start.y {
+            true => Some([start.x, start.y, end.x - start.x, end.y - start.y]),
+            false => None,
+        }
+    }
+
+    // Min and max corners passed to the shaders. Anything that isn't a local
+    // clip covers everything.
+    fn local_raw(clip: Option<&UiClip>) -> glam::Vec4 {
+        match clip {
+            Some(UiClip::Local { min, max }) => glam::vec4(min.x, min.y, max.x, max.y),
+            _ => glam::vec4(f32::MIN, f32::MIN, f32::MAX, f32::MAX),
         }
     }
 }
@@ -46,20 +154,109 @@ struct Ui3dData {
     ui_position_uniform_buffer: wgpu::Buffer,
     ui_position_uniform_bind_group: wgpu::BindGroup,
     size: [f32; 2],
+    selection_range: [f32; 2],
 
     text_buffer: TextBuffer,
+    /// False while the entity is [`Hidden`].
+    visible: bool,
+    depth_tested: bool,
+    clip: Option<UiClip>,
+
+    occlusion_cull: bool,
+    /// Occlusion query wrapped around the menu background this frame.
+    query: Option<u32>,
+    /// No part of the menu passed the depth test when last queried.
+    occluded: bool,
+    /// Set when the entity is found during prep, so removed entities can be
+    /// dropped without looking each one up in the world.
+    seen: bool,
+}
+
+impl Ui3dData {
+    #[inline]
+    fn wants_query(&self) -> bool {
+        self.visible && self.depth_tested && self.occlusion_cull
+    }
 }
 
 //====================================================================
 
+/// Menu and text pipelines sharing a depth test.
+struct UiPipelines {
+    ui: wgpu::RenderPipeline,
+    text: wgpu::RenderPipeline,
+}
+
+impl UiPipelines {
+    // Layouts are the frame, text atlas, ui uniform and ui position uniform
+    fn new(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        layouts: [&wgpu::BindGroupLayout; 4],
+        depth_compare: wgpu::CompareFunction,
+    ) -> Self {
+        let [frame_layout, text_atlas_layout, ui_layout, ui_position_layout] = layouts;
+
+        let fragment_targets = [Some(wgpu::ColorTargetState {
+            format: config.format,
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+            write_mask: wgpu::ColorWrites::all(),
+        })];
+
+        let descriptor = || tools::RenderPipelineDescriptor {
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                cull_mode: Some(wgpu::Face::Back),
+                ..Default::default()
+            },
+            fragment_targets: Some(&fragment_targets),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            ..Default::default()
+        };
+
+        let ui = tools::create_pipeline(
+            device,
+            config,
+            "Ui Renderer",
+            &[frame_layout, ui_layout, ui_position_layout],
+            &[],
+            include_str!("shaders/ui3d.wgsl"),
+            descriptor(),
+        );
+
+        let text = tools::create_pipeline(
+            device,
+            config,
+            "Ui Text Renderer",
+            &[frame_layout, text_atlas_layout, ui_position_layout],
+            &[TextVertex::desc()],
+            include_str!("shaders/text.wgsl"),
+            descriptor(),
+        );
+
+        Self { ui, text }
+    }
+}
+
 pub struct Ui3dRenderer {
-    ui_pipeline: wgpu::RenderPipeline,
-    text_pipeline: wgpu::RenderPipeline,
+    /// Drawn over everything.
+    overlay: UiPipelines,
+    /// Created the first time a depth tested [`Ui3d`] shows up.
+    depth_tested: Option<UiPipelines>,
+    config: wgpu::SurfaceConfiguration,
 
     ui_uniform_bind_group_layout: wgpu::BindGroupLayout,
     ui_position_uniform_bind_group_layout: wgpu::BindGroupLayout,
 
     instances: HashMap<Entity, Ui3dData>,
+    /// Created the first time a [`Ui3d`] asks for occlusion culling.
+    occlusion: Option<OcclusionQueries>,
 }
 
 impl Ui3dRenderer {
@@ -67,12 +264,16 @@ impl Ui3dRenderer {
         device: &wgpu::Device,
         config: &wgpu::SurfaceConfiguration,
         text_atlas: &TextAtlas,
-        camera_bind_group_layout: &wgpu::BindGroupLayout,
+        shared: &SharedRenderResources,
     ) -> Self {
+        // Fragment visible for the local clip
         let ui_position_uniform_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("Ui Instance Buffer Bind Group Layout"),
-                entries: &[tools::bgl_uniform_entry(0, wgpu::ShaderStages::VERTEX)],
+                entries: &[tools::bgl_uniform_entry(
+                    0,
+                    wgpu::ShaderStages::VERTEX_FRAGMENT,
+                )],
             });
 
         let ui_uniform_bind_group_layout =
@@ -81,78 +282,26 @@ impl Ui3dRenderer {
                 entries: &[tools::bgl_uniform_entry(0, wgpu::ShaderStages::VERTEX)],
             });
 
-        let ui_pipeline = tools::create_pipeline(
-            device,
-            config,
-            "Ui Renderer",
-            &[
-                camera_bind_group_layout,
-                &ui_uniform_bind_group_layout,
-                &ui_position_uniform_bind_group_layout,
-            ],
-            &[],
-            include_str!("shaders/ui3d.wgsl"),
-            tools::RenderPipelineDescriptor {
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleStrip,
-                    cull_mode: Some(wgpu::Face::Back),
-                    ..Default::default()
-                },
-                fragment_targets: Some(&[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::all(),
-                })]),
-                depth_stencil: Some(wgpu::DepthStencilState {
-                    format: Texture::DEPTH_FORMAT,
-                    depth_write_enabled: false,
-                    depth_compare: wgpu::CompareFunction::Always,
-                    stencil: wgpu::StencilState::default(),
-                    bias: wgpu::DepthBiasState::default(),
-                }),
-                ..Default::default()
-            },
-        );
-
-        let text_pipeline = tools::create_pipeline(
+        let overlay = UiPipelines::new(
             device,
             config,
-            "Ui Text Renderer",
-            &[
-                camera_bind_group_layout,
+            [
+                shared.frame_bind_group_layout(),
                 text_atlas.bind_group_layout(),
+                &ui_uniform_bind_group_layout,
                 &ui_position_uniform_bind_group_layout,
             ],
-            &[TextVertex::desc()],
-            include_str!("shaders/text.wgsl"),
-            tools::RenderPipelineDescriptor {
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleStrip,
-                    cull_mode: Some(wgpu::Face::Back),
-                    ..Default::default()
-                },
-                fragment_targets: Some(&[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::all(),
-                })]),
-                depth_stencil: Some(wgpu::DepthStencilState {
-                    format: Texture::DEPTH_FORMAT,
-                    depth_write_enabled: false,
-                    depth_compare: wgpu::CompareFunction::Always,
-                    stencil: wgpu::StencilState::default(),
-                    bias: wgpu::DepthBiasState::default(),
-                }),
-                ..Default::default()
-            },
+            wgpu::CompareFunction::Always,
         );
 
         Self {
-            ui_pipeline,
-            text_pipeline,
+            overlay,
+            depth_tested: None,
+            config: config.clone(),
             ui_uniform_bind_group_layout,
             ui_position_uniform_bind_group_layout,
             instances: HashMap::default(),
+            occlusion: None,
         }
     }
 
@@ -165,32 +314,128 @@ impl Ui3dRenderer {
     }
 
     // Prep text
+    #[tracing::instrument(skip_all, name = "ui3d_prep")]
     pub(crate) fn prep(
         &mut self,
         world: &mut World,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         text_res: &mut TextResources,
+        shared: &SharedRenderResources,
     ) {
-        let mut previous = self.instances.keys().map(|id| *id).collect::<HashSet<_>>();
-
         world
             .query_mut::<&Ui3d>()
             .into_iter()
-            .for_each(|(entity, ui)| {
-                previous.remove(&entity);
+            .for_each(|(entity, ui)| match self.instances.get_mut(&entity) {
+                Some(data) => {
+                    data.seen = true;
 
-                if !self.instances.contains_key(&entity) {
-                    self.insert_ui(device, &mut text_res.font_system, entity, ui)
+                    // Only marks the buffer dirty if the options changed
+                    data.text_buffer
+                        .set_text(&mut text_res.font_system, &options_text(ui));
                 }
+                None => self.insert_ui(device, &mut text_res.font_system, entity, ui),
             });
 
-        self.prep_text(world, device, queue, text_res);
-        self.prep_ui(world, queue, &mut text_res.font_system);
-
-        previous.into_iter().for_each(|to_remove| {
-            self.instances.remove(&to_remove);
+        // Catch anything removed without going through `remove_entity`
+        self.instances.retain(|entity, data| {
+            let seen = std::mem::take(&mut data.seen);
+            if !seen {
+                log::debug!(
+                    "Ui3d entity {:?} removed without notifying renderer",
+                    entity
+                );
+            }
+            seen
         });
+
+        self.prep_text(world, device, queue, shared.recorder(), text_res);
+        self.prep_ui(world, queue, shared.recorder(), &mut text_res.font_system);
+        self.prep_occlusion(device);
+
+        // Most scenes never need depth tested ui so only create it when used
+        if self.depth_tested.is_none() && self.instances.values().any(|data| data.depth_tested) {
+            log::debug!("Creating depth tested ui pipelines");
+
+            self.depth_tested = Some(UiPipelines::new(
+                device,
+                &self.config,
+                [
+                    shared.frame_bind_group_layout(),
+                    text_res.text_atlas.bind_group_layout(),
+                    &self.ui_uniform_bind_group_layout,
+                    &self.ui_position_uniform_bind_group_layout,
+                ],
+                wgpu::CompareFunction::LessEqual,
+            ));
+        }
+    }
+
+    /// Size of the menu background calculated during the last prep.
+    #[inline]
+    pub fn ui_size(&self, entity: Entity) -> Option<[f32; 2]> {
+        self.instances.get(&entity).map(|data| data.size)
+    }
+
+    /// Selection range calculated during the last prep, see
+    /// [`Ui3d::selection_range`].
+    #[inline]
+    pub fn ui_selection_range(&self, entity: Entity) -> Option<[f32; 2]> {
+        self.instances.get(&entity).map(|data| data.selection_range)
+    }
+
+    /// Occlusion queries made this frame and menus whose text is skipped
+    /// because they were hidden last frame.
+    pub(crate) fn occlusion_stats(&self) -> (u32, u32) {
+        let queries = self
+            .occlusion
+            .as_ref()
+            .map(|occlusion| occlusion.queried.len() as u32)
+            .unwrap_or(0);
+
+        let occluded = self
+            .instances
+            .values()
+            .filter(|data| data.visible && data.occluded)
+            .count() as u32;
+
+        (queries, occluded)
+    }
+
+    /// Query set the main render pass needs when any queries were handed out
+    /// this frame.
+    #[inline]
+    pub(crate) fn occlusion_query_set(&self) -> Option<&wgpu::QuerySet> {
+        self.occlusion
+            .as_ref()
+            .filter(|occlusion| !occlusion.queried.is_empty())
+            .map(|occlusion| &occlusion.query_set)
+    }
+
+    /// Copy this frame's query results out for reading back. Call after the
+    /// pass the ui was rendered in.
+    #[inline]
+    pub(crate) fn resolve_occlusion(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        if let Some(occlusion) = &mut self.occlusion {
+            occlusion.resolve(encoder);
+        }
+    }
+
+    /// Start reading back the results. Call after the resolve has been
+    /// submitted.
+    #[inline]
+    pub(crate) fn map_occlusion(&mut self) {
+        if let Some(occlusion) = &mut self.occlusion {
+            occlusion.map();
+        }
+    }
+
+    /// Free the GPU resources held for an entity.
+    #[inline]
+    pub(crate) fn remove_entity(&mut self, entity: Entity) {
+        if self.instances.remove(&entity).is_some() {
+            log::trace!("Removed ui3d data for entity {:?}", entity);
+        }
     }
 
     fn prep_text(
@@ -198,6 +443,7 @@ impl Ui3dRenderer {
         world: &mut World,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
+        recorder: &FrameRecorder,
         text_res: &mut TextResources,
     ) {
         world
@@ -212,6 +458,7 @@ impl Ui3dRenderer {
                 if let Some(rebuild) = crate::text_shared::prep(
                     device,
                     queue,
+                    recorder,
                     &mut text_res.font_system,
                     &mut text_res.swash_cache,
                     &mut text_res.text_atlas,
@@ -221,6 +468,7 @@ impl Ui3dRenderer {
                     tools::update_instance_buffer(
                         device,
                         queue,
+                        recorder,
                         "UI3d Text Vertex Buffer",
                         &mut data.text_buffer.vertex_buffer,
                         &mut data.text_buffer.vertex_count,
@@ -234,18 +482,44 @@ impl Ui3dRenderer {
         &mut self,
         world: &mut World,
         queue: &wgpu::Queue,
+        recorder: &FrameRecorder,
         font_system: &mut cosmic_text::FontSystem,
     ) {
+        let linear_target = color::is_linear_target(self.config.format);
+
         world
-            .query_mut::<(&Transform, &Ui3d)>()
+            .query_mut::<(&Transform, &Ui3d, Option<&Hidden>, Option<&Opacity>)>()
             .into_iter()
-            .for_each(|(entity, (transform, ui))| {
+            .for_each(|(entity, (transform, ui, hidden, opacity))| {
                 let data = self.instances.get_mut(&entity).unwrap();
 
+                data.depth_tested = ui.depth_tested;
+                data.clip = ui.clip;
+                data.occlusion_cull = ui.occlusion_cull;
+
+                // Keep the buffers and text around for when it's shown again
+                data.visible = hidden.is_none();
+                if !data.wants_query() {
+                    data.occluded = false;
+                }
+                if !data.visible {
+                    return;
+                }
+
+                let opacity = Opacity::of(opacity);
+
                 let position_raw = UiPositionUniformRaw {
                     transform: transform.to_matrix(),
+                    opacity,
+                    linear_target: linear_target as u32,
+                    pad: [0; 2],
+                    clip: UiClip::local_raw(ui.clip.as_ref()),
                 };
 
+                recorder.record(|| CaptureCommand::WriteBuffer {
+                    label: String::from("UI3d Position Uniform"),
+                    bytes: std::mem::size_of::<UiPositionUniformRaw>() as u64,
+                });
                 queue
                     .write_buffer_with(
                         &data.ui_position_uniform_buffer,
@@ -262,39 +536,35 @@ impl Ui3dRenderer {
                 //     bytemuck::cast_slice(&[position_raw]),
                 // );
 
-                let longest_line = ui.options.iter().reduce(|a, b| match a.len() < b.len() {
-                    true => a,
-                    false => b,
-                });
-
-                let longest_line = match longest_line {
-                    Some(val) => val,
-                    None => return,
-                };
-
-                let selected = ui.selected.clamp(0, ui.options.len() as u8) as f32;
-
-                let option_count = ui.options.len() as f32;
-                let option_range = 1. / option_count;
+                if ui.options.is_empty() {
+                    return;
+                }
 
-                let ui_size = glam::vec2(
-                    ui.font_size * longest_line.len() as f32,
-                    ui.font_size * option_count,
-                );
+                let ui_size = ui.size();
+                let selection_range = ui.selection_range();
 
                 let ui_raw = UiUniformRaw {
                     size: ui_size,
-                    menu_color: ui.menu_color.into(),
-                    selection_color: ui.selection_color.into(),
-                    selection_range_y: glam::vec2(
-                        option_range * selected,
-                        option_range * (selected + 1.),
-                    ),
+                    menu_color: color::srgba_for_target(
+                        Opacity::apply(ui.menu_color, opacity),
+                        linear_target,
+                    )
+                    .into(),
+                    selection_color: color::srgba_for_target(
+                        Opacity::apply(ui.selection_color, opacity),
+                        linear_target,
+                    )
+                    .into(),
+                    selection_range_y: selection_range,
 
                     pad: [0.; 2],
                     pad2: [0.; 2],
                 };
 
+                recorder.record(|| CaptureCommand::WriteBuffer {
+                    label: String::from("UI3d Uniform"),
+                    bytes: std::mem::size_of::<UiUniformRaw>() as u64,
+                });
                 queue
                     .write_buffer_with(
                         &data.ui_uniform_buffer,
@@ -307,9 +577,54 @@ impl Ui3dRenderer {
                 // queue.write_buffer(&data.ui_uniform_buffer, 0, bytemuck::cast_slice(&[ui_raw]));
 
                 data.size = ui_size.to_array();
+                data.selection_range = selection_range.to_array();
 
                 data.text_buffer
-                    .set_metrics(font_system, Metrics::new(ui.font_size, ui.font_size));
+                    .set_size(font_system, ui.font_size, ui.font_size);
+            });
+    }
+
+    // Apply the last results read back then hand out queries for this frame
+    fn prep_occlusion(&mut self, device: &wgpu::Device) {
+        let wanted = self
+            .instances
+            .iter()
+            .filter(|(_, data)| data.wants_query())
+            .map(|(entity, _)| *entity)
+            .collect::<Vec<_>>();
+
+        if self.occlusion.is_none() && !wanted.is_empty() {
+            log::debug!("Creating ui occlusion queries");
+            self.occlusion = Some(OcclusionQueries::new(device, wanted.len() as u32));
+        }
+
+        let occlusion = match &mut self.occlusion {
+            Some(occlusion) => occlusion,
+            None => return,
+        };
+
+        occlusion
+            .collect()
+            .into_iter()
+            .for_each(|(entity, occluded)| {
+                if let Some(data) = self.instances.get_mut(&entity) {
+                    data.occluded = occluded && data.wants_query();
+                }
+            });
+
+        self.instances
+            .values_mut()
+            .for_each(|data| data.query = None);
+
+        occlusion.begin(device, wanted);
+        occlusion
+            .queried
+            .iter()
+            .enumerate()
+            .for_each(|(index, entity)| {
+                if let Some(data) = self.instances.get_mut(entity) {
+                    data.query = Some(index as u32);
+                }
             });
     }
 
@@ -366,6 +681,10 @@ impl Ui3dRenderer {
             "Ui Position",
             &[UiPositionUniformRaw {
                 transform: glam::Mat4::default(),
+                opacity: 1.,
+                linear_target: 1,
+                pad: [0; 2],
+                clip: UiClip::local_raw(None),
             }],
         );
 
@@ -380,12 +699,7 @@ impl Ui3dRenderer {
             }],
         });
 
-        let text = ui
-            .options
-            .iter()
-            .cloned()
-            .reduce(|a, b| format!("{}\n{}", a, b))
-            .unwrap_or(String::new());
+        let text = options_text(ui);
 
         let text_buffer = TextBuffer::new(
             device,
@@ -410,39 +724,316 @@ impl Ui3dRenderer {
                 ui_position_uniform_buffer,
                 ui_position_uniform_bind_group,
                 size: [1., 1.],
+                selection_range: [0., 0.],
                 text_buffer,
+                visible: true,
+                depth_tested: ui.depth_tested,
+                clip: ui.clip,
+                occlusion_cull: ui.occlusion_cull,
+                query: None,
+                occluded: false,
+                seen: true,
             },
         );
     }
 
+    #[tracing::instrument(skip_all, name = "ui3d_render")]
     pub(crate) fn render(
         &self,
         pass: &mut wgpu::RenderPass,
         text_atlas: &TextAtlas,
-        camera_bind_group: &wgpu::BindGroup,
+        shared: &SharedRenderResources,
+        target_size: [u32; 2],
     ) {
-        // Set camera (both pipelines)
-        pass.set_bind_group(0, camera_bind_group, &[]);
+        // Set camera and frame (both pipelines)
+        pass.set_bind_group(0, shared.frame_bind_group(), &[]);
+
+        // Depth tested first so the overlay always ends up on top
+        if let Some(depth_tested) = &self.depth_tested {
+            self.render_pipelines(
+                pass,
+                shared.recorder(),
+                text_atlas,
+                depth_tested,
+                true,
+                target_size,
+            );
+        }
+        self.render_pipelines(
+            pass,
+            shared.recorder(),
+            text_atlas,
+            &self.overlay,
+            false,
+            target_size,
+        );
+    }
+
+    fn render_pipelines(
+        &self,
+        pass: &mut wgpu::RenderPass,
+        recorder: &FrameRecorder,
+        text_atlas: &TextAtlas,
+        pipelines: &UiPipelines,
+        depth_tested: bool,
+        target_size: [u32; 2],
+    ) {
+        let instances = || {
+            self.instances
+                .values()
+                .filter(|instance| instance.visible && instance.depth_tested == depth_tested)
+        };
+
+        // Screen clips that don't cover anything skip the instance entirely
+        let mut scissor = Scissor::new(target_size);
 
         // Draw UI background
-        pass.set_pipeline(&self.ui_pipeline);
+        recorder.record(|| CaptureCommand::SetPipeline {
+            label: match depth_tested {
+                true => "UI3d Depth Tested Pipeline",
+                false => "UI3d Pipeline",
+            },
+        });
+        pass.set_pipeline(&pipelines.ui);
 
-        self.instances.values().into_iter().for_each(|instance| {
-            pass.set_bind_group(1, &instance.ui_uniform_bind_group, &[]);
-            pass.set_bind_group(2, &instance.ui_position_uniform_bind_group, &[]);
-            pass.draw(0..4, 0..1);
+        instances().for_each(|instance| {
+            // Queries still have to end when clipped away, counting as hidden
+            let clipped = !scissor.set(pass, instance.clip.as_ref());
+
+            if let Some(query) = instance.query {
+                pass.begin_occlusion_query(query);
+            }
+
+            if !clipped {
+                recorder.record(|| CaptureCommand::Draw {
+                    vertices: 4,
+                    instances: 1,
+                    indexed: false,
+                });
+                pass.set_bind_group(1, &instance.ui_uniform_bind_group, &[]);
+                pass.set_bind_group(2, &instance.ui_position_uniform_bind_group, &[]);
+                pass.draw(0..4, 0..1);
+            }
+
+            if instance.query.is_some() {
+                pass.end_occlusion_query();
+            }
         });
 
-        // // Draw Text
-        pass.set_pipeline(&self.text_pipeline);
+        // Draw Text
+        recorder.record(|| CaptureCommand::SetPipeline {
+            label: match depth_tested {
+                true => "UI3d Text Depth Tested Pipeline",
+                false => "UI3d Text Pipeline",
+            },
+        });
+        recorder.record(|| CaptureCommand::SetBindGroup {
+            index: 1,
+            label: String::from("Text atlas"),
+        });
+        pass.set_pipeline(&pipelines.text);
         pass.set_bind_group(1, text_atlas.bind_group(), &[]);
 
-        self.instances.values().into_iter().for_each(|instance| {
+        instances().for_each(|instance| {
+            if instance.occluded || !scissor.set(pass, instance.clip.as_ref()) {
+                return;
+            }
+
+            recorder.record(|| CaptureCommand::Draw {
+                vertices: 4,
+                instances: instance.text_buffer.vertex_count,
+                indexed: false,
+            });
             pass.set_vertex_buffer(0, instance.text_buffer.vertex_buffer.slice(..));
             pass.set_bind_group(2, &instance.ui_position_uniform_bind_group, &[]);
             pass.draw(0..4, 0..instance.text_buffer.vertex_count);
         });
+
+        // Leave the pass unclipped for whatever draws next
+        scissor.set(pass, None);
+    }
+}
+
+//====================================================================
+
+/// Tracks the scissor rect so it's only changed between differently clipped
+/// instances.
+struct Scissor {
+    target_size: [u32; 2],
+    current: Option<[u32; 4]>,
+}
+
+impl Scissor {
+    #[inline]
+    fn new(target_size: [u32; 2]) -> Self {
+        Self {
+            target_size,
+            current: None,
+        }
+    }
+
+    // False if the instance is clipped away completely
+    fn set(&mut self, pass: &mut wgpu::RenderPass, clip: Option<&UiClip>) -> bool {
+        let rect = match clip {
+            Some(clip @ UiClip::Screen { .. }) => match clip.scissor_rect(self.target_size) {
+                Some(rect) => Some(rect),
+                None => return false,
+            },
+            _ => None,
+        };
+
+        if rect != self.current {
+            let [x, y, width, height] =
+                rect.unwrap_or([0, 0, self.target_size[0], self.target_size[1]]);
+            pass.set_scissor_rect(x, y, width, height);
+            self.current = rect;
+        }
+
+        true
+    }
+}
+
+//====================================================================
+
+const QUERY_RESULT_SIZE: u64 = std::mem::size_of::<u64>() as u64;
+
+/// Occlusion queries wrapped around the backgrounds of depth tested menus,
+/// read back a frame later to find which were completely hidden.
+struct OcclusionQueries {
+    query_set: wgpu::QuerySet,
+    capacity: u32,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+
+    /// Entities queried this frame, in query order.
+    queried: Vec<Entity>,
+    /// Entities of the results being read back, in query order.
+    reading: Vec<Entity>,
+    mapped: Option<Receiver<Result<(), wgpu::BufferAsyncError>>>,
+}
+
+impl OcclusionQueries {
+    fn new(device: &wgpu::Device, capacity: u32) -> Self {
+        // Grow ahead so a few more menus don't recreate everything
+        let capacity = capacity.next_power_of_two();
+
+        Self {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("Ui Occlusion Query Set"),
+                ty: wgpu::QueryType::Occlusion,
+                count: capacity,
+            }),
+            capacity,
+            resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Ui Occlusion Resolve Buffer"),
+                size: capacity as u64 * QUERY_RESULT_SIZE,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            readback_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Ui Occlusion Readback Buffer"),
+                size: capacity as u64 * QUERY_RESULT_SIZE,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+            queried: Vec::new(),
+            reading: Vec::new(),
+            mapped: None,
+        }
+    }
+
+    // Hand out queries for this frame. Skipped while the last results are
+    // still being read back, which can take more than one frame on web.
+    fn begin(&mut self, device: &wgpu::Device, entities: Vec<Entity>) {
+        self.queried.clear();
+
+        if !self.reading.is_empty() || entities.is_empty() {
+            return;
+        }
+
+        if entities.len() as u32 > self.capacity {
+            log::trace!("Growing ui occlusion queries to fit {}", entities.len());
+            *self = Self::new(device, entities.len() as u32);
+        }
+
+        self.queried = entities;
     }
+
+    fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        if self.queried.is_empty() {
+            return;
+        }
+
+        let count = self.queried.len() as u32;
+
+        encoder.resolve_query_set(&self.query_set, 0..count, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.readback_buffer,
+            0,
+            count as u64 * QUERY_RESULT_SIZE,
+        );
+
+        self.reading = std::mem::take(&mut self.queried);
+    }
+
+    fn map(&mut self) {
+        if self.reading.is_empty() || self.mapped.is_some() {
+            return;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        self.readback_buffer
+            .slice(..self.reading.len() as u64 * QUERY_RESULT_SIZE)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                sender.send(result).ok();
+            });
+
+        self.mapped = Some(receiver);
+    }
+
+    // Whether each entity read back was completely hidden, once mapped
+    fn collect(&mut self) -> Vec<(Entity, bool)> {
+        let result = match self.mapped.as_ref().map(|receiver| receiver.try_recv()) {
+            Some(Ok(result)) => result,
+            _ => return Vec::new(),
+        };
+
+        self.mapped = None;
+        let reading = std::mem::take(&mut self.reading);
+
+        if let Err(e) = result {
+            log::error!("Unable to read back ui occlusion queries: {}", e);
+            return Vec::new();
+        }
+
+        let slice = self
+            .readback_buffer
+            .slice(..reading.len() as u64 * QUERY_RESULT_SIZE);
+        let data = slice.get_mapped_range();
+
+        let results = reading
+            .into_iter()
+            .zip(data.chunks_exact(QUERY_RESULT_SIZE as usize))
+            .map(|(entity, samples)| (entity, bytemuck::pod_read_unaligned::<u64>(samples) == 0))
+            .collect();
+
+        drop(data);
+        self.readback_buffer.unmap();
+
+        results
+    }
+}
+
+//====================================================================
+
+fn options_text(ui: &Ui3d) -> String {
+    ui.options
+        .iter()
+        .cloned()
+        .reduce(|a, b| format!("{}\n{}", a, b))
+        .unwrap_or(String::new())
 }
 
 //====================================================================
@@ -451,6 +1042,14 @@ impl Ui3dRenderer {
 #[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Debug)]
 struct UiPositionUniformRaw {
     transform: glam::Mat4,
+    /// Only applied to the text. The menu colors have it folded in already.
+    opacity: f32,
+    /// Nonzero if text colors are converted to linear, see
+    /// [`color::is_linear_target`].
+    linear_target: u32,
+    pad: [u32; 2],
+    /// Local clip as min xy then max xy.
+    clip: glam::Vec4,
 }
 
 #[repr(C)]
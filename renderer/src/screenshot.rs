@@ -0,0 +1,160 @@
+//====================================================================
+
+use std::sync::mpsc::{self, Receiver};
+
+//====================================================================
+
+struct PendingCapture {
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
+    bgra: bool,
+    mapped: Option<Receiver<Result<(), wgpu::BufferAsyncError>>>,
+}
+
+/// Copies presented frames back from the gpu when asked.
+#[derive(Default)]
+pub(crate) struct Screenshots {
+    requested: bool,
+    pending: Option<PendingCapture>,
+    ready: Option<image::RgbaImage>,
+}
+
+impl Screenshots {
+    #[inline]
+    pub fn request(&mut self) {
+        self.requested = true;
+    }
+
+    #[inline]
+    pub fn take(&mut self) -> Option<image::RgbaImage> {
+        self.ready.take()
+    }
+
+    /// Record a copy of `texture` if a screenshot was requested. `texture`
+    /// needs to have been created with [`wgpu::TextureUsages::COPY_SRC`].
+    pub fn copy_texture(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) {
+        if !self.requested || self.pending.is_some() {
+            return;
+        }
+        self.requested = false;
+
+        if !texture.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+            log::warn!("Surface can't be copied from - unable to take screenshot");
+            return;
+        }
+
+        let bgra = match texture.format() {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            format => {
+                log::warn!("Screenshots of {:?} surfaces aren't supported", format);
+                return;
+            }
+        };
+
+        let width = texture.width();
+        let height = texture.height();
+
+        // Buffer copies need each row aligned
+        let padded_bytes_per_row = (width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Screenshot Buffer"),
+            size: padded_bytes_per_row as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+
+        self.pending = Some(PendingCapture {
+            buffer,
+            width,
+            height,
+            padded_bytes_per_row,
+            bgra,
+            mapped: None,
+        });
+    }
+
+    /// Start reading back a copy. Call after the copy has been submitted.
+    pub fn map(&mut self) {
+        let pending = match &mut self.pending {
+            Some(pending) if pending.mapped.is_none() => pending,
+            _ => return,
+        };
+
+        let (sender, receiver) = mpsc::channel();
+        pending
+            .buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                sender.send(result).ok();
+            });
+
+        pending.mapped = Some(receiver);
+    }
+
+    /// Collect a copy once the device has finished mapping it. May take more
+    /// than one frame on web.
+    pub fn collect(&mut self) {
+        let result = match self
+            .pending
+            .as_ref()
+            .and_then(|pending| pending.mapped.as_ref())
+            .map(|receiver| receiver.try_recv())
+        {
+            Some(Ok(result)) => result,
+            _ => return,
+        };
+
+        let pending = self.pending.take().unwrap();
+
+        if let Err(e) = result {
+            log::error!("Unable to read back screenshot: {}", e);
+            return;
+        }
+
+        let data = pending.buffer.slice(..).get_mapped_range();
+        let row_bytes = (pending.width * 4) as usize;
+
+        let mut pixels = Vec::with_capacity(row_bytes * pending.height as usize);
+        data.chunks(pending.padded_bytes_per_row as usize)
+            .for_each(|row| pixels.extend_from_slice(&row[..row_bytes]));
+
+        drop(data);
+        pending.buffer.unmap();
+
+        if pending.bgra {
+            pixels
+                .chunks_exact_mut(4)
+                .for_each(|pixel| pixel.swap(0, 2));
+        }
+
+        // Surfaces aren't always opaque
+        pixels.chunks_exact_mut(4).for_each(|pixel| pixel[3] = 255);
+
+        self.ready = image::RgbaImage::from_raw(pending.width, pending.height, pixels);
+        log::debug!("Screenshot taken ({}x{})", pending.width, pending.height);
+    }
+}
+
+//====================================================================
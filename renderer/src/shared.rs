@@ -1,6 +1,13 @@
 //====================================================================
 
-use super::{texture::Texture, tools};
+use wgpu::util::DeviceExt;
+
+use super::{
+    camera::{CameraUniform, CameraUniformRaw},
+    capture::{CaptureCommand, FrameRecorder},
+    texture::Texture,
+    tools,
+};
 
 //====================================================================
 
@@ -12,6 +19,13 @@ pub trait Vertex: bytemuck::Pod {
 
 pub struct SharedRenderResources {
     pub texture_bind_group_layout: wgpu::BindGroupLayout,
+
+    camera_buffer: wgpu::Buffer,
+    frame_buffer: wgpu::Buffer,
+    frame_bind_group_layout: wgpu::BindGroupLayout,
+    frame_bind_group: wgpu::BindGroup,
+
+    recorder: FrameRecorder,
 }
 
 impl SharedRenderResources {
@@ -22,8 +36,52 @@ impl SharedRenderResources {
                 entries: &[tools::bgl_texture_entry(0), tools::bgl_sampler_entry(1)],
             });
 
+        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Camera buffer"),
+            contents: bytemuck::cast_slice(&[CameraUniformRaw::new(
+                glam::Mat4::IDENTITY,
+                glam::Vec3::ZERO,
+            )]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let frame_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Frame buffer"),
+            contents: bytemuck::cast_slice(&[FrameUniform::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let frame_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Frame Bind Group Layout"),
+                entries: &[
+                    tools::bgl_uniform_entry(0, wgpu::ShaderStages::VERTEX_FRAGMENT),
+                    tools::bgl_uniform_entry(1, wgpu::ShaderStages::VERTEX_FRAGMENT),
+                ],
+            });
+
+        let frame_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Frame Bind Group"),
+            layout: &frame_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: camera_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: frame_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
         Self {
             texture_bind_group_layout,
+            camera_buffer,
+            frame_buffer,
+            frame_bind_group_layout,
+            frame_bind_group,
+            recorder: FrameRecorder::default(),
         }
     }
 
@@ -32,6 +90,46 @@ impl SharedRenderResources {
         &self.texture_bind_group_layout
     }
 
+    /// Layout every pipeline uses at group 0, holding the camera at binding
+    /// 0 and the [`FrameUniform`] at binding 1.
+    #[inline]
+    pub fn frame_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.frame_bind_group_layout
+    }
+
+    #[inline]
+    pub fn frame_bind_group(&self) -> &wgpu::BindGroup {
+        &self.frame_bind_group
+    }
+
+    /// Where commands are recorded while a frame is being captured.
+    #[inline]
+    pub fn recorder(&self) -> &FrameRecorder {
+        &self.recorder
+    }
+
+    #[inline]
+    pub fn update_camera<C: CameraUniform>(&self, queue: &wgpu::Queue, camera: &C) {
+        self.recorder.record(|| CaptureCommand::WriteBuffer {
+            label: String::from("Camera Uniform"),
+            bytes: std::mem::size_of::<CameraUniformRaw>() as u64,
+        });
+        queue.write_buffer(
+            &self.camera_buffer,
+            0,
+            bytemuck::cast_slice(&[camera.into_uniform()]),
+        );
+    }
+
+    #[inline]
+    pub fn update_frame(&self, queue: &wgpu::Queue, frame: &FrameUniform) {
+        self.recorder.record(|| CaptureCommand::WriteBuffer {
+            label: String::from("Frame Uniform"),
+            bytes: std::mem::size_of::<FrameUniform>() as u64,
+        });
+        queue.write_buffer(&self.frame_buffer, 0, bytemuck::cast_slice(&[*frame]));
+    }
+
     pub fn create_bind_group(
         &self,
         device: &wgpu::Device,
@@ -53,6 +151,45 @@ impl SharedRenderResources {
             ],
         })
     }
+
+    /// Bind the texture with a sampler other than its own.
+    pub fn create_sampler_bind_group(
+        &self,
+        device: &wgpu::Device,
+        texture: &Texture,
+        sampler: &wgpu::Sampler,
+        label: Option<&str>,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label,
+            layout: &self.texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        })
+    }
+}
+
+//====================================================================
+
+/// Values for the whole frame, bound next to the camera so any shader can
+/// animate without per entity buffer writes.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, bytemuck::Zeroable, bytemuck::Pod)]
+pub struct FrameUniform {
+    /// Size in pixels of the area the scene is drawn into.
+    pub viewport_size: [f32; 2],
+    /// Seconds since the renderer was created.
+    pub time: f32,
+    /// Frames rendered so far, wrapping on overflow.
+    pub frame: u32,
 }
 
 //====================================================================
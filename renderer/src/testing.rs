@@ -0,0 +1,344 @@
+//====================================================================
+
+use std::sync::Arc;
+
+use common::PhysicalSize;
+use hecs::World;
+
+use crate::{
+    camera::{Camera, PerspectiveCamera},
+    pipelines::{texture_pipeline::TextureRenderer, ui3d_pipeline::Ui3dRenderer},
+    shared::SharedRenderResources,
+    text_shared::TextResources,
+    texture::Texture,
+    texture_storage::{DefaultTexture, LoadedTexture},
+};
+
+//====================================================================
+
+/// Device and offscreen target for running pipelines without a window, so
+/// tests can check batching, atlas usage and output pixels.
+///
+/// ```ignore
+/// let mut harness = HeadlessHarness::new(PhysicalSize::new(64, 64)).expect("no adapter");
+/// let mut sprites = harness.texture_renderer();
+/// harness.run_texture_renderer(&mut sprites, &mut world);
+/// let pixels = harness.read_pixels();
+/// ```
+pub struct HeadlessHarness {
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    pub config: wgpu::SurfaceConfiguration,
+
+    pub shared: SharedRenderResources,
+    pub default_texture: DefaultTexture,
+    pub camera: Camera,
+    pub text_res: TextResources,
+    pub clear_color: wgpu::Color,
+
+    target: wgpu::Texture,
+    target_view: wgpu::TextureView,
+    depth_texture: Texture,
+}
+
+impl HeadlessHarness {
+    pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+    /// Create a harness using whichever adapter is available, preferring a
+    /// software fallback adapter. Returns `None` if no adapter can be found.
+    pub fn new(size: PhysicalSize<u32>) -> Option<Self> {
+        pollster::block_on(Self::new_async(size))
+    }
+
+    pub async fn new_async(size: PhysicalSize<u32>) -> Option<Self> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+
+        let adapter = match instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::LowPower,
+                force_fallback_adapter: true,
+                compatible_surface: None,
+            })
+            .await
+        {
+            Some(adapter) => adapter,
+            None => {
+                instance
+                    .request_adapter(&wgpu::RequestAdapterOptions::default())
+                    .await?
+            }
+        };
+
+        log::debug!("Headless harness using adapter {:?}", adapter.get_info());
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: Some("Headless Device"),
+                    required_features: wgpu::Features::empty(),
+                    required_limits: wgpu::Limits::downlevel_defaults(),
+                    memory_hints: wgpu::MemoryHints::default(),
+                },
+                None,
+            )
+            .await
+            .ok()?;
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: Self::FORMAT,
+            width: size.width,
+            height: size.height,
+            present_mode: wgpu::PresentMode::AutoNoVsync,
+            desired_maximum_frame_latency: 2,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+        };
+
+        let target = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Headless Target"),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let target_view = target.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let depth_texture = Texture::create_depth_texture(&device, size, "Headless Depth");
+
+        let shared = SharedRenderResources::new(&device);
+
+        let default_texture = DefaultTexture::new(Arc::new(LoadedTexture::load_texture(
+            &device,
+            &shared,
+            Texture::from_color(&device, &queue, [255; 3], Some("Default Texture"), None),
+        )));
+
+        let mut camera = Camera::new(PerspectiveCamera::default());
+        camera.set_aspect(size.width as f32, size.height as f32);
+
+        let text_res = TextResources::new(&device);
+
+        Some(Self {
+            device,
+            queue,
+            config,
+            shared,
+            default_texture,
+            camera,
+            text_res,
+            clear_color: wgpu::Color::BLACK,
+            target,
+            target_view,
+            depth_texture,
+        })
+    }
+
+    #[inline]
+    pub fn size(&self) -> PhysicalSize<u32> {
+        PhysicalSize::new(self.config.width, self.config.height)
+    }
+
+    //--------------------------------------------------
+
+    pub fn texture_renderer(&self) -> TextureRenderer {
+        TextureRenderer::new(
+            &self.device,
+            &self.config,
+            &self.shared,
+            self.default_texture.texture(),
+        )
+    }
+
+    pub fn ui3d_renderer(&self) -> Ui3dRenderer {
+        Ui3dRenderer::new(
+            &self.device,
+            &self.config,
+            &self.text_res.text_atlas,
+            &self.shared,
+        )
+    }
+
+    #[inline]
+    pub fn prep_texture_renderer(&self, renderer: &mut TextureRenderer, world: &mut World) {
+        renderer.prep(
+            world,
+            &self.device,
+            &self.queue,
+            &self.shared,
+            self.camera.camera.translation,
+            None,
+        );
+    }
+
+    /// Prep the renderer from the world then draw it into the target.
+    pub fn run_texture_renderer(&mut self, renderer: &mut TextureRenderer, world: &mut World) {
+        self.camera.update_camera(&self.queue, &self.shared);
+        renderer.prep(
+            world,
+            &self.device,
+            &self.queue,
+            &self.shared,
+            self.camera.camera.translation,
+            None,
+        );
+
+        self.render(None, |pass, harness| renderer.render(pass, &harness.shared));
+    }
+
+    #[inline]
+    pub fn prep_ui3d_renderer(&mut self, renderer: &mut Ui3dRenderer, world: &mut World) {
+        renderer.prep(
+            world,
+            &self.device,
+            &self.queue,
+            &mut self.text_res,
+            &self.shared,
+        );
+    }
+
+    /// Prep the renderer from the world then draw it into the target.
+    pub fn run_ui3d_renderer(&mut self, renderer: &mut Ui3dRenderer, world: &mut World) {
+        self.camera.update_camera(&self.queue, &self.shared);
+        renderer.prep_rotations(world, self.camera.camera.translation);
+        renderer.prep(
+            world,
+            &self.device,
+            &self.queue,
+            &mut self.text_res,
+            &self.shared,
+        );
+
+        self.render(renderer.occlusion_query_set(), |pass, harness| {
+            renderer.render(
+                pass,
+                &harness.text_res.text_atlas,
+                &harness.shared,
+                [harness.config.width, harness.config.height],
+            )
+        });
+
+        self.text_res.text_atlas.post_render_trim();
+    }
+
+    fn render(
+        &self,
+        occlusion_query_set: Option<&wgpu::QuerySet>,
+        draw: impl FnOnce(&mut wgpu::RenderPass, &Self),
+    ) {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Headless Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &self.target_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(self.clear_color),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth_texture.view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set,
+            });
+
+            draw(&mut render_pass, self);
+        }
+
+        self.queue.submit(Some(encoder.finish()));
+        self.device.poll(wgpu::Maintain::Wait);
+    }
+
+    //--------------------------------------------------
+
+    /// Copy the target back to the cpu as tightly packed RGBA8 rows.
+    pub fn read_pixels(&self) -> Vec<u8> {
+        let size = self.size();
+
+        // Rows must be aligned for buffer copies
+        let unpadded_row = size.width * 4;
+        let padded_row = unpadded_row.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Headless Readback Buffer"),
+            size: (padded_row * size.height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+
+        encoder.copy_texture_to_buffer(
+            self.target.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row),
+                    rows_per_image: Some(size.height),
+                },
+            },
+            self.target.size(),
+        );
+
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |result| {
+            result.expect("Unable to map readback buffer")
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+
+        let data = slice.get_mapped_range();
+
+        let pixels = data
+            .chunks_exact(padded_row as usize)
+            .flat_map(|row| &row[..unpadded_row as usize])
+            .copied()
+            .collect();
+
+        drop(data);
+        buffer.unmap();
+
+        pixels
+    }
+
+    /// Read a single RGBA8 pixel from the target.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let pixels = self.read_pixels();
+        let index = ((y * self.config.width + x) * 4) as usize;
+
+        [
+            pixels[index],
+            pixels[index + 1],
+            pixels[index + 2],
+            pixels[index + 3],
+        ]
+    }
+}
+
+//====================================================================
@@ -1,6 +1,7 @@
 //====================================================================
 
 use std::{
+    borrow::Cow,
     collections::HashSet,
     error::Error,
     fmt::Display,
@@ -8,20 +9,36 @@ use std::{
 };
 
 use common::Size;
-use cosmic_text::{Attrs, Buffer, CacheKey, Color, Metrics, Shaping, SwashImage, Wrap};
+use cosmic_text::{
+    Attrs, AttrsOwned, Buffer, CacheKey, Color, Metrics, Shaping, SwashContent, SwashImage, Wrap,
+};
 use etagere::{euclid::Size2D, AllocId, BucketedAtlasAllocator};
 use lru::LruCache;
 use rustc_hash::FxHasher;
 
-use crate::{shared::Vertex, texture::Texture, tools};
+use crate::{capture::FrameRecorder, shared::Vertex, texture::Texture, tools};
 
 //====================================================================
 
 type FastHasher = BuildHasherDefault<FxHasher>;
 
+/// Which atlas page a glyph is stored in.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GlyphContent {
+    /// Single channel coverage, tinted by the text color.
+    Mask = 0,
+    /// Pre-colored RGBA glyph, such as emoji.
+    Color = 1,
+}
+
 pub struct GlyphData {
     alloc_id: AllocId,
+    pub content: GlyphContent,
+    /// Top left of the glyph in its atlas page, in texels so it stays valid
+    /// when the page grows.
     pub uv_start: [f32; 2],
+    /// Bottom right of the glyph in its atlas page, in texels.
     pub uv_end: [f32; 2],
     pub left: f32,
     pub top: f32,
@@ -43,7 +60,7 @@ impl Display for CacheGlyphError {
         let msg = match &self {
             CacheGlyphError::NoGlyphImage => "Unable to get image from proved glyph.",
             CacheGlyphError::OutOfSpace => {
-                "Atlas texture is not big enough to store new glyphs - TODO"
+                "Atlas texture is at its largest size and full of glyphs in use."
             }
             CacheGlyphError::LruStorageError => {
                 "Error accessing glyphs from LRU - This shouldn't really happen."
@@ -56,14 +73,84 @@ impl Display for CacheGlyphError {
 
 //====================================================================
 
-pub struct TextAtlas {
+struct AtlasPage {
     packer: BucketedAtlasAllocator,
+    texture: Texture,
+    texture_size: Size<u32>,
+    label: &'static str,
+}
+
+impl AtlasPage {
+    fn new(
+        device: &wgpu::Device,
+        size: u32,
+        format: wgpu::TextureFormat,
+        label: &'static str,
+    ) -> Self {
+        let packer = BucketedAtlasAllocator::new(Size2D::new(size as i32, size as i32));
+
+        let texture_size = Size::new(size, size);
+        let texture = Texture::from_size_format(device, texture_size, format, Some(label), None);
+
+        Self {
+            packer,
+            texture,
+            texture_size,
+            label,
+        }
+    }
+
+    // Double the page size, keeping every glyph where it is. Returns false if
+    // the page is already as big as the device allows.
+    fn grow(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        let max_size = device.limits().max_texture_dimension_2d;
+        if self.texture_size.width >= max_size {
+            return false;
+        }
+
+        let size = (self.texture_size.width * 2).min(max_size);
+        log::debug!("Growing {} to {}x{}", self.label, size, size);
+
+        let texture_size = Size::new(size, size);
+        let texture = Texture::from_size_format(
+            device,
+            texture_size,
+            self.texture.texture.format(),
+            Some(self.label),
+            None,
+        );
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Text Atlas Grow Encoder"),
+        });
+        encoder.copy_texture_to_texture(
+            self.texture.texture.as_image_copy(),
+            texture.texture.as_image_copy(),
+            wgpu::Extent3d {
+                width: self.texture_size.width,
+                height: self.texture_size.height,
+                depth_or_array_layers: 1,
+            },
+        );
+        queue.submit(Some(encoder.finish()));
+
+        self.packer.grow(Size2D::new(size as i32, size as i32));
+        self.texture = texture;
+        self.texture_size = texture_size;
+
+        true
+    }
+}
+
+/// Glyph atlas split into two pages - an R8 page for regular glyph masks and
+/// an RGBA page for color glyphs.
+pub struct TextAtlas {
+    mask_page: AtlasPage,
+    color_page: AtlasPage,
 
     glyphs_in_use: HashSet<CacheKey, FastHasher>,
     cached_glyphs: LruCache<CacheKey, GlyphData, FastHasher>,
 
-    texture: Texture,
-    texture_size: Size<u32>,
     bind_group_layout: wgpu::BindGroupLayout,
     bind_group: wgpu::BindGroup,
 }
@@ -72,45 +159,69 @@ impl TextAtlas {
     pub fn new(device: &wgpu::Device) -> Self {
         const DEFAULT_START_SIZE: u32 = 256;
 
-        let packer = BucketedAtlasAllocator::new(Size2D::new(
-            DEFAULT_START_SIZE as i32,
-            DEFAULT_START_SIZE as i32,
-        ));
+        let mask_page = AtlasPage::new(
+            device,
+            DEFAULT_START_SIZE,
+            wgpu::TextureFormat::R8Unorm,
+            "Text Atlas Mask Texture",
+        );
+
+        let color_page = AtlasPage::new(
+            device,
+            DEFAULT_START_SIZE,
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            "Text Atlas Color Texture",
+        );
+
         let glyphs_in_use = HashSet::with_hasher(FastHasher::default());
         let cached_glyphs = LruCache::unbounded_with_hasher(FastHasher::default());
 
-        let texture_size = Size::new(DEFAULT_START_SIZE, DEFAULT_START_SIZE);
-        let texture = Texture::from_size(device, texture_size, Some("Text Atlas Texture"), None);
-
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("Text Atlas Bind Group Layout"),
-            entries: &[tools::bgl_texture_entry(0), tools::bgl_sampler_entry(1)],
+            entries: &[
+                tools::bgl_texture_entry(0),
+                tools::bgl_sampler_entry(1),
+                tools::bgl_texture_entry(2),
+            ],
         });
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        let bind_group =
+            Self::create_bind_group(device, &bind_group_layout, &mask_page, &color_page);
+
+        Self {
+            mask_page,
+            color_page,
+            glyphs_in_use,
+            cached_glyphs,
+            bind_group_layout,
+            bind_group,
+        }
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        mask_page: &AtlasPage,
+        color_page: &AtlasPage,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Text Atlas Bind Group"),
-            layout: &bind_group_layout,
+            layout,
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                    resource: wgpu::BindingResource::TextureView(&mask_page.texture.view),
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                    resource: wgpu::BindingResource::Sampler(&mask_page.texture.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&color_page.texture.view),
                 },
             ],
-        });
-
-        Self {
-            packer,
-            glyphs_in_use,
-            cached_glyphs,
-            texture,
-            texture_size,
-            bind_group_layout,
-            bind_group,
-        }
+        })
     }
 
     #[inline]
@@ -122,26 +233,55 @@ impl TextAtlas {
     pub fn bind_group(&self) -> &wgpu::BindGroup {
         &self.bind_group
     }
+
+    #[inline]
+    pub fn cached_glyph_count(&self) -> usize {
+        self.cached_glyphs.len()
+    }
+
+    #[inline]
+    pub fn glyphs_in_use_count(&self) -> usize {
+        self.glyphs_in_use.len()
+    }
+
+    /// Width and height in texels of the mask and color pages.
+    #[inline]
+    pub fn page_sizes(&self) -> [u32; 2] {
+        [
+            self.mask_page.texture_size.width,
+            self.color_page.texture_size.width,
+        ]
+    }
+
+    #[inline]
+    fn page_mut(&mut self, content: GlyphContent) -> &mut AtlasPage {
+        match content {
+            GlyphContent::Mask => &mut self.mask_page,
+            GlyphContent::Color => &mut self.color_page,
+        }
+    }
 }
 
 //--------------------------------------------------
 
 impl TextAtlas {
-    // Cache glyph if not already and then promote in LRU
+    // Cache glyph if not already and then promote in LRU.
+    // Returns true if the glyph had to be (re)cached.
     pub fn use_glyph(
         &mut self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
+        recorder: &FrameRecorder,
         font_system: &mut cosmic_text::FontSystem,
         swash_cache: &mut cosmic_text::SwashCache,
         key: &CacheKey,
-    ) -> Result<(), CacheGlyphError> {
+    ) -> Result<bool, CacheGlyphError> {
         // Already has glyph cached
         if self.cached_glyphs.contains(key) {
             self.cached_glyphs.promote(key);
             self.glyphs_in_use.insert(*key);
 
-            Ok(())
+            Ok(false)
         }
         // Try to cache glyph
         else {
@@ -149,14 +289,28 @@ impl TextAtlas {
                 .get_image_uncached(font_system, *key)
                 .ok_or(CacheGlyphError::NoGlyphImage)?;
 
-            self.cache_glyph(device, queue, key, &image)?;
+            self.cache_glyph(device, queue, recorder, key, &image)?;
 
             self.cached_glyphs.promote(key);
             self.glyphs_in_use.insert(*key);
-            Ok(())
+            Ok(true)
         }
     }
 
+    /// Mark already cached glyphs as in use without touching the font system.
+    /// Returns false if any of the glyphs have since been evicted.
+    pub fn use_cached_glyphs(&mut self, keys: &[CacheKey]) -> bool {
+        keys.iter()
+            .all(|key| match self.cached_glyphs.contains(key) {
+                true => {
+                    self.cached_glyphs.promote(key);
+                    self.glyphs_in_use.insert(*key);
+                    true
+                }
+                false => false,
+            })
+    }
+
     #[inline]
     pub fn get_glyph_data(&mut self, key: &CacheKey) -> Option<&GlyphData> {
         self.cached_glyphs.get(key)
@@ -166,37 +320,59 @@ impl TextAtlas {
         &mut self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
+        recorder: &FrameRecorder,
         key: &CacheKey,
         image: &SwashImage,
     ) -> Result<(), CacheGlyphError> {
         let image_width = image.placement.width;
         let image_height = image.placement.height;
 
+        let (content, data) = match image.content {
+            SwashContent::Mask => (GlyphContent::Mask, Cow::Borrowed(&image.data)),
+            SwashContent::Color => (GlyphContent::Color, Cow::Borrowed(&image.data)),
+
+            // Collapse per channel coverage down to a single mask value
+            SwashContent::SubpixelMask => (
+                GlyphContent::Mask,
+                Cow::Owned(
+                    image
+                        .data
+                        .chunks_exact(4)
+                        .map(|pixel| {
+                            ((pixel[0] as u16 + pixel[1] as u16 + pixel[2] as u16) / 3) as u8
+                        })
+                        .collect::<Vec<_>>(),
+                ),
+            ),
+        };
+
         let size = etagere::Size::new(image_width.max(1) as i32, image_height.max(1) as i32);
 
         let allocation = loop {
-            match self.packer.allocate(size) {
+            match self.page_mut(content).packer.allocate(size) {
                 Some(allocation) => break allocation,
 
                 // Keep trying to free space until error or can allocate
-                None => self.free_space(device)?,
+                None => self.free_space(device, queue, content)?,
             }
         };
 
+        let page = self.page_mut(content);
+
         let x = allocation.rectangle.min.x as u32;
         let y = allocation.rectangle.min.y as u32;
 
-        self.texture
-            .update_area(queue, &image.data, x, y, image_width, image_height);
+        page.texture
+            .update_area(queue, recorder, &data, x, y, image_width, image_height);
 
         let uv_start = [
-            allocation.rectangle.min.x as f32 / self.texture_size.width as f32,
-            allocation.rectangle.min.y as f32 / self.texture_size.height as f32,
+            allocation.rectangle.min.x as f32,
+            allocation.rectangle.min.y as f32,
         ];
 
         let uv_end = [
-            allocation.rectangle.max.x as f32 / self.texture_size.width as f32,
-            allocation.rectangle.max.y as f32 / self.texture_size.height as f32,
+            allocation.rectangle.max.x as f32,
+            allocation.rectangle.max.y as f32,
         ];
 
         let left = image.placement.left as f32;
@@ -214,6 +390,7 @@ impl TextAtlas {
 
         let glyph_data = GlyphData {
             alloc_id: allocation.id,
+            content,
             uv_start,
             uv_end,
             left,
@@ -227,28 +404,57 @@ impl TextAtlas {
         Ok(())
     }
 
-    fn free_space(&mut self, _device: &wgpu::Device) -> Result<(), CacheGlyphError> {
-        //
-        match self.cached_glyphs.peek_lru() {
-            // Check if last used key is in use. If so, grow atlas
-            Some((key, _)) => {
-                if self.glyphs_in_use.contains(key) {
-                    // TODO - Try to grow glyph cache - Make sure to re-set all glyph data UVs
-                    return Err(CacheGlyphError::OutOfSpace);
-                }
-            }
-            // Issues with size of lru
-            None => return Err(CacheGlyphError::LruStorageError),
+    fn free_space(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        content: GlyphContent,
+    ) -> Result<(), CacheGlyphError> {
+        // Find least recently used glyph on the page that needs space
+        let key = match self
+            .cached_glyphs
+            .iter()
+            .rev()
+            .find(|(_, data)| data.content == content)
+        {
+            Some((key, _)) if !self.glyphs_in_use.contains(key) => *key,
+
+            // Everything on the page is in use or there's nothing to free
+            _ => return self.grow_page(device, queue, content),
         };
 
-        let (key, val) = self.cached_glyphs.pop_lru().unwrap();
+        let val = self
+            .cached_glyphs
+            .pop(&key)
+            .ok_or(CacheGlyphError::LruStorageError)?;
 
-        self.packer.deallocate(val.alloc_id);
-        self.cached_glyphs.pop(&key);
+        self.page_mut(content).packer.deallocate(val.alloc_id);
 
         return Ok(());
     }
 
+    // Glyph UVs are in texels so nothing cached needs updating, only the bind
+    // group pointing at the old texture
+    fn grow_page(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        content: GlyphContent,
+    ) -> Result<(), CacheGlyphError> {
+        if !self.page_mut(content).grow(device, queue) {
+            return Err(CacheGlyphError::OutOfSpace);
+        }
+
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.mask_page,
+            &self.color_page,
+        );
+
+        Ok(())
+    }
+
     #[inline]
     pub fn post_render_trim(&mut self) {
         self.glyphs_in_use.clear();
@@ -283,16 +489,18 @@ pub struct TextVertex {
     uv_start: [f32; 2],
     uv_end: [f32; 2],
     color: u32,
+    content: u32,
 }
 
 impl Vertex for TextVertex {
     fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
-        const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+        const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 6] = wgpu::vertex_attr_array![
             0 => Float32x2,
             1 => Float32x2,
             2 => Float32x2,
             3 => Float32x2,
             4 => Uint32,
+            5 => Uint32,
         ];
 
         wgpu::VertexBufferLayout {
@@ -312,7 +520,13 @@ pub struct TextBuffer {
     lines: Vec<TextBufferLine>,
 
     buffer: Buffer,
+    text: String,
+    attributes: AttrsOwned,
     color: Color,
+
+    // Set when the text needs re-laying out and re-prepping
+    dirty: bool,
+    glyph_keys: Vec<CacheKey>,
 }
 
 pub struct TextBufferDescriptor<'a> {
@@ -365,13 +579,84 @@ impl TextBuffer {
             vertex_count,
             lines,
             buffer,
+            text: desc.text.to_string(),
+            attributes: AttrsOwned::new(desc.attributes),
             color: desc.color,
+            dirty: true,
+            glyph_keys: Vec::new(),
         }
     }
 
     #[inline]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    #[inline]
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    #[inline]
+    pub fn metrics(&self) -> Metrics {
+        self.buffer.metrics()
+    }
+
+    #[inline]
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Replace the buffer text. Does nothing if the text is unchanged.
+    #[tracing::instrument(skip_all, name = "text_shaping")]
+    pub fn set_text(&mut self, font_system: &mut cosmic_text::FontSystem, text: &str) {
+        if self.text == text {
+            return;
+        }
+
+        self.text.clear();
+        self.text.push_str(text);
+
+        self.buffer.set_text(
+            font_system,
+            &self.text,
+            self.attributes.as_attrs(),
+            Shaping::Advanced,
+        );
+        self.dirty = true;
+    }
+
+    pub fn set_attributes(&mut self, font_system: &mut cosmic_text::FontSystem, attributes: Attrs) {
+        self.attributes = AttrsOwned::new(attributes);
+
+        self.buffer
+            .set_text(font_system, &self.text, attributes, Shaping::Advanced);
+        self.dirty = true;
+    }
+
+    #[inline]
+    pub fn set_color(&mut self, color: Color) {
+        if self.color != color {
+            self.color = color;
+            self.dirty = true;
+        }
+    }
+
+    #[inline]
+    pub fn set_size(
+        &mut self,
+        font_system: &mut cosmic_text::FontSystem,
+        font_size: f32,
+        line_height: f32,
+    ) {
+        self.set_metrics(font_system, Metrics::new(font_size, line_height));
+    }
+
     pub fn set_metrics(&mut self, font_system: &mut cosmic_text::FontSystem, metrics: Metrics) {
-        self.buffer.set_metrics(font_system, metrics);
+        if self.buffer.metrics() != metrics {
+            self.buffer.set_metrics(font_system, metrics);
+            self.dirty = true;
+        }
     }
 }
 
@@ -394,14 +679,24 @@ struct LocalGlyphData {
 
 //====================================================================
 
+#[tracing::instrument(skip_all, name = "text_prep")]
 pub fn prep(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
+    recorder: &FrameRecorder,
     font_system: &mut cosmic_text::FontSystem,
     swash_cache: &mut cosmic_text::SwashCache,
     text_atlas: &mut TextAtlas,
     text_buffer: &mut TextBuffer,
 ) -> Option<Vec<TextVertex>> {
+    // Nothing changed - keep glyphs alive in the atlas and reuse existing vertices
+    if !text_buffer.dirty && text_atlas.use_cached_glyphs(&text_buffer.glyph_keys) {
+        return None;
+    }
+
+    text_buffer.dirty = false;
+    text_buffer.glyph_keys.clear();
+
     let mut rebuild_all_lines = false;
 
     let local_glyph_data = text_buffer
@@ -424,16 +719,23 @@ pub fn prep(
                     let physical = glyph.physical((0., 0.), 1.);
 
                     // Try to prep glyph in atlas
-                    if let Err(_) = text_atlas.use_glyph(
+                    match text_atlas.use_glyph(
                         device,
                         queue,
+                        recorder,
                         font_system,
                         swash_cache,
                         &physical.cache_key,
                     ) {
-                        unimplemented!()
+                        // Glyph moved in the atlas, so UVs need updating
+                        Ok(true) => rebuild_all_lines = true,
+                        Ok(false) => {}
+                        // Left out of the vertices below
+                        Err(err) => log::warn!("Unable to cache glyph: {}", err),
                     }
 
+                    text_buffer.glyph_keys.push(physical.cache_key);
+
                     // Check if glyph has specific color to use
                     let color = match glyph.color_opt {
                         Some(color) => color,
@@ -485,19 +787,20 @@ pub fn prep(
         true => Some(
             local_glyph_data
                 .into_iter()
-                .map(|local_data| {
-                    let data = text_atlas.get_glyph_data(&local_data.key).unwrap();
+                .filter_map(|local_data| {
+                    let data = text_atlas.get_glyph_data(&local_data.key)?;
 
                     let x = local_data.x + data.left + data.width / 2.;
                     let y = local_data.y + data.top; // TODO - Run Line
 
-                    TextVertex {
+                    Some(TextVertex {
                         glyph_pos: [x, y],
                         glyph_size: [data.width, data.height],
                         uv_start: data.uv_start,
                         uv_end: data.uv_end,
                         color: local_data.color.0,
-                    }
+                        content: data.content as u32,
+                    })
                 })
                 .collect::<Vec<_>>(),
         ),
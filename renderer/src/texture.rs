@@ -1,8 +1,10 @@
 //====================================================================
 
-use common::Size;
+use common::{PhysicalSize, Size};
 use image::GenericImageView;
 
+use crate::capture::{CaptureCommand, FrameRecorder};
+
 //====================================================================
 
 #[derive(Debug)]
@@ -17,7 +19,7 @@ impl Texture {
 
     pub fn create_depth_texture(
         device: &wgpu::Device,
-        window_size: Size<u32>,
+        window_size: PhysicalSize<u32>,
         label: &str,
     ) -> Self {
         let size = wgpu::Extent3d {
@@ -59,6 +61,45 @@ impl Texture {
             sampler,
         }
     }
+
+    /// Create a texture that can be rendered into and then sampled from.
+    pub fn create_render_target(
+        device: &wgpu::Device,
+        size: PhysicalSize<u32>,
+        format: wgpu::TextureFormat,
+        filter: wgpu::FilterMode,
+        label: &str,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(&format!("Render Target: {}", label)),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some(&format!("Render Target Sampler: {}", label)),
+            mag_filter: filter,
+            min_filter: filter,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+        }
+    }
 }
 
 //--------------------------------------------------
@@ -157,11 +198,22 @@ impl Texture {
         }
     }
 
+    #[inline]
     pub fn from_size(
         device: &wgpu::Device,
         size: Size<u32>,
         label: Option<&str>,
         sampler: Option<&wgpu::SamplerDescriptor>,
+    ) -> Self {
+        Self::from_size_format(device, size, wgpu::TextureFormat::R8Unorm, label, sampler)
+    }
+
+    pub fn from_size_format(
+        device: &wgpu::Device,
+        size: Size<u32>,
+        format: wgpu::TextureFormat,
+        label: Option<&str>,
+        sampler: Option<&wgpu::SamplerDescriptor>,
     ) -> Self {
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label,
@@ -173,8 +225,11 @@ impl Texture {
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::R8Unorm,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            format,
+            // Copied from when the text atlas grows
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
             view_formats: &[],
         });
 
@@ -190,15 +245,44 @@ impl Texture {
 }
 
 impl Texture {
+    /// Approximate GPU memory used by this texture, including all mip levels.
+    pub fn byte_size(&self) -> u64 {
+        let format = self.texture.format();
+        let (block_width, block_height) = format.block_dimensions();
+        let block_size = format
+            .block_copy_size(Some(wgpu::TextureAspect::All))
+            .or_else(|| format.block_copy_size(Some(wgpu::TextureAspect::DepthOnly)))
+            .unwrap_or(4) as u64;
+
+        let size = self.texture.size();
+
+        (0..self.texture.mip_level_count())
+            .map(|level| {
+                let width = (size.width >> level).max(1).div_ceil(block_width) as u64;
+                let height = (size.height >> level).max(1).div_ceil(block_height) as u64;
+                width * height * size.depth_or_array_layers as u64 * block_size
+            })
+            .sum::<u64>()
+            * self.texture.sample_count() as u64
+    }
+
     pub fn update_area(
         &mut self,
         queue: &wgpu::Queue,
+        recorder: &FrameRecorder,
         data: &[u8],
         start_x: u32,
         start_y: u32,
         data_width: u32,
         data_height: u32,
     ) {
+        let bytes_per_pixel = self.texture.format().block_copy_size(None).unwrap_or(1);
+
+        recorder.record(|| CaptureCommand::WriteTexture {
+            label: String::from("Texture area"),
+            bytes: data.len() as u64,
+        });
+
         queue.write_texture(
             wgpu::ImageCopyTexture {
                 texture: &self.texture,
@@ -213,7 +297,7 @@ impl Texture {
             data,
             wgpu::ImageDataLayout {
                 offset: 0,
-                bytes_per_row: Some(data_width),
+                bytes_per_row: Some(data_width * bytes_per_pixel),
                 rows_per_image: None, //Some(data_height),
             },
             wgpu::Extent3d {
@@ -1,6 +1,8 @@
 //====================================================================
 
-use std::sync::{atomic::AtomicU32, Arc};
+use std::sync::{atomic::AtomicU32, Arc, Weak};
+
+use rustc_hash::FxHashMap;
 
 use super::{shared::SharedRenderResources, texture::Texture};
 
@@ -44,6 +46,11 @@ impl LoadedTexture {
     pub fn bind_group(&self) -> &wgpu::BindGroup {
         &self.bind_group
     }
+
+    #[inline]
+    pub fn byte_size(&self) -> u64 {
+        self._texture.byte_size()
+    }
 }
 
 impl PartialEq for LoadedTexture {
@@ -78,3 +85,217 @@ impl DefaultTexture {
 }
 
 //====================================================================
+
+#[derive(Debug)]
+struct RegistryEntry {
+    label: String,
+    bytes: u64,
+    handle: Weak<LoadedTexture>,
+    // Keeps the texture cached while nothing else uses it. Dropped on eviction.
+    retained: Option<Arc<LoadedTexture>>,
+    last_used: u64,
+}
+
+impl RegistryEntry {
+    #[inline]
+    fn is_alive(&self) -> bool {
+        self.handle.strong_count() > 0
+    }
+
+    // Only the registry itself is holding the texture
+    #[inline]
+    fn is_unused(&self) -> bool {
+        self.retained.is_some() && self.handle.strong_count() == 1
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TextureUsage {
+    pub id: u32,
+    pub label: String,
+    pub bytes: u64,
+    pub users: usize,
+    pub cached: bool,
+}
+
+/// Tracks every registered texture and how much GPU memory it uses.
+/// Textures are cached by label until the budget is exceeded, at which point
+/// cached textures nobody else is holding are evicted, least recently used first.
+#[derive(Debug, Default)]
+pub struct TextureRegistry {
+    entries: FxHashMap<u32, RegistryEntry>,
+    labels: FxHashMap<String, u32>,
+    budget: Option<u64>,
+    frame: u64,
+}
+
+impl TextureRegistry {
+    #[inline]
+    pub fn budget(&self) -> Option<u64> {
+        self.budget
+    }
+
+    #[inline]
+    pub fn set_budget(&mut self, budget: Option<u64>) {
+        self.budget = budget;
+    }
+
+    /// Start tracking a texture. If `retain` is set, the registry keeps it alive
+    /// (and available through `get`) until it gets evicted.
+    pub fn insert(
+        &mut self,
+        label: impl Into<String>,
+        texture: LoadedTexture,
+        retain: bool,
+    ) -> Arc<LoadedTexture> {
+        let texture = Arc::new(texture);
+        self.track(label, &texture, retain);
+        texture
+    }
+
+    pub fn track(&mut self, label: impl Into<String>, texture: &Arc<LoadedTexture>, retain: bool) {
+        let label = label.into();
+
+        self.labels.insert(label.clone(), texture.id());
+
+        self.entries.insert(
+            texture.id(),
+            RegistryEntry {
+                label,
+                bytes: texture.byte_size(),
+                handle: Arc::downgrade(texture),
+                retained: match retain {
+                    true => Some(texture.clone()),
+                    false => None,
+                },
+                last_used: self.frame,
+            },
+        );
+    }
+
+    pub fn get(&mut self, label: &str) -> Option<Arc<LoadedTexture>> {
+        let id = self.labels.get(label)?;
+        let entry = self.entries.get_mut(id)?;
+
+        let texture = entry.handle.upgrade()?;
+        entry.last_used = self.frame;
+
+        Some(texture)
+    }
+
+    /// Total bytes of all textures that are still alive.
+    pub fn total_bytes(&self) -> u64 {
+        self.entries
+            .values()
+            .filter(|entry| entry.is_alive())
+            .map(|entry| entry.bytes)
+            .sum()
+    }
+
+    /// Drop entries for textures that have been freed and evict unused cached
+    /// textures while over budget. Returns the number of bytes evicted.
+    pub fn trim(&mut self) -> u64 {
+        self.frame += 1;
+
+        self.entries.retain(|_, entry| entry.is_alive());
+        self.labels.retain(|_, id| self.entries.contains_key(id));
+
+        let budget = match self.budget {
+            Some(budget) => budget,
+            None => return 0,
+        };
+
+        let mut total = self.total_bytes();
+        if total <= budget {
+            return 0;
+        }
+
+        let mut candidates = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.is_unused())
+            .map(|(id, entry)| (entry.last_used, *id))
+            .collect::<Vec<_>>();
+
+        candidates.sort_unstable();
+
+        let mut evicted = 0;
+
+        for (_, id) in candidates {
+            if total <= budget {
+                break;
+            }
+
+            if let Some(entry) = self.entries.remove(&id) {
+                log::trace!("Evicting texture '{}' ({} bytes)", entry.label, entry.bytes);
+                if self.labels.get(&entry.label) == Some(&id) {
+                    self.labels.remove(&entry.label);
+                }
+                total -= entry.bytes;
+                evicted += entry.bytes;
+            }
+        }
+
+        if total > budget {
+            log::warn!(
+                "Texture memory over budget: {} / {} bytes in use",
+                total,
+                budget
+            );
+        }
+
+        evicted
+    }
+
+    /// Memory usage of every live texture, largest first.
+    pub fn report(&self) -> Vec<TextureUsage> {
+        let mut report = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.is_alive())
+            .map(|(id, entry)| {
+                let cached = entry.retained.is_some();
+                TextureUsage {
+                    id: *id,
+                    label: entry.label.clone(),
+                    bytes: entry.bytes,
+                    users: entry.handle.strong_count() - cached as usize,
+                    cached,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        report.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+        report
+    }
+
+    pub fn log_report(&self) {
+        let report = self.report();
+
+        log::debug!(
+            "Texture memory: {} textures, {:.2} MiB{}",
+            report.len(),
+            self.total_bytes() as f64 / (1024. * 1024.),
+            match self.budget {
+                Some(budget) => format!(" / {:.2} MiB", budget as f64 / (1024. * 1024.)),
+                None => String::new(),
+            }
+        );
+
+        report.iter().for_each(|usage| {
+            log::debug!(
+                "    [{}] '{}' - {:.1} KiB, {} users{}",
+                usage.id,
+                usage.label,
+                usage.bytes as f64 / 1024.,
+                usage.users,
+                match usage.cached {
+                    true => ", cached",
+                    false => "",
+                }
+            )
+        });
+    }
+}
+
+//====================================================================
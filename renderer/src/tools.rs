@@ -4,7 +4,10 @@ use std::{marker::PhantomData, num::NonZeroU32};
 
 use wgpu::util::DeviceExt;
 
-use super::texture::Texture;
+use super::{
+    capture::{CaptureCommand, FrameRecorder},
+    texture::Texture,
+};
 
 //====================================================================
 
@@ -13,6 +16,8 @@ pub struct RenderPipelineDescriptor<'a> {
     pub depth_stencil: Option<wgpu::DepthStencilState>,
     pub multisample: wgpu::MultisampleState,
     pub fragment_targets: Option<&'a [Option<wgpu::ColorTargetState>]>,
+    /// Fragment shader entry point, `fs_main` if not set.
+    pub fragment_entry: Option<&'a str>,
     pub multiview: Option<NonZeroU32>,
     pub cache: Option<&'a wgpu::PipelineCache>,
 }
@@ -24,6 +29,7 @@ impl<'a> Default for RenderPipelineDescriptor<'a> {
             depth_stencil: None,
             multisample: Default::default(),
             fragment_targets: None,
+            fragment_entry: None,
             multiview: None,
             cache: None,
         }
@@ -91,7 +97,7 @@ pub fn create_pipeline(
         multisample: desc.multisample,
         fragment: Some(wgpu::FragmentState {
             module: &shader_module,
-            entry_point: Some("fs_main"),
+            entry_point: Some(desc.fragment_entry.unwrap_or("fs_main")),
             compilation_options: Default::default(),
             targets: fragment_targets,
         }),
@@ -122,12 +128,13 @@ pub fn bgl_uniform_entry(
 pub fn bgl_storage_entry(
     binding: u32,
     visibility: wgpu::ShaderStages,
+    read_only: bool,
 ) -> wgpu::BindGroupLayoutEntry {
     wgpu::BindGroupLayoutEntry {
         binding,
         visibility,
         ty: wgpu::BindingType::Buffer {
-            ty: wgpu::BufferBindingType::Storage { read_only: true },
+            ty: wgpu::BufferBindingType::Storage { read_only },
             has_dynamic_offset: false,
             min_binding_size: None,
         },
@@ -195,6 +202,7 @@ pub fn buffer<D: bytemuck::Pod>(
 pub fn update_instance_buffer<T: bytemuck::Pod>(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
+    recorder: &FrameRecorder,
 
     label: &str,
     buffer: &mut wgpu::Buffer,
@@ -213,14 +221,24 @@ pub fn update_instance_buffer<T: bytemuck::Pod>(
         return;
     }
 
+    let bytes = std::mem::size_of_val(data) as u64;
+
     // We can fit all data inside existing buffer
     if data.len() <= *instance_count as usize {
+        recorder.record(|| CaptureCommand::WriteBuffer {
+            label: label.into(),
+            bytes,
+        });
         queue.write_buffer(buffer, 0, bytemuck::cast_slice(data));
         *instance_count = data.len() as u32; // TODO - add additional variable for buffer size
         return;
     }
 
     // Buffer is too small to fit new data. Create a new bigger one.
+    recorder.record(|| CaptureCommand::CreateBuffer {
+        label: label.into(),
+        bytes,
+    });
     *instance_count = data.len() as u32;
     *buffer = create_instance_buffer(device, label, data);
 }
@@ -0,0 +1,146 @@
+//====================================================================
+
+use common::{PhysicalSize, Size};
+
+//====================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AspectPolicy {
+    /// Fill the window, distorting the image if the aspect ratios differ.
+    Stretch,
+    /// Keep the virtual aspect ratio and fill the remaining space with bars.
+    #[default]
+    Letterbox,
+    /// Keep the virtual aspect ratio by showing more of the scene on one axis.
+    Expand,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VirtualResolution {
+    pub size: Size<f32>,
+    pub policy: AspectPolicy,
+}
+
+impl VirtualResolution {
+    #[inline]
+    pub fn new(width: f32, height: f32, policy: AspectPolicy) -> Self {
+        Self {
+            size: Size::new(width, height),
+            policy,
+        }
+    }
+}
+
+//====================================================================
+
+/// Area of the window the scene is drawn into, along with the size of that
+/// area in virtual coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub virtual_size: Size<f32>,
+}
+
+impl Viewport {
+    pub fn new(window_size: PhysicalSize<u32>, resolution: Option<VirtualResolution>) -> Self {
+        let window = Size::new(
+            window_size.width.max(1) as f32,
+            window_size.height.max(1) as f32,
+        );
+
+        let resolution = match resolution {
+            Some(resolution) => resolution,
+            None => {
+                return Self {
+                    x: 0.,
+                    y: 0.,
+                    width: window.width,
+                    height: window.height,
+                    virtual_size: window,
+                }
+            }
+        };
+
+        let scale = f32::min(
+            window.width / resolution.size.width,
+            window.height / resolution.size.height,
+        );
+
+        match resolution.policy {
+            AspectPolicy::Stretch => Self {
+                x: 0.,
+                y: 0.,
+                width: window.width,
+                height: window.height,
+                virtual_size: resolution.size,
+            },
+
+            AspectPolicy::Letterbox => {
+                let width = (resolution.size.width * scale).floor().max(1.);
+                let height = (resolution.size.height * scale).floor().max(1.);
+
+                Self {
+                    x: ((window.width - width) / 2.).floor(),
+                    y: ((window.height - height) / 2.).floor(),
+                    width,
+                    height,
+                    virtual_size: resolution.size,
+                }
+            }
+
+            AspectPolicy::Expand => Self {
+                x: 0.,
+                y: 0.,
+                width: window.width,
+                height: window.height,
+                virtual_size: Size::new(window.width / scale, window.height / scale),
+            },
+        }
+    }
+
+    #[inline]
+    pub fn aspect(&self) -> f32 {
+        self.virtual_size.width / self.virtual_size.height
+    }
+
+    /// Whether the viewport covers the whole window, meaning no bars are needed.
+    #[inline]
+    pub fn fills(&self, window_size: PhysicalSize<u32>) -> bool {
+        self.x == 0.
+            && self.y == 0.
+            && self.width == window_size.width as f32
+            && self.height == window_size.height as f32
+    }
+
+    /// Convert a window position (in physical pixels) into virtual coordinates.
+    /// Returns `None` if the position lies on the bars.
+    pub fn window_to_virtual(&self, position: glam::Vec2) -> Option<glam::Vec2> {
+        let local = position - glam::vec2(self.x, self.y);
+
+        if local.x < 0. || local.y < 0. || local.x > self.width || local.y > self.height {
+            return None;
+        }
+
+        Some(glam::vec2(
+            local.x / self.width * self.virtual_size.width,
+            local.y / self.height * self.virtual_size.height,
+        ))
+    }
+
+    /// Convert a window position (in physical pixels) into normalized device
+    /// coordinates, -1 to 1 across the viewport with y up. Returns `None` if
+    /// the position lies on the bars.
+    pub fn window_to_ndc(&self, position: glam::Vec2) -> Option<glam::Vec2> {
+        let virtual_position = self.window_to_virtual(position)?;
+
+        Some(glam::vec2(
+            virtual_position.x / self.virtual_size.width * 2. - 1.,
+            1. - virtual_position.y / self.virtual_size.height * 2.,
+        ))
+    }
+}
+
+//====================================================================
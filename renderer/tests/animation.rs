@@ -0,0 +1,209 @@
+//====================================================================
+
+use std::sync::Arc;
+
+use common::Transform;
+use hecs::World;
+use renderer::{
+    animation::{
+        self, AnimationClip, AnimationPlayer, Joint, JointChannel, JointPalette, Keyframe,
+        Skeleton, MAX_JOINTS,
+    },
+    error::SkeletonError,
+};
+
+//====================================================================
+
+const EPSILON: f32 = 1e-5;
+
+fn joint(name: &str, parent: Option<usize>, rest: glam::Vec3) -> Joint {
+    Joint {
+        name: name.into(),
+        parent,
+        inverse_bind: glam::Mat4::IDENTITY,
+        rest: Transform::from_translation(rest),
+    }
+}
+
+// Root with a child one unit up
+fn arm() -> Arc<Skeleton> {
+    Arc::new(
+        Skeleton::new(vec![
+            joint("root", None, glam::Vec3::ZERO),
+            joint("hand", Some(0), glam::Vec3::Y),
+        ])
+        .unwrap(),
+    )
+}
+
+// Moves the root from the origin to `to` over a second
+fn slide(name: &str, to: glam::Vec3) -> Arc<AnimationClip> {
+    Arc::new(AnimationClip {
+        name: name.into(),
+        duration: 1.,
+        channels: vec![JointChannel {
+            joint: 0,
+            keyframes: vec![
+                Keyframe {
+                    time: 0.,
+                    transform: Transform::default(),
+                },
+                Keyframe {
+                    time: 1.,
+                    transform: Transform::from_translation(to),
+                },
+            ],
+        }],
+    })
+}
+
+fn translations(world: &World, entity: hecs::Entity) -> Vec<glam::Vec3> {
+    world
+        .get::<&JointPalette>(entity)
+        .unwrap()
+        .0
+        .iter()
+        .map(|matrix| matrix.w_axis.truncate())
+        .collect()
+}
+
+fn assert_close(actual: glam::Vec3, expected: glam::Vec3) {
+    assert!(
+        actual.distance(expected) < EPSILON,
+        "expected {}, got {}",
+        expected,
+        actual
+    );
+}
+
+//====================================================================
+
+#[test]
+fn skeleton_rejects_parents_after_children() {
+    let err = Skeleton::new(vec![
+        joint("hand", Some(1), glam::Vec3::Y),
+        joint("root", None, glam::Vec3::ZERO),
+    ])
+    .unwrap_err();
+
+    assert_eq!(
+        err,
+        SkeletonError::ParentOrder {
+            joint: 0,
+            parent: 1
+        }
+    );
+
+    // Its own parent, or one past the end
+    let err = Skeleton::new(vec![joint("root", Some(0), glam::Vec3::ZERO)]).unwrap_err();
+    assert_eq!(
+        err,
+        SkeletonError::ParentOrder {
+            joint: 0,
+            parent: 0
+        }
+    );
+
+    let err = Skeleton::new(vec![joint("root", Some(4), glam::Vec3::ZERO)]).unwrap_err();
+    assert_eq!(
+        err,
+        SkeletonError::ParentOrder {
+            joint: 0,
+            parent: 4
+        }
+    );
+}
+
+#[test]
+fn skeleton_rejects_too_many_joints() {
+    let joints = (0..=MAX_JOINTS)
+        .map(|index| joint("joint", index.checked_sub(1), glam::Vec3::Y))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        Skeleton::new(joints).unwrap_err(),
+        SkeletonError::TooManyJoints(MAX_JOINTS + 1)
+    );
+}
+
+#[test]
+fn palette_chains_parent_transforms() {
+    let skeleton = arm();
+    let pose = [
+        Transform::from_translation(glam::vec3(2., 0., 0.)),
+        Transform::from_translation(glam::Vec3::Y),
+    ];
+
+    let mut palette = Vec::new();
+    animation::compute_joint_palette(&skeleton, &pose, &mut palette);
+
+    assert_eq!(palette.len(), 2);
+    assert_close(palette[0].w_axis.truncate(), glam::vec3(2., 0., 0.));
+    assert_close(palette[1].w_axis.truncate(), glam::vec3(2., 1., 0.));
+}
+
+#[test]
+fn palette_applies_inverse_bind() {
+    let mut joints = vec![joint("root", None, glam::Vec3::ZERO)];
+    joints[0].inverse_bind = glam::Mat4::from_translation(-glam::Vec3::X);
+    let skeleton = Skeleton::new(joints).unwrap();
+
+    let mut palette = Vec::new();
+    animation::compute_joint_palette(&skeleton, &[Transform::default()], &mut palette);
+
+    assert_close(palette[0].w_axis.truncate(), -glam::Vec3::X);
+}
+
+#[test]
+fn player_samples_rest_pose_without_a_clip() {
+    let mut world = World::new();
+    let entity = world.spawn((AnimationPlayer::new(arm()),));
+
+    animation::tick_animations(&mut world, 0.5);
+
+    let translations = translations(&world, entity);
+    assert_close(translations[0], glam::Vec3::ZERO);
+    assert_close(translations[1], glam::Vec3::Y);
+}
+
+#[test]
+fn player_interpolates_keyframes() {
+    let mut world = World::new();
+    let mut player = AnimationPlayer::new(arm());
+    player.play(slide("walk", glam::vec3(4., 0., 0.)));
+    let entity = world.spawn((player,));
+
+    animation::tick_animations(&mut world, 0.25);
+
+    let translations = translations(&world, entity);
+    assert_close(translations[0], glam::vec3(1., 0., 0.));
+    assert_close(translations[1], glam::vec3(1., 1., 0.));
+}
+
+#[test]
+fn crossfade_blends_between_clips() {
+    let mut world = World::new();
+    let mut player = AnimationPlayer::new(arm());
+    player.looping = false;
+    player.play(slide("right", glam::vec3(4., 0., 0.)));
+    let entity = world.spawn((player,));
+
+    // Finish the first clip, then fade into one holding still at the origin
+    animation::tick_animations(&mut world, 1.);
+    world
+        .get::<&mut AnimationPlayer>(entity)
+        .unwrap()
+        .crossfade_to(slide("still", glam::Vec3::ZERO), 1.);
+
+    animation::tick_animations(&mut world, 0.5);
+    assert_close(translations(&world, entity)[0], glam::vec3(2., 0., 0.));
+
+    animation::tick_animations(&mut world, 0.5);
+    assert_close(translations(&world, entity)[0], glam::Vec3::ZERO);
+
+    let player = world.get::<&AnimationPlayer>(entity).unwrap();
+    assert_eq!(player.current_clip(), Some("still"));
+    assert!(player.is_finished());
+}
+
+//====================================================================
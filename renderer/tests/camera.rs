@@ -0,0 +1,108 @@
+//====================================================================
+
+use renderer::camera::PerspectiveCamera;
+
+//====================================================================
+
+const EPSILON: f32 = 1e-4;
+
+fn camera() -> PerspectiveCamera {
+    PerspectiveCamera {
+        aspect: 16. / 9.,
+        fovy: std::f32::consts::FRAC_PI_3,
+        translation: glam::vec3(3., 10., -20.),
+        rotation: glam::Quat::from_rotation_y(0.4) * glam::Quat::from_rotation_x(0.5),
+        ..Default::default()
+    }
+}
+
+fn group() -> Vec<glam::Vec3> {
+    vec![
+        glam::vec3(-4., 0., 2.),
+        glam::vec3(5., 1., -3.),
+        glam::vec3(0., 6., 8.),
+        glam::vec3(2., -2., 0.),
+    ]
+}
+
+//====================================================================
+
+#[test]
+fn points_behind_the_camera_are_not_projected() {
+    let camera = PerspectiveCamera::default();
+
+    assert!(camera.project(glam::vec3(0., 0., 5.)).is_some());
+    assert!(camera.project(glam::vec3(0., 0., -5.)).is_none());
+}
+
+#[test]
+fn rays_pass_back_through_projected_points() {
+    let camera = camera();
+    let point = glam::vec3(1., 2., 3.);
+
+    let ndc = camera.project(point).unwrap();
+    let (origin, direction) = camera.ray(ndc);
+
+    // Unprojecting loses some precision with the far plane so far out
+    let along = (point - origin).dot(direction);
+    assert!(along > 0.);
+    assert!((origin + direction * along).distance(point) < 1e-3);
+}
+
+#[test]
+fn nothing_to_frame_without_points() {
+    assert_eq!(camera().frame_points(&[], 1.), None);
+}
+
+#[test]
+fn framed_points_are_in_view() {
+    let mut camera = camera();
+    let points = group();
+
+    camera.translation = camera.frame_points(&points, 0.).unwrap();
+
+    points.iter().for_each(|point| {
+        let ndc = camera.project(*point).unwrap();
+        assert!(
+            ndc.abs().max_element() <= 1. + EPSILON,
+            "{} off screen",
+            point
+        );
+    });
+}
+
+#[test]
+fn framing_keeps_the_rotation() {
+    let mut camera = camera();
+    let rotation = camera.rotation;
+
+    camera.translation = camera.frame_points(&group(), 0.).unwrap();
+    assert_eq!(camera.rotation, rotation);
+
+    // Centered on the group
+    let center = camera.project(glam::vec3(0.5, 2., 2.5)).unwrap();
+    assert!(center.length() < EPSILON);
+}
+
+#[test]
+fn padding_moves_the_camera_back() {
+    let camera = camera();
+    let points = group();
+
+    let center = glam::vec3(0.5, 2., 2.5);
+    let tight = camera.frame_points(&points, 0.).unwrap();
+    let padded = camera.frame_points(&points, 2.).unwrap();
+
+    assert!(padded.distance(center) > tight.distance(center));
+}
+
+#[test]
+fn single_points_are_framed_at_the_near_plane() {
+    let camera = camera();
+    let point = glam::vec3(1., 1., 1.);
+
+    let translation = camera.frame_points(&[point], 0.).unwrap();
+    assert!((translation.distance(point) - camera.z_near).abs() < EPSILON);
+}
+
+//====================================================================
@@ -0,0 +1,90 @@
+//====================================================================
+
+use renderer::capture::{CaptureCommand, FrameCapture};
+
+//====================================================================
+
+fn capture() -> FrameCapture {
+    FrameCapture {
+        frame: 12,
+        commands: vec![
+            CaptureCommand::CreateBuffer {
+                label: String::from("Texture Instance"),
+                bytes: 256,
+            },
+            CaptureCommand::WriteTexture {
+                label: String::from("Text Atlas"),
+                bytes: 1024,
+            },
+            CaptureCommand::BeginPass { label: "Scene" },
+            CaptureCommand::SetPipeline {
+                label: "Texture Pipeline",
+            },
+            CaptureCommand::SetBindGroup {
+                index: 1,
+                label: String::from("hero.png"),
+            },
+            CaptureCommand::Draw {
+                vertices: 6,
+                instances: 3,
+                indexed: true,
+            },
+            CaptureCommand::WriteBuffer {
+                label: String::from("Particles"),
+                bytes: 64,
+            },
+            CaptureCommand::DrawIndirect { max_instances: 100 },
+            CaptureCommand::Dispatch { workgroups: 4 },
+        ],
+    }
+}
+
+//====================================================================
+
+#[test]
+fn report_summarizes_the_frame() {
+    let capture = capture();
+
+    assert_eq!(capture.draw_calls(), 2);
+    assert_eq!(capture.bytes_uploaded(), 256 + 1024 + 64);
+
+    let report = capture.report();
+    assert_eq!(
+        report.lines().next(),
+        Some("Frame 12 capture - 9 commands, 2 draw calls, 1344 bytes uploaded")
+    );
+}
+
+#[test]
+fn report_lists_commands_in_order() {
+    let report = capture().report();
+    let lines = report.lines().skip(1).collect::<Vec<_>>();
+
+    assert_eq!(
+        lines,
+        vec![
+            "  Create buffer  'Texture Instance' (256 bytes)",
+            "  Write texture  'Text Atlas' (1024 bytes)",
+            "Pass 'Scene'",
+            "  Pipeline 'Texture Pipeline'",
+            "    Bind group 1 = hero.png",
+            "    Draw indexed - 6 vertices x 3 instances",
+            "  Write buffer   'Particles' (64 bytes)",
+            "    Draw indirect - up to 100 instances",
+            "    Dispatch - 4 workgroups",
+        ]
+    );
+}
+
+#[test]
+fn empty_capture_reports_only_its_header() {
+    let capture = FrameCapture::default();
+
+    assert_eq!(capture.draw_calls(), 0);
+    assert_eq!(
+        capture.report(),
+        "Frame 0 capture - 0 commands, 0 draw calls, 0 bytes uploaded\n"
+    );
+}
+
+//====================================================================
@@ -0,0 +1,94 @@
+//====================================================================
+
+use renderer::pipelines::particle_pipeline::{self, simulate_particles, ParticleRaw, ParticleRing};
+
+//====================================================================
+
+fn particle(age: f32, lifetime: f32) -> ParticleRaw {
+    ParticleRaw {
+        position: glam::Vec3::ZERO,
+        age,
+        velocity: glam::vec3(1., 0., 0.),
+        lifetime,
+        color: glam::Vec4::ONE,
+    }
+}
+
+//====================================================================
+
+#[test]
+fn rings_fill_in_order() {
+    let mut ring = ParticleRing::new(8);
+
+    assert_eq!(ring.claim(3), [(0, 3), (0, 0)]);
+    assert_eq!(ring.claim(4), [(3, 4), (0, 0)]);
+}
+
+#[test]
+fn rings_wrap_over_the_oldest() {
+    let mut ring = ParticleRing::new(8);
+    ring.claim(6);
+
+    assert_eq!(ring.claim(5), [(6, 2), (0, 3)]);
+    assert_eq!(ring.claim(1), [(3, 1), (0, 0)]);
+}
+
+#[test]
+fn rings_never_claim_past_their_capacity() {
+    let mut ring = ParticleRing::new(4);
+    ring.claim(1);
+
+    assert_eq!(ring.claim(10), [(1, 3), (0, 1)]);
+    assert_eq!(ring.claim(0), [(1, 0), (0, 0)]);
+    assert_eq!(ParticleRing::new(0).capacity(), 1);
+}
+
+#[test]
+fn cpu_simulation_applies_gravity_then_velocity() {
+    let mut particles = [particle(0., 10.)];
+
+    let alive = simulate_particles(&mut particles, glam::vec3(0., -2., 0.), 0.5);
+
+    assert_eq!(alive.len(), 1);
+    assert_eq!(particles[0].velocity, glam::vec3(1., -1., 0.));
+    assert_eq!(particles[0].position, glam::vec3(0.5, -0.5, 0.));
+    assert_eq!(particles[0].age, 0.5);
+}
+
+#[test]
+fn cpu_simulation_drops_dead_particles() {
+    let mut particles = [particle(0., 1.), particle(0.75, 1.), particle(1., 1.)];
+
+    let alive = simulate_particles(&mut particles, glam::Vec3::ZERO, 0.5);
+    assert_eq!(alive.len(), 1);
+
+    // Dead particles are left as they were
+    assert_eq!(particles[2].age, 1.);
+    assert_eq!(particles[2].position, glam::Vec3::ZERO);
+}
+
+#[test]
+fn gpu_simulation_needs_storage_in_vertex_shaders_and_indirect_draws() {
+    let limits = wgpu::Limits::default();
+    let all = wgpu::DownlevelFlags::all();
+
+    assert!(particle_pipeline::supports_gpu_simulation(&limits, all));
+    assert!(!particle_pipeline::supports_gpu_simulation(
+        &limits,
+        all - wgpu::DownlevelFlags::VERTEX_STORAGE
+    ));
+    assert!(!particle_pipeline::supports_gpu_simulation(
+        &limits,
+        all - wgpu::DownlevelFlags::INDIRECT_EXECUTION
+    ));
+}
+
+#[test]
+fn webgl_simulates_on_the_cpu() {
+    assert!(!particle_pipeline::supports_gpu_simulation(
+        &wgpu::Limits::downlevel_webgl2_defaults(),
+        wgpu::DownlevelFlags::all()
+    ));
+}
+
+//====================================================================
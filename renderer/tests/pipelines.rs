@@ -0,0 +1,375 @@
+//====================================================================
+
+use std::{collections::HashMap, sync::Arc};
+
+use common::{PhysicalSize, Transform};
+use hecs::World;
+use renderer::{
+    image,
+    pipelines::{
+        texture_pipeline::{
+            self, BatchKey, BlendMode, InstanceTexture, Material, SamplerPreset, Sprite,
+            SpriteLayer,
+        },
+        ui3d_pipeline::Ui3d,
+        Hidden, Opacity,
+    },
+    testing::HeadlessHarness,
+    texture::Texture,
+    texture_storage::LoadedTexture,
+};
+
+//====================================================================
+
+fn harness() -> HeadlessHarness {
+    HeadlessHarness::new(PhysicalSize::new(64, 64)).expect("No wgpu adapter available")
+}
+
+fn texture(harness: &HeadlessHarness, color: [u8; 3]) -> Arc<LoadedTexture> {
+    Arc::new(LoadedTexture::load_texture(
+        &harness.device,
+        &harness.shared,
+        Texture::from_color(&harness.device, &harness.queue, color, None, None),
+    ))
+}
+
+fn sprite(material: impl Into<Material>) -> (Transform, Sprite) {
+    (
+        Transform::default(),
+        Sprite::new(material, glam::vec2(8., 8.)),
+    )
+}
+
+// Instance counts of every batch, in draw order
+fn batch_counts(harness: &HeadlessHarness, world: &mut World) -> Vec<u32> {
+    let mut renderer = harness.texture_renderer();
+    harness.prep_texture_renderer(&mut renderer, world);
+
+    let mut batches = renderer.batches().collect::<Vec<_>>();
+    batches.sort_by_key(|(key, _)| *key);
+    batches.into_iter().map(|(_, count)| count).collect()
+}
+
+fn instance_at(position: glam::Vec3) -> InstanceTexture {
+    InstanceTexture {
+        size: glam::Vec2::ONE,
+        pad: [0.; 2],
+        transform: glam::Mat4::from_translation(position),
+        color: glam::Vec4::ONE,
+        palette: glam::Vec4::ZERO,
+        uv: glam::vec4(0., 0., 1., 1.),
+    }
+}
+
+// Text with a large font so only a few glyphs fill the starting atlas page
+fn big_text(text: &str) -> (Transform, Ui3d) {
+    (
+        Transform::default(),
+        Ui3d {
+            options: text.split(' ').map(String::from).collect(),
+            font_size: 120.,
+            ..Default::default()
+        },
+    )
+}
+
+// Glyphs can't be rasterized without a system font to draw them from
+fn assert_glyphs(harness: &HeadlessHarness) {
+    assert!(
+        harness.text_res.text_atlas.cached_glyph_count() > 0,
+        "No fonts available"
+    );
+}
+
+//====================================================================
+
+#[test]
+#[ignore = "needs a wgpu adapter, run with --ignored"]
+fn sprites_sharing_a_material_batch_together() {
+    let harness = harness();
+
+    let red = texture(&harness, [255, 0, 0]);
+    let blue = texture(&harness, [0, 0, 255]);
+
+    let mut world = World::new();
+    world.spawn(sprite(red.clone()));
+    world.spawn(sprite(red.clone()));
+    world.spawn(sprite(red.clone()));
+    world.spawn(sprite(blue));
+
+    let mut counts = batch_counts(&harness, &mut world);
+    counts.sort();
+    assert_eq!(counts, vec![1, 3]);
+}
+
+#[test]
+#[ignore = "needs a wgpu adapter, run with --ignored"]
+fn layers_and_blend_modes_split_batches() {
+    let harness = harness();
+
+    let red = texture(&harness, [255, 0, 0]);
+
+    let mut world = World::new();
+    world.spawn(sprite(red.clone()));
+    world.spawn(sprite(red.clone()));
+    world.spawn(sprite(
+        Material::new(red.clone()).with_blend(BlendMode::Additive),
+    ));
+
+    let (transform, raised) = sprite(red);
+    world.spawn((transform, raised, SpriteLayer(1)));
+
+    // Layer 0 alpha, layer 0 additive, then layer 1
+    assert_eq!(batch_counts(&harness, &mut world), vec![2, 1, 1]);
+}
+
+#[test]
+#[ignore = "needs a wgpu adapter, run with --ignored"]
+fn translucent_sprites_batch_after_everything_else() {
+    let harness = harness();
+
+    let red = texture(&harness, [255, 0, 0]);
+
+    let mut world = World::new();
+    let (transform, faded) = sprite(red.clone());
+    world.spawn((transform, faded, Opacity(0.5)));
+
+    (0..2).for_each(|_| {
+        let (transform, raised) = sprite(red.clone());
+        world.spawn((transform, raised, SpriteLayer(1)));
+    });
+
+    // Effects already skip depth writes, so only alpha blending counts
+    let additive = Material::new(red).with_blend(BlendMode::Additive);
+    let (transform, glow) = sprite(additive);
+    world.spawn((transform, glow, Opacity(0.5)));
+
+    // Additive, layer 1, then the faded sprite despite its lower layer
+    assert_eq!(batch_counts(&harness, &mut world), vec![1, 2, 1]);
+}
+
+#[test]
+#[ignore = "needs a wgpu adapter, run with --ignored"]
+fn translucent_sprites_draw_far_to_near_across_textures() {
+    let harness = harness();
+
+    // The far sprite's texture sorts last by id
+    let blue = texture(&harness, [0, 0, 255]);
+    let red = texture(&harness, [255, 0, 0]);
+
+    let camera = harness.camera.camera.translation;
+
+    let mut world = World::new();
+    [(&blue, 50.), (&red, 200.)]
+        .into_iter()
+        .for_each(|(texture, distance)| {
+            world.spawn((
+                Transform::from_translation(camera + glam::vec3(0., 0., distance)),
+                Sprite::new(texture.clone(), glam::vec2(8., 8.)),
+                Opacity(0.5),
+            ));
+        });
+
+    let mut renderer = harness.texture_renderer();
+    harness.prep_texture_renderer(&mut renderer, &mut world);
+
+    let mut batches = renderer.batches().collect::<Vec<_>>();
+    batches.sort_by_key(|(key, _)| *key);
+
+    let textures = batches
+        .iter()
+        .map(|(key, _)| key.texture)
+        .collect::<Vec<_>>();
+    assert_eq!(textures, [red.id(), blue.id()]);
+}
+
+#[test]
+fn translucent_instances_order_far_to_near() {
+    let camera = glam::vec3(0., 0., -10.);
+
+    let key = |texture| BatchKey {
+        translucent: true,
+        depth_order: 0,
+        layer: SpriteLayer(0),
+        stack: 0,
+        blend: BlendMode::Alpha,
+        sampler: SamplerPreset::Nearest,
+        texture,
+    };
+
+    let mut batches = HashMap::new();
+    batches.insert(
+        key(1),
+        vec![
+            instance_at(glam::vec3(0., 0., -5.)),
+            instance_at(glam::vec3(0., 0., 20.)),
+        ],
+    );
+    batches.insert(key(2), vec![instance_at(glam::vec3(3., 0., 0.))]);
+
+    let opaque = BatchKey {
+        translucent: false,
+        ..key(1)
+    };
+    batches.insert(opaque, vec![instance_at(glam::Vec3::ZERO)]);
+
+    texture_pipeline::order_translucent(&mut batches, camera);
+
+    let mut batches = batches.into_iter().collect::<Vec<_>>();
+    batches.sort_by_key(|(key, _)| *key);
+
+    let order = batches
+        .iter()
+        .flat_map(|(key, instances)| {
+            instances
+                .iter()
+                .map(|instance| (key.texture, instance.transform.w_axis.z))
+        })
+        .collect::<Vec<_>>();
+
+    // Opaque first, then the second texture drawn between the first's sprites
+    assert_eq!(order, [(1, 0.), (1, 20.), (2, 0.), (1, -5.)]);
+    assert_eq!(batches[0].0, opaque);
+}
+
+#[test]
+#[ignore = "needs a wgpu adapter, run with --ignored"]
+fn tiled_sprites_repeat_without_a_texture_array() {
+    let mut harness = harness();
+
+    // Red on the left, blue on the right
+    let mut tiles = image::RgbaImage::new(2, 1);
+    tiles.put_pixel(0, 0, image::Rgba([255, 0, 0, 255]));
+    tiles.put_pixel(1, 0, image::Rgba([0, 0, 255, 255]));
+
+    let texture = Arc::new(LoadedTexture::load_texture(
+        &harness.device,
+        &harness.shared,
+        Texture::from_image(
+            &harness.device,
+            &harness.queue,
+            &image::DynamicImage::ImageRgba8(tiles),
+            None,
+            None,
+        ),
+    ));
+
+    let mut world = World::new();
+    world.spawn((
+        Transform::from_translation(glam::vec3(0., 0., 19.)),
+        Sprite {
+            uv_scale: glam::vec2(2., 1.),
+            ..Sprite::new(texture, glam::vec2(8., 8.))
+        },
+    ));
+
+    // The harness doesn't ask for binding arrays, so each texture is bound
+    // on its own
+    let mut renderer = harness.texture_renderer();
+    harness.run_texture_renderer(&mut renderer, &mut world);
+
+    let size = harness.size();
+    let pixels = harness.read_pixels();
+    let row = &pixels[(size.height / 2 * size.width * 4) as usize..][..(size.width * 4) as usize];
+
+    // Colors across the middle row, ignoring the background
+    let mut colors = row
+        .chunks_exact(4)
+        .map(|pixel| [pixel[0], pixel[1], pixel[2]])
+        .filter(|color| *color != [0; 3])
+        .collect::<Vec<_>>();
+    colors.dedup();
+
+    // Clamping would stretch the edge texel over the second half
+    assert_eq!(colors.len(), 4, "{:?}", colors);
+    assert_eq!(colors[0], colors[2]);
+    assert_eq!(colors[1], colors[3]);
+    assert_ne!(colors[0], colors[1]);
+}
+
+#[test]
+#[ignore = "needs a wgpu adapter, run with --ignored"]
+fn hidden_sprites_are_left_out_of_batches() {
+    let harness = harness();
+
+    let red = texture(&harness, [255, 0, 0]);
+
+    let mut world = World::new();
+    world.spawn(sprite(red.clone()));
+    let (transform, hidden) = sprite(red.clone());
+    world.spawn((transform, hidden, Hidden));
+
+    assert_eq!(batch_counts(&harness, &mut world), vec![1]);
+
+    world.clear();
+    let (transform, hidden) = sprite(red);
+    world.spawn((transform, hidden, Hidden));
+
+    assert!(batch_counts(&harness, &mut world).is_empty());
+}
+
+//====================================================================
+
+#[test]
+#[ignore = "needs a wgpu adapter and a system font, run with --ignored"]
+fn atlas_keeps_glyphs_cached_between_frames() {
+    let mut harness = harness();
+
+    let mut world = World::new();
+    let mut renderer = harness.ui3d_renderer();
+    world.spawn(big_text("Attack Defend"));
+
+    harness.run_ui3d_renderer(&mut renderer, &mut world);
+    assert_glyphs(&harness);
+
+    let atlas = &harness.text_res.text_atlas;
+    let cached = atlas.cached_glyph_count();
+    assert_eq!(atlas.glyphs_in_use_count(), 0);
+
+    // Unchanged text reuses what's cached
+    harness.run_ui3d_renderer(&mut renderer, &mut world);
+    assert_eq!(harness.text_res.text_atlas.cached_glyph_count(), cached);
+}
+
+#[test]
+#[ignore = "needs a wgpu adapter and a system font, run with --ignored"]
+fn atlas_grows_to_fit_glyphs_in_use() {
+    let mut harness = harness();
+
+    let mut world = World::new();
+    let mut renderer = harness.ui3d_renderer();
+    let starting_size = harness.text_res.text_atlas.page_sizes()[0];
+
+    world.spawn(big_text("ABCDEFGHIJKLM NOPQRSTUVWXYZ"));
+
+    harness.prep_ui3d_renderer(&mut renderer, &mut world);
+    assert_glyphs(&harness);
+
+    let atlas = &harness.text_res.text_atlas;
+    assert!(atlas.page_sizes()[0] > starting_size);
+    assert_eq!(atlas.glyphs_in_use_count(), atlas.cached_glyph_count());
+}
+
+#[test]
+#[ignore = "needs a wgpu adapter and a system font, run with --ignored"]
+fn atlas_evicts_unused_glyphs_before_growing() {
+    let mut harness = harness();
+
+    let mut world = World::new();
+    let mut renderer = harness.ui3d_renderer();
+
+    let first = world.spawn(big_text("ABCDEFGHIJKLM"));
+    harness.run_ui3d_renderer(&mut renderer, &mut world);
+    assert_glyphs(&harness);
+
+    let page_size = harness.text_res.text_atlas.page_sizes()[0];
+
+    // Same number of glyphs at the same size fit once the old ones are freed
+    world.despawn(first).unwrap();
+    world.spawn(big_text("NOPQRSTUVWXYZ"));
+    harness.run_ui3d_renderer(&mut renderer, &mut world);
+
+    assert_eq!(harness.text_res.text_atlas.page_sizes()[0], page_size);
+}
+
+//====================================================================
@@ -0,0 +1,200 @@
+//====================================================================
+
+use common::{PhysicalSize, Transform};
+use hecs::World;
+use renderer::{
+    pipelines::{
+        ui3d_pipeline::{Ui3d, UiClip},
+        Hidden,
+    },
+    testing::HeadlessHarness,
+};
+
+//====================================================================
+
+const EPSILON: f32 = 1e-6;
+
+fn harness() -> HeadlessHarness {
+    HeadlessHarness::new(PhysicalSize::new(64, 64)).expect("No wgpu adapter available")
+}
+
+fn menu(options: &[&str], selected: u8, font_size: f32) -> Ui3d {
+    Ui3d {
+        options: options.iter().map(|option| option.to_string()).collect(),
+        selected,
+        font_size,
+        ..Default::default()
+    }
+}
+
+fn assert_close(actual: [f32; 2], expected: [f32; 2]) {
+    assert!(
+        (actual[0] - expected[0]).abs() < EPSILON && (actual[1] - expected[1]).abs() < EPSILON,
+        "expected {:?}, got {:?}",
+        expected,
+        actual
+    );
+}
+
+//====================================================================
+
+#[test]
+fn size_fits_longest_option() {
+    let ui = menu(&["Attack", "Defend", "Use Item"], 0, 30.);
+    assert_close(ui.size().to_array(), [240., 90.]);
+
+    let ui = menu(&["Yes"], 0, 24.);
+    assert_close(ui.size().to_array(), [72., 24.]);
+
+    let ui = menu(&[], 0, 30.);
+    assert_close(ui.size().to_array(), [0., 0.]);
+}
+
+#[test]
+fn selection_covers_selected_option() {
+    let ui = menu(&["Attack", "Defend", "Use Item"], 1, 30.);
+    assert_close(ui.selection_range().to_array(), [1. / 3., 2. / 3.]);
+
+    let ui = menu(&["Yes", "No"], 0, 30.);
+    assert_close(ui.selection_range().to_array(), [0., 0.5]);
+
+    let ui = menu(&["Only"], 0, 30.);
+    assert_close(ui.selection_range().to_array(), [0., 1.]);
+}
+
+#[test]
+fn selection_past_end_highlights_last_option() {
+    let ui = menu(&["Yes", "No"], 5, 30.);
+    assert_close(ui.selection_range().to_array(), [0.5, 1.]);
+
+    let ui = menu(&[], 3, 30.);
+    assert_close(ui.selection_range().to_array(), [0., 0.]);
+}
+
+#[test]
+fn bounds_cover_menu_background() {
+    let ui = menu(&["Attack", "Defend", "Use Item"], 0, 30.);
+    let (min, max) = ui.bounds();
+
+    assert_close(min.to_array(), [0., -81.]);
+    assert_close(max.to_array(), [240., 9.]);
+    assert_close((max - min).to_array(), ui.size().to_array());
+}
+
+#[test]
+fn screen_clip_scissor_fits_target() {
+    let clip = UiClip::Screen {
+        min: glam::vec2(0.25, 0.5),
+        max: glam::vec2(0.75, 1.),
+    };
+    assert_eq!(clip.scissor_rect([64, 64]), Some([16, 32, 32, 32]));
+
+    // Partly off screen is cut down to the target
+    let clip = UiClip::Screen {
+        min: glam::vec2(-0.5, 0.5),
+        max: glam::vec2(0.5, 2.),
+    };
+    assert_eq!(clip.scissor_rect([64, 64]), Some([0, 32, 32, 32]));
+
+    let clip = UiClip::Screen {
+        min: glam::vec2(1.5, 0.),
+        max: glam::vec2(2., 1.),
+    };
+    assert_eq!(clip.scissor_rect([64, 64]), None);
+
+    let clip = UiClip::Local {
+        min: glam::Vec2::ZERO,
+        max: glam::Vec2::ONE,
+    };
+    assert_eq!(clip.scissor_rect([64, 64]), None);
+}
+
+//====================================================================
+
+#[test]
+#[ignore = "needs a wgpu adapter, run with --ignored"]
+fn prep_matches_golden_layouts() {
+    let mut harness = harness();
+
+    let mut world = World::new();
+    let mut renderer = harness.ui3d_renderer();
+
+    let cases = [
+        (
+            menu(&["Attack", "Defend", "Use Item"], 2, 30.),
+            [240., 90.],
+            [2. / 3., 1.],
+        ),
+        (
+            menu(&["Goblin (85%)", "Orc (40%)"], 1, 30.),
+            [360., 60.],
+            [0.5, 1.],
+        ),
+        (
+            menu(&["Use Potion?", "Yes", "No"], 1, 20.),
+            [220., 60.],
+            [1. / 3., 2. / 3.],
+        ),
+        (menu(&["MISS"], 0, 48.), [192., 48.], [0., 1.]),
+    ];
+
+    let entities = cases
+        .iter()
+        .map(|(ui, _, _)| world.spawn((Transform::default(), ui.clone())))
+        .collect::<Vec<_>>();
+
+    harness.prep_ui3d_renderer(&mut renderer, &mut world);
+
+    entities
+        .iter()
+        .zip(&cases)
+        .for_each(|(entity, (_, size, selection))| {
+            assert_close(renderer.ui_size(*entity).unwrap(), *size);
+            assert_close(renderer.ui_selection_range(*entity).unwrap(), *selection);
+        });
+}
+
+#[test]
+#[ignore = "needs a wgpu adapter, run with --ignored"]
+fn prep_follows_changes() {
+    let mut harness = harness();
+
+    let mut world = World::new();
+    let mut renderer = harness.ui3d_renderer();
+
+    let entity = world.spawn((Transform::default(), menu(&["Yes", "No"], 0, 30.)));
+    harness.prep_ui3d_renderer(&mut renderer, &mut world);
+    assert_close(renderer.ui_size(entity).unwrap(), [90., 60.]);
+
+    {
+        let mut ui = world.get::<&mut Ui3d>(entity).unwrap();
+        ui.options.push(String::from("Maybe later"));
+        ui.selected = 2;
+        ui.font_size = 10.;
+    }
+
+    harness.prep_ui3d_renderer(&mut renderer, &mut world);
+    assert_close(renderer.ui_size(entity).unwrap(), [110., 30.]);
+    assert_close(renderer.ui_selection_range(entity).unwrap(), [2. / 3., 1.]);
+}
+
+#[test]
+#[ignore = "needs a wgpu adapter, run with --ignored"]
+fn hidden_keeps_last_layout() {
+    let mut harness = harness();
+
+    let mut world = World::new();
+    let mut renderer = harness.ui3d_renderer();
+
+    let entity = world.spawn((Transform::default(), menu(&["Attack", "Defend"], 1, 30.)));
+    harness.prep_ui3d_renderer(&mut renderer, &mut world);
+
+    world.insert_one(entity, Hidden).unwrap();
+    world.get::<&mut Ui3d>(entity).unwrap().font_size = 60.;
+    harness.prep_ui3d_renderer(&mut renderer, &mut world);
+
+    assert_close(renderer.ui_size(entity).unwrap(), [180., 60.]);
+    assert_close(renderer.ui_selection_range(entity).unwrap(), [0.5, 1.]);
+}
+
+//====================================================================